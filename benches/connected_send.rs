@@ -0,0 +1,48 @@
+//! 단일 상대 전송: 매 패킷 `send_to` vs `connect()`한 소켓의 `send` (`PeerUdpSocket`)
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use sfp::{DatagramSocket, PeerUdpSocket, DEFAULT_CHUNK_SIZE};
+use tokio::net::UdpSocket;
+
+/// 반복마다 보내는 데이터그램 수
+const DATAGRAMS: usize = 256;
+
+async fn burst<S: DatagramSocket>(socket: &S, target: std::net::SocketAddr, payload: &[u8]) {
+    for _ in 0..DATAGRAMS {
+        // 받는 쪽이 비우지 않아 수신 버퍼가 차면 커널이 버리지만 송신 비용은 같음
+        black_box(socket.send_to(payload, target).await.expect("send"));
+    }
+}
+
+fn bench_connected_send(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("tokio runtime");
+    let payload = vec![0xA5u8; DEFAULT_CHUNK_SIZE];
+    // 두 경우가 같은 수신 버퍼 상태를 보도록 목적지 소켓을 따로 둠
+    let (sinks, unconnected, connected) = rt.block_on(async {
+        let bind = || UdpSocket::bind("127.0.0.1:0");
+        let sinks = [bind().await.expect("bind"), bind().await.expect("bind")];
+        let connected = PeerUdpSocket::new(bind().await.expect("bind"));
+        connected
+            .connect_peer(sinks[1].local_addr().expect("addr"))
+            .await
+            .expect("connect");
+        (sinks, bind().await.expect("bind"), connected)
+    });
+    let [plain_target, connected_target] = sinks.each_ref().map(|s| s.local_addr().expect("addr"));
+
+    let mut group = c.benchmark_group("connected_send");
+    group.throughput(Throughput::Elements(DATAGRAMS as u64));
+    group.bench_function("send_to", |b| {
+        b.iter(|| rt.block_on(burst(&unconnected, plain_target, &payload)))
+    });
+    group.bench_function("connected", |b| {
+        b.iter(|| rt.block_on(burst(&connected, connected_target, &payload)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_connected_send);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! 일대다 전송: 수신자마다 재분할 vs 한 번 준비한 세그먼트 재사용
+
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sfp::{SegmentBuilder, DEFAULT_CHUNK_SIZE, DEFAULT_SEGMENT_SIZE};
+
+/// 같은 세그먼트를 받는 수신자 수
+const PEERS: u64 = 16;
+
+/// 중복 전송 비율
+const REDUNDANCY: f64 = 0.1;
+
+fn bench_prepared(c: &mut Criterion) {
+    let mut group = c.benchmark_group("one_to_many");
+    let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+    let data: Vec<u8> = (0..DEFAULT_SEGMENT_SIZE).map(|i| i as u8).collect();
+    let source = Bytes::from(data);
+
+    // 수신자마다 분할, CRC, 중복 청크 생성, 직렬화
+    group.bench_function("resplit_per_peer", |b| {
+        b.iter(|| {
+            let mut sent = 0;
+            for peer in 0..PEERS {
+                let chunks = builder.split_bytes_into_chunks(peer + 1, source.clone(), 0);
+                let redundant = builder.create_redundant_chunks(&chunks, REDUNDANCY);
+                for chunk in chunks.iter().chain(&redundant) {
+                    sent += black_box(chunk.to_bytes()).len();
+                }
+            }
+            sent
+        })
+    });
+
+    // 한 번 준비한 뒤 수신자마다 세그먼트 ID/타임스탬프만 기록
+    group.bench_function("prepared_once", |b| {
+        let mut buf = Vec::with_capacity(2048);
+        b.iter(|| {
+            let prepared = builder.prepare(source.clone(), 0, REDUNDANCY);
+            let mut sent = 0;
+            for peer in 0..PEERS {
+                for index in 0..prepared.datagram_count() {
+                    prepared.write_datagram_now(index, peer + 1, &mut buf);
+                    sent += black_box(&buf).len();
+                }
+            }
+            sent
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_prepared);
+criterion_main!(benches);
@@ -0,0 +1,134 @@
+//! 수신 워커 분배: 공유 수신 큐(Mutex) vs 세그먼트별 워커 큐
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sfp::dispatch::{Dispatched, ShardedDispatcher};
+use sfp::{Chunk, SegmentBuilder, DEFAULT_CHUNK_SIZE};
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc;
+
+/// 수신 워커 수
+const WORKERS: usize = 4;
+
+/// 반복마다 처리하는 세그먼트 수
+const SEGMENTS: u64 = 256;
+
+/// 세그먼트당 청크 수
+const CHUNKS_PER_SEGMENT: usize = 32;
+
+type SeenChunks = HashMap<u64, HashSet<u32>>;
+
+fn datagrams() -> Vec<Vec<u8>> {
+    let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+    let data = vec![7u8; DEFAULT_CHUNK_SIZE * CHUNKS_PER_SEGMENT];
+    // 세그먼트를 번갈아 보내 실제 수신처럼 여러 세그먼트가 섞여 도착
+    let segments: Vec<Vec<Chunk>> = (1..=SEGMENTS)
+        .map(|id| builder.split_into_chunks(id, &data, 0))
+        .collect();
+    (0..CHUNKS_PER_SEGMENT)
+        .flat_map(|i| segments.iter().map(move |chunks| chunks[i].to_bytes()))
+        .collect()
+}
+
+/// 모든 워커가 하나의 수신 큐와 세그먼트 맵을 잠가서 공유
+async fn shared_mutex(packets: Vec<Vec<u8>>) -> usize {
+    let (tx, rx) = mpsc::channel::<Vec<u8>>(packets.len());
+    let rx = Arc::new(tokio::sync::Mutex::new(rx));
+    let seen = Arc::new(parking_lot::Mutex::new(SeenChunks::new()));
+
+    let workers: Vec<_> = (0..WORKERS)
+        .map(|_| {
+            let rx = rx.clone();
+            let seen = seen.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(data) = rx.lock().await.recv().await else {
+                        break;
+                    };
+                    if let Some(chunk) = Chunk::from_bytes(&data) {
+                        seen.lock()
+                            .entry(chunk.header.segment_id)
+                            .or_default()
+                            .insert(chunk.header.chunk_id);
+                    }
+                }
+            })
+        })
+        .collect();
+
+    for packet in packets {
+        let _ = tx.send(packet).await;
+    }
+    drop(tx);
+    for worker in workers {
+        let _ = worker.await;
+    }
+    let total = seen.lock().values().map(HashSet::len).sum();
+    total
+}
+
+/// 수신 태스크가 세그먼트 ID로 워커 큐를 골라 넣고, 워커는 자기 맵만 사용
+async fn sharded(packets: Vec<Vec<u8>>) -> usize {
+    let (dispatcher, receivers) = ShardedDispatcher::new(WORKERS, packets.len());
+
+    let workers: Vec<_> = receivers
+        .into_iter()
+        .map(|mut rx| {
+            tokio::spawn(async move {
+                let mut seen = SeenChunks::new();
+                while let Some(data) = rx.recv().await {
+                    if let Some(chunk) = Chunk::from_bytes(&data) {
+                        seen.entry(chunk.header.segment_id)
+                            .or_default()
+                            .insert(chunk.header.chunk_id);
+                    }
+                }
+                seen.values().map(HashSet::len).sum::<usize>()
+            })
+        })
+        .collect();
+
+    for packet in packets {
+        assert_eq!(dispatcher.dispatch(packet), Dispatched::Queued);
+    }
+    drop(dispatcher);
+    let mut total = 0;
+    for worker in workers {
+        total += worker.await.unwrap_or(0);
+    }
+    total
+}
+
+fn bench_receive_dispatch(c: &mut Criterion) {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(WORKERS)
+        .build()
+        .expect("tokio runtime");
+    let packets = datagrams();
+    let expected = packets.len();
+
+    let mut group = c.benchmark_group("receive_dispatch");
+    group.throughput(Throughput::Elements(expected as u64));
+    let run = |rt: &Runtime, shard: bool| {
+        let packets = packets.clone();
+        let total = if shard {
+            rt.block_on(sharded(packets))
+        } else {
+            rt.block_on(shared_mutex(packets))
+        };
+        assert_eq!(total, expected);
+    };
+
+    group.bench_function(BenchmarkId::new("shared_mutex", WORKERS), |b| {
+        b.iter(|| run(&rt, false))
+    });
+    group.bench_function(BenchmarkId::new("sharded", WORKERS), |b| {
+        b.iter(|| run(&rt, true))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_receive_dispatch);
+criterion_main!(benches);
@@ -0,0 +1,88 @@
+//! 수신 조립 샤드 수(`parallel_workers`)별 처리량: 시뮬레이터로 청크를 밀어 넣고 모든 세그먼트가
+//! 완료될 때까지 (`cargo bench --features sim --bench receive_shards`)
+//!
+//! 샤드마다 런타임 워커 스레드를 하나씩 두므로 코어가 샤드 수보다 적으면 늘지 않음
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sfp::sim::{SimConfig, SimNetwork};
+use sfp::{
+    Chunk, Config, DatagramSocket, PathManager, Receiver, SegmentBuilder, DEFAULT_CHUNK_SIZE,
+};
+
+/// 비교할 샤드 수
+const SHARDS: [usize; 4] = [1, 2, 4, 8];
+
+/// 반복마다 조립하는 세그먼트 수
+const SEGMENTS: u64 = 256;
+
+/// 세그먼트당 청크 수
+const CHUNKS_PER_SEGMENT: usize = 32;
+
+fn datagrams() -> Vec<Vec<u8>> {
+    let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+    let data = vec![7u8; DEFAULT_CHUNK_SIZE * CHUNKS_PER_SEGMENT];
+    // 세그먼트를 번갈아 보내 모든 샤드에 고르게 도착
+    let segments: Vec<Vec<Chunk>> = (1..=SEGMENTS)
+        .map(|id| builder.split_into_chunks(id, &data, 0))
+        .collect();
+    (0..CHUNKS_PER_SEGMENT)
+        .flat_map(|i| segments.iter().map(move |chunks| chunks[i].to_bytes()))
+        .collect()
+}
+
+/// 수신자를 띄워 청크를 모두 보내고 완료된 세그먼트 수 반환
+async fn assemble(shards: usize, packets: &[Vec<u8>]) -> u64 {
+    let net = SimNetwork::new(SimConfig::default());
+    let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+    let server = net.bind(server_addr);
+    let config = Config {
+        parallel_workers: shards,
+        // 손실이 없으므로 NACK 라운드가 측정에 끼지 않도록
+        nack_timeout_ms: 10_000,
+        ..Config::default()
+    };
+    let (receiver, mut segment_rx) = Receiver::start_with_socket(
+        config.clone(),
+        Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+        server_addr,
+        Arc::new(PathManager::new(config)),
+    )
+    .await
+    .expect("receiver");
+
+    let mut buf = vec![0u8; 2048];
+    let (_, client_addr) = server.recv_from(&mut buf).await.expect("init");
+    for packet in packets {
+        server.send_to(packet, client_addr).await.expect("send");
+    }
+    let mut completed = 0;
+    while completed < SEGMENTS && segment_rx.recv().await.is_some() {
+        completed += 1;
+    }
+    receiver.stop().await;
+    completed
+}
+
+fn bench_receive_shards(c: &mut Criterion) {
+    let packets = datagrams();
+
+    let mut group = c.benchmark_group("receive_shards");
+    group.throughput(Throughput::Elements(packets.len() as u64));
+    for shards in SHARDS {
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(shards)
+            .enable_all()
+            .build()
+            .expect("tokio runtime");
+        group.bench_with_input(BenchmarkId::from_parameter(shards), &shards, |b, &shards| {
+            b.iter(|| assert_eq!(rt.block_on(assemble(shards, &packets)), SEGMENTS))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_receive_shards);
+criterion_main!(benches);
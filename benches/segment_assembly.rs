@@ -0,0 +1,131 @@
+//! 세그먼트 하나 조립: 청크별 HashMap + Vec vs `Segment` (버퍼 하나 + 수신 비트맵)
+//!
+//! 시간은 criterion으로, 힙 사용량은 할당 집계기로 재서 출력: 가득 찬 세그먼트를 붙잡는 동안의
+//! 바이트와 할당 횟수, 조립까지 마친 최대 바이트 (HashMap 방식은 조립하며 버퍼를 하나 더 만듦)
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sfp::{Chunk, Segment, SegmentBuilder, DEFAULT_CHUNK_SIZE, DEFAULT_SEGMENT_SIZE};
+
+/// 살아 있는 힙 바이트, 최대값, 할당 횟수 집계
+struct CountingAlloc;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let live = LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOC: CountingAlloc = CountingAlloc;
+
+/// 예전 바이너리/예제 방식: 청크 ID → (오프셋, 데이터 사본)
+fn receive_hashmap(chunks: &[Chunk]) -> HashMap<u32, (u32, Vec<u8>)> {
+    let mut received = HashMap::new();
+    for chunk in chunks {
+        received
+            .entry(chunk.header.chunk_id)
+            .or_insert_with(|| (chunk.header.offset, chunk.data.to_vec()));
+    }
+    received
+}
+
+fn assemble_hashmap(received: &HashMap<u32, (u32, Vec<u8>)>, segment_size: usize) -> Vec<u8> {
+    let mut data = vec![0u8; segment_size];
+    for (offset, chunk) in received.values() {
+        let offset = *offset as usize;
+        data[offset..offset + chunk.len()].copy_from_slice(chunk);
+    }
+    data
+}
+
+fn receive_segment(chunks: &[Chunk], segment_size: usize) -> Segment {
+    let mut segment = Segment::new_for_receive(1, segment_size, chunks.len() as u32);
+    for chunk in chunks {
+        segment.insert_chunk(chunk);
+    }
+    segment
+}
+
+/// 힙 사용량 (시작 시점 대비)
+struct HeapUsage {
+    /// 받은 청크를 붙잡고 있는 바이트
+    held: usize,
+    /// 받는 동안의 할당 횟수
+    allocations: usize,
+    /// 조립까지의 최대 바이트
+    peak: usize,
+}
+
+fn measure<T, U>(receive: impl FnOnce() -> T, assemble: impl FnOnce(T) -> U) -> HeapUsage {
+    let before = LIVE_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(before, Ordering::Relaxed);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+
+    let received = receive();
+    let held = LIVE_BYTES.load(Ordering::Relaxed) - before;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    drop(black_box(assemble(received)));
+    HeapUsage {
+        held,
+        allocations,
+        peak: PEAK_BYTES.load(Ordering::Relaxed) - before,
+    }
+}
+
+fn bench_segment_assembly(c: &mut Criterion) {
+    let data: Bytes = (0..DEFAULT_SEGMENT_SIZE).map(|i| i as u8).collect::<Vec<u8>>().into();
+    let chunks = SegmentBuilder::new(DEFAULT_CHUNK_SIZE).split_into_chunks(1, &data, 0);
+
+    let usages = [
+        (
+            "hashmap",
+            measure(
+                || receive_hashmap(&chunks),
+                |received| assemble_hashmap(&received, data.len()),
+            ),
+        ),
+        (
+            "segment",
+            measure(|| receive_segment(&chunks, data.len()), Segment::into_data),
+        ),
+    ];
+    println!("segment_assembly/memory: {} 청크, {} bytes 세그먼트", chunks.len(), data.len());
+    for (name, usage) in usages {
+        println!(
+            "  {:<8} 보유 {} bytes ({} 할당), 조립 최대 {} bytes",
+            name, usage.held, usage.allocations, usage.peak
+        );
+    }
+
+    let mut group = c.benchmark_group("segment_assembly");
+    group.bench_function(BenchmarkId::new("hashmap", chunks.len()), |b| {
+        b.iter(|| {
+            let received = receive_hashmap(black_box(&chunks));
+            black_box(assemble_hashmap(&received, data.len()))
+        })
+    });
+    group.bench_function(BenchmarkId::new("segment", chunks.len()), |b| {
+        b.iter(|| black_box(receive_segment(black_box(&chunks), data.len()).into_data()))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_segment_assembly);
+criterion_main!(benches);
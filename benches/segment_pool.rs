@@ -0,0 +1,39 @@
+//! 세그먼트 수신 버퍼: 풀 재사용 vs 매번 새 할당
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sfp::{Segment, SegmentBufferPool, DEFAULT_SEGMENT_SIZE};
+
+const SEGMENTS: u64 = 256;
+
+fn bench_segment_alloc(c: &mut Criterion) {
+    let mut group = c.benchmark_group("segment_alloc");
+    let total_chunks = 55;
+
+    group.bench_function(BenchmarkId::new("fresh", SEGMENTS), |b| {
+        b.iter(|| {
+            for id in 0..SEGMENTS {
+                let segment = Segment::new_for_receive(id, DEFAULT_SEGMENT_SIZE, total_chunks);
+                black_box(segment.into_data());
+            }
+        })
+    });
+
+    let pool = SegmentBufferPool::new(DEFAULT_SEGMENT_SIZE, 32);
+    pool.prefill(32);
+    group.bench_function(BenchmarkId::new("pooled", SEGMENTS), |b| {
+        b.iter(|| {
+            for id in 0..SEGMENTS {
+                let segment =
+                    Segment::new_for_receive_pooled(id, DEFAULT_SEGMENT_SIZE, total_chunks, &pool);
+                let data = segment.into_data();
+                pool.track_delivered(&data);
+                black_box(data);
+            }
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_segment_alloc);
+criterion_main!(benches);
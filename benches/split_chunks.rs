@@ -0,0 +1,35 @@
+//! 세그먼트 청크 분할: 청크별 복사 vs `Bytes` 슬라이스
+
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use sfp::{SegmentBuilder, DEFAULT_CHUNK_SIZE, DEFAULT_SEGMENT_SIZE};
+
+fn bench_split(c: &mut Criterion) {
+    let mut group = c.benchmark_group("split_into_chunks");
+    let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+    let data: Vec<u8> = (0..DEFAULT_SEGMENT_SIZE).map(|i| i as u8).collect();
+    let source = Bytes::from(data.clone());
+
+    group.bench_function("per_chunk_copy", |b| {
+        b.iter(|| {
+            let chunks: Vec<_> = data
+                .chunks(DEFAULT_CHUNK_SIZE)
+                .map(Bytes::copy_from_slice)
+                .collect();
+            black_box(chunks)
+        })
+    });
+
+    group.bench_function("copy_once", |b| {
+        b.iter(|| black_box(builder.split_into_chunks(1, &data, 0)))
+    });
+
+    group.bench_function("sliced", |b| {
+        b.iter(|| black_box(builder.split_bytes_into_chunks(1, source.clone(), 0)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_split);
+criterion_main!(benches);
@@ -11,21 +11,36 @@
 //!   --encrypt, -e     암호화 활성화 (X25519 + ChaCha20-Poly1305)
 //!   --workers <N>     [서버 전용] 병렬 워커 수 (기본: CPU 코어 수)
 
+// 인자 파싱/수신 루프는 원래 형태를 유지 (clippy 제안으로 바꾸지 않음)
+#![allow(clippy::collapsible_match, clippy::while_let_loop)]
+
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use tokio::net::UdpSocket;
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tracing::{info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
 use sfp::bbr::BbrLite;
-use sfp::chunk::SegmentBuilder;
-use sfp::crypto::{CryptoSession, EphemeralKeyPair, KeyExchangeMessage};
-use sfp::message::{FlowControlMessage, InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage};
-use sfp::Config;
+use sfp::chunk::{Segment, SegmentBuilder};
+use sfp::crypto::{CryptoSession, KeyExchange, KeyExchangeMessage};
+use sfp::dispatch::{Dispatched, ShardedDispatcher};
+use sfp::flow::FlowControlScheduler;
+use sfp::message::{
+    FlowControlMessage, InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage, ProbeMessage,
+    ProbeReportMessage,
+};
+use sfp::probe::{self, ProbeMeter};
+use sfp::recorder::NdjsonRecorder;
+use sfp::slowstart::SlowStart;
+use sfp::{Config, TransferReport};
+
+/// 조립 중인 세그먼트 (segment_id -> 세그먼트 버퍼 + 수신 비트맵, 완료하면 제거)
+type SegmentChunkMap = HashMap<u64, Segment>;
 
 /// 테스트용 텍스트 데이터 생성
 fn generate_test_text(size_mb: usize) -> Vec<u8> {
@@ -95,6 +110,7 @@ async fn run_server(
     config: Config,
     encrypt: bool,
     _num_workers: usize,
+    bbr_log: Option<String>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let socket = Arc::new(UdpSocket::bind(addr).await?);
     info!("📡 서버 시작: {}", addr);
@@ -179,21 +195,28 @@ async fn run_server(
                     // 암호화 설정 (키 교환)
                     let crypto = if encrypt {
                         info!("🔐 키 교환 시작...");
-                        
-                        let server_keypair = EphemeralKeyPair::generate();
-                        let server_public = server_keypair.public_key_bytes();
-                        let key_msg = KeyExchangeMessage { public_key: server_public };
-                        
-                        // 클라이언트 공개키 수신 (수신 큐에서)
-                        let client_key_msg = loop {
-                            let _ = priority_tx.send((key_msg.to_bytes(), addr)).await;
-                            
+
+                        // 클라이언트 공개키 수신 (기한/재전송 횟수 제한)
+                        let mut exchange = KeyExchange::initiator(&config, Instant::now());
+                        let session = loop {
+                            match exchange.poll_transmit(Instant::now()) {
+                                Ok(Some(key_msg)) => {
+                                    let _ = priority_tx.send((key_msg, addr)).await;
+                                }
+                                Ok(None) => {}
+                                Err(e) => break Err(e),
+                            }
+
+                            let wait = exchange.poll_timeout(Instant::now());
                             let mut rx = recv_rx.lock().await;
-                            match tokio::time::timeout(Duration::from_millis(500), rx.recv()).await {
-                                Ok(Some((data, _))) => {
+                            match tokio::time::timeout(wait, rx.recv()).await {
+                                Ok(Some((data, from))) => {
                                     drop(rx);
-                                    if let Some(msg) = KeyExchangeMessage::from_bytes(&data) {
-                                        break msg;
+                                    if from != addr {
+                                        continue;
+                                    }
+                                    if let Some(session) = exchange.on_datagram(&data) {
+                                        break Ok(session);
                                     }
                                 }
                                 Ok(None) => return Err("수신 채널 종료".into()),
@@ -203,11 +226,18 @@ async fn run_server(
                                 }
                             }
                         };
-                        
-                        let session = CryptoSession::establish(server_keypair, client_key_msg.public_key);
-                        info!("🔐 키 교환 완료!");
-                        
-                        Some(Arc::new(Mutex::new(session)))
+
+                        match session {
+                            Ok(session) => {
+                                info!("🔐 키 교환 완료!");
+                                Some(Arc::new(Mutex::new(session)))
+                            }
+                            Err(e) => {
+                                // 세션 해제 후 다음 클라이언트 대기
+                                warn!("🔐 {} ({}), 연결 대기로 복귀", e, addr);
+                                continue;
+                            }
+                        }
                     } else {
                         None
                     };
@@ -221,17 +251,24 @@ async fn run_server(
     // InitAck 전송 (클라이언트 타임스탬프 에코 - RTT 측정용)
     let ack = InitAckMessage::with_client_timestamp(
         data.len() as u64,
-        config.chunk_size as u16,
+        config.effective_chunk_size() as u16,
         config.segment_size as u32,
         config.base_redundancy_ratio as f32,
         client_timestamp,
     );
     let _ = priority_tx.send((ack.to_bytes(), client_addr)).await;
 
+    // 대역폭 측정 버스트 (클라이언트가 도착 간격으로 링크 용량을 재서 보고)
+    if config.bandwidth_probe {
+        for probe_msg in probe::probe_train(&config) {
+            let _ = priority_tx.send((probe_msg, client_addr)).await;
+        }
+    }
+
     // 세그먼트 준비 (병렬 처리)
-    let segment_builder = Arc::new(SegmentBuilder::new(config.chunk_size));
+    let segment_builder = Arc::new(SegmentBuilder::new(config.effective_chunk_size().max(1)));
     let data = Arc::new(data);
-    let total_segments = (data.len() + config.segment_size - 1) / config.segment_size;
+    let total_segments = data.len().div_ceil(config.segment_size);
     
     info!("🚀 전송 시작: {} 세그먼트", total_segments);
 
@@ -246,35 +283,79 @@ async fn run_server(
     let bbr = Arc::new(tokio::sync::Mutex::new(BbrLite::new(initial_rtt, initial_rate)));
     let segments_sent = Arc::new(std::sync::atomic::AtomicU64::new(0));
     let sending_active = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+    // 첫 RTT 버스트 방지: 초기 윈도우에서 시작해 FlowControl 전달 보고로 확대
+    let slow_start = {
+        let mut ss = SlowStart::new(&config);
+        ss.on_rtt_sample(Duration::from_secs_f64(initial_rtt));
+        Arc::new(tokio::sync::Mutex::new(ss))
+    };
+    let chunks_per_segment = config.chunks_per_segment() as u32;
+    let probe_len = probe::probe_datagram_len(&config);
     
     // FlowControl 피드백 태스크 (BBR 업데이트)
     let fc_bbr = bbr.clone();
+    let fc_slow_start = slow_start.clone();
     let fc_sending = sending_active.clone();
     let fc_recv_rx = recv_rx.clone();
+    let mut fc_recorder = match bbr_log {
+        Some(path) => Some(NdjsonRecorder::create(&path)?),
+        None => None,
+    };
     
     let _fc_task = tokio::spawn(async move {
         let mut last_log = Instant::now();
+        let mut last_completed_count = 0u64;
         
         while fc_sending.load(std::sync::atomic::Ordering::Relaxed) {
             let mut rx = fc_recv_rx.lock().await;
             match tokio::time::timeout(Duration::from_millis(100), rx.recv()).await {
                 Ok(Some((data, _addr))) => {
                     drop(rx);
+
+                    // 프로브 측정 결과 → 초기 윈도우/페이싱을 실제 용량에서 시작
+                    if let Some(report) = ProbeReportMessage::from_bytes(&data) {
+                        if report.rate > 0 {
+                            fc_slow_start.lock().await.on_capacity_estimate(report.rate as f64, probe_len);
+                            fc_bbr.lock().await.pacing_rate = report.rate as f64;
+                            info!("📶 프로브 측정: {:.0}MB/s ({}개 수신)",
+                                report.rate as f64 / 1024.0 / 1024.0, report.received);
+                        }
+                        continue;
+                    }
                     
                     // FlowControl 메시지 → BBR 업데이트
                     if let Some(fc) = FlowControlMessage::from_bytes(&data) {
+                        {
+                            let mut ss = fc_slow_start.lock().await;
+                            if ss.is_active() {
+                                let completed =
+                                    fc.completed_segment_count.saturating_sub(last_completed_count);
+                                ss.on_delivered((completed as u32).saturating_mul(chunks_per_segment));
+                                if fc.loss_rate >= sfp::flow::LOSS_DELTA_THRESHOLD {
+                                    ss.on_loss();
+                                }
+                            }
+                            last_completed_count =
+                                last_completed_count.max(fc.completed_segment_count);
+                        }
+
                         let mut b = fc_bbr.lock().await;
-                        // RTT 업데이트 (FlowControl에서 수신 속도를 RTT 추정에 활용)
-                        if fc.processing_rate > 0.0 {
-                            // 수신률 기반 RTT 추정
-                            let estimated_rtt = 0.001; // 기본 1ms
-                            b.on_rtt_update(estimated_rtt);
+                        // 수신측 대역폭 추정은 아직 전달 측정이 없을 때만 시작 속도로 씀
+                        if fc.estimated_bandwidth_bytes_per_sec > 0 && b.delivery_rate == 0.0 {
+                            b.pacing_rate = fc.estimated_bandwidth_bytes_per_sec as f64;
                         }
                         b.update_rate();
+
+                        // 혼잡제어 판단 근거 기록 (NDJSON)
+                        if let Some(recorder) = fc_recorder.as_mut() {
+                            let _ = recorder.record("bbr", &b.snapshot());
+                        }
                         
                         if last_log.elapsed() > Duration::from_millis(500) {
-                            info!("📶 BBR rate:{:.0}MB/s min_rtt:{:.2}ms",
-                                b.pacing_rate / 1024.0 / 1024.0, b.min_rtt * 1000.0);
+                            info!("📶 BBR rate:{:.0}MB/s min_rtt:{:.2}ms 수신측 처리:{:.0}MB/s",
+                                b.pacing_rate / 1024.0 / 1024.0, b.min_rtt * 1000.0,
+                                fc.processing_rate_bytes_per_sec as f64 / 1024.0 / 1024.0);
                             last_log = Instant::now();
                         }
                     }
@@ -282,6 +363,10 @@ async fn run_server(
                 _ => { drop(rx); }
             }
         }
+
+        if let Some(recorder) = fc_recorder.as_mut() {
+            let _ = recorder.flush();
+        }
     });
     
     // 데이터 전송 (pacing 적용)
@@ -292,6 +377,8 @@ async fn run_server(
 
     let segment_size = config.segment_size;
     let redundancy_ratio = config.base_redundancy_ratio;
+    let mut slow_start_active = true;
+    let mut next_send = Instant::now();
     
     for segment_id in 1..=total_segments as u64 {
         let offset = (segment_id as usize - 1) * segment_size;
@@ -305,7 +392,7 @@ async fn run_server(
             segment_data.to_vec()
         };
 
-        let chunks = segment_builder.split_into_chunks(segment_id, &processed_data, 0);
+        let chunks = segment_builder.split_bytes_into_chunks(segment_id, Bytes::from(processed_data), 0);
         let redundant_chunks = segment_builder.create_redundant_chunks(&chunks, redundancy_ratio);
 
         {
@@ -326,20 +413,47 @@ async fn run_server(
             }
         }
         
-        for chunk in &chunks {
-            let bytes = chunk.to_bytes();
-            segment_bytes += bytes.len();
-            let _ = tx.send((bytes, client_addr)).await;
-            total_chunks += 1;
-        }
-        for chunk in &redundant_chunks {
+        for (i, chunk) in chunks.iter().chain(redundant_chunks.iter()).enumerate() {
+            // 슬로 스타트 중에는 윈도우를 RTT에 나눠 페이싱 (종료 후에는 검사 생략)
+            if slow_start_active {
+                loop {
+                    let mut ss = slow_start.lock().await;
+                    let wait = ss.next_send(Instant::now());
+                    if !ss.is_active() {
+                        slow_start_active = false;
+                        info!("🐢 슬로 스타트 종료: 윈도우 {} 청크 ({:.0} MB/s)",
+                            ss.window(), ss.rate(packet_size) / 1024.0 / 1024.0);
+                    }
+                    drop(ss);
+                    if wait.is_zero() {
+                        break;
+                    }
+                    tokio::time::sleep(wait).await;
+                }
+            }
+
             let bytes = chunk.to_bytes();
             segment_bytes += bytes.len();
             let _ = tx.send((bytes, client_addr)).await;
-            total_redundant += 1;
+            if i < chunks.len() {
+                total_chunks += 1;
+            } else {
+                total_redundant += 1;
+            }
         }
         
         segments_sent.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        // 슬로 스타트 이후에는 BBR 속도로 페이싱 (RTT가 늘면 손실 전에 감속한 속도가 반영됨)
+        // 늦게 깨어난 만큼은 5ms까지만 몰아서 보냄
+        if !slow_start_active {
+            let now = Instant::now();
+            let delay = bbr.lock().await.pacing_delay(segment_bytes);
+            next_send = next_send.max(now.checked_sub(Duration::from_millis(5)).unwrap_or(now)) + delay;
+            if next_send > now + Duration::from_millis(1) {
+                tokio::time::sleep(next_send - now).await;
+            }
+        }
         
         // BBR 통계 업데이트 (대기 없이)
         if segment_id % 100 == 0 {
@@ -486,7 +600,7 @@ async fn run_server(
                             chunk_count += 1;
                             
                             // BBR pacing 적용
-                            if chunk_count % 10 == 0 {
+                            if chunk_count.is_multiple_of(10) {
                                 let delay = {
                                     let mut guard = b.lock().await;
                                     guard.on_packet_sent(packet_size * 10);
@@ -558,6 +672,7 @@ async fn run_server(
 async fn run_client(
     server_addr: SocketAddr,
     encrypt: bool,
+    config: &Config,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     info!("📡 클라이언트 시작");
     info!("🎯 서버: {}", server_addr);
@@ -579,18 +694,24 @@ async fn run_client(
     });
 
     // ═══════════════════════════════════════════════════════════════
-    // 단일 수신 큐 + 수신 태스크 (모든 수신은 이 큐를 통해)
+    // 수신 태스크: 청크는 세그먼트 담당 워커 큐로, 컨트롤 메시지는 수신 큐로
     // ═══════════════════════════════════════════════════════════════
-    let (recv_tx, recv_rx) = mpsc::channel::<Vec<u8>>(100_000);
+    let num_workers = 4;
+    let (recv_tx, recv_rx) = mpsc::channel::<Vec<u8>>(1000);
     let recv_rx = Arc::new(tokio::sync::Mutex::new(recv_rx));
-    
+    let (dispatcher, worker_rxs) = ShardedDispatcher::new(num_workers, 100_000 / num_workers);
+    let dispatcher = Arc::new(dispatcher);
+
     let recv_socket = socket.clone();
+    let recv_dispatcher = dispatcher.clone();
     let _recv_task = tokio::spawn(async move {
         let mut buf = vec![0u8; 2048];
         loop {
             match recv_socket.recv_from(&mut buf).await {
                 Ok((len, _)) => {
-                    let _ = recv_tx.try_send(buf[..len].to_vec());
+                    if let Dispatched::NotChunk(data) = recv_dispatcher.dispatch(buf[..len].to_vec()) {
+                        let _ = recv_tx.try_send(data);
+                    }
                 }
                 Err(_) => break,
             }
@@ -628,40 +749,41 @@ async fn run_client(
                     break (ack, None, rtt);
                 }
                 
-                if encrypt {
-                    if let Some(server_key_msg) = KeyExchangeMessage::from_bytes(&data) {
-                        info!("🔑 서버 공개키 수신 완료");
-                        
-                        let client_keypair = EphemeralKeyPair::generate();
-                        let client_public = client_keypair.public_key_bytes();
-                        let key_msg = KeyExchangeMessage { public_key: client_public };
-                        let _ = send_tx.send(key_msg.to_bytes()).await;
-                        info!("🔑 클라이언트 공개키 전송 완료");
-                        
-                        let session = CryptoSession::establish(client_keypair, server_key_msg.public_key);
-                        info!("🔐 키 교환 완료!");
-                        let crypto = Some(Arc::new(Mutex::new(session)));
-                        
-                        // InitAck 대기
-                        let ack = loop {
-                            let mut rx = recv_rx.lock().await;
-                            match tokio::time::timeout(retry_interval, rx.recv()).await {
-                                Ok(Some(data)) => {
-                                    drop(rx);
-                                    if let Some(ack) = sfp::message::InitAckMessage::from_bytes(&data) {
-                                        break ack;
-                                    }
-                                }
-                                Ok(None) => return Err("수신 채널 종료".into()),
-                                Err(_) => {
-                                    drop(rx);
-                                    let _ = send_tx.send(init_msg.to_bytes()).await;
+                if encrypt && KeyExchangeMessage::from_bytes(&data).is_some() {
+                    info!("🔑 서버 공개키 수신 완료");
+
+                    let mut exchange = KeyExchange::responder(config, Instant::now());
+                    let session = exchange.on_datagram(&data).ok_or("키 교환 실패")?;
+                    info!("🔐 키 교환 완료!");
+                    let crypto = Some(Arc::new(Mutex::new(session)));
+
+                    // 공개키 응답 + InitAck 대기 (서버 재전송 시 재응답, 기한 초과 시 중단)
+                    let ack = loop {
+                        if let Some(key_msg) = exchange.poll_transmit(Instant::now())? {
+                            let _ = send_tx.send(key_msg).await;
+                            info!("🔑 클라이언트 공개키 전송");
+                        }
+
+                        let wait = exchange.poll_timeout(Instant::now()).min(retry_interval);
+                        let mut rx = recv_rx.lock().await;
+                        match tokio::time::timeout(wait, rx.recv()).await {
+                            Ok(Some(data)) => {
+                                drop(rx);
+                                if let Some(ack) = sfp::message::InitAckMessage::from_bytes(&data) {
+                                    exchange.confirm();
+                                    break ack;
                                 }
+                                exchange.on_datagram(&data);
                             }
-                        };
-                        let rtt = init_send_time.elapsed().as_micros() as u64;
-                        break (ack, crypto, rtt);
-                    }
+                            Ok(None) => return Err("수신 채널 종료".into()),
+                            Err(_) => {
+                                drop(rx);
+                                let _ = send_tx.send(init_msg.to_bytes()).await;
+                            }
+                        }
+                    };
+                    let rtt = init_send_time.elapsed().as_micros() as u64;
+                    break (ack, crypto, rtt);
                 }
             }
             Ok(None) => return Err("수신 채널 종료".into()),
@@ -678,25 +800,27 @@ async fn run_client(
     
     info!("✅ InitAck 수신 완료 (시도: {}회)", retry_count + 1);
     
-    // RTT 기반 초기 대역폭 추정
-    // InitAck 메시지 크기 ~200 bytes, RTT로 나누어 대역폭 추정
-    // 로컬 환경에서 RTT가 매우 짧으면 높은 대역폭 추정
-    let estimated_bandwidth_mbps = if rtt_us > 0 {
-        // InitAck ~200 bytes * 2 (왕복) / RTT = bytes/sec
-        // 실제 대역폭은 이보다 훨씬 높으므로 곱하기 1000
-        let bw = (400.0 * 1_000_000.0 / rtt_us as f64) * 1000.0;
-        (bw / 1024.0 / 1024.0).min(500.0).max(50.0) // 50~500 MB/s 범위
+    // 초기 대역폭: InitAck 뒤 프로브 버스트의 도착 간격으로 측정 (측정 못 하면 기본값)
+    let probed_rate = if config.bandwidth_probe {
+        measure_probe_rate(&recv_rx, &send_tx).await
     } else {
-        100.0 // 기본값 100 MB/s
+        None
     };
-    info!("📊 RTT: {}μs → 추정 대역폭: {:.0} MB/s", rtt_us, estimated_bandwidth_mbps);
+    match probed_rate {
+        Some(rate) => info!("📊 RTT: {}μs → 초기 대역폭: {:.0} MB/s (프로브 측정)",
+            rtt_us, rate as f64 / 1024.0 / 1024.0),
+        None => info!("📊 RTT: {}μs → 초기 대역폭: 측정 안 됨", rtt_us),
+    }
     
-    // 초기 FlowControl 전송 (추정 대역폭을 processing_rate로 전달)
-    // buffer_available=1000, last_completed=0, in_progress=0, loss=0, rate=추정대역폭
-    let initial_fc = FlowControlMessage::new(1000, 0, 0, 0.0, estimated_bandwidth_mbps as f32);
+    // 초기 FlowControl 전송 (아직 처리한 것이 없으므로 처리 속도 0, 추정 대역폭만 전달).
+    // 측정하지 못했으면 0("모름")으로 보내 서버가 지어낸 값으로 페이싱하지 않게 함
+    let estimated_bandwidth = probed_rate.unwrap_or(0);
+    let initial_fc = FlowControlMessage::new(1000, 0, 0, 0.0, 0)
+        .with_estimated_bandwidth(estimated_bandwidth);
     let _ = send_tx.send(initial_fc.to_bytes()).await;
     
-    // 서버에서 받은 설정 정보
+    // 서버에서 받은 설정 정보 (세그먼트 수가 파일 크기와 맞는지 먼저 확인)
+    init_ack.validate()?;
     let total_file_size = init_ack.total_file_size as usize;
     let expected_segments = init_ack.total_segments as usize;
     let segment_size = init_ack.segment_size as usize;
@@ -716,14 +840,13 @@ async fn run_client(
     
     // ═══════════════════════════════════════════════════════════════
     // 병렬 파이프라인 구조:
-    // [수신 태스크(시작 시 생성됨)] → recv_rx → [처리 워커 풀] → assembled_channel → [조립 태스크]
+    // [수신 태스크(시작 시 생성됨)] → 워커별 큐 → [처리 워커 풀] → assembled_channel → [조립 태스크]
     // ═══════════════════════════════════════════════════════════════
     
-    // 공유 상태 (락 기반)
-    let segment_chunks: Arc<tokio::sync::RwLock<HashMap<u64, HashMap<u32, Vec<u8>>>>> = 
-        Arc::new(tokio::sync::RwLock::new(HashMap::new()));
-    let segment_total_chunks: Arc<tokio::sync::RwLock<HashMap<u64, u32>>> = 
-        Arc::new(tokio::sync::RwLock::new(HashMap::new()));
+    // 워커별 상태 (세그먼트는 한 워커만 다루므로 워커 간 잠금 경합 없음, 모니터링 루프만 읽음)
+    let segment_chunks: Vec<Arc<tokio::sync::RwLock<SegmentChunkMap>>> = (0..num_workers)
+        .map(|_| Arc::new(tokio::sync::RwLock::new(HashMap::new())))
+        .collect();
     let assembled_segments: Arc<tokio::sync::RwLock<std::collections::HashSet<u64>>> = 
         Arc::new(tokio::sync::RwLock::new(std::collections::HashSet::new()));
     
@@ -732,26 +855,23 @@ async fn run_client(
     let last_chunk_time = Arc::new(tokio::sync::RwLock::new(Instant::now()));
     
     // 채널들 (수신 큐는 이미 생성됨, 조립용 채널만 생성)
-    let (assembled_tx, mut assembled_rx) = mpsc::channel::<(u64, Vec<u8>)>(1000);
+    let (assembled_tx, mut assembled_rx) = mpsc::channel::<(u64, Bytes)>(1000);
     
     // 최종 결과 저장소
-    let decrypted_segments: Arc<Mutex<HashMap<u64, Vec<u8>>>> = Arc::new(Mutex::new(HashMap::new()));
+    let decrypted_segments: Arc<Mutex<HashMap<u64, Bytes>>> = Arc::new(Mutex::new(HashMap::new()));
     
     // 종료 플래그
     let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
     
     // ─────────────────────────────────────────────────────────────────
     // 처리 워커 풀 (파싱 + 중복검사 + 저장 + 세그먼트 완료 체크)
-    // 수신 태스크는 이미 시작됨, 여기서는 recv_rx에서 읽기만
+    // 수신 태스크는 이미 시작됨, 워커는 자기 큐에서만 읽음
     // ─────────────────────────────────────────────────────────────────
-    let num_workers = 4;
     let mut worker_handles = Vec::new();
     
-    for _worker_id in 0..num_workers {
-        let rx = recv_rx.clone();
+    for (worker_id, mut rx) in worker_rxs.into_iter().enumerate() {
         let last_chunk = last_chunk_time.clone();
-        let chunks = segment_chunks.clone();
-        let totals = segment_total_chunks.clone();
+        let chunks = segment_chunks[worker_id].clone();
         let assembled = assembled_segments.clone();
         let assembled_tx = assembled_tx.clone();
         let chunks_count = total_chunks_received.clone();
@@ -759,17 +879,14 @@ async fn run_client(
         
         let handle = tokio::spawn(async move {
             loop {
-                let data = {
-                    let mut rx_guard = rx.lock().await;
-                    match tokio::time::timeout(Duration::from_millis(50), rx_guard.recv()).await {
-                        Ok(Some(data)) => data,
-                        Ok(None) => break,  // 채널 닫힘
-                        Err(_) => {
-                            if !worker_running.load(std::sync::atomic::Ordering::Relaxed) {
-                                break;
-                            }
-                            continue;
+                let data = match tokio::time::timeout(Duration::from_millis(50), rx.recv()).await {
+                    Ok(Some(data)) => data,
+                    Ok(None) => break,  // 채널 닫힘
+                    Err(_) => {
+                        if !worker_running.load(std::sync::atomic::Ordering::Relaxed) {
+                            break;
                         }
+                        continue;
                     }
                 };
                 
@@ -779,37 +896,34 @@ async fn run_client(
                 // 청크 파싱
                 if let Some(chunk) = sfp::chunk::Chunk::from_bytes(&data) {
                     let segment_id = chunk.header.segment_id;
-                    let chunk_id = chunk.header.chunk_id;
                     let total_chunks = chunk.header.total_chunks;
+                    let segment_size = chunk.header.segment_size as usize;
                     
-                    // 총 청크 수 저장
-                    totals.write().await.insert(segment_id, total_chunks);
+                    // 조립을 마친 세그먼트의 늦은 청크와 크기보다 청크가 많다는 헤더는 버림
+                    if total_chunks as usize > segment_size.max(1)
+                        || assembled.read().await.contains(&segment_id)
+                    {
+                        continue;
+                    }
                     
-                    // 청크 저장 (중복 검사 포함)
+                    // 청크 저장 (중복 검사 포함, 세그먼트 버퍼의 오프셋에 바로 복사)
                     let mut chunks_guard = chunks.write().await;
-                    let segment = chunks_guard.entry(segment_id).or_insert_with(HashMap::new);
-                    if !segment.contains_key(&chunk_id) {
-                        segment.insert(chunk_id, chunk.data.to_vec());
+                    let segment = chunks_guard.entry(segment_id).or_insert_with(|| {
+                        Segment::new_for_receive(segment_id, segment_size, total_chunks)
+                    });
+                    if segment.insert_chunk(&chunk) {
                         chunks_count.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                     }
                     
-                    // 세그먼트 완료 체크
-                    let is_complete = segment.len() >= total_chunks as usize;
-                    let already_assembled = assembled.read().await.contains(&segment_id);
-                    
-                    if is_complete && !already_assembled {
-                        // 청크 순서대로 조립
-                        let mut segment_data = Vec::with_capacity(total_chunks as usize * 1200);
-                        for i in 0..total_chunks {
-                            if let Some(chunk_data) = segment.get(&i) {
-                                segment_data.extend_from_slice(chunk_data);
-                            }
-                        }
-                        
+                    // 세그먼트 완료 체크 (완료한 버퍼는 복사 없이 그대로 넘김)
+                    if segment.is_complete() {
+                        let segment = chunks_guard.remove(&segment_id);
                         drop(chunks_guard);  // 락 해제
                         
-                        assembled.write().await.insert(segment_id);
-                        let _ = assembled_tx.try_send((segment_id, segment_data));
+                        if let Some(segment) = segment {
+                            assembled.write().await.insert(segment_id);
+                            let _ = assembled_tx.try_send((segment_id, segment.into_data()));
+                        }
                     }
                 }
             }
@@ -828,7 +942,7 @@ async fn run_client(
             let final_data = if encrypt {
                 if let Some(ref session) = crypto {
                     let session = session.lock().await;
-                    session.decrypt(&segment_data).unwrap_or(segment_data)
+                    session.decrypt(&segment_data).map(Bytes::from).unwrap_or(segment_data)
                 } else {
                     segment_data
                 }
@@ -844,7 +958,10 @@ async fn run_client(
     // ─────────────────────────────────────────────────────────────────
     let mut nack_count = 0u64;
     let mut last_progress_time = Instant::now();
-    let mut flow_control_time = Instant::now();
+    let mut flow_control = FlowControlScheduler::new(config);
+    // 최근 처리 속도 측정 구간 (시작 시각, 그때까지 조립한 바이트)
+    let mut rate_window = (Instant::now(), 0usize);
+    let mut processing_rate = 0u64;
     
     loop {
         tokio::time::sleep(Duration::from_millis(100)).await;
@@ -866,21 +983,34 @@ async fn run_client(
             last_progress_time = Instant::now();
         }
         
-        // 흐름 제어 메시지 전송 (100ms마다)
-        if flow_control_time.elapsed() > Duration::from_millis(100) {
-            let chunks_map = segment_chunks.read().await;
+        // 흐름 제어 메시지 전송 (큰 변화 시 즉시, 그 외에는 느린 하트비트)
+        {
+            // 완료한 세그먼트는 워커가 맵에서 빼므로 남은 것이 조립 중인 세그먼트
+            let mut incomplete_segments = 0;
+            for shard in &segment_chunks {
+                incomplete_segments += shard.read().await.len();
+            }
             let assembled_set = assembled_segments.read().await;
-            let incomplete_segments = chunks_map.len() - assembled_set.len();
+            let assembled_bytes = assembled_set.len() * segment_size;
+            let window = rate_window.0.elapsed();
+            if window >= Duration::from_secs(1) {
+                let bytes = assembled_bytes.saturating_sub(rate_window.1);
+                processing_rate = (bytes as f64 / window.as_secs_f64()) as u64;
+                rate_window = (Instant::now(), assembled_bytes);
+            }
             
             let fc = FlowControlMessage::new(
                 assembled_set.len() as u32,
                 assembled_set.iter().max().copied().unwrap_or(0),
                 incomplete_segments as u32,
                 0.0,
-                assembled_set.len() as f32,
-            );
-            let _ = send_tx.try_send(fc.to_bytes());
-            flow_control_time = Instant::now();
+                processing_rate,
+            )
+            .with_completed_segments(assembled_set.len() as u64)
+            .with_estimated_bandwidth(estimated_bandwidth);
+            if flow_control.poll(&fc, Instant::now()) {
+                let _ = send_tx.try_send(fc.to_bytes());
+            }
         }
         
         // 완료 체크
@@ -891,31 +1021,32 @@ async fn run_client(
         
         // NACK 전송 (데이터가 잠시 안오면)
         if last_chunk.elapsed() > Duration::from_millis(200) {
-            let chunks_map = segment_chunks.read().await;
-            let totals_map = segment_total_chunks.read().await;
+            let mut chunk_maps = Vec::with_capacity(num_workers);
+            for chunks in &segment_chunks {
+                chunk_maps.push(chunks.read().await);
+            }
             let assembled_set = assembled_segments.read().await;
             
             let mut nacks_sent = 0;
             let mut total_chunks_requested = 0u64;
             
             // 1. 부분적으로 받은 세그먼트의 누락 청크 요청
-            for (segment_id, chunks) in chunks_map.iter() {
-                if !assembled_set.contains(segment_id) {
-                    let total_chunks = totals_map.get(segment_id).copied().unwrap_or(55);
-                    let received: std::collections::HashSet<u32> = chunks.keys().copied().collect();
-                    let missing: Vec<u32> = (0..total_chunks)
-                        .filter(|i| !received.contains(i))
-                        .collect();
+            'shards: for chunks_map in chunk_maps.iter() {
+                for (segment_id, segment) in chunks_map.iter() {
+                    if !assembled_set.contains(segment_id) {
+                        let missing = segment.missing_chunk_ids();
                     
-                    if !missing.is_empty() {
-                        total_chunks_requested += missing.len() as u64;
-                        let nack = NackMessage::new(*segment_id, missing.clone(), 0.0, 0);
-                        let _ = send_tx.try_send(nack.to_bytes());
-                        nack_count += 1;
-                        nacks_sent += 1;
+                        if !missing.is_empty() {
+                            total_chunks_requested += missing.len() as u64;
+                            let receive_ratio = segment.receive_ratio() as f32;
+                            let nack = NackMessage::new(*segment_id, missing.clone(), receive_ratio, 0);
+                            let _ = send_tx.try_send(nack.to_bytes());
+                            nack_count += 1;
+                            nacks_sent += 1;
                         
-                        if nacks_sent >= 50 {
-                            break;
+                            if nacks_sent >= 50 {
+                                break 'shards;
+                            }
                         }
                     }
                 }
@@ -924,7 +1055,9 @@ async fn run_client(
             // 2. 아예 청크를 하나도 못 받은 세그먼트 요청 (전체 세그먼트 요청)
             if nacks_sent < 50 {
                 for seg_id in 1..=expected_segments as u64 {
-                    if !assembled_set.contains(&seg_id) && !chunks_map.contains_key(&seg_id) {
+                    if !assembled_set.contains(&seg_id)
+                        && !chunk_maps[dispatcher.shard_of(seg_id)].contains_key(&seg_id)
+                    {
                         // 전체 청크 요청
                         let all_chunks: Vec<u32> = (0..chunks_per_segment as u32).collect();
                         total_chunks_requested += chunks_per_segment as u64;
@@ -945,11 +1078,11 @@ async fn run_client(
             }
         }
         
-        // 10초간 새 데이터 없고 95% 이상 받았으면 종료
+        // 10초간 새 데이터 없고 95% 이상 받았으면 더 기다리지 않음 (빈 구간은 아래에서 보고)
         if last_chunk.elapsed() > Duration::from_secs(10) {
             let progress = assembled_count as f64 / expected_segments as f64;
             if progress >= 0.95 {
-                info!("✅ 95% 이상 수신 완료, 종료");
+                info!("⏹️  95% 이상 수신 후 10초간 새 데이터 없음, 종료");
                 break;
             }
         }
@@ -987,6 +1120,27 @@ async fn run_client(
         }
     }
 
+    // 빠진 세그먼트가 있으면 구멍 난 데이터를 성공으로 돌려주지 않음
+    let missing: Vec<u64> = (1..=expected_segments as u64)
+        .filter(|id| !final_segments.contains_key(id))
+        .collect();
+    if !missing.is_empty() {
+        let layout = (1..=expected_segments as u64).map(|id| {
+            let offset = (id - 1) * segment_size as u64;
+            let len = (segment_size as u64).min((total_file_size as u64).saturating_sub(offset));
+            (id, len, final_segments.contains_key(&id))
+        });
+        let missing_bytes = TransferReport::missing_byte_ranges(layout);
+        let missing_segments = TransferReport::segment_ranges(missing);
+        warn!("⚠️  불완전 수신: 빠진 세그먼트 {:?}", missing_segments);
+        warn!("   빠진 바이트 구간: {:?}", missing_bytes);
+        return Err(sfp::Error::IncompleteTransfer {
+            missing_segments,
+            missing_bytes,
+        }
+        .into());
+    }
+
     let elapsed = start.elapsed();
     let throughput = received_data.len() as f64 / elapsed.as_secs_f64() / 1024.0 / 1024.0;
 
@@ -1033,6 +1187,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut addr: SocketAddr = "127.0.0.1:9000".parse()?;
     let mut encrypt = false;
     let mut num_workers = num_cpus();
+    let mut bbr_log: Option<String> = None;
 
     let mut i = 1;
     while i < args.len() {
@@ -1058,6 +1213,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     i += 1;
                 }
             }
+            "--bbr-log" => {
+                if i + 1 < args.len() {
+                    bbr_log = Some(args[i + 1].clone());
+                    i += 1;
+                }
+            }
             "--help" | "-h" => {
                 println!(r#"
 대용량 파일 전송 테스트 (병렬 처리 + 암호화 지원)
@@ -1072,6 +1233,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
   --bind, -b <ADDR> 서버: 바인드 주소 / 클라이언트: 서버 주소 (기본: 127.0.0.1:9000)
   --encrypt, -e     암호화 활성화 (X25519 + ChaCha20-Poly1305)
   --workers <N>     병렬 워커 수 (기본: CPU 코어 수)
+  --bbr-log <PATH>  서버: BBR 스냅샷을 NDJSON으로 기록
 
 예시:
   # 서버 (외부 접속 허용)
@@ -1092,14 +1254,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // 설정
-    let mut config = Config::default();
-    config.chunk_size = 1200;
-    config.segment_size = 65536;  // 64KB
-    config.base_redundancy_ratio = 0.20;  // 20% 중복
-    config.nack_timeout_ms = 100;  // NACK 체크 주기
-    config.segment_timeout_ms = 30000;  // 30초 세그먼트 타임아웃
-    config.encryption_enabled = encrypt;
-    config.parallel_workers = num_workers;
+    let config = Config {
+        chunk_size: 1200,
+        segment_size: 65536,           // 64KB
+        base_redundancy_ratio: 0.20,   // 20% 중복
+        nack_timeout_ms: 100,          // NACK 체크 주기
+        segment_timeout_ms: 30000,     // 30초 세그먼트 타임아웃
+        encryption_enabled: encrypt,
+        parallel_workers: num_workers,
+        bandwidth_probe: true,         // 초기 대역폭을 추정 대신 측정
+        initial_window_chunks: 10,     // FlowControl 보고로 늘리는 슬로 스타트
+        ..Config::default()
+    };
 
     let _data_size = size_mb * 1024 * 1024;
 
@@ -1113,7 +1279,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("═══════════════════════════════════════════");
 
         let data = generate_test_text(size_mb);
-        run_server(addr, data, config, encrypt, num_workers).await?;
+        run_server(addr, data, config, encrypt, num_workers, bbr_log).await?;
 
     } else if is_client {
         // 클라이언트 모드
@@ -1124,7 +1290,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         info!("═══════════════════════════════════════════");
 
-        let received = run_client(addr, encrypt).await?;
+        let received = run_client(addr, encrypt, &config).await?;
 
         // 데이터 일부 출력 (확인용)
         if !received.is_empty() {
@@ -1145,6 +1311,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// InitAck 뒤 프로브 버스트를 받아 전달 속도(bytes/s)를 재고 서버에 보고
+async fn measure_probe_rate(
+    recv_rx: &Arc<tokio::sync::Mutex<mpsc::Receiver<Vec<u8>>>>,
+    send_tx: &mpsc::Sender<Vec<u8>>,
+) -> Option<u64> {
+    let deadline = Instant::now() + Duration::from_millis(500);
+    let mut meter = ProbeMeter::new();
+    let mut rx = recv_rx.lock().await;
+    while !meter.is_complete() {
+        // 첫 프로브 이후에는 100ms 동안 다음 프로브가 없으면 받은 만큼으로 계산
+        let wait = match meter.last_arrival() {
+            Some(at) => (at + Duration::from_millis(100)).min(deadline),
+            None => deadline,
+        }
+        .saturating_duration_since(Instant::now());
+        match tokio::time::timeout(wait, rx.recv()).await {
+            Ok(Some(data)) => {
+                if let Some(probe_msg) = ProbeMessage::from_bytes(&data) {
+                    meter.on_probe(&probe_msg, data.len(), Instant::now());
+                }
+            }
+            _ => break,
+        }
+    }
+    drop(rx);
+
+    let report = meter.take_report()?;
+    let _ = send_tx.send(report.to_bytes()).await;
+    (report.rate > 0).then_some(report.rate)
+}
+
 /// CPU 코어 수 반환
 fn num_cpus() -> usize {
     std::thread::available_parallelism()
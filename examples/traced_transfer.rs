@@ -0,0 +1,127 @@
+//! 연결별 로그 필터링 예제
+//!
+//! 로컬호스트에서 송신자/수신자 두 쌍을 동시에 실행하고,
+//! `connection` 스팬의 `peer` 필드로 한 연결의 로그만 골라 출력
+//!
+//! 사용법:
+//!   cargo run --example traced_transfer -- [FILTER]
+//!
+//! FILTER를 생략하면 `RUST_LOG`, 그것도 없으면 첫 번째 연결(A)만 debug로 출력.
+//! 예) 두 연결 모두 보기:
+//!   cargo run --example traced_transfer -- 'info,sfp=debug'
+//! 예) 연결 B의 세그먼트 7만 보기:
+//!   cargo run --example traced_transfer -- 'warn,[segment{segment_id=7}]=debug'
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::net::UdpSocket;
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use sfp::multipath::PathManager;
+use sfp::receiver::Receiver;
+use sfp::sender::Sender;
+use sfp::Config;
+
+/// 연결 (이름, 서버 주소, 클라이언트 주소)
+const CONNECTIONS: [(&str, &str, &str); 2] = [
+    ("A", "127.0.0.1:47101", "127.0.0.1:47102"),
+    ("B", "127.0.0.1:47201", "127.0.0.1:47202"),
+];
+
+/// 연결당 전송할 세그먼트 수
+const SEGMENTS: usize = 8;
+
+/// 연결 A의 양쪽 끝(송신자 쪽 peer = 클라이언트, 수신자 쪽 peer = 서버)만 debug
+fn default_filter() -> String {
+    let (_, server, client) = CONNECTIONS[0];
+    format!(
+        "warn,[connection{{peer={}}}]=debug,[connection{{peer={}}}]=debug",
+        server, client
+    )
+}
+
+async fn run_connection(
+    name: &'static str,
+    server_addr: SocketAddr,
+    client_addr: SocketAddr,
+) -> sfp::Result<()> {
+    // 두 연결이 같은 루프백에서 버스트로 보내면 수신 버퍼 초과로 세그먼트 전체가 유실될 수 있음
+    let config = Config {
+        chunk_interval_us: 100,
+        ..Config::default()
+    };
+
+    let socket = Arc::new(UdpSocket::bind(server_addr).await?);
+    let sender = Arc::new(Sender::new(
+        config.clone(),
+        Arc::new(PathManager::new(config.clone())),
+    ));
+    let sender_task = sender.clone();
+    let socket_task = socket.clone();
+    let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+    let (receiver, mut segments) = Receiver::start(
+        config.clone(),
+        client_addr,
+        server_addr,
+        Arc::new(PathManager::new(config)),
+    )
+    .await?;
+
+    while sender.client_addr() != Some(client_addr) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    for i in 0..SEGMENTS {
+        let payload = Bytes::from(vec![i as u8; 16 * 1024]);
+        sender.send_data(payload, &*socket).await?;
+    }
+
+    let mut bytes = 0;
+    for _ in 0..SEGMENTS {
+        match tokio::time::timeout(Duration::from_secs(5), segments.recv()).await {
+            Ok(Some((_, data))) => bytes += data.len(),
+            _ => break,
+        }
+    }
+
+    // 애플리케이션 로그도 같은 연결 스팬에 묶어 필터 적용
+    info!(parent: receiver.span(), "연결 {} 수신 완료: {} bytes", name, bytes);
+
+    receiver.stop().await;
+    sender.stop();
+    handle.abort();
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let filter = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var(EnvFilter::DEFAULT_ENV).ok())
+        .unwrap_or_else(default_filter);
+    println!("필터: {}", filter);
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(&filter)?)
+        .init();
+
+    let tasks: Vec<_> = CONNECTIONS
+        .iter()
+        .map(|&(name, server, client)| {
+            let server: SocketAddr = server.parse().expect("서버 주소");
+            let client: SocketAddr = client.parse().expect("클라이언트 주소");
+            tokio::spawn(run_connection(name, server, client))
+        })
+        .collect();
+
+    for task in tasks {
+        task.await??;
+    }
+
+    Ok(())
+}
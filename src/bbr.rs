@@ -1,15 +1,48 @@
+use serde::Serialize;
+
 #[derive(Debug)]
 pub struct BbrLite {
     pub pacing_rate: f64,   // bytes/sec
-    pub min_rtt: f64,       // seconds
+    pub min_rtt: f64,       // seconds, 최근 `min_rtt_window` 동안의 최소 RTT
+    pub min_rtt_stamp: std::time::Instant, // min_rtt를 마지막으로 갱신한 시각
     pub last_rtt: f64,      // seconds
+    pub srtt: f64,          // seconds, 평활 RTT (샘플 전에는 0)
     pub delivered_bytes: u64,
     pub delivered_prev: u64,
     pub last_ts: std::time::Instant,
 
+    // 마지막 update_rate에서 계산한 판단 근거
+    pub btlbw: f64,         // bytes/sec
+    pub delivery_rate: f64, // bytes/sec
+    pub queue_ratio: f64,   // last_rtt / min_rtt
+    pub applied_gain: f64,
+
     // parameters
     pub gain: f64,
     pub probe_interval: f64,
+    /// min_rtt 유지 기간 (초). 이 기간 동안 더 작은 표본이 없으면 다음 표본으로 교체해
+    /// 경로가 바뀌어 기본 RTT가 늘어도 큐로 오인하지 않음
+    pub min_rtt_window: f64,
+    /// 지연 기반 감속 시작점 (srtt / min_rtt). 넘으면 큐가 쌓이는 것으로 보고 손실 전에 감속
+    /// (`f64::INFINITY`면 지연 신호 무시)
+    pub delay_threshold: f64,
+    /// 큐가 없을 때 갱신마다 송신 속도 대비 올리는 배율
+    pub probe_gain: f64,
+}
+
+/// 특정 시점의 혼잡제어 상태 (로깅/튜닝용)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct BbrSnapshot {
+    pub pacing_rate: f64,   // bytes/sec
+    pub min_rtt: f64,       // seconds
+    pub last_rtt: f64,      // seconds
+    pub srtt: f64,          // seconds
+    pub btlbw: f64,         // bytes/sec
+    pub delivery_rate: f64, // bytes/sec
+    pub queue_ratio: f64,
+    pub gain: f64,          // 마지막으로 적용된 gain
+    pub bdp_bytes: f64,     // delivery_rate × min_rtt
+    pub delivered_bytes: u64,
 }
 
 impl BbrLite {
@@ -17,13 +50,23 @@ impl BbrLite {
         Self {
             pacing_rate: initial_rate,     // 초기 대역폭 추정값
             min_rtt: initial_rtt,
+            min_rtt_stamp: std::time::Instant::now(),
             last_rtt: initial_rtt,
+            srtt: 0.0,
             delivered_bytes: 0,
             delivered_prev: 0,
             last_ts: std::time::Instant::now(),
 
+            btlbw: 0.0,
+            delivery_rate: 0.0,
+            queue_ratio: 1.0,
+            applied_gain: 1.0,
+
             gain: 1.0,
             probe_interval: 0.20, // 200ms
+            min_rtt_window: 10.0, // 10s
+            delay_threshold: 1.25, // min_rtt보다 25% 이상 길어지면 감속
+            probe_gain: 1.25,
         }
     }
 
@@ -34,16 +77,39 @@ impl BbrLite {
 
     // 호출 위치: RTT 샘플 도착 시
     pub fn on_rtt_update(&mut self, rtt: f64) {
+        self.on_rtt_update_at(rtt, std::time::Instant::now());
+    }
+
+    /// 지정 시각의 RTT 표본 (시뮬레이션/테스트용)
+    ///
+    /// min_rtt는 `min_rtt_window` 동안의 최솟값: 기간이 지나면 더 크더라도 현재 표본으로 교체
+    pub fn on_rtt_update_at(&mut self, rtt: f64, now: std::time::Instant) {
         self.last_rtt = rtt;
-        if rtt < self.min_rtt {
+        let expired =
+            now.saturating_duration_since(self.min_rtt_stamp).as_secs_f64() > self.min_rtt_window;
+        if rtt <= self.min_rtt || expired {
             self.min_rtt = rtt;
+            self.min_rtt_stamp = now;
         }
+        // RFC 6298과 같은 1/8 가중 평균 (순간 튀는 샘플에 감속하지 않도록)
+        self.srtt = if self.srtt > 0.0 {
+            self.srtt + (rtt - self.srtt) / 8.0
+        } else {
+            rtt
+        };
     }
 
     // 호출 위치: 주기적 (예: 50~100ms )
     pub fn update_rate(&mut self) {
-        let now = std::time::Instant::now();
-        let dt = now.duration_since(self.last_ts).as_secs_f64();
+        self.update_rate_at(std::time::Instant::now());
+    }
+
+    /// 지정 시각 기준 갱신 (시뮬레이션/테스트용)
+    ///
+    /// - 큐 없음 (srtt / min_rtt ≤ `delay_threshold`): 보낸 속도의 `probe_gain`배까지 올림
+    /// - 큐 쌓임: 보낸 속도 이하로 낮춘 뒤 `exp(-(queue_ratio - 1))`배로 줄여 큐를 비움
+    pub fn update_rate_at(&mut self, now: std::time::Instant) {
+        let dt = now.saturating_duration_since(self.last_ts).as_secs_f64();
 
         if dt < self.probe_interval {
             return; // 아직 갱신할 때 아님
@@ -56,21 +122,133 @@ impl BbrLite {
         self.last_ts = now;
 
         let btlbw = delivered as f64 / self.last_rtt.max(0.000001);
-        let queue_ratio = self.last_rtt / self.min_rtt.max(0.000001);
+        let rtt = if self.srtt > 0.0 { self.srtt } else { self.last_rtt };
+        let queue_ratio = rtt / self.min_rtt.max(0.000001);
 
-        let gain = (- (queue_ratio - 1.0)).exp();
-        self.pacing_rate *= btlbw * gain;
+        let gain = if queue_ratio > self.delay_threshold {
+            // 손실 전에 감속: 병목 큐가 비도록 실제 송신 속도보다 낮게
+            let gain = (- (queue_ratio - 1.0)).exp();
+            self.pacing_rate = self.pacing_rate.min(delivery_rate) * gain;
+            gain
+        } else {
+            self.pacing_rate = self.pacing_rate.max(delivery_rate * self.probe_gain);
+            self.probe_gain
+        };
 
-        // delivery_rate를 기반으로 보정
-        self.pacing_rate = self.pacing_rate.max(delivery_rate * 0.8);
+        self.btlbw = btlbw;
+        self.delivery_rate = delivery_rate;
+        self.queue_ratio = queue_ratio;
+        self.applied_gain = gain;
 
         // 상한/하한
         self.pacing_rate = self.pacing_rate.clamp(10_000_000.0, 5_000_000_000.0);
     }
 
+    // 현재 상태 스냅샷
+    pub fn snapshot(&self) -> BbrSnapshot {
+        BbrSnapshot {
+            pacing_rate: self.pacing_rate,
+            min_rtt: self.min_rtt,
+            last_rtt: self.last_rtt,
+            srtt: self.srtt,
+            btlbw: self.btlbw,
+            delivery_rate: self.delivery_rate,
+            queue_ratio: self.queue_ratio,
+            gain: self.applied_gain,
+            bdp_bytes: self.delivery_rate * self.min_rtt,
+            delivered_bytes: self.delivered_bytes,
+        }
+    }
+
     // pacing delay 계산
     pub fn pacing_delay(&self, packet_size: usize) -> std::time::Duration {
         let sec = (packet_size as f64 / self.pacing_rate).max(0.000_001);
         std::time::Duration::from_secs_f64(sec)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_tracks_update_inputs() {
+        let mut bbr = BbrLite::new(0.010, 20_000_000.0);
+        bbr.probe_interval = 0.0;
+        bbr.on_packet_sent(1_000_000);
+        bbr.on_rtt_update(0.020);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        bbr.update_rate();
+
+        let snap = bbr.snapshot();
+        assert_eq!(snap.pacing_rate, bbr.pacing_rate);
+        assert_eq!(snap.queue_ratio, 2.0);
+        assert_eq!(snap.gain, (-1.0f64).exp());
+        assert_eq!(snap.btlbw, 1_000_000.0 / 0.020);
+        assert_eq!(snap.bdp_bytes, snap.delivery_rate * 0.010);
+        assert_eq!(snap.delivered_bytes, 1_000_000);
+    }
+
+    /// 병목 큐 모델 위에서 1ms 단위로 `ms`만큼 보내고 (최대 큐 지연, 마지막 속도) 반환.
+    /// 손실은 없고 큐가 쌓인 만큼 RTT만 늘어남
+    fn run_bottleneck(bbr: &mut BbrLite, ms: u64) -> (f64, f64) {
+        const CAPACITY: f64 = 100_000_000.0; // bytes/sec
+        const BASE_RTT: f64 = 0.010;
+        let t0 = bbr.last_ts;
+        let mut queue = 0.0f64;
+        let mut max_delay = 0.0f64;
+        for step in 1..=ms {
+            let sent = bbr.pacing_rate * 0.001;
+            bbr.on_packet_sent(sent as usize);
+            queue = (queue + sent - CAPACITY * 0.001).max(0.0);
+
+            let delay = queue / CAPACITY;
+            if step > 200 {
+                max_delay = max_delay.max(delay);
+            }
+            let now = t0 + std::time::Duration::from_millis(step);
+            bbr.on_rtt_update_at(BASE_RTT + delay, now);
+            bbr.update_rate_at(now);
+        }
+        (max_delay, bbr.pacing_rate)
+    }
+
+    #[test]
+    fn test_rtt_inflation_backs_off_before_loss() {
+        let mut bbr = BbrLite::new(0.010, 300_000_000.0);
+        bbr.probe_interval = 0.020;
+        let (max_delay, rate) = run_bottleneck(&mut bbr, 3000);
+
+        // 링크 용량 근처로 내려오고 큐 지연은 기본 RTT의 몇 배 이내
+        assert!(max_delay < 0.030, "큐 지연 {:.1}ms", max_delay * 1000.0);
+        assert!((50_000_000.0..200_000_000.0).contains(&rate), "{}", rate);
+
+        // 지연 신호를 무시하면 같은 구간에 큐가 계속 쌓임
+        let mut loss_only = BbrLite::new(0.010, 300_000_000.0);
+        loss_only.probe_interval = 0.020;
+        loss_only.delay_threshold = f64::INFINITY;
+        let (max_delay, _) = run_bottleneck(&mut loss_only, 3000);
+        assert!(max_delay > 1.0, "큐 지연 {:.1}ms", max_delay * 1000.0);
+    }
+
+    #[test]
+    fn test_min_rtt_expires_after_path_change() {
+        let mut bbr = BbrLite::new(0.010, 100_000_000.0);
+        let t0 = bbr.min_rtt_stamp;
+        let at = |ms: u64| t0 + std::time::Duration::from_millis(ms);
+        bbr.on_rtt_update_at(0.010, at(0));
+
+        // 경로가 바뀌어 기본 RTT가 40ms로: 기간 안에는 이전 최솟값 유지
+        bbr.on_rtt_update_at(0.040, at(5_000));
+        assert_eq!(bbr.min_rtt, 0.010);
+
+        // 기간 동안 더 작은 표본이 없으면 새 경로의 RTT로 교체하고, 큐가 없으니 감속하지 않음
+        bbr.on_rtt_update_at(0.040, at(10_001));
+        assert_eq!(bbr.min_rtt, 0.040);
+        bbr.probe_interval = 0.0;
+        bbr.on_packet_sent(1_000_000);
+        bbr.update_rate_at(at(10_101));
+        assert!(bbr.queue_ratio <= bbr.delay_threshold, "{}", bbr.queue_ratio);
+        assert_eq!(bbr.applied_gain, bbr.probe_gain);
+    }
+}
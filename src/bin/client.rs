@@ -14,19 +14,24 @@
 //!   # 예상 크기 지정
 //!   cargo run --release --bin sfp-client -- -s 127.0.0.1:9000 -o data.bin --size 104857600
 
+// 인자 파싱/수신 루프는 원래 형태를 유지 (clippy 제안으로 바꾸지 않음)
+#![allow(clippy::collapsible_match, clippy::while_let_loop)]
+
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use bytes::Bytes;
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
-use tracing::{info, warn, Level};
+use tracing::{debug, info, warn, Level};
 use tracing_subscriber::FmtSubscriber;
 
-use sfp::chunk::Chunk;
-use sfp::message::{InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage};
+use sfp::chunk::{Chunk, Segment};
+use sfp::message::{CookieMessage, InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage};
+use sfp::stats::QueueDepth;
 use sfp::Config;
 
 /// 클라이언트 설정
@@ -96,6 +101,12 @@ fn parse_args() -> ClientConfig {
                     i += 1;
                 }
             }
+            "--max-size" => {
+                if i + 1 < args.len() {
+                    config.config.max_accept_file_size = args[i + 1].parse().expect("유효한 숫자 필요");
+                    i += 1;
+                }
+            }
             "--help" | "-h" => {
                 println!(
                     r#"SFP Client - Super Light Stream Protocol 클라이언트
@@ -114,6 +125,7 @@ NACK 기반 블록 조립형 고속 전송 프로토콜 클라이언트
   --size <BYTES>         예상 데이터 크기 (바이트)
   -e, --encrypt          암호화 활성화 (X25519 + ChaCha20-Poly1305)
   -w, --workers <N>      병렬 워커 수 (기본: CPU 코어 수)
+  --max-size <BYTES>     받아들일 최대 전송 크기, 넘으면 거부 (기본: 0 = 제한 없음)
   -h, --help             이 도움말 출력
 
 예시:
@@ -162,7 +174,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 송신 큐: 우선순위 큐 (Init, NACK) + 일반 큐 (기타)
     // ═══════════════════════════════════════════════════════════════
     let (priority_tx, mut priority_rx) = mpsc::channel::<Vec<u8>>(1000);
-    let (_data_tx, mut data_rx) = mpsc::channel::<Vec<u8>>(10_000);
+    let (data_tx, mut data_rx) = mpsc::channel::<Vec<u8>>(10_000);
 
     // 송신 태스크
     let send_socket = socket.clone();
@@ -192,9 +204,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // ═══════════════════════════════════════════════════════════════
     // 수신 큐 + 수신 태스크
     // ═══════════════════════════════════════════════════════════════
-    let (recv_tx, recv_rx) = mpsc::channel::<Vec<u8>>(100_000);
-    let recv_rx = Arc::new(tokio::sync::Mutex::new(recv_rx));
-    
+    let (recv_tx, mut recv_rx) = mpsc::channel::<Vec<u8>>(100_000);
+
+    // 큐 적재량 주기 보고 (송신 큐 포화 = 송신 병목, 수신 큐 적체 = 처리 병목)
+    let queues = [
+        ("priority", priority_tx.downgrade()),
+        ("data", data_tx.downgrade()),
+        ("recv", recv_tx.downgrade()),
+    ];
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let depths: Vec<QueueDepth> = queues
+                .iter()
+                .filter_map(|(name, tx)| QueueDepth::of_weak(name, 0, tx))
+                .collect();
+            if depths.is_empty() {
+                break;
+            }
+            debug!(
+                "큐 적재량: {}",
+                depths.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")
+            );
+        }
+    });
+
     let recv_socket = socket.clone();
     let _recv_task = tokio::spawn(async move {
         let mut buf = vec![0u8; 2048];
@@ -209,10 +244,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     });
 
     // === Phase 1: 핸드쉐이크 (Init/InitAck) ===
-    let init_request = InitMessage::new(
+    let mut init_request = InitMessage::new(
         client_config.encrypt,
         [0u8; 32],
-    );
+    )
+    .with_supported_limits(&client_config.config);
 
     info!("Sending Init to server (via priority queue)...");
     let mut init_ack: Option<InitAckMessage> = None;
@@ -228,25 +264,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // 수신 큐에서 읽기
-        let mut rx = recv_rx.lock().await;
-        match tokio::time::timeout(retry_interval, rx.recv()).await {
+        match tokio::time::timeout(retry_interval, recv_rx.recv()).await {
             Ok(Some(buf)) => {
-                drop(rx);
-                if let Ok(header) = bincode::deserialize::<MessageHeader>(&buf[..buf.len().min(32)]) {
+                if let Some(header) = MessageHeader::from_bytes(&buf) {
                     if header.msg_type == MessageType::InitAck {
                         if let Some(resp) = InitAckMessage::from_bytes(&buf) {
                             init_ack = Some(resp);
                         }
+                    } else if header.msg_type == MessageType::Cookie {
+                        // 서버 쿠키를 담아 다음 Init 전송
+                        if let Some(cookie) = CookieMessage::from_bytes(&buf) {
+                            init_request.cookie = cookie.cookie;
+                            info!("Cookie received, resending Init");
+                        }
                     }
                 }
             }
             Ok(None) => {
-                drop(rx);
                 warn!("Receive channel closed");
             }
-            Err(_) => {
-                drop(rx);
-            }
+            Err(_) => {}
         }
 
         retry_count += 1;
@@ -254,6 +291,32 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let metadata = init_ack.ok_or("Failed to receive InitAck from server")?;
 
+    // 세그먼트 수가 파일 크기와 맞지 않으면 완료 판정/NACK 범위를 믿을 수 없고,
+    // 알린 수신 한도를 넘는 청크/세그먼트는 조립할 수 없음
+    let limits = metadata.validate().and_then(|()| {
+        metadata.validate_limits(
+            init_request.max_supported_chunk_size,
+            init_request.max_supported_segment_size,
+        )
+    });
+    if let Err(e) = limits {
+        let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+        let _ = socket.send_to(&close, server_addr).await;
+        return Err(e.into());
+    }
+
+    // 서버가 알린 크기가 한도를 넘으면 버퍼를 만들기 전에 거부
+    let max_accept = client_config.config.max_accept_file_size;
+    if max_accept > 0 && metadata.total_file_size > max_accept {
+        let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+        let _ = socket.send_to(&close, server_addr).await;
+        return Err(sfp::Error::FileTooLarge {
+            size: metadata.total_file_size,
+            max: max_accept,
+        }
+        .into());
+    }
+
     info!("InitAck received:");
     info!("  Total file size: {} bytes", metadata.total_file_size);
     info!("  Total segments: {}", metadata.total_segments);
@@ -266,10 +329,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting data reception...");
     let start = Instant::now();
 
-    // 세그먼트별 청크 수신 상태
-    // segment_id -> (received_chunks: HashMap<chunk_id, data>, total_chunks)
-    let mut segment_chunks: HashMap<u64, (HashMap<u32, Vec<u8>>, u32)> = HashMap::new();
-    let mut completed_segments: HashMap<u64, Vec<u8>> = HashMap::new();
+    // 세그먼트별 청크 수신 상태 (라이브러리 `Segment`: 세그먼트 버퍼 하나 + 수신 비트맵)
+    let mut segment_chunks: HashMap<u64, Segment> = HashMap::new();
+    let mut completed_segments: HashMap<u64, Bytes> = HashMap::new();
     let mut total_chunks_received = 0u64;
     let mut total_nacks_sent = 0u64;
 
@@ -279,6 +341,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // NACK 타이밍
     let mut last_nack_time = Instant::now();
     let nack_interval = Duration::from_millis(200);
+    let max_datagram_size = client_config.config.max_datagram_size;
     let mut last_progress_time = Instant::now();
 
     // 수신 루프
@@ -299,58 +362,37 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
 
         // 패킷 수신 (수신 큐에서 읽기)
-        let mut rx = recv_rx.lock().await;
-        match tokio::time::timeout(Duration::from_millis(50), rx.recv()).await {
-            Ok(Some(buf)) => {
-                drop(rx);
-                // 청크 파싱
-                if let Some(chunk) = Chunk::from_bytes(&buf) {
-                    let seg_id = chunk.header.segment_id;
-                    let chunk_id = chunk.header.chunk_id;
-                    let total_chunks = chunk.header.total_chunks;
-
-                    // 이미 완료된 세그먼트 스킵
-                    if completed_segments.contains_key(&seg_id) {
-                        continue;
-                    }
+        if let Ok(Some(buf)) = tokio::time::timeout(Duration::from_millis(50), recv_rx.recv()).await {
+            // 청크 파싱
+            if let Some(chunk) = Chunk::from_bytes(&buf) {
+                let seg_id = chunk.header.segment_id;
+                let total_chunks = chunk.header.total_chunks;
+                let segment_size = chunk.header.segment_size as usize;
+
+                // 이미 완료된 세그먼트와, 크기보다 청크가 많다는 헤더(비트맵 과대 할당) 스킵
+                if completed_segments.contains_key(&seg_id)
+                    || total_chunks as usize > segment_size.max(1)
+                {
+                    continue;
+                }
 
-                    // 세그먼트 청크 저장
-                    let entry = segment_chunks
-                        .entry(seg_id)
-                        .or_insert_with(|| (HashMap::new(), total_chunks));
-                    
-                    if !entry.0.contains_key(&chunk_id) {
-                        entry.0.insert(chunk_id, chunk.data.to_vec());
-                        total_chunks_received += 1;
-                    }
+                // 청크 헤더의 오프셋으로 세그먼트 버퍼에 바로 복사 (세그먼트마다 크기가 다를 수 있음)
+                let segment = segment_chunks
+                    .entry(seg_id)
+                    .or_insert_with(|| {
+                        Segment::new_for_receive(seg_id, segment_size, total_chunks)
+                    });
+                if segment.insert_chunk(&chunk) {
+                    total_chunks_received += 1;
+                }
 
-                    // 세그먼트 완료 체크
-                    if entry.0.len() as u32 == total_chunks {
-                        // 세그먼트 조립
-                        let mut segment_data = vec![0u8; metadata.segment_size as usize];
-                        for (&cid, data) in &entry.0 {
-                            let offset = cid as usize * metadata.chunk_size as usize;
-                            let end = (offset + data.len()).min(segment_data.len());
-                            segment_data[offset..end].copy_from_slice(&data[..end - offset]);
-                        }
-                        // 마지막 세그먼트는 크기가 다를 수 있음
-                        if seg_id == total_segments {
-                            let last_seg_size = (metadata.total_file_size % metadata.segment_size as u64) as usize;
-                            if last_seg_size > 0 {
-                                segment_data.truncate(last_seg_size);
-                            }
-                        }
-                        completed_segments.insert(seg_id, segment_data);
-                        segment_chunks.remove(&seg_id);
+                // 세그먼트 완료 체크
+                if segment.is_complete() {
+                    if let Some(segment) = segment_chunks.remove(&seg_id) {
+                        completed_segments.insert(seg_id, segment.into_data());
                     }
                 }
             }
-            Ok(None) => {
-                drop(rx);
-            }
-            Err(_) => {
-                drop(rx);
-            }
         }
 
         // 진행률 로깅 (2초마다)
@@ -372,15 +414,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut total_missing_chunks = 0;
 
             // 부분 수신된 세그먼트의 누락 청크 요청
-            for (&seg_id, (received, total)) in &segment_chunks {
-                let missing: Vec<u32> = (0..*total)
-                    .filter(|id| !received.contains_key(id))
-                    .collect();
+            for (&seg_id, segment) in &segment_chunks {
+                let missing = segment.missing_chunk_ids();
 
                 if !missing.is_empty() {
-                    let nack = NackMessage::new(seg_id, missing.clone(), 0.0, 0);
-                    // NACK은 우선순위 큐로 전송
-                    let _ = priority_tx.send(nack.to_bytes()).await;
+                    // 수신률이 높은 세그먼트일수록 서버가 먼저 재전송
+                    let receive_ratio = segment.receive_ratio() as f32;
+                    let nack = NackMessage::new(seg_id, missing.clone(), receive_ratio, 0);
+                    // NACK은 우선순위 큐로 전송 (데이터그램 한도를 넘으면 조각으로)
+                    for fragment in nack.fragments(max_datagram_size) {
+                        let _ = priority_tx.send(fragment.to_bytes()).await;
+                    }
                     nack_count += 1;
                     total_missing_chunks += missing.len();
                     total_nacks_sent += 1;
@@ -394,7 +438,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     let missing: Vec<u32> = (0..chunks_per_segment).collect();
                     let nack = NackMessage::new(seg_id, missing.clone(), 0.0, 0);
                     // NACK은 우선순위 큐로 전송
-                    let _ = priority_tx.send(nack.to_bytes()).await;
+                    for fragment in nack.fragments(max_datagram_size) {
+                        let _ = priority_tx.send(fragment.to_bytes()).await;
+                    }
                     nack_count += 1;
                     total_missing_chunks += missing.len();
                     total_nacks_sent += 1;
@@ -14,26 +14,43 @@
 //!   # 암호화 전송 + 50% 중복
 //!   cargo run --release --bin sfp-server -- -f data.bin --encrypt --redundancy 0.5
 
+// 인자 파싱/수신 루프는 원래 형태를 유지 (clippy 제안으로 바꾸지 않음)
+#![allow(clippy::collapsible_match, clippy::while_let_loop)]
+
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
+use bytes::Bytes;
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
-use tracing::{info, Level};
-use tracing_subscriber::FmtSubscriber;
+use tracing::{debug, debug_span, info, info_span, warn, Instrument};
+use tracing_subscriber::{EnvFilter, FmtSubscriber};
 
 use sfp::bbr::BbrLite;
 use sfp::chunk::SegmentBuilder;
-use sfp::message::{InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage};
+use sfp::egress::{EgressLimiter, EgressShare};
+use sfp::guard::ControlGuard;
+use sfp::message::{CookieMessage, InitAckMessage, InitMessage, MessageHeader, MessageType, NackMessage};
+use sfp::session::{InitDisposition, SessionTable};
+use sfp::stats::QueueDepth;
 use sfp::Config;
 
+/// 세그먼트 청크 캐시 (NACK 재전송용 - 이미 분할된 청크 저장)
+type SegmentChunkCache = Arc<tokio::sync::RwLock<std::collections::HashMap<u64, Vec<sfp::chunk::Chunk>>>>;
+
+/// `--selftest` 전송량
+const SELFTEST_PAYLOAD: usize = 16 * 1024 * 1024;
+
 /// 서버 설정
 struct ServerConfig {
     bind_addr: SocketAddr,
     file_path: Option<PathBuf>,
     encrypt: bool,
     workers: usize,
+    /// 루프백 자체 점검만 실행하고 종료
+    selftest: bool,
     config: Config,
 }
 
@@ -44,6 +61,7 @@ impl Default for ServerConfig {
             file_path: None,
             encrypt: false,
             workers: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4),
+            selftest: false,
             config: Config::default(),
         }
     }
@@ -87,6 +105,12 @@ fn parse_args() -> ServerConfig {
                     i += 1;
                 }
             }
+            "--egress-limit" => {
+                if i + 1 < args.len() {
+                    config.config.total_egress_limit = args[i + 1].parse().expect("유효한 숫자 필요");
+                    i += 1;
+                }
+            }
             "--encrypt" | "-e" => {
                 config.encrypt = true;
                 config.config.encryption_enabled = true;
@@ -98,6 +122,9 @@ fn parse_args() -> ServerConfig {
                     i += 1;
                 }
             }
+            "--selftest" => {
+                config.selftest = true;
+            }
             "--help" | "-h" => {
                 println!(
                     r#"SFP Server - Segment Flow Protocol 서버
@@ -117,6 +144,8 @@ NACK 기반 블록 조립형 고속 전송 프로토콜 서버
   --chunk-size <SIZE>     청크 크기 바이트 (기본: 1200)
   --segment-size <SIZE>   세그먼트 크기 바이트 (기본: 65536)
   --redundancy <RATIO>    중복 전송 비율 0.0~1.0 (기본: 0.15 = 15%)
+  --egress-limit <BPS>    전체 송신 상한 bytes/s, 세션들이 공정 분배 (기본: 0 = 제한 없음)
+  --selftest              루프백 자체 점검 (처리율/손실/오버헤드/무결성) 후 종료
   -h, --help              이 도움말 출력
 
 예시:
@@ -128,6 +157,9 @@ NACK 기반 블록 조립형 고속 전송 프로토콜 서버
   
   # 30% 중복 + 암호화 (불안정 네트워크용)
   cargo run --release --bin sfp-server -- -f data.bin --redundancy 0.3 -e
+
+  # 설치 환경 점검
+  cargo run --release --bin sfp-server -- --selftest
 "#
                 );
                 std::process::exit(0);
@@ -142,14 +174,22 @@ NACK 기반 블록 조립형 고속 전송 프로토콜 서버
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // 로깅 설정
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
+    // 로깅 설정 (RUST_LOG로 연결/세그먼트 스팬 필터링 가능)
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = FmtSubscriber::builder().with_env_filter(filter).finish();
     tracing::subscriber::set_global_default(subscriber)?;
 
     let server_config = parse_args();
 
+    if server_config.selftest {
+        let report = sfp::selftest::run_selftest(&server_config.config, SELFTEST_PAYLOAD).await?;
+        println!("{}", report.summary());
+        if !report.verified {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     info!("SFP Server starting...");
     info!("Bind address: {}", server_config.bind_addr);
     info!("Chunk size: {} bytes", server_config.config.chunk_size);
@@ -176,12 +216,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Server listening on {}", server_config.bind_addr);
 
     // 세그먼트 빌더
-    let segment_builder = Arc::new(SegmentBuilder::new(server_config.config.chunk_size));
+    let segment_builder = Arc::new(SegmentBuilder::new(server_config.config.effective_chunk_size().max(1)));
     let config = server_config.config.clone();
 
-    // 세그먼트 청크 캐시 (NACK 재전송용 - 이미 분할된 청크 저장)
-    let segment_chunks: Arc<tokio::sync::RwLock<std::collections::HashMap<u64, Vec<sfp::chunk::Chunk>>>> =
-        Arc::new(tokio::sync::RwLock::new(std::collections::HashMap::new()));
+    // 클라이언트 세션 (세션별 청크 캐시 + 전송 태스크, 재시작 시 교체)
+    let mut sessions: SessionTable<SegmentChunkCache> = SessionTable::new();
+
+    // 전체 송신 상한을 세션별로 나눠 쓰는 몫 (상한이 없으면 비어 있음)
+    let egress_limiter = EgressLimiter::from_config(&config);
+    let mut egress_shares: std::collections::HashMap<SocketAddr, Arc<EgressShare>> =
+        std::collections::HashMap::new();
     
     // BBR 혼잡 제어 (향후 동적 pacing용)
     let _bbr = Arc::new(tokio::sync::Mutex::new(BbrLite::new(0.001, 300_000_000.0)));
@@ -225,7 +269,31 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 수신 큐 + 수신 태스크
     // ─────────────────────────────────────────────────────────────────
     let (recv_tx, mut recv_rx) = mpsc::channel::<(Vec<u8>, SocketAddr)>(100_000);
-    
+
+    // 큐 적재량 주기 보고 (송신 큐 포화 = 송신 병목, 수신 큐 적체 = 처리 병목)
+    let queues = [
+        ("priority", priority_tx.downgrade()),
+        ("data", data_tx.downgrade()),
+        ("recv", recv_tx.downgrade()),
+    ];
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            let depths: Vec<QueueDepth> = queues
+                .iter()
+                .filter_map(|(name, tx)| QueueDepth::of_weak(name, 0, tx))
+                .collect();
+            if depths.is_empty() {
+                break;
+            }
+            debug!(
+                "큐 적재량: {}",
+                depths.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")
+            );
+        }
+    });
+
     let recv_socket = socket.clone();
     let _recv_task = tokio::spawn(async move {
         let mut buf = vec![0u8; 2048];
@@ -239,57 +307,124 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
-    let data = Arc::new(data);
+    let data = Bytes::from(data);
 
     info!("Waiting for client connection (Init)...");
 
-    let mut transfer_started = false;
     let mut cached_init_ack: Option<Vec<u8>> = None;
 
+    // 컨트롤 경로 보호: 속도 제한 + 쿠키로 도달성이 확인된 클라이언트만 대량 전송
+    let guard = ControlGuard::new(&config);
+    let mut validated_clients: std::collections::HashSet<SocketAddr> = std::collections::HashSet::new();
+
     while let Some((buf, addr)) = recv_rx.recv().await {
         let len = buf.len();
 
+        // 도달성이 확인되지 않은 출발지만 속도 제한
+        if !validated_clients.contains(&addr) && !guard.allow(addr) {
+            continue;
+        }
+
         // 메시지 파싱
-        if let Ok(header) = bincode::deserialize::<MessageHeader>(&buf[..len.min(32)]) {
+        if let Some(header) = MessageHeader::from_bytes(&buf[..len]) {
             match header.msg_type {
                 MessageType::Init => {
                     // 초기화 요청 처리
                     if let Some(init_req) = InitMessage::from_bytes(&buf[..len]) {
-                        // InitAck 생성 또는 캐시된 것 사용
-                        let init_ack_bytes = if let Some(ref cached) = cached_init_ack {
-                            cached.clone()
-                        } else {
-                            let mut init_ack = InitAckMessage::new(
-                                data.len() as u64,
-                                config.chunk_size as u16,
+                        // 쿠키 없는 Init에는 작은 쿠키 메시지로만 응답
+                        if config.require_init_cookie && guard.verify_cookie(addr, &init_req.cookie).is_err() {
+                            let cookie = CookieMessage::new(guard.cookie_for(addr));
+                            let _ = priority_tx.send((cookie.to_bytes(), addr)).await;
+                            continue;
+                        }
+                        validated_clients.insert(addr);
+
+                        // 청크 크기는 세션 중 바꿀 수 없으므로 클라이언트 한도를 넘으면 전송하지 않음.
+                        // 실제 크기를 담은 InitAck으로 응답해 클라이언트가 사유를 알고 종료하게 함
+                        let chunk_size = config.effective_chunk_size();
+                        let max_chunk = init_req.max_supported_chunk_size as usize;
+                        if max_chunk > 0 && chunk_size > max_chunk {
+                            warn!(
+                                "Connection refused: chunk size {} > client limit {} ({})",
+                                chunk_size, max_chunk, addr
+                            );
+                            let init_ack = InitAckMessage::new(
+                                0,
+                                chunk_size as u16,
                                 config.segment_size as u32,
                                 config.base_redundancy_ratio as f32,
                             );
-                            init_ack.encryption_enabled = init_req.encryption_enabled;
-                            let bytes = init_ack.to_bytes();
-                            cached_init_ack = Some(bytes.clone());
-                            
-                            info!("Init received from: {}", addr);
-                            info!("  Total file size: {} bytes", init_ack.total_file_size);
-                            info!("  Total segments: {}", init_ack.total_segments);
-                            bytes
+                            let _ = priority_tx.send((init_ack.to_bytes(), addr)).await;
+                            continue;
+                        }
+
+                        // 세그먼트 크기는 클라이언트가 조립할 수 있는 크기 이하로
+                        let segment_size = match init_req.max_supported_segment_size as usize {
+                            0 => config.segment_size,
+                            limit => config.segment_size.min(limit),
+                        };
+
+                        // InitAck 생성 또는 캐시된 것 사용 (세그먼트 크기를 줄인 클라이언트는 따로 생성)
+                        let init_ack_bytes = match &cached_init_ack {
+                            Some(cached) if segment_size == config.segment_size => cached.clone(),
+                            _ => {
+                                let mut init_ack = InitAckMessage::new(
+                                    data.len() as u64,
+                                    chunk_size as u16,
+                                    segment_size as u32,
+                                    config.base_redundancy_ratio as f32,
+                                );
+                                init_ack.encryption_enabled = init_req.encryption_enabled;
+                                let bytes = init_ack.to_bytes();
+                                if segment_size == config.segment_size {
+                                    cached_init_ack = Some(bytes.clone());
+                                }
+
+                                info!("Init received from: {}", addr);
+                                info!("  Total file size: {} bytes", init_ack.total_file_size);
+                                info!("  Total segments: {}", init_ack.total_segments);
+                                bytes
+                            }
                         };
 
                         // InitAck을 우선순위 큐로 전송
                         let _ = priority_tx.send((init_ack_bytes, addr)).await;
 
-                        // 첫 번째 Init에서만 데이터 전송 시작
-                        if !transfer_started {
-                            transfer_started = true;
-                            
+                        // 새 세션에서만 데이터 전송 시작 (같은 주소의 재시작이면 이전 세션 정리 후)
+                        let disposition = sessions.on_init(addr, init_req.session_id);
+                        if let InitDisposition::Restarted { epoch, previous_epoch } = disposition {
+                            info!(
+                                "Client restarted: {} (session epoch {} -> {}), previous transfer cancelled",
+                                addr, previous_epoch, epoch
+                            );
+                        }
+
+                        if disposition.is_fresh() {
+                            let segment_chunks = match sessions.state(&addr) {
+                                Some(cache) => cache.clone(),
+                                None => continue,
+                            };
                             let data_clone = data.clone();
                             let config_clone = config.clone();
                             let segment_builder_clone = segment_builder.clone();
                             let segment_chunks_clone = segment_chunks.clone();
                             let data_tx_clone = data_tx.clone();
-                            let total_segments = (data.len() + config.segment_size - 1) / config.segment_size;
-                            
-                            tokio::spawn(async move {
+                            let egress = egress_limiter
+                                .as_ref()
+                                .map(|limiter| Arc::new(limiter.register(config.egress_weight)));
+                            match &egress {
+                                Some(share) => egress_shares.insert(addr, share.clone()),
+                                None => egress_shares.remove(&addr),
+                            };
+                            let total_segments = data.len().div_ceil(segment_size);
+                            let span = info_span!(
+                                "connection",
+                                peer = %addr,
+                                epoch = disposition.epoch(),
+                                session = init_req.session_id
+                            );
+
+                            let transfer = async move {
                                 info!("Starting data transfer...");
                                 let start = std::time::Instant::now();
 
@@ -298,11 +433,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 let mut total_chunks = 0u64;
 
                                 while offset < data_clone.len() {
-                                    let end = (offset + config_clone.segment_size).min(data_clone.len());
-                                    let segment_data = &data_clone[offset..end];
+                                    let end = (offset + segment_size).min(data_clone.len());
+                                    let segment_data = data_clone.slice(offset..end);
 
                                     // 청크 분할
-                                    let chunks = segment_builder_clone.split_into_chunks(segment_id, segment_data, 0);
+                                    let chunks = segment_builder_clone.split_bytes_into_chunks(segment_id, segment_data, 0);
                                     let redundant_chunks = segment_builder_clone
                                         .create_redundant_chunks(&chunks, config_clone.base_redundancy_ratio);
 
@@ -325,13 +460,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                     // 청크 전송
                                     for chunk in chunks.iter().chain(redundant_chunks.iter()) {
                                         let bytes = chunk.to_bytes();
+                                        if let Some(egress) = &egress {
+                                            egress.acquire(bytes.len()).await;
+                                        }
                                         if data_tx_clone.send((bytes, addr)).await.is_err() {
                                             return;
                                         }
                                         total_chunks += 1;
                                     }
 
-                                    if segment_id % 10 == 0 || offset + config_clone.segment_size >= data_clone.len() {
+                                    if segment_id.is_multiple_of(10) || offset + segment_size >= data_clone.len() {
                                         info!(
                                             "Progress: segment {}/{} ({:.1}%)",
                                             segment_id, total_segments,
@@ -351,7 +489,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                                 info!("  Total chunks: {}", total_chunks);
                                 info!("  Throughput: {:.2} MB/s", throughput);
                                 info!("Waiting for NACK retransmission requests...");
-                            });
+                            };
+                            let transfer = tokio::spawn(transfer.instrument(span));
+                            sessions.attach_task(addr, disposition.epoch(), transfer.abort_handle());
                         } else {
                             // 이미 전송 중이면 InitAck만 재전송 (로그는 간략히)
                             info!("Init re-received, InitAck resent (transfer already in progress)");
@@ -360,22 +500,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
 
                 MessageType::Nack => {
-                    // NACK 처리 - 캐시된 청크로 즉시 재전송
+                    // 미확인 출발지의 NACK은 무시 (반사 공격 방지)
+                    if !validated_clients.contains(&addr) {
+                        continue;
+                    }
+
+                    // NACK 처리 - 해당 세션의 캐시된 청크로 즉시 재전송
                     if let Some(nack) = NackMessage::from_bytes(&buf[..len]) {
-                        let segment_chunks_clone = segment_chunks.clone();
+                        let segment_chunks_clone = match sessions.state(&addr) {
+                            Some(cache) => cache.clone(),
+                            None => continue,
+                        };
                         let data_tx_clone = data_tx.clone();
-                        
-                        tokio::spawn(async move {
+                        let egress = egress_shares.get(&addr).cloned();
+                        let span = debug_span!(
+                            parent: &info_span!("connection", peer = %addr, epoch = sessions.epoch(&addr)),
+                            "segment",
+                            segment_id = nack.segment_id,
+                            nic_id = nack.nic_id
+                        );
+
+                        let retransmit = async move {
                             let cache = segment_chunks_clone.read().await;
                             if let Some(chunks) = cache.get(&nack.segment_id) {
                                 for &chunk_id in &nack.missing_chunk_ids {
                                     if let Some(chunk) = chunks.get(chunk_id as usize) {
                                         let bytes = chunk.to_bytes();
+                                        if let Some(egress) = &egress {
+                                            egress.acquire(bytes.len()).await;
+                                        }
                                         let _ = data_tx_clone.send((bytes, addr)).await;
                                     }
                                 }
                             }
-                        });
+                        };
+                        tokio::spawn(retransmit.instrument(span));
                     }
                 }
 
@@ -384,6 +543,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 }
 
                 MessageType::Close => {
+                    sessions.remove(&addr);
+                    egress_shares.remove(&addr);
                     info!("Client disconnected: {}", addr);
                 }
 
@@ -0,0 +1,167 @@
+//! 소켓 버퍼 자동 조정 (`Config::auto_buffer_tuning`)
+//!
+//! 고정 버퍼는 대역폭 × 지연이 큰 경로에서 모자라 커널이 데이터그램을 버리고, LAN에서는
+//! 메모리만 차지함. 측정한 전달 속도와 RTT로 대역폭-지연 곱(BDP)을 구해 버퍼를
+//! `[min_socket_buffer_size, max_socket_buffer_size]` 안에서 다시 맞춤
+//!
+//! - 전달 속도는 `RATE_WINDOW`마다 잰 구간 속도의 EWMA
+//! - 목표 = 속도 × RTT × `BDP_HEADROOM` (버스트와 RTT 변동 여유)
+//! - 현재 크기와 `RESIZE_THRESHOLD` 이상 차이 날 때만 바꿈 (잦은 setsockopt 방지)
+
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::transport::DatagramSocket;
+use crate::Config;
+
+/// 전달 속도 측정 구간
+pub const RATE_WINDOW: Duration = Duration::from_millis(100);
+
+/// BDP 대비 버퍼 여유 배수
+pub const BDP_HEADROOM: f64 = 2.0;
+
+/// 크기를 바꾸는 최소 상대 차이
+pub const RESIZE_THRESHOLD: f64 = 0.25;
+
+/// 구간 속도 EWMA 가중치
+const RATE_GAIN: f64 = 0.25;
+
+/// BDP 기반 소켓 버퍼 크기 조정기
+#[derive(Debug, Clone)]
+pub struct BufferTuner {
+    min: usize,
+    max: usize,
+    current: usize,
+    rtt: Duration,
+    /// 전달 속도 추정 (bytes/s, 0이면 아직 없음)
+    rate: f64,
+    window_start: Option<Instant>,
+    window_bytes: u64,
+}
+
+impl BufferTuner {
+    /// 설정의 수신 버퍼 크기에서 시작 (한도 안으로 맞춤)
+    pub fn new(config: &Config) -> Self {
+        let min = config.min_socket_buffer_size.max(1);
+        let max = config.max_socket_buffer_size.max(min);
+        Self {
+            min,
+            max,
+            current: config.recv_buffer_size.clamp(min, max),
+            rtt: Duration::from_millis(config.slow_start_initial_rtt_ms),
+            rate: 0.0,
+            window_start: None,
+            window_bytes: 0,
+        }
+    }
+
+    /// 현재 버퍼 크기
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// 전달 속도 추정 (bytes/s)
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// RTT 측정값 반영
+    pub fn on_rtt_sample(&mut self, rtt: Duration) {
+        if !rtt.is_zero() {
+            self.rtt = rtt;
+        }
+    }
+
+    /// 대역폭 측정값(프로브 등)으로 속도 추정 초기화
+    pub fn on_rate_estimate(&mut self, rate: f64) {
+        if rate > 0.0 && self.rate == 0.0 {
+            self.rate = rate;
+        }
+    }
+
+    /// 현재 추정으로 계산한 목표 크기
+    pub fn target(&self) -> usize {
+        let bdp = self.rate * self.rtt.as_secs_f64() * BDP_HEADROOM;
+        (bdp as usize).clamp(self.min, self.max)
+    }
+
+    /// 전달된 바이트 기록. 구간이 끝나 크기를 바꿔야 하면 새 크기 반환
+    pub fn on_delivered(&mut self, bytes: usize, now: Instant) -> Option<usize> {
+        let start = *self.window_start.get_or_insert(now);
+        self.window_bytes += bytes as u64;
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed < RATE_WINDOW {
+            return None;
+        }
+
+        let sample = self.window_bytes as f64 / elapsed.as_secs_f64();
+        self.rate = if self.rate == 0.0 {
+            sample
+        } else {
+            self.rate + (sample - self.rate) * RATE_GAIN
+        };
+        self.window_start = Some(now);
+        self.window_bytes = 0;
+        self.retune()
+    }
+
+    /// 목표와 충분히 차이 나면 현재 크기를 목표로 바꾸고 반환
+    pub fn retune(&mut self) -> Option<usize> {
+        if self.rate == 0.0 {
+            return None;
+        }
+        let target = self.target();
+        let diff = target.abs_diff(self.current) as f64;
+        if diff < self.current as f64 * RESIZE_THRESHOLD {
+            return None;
+        }
+        self.current = target;
+        Some(target)
+    }
+}
+
+/// 소켓 송수신 버퍼에 적용 (실패하면 경고만 남기고 이전 크기로 계속)
+pub fn apply<S: DatagramSocket>(socket: &S, size: usize) {
+    match socket.set_buffer_sizes(size, size) {
+        Ok(()) => debug!("소켓 버퍼 조정: {} bytes", size),
+        Err(e) => warn!("소켓 버퍼 조정 실패 ({} bytes): {}", size, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_follows_bdp_within_bounds() {
+        let config = Config {
+            recv_buffer_size: 2 * 1024 * 1024,
+            min_socket_buffer_size: 256 * 1024,
+            max_socket_buffer_size: 16 * 1024 * 1024,
+            ..Config::default()
+        };
+
+        // 1Gbps × 100ms = 12.5MB → 여유 배수를 곱하면 상한에 걸림
+        let mut wan = BufferTuner::new(&config);
+        wan.on_rtt_sample(Duration::from_millis(100));
+        let t0 = Instant::now();
+        assert_eq!(wan.on_delivered(0, t0), None);
+        assert_eq!(wan.on_delivered(12_500_000, t0 + RATE_WINDOW), Some(16 * 1024 * 1024));
+        assert_eq!(wan.current(), 16 * 1024 * 1024);
+
+        // 100Mbps × 1ms LAN → 하한으로 줄임
+        let mut lan = BufferTuner::new(&config);
+        lan.on_rtt_sample(Duration::from_millis(1));
+        lan.on_delivered(0, t0);
+        assert_eq!(lan.on_delivered(1_250_000, t0 + RATE_WINDOW), Some(256 * 1024));
+
+        // 목표와 차이가 작으면 그대로
+        let mut steady = BufferTuner::new(&config);
+        steady.on_rtt_sample(Duration::from_millis(100));
+        steady.on_rate_estimate(11.0 * 1024.0 * 1024.0);
+        assert_eq!(steady.target(), 2_306_867);
+        assert_eq!(steady.retune(), None);
+        assert_eq!(steady.current(), 2 * 1024 * 1024);
+    }
+}
@@ -0,0 +1,226 @@
+//! 준비된 세그먼트 캐시 (`Config::segment_cache_bytes`, `Sender::preload`)
+//!
+//! 고정된 데이터셋을 여러 연결에 반복해 보내는 서버는 연결마다 같은 세그먼트를 다시 분할하고
+//! 직렬화함. 데이터셋 오프셋별로 `PreparedSegment`를 보관해 다음 연결은 캐시에서 바로 보냄
+//!
+//! - 항목 비용 = 세그먼트 데이터 + 직렬화된 데이터그램 바이트, 합이 용량을 넘지 않음
+//! - 조회는 오프셋과 내용이 모두 같을 때만 적중 (데이터셋이 바뀌면 자연히 빗나감)
+//! - 가득 차면 가장 오래 쓰지 않은 항목부터 버림 (LRU). `insert_if_room`은 버리지 않음
+//! - 세션별 송신자들이 `Sender::with_segment_cache`로 하나를 공유 가능
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::chunk::PreparedSegment;
+use crate::Config;
+
+#[derive(Debug)]
+struct Entry {
+    prepared: Arc<PreparedSegment>,
+    cost: usize,
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheInner {
+    entries: HashMap<u64, Entry>,
+    /// 마지막 사용 순번 → 오프셋 (가장 작은 것이 가장 오래 안 쓴 항목)
+    recency: BTreeMap<u64, u64>,
+    bytes: usize,
+    clock: u64,
+}
+
+impl CacheInner {
+    fn touch(&mut self, offset: u64) {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(entry) = self.entries.get_mut(&offset) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = clock;
+            self.recency.insert(clock, offset);
+        }
+    }
+
+    fn remove(&mut self, offset: u64) {
+        if let Some(entry) = self.entries.remove(&offset) {
+            self.recency.remove(&entry.last_used);
+            self.bytes -= entry.cost;
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        match self.recency.first_key_value() {
+            Some((_, &offset)) => {
+                self.remove(offset);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// 데이터셋 오프셋별 준비된 세그먼트 LRU 캐시
+#[derive(Debug)]
+pub struct SegmentCache {
+    capacity: usize,
+    inner: Mutex<CacheInner>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SegmentCache {
+    /// 용량 `capacity` bytes로 생성
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            capacity,
+            inner: Mutex::new(CacheInner::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// `Config::segment_cache_bytes` 기반 생성 (0이면 없음)
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        (config.segment_cache_bytes > 0).then(|| Self::new(config.segment_cache_bytes))
+    }
+
+    /// 세그먼트 하나가 차지하는 용량
+    pub fn cost(prepared: &PreparedSegment) -> usize {
+        prepared.segment_size() + prepared.encoded_len()
+    }
+
+    /// `offset`에서 시작하는 세그먼트 조회. `rest`는 데이터셋의 `offset` 이후 부분이고
+    /// 캐시된 세그먼트 내용이 그 앞부분과 같을 때만 반환
+    pub fn get(&self, offset: u64, rest: &[u8]) -> Option<Arc<PreparedSegment>> {
+        let mut inner = self.inner.lock();
+        let found = inner
+            .entries
+            .get(&offset)
+            .filter(|entry| entry.prepared.matches(rest))
+            .map(|entry| entry.prepared.clone());
+        match &found {
+            Some(_) => {
+                inner.touch(offset);
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        found
+    }
+
+    /// 오래된 항목을 버려서라도 넣음. 용량보다 큰 세그먼트면 `false`
+    pub fn insert(&self, offset: u64, prepared: Arc<PreparedSegment>) -> bool {
+        self.insert_with(offset, prepared, true)
+    }
+
+    /// 남은 용량에 들어갈 때만 넣음 (미리 채우기용, 기존 항목은 버리지 않음)
+    pub fn insert_if_room(&self, offset: u64, prepared: Arc<PreparedSegment>) -> bool {
+        self.insert_with(offset, prepared, false)
+    }
+
+    fn insert_with(&self, offset: u64, prepared: Arc<PreparedSegment>, evict: bool) -> bool {
+        let cost = Self::cost(&prepared);
+        if cost > self.capacity {
+            return false;
+        }
+        let mut inner = self.inner.lock();
+        inner.remove(offset);
+        while inner.bytes + cost > self.capacity {
+            if !evict || !inner.evict_oldest() {
+                return false;
+            }
+        }
+        inner.clock += 1;
+        let last_used = inner.clock;
+        inner.recency.insert(last_used, offset);
+        inner.entries.insert(
+            offset,
+            Entry {
+                prepared,
+                cost,
+                last_used,
+            },
+        );
+        inner.bytes += cost;
+        true
+    }
+
+    /// 용량 (bytes)
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// 사용 중인 용량 (bytes)
+    pub fn bytes(&self) -> usize {
+        self.inner.lock().bytes
+    }
+
+    /// 캐시된 세그먼트 수
+    pub fn len(&self) -> usize {
+        self.inner.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 적중 횟수
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// 빗나간 횟수 (그때마다 세그먼트를 새로 분할)
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// 모든 항목 제거
+    pub fn clear(&self) {
+        *self.inner.lock() = CacheInner::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    use crate::chunk::SegmentBuilder;
+
+    fn prepared(data: &[u8]) -> Arc<PreparedSegment> {
+        let chunks = SegmentBuilder::new(100).split_into_chunks(0, data, 0);
+        Arc::new(PreparedSegment::new(chunks, Vec::new()))
+    }
+
+    #[test]
+    fn test_lru_eviction_within_capacity() {
+        let a = prepared(&[1u8; 250]);
+        let cost = SegmentCache::cost(&a);
+        let cache = SegmentCache::new(cost * 2);
+        let dataset: Bytes = [[1u8; 250], [2u8; 250], [3u8; 250]].concat().into();
+
+        assert!(cache.insert_if_room(0, a));
+        assert!(cache.insert_if_room(250, prepared(&dataset[250..500])));
+        // 미리 채우기는 기존 항목을 버리지 않음
+        assert!(!cache.insert_if_room(500, prepared(&dataset[500..])));
+        assert_eq!(cache.bytes(), cost * 2);
+
+        // 0을 최근에 썼으므로 250이 밀려남
+        assert!(cache.get(0, &dataset).is_some());
+        assert!(cache.insert(500, prepared(&dataset[500..])));
+        assert!(cache.get(250, &dataset[250..]).is_none());
+        assert!(cache.get(0, &dataset).is_some());
+        assert!(cache.get(500, &dataset[500..]).is_some());
+
+        // 내용이 다르면 빗나감
+        assert!(cache.get(0, &[9u8; 250]).is_none());
+        assert_eq!((cache.hits(), cache.misses()), (3, 2));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.insert(750, prepared(&[0u8; 1000])));
+    }
+}
@@ -3,15 +3,25 @@
 //! - Segment: 큰 논리 블록 (64KB ~ 128KB)
 //! - Chunk: UDP 패킷 크기의 퍼즐 조각 (1100 ~ 1300 bytes)
 
-use bytes::{Bytes, BytesMut};
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use bytes::BytesMut;
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
+use crate::codec::{Reader, Writer};
+use crate::{CHUNK_BATCH_MAGIC, CHUNK_MAGIC};
+
 /// 세그먼트 ID (64비트)
 pub type SegmentId = u64;
 
 /// 청크 ID (32비트, 세그먼트 내 인덱스)
 pub type ChunkId = u32;
 
+/// 파일 ID (다중 파일 세션에서 세그먼트가 속한 파일, 0은 매니페스트/단일 스트림)
+pub type FileId = u32;
+
 /// 청크 헤더
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkHeader {
@@ -44,6 +54,78 @@ pub struct ChunkHeader {
 
     /// 타임스탬프 (마이크로초)
     pub timestamp_us: u64,
+
+    /// 파일 ID
+    pub file_id: FileId,
+
+    /// 세그먼트 끝이 애플리케이션 메시지 경계 (`Sender::send_message`)
+    pub boundary: bool,
+
+    /// 세그먼트가 스트림의 마지막 (`Sender::send_end_of_stream`, 항상 `boundary`와 함께)
+    pub end_of_stream: bool,
+
+    /// FEC 패리티 청크면 세그먼트의 패리티 샤드 수 (원본/중복 청크는 0)
+    pub parity_shards: u8,
+}
+
+impl ChunkHeader {
+    /// 인코딩된 헤더 크기 (바이트)
+    pub const ENCODED_LEN: usize = 47;
+
+    fn encode(&self, w: &mut Writer) {
+        w.put_u64(self.segment_id);
+        w.put_u32(self.chunk_id);
+        w.put_u32(self.total_chunks);
+        w.put_u32(self.offset);
+        w.put_u16(self.data_len);
+        w.put_u32(self.segment_size);
+        w.put_u8(self.nic_id);
+        w.put_bool(self.is_redundant);
+        w.put_u32(self.crc32);
+        w.put_u64(self.timestamp_us);
+        w.put_u32(self.file_id);
+        w.put_bool(self.boundary);
+        w.put_bool(self.end_of_stream);
+        w.put_u8(self.parity_shards);
+    }
+
+    fn decode(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Self {
+            segment_id: r.get_u64()?,
+            chunk_id: r.get_u32()?,
+            total_chunks: r.get_u32()?,
+            offset: r.get_u32()?,
+            data_len: r.get_u16()?,
+            segment_size: r.get_u32()?,
+            nic_id: r.get_u8()?,
+            is_redundant: r.get_bool()?,
+            crc32: r.get_u32()?,
+            timestamp_us: r.get_u64()?,
+            // file_id 이전의 40바이트 헤더는 단일 스트림
+            file_id: r.get_u32().unwrap_or(0),
+            // boundary 이전의 44바이트 헤더는 메시지 경계 없음
+            boundary: r.get_bool().unwrap_or(false),
+            // end_of_stream 이전의 45바이트 헤더는 스트림 끝 표시 없음
+            end_of_stream: r.get_bool().unwrap_or(false),
+            // parity_shards 이전의 46바이트 헤더는 패리티 아님
+            parity_shards: r.get_u8().unwrap_or(0),
+        })
+    }
+}
+
+/// 현재 시각 (마이크로초, no_std에서는 0)
+fn now_us() -> u64 {
+    #[cfg(feature = "std")]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        0
+    }
 }
 
 /// 청크 (송신 패킷 단위)
@@ -58,6 +140,7 @@ pub struct Chunk {
 
 impl Chunk {
     /// 새 청크 생성
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         segment_id: SegmentId,
         chunk_id: ChunkId,
@@ -69,10 +152,7 @@ impl Chunk {
         is_redundant: bool,
     ) -> Self {
         let crc32 = crc32fast::hash(&data);
-        let timestamp_us = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_micros() as u64;
+        let timestamp_us = now_us();
 
         Self {
             header: ChunkHeader {
@@ -86,36 +166,88 @@ impl Chunk {
                 is_redundant,
                 crc32,
                 timestamp_us,
+                file_id: 0,
+                boundary: false,
+                end_of_stream: false,
+                parity_shards: 0,
             },
             data,
         }
     }
 
+    /// 청크 데이터그램 프리픽스 크기 (매직 넘버 + 헤더 길이)
+    pub const PREFIX_LEN: usize = 6;
+
     /// 청크를 바이트로 직렬화
+    ///
+    /// `[CHUNK_MAGIC u32][헤더 길이 u16][헤더][데이터]`
     pub fn to_bytes(&self) -> Vec<u8> {
-        let header_bytes = bincode::serialize(&self.header).unwrap_or_default();
-        let header_len = header_bytes.len() as u16;
+        let mut w =
+            Writer::with_capacity(Self::PREFIX_LEN + ChunkHeader::ENCODED_LEN + self.data.len());
+        w.put_u32(CHUNK_MAGIC);
+        w.put_u16(ChunkHeader::ENCODED_LEN as u16);
+        self.header.encode(&mut w);
+        w.put_slice(&self.data);
+        w.into_inner()
+    }
+
+    /// `datagram_len`까지 0 바이트로 채워 직렬화 (이미 그보다 길면 패딩 없음)
+    ///
+    /// 헤더의 `data_len`이 원래 데이터 길이를 기록하므로 `from_bytes`가 패딩을 제거
+    pub fn to_padded_bytes(&self, datagram_len: usize) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        if bytes.len() < datagram_len {
+            bytes.resize(datagram_len, 0);
+        }
+        bytes
+    }
+
+    /// 재전송용 직렬화 (`to_padded_bytes`와 같되 타임스탬프를 지금으로 바꿈)
+    ///
+    /// 처음 보낸 시각을 그대로 두면 수신자가 재는 단방향 지연에 재전송 대기 시간이 더해져
+    /// 경로가 바뀐 것처럼 보임
+    pub fn to_retransmit_bytes(&self, datagram_len: usize) -> Vec<u8> {
+        let mut bytes = self.to_padded_bytes(datagram_len);
+        bytes[TIMESTAMP_POS..TIMESTAMP_POS + 8].copy_from_slice(&now_us().to_le_bytes());
+        bytes
+    }
 
-        let mut buf = Vec::with_capacity(2 + header_bytes.len() + self.data.len());
-        buf.extend_from_slice(&header_len.to_le_bytes());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&self.data);
-        buf
+    /// 청크 매직 넘버로 시작하는 데이터그램인지 확인 (파싱 없이)
+    pub fn has_magic(bytes: &[u8]) -> bool {
+        bytes.len() >= 4 && bytes[..4] == CHUNK_MAGIC.to_le_bytes()
+    }
+
+    /// 청크 데이터그램의 세그먼트 ID만 읽기 (전체 파싱 없이, 워커 분배용)
+    pub fn peek_segment_id(bytes: &[u8]) -> Option<SegmentId> {
+        if !Self::has_magic(bytes) {
+            return None;
+        }
+        let id = bytes.get(Self::PREFIX_LEN..Self::PREFIX_LEN + 8)?;
+        Some(u64::from_le_bytes(id.try_into().ok()?))
     }
 
     /// 바이트에서 청크 역직렬화
+    ///
+    /// 청크 매직 넘버가 없으면 (다른 송신자의 패킷, 컨트롤 메시지 등) 거부.
+    /// 데이터 뒤의 바이트는 패딩으로 보고 버리되, 0이 아닌 바이트가 있으면 거부
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() < 2 {
+        if bytes.len() < Self::PREFIX_LEN || !Self::has_magic(bytes) {
             return None;
         }
 
-        let header_len = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
-        if bytes.len() < 2 + header_len {
+        let header_len = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+        let body = Self::PREFIX_LEN + header_len;
+        if bytes.len() < body {
             return None;
         }
 
-        let header: ChunkHeader = bincode::deserialize(&bytes[2..2 + header_len]).ok()?;
-        let data = Bytes::copy_from_slice(&bytes[2 + header_len..]);
+        let header = ChunkHeader::decode(&mut Reader::new(&bytes[Self::PREFIX_LEN..body]))?;
+        let payload = &bytes[body..];
+        let data_len = header.data_len as usize;
+        if payload.len() < data_len || payload[data_len..].iter().any(|&b| b != 0) {
+            return None;
+        }
+        let data = Bytes::copy_from_slice(&payload[..data_len]);
 
         Some(Self { header, data })
     }
@@ -126,7 +258,91 @@ impl Chunk {
     }
 }
 
+/// 여러 청크 데이터그램을 묶은 데이터그램 (`Config::coalesce_chunks`)
+///
+/// `[CHUNK_BATCH_MAGIC u32][레코드 수 u16]` 뒤에 `[길이 u16][청크 데이터그램]` 반복.
+/// 작은 세그먼트의 청크마다 드는 UDP/IP 헤더와 패킷 처리 비용을 나눠 냄
+pub struct ChunkBatch;
+
+impl ChunkBatch {
+    /// 묶음 프리픽스 크기 (매직 넘버 + 레코드 수)
+    pub const PREFIX_LEN: usize = 6;
+
+    /// 레코드마다 붙는 길이 크기
+    pub const RECORD_PREFIX_LEN: usize = 2;
+
+    /// 레코드들을 묶었을 때의 데이터그램 크기
+    pub fn encoded_len<'a>(records: impl IntoIterator<Item = &'a [u8]>) -> usize {
+        Self::PREFIX_LEN
+            + records
+                .into_iter()
+                .map(|record| Self::RECORD_PREFIX_LEN + record.len())
+                .sum::<usize>()
+    }
+
+    /// 청크 데이터그램(`Chunk::to_bytes`)들을 한 데이터그램으로 묶음
+    pub fn encode<'a>(records: impl IntoIterator<Item = &'a [u8]> + Clone) -> Vec<u8> {
+        let mut w = Writer::with_capacity(Self::encoded_len(records.clone()));
+        w.put_u32(CHUNK_BATCH_MAGIC);
+        let count_at = w.len();
+        w.put_u16(0);
+        let mut count = 0u16;
+        for record in records {
+            w.put_u16(record.len() as u16);
+            w.put_slice(record);
+            count += 1;
+        }
+        let mut bytes = w.into_inner();
+        bytes[count_at..count_at + 2].copy_from_slice(&count.to_le_bytes());
+        bytes
+    }
+
+    /// 묶음 매직 넘버로 시작하는 데이터그램인지 확인 (파싱 없이)
+    pub fn has_magic(bytes: &[u8]) -> bool {
+        bytes.len() >= 4 && bytes[..4] == CHUNK_BATCH_MAGIC.to_le_bytes()
+    }
+
+    /// 레코드(청크 데이터그램)로 나눔. 길이가 맞지 않거나 뒤에 남는 바이트가 있으면 거부
+    pub fn records(bytes: &[u8]) -> Option<Vec<&[u8]>> {
+        if bytes.len() < Self::PREFIX_LEN || !Self::has_magic(bytes) {
+            return None;
+        }
+        let count = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+        let mut rest = &bytes[Self::PREFIX_LEN..];
+        // 레코드마다 길이 필드가 있으므로 데이터그램에 들어갈 수 없는 레코드 수는 할당 전에 거부
+        if count > rest.len() / Self::RECORD_PREFIX_LEN {
+            return None;
+        }
+        let mut records = Vec::with_capacity(count);
+        for _ in 0..count {
+            let (len, tail) = rest.split_first_chunk::<{ Self::RECORD_PREFIX_LEN }>()?;
+            let len = u16::from_le_bytes(*len) as usize;
+            if tail.len() < len {
+                return None;
+            }
+            let (record, tail) = tail.split_at(len);
+            records.push(record);
+            rest = tail;
+        }
+        rest.is_empty().then_some(records)
+    }
+
+    /// 묶음 안의 청크 역직렬화 (하나라도 청크가 아니면 전체 거부)
+    pub fn decode(bytes: &[u8]) -> Option<Vec<Chunk>> {
+        Self::records(bytes)?.into_iter().map(Chunk::from_bytes).collect()
+    }
+}
+
+/// 모든 바이트가 같으면 그 값 (빈 데이터는 None)
+///
+/// 0으로 채운 구멍이나 같은 값이 반복되는 구간을 `SparseSegmentMessage`로 대신 보낼지 판단
+pub fn uniform_fill(data: &[u8]) -> Option<u8> {
+    let (&first, rest) = data.split_first()?;
+    rest.iter().all(|&b| b == first).then_some(first)
+}
+
 /// 세그먼트 (큰 논리 블록)
+#[cfg(feature = "std")]
 #[derive(Debug)]
 pub struct Segment {
     /// 세그먼트 ID
@@ -147,10 +363,14 @@ pub struct Segment {
     /// 수신된 청크 수
     pub received_count: u32,
 
+    /// 수신한 FEC 패리티 샤드 (인덱스 = 패리티 순번, 길이 = 세그먼트의 패리티 샤드 수)
+    parity: Vec<Option<Bytes>>,
+
     /// 생성 시간
     pub created_at: std::time::Instant,
 }
 
+#[cfg(feature = "std")]
 impl Segment {
     /// 새 세그먼트 생성 (수신측)
     pub fn new_for_receive(id: SegmentId, total_size: usize, total_chunks: u32) -> Self {
@@ -164,13 +384,42 @@ impl Segment {
             received_chunks: vec![false; total_chunks as usize],
             total_chunks,
             received_count: 0,
+            parity: Vec::new(),
+            created_at: std::time::Instant::now(),
+        }
+    }
+
+    /// 버퍼 풀에서 수신 버퍼를 받아 세그먼트 생성 (수신측)
+    pub fn new_for_receive_pooled(
+        id: SegmentId,
+        total_size: usize,
+        total_chunks: u32,
+        pool: &crate::pool::SegmentBufferPool,
+    ) -> Self {
+        Self {
+            id,
+            data: pool.acquire(total_size),
+            total_size,
+            received_chunks: vec![false; total_chunks as usize],
+            total_chunks,
+            received_count: 0,
+            parity: Vec::new(),
             created_at: std::time::Instant::now(),
         }
     }
 
-    /// 청크 삽입
+    /// 청크가 세그먼트의 총 청크 수/크기와 다른 분할 기준을 말하는지 (프로토콜 버그나 위조)
+    pub fn contradicts(&self, chunk: &Chunk) -> bool {
+        chunk.header.total_chunks != self.total_chunks
+            || chunk.header.segment_size as usize != self.total_size
+    }
+
+    /// 청크 삽입. 세그먼트와 분할 기준이 다른 청크(`contradicts`)는 넣지 않음
     pub fn insert_chunk(&mut self, chunk: &Chunk) -> bool {
         let chunk_id = chunk.header.chunk_id as usize;
+        if self.contradicts(chunk) {
+            return false;
+        }
 
         // 이미 받은 청크면 무시
         if chunk_id >= self.received_chunks.len() || self.received_chunks[chunk_id] {
@@ -194,6 +443,65 @@ impl Segment {
         true
     }
 
+    /// 패리티 청크 저장 (샤드 수는 헤더의 `parity_shards`). 처음 받은 샤드면 true
+    ///
+    /// 샤드 길이는 세그먼트 분할과 맞아야 함: `(total_chunks - 1) * len < total_size <= total_chunks * len`
+    pub fn insert_parity(&mut self, chunk: &Chunk) -> bool {
+        let Some(index) = chunk.header.chunk_id.checked_sub(self.total_chunks) else {
+            return false;
+        };
+        let (index, count) = (index as usize, chunk.header.parity_shards as usize);
+        if index >= count || self.total_chunks as usize + count > crate::fec::MAX_SHARDS || !chunk.verify_crc() {
+            return false;
+        }
+        let (len, shards) = (chunk.data.len(), self.total_chunks as usize);
+        if (shards - 1).saturating_mul(len) >= self.total_size || shards.saturating_mul(len) < self.total_size {
+            return false;
+        }
+        if self.parity.len() != count {
+            self.parity = vec![None; count];
+        }
+        self.parity[index].replace(chunk.data.clone()).is_none()
+    }
+
+    /// 받은 패리티 샤드 수
+    pub fn parity_received(&self) -> usize {
+        self.parity.iter().flatten().count()
+    }
+
+    /// 받지 못한 패리티 청크 ID (패리티를 하나도 받지 못해 샤드 수를 모르면 None)
+    pub fn missing_parity(&self) -> Option<Vec<ChunkId>> {
+        if self.parity.is_empty() {
+            return None;
+        }
+        let missing = self.parity.iter().enumerate().filter(|(_, shard)| shard.is_none());
+        Some(missing.map(|(index, _)| self.total_chunks + index as ChunkId).collect())
+    }
+
+    /// 완료 여부. 미완료면 받은 원본 + 패리티로 누락 청크 복원을 먼저 시도
+    pub fn try_complete(&mut self) -> bool {
+        if !self.is_complete() && !self.parity.is_empty() {
+            let parity = core::mem::take(&mut self.parity);
+            crate::fec::reconstruct(self, &parity);
+            self.parity = parity;
+        }
+        self.is_complete()
+    }
+
+    /// 받은 패리티로도 복원할 수 없는 누락 청크 수 (NACK으로 채워야 할 양)
+    pub fn fec_deficit(&self) -> usize {
+        let missing = self.total_chunks.saturating_sub(self.received_count) as usize;
+        missing.saturating_sub(self.parity_received())
+    }
+
+    /// 해당 청크 수신 여부
+    pub fn has_chunk(&self, chunk_id: ChunkId) -> bool {
+        self.received_chunks
+            .get(chunk_id as usize)
+            .copied()
+            .unwrap_or(false)
+    }
+
     /// 완료 여부 확인
     pub fn is_complete(&self) -> bool {
         self.received_count >= self.total_chunks
@@ -201,12 +509,22 @@ impl Segment {
 
     /// 누락된 청크 ID 목록 반환
     pub fn missing_chunk_ids(&self) -> Vec<ChunkId> {
-        self.received_chunks
-            .iter()
-            .enumerate()
-            .filter(|(_, &received)| !received)
-            .map(|(id, _)| id as ChunkId)
-            .collect()
+        self.missing_ranges().flat_map(|(start, end)| start..end).collect()
+    }
+
+    /// 연속으로 누락된 청크 구간 `[start, end)` (수신 비트맵에서 바로 계산, ID 목록 할당 없음)
+    pub fn missing_ranges(&self) -> impl Iterator<Item = (ChunkId, ChunkId)> + '_ {
+        let received = &self.received_chunks;
+        let mut pos = 0;
+        core::iter::from_fn(move || {
+            let start = pos + received[pos..].iter().position(|&r| !r)?;
+            let end = received[start..]
+                .iter()
+                .position(|&r| r)
+                .map_or(received.len(), |len| start + len);
+            pos = end;
+            Some((start as ChunkId, end as ChunkId))
+        })
     }
 
     /// 수신률 계산
@@ -217,10 +535,150 @@ impl Segment {
         self.received_count as f64 / self.total_chunks as f64
     }
 
+    /// 조립 중인 데이터 (받지 않은 청크 자리는 0 또는 이전 내용)
+    pub fn data(&self) -> &[u8] {
+        &self.data[..self.total_size]
+    }
+
+    /// 받은 청크와 패리티를 모두 잊고 처음부터 다시 조립 (버퍼는 재사용)
+    pub fn reset(&mut self) {
+        self.received_chunks.fill(false);
+        self.received_count = 0;
+        self.parity.clear();
+    }
+
+    /// 세그먼트 전체를 `fill`로 채우고 모든 청크를 받은 것으로 표시 (균일 세그먼트 표시 수신)
+    pub fn fill_uniform(&mut self, fill: u8) {
+        self.data[..self.total_size].fill(fill);
+        self.received_chunks.fill(true);
+        self.received_count = self.total_chunks;
+        self.parity.clear();
+    }
+
     /// 완료된 데이터 추출
     pub fn into_data(self) -> Bytes {
         self.data.freeze()
     }
+
+    /// 미완료 세그먼트의 버퍼를 풀에 반납
+    pub fn recycle(self, pool: &crate::pool::SegmentBufferPool) {
+        pool.release(self.data);
+    }
+}
+
+/// 데이터그램 안의 세그먼트 ID 위치
+#[cfg(feature = "std")]
+const SEGMENT_ID_POS: usize = Chunk::PREFIX_LEN;
+
+/// 데이터그램 안의 타임스탬프 위치 (segment_id ~ crc32 다음)
+const TIMESTAMP_POS: usize = Chunk::PREFIX_LEN + 32;
+
+/// 여러 수신자에게 반복 전송하기 위해 한 번만 분할·직렬화한 세그먼트
+///
+/// 청크 분할, CRC, 중복/패리티 생성, 직렬화를 미리 끝내 두고
+/// 전송할 때는 세그먼트 ID와 타임스탬프만 데이터그램에 기록
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct PreparedSegment {
+    chunks: Vec<Chunk>,
+    redundant_chunks: Vec<Chunk>,
+    /// 직렬화된 데이터그램 (원본 청크 → 중복 청크 순)
+    datagrams: Vec<Bytes>,
+    segment_size: usize,
+}
+
+#[cfg(feature = "std")]
+impl PreparedSegment {
+    /// 분할된 원본/중복 청크로 생성 (청크의 세그먼트 ID와 타임스탬프는 전송 시 덮어씀)
+    pub fn new(chunks: Vec<Chunk>, redundant_chunks: Vec<Chunk>) -> Self {
+        Self::padded(chunks, redundant_chunks, 0)
+    }
+
+    /// 데이터그램을 `datagram_len`까지 패딩해 준비 (`Chunk::to_padded_bytes`)
+    pub fn padded(chunks: Vec<Chunk>, redundant_chunks: Vec<Chunk>, datagram_len: usize) -> Self {
+        let datagrams = chunks
+            .iter()
+            .chain(&redundant_chunks)
+            .map(|chunk| Bytes::from(chunk.to_padded_bytes(datagram_len)))
+            .collect();
+        let segment_size = chunks.first().map_or(0, |c| c.header.segment_size as usize);
+        Self {
+            chunks,
+            redundant_chunks,
+            datagrams,
+            segment_size,
+        }
+    }
+
+    /// 원본 청크
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// 중복(또는 패리티) 청크
+    pub fn redundant_chunks(&self) -> &[Chunk] {
+        &self.redundant_chunks
+    }
+
+    /// 세그먼트 데이터 크기
+    pub fn segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    /// 전송할 데이터그램 수 (원본 + 중복)
+    pub fn datagram_count(&self) -> usize {
+        self.datagrams.len()
+    }
+
+    /// 직렬화된 데이터그램 바이트 합
+    pub fn encoded_len(&self) -> usize {
+        self.datagrams.iter().map(Bytes::len).sum()
+    }
+
+    /// 세그먼트 데이터가 `data`의 앞 `segment_size` 바이트와 같은지
+    pub fn matches(&self, data: &[u8]) -> bool {
+        let Some(data) = data.get(..self.segment_size) else {
+            return false;
+        };
+        self.chunks.iter().all(|chunk| {
+            let start = chunk.header.offset as usize;
+            data.get(start..start + chunk.data.len()) == Some(&chunk.data[..])
+        })
+    }
+
+    /// `index`번째 데이터그램을 `buf`에 기록하고 세그먼트 ID/타임스탬프 지정
+    pub fn write_datagram(
+        &self,
+        index: usize,
+        segment_id: SegmentId,
+        timestamp_us: u64,
+        buf: &mut Vec<u8>,
+    ) {
+        buf.clear();
+        buf.extend_from_slice(&self.datagrams[index]);
+        buf[SEGMENT_ID_POS..SEGMENT_ID_POS + 8].copy_from_slice(&segment_id.to_le_bytes());
+        buf[TIMESTAMP_POS..TIMESTAMP_POS + 8].copy_from_slice(&timestamp_us.to_le_bytes());
+    }
+
+    /// 현재 시각으로 `index`번째 데이터그램 기록
+    pub fn write_datagram_now(&self, index: usize, segment_id: SegmentId, buf: &mut Vec<u8>) {
+        self.write_datagram(index, segment_id, now_us(), buf);
+    }
+
+    /// 재전송 캐시용 청크 (세그먼트 ID만 바꾼 사본, 데이터는 공유)
+    pub fn chunks_for(&self, segment_id: SegmentId) -> (Vec<Chunk>, Vec<Chunk>) {
+        let with_id = |chunks: &[Chunk]| -> Vec<Chunk> {
+            chunks
+                .iter()
+                .map(|chunk| {
+                    let mut chunk = chunk.clone();
+                    chunk.header.segment_id = segment_id;
+                    chunk
+                })
+                .collect()
+        };
+        (with_id(&self.chunks), with_id(&self.redundant_chunks))
+    }
 }
 
 /// 세그먼트 생성기 (송신측)
@@ -234,26 +692,42 @@ impl SegmentBuilder {
     }
 
     /// 데이터를 청크들로 분할
+    ///
+    /// 세그먼트 전체를 한 번만 복사한 뒤 `split_bytes_into_chunks`로 슬라이스
     pub fn split_into_chunks(
         &self,
         segment_id: SegmentId,
         data: &[u8],
         nic_id: u8,
     ) -> Vec<Chunk> {
-        let total_chunks = (data.len() + self.chunk_size - 1) / self.chunk_size;
+        self.split_bytes_into_chunks(segment_id, Bytes::copy_from_slice(data), nic_id)
+    }
+
+    /// `Bytes` 세그먼트를 복사 없이 청크들로 분할
+    ///
+    /// 각 청크 데이터는 원본 버퍼를 참조 카운트로 공유하는 `slice()`.
+    /// 빈 세그먼트도 길이 0 청크 하나로 보냄 (청크가 없으면 수신자는 세그먼트가 있는지 모르고
+    /// 송신자는 오지 않을 완료 보고를 기다림)
+    pub fn split_bytes_into_chunks(
+        &self,
+        segment_id: SegmentId,
+        data: Bytes,
+        nic_id: u8,
+    ) -> Vec<Chunk> {
+        let total_chunks = data.len().div_ceil(self.chunk_size).max(1);
         let segment_size = data.len() as u32;
 
-        data.chunks(self.chunk_size)
-            .enumerate()
-            .map(|(idx, chunk_data)| {
+        (0..total_chunks)
+            .map(|idx| {
                 let offset = idx * self.chunk_size;
+                let end = (offset + self.chunk_size).min(data.len());
                 Chunk::new(
                     segment_id,
                     idx as ChunkId,
                     total_chunks as u32,
                     offset as u32,
                     segment_size,
-                    Bytes::copy_from_slice(chunk_data),
+                    data.slice(offset..end),
                     nic_id,
                     false,
                 )
@@ -261,16 +735,68 @@ impl SegmentBuilder {
             .collect()
     }
 
+    /// 청크마다 경로를 골라 그 경로의 크기로 분할 (`next_path`: (NIC ID, 청크 데이터 크기))
+    ///
+    /// 청크 크기는 `chunk_size`를 넘지 않음. 수신측은 헤더의 `offset`/`data_len`으로 조립하므로
+    /// 한 세그먼트 안에 크기가 다른 청크가 섞여도 됨
+    pub fn split_bytes_for_paths(
+        &self,
+        segment_id: SegmentId,
+        data: Bytes,
+        mut next_path: impl FnMut() -> (u8, usize),
+    ) -> Vec<Chunk> {
+        let segment_size = data.len() as u32;
+        let mut chunks = Vec::new();
+        let mut offset = 0;
+        loop {
+            let (nic_id, size) = next_path();
+            let end = (offset + size.clamp(1, self.chunk_size.max(1))).min(data.len());
+            chunks.push(Chunk::new(
+                segment_id,
+                chunks.len() as ChunkId,
+                0,
+                offset as u32,
+                segment_size,
+                data.slice(offset..end),
+                nic_id,
+                false,
+            ));
+            offset = end;
+            if offset >= data.len() {
+                break;
+            }
+        }
+        let total_chunks = chunks.len() as u32;
+        for chunk in &mut chunks {
+            chunk.header.total_chunks = total_chunks;
+        }
+        chunks
+    }
+
+    /// 반복 전송용 세그먼트 준비 (중복 전송 모드)
+    #[cfg(feature = "std")]
+    pub fn prepare(&self, data: Bytes, nic_id: u8, redundancy_ratio: f64) -> PreparedSegment {
+        let chunks = self.split_bytes_into_chunks(0, data, nic_id);
+        let redundant_chunks = self.create_redundant_chunks(&chunks, redundancy_ratio);
+        PreparedSegment::new(chunks, redundant_chunks)
+    }
+
     /// 중복 청크 생성
+    #[cfg(feature = "std")]
     pub fn create_redundant_chunks(
         &self,
         chunks: &[Chunk],
         redundancy_ratio: f64,
     ) -> Vec<Chunk> {
         use rand::seq::SliceRandom;
-        let mut rng = rand::thread_rng();
 
         let redundant_count = (chunks.len() as f64 * redundancy_ratio).ceil() as usize;
+        if redundant_count == 0 {
+            // 난수/인덱스 할당 없이 종료
+            return Vec::new();
+        }
+
+        let mut rng = rand::thread_rng();
         let mut indices: Vec<usize> = (0..chunks.len()).collect();
         indices.shuffle(&mut rng);
 
@@ -294,10 +820,47 @@ impl SegmentBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Config, DEFAULT_CHUNK_SIZE, DEFAULT_SEGMENT_SIZE};
+
+    #[test]
+    fn test_chunk_batch_splits_into_records() {
+        let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+        let chunks: Vec<Chunk> = (1..=4u64)
+            .map(|id| builder.split_into_chunks(id, &[id as u8; 200], 0).remove(0))
+            .collect();
+        let records: Vec<Vec<u8>> = chunks.iter().map(Chunk::to_bytes).collect();
+
+        let batch = ChunkBatch::encode(records.iter().map(Vec::as_slice));
+        assert_eq!(batch.len(), ChunkBatch::encoded_len(records.iter().map(Vec::as_slice)));
+        assert!(batch.len() < 1472);
+        assert!(ChunkBatch::has_magic(&batch) && !Chunk::has_magic(&batch));
+        assert!(Chunk::from_bytes(&batch).is_none());
+
+        let decoded = ChunkBatch::decode(&batch).unwrap();
+        assert_eq!(decoded.len(), 4);
+        for (chunk, original) in decoded.iter().zip(&chunks) {
+            assert_eq!(chunk.header.segment_id, original.header.segment_id);
+            assert_eq!(chunk.data, original.data);
+            assert!(chunk.verify_crc());
+        }
+
+        // 잘린 묶음, 남는 바이트, 청크가 아닌 레코드는 거부
+        assert!(ChunkBatch::records(&batch[..batch.len() - 1]).is_none());
+        let mut trailing = batch.clone();
+        trailing.push(0);
+        assert!(ChunkBatch::records(&trailing).is_none());
+        assert!(ChunkBatch::decode(&ChunkBatch::encode([&b"junk"[..]])).is_none());
+
+        // 데이터그램 길이로 담을 수 없는 레코드 수
+        let mut inflated = batch[..ChunkBatch::PREFIX_LEN].to_vec();
+        inflated[4..6].copy_from_slice(&u16::MAX.to_le_bytes());
+        inflated.extend_from_slice(&[0, 0]);
+        assert!(ChunkBatch::records(&inflated).is_none());
+    }
 
     #[test]
     fn test_chunk_serialization() {
-        let chunk = Chunk::new(
+        let mut chunk = Chunk::new(
             1,
             0,
             10,
@@ -307,15 +870,109 @@ mod tests {
             0,
             false,
         );
-
+        chunk.header.boundary = true;
         let bytes = chunk.to_bytes();
         let restored = Chunk::from_bytes(&bytes).unwrap();
 
         assert_eq!(chunk.header.segment_id, restored.header.segment_id);
         assert_eq!(chunk.header.chunk_id, restored.header.chunk_id);
+        assert!(restored.header.boundary);
+        assert_eq!(chunk.data, restored.data);
+
+        // 경계 필드가 없는 44바이트 헤더, 스트림 끝 필드가 없는 45바이트 헤더
+        let header_end = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        let legacy = |trim: usize| {
+            let mut legacy = bytes[..header_end - trim].to_vec();
+            legacy[4..6].copy_from_slice(&((ChunkHeader::ENCODED_LEN - trim) as u16).to_le_bytes());
+            legacy.extend_from_slice(&bytes[header_end..]);
+            Chunk::from_bytes(&legacy).unwrap()
+        };
+        let restored = legacy(3);
+        assert!(!restored.header.boundary);
+        assert_eq!(chunk.data, restored.data);
+        let restored = legacy(2);
+        assert!(restored.header.boundary);
+        assert!(!restored.header.end_of_stream);
         assert_eq!(chunk.data, restored.data);
     }
 
+    #[test]
+    fn test_header_codec_matches_bincode() {
+        let chunk = Chunk::new(
+            7,
+            3,
+            55,
+            3600,
+            65536,
+            Bytes::from(vec![9u8; 32]),
+            1,
+            true,
+        );
+
+        let bytes = chunk.to_bytes();
+        let header_bytes = bincode::serialize(&chunk.header).unwrap();
+
+        assert_eq!(header_bytes.len(), ChunkHeader::ENCODED_LEN);
+        let prefix = Chunk::PREFIX_LEN;
+        assert_eq!(&bytes[prefix..prefix + ChunkHeader::ENCODED_LEN], header_bytes.as_slice());
+    }
+
+    #[test]
+    fn test_retransmit_bytes_restamped() {
+        let mut chunk = Chunk::new(7, 3, 55, 3600, 65536, Bytes::from(vec![1, 2, 3]), 1, true);
+        chunk.header.timestamp_us = 1;
+        let decoded = Chunk::from_bytes(&chunk.to_retransmit_bytes(0)).unwrap();
+        assert!(decoded.header.timestamp_us > 1);
+        assert_eq!(decoded.data, chunk.data);
+        assert!(decoded.verify_crc());
+    }
+
+    #[test]
+    fn test_chunk_golden_bytes() {
+        let golden = crate::message::tests::hex(
+            "43504653 2f00
+             0700000000000000 03000000 37000000 100e0000 0500 00000100 01 01
+             f4990b47 0807060504030201 09000000 01 01 00
+             0102030405",
+        );
+        let data = Bytes::from(vec![1, 2, 3, 4, 5]);
+        let mut chunk = Chunk::new(7, 3, 55, 3600, 65536, data, 1, true);
+        chunk.header.timestamp_us = 0x0102_0304_0506_0708;
+        chunk.header.file_id = 9;
+        chunk.header.boundary = true;
+        chunk.header.end_of_stream = true;
+        assert_eq!(chunk.to_bytes(), golden);
+        assert_eq!(Chunk::peek_segment_id(&golden), Some(7));
+
+        let decoded = Chunk::from_bytes(&golden).unwrap();
+        assert_eq!(decoded.header.offset, 3600);
+        assert_eq!(decoded.header.segment_size, 65536);
+        assert_eq!(decoded.header.crc32, 0x470B_99F4);
+        assert!(decoded.verify_crc());
+
+        let batch = crate::message::tests::hex("42504653 0200 0300 616263 0100 7a");
+        assert_eq!(ChunkBatch::encode([&b"abc"[..], &b"z"[..]]), batch);
+        assert_eq!(ChunkBatch::records(&batch).unwrap(), vec![&b"abc"[..], &b"z"[..]]);
+    }
+
+    #[test]
+    fn test_foreign_packet_rejected() {
+        let chunk = Chunk::new(1, 0, 1, 0, 5, Bytes::from(vec![1, 2, 3, 4, 5]), 0, false);
+        let bytes = chunk.to_bytes();
+        assert!(Chunk::from_bytes(&bytes).is_some());
+
+        // 매직 넘버 없는 (이전 형식 / 다른 송신자) 청크
+        assert!(Chunk::from_bytes(&bytes[4..]).is_none());
+
+        // 컨트롤 메시지는 청크로 파싱되지 않음
+        let nack = crate::NackMessage::new(1, vec![0], 0.5, 0);
+        assert!(Chunk::from_bytes(&nack.to_bytes()).is_none());
+
+        // 매직만 맞고 잘린 패킷
+        assert!(Chunk::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(Chunk::from_bytes(&CHUNK_MAGIC.to_le_bytes()).is_none());
+    }
+
     #[test]
     fn test_segment_assembly() {
         let builder = SegmentBuilder::new(100);
@@ -333,4 +990,171 @@ mod tests {
         assert!(segment.is_complete());
         assert_eq!(segment.into_data().as_ref(), &data);
     }
+
+    #[test]
+    fn test_boundary_sizes_split_and_assemble() {
+        let builder = SegmentBuilder::new(100);
+        // (길이, 청크 수, 마지막 청크 길이)
+        for (len, count, last) in [(0, 1, 0), (1, 1, 1), (100, 1, 100), (101, 2, 1)] {
+            let data: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let chunks = builder.split_into_chunks(1, &data, 0);
+            assert_eq!(chunks.len(), count, "len {}", len);
+            assert_eq!(chunks.last().unwrap().data.len(), last, "len {}", len);
+
+            let mut segment = Segment::new_for_receive(1, len, count as u32);
+            for chunk in &chunks {
+                let decoded = Chunk::from_bytes(&chunk.to_bytes()).unwrap();
+                assert!(segment.insert_chunk(&decoded), "len {}", len);
+            }
+            assert!(segment.is_complete());
+            assert_eq!(segment.into_data().as_ref(), &data[..]);
+        }
+    }
+
+    #[test]
+    fn test_missing_ranges_match_missing_ids() {
+        let builder = SegmentBuilder::new(10);
+        let chunks = builder.split_into_chunks(1, &[0u8; 1000], 0);
+
+        // 10..50 연속 손실 + 흩어진 손실 + 마지막 청크 손실
+        let lost = |id: ChunkId| (10..50).contains(&id) || id % 17 == 5 || id == 99;
+        let mut segment = Segment::new_for_receive(1, 1000, chunks.len() as u32);
+        for chunk in chunks.iter().filter(|c| !lost(c.header.chunk_id)) {
+            segment.insert_chunk(chunk);
+        }
+
+        let ranges: Vec<_> = segment.missing_ranges().collect();
+        let expected: Vec<ChunkId> = (0..100).filter(|&id| lost(id)).collect();
+        let flattened: Vec<ChunkId> = ranges.iter().flat_map(|&(s, e)| s..e).collect();
+        assert_eq!(flattened, expected);
+        assert_eq!(segment.missing_chunk_ids(), expected);
+        assert!(ranges.contains(&(10, 50)));
+        assert_eq!(ranges.last(), Some(&(99, 100)));
+        assert!(ranges.windows(2).all(|w| w[0].1 < w[1].0));
+
+        for chunk in &chunks {
+            segment.insert_chunk(chunk);
+        }
+        assert_eq!(segment.missing_ranges().next(), None);
+        assert!(Segment::new_for_receive(2, 0, 0).missing_ranges().next().is_none());
+    }
+
+    #[test]
+    fn test_uniform_segment_fill() {
+        assert_eq!(uniform_fill(&[0u8; 4096]), Some(0));
+        assert_eq!(uniform_fill(&[0xFF; 3]), Some(0xFF));
+        assert_eq!(uniform_fill(&[0, 0, 1]), None);
+        assert_eq!(uniform_fill(&[]), None);
+
+        // 일부 청크를 받은 뒤 표시가 와도 전체가 채워지고 완료
+        let chunks = SegmentBuilder::new(10).split_into_chunks(1, &[7u8; 95], 0);
+        let mut segment = Segment::new_for_receive(1, 95, chunks.len() as u32);
+        segment.insert_chunk(&chunks[3]);
+        segment.fill_uniform(7);
+        assert!(segment.is_complete());
+        assert!(segment.missing_ranges().next().is_none());
+        assert_eq!(segment.data(), &[7u8; 95][..]);
+    }
+
+    #[test]
+    fn test_prepared_segment_stamps_id_and_time() {
+        let builder = SegmentBuilder::new(100);
+        let data: Vec<u8> = (0..250).map(|i| i as u8).collect();
+        let prepared = builder.prepare(Bytes::from(data.clone()), 0, 0.5);
+        assert_eq!(prepared.chunks().len(), 3);
+        assert_eq!(prepared.datagram_count(), 3 + 2);
+
+        // 두 수신자에게 서로 다른 세그먼트 ID로 전송해도 재분할 없이 동일한 청크
+        let mut buf = Vec::new();
+        for (segment_id, timestamp_us) in [(7u64, 1_000u64), (42, 2_000)] {
+            let mut segment = Segment::new_for_receive(segment_id, data.len(), 3);
+            for index in 0..prepared.datagram_count() {
+                prepared.write_datagram(index, segment_id, timestamp_us, &mut buf);
+                let chunk = Chunk::from_bytes(&buf).unwrap();
+                assert_eq!(chunk.header.segment_id, segment_id);
+                assert_eq!(chunk.header.timestamp_us, timestamp_us);
+                assert!(chunk.verify_crc());
+                segment.insert_chunk(&chunk);
+            }
+            assert!(segment.is_complete());
+            assert_eq!(segment.into_data().as_ref(), data.as_slice());
+        }
+
+        let (chunks, redundant) = prepared.chunks_for(9);
+        assert!(chunks.iter().chain(&redundant).all(|c| c.header.segment_id == 9));
+    }
+
+    #[test]
+    fn test_sliced_chunks_share_source() {
+        let builder = SegmentBuilder::new(DEFAULT_CHUNK_SIZE);
+        let data: Vec<u8> = (0..DEFAULT_SEGMENT_SIZE).map(|i| (i * 7) as u8).collect();
+        let source = Bytes::from(data.clone());
+        let chunks = builder.split_bytes_into_chunks(9, source.clone(), 0);
+
+        assert_eq!(chunks.len(), DEFAULT_SEGMENT_SIZE.div_ceil(DEFAULT_CHUNK_SIZE));
+
+        // 청크 데이터가 원본 버퍼 안을 가리키는지 (복사 없음)
+        let range = source.as_ptr() as usize..source.as_ptr() as usize + source.len();
+        for chunk in &chunks {
+            assert!(range.contains(&(chunk.data.as_ptr() as usize)));
+        }
+
+        // 복사 분할과 동일한 청크 및 조립 결과
+        let copied = builder.split_into_chunks(9, &data, 0);
+        let mut segment = Segment::new_for_receive(9, data.len(), chunks.len() as u32);
+        for (sliced, copied) in chunks.iter().zip(&copied) {
+            assert_eq!(sliced.data, copied.data);
+            assert_eq!(sliced.header.crc32, copied.header.crc32);
+            assert!(segment.insert_chunk(sliced));
+        }
+        assert!(segment.is_complete());
+        assert_eq!(segment.into_data().as_ref(), data.as_slice());
+    }
+
+    #[test]
+    fn test_encrypted_chunks_fit_datagram() {
+        let mut cipher = crate::SegmentCipher::new(&[3u8; 32]);
+        for base in [
+            Config::default(),
+            Config::low_spec(),
+            Config::high_performance(),
+            Config::unstable_network(),
+        ] {
+            let config = Config {
+                encryption_enabled: true,
+                ..base
+            };
+            // 세그먼트를 한 번 봉인한 뒤 청크로 나눔 (청크마다 오버헤드가 붙지 않음)
+            let builder = SegmentBuilder::new(config.effective_chunk_size());
+            let data: Vec<u8> = (0..config.effective_segment_size()).map(|i| i as u8).collect();
+            let sealed = cipher.encrypt_segment(1, &data).unwrap();
+            assert_eq!(sealed.len(), config.sealed_segment_len(data.len()));
+            assert!(sealed.len() <= config.segment_size);
+            let chunks = builder.split_into_chunks(1, &sealed, 0);
+            assert!(chunks.len() <= config.chunks_per_segment());
+            for chunk in chunks {
+                assert!(chunk.to_bytes().len() <= config.max_datagram_size);
+            }
+        }
+
+        // MTU에 딱 맞춘 청크는 암호화해도 줄어들지 않음
+        let tuned = Config {
+            chunk_size: 1472 - Chunk::PREFIX_LEN - ChunkHeader::ENCODED_LEN,
+            ..Config::default()
+        };
+        assert_eq!(tuned.effective_chunk_size(), tuned.chunk_size);
+        let encrypted = Config {
+            encryption_enabled: true,
+            ..tuned.clone()
+        };
+        assert_eq!(encrypted.effective_chunk_size(), tuned.chunk_size);
+        assert_eq!(encrypted.effective_segment_size(), tuned.segment_size - 28);
+
+        // 프레이밍조차 들어가지 않는 데이터그램 크기
+        let tiny = Config {
+            max_datagram_size: Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN,
+            ..encrypted
+        };
+        assert_eq!(tiny.effective_chunk_size(), 0);
+    }
 }
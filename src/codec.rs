@@ -0,0 +1,168 @@
+//! 바이트 수준 와이어 코덱 (no_std + alloc)
+//!
+//! bincode 1.x 기본 인코딩(리틀 엔디안, 고정 길이 정수)과 바이트 단위로 동일한
+//! 출력을 만들어, std 없이도 청크/헤더/NACK을 조립하고 파싱할 수 있게 함
+//!
+//! ## 바이트 순서 보장
+//! 청크 헤더와 모든 컨트롤 메시지의 여러 바이트 필드(u16/u32/u64/f32)는 이 코덱만 거치며
+//! 호스트 엔디언과 무관하게 항상 리틀 엔디언으로 쓰고 읽음. 필드는 패딩이나 정렬 없이
+//! 선언 순서대로 붙어 있고, 판독기는 바이트 단위로 복사하므로 입력 버퍼의 정렬도 요구하지 않음.
+//! 빅 엔디언 타깃도 같은 바이트를 만들고 같은 값으로 읽음
+
+use alloc::vec::Vec;
+
+/// 리틀 엔디안 바이트 기록기
+pub(crate) struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn put_u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    pub(crate) fn put_bool(&mut self, v: bool) {
+        self.buf.push(v as u8);
+    }
+
+    pub(crate) fn put_u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_u64(&mut self, v: u64) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_f32(&mut self, v: f32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    pub(crate) fn put_slice(&mut self, v: &[u8]) {
+        self.buf.extend_from_slice(v);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// 리틀 엔디안 바이트 판독기
+pub(crate) struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let end = self.pos.checked_add(N)?;
+        let bytes = self.buf.get(self.pos..end)?;
+        self.pos = end;
+        let mut out = [0u8; N];
+        out.copy_from_slice(bytes);
+        Some(out)
+    }
+
+    pub(crate) fn get_u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    pub(crate) fn get_bool(&mut self) -> Option<bool> {
+        match self.get_u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn get_u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn get_u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn get_u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn get_f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    pub(crate) fn get_array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take()
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn get_slice(&mut self, len: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.buf.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    /// 남은 바이트 수
+    pub(crate) fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fields_little_endian_on_any_host() {
+        let mut w = Writer::with_capacity(19);
+        w.put_u16(0x0102);
+        w.put_u32(0x0304_0506);
+        w.put_u64(0x0708_090A_0B0C_0D0E);
+        w.put_f32(1.0);
+        w.put_bool(true);
+        assert_eq!(
+            w.into_inner(),
+            [
+                0x02, 0x01, 0x06, 0x05, 0x04, 0x03, 0x0E, 0x0D, 0x0C, 0x0B, 0x0A, 0x09, 0x08, 0x07,
+                0x00, 0x00, 0x80, 0x3F, 0x01,
+            ]
+        );
+
+        // 빅 엔디언 배치로 만든 바이트는 뒤집힌 값으로 읽힘 (호스트 순서를 따르지 않음)
+        let mut r = Reader::new(&[0x01, 0x02, 0x00, 0x00, 0x00, 0x2A]);
+        assert_eq!(r.get_u16(), Some(0x0201));
+        assert_eq!(r.get_u32(), Some(0x2A00_0000));
+        assert_eq!(r.remaining(), 0);
+        assert_eq!(r.get_u8(), None);
+    }
+
+    #[test]
+    fn test_reader_needs_no_alignment() {
+        // 홀수 오프셋에서 시작하는 u64/u32도 같은 값
+        let mut buf = [0u8; 13];
+        buf[1..9].copy_from_slice(&0x1122_3344_5566_7788u64.to_le_bytes());
+        buf[9..13].copy_from_slice(&0xDEAD_BEEFu32.to_le_bytes());
+        let mut r = Reader::new(&buf[1..]);
+        assert_eq!(r.get_u64(), Some(0x1122_3344_5566_7788));
+        assert_eq!(r.get_u32(), Some(0xDEAD_BEEF));
+        assert_eq!(r.get_bool(), None);
+    }
+}
@@ -1,18 +1,71 @@
 //! 프로토콜 설정
 
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::chunk::{Chunk, ChunkHeader};
+use crate::crypto::{KeyProvider, SharedKeyProvider, NONCE_SIZE, TAG_SIZE};
+use crate::plan::TransferPlan;
 use crate::{DEFAULT_CHUNK_SIZE, DEFAULT_SEGMENT_SIZE};
 
+/// 사용자 정의 중복률 정책
+///
+/// 송신자가 `ratio_adjust_interval_ms`마다 호출해 다음 세그먼트의 중복 비율(0.0 ~ 1.0)을 정함.
+/// `Fn(f64, u64, f64) -> f64` 클로저도 그대로 사용 가능
+pub trait RedundancyPolicy: Send + Sync {
+    /// - `loss_rate`: NIC 경로 중 최대 손실률
+    /// - `rtt_us`: 송신자가 추정한 RTT (쿠키 왕복, 없으면 `slow_start_initial_rtt_ms`)
+    /// - `recent_loss`: 최근 완료 세그먼트의 손실률
+    fn redundancy(&self, loss_rate: f64, rtt_us: u64, recent_loss: f64) -> f64;
+}
+
+impl<F> RedundancyPolicy for F
+where
+    F: Fn(f64, u64, f64) -> f64 + Send + Sync,
+{
+    fn redundancy(&self, loss_rate: f64, rtt_us: u64, recent_loss: f64) -> f64 {
+        self(loss_rate, rtt_us, recent_loss)
+    }
+}
+
+/// `Config`에 담는 공유 정책 핸들 (설정 복제 시 같은 정책을 공유)
+#[derive(Clone)]
+pub struct SharedRedundancyPolicy(Arc<dyn RedundancyPolicy>);
+
+impl SharedRedundancyPolicy {
+    pub fn new(policy: impl RedundancyPolicy + 'static) -> Self {
+        Self(Arc::new(policy))
+    }
+}
+
+impl RedundancyPolicy for SharedRedundancyPolicy {
+    fn redundancy(&self, loss_rate: f64, rtt_us: u64, recent_loss: f64) -> f64 {
+        self.0.redundancy(loss_rate, rtt_us, recent_loss)
+    }
+}
+
+impl fmt::Debug for SharedRedundancyPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedRedundancyPolicy(..)")
+    }
+}
+
 /// SLS 프로토콜 설정
 #[derive(Debug, Clone)]
 pub struct Config {
     /// 청크 크기 (바이트)
     pub chunk_size: usize,
 
+    /// 컨트롤 메시지 데이터그램 최대 크기 (바이트, 경로 MTU - IP/UDP 헤더)
+    /// 넘는 NACK은 여러 조각으로 나누고, 나눌 수 없는 메시지는 전송 전에 거부
+    pub max_datagram_size: usize,
+
     /// 세그먼트 크기 (바이트)
     pub segment_size: usize,
 
     /// 기본 중복 전송 비율 (0.0 ~ 1.0)
-    /// 예: 0.2 = 20% 추가 전송
+    /// 예: 0.2 = 20% 추가 전송. 0이면 중복 전송 끔 (손실률과 무관하게 적응형 조정도 하지 않음)
     pub base_redundancy_ratio: f64,
 
     /// 최대 중복 전송 비율
@@ -21,16 +74,90 @@ pub struct Config {
     /// 최소 중복 전송 비율
     pub min_redundancy_ratio: f64,
 
+    /// RTT 100ms당 중복률 하한 증가분 (0이면 RTT와 무관하게 손실률로만 조정)
+    /// NACK 재전송은 RTT 한 번을 더 기다리므로 먼 경로는 손실이 적어도 미리 더 보냄.
+    /// 하한도 max_redundancy_ratio를 넘지 않음
+    pub redundancy_rtt_weight: f64,
+
+    /// 중복률 정책 (None이면 `calculate_redundancy`의 선형 조정)
+    /// 지정하면 min/max_redundancy_ratio 대신 정책의 결과를 0.0 ~ 1.0으로만 제한해 사용
+    pub redundancy_policy: Option<SharedRedundancyPolicy>,
+
     /// NACK 대기 타임아웃 (밀리초)
     pub nack_timeout_ms: u64,
 
+    /// 청크가 하나도 오지 않은 채 이어진 NACK 라운드가 이 수에 이르고 RTT의 두 배가 지나면
+    /// 업링크 단절로 판단 (멀티패스면 다른 NIC로 NACK 전환, 단일 경로면 `Error::UplinkLikelyBroken`). 0이면 감지 안 함
+    pub uplink_silent_nack_rounds: u32,
+
+    /// 진전 없이 이어진 NACK이 이 횟수에 이르면 세그먼트를 영구 실패로 포기하고 나머지 전송 계속
+    /// (세그먼트에 청크가 하나도 더 오지 않은 NACK 라운드만 셈, 진전 없는 시간도 RTT × 이 횟수는
+    /// 지나야 함, 0이면 세그먼트 타임아웃까지 대기)
+    pub max_stalled_nacks: u32,
+
+    /// NACK이 이 횟수만큼 누락이 줄지 않은 채 이어지거나 꼬리 손실 탐침이 이 횟수만큼 응답 없이
+    /// 이어지면 PMTU 블랙홀을 의심하고, 크기를 줄여 가는 탐침으로 통과하는 청크 크기를 찾아
+    /// 다시 보냄. 0이면 감지 안 함
+    pub pmtu_blackhole_nacks: u32,
+
+    /// 청크 하나를 이 횟수만큼 재전송하고도 다시 NACK되면 전달할 수 없다고 보고 세그먼트를
+    /// 포기한 뒤 수신자에게 `GiveUp`으로 알림 (수신자는 NACK을 멈추고 세그먼트 실패 처리).
+    /// 꼬리 손실 탐침도 재전송으로 셈. 0이면 세그먼트 타임아웃까지 무제한 재전송
+    pub max_chunk_retransmits: u32,
+
+    /// 델타 NACK 사용 (세그먼트마다 직전 NACK 이후 새로 누락되거나 채워진 청크만 보고)
+    /// 지속 손실 중 같은 누락 목록을 반복해 보내지 않아 업링크가 줄어듦
+    pub delta_nacks: bool,
+
+    /// 델타 NACK 사용 시 전체 누락 목록을 다시 보내는 주기 (세그먼트당 NACK 수)
+    /// 유실된 델타 때문에 어긋난 송신자 상태를 복구. 0이나 1이면 매번 전체 목록
+    pub nack_resync_interval: u32,
+
     /// 세그먼트 완료 대기 타임아웃 (밀리초)
     pub segment_timeout_ms: u64,
 
+    /// 세그먼트가 생긴 지 이 시간(밀리초) 안에 조립되지 않으면 NACK을 격상 (0이면 끔)
+    /// 격상한 세그먼트는 순서 역전 대기 없이 먼저 요청하고, FEC 모드면 패리티 재전송도 요청
+    pub assembly_soft_deadline_ms: u64,
+
+    /// 이 시간(밀리초) 안에 조립되지 않으면 `segment_timeout_ms`를 기다리지 않고 영구 실패 처리
+    /// (0이면 끔)
+    pub assembly_hard_deadline_ms: u64,
+
+    /// 소비자가 아직 가져가지 않은 완료 세그먼트를 보관하는 창 크기 (세그먼트 수)
+    /// 창이 차면 새 세그먼트의 청크만 받지 않고, 이미 조립 중인 세그먼트는 계속 처리.
+    /// 0이면 제한 없이 보관
+    pub delivery_window_segments: usize,
+
+    /// `FileReceiver::receive_file`가 기다리는 세그먼트가 수신 기한 안에 오지 않을 때 그
+    /// 세그먼트를 다시 요청하는 횟수. 다 쓰면 `Error::SegmentTimeout` (기한은 RTT에 비례)
+    pub receive_segment_retries: u32,
+
+    /// 매니페스트에 파일 세그먼트마다 SHA-256을 실어 보냄 (송신측, 세그먼트당 32바이트).
+    /// 수신자는 완료된 세그먼트를 전달하기 전에 검증하고, 불일치하면 그 세그먼트만 다시 받음.
+    /// 수신측에서도 켜면 매니페스트보다 먼저 완료한 파일 세그먼트를 매니페스트가 올 때까지 보관
+    pub segment_digests: bool,
+
     /// 청크 전송 간격 (마이크로초)
     /// 0이면 최대 속도로 전송
     pub chunk_interval_us: u64,
 
+    /// 서버 전체 송신 상한 (bytes/s, 0이면 제한 없음)
+    /// 같은 `EgressLimiter`를 공유하는 세션들이 가중치 비율로 나눠 사용
+    pub total_egress_limit: u64,
+
+    /// 이 세션의 송신 상한 분배 가중치 (활성 세션 가중치 합 대비 비율)
+    pub egress_weight: f64,
+
+    /// 준비된 세그먼트 캐시 용량 (bytes, 0이면 끔, `SegmentCache`)
+    /// 같은 데이터셋을 반복해 보내는 서버가 연결마다 다시 분할하지 않도록 오프셋별로 보관.
+    /// `Sender::preload`로 미리 채울 수 있고, 가득 차면 가장 오래 안 쓴 세그먼트부터 버림
+    pub segment_cache_bytes: usize,
+
+    /// 스트림 소스를 미리 읽어 두는 세그먼트 수 (`FileSender::send_stream_prefetched`, 최소 1)
+    /// 느린 디스크 읽기가 송신 페이싱을 끊지 않도록 별도 IO 작업이 이만큼 앞서 읽음
+    pub readahead_segments: usize,
+
     /// NIC별 속도 측정 윈도우 (청크 수)
     pub stats_window_size: usize,
 
@@ -46,32 +173,233 @@ pub struct Config {
     /// 송신 버퍼 크기
     pub send_buffer_size: usize,
 
+    /// 측정한 대역폭-지연 곱(BDP)으로 소켓 버퍼 크기 자동 조정
+    /// 시작 크기는 `recv_buffer_size`/`send_buffer_size`, 전송 중 아래 한도 안에서 다시 맞춤
+    pub auto_buffer_tuning: bool,
+
+    /// 자동 조정 시 소켓 버퍼 최소 크기 (바이트)
+    pub min_socket_buffer_size: usize,
+
+    /// 자동 조정 시 소켓 버퍼 최대 크기 (바이트)
+    pub max_socket_buffer_size: usize,
+
+    /// 수신자가 받아들일 최대 전송 크기 (바이트, 0이면 제한 없음)
+    /// InitAck이 알린 `total_file_size`가 이보다 크면 버퍼나 출력 파일을 만들기 전에 거부
+    pub max_accept_file_size: u64,
+
+    /// 수신자가 받을 수 있는 최대 청크 크기 (바이트, 0이면 제한 없음)
+    /// Init에 실어 보내며, 서버 청크가 더 크면 핸드셰이크가 `Error::InvalidInitAck`로 실패
+    pub max_supported_chunk_size: usize,
+
+    /// 수신자가 조립할 수 있는 최대 세그먼트 크기 (바이트, 0이면 제한 없음)
+    /// 서버는 세그먼트 크기(적응형 상한 포함)를 이 값 이하로 줄여 보냄
+    pub max_supported_segment_size: usize,
+
     /// 암호화 활성화 (선택)
     /// X25519 키 교환 + ChaCha20-Poly1305 사용
     pub encryption_enabled: bool,
 
-    /// 병렬 처리 워커 수 (0이면 CPU 코어 수 사용)
+    /// 모든 청크 데이터그램을 같은 크기로 패딩 (트래픽 분석 대응)
+    /// 세그먼트 마지막의 짧은 청크로 세그먼트 경계가 드러나지 않도록 0 바이트를 덧붙임.
+    /// 헤더의 `data_len`이 원래 길이라 수신자는 설정과 무관하게 패딩을 제거.
+    /// 암호화 시에는 패딩이 AEAD 밖에 붙지 않도록 송신자가 덧붙이지 않으므로, 세그먼트를
+    /// `CryptoSession::encrypt_padded`(블록 = `effective_chunk_size()`)로 봉인해 평문 안에서 채움
+    pub pad_datagrams: bool,
+
+    /// 작은 청크 여러 개를 `max_datagram_size` 안에서 데이터그램 하나로 묶어 전송 (`ChunkBatch`)
+    /// 작은 세그먼트 설정에서 데이터그램당 헤더 오버헤드를 줄임. 묶음은 다음 주기 작업
+    /// (최대 10ms) 또는 `Sender::flush_coalesced`에서 나감. `pad_datagrams`를 켜면 무시
+    pub coalesce_chunks: bool,
+
+    /// 클라이언트가 정해지면 `Sender::start`의 소켓을 `connect()`하고 `send_to` 대신 `send` 사용
+    /// (`PeerUdpSocket`). 패킷마다 목적지 경로 조회를 생략. 연결된 동안 다른 주소의 패킷은
+    /// 커널이 걸러내므로 연결이 닫히면 해제. 여러 세션이 소켓을 공유하면 끌 것
+    pub connect_socket: bool,
+
+    /// 키 교환 공개키 재전송 간격 (밀리초)
+    pub key_exchange_retry_ms: u64,
+
+    /// 키 교환 공개키 최대 재전송 횟수 (첫 전송 제외)
+    pub key_exchange_max_retries: u32,
+
+    /// 키 교환 전체 기한 (밀리초)
+    /// 상대 공개키를 이 안에 받지 못하면 `Error::KeyExchangeTimeout`으로 세션 해제
+    /// (Init만 보내고 사라진 클라이언트가 서버를 붙잡지 못하도록)
+    pub key_exchange_timeout_ms: u64,
+
+    /// 암호화 세션을 묶을 애플리케이션 컨텍스트 (세션 토큰, 리소스 ID 등)
+    /// 모든 세그먼트의 AEAD 연관 데이터로 사용되어 양측 값이 같을 때만 복호화 성공
+    pub crypto_context: Option<Vec<u8>>,
+
+    /// 키 교환의 키쌍 생성과 DH 계산을 맡길 제공자 (None이면 프로세스 안의 x25519-dalek)
+    pub key_provider: Option<SharedKeyProvider>,
+
+    /// 병렬 처리 워커 수, 수신자는 세그먼트 조립 샤드 수 (0이면 CPU 코어 수 사용)
     pub parallel_workers: usize,
+
+    /// 수신자 고정 바인드 포트 (0이면 bind_addr의 포트 사용)
+    /// NAT 매핑 유지를 위해 소스 포트를 고정할 때 사용
+    pub local_port: u16,
+
+    /// 송신 패킷의 DSCP (0~63, None이면 운영체제 기본값)
+    /// 송신자/수신자/NIC 소켓의 IPv4 TOS·IPv6 Traffic Class 상위 6비트에 기록.
+    /// 하위 2비트(ECN)는 건드리지 않음
+    pub dscp: Option<u8>,
+
+    /// NAT keepalive 간격 (밀리초, 0이면 비활성화)
+    /// 이 시간 동안 수신이 없으면 데이터 소켓으로 Heartbeat 전송
+    pub nat_keepalive_interval_ms: u64,
+
+    /// 미확인 출발지별 초당 허용 컨트롤 메시지 수 (0이면 제한 없음)
+    /// 위조 출발지 Init/NACK 폭주로 인한 증폭 공격 완화 (쿠키 확인된 클라이언트는 제외)
+    pub control_rate_per_sec: f64,
+
+    /// 출발지별 컨트롤 메시지 버스트 허용량
+    pub control_burst: u32,
+
+    /// Init 쿠키 요구 여부
+    /// 활성화 시 서버가 발급한 쿠키를 되돌려 보낸 Init에만 InitAck/대량 전송
+    pub require_init_cookie: bool,
+
+    /// Init 쿠키 유효 기간 (밀리초)
+    /// 이보다 오래된 쿠키를 담은 Init은 캡처 후 재전송으로 보고 거부
+    pub init_cookie_lifetime_ms: u64,
+
+    /// 수신 세그먼트 버퍼 풀 크기 (0이면 풀 비활성화)
+    /// 세그먼트 수신 버퍼를 재사용해 할당/페이지 폴트 감소
+    pub segment_pool_capacity: usize,
+
+    /// 수신 시작 시 풀 버퍼를 미리 할당하고 페이지 터치
+    pub segment_pool_pretouch: bool,
+
+    /// FEC 모드 자동 전환 손실률 임계값 (0이면 자동 전환 비활성화)
+    /// 이 이상의 손실이 지속되면 중복 전송 대신 Reed-Solomon 패리티 전송
+    pub fec_loss_threshold: f64,
+
+    /// 모드 전환 전 임계값 초과/미만 유지 시간 (밀리초)
+    pub fec_switch_hold_ms: u64,
+
+    /// 적응형 세그먼트 크기 하한 (바이트)
+    /// min < max이면 송신자가 min에서 시작해 손실 없는 전달마다 키우고 손실 시 줄임
+    /// 0 또는 min >= max이면 segment_size 고정 (FileReceiver::receive_file처럼
+    /// 세그먼트 수를 미리 아는 수신 방식은 고정 크기 필요)
+    pub min_segment_size: usize,
+
+    /// 적응형 세그먼트 크기 상한 (바이트)
+    pub max_segment_size: usize,
+
+    /// FlowControl 최소 전송 간격 (밀리초)
+    /// 손실 급증/버퍼 압박 등 큰 변화는 즉시 보내되 이 간격보다 자주 보내지 않음
+    pub flowcontrol_min_interval_ms: u64,
+
+    /// FlowControl 최대 전송 간격 (밀리초)
+    /// 변화가 없으면 이 간격(±10% 지터)마다 하트비트로만 전송
+    pub flowcontrol_max_interval_ms: u64,
+
+    /// 슬로 스타트 초기 윈도우 (청크 수, 0이면 비활성화, 기본 0)
+    /// 전송 시작 시 RTT당 이만큼만 보내고 전달 확인에 따라 늘린 뒤 최대 속도로 전환.
+    /// 윈도우는 세그먼트 완료 보고로만 늘어나므로 세그먼트 하나의 청크 수가 이보다 많으면
+    /// 완료될 때까지 RTT당 초기 윈도우에 묶임 (작은 세그먼트로 보낼 때만 켬)
+    pub initial_window_chunks: u32,
+
+    /// RTT 샘플이 없을 때 슬로 스타트 라운드 길이 (밀리초)
+    /// 쿠키 핸드셰이크를 거치면 쿠키 왕복 시간으로 대체
+    pub slow_start_initial_rtt_ms: u64,
+
+    /// 핸드셰이크 직후 대역폭 측정 (선택)
+    /// 서버가 짧은 프로브 버스트를 보내고 클라이언트가 잰 전달 속도로 초기 윈도우를 정함
+    pub bandwidth_probe: bool,
+
+    /// 네트워크 전환 자동 감지 임계값 (밀리초, 0이면 `Receiver::notify_network_change`로만 통보)
+    /// 수신자가 잰 단방향 지연의 기준선이 이만큼 이상 계속 바뀌면 경로가 바뀐 것으로 보고
+    /// 송신자의 혼잡/중복률 추정치를 보수적인 초기 상태로 되돌려 새 경로를 다시 잼
+    pub network_change_delay_step_ms: u64,
+
+    /// 모든 바이트가 같은 세그먼트(0으로 채운 구멍 등)를 청크 대신 `SparseSegmentMessage`로 전송 (선택)
+    /// 수신자가 Init에서 지원을 알렸을 때만 사용. `encryption_enabled`이면 표시를 인증할 키
+    /// (`Sender::set_metadata_auth`)가 있어야 하며, 없으면 청크로 보냄
+    pub sparse_segments: bool,
+
+    /// 청크/세그먼트/NACK 단위 디버그 로그 표본 비율 (N건 중 1건 기록)
+    /// 1이면 모두 기록, 0이면 개별 로그 없이 주기 요약만
+    pub log_sample_every: u32,
+
+    /// 표본 추출한 이벤트의 요약 로그 간격 (밀리초, 0이면 요약 안 함)
+    pub log_summary_interval_ms: u64,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             chunk_size: DEFAULT_CHUNK_SIZE,
+            max_datagram_size: 1472,
             segment_size: DEFAULT_SEGMENT_SIZE,
             base_redundancy_ratio: 0.15,      // 15% 기본 중복
             max_redundancy_ratio: 0.70,       // 최대 70%
             min_redundancy_ratio: 0.05,       // 최소 5%
+            redundancy_rtt_weight: 0.05,
+            redundancy_policy: None,
             nack_timeout_ms: 50,              // 50ms
+            uplink_silent_nack_rounds: 8,
+            max_stalled_nacks: 16,
+            pmtu_blackhole_nacks: 3,
+            max_chunk_retransmits: 32,
+            delta_nacks: false,
+            nack_resync_interval: 8,
             segment_timeout_ms: 5000,         // 5초
+            assembly_soft_deadline_ms: 0,
+            assembly_hard_deadline_ms: 0,
+            delivery_window_segments: 64,
+            receive_segment_retries: 8,
+            segment_digests: false,
             chunk_interval_us: 0,             // 최대 속도
+            total_egress_limit: 0,            // 제한 없음
+            egress_weight: 1.0,
+            segment_cache_bytes: 0,
+            readahead_segments: 4,
             stats_window_size: 100,           // 100개 청크 기준
             ratio_adjust_interval_ms: 100,    // 100ms마다 재조정
             max_concurrent_segments: 16,
             recv_buffer_size: 2 * 1024 * 1024, // 2MB
             send_buffer_size: 2 * 1024 * 1024, // 2MB
+            auto_buffer_tuning: false,
+            min_socket_buffer_size: 256 * 1024,
+            max_socket_buffer_size: 64 * 1024 * 1024,
+            max_accept_file_size: 0,
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
             encryption_enabled: false,        // 암호화 비활성화 (기본)
+            pad_datagrams: false,
+            coalesce_chunks: false,
+            connect_socket: false,
+            key_exchange_retry_ms: 500,
+            key_exchange_max_retries: 10,
+            key_exchange_timeout_ms: 5_000,   // 5초
+            crypto_context: None,
+            key_provider: None,
             parallel_workers: 0,              // CPU 코어 수 사용
+            local_port: 0,                    // 자동 할당
+            dscp: None,
+            nat_keepalive_interval_ms: 0,     // keepalive 비활성화
+            control_rate_per_sec: 200.0,      // 출발지당 초당 200개
+            control_burst: 64,
+            require_init_cookie: true,
+            init_cookie_lifetime_ms: 5_000,   // 5초
+            segment_pool_capacity: 32,        // 동시 세그먼트 수의 2배
+            segment_pool_pretouch: false,
+            fec_loss_threshold: 0.15,         // 15% 이상 손실 지속 시
+            fec_switch_hold_ms: 2000,
+            min_segment_size: 0,              // 적응 비활성화 (segment_size 고정)
+            max_segment_size: 0,
+            flowcontrol_min_interval_ms: 20,  // 변화 시 최대 50회/초
+            flowcontrol_max_interval_ms: 1000, // 정상 상태 1초
+            initial_window_chunks: 0,         // 비활성 (세그먼트 완료로만 늘어남)
+            slow_start_initial_rtt_ms: 100,
+            bandwidth_probe: false,
+            network_change_delay_step_ms: 0,
+            sparse_segments: false,
+            log_sample_every: 1,
+            log_summary_interval_ms: 1000,
         }
     }
 }
@@ -84,11 +412,130 @@ impl Config {
 
     /// 세그먼트당 청크 수 계산
     pub fn chunks_per_segment(&self) -> usize {
-        (self.segment_size + self.chunk_size - 1) / self.chunk_size
+        self.segment_size.div_ceil(self.effective_chunk_size().max(1))
+    }
+
+    /// 세그먼트 하나에 붙는 암호화 오버헤드 (nonce + 인증 태그, 암호화를 끄면 0)
+    ///
+    /// 세그먼트 단위로 봉인하므로 청크 수와 상관없이 세그먼트당 한 번
+    pub fn segment_encryption_overhead(&self) -> usize {
+        if self.encryption_enabled {
+            NONCE_SIZE + TAG_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// 실제로 청크에 싣는 데이터 크기
+    ///
+    /// 암호화 시에는 청크 데이터그램(프리픽스 + 헤더 + 데이터)이 `max_datagram_size` 안에
+    /// 들어가도록 `chunk_size`를 줄임. 프레이밍조차 들어가지 않으면 0
+    pub fn effective_chunk_size(&self) -> usize {
+        if !self.encryption_enabled {
+            return self.chunk_size;
+        }
+        let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        self.chunk_size.min(self.max_datagram_size.saturating_sub(framing))
+    }
+
+    /// 평문 `len` 바이트 세그먼트를 봉인한 크기 (암호화를 끄면 그대로)
+    ///
+    /// `pad_datagrams`와 함께 쓰면 `CryptoSession::encrypt_padded`처럼 청크 크기 배수로 채움
+    pub fn sealed_segment_len(&self, len: usize) -> usize {
+        let overhead = self.segment_encryption_overhead();
+        if overhead == 0 {
+            len
+        } else if self.pad_datagrams {
+            let block = self.effective_chunk_size().max(1);
+            (len + overhead + 1).div_ceil(block) * block
+        } else {
+            len + overhead
+        }
+    }
+
+    /// 봉인한 세그먼트가 `segment_size`를 넘지 않는 평문 크기 (암호화를 끄면 그대로)
+    pub fn plaintext_segment_size(&self, segment_size: usize) -> usize {
+        let overhead = self.segment_encryption_overhead();
+        if overhead == 0 {
+            segment_size
+        } else if self.pad_datagrams {
+            let block = self.effective_chunk_size().max(1);
+            (segment_size / block * block).saturating_sub(overhead + 1)
+        } else {
+            segment_size.saturating_sub(overhead)
+        }
+    }
+
+    /// 암호화 시 애플리케이션이 세그먼트 하나에 담을 평문 크기 (`plaintext_segment_size` 참고)
+    pub fn effective_segment_size(&self) -> usize {
+        self.plaintext_segment_size(self.segment_size)
+    }
+
+    /// 송신자가 청크 데이터그램을 0으로 채울 크기 (`pad_datagrams`를 끄거나 암호화 중이면 0)
+    ///
+    /// 가장 큰 청크 데이터그램 기준: 프리픽스 + 헤더 + 실효 청크 크기. 암호화 중에는 인증되지
+    /// 않는 바깥 패딩 대신 봉인 전에 평문을 채우므로 (`CryptoSession::encrypt_padded`) 덧붙이지 않음
+    pub fn padded_datagram_len(&self) -> usize {
+        if !self.pad_datagrams || self.encryption_enabled {
+            return 0;
+        }
+        Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN + self.effective_chunk_size()
+    }
+
+    /// 이 설정으로 `file_size` 바이트를 보낼 때의 계획 (보내지 않고 계산만, `plan` 모듈 참고)
+    pub fn plan_transfer(&self, file_size: u64) -> TransferPlan {
+        TransferPlan::new(self, file_size)
+    }
+
+    /// 중복 전송 사용 여부 (`base_redundancy_ratio`가 0이면 끔)
+    pub fn redundancy_enabled(&self) -> bool {
+        self.base_redundancy_ratio > 0.0
+    }
+
+    /// 다음 세그먼트의 중복 비율 (`redundancy_policy`가 있으면 그 결과, 없으면 선형 조정)
+    pub fn redundancy(&self, loss_rate: f64, rtt_us: u64, recent_loss: f64) -> f64 {
+        if !self.redundancy_enabled() {
+            return 0.0;
+        }
+        match &self.redundancy_policy {
+            Some(policy) => {
+                let ratio = policy.redundancy(loss_rate, rtt_us, recent_loss);
+                if ratio.is_finite() {
+                    ratio.clamp(0.0, 1.0)
+                } else {
+                    self.base_redundancy_ratio
+                }
+            }
+            None => self
+                .calculate_redundancy(loss_rate.max(recent_loss))
+                .max(self.rtt_redundancy_floor(rtt_us))
+                .min(self.max_redundancy_ratio),
+        }
+    }
+
+    /// RTT에 따른 중복률 하한 (`min_redundancy_ratio` + 100ms당 `redundancy_rtt_weight`)
+    pub fn rtt_redundancy_floor(&self, rtt_us: u64) -> f64 {
+        self.min_redundancy_ratio + self.redundancy_rtt_weight * rtt_us as f64 / 100_000.0
+    }
+
+    /// 키 교환 제공자 지정 (HSM/KMS 등 외부 키 관리)
+    pub fn with_key_provider(mut self, provider: impl KeyProvider + 'static) -> Self {
+        self.key_provider = Some(SharedKeyProvider::new(provider));
+        self
+    }
+
+    /// 정책 지정
+    pub fn with_redundancy_policy(mut self, policy: impl RedundancyPolicy + 'static) -> Self {
+        self.redundancy_policy = Some(SharedRedundancyPolicy::new(policy));
+        self
     }
 
     /// 손실률 기반 중복 비율 계산
     pub fn calculate_redundancy(&self, loss_rate: f64) -> f64 {
+        // 중복 전송을 끈 설정은 min_redundancy_ratio로 끌어올리지 않음
+        if !self.redundancy_enabled() {
+            return 0.0;
+        }
         // 손실률이 높을수록 중복 비율 증가
         // loss_rate 0.0 -> base_redundancy
         // loss_rate 0.3 -> max_redundancy에 가까워짐
@@ -100,20 +547,74 @@ impl Config {
     pub fn low_spec() -> Self {
         Self {
             chunk_size: 1100,
+            max_datagram_size: 1472,
             segment_size: 32768,              // 32KB
             base_redundancy_ratio: 0.20,      // 20%
             max_redundancy_ratio: 0.60,
             min_redundancy_ratio: 0.10,
+            redundancy_rtt_weight: 0.05,
+            redundancy_policy: None,
             nack_timeout_ms: 100,
+            uplink_silent_nack_rounds: 8,
+            max_stalled_nacks: 16,
+            pmtu_blackhole_nacks: 3,
+            max_chunk_retransmits: 32,
+            delta_nacks: false,
+            nack_resync_interval: 8,
             segment_timeout_ms: 10000,
+            assembly_soft_deadline_ms: 0,
+            assembly_hard_deadline_ms: 0,
+            delivery_window_segments: 16,
+            receive_segment_retries: 8,
+            segment_digests: false,
             chunk_interval_us: 100,           // 약간의 간격
+            total_egress_limit: 0,
+            egress_weight: 1.0,
+            segment_cache_bytes: 0,
+            readahead_segments: 4,
             stats_window_size: 50,
             ratio_adjust_interval_ms: 200,
             max_concurrent_segments: 4,
             recv_buffer_size: 512 * 1024,     // 512KB
             send_buffer_size: 512 * 1024,
+            auto_buffer_tuning: false,
+            min_socket_buffer_size: 128 * 1024,
+            max_socket_buffer_size: 8 * 1024 * 1024,
+            max_accept_file_size: 0,
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
             encryption_enabled: false,
+            pad_datagrams: false,
+            coalesce_chunks: false,
+            connect_socket: false,
+            key_exchange_retry_ms: 500,
+            key_exchange_max_retries: 10,
+            key_exchange_timeout_ms: 5_000,
+            crypto_context: None,
+            key_provider: None,
             parallel_workers: 2,              // 저사양은 2 워커
+            local_port: 0,
+            dscp: None,
+            nat_keepalive_interval_ms: 0,
+            control_rate_per_sec: 100.0,
+            control_burst: 32,
+            require_init_cookie: true,
+            init_cookie_lifetime_ms: 5_000,
+            segment_pool_capacity: 8,
+            segment_pool_pretouch: false,
+            fec_loss_threshold: 0.15,
+            fec_switch_hold_ms: 3000,
+            min_segment_size: 0,
+            max_segment_size: 0,
+            flowcontrol_min_interval_ms: 50,
+            flowcontrol_max_interval_ms: 2000,
+            initial_window_chunks: 0,
+            slow_start_initial_rtt_ms: 200,
+            bandwidth_probe: false,
+            network_change_delay_step_ms: 0,
+            sparse_segments: false,
+            log_sample_every: 1,
+            log_summary_interval_ms: 1000,
         }
     }
 
@@ -121,20 +622,74 @@ impl Config {
     pub fn high_performance() -> Self {
         Self {
             chunk_size: 1400,
+            max_datagram_size: 1472,
             segment_size: 131072,             // 128KB
             base_redundancy_ratio: 0.10,      // 10%
             max_redundancy_ratio: 0.50,
             min_redundancy_ratio: 0.05,
+            redundancy_rtt_weight: 0.03,
+            redundancy_policy: None,
             nack_timeout_ms: 30,
+            uplink_silent_nack_rounds: 8,
+            max_stalled_nacks: 16,
+            pmtu_blackhole_nacks: 3,
+            max_chunk_retransmits: 32,
+            delta_nacks: false,
+            nack_resync_interval: 8,
             segment_timeout_ms: 3000,
+            assembly_soft_deadline_ms: 0,
+            assembly_hard_deadline_ms: 0,
+            delivery_window_segments: 128,
+            receive_segment_retries: 8,
+            segment_digests: false,
             chunk_interval_us: 0,             // 최대 속도
+            total_egress_limit: 0,
+            egress_weight: 1.0,
+            segment_cache_bytes: 0,
+            readahead_segments: 8,
             stats_window_size: 200,
             ratio_adjust_interval_ms: 50,
             max_concurrent_segments: 32,
             recv_buffer_size: 8 * 1024 * 1024, // 8MB
             send_buffer_size: 8 * 1024 * 1024,
+            auto_buffer_tuning: false,
+            min_socket_buffer_size: 1024 * 1024,
+            max_socket_buffer_size: 256 * 1024 * 1024,
+            max_accept_file_size: 0,
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
             encryption_enabled: false,
+            pad_datagrams: false,
+            coalesce_chunks: false,
+            connect_socket: false,
+            key_exchange_retry_ms: 250,
+            key_exchange_max_retries: 8,
+            key_exchange_timeout_ms: 2_000,
+            crypto_context: None,
+            key_provider: None,
             parallel_workers: 0,              // 모든 코어 사용
+            local_port: 0,
+            dscp: None,
+            nat_keepalive_interval_ms: 0,
+            control_rate_per_sec: 500.0,
+            control_burst: 128,
+            require_init_cookie: true,
+            init_cookie_lifetime_ms: 2_000,
+            segment_pool_capacity: 64,
+            segment_pool_pretouch: true,      // 시작 시 미리 터치
+            fec_loss_threshold: 0.10,
+            fec_switch_hold_ms: 1000,
+            min_segment_size: 0,
+            max_segment_size: 0,
+            flowcontrol_min_interval_ms: 10,
+            flowcontrol_max_interval_ms: 500,
+            initial_window_chunks: 0,
+            slow_start_initial_rtt_ms: 50,
+            bandwidth_probe: false,
+            network_change_delay_step_ms: 0,
+            sparse_segments: false,
+            log_sample_every: 1000, // 고속 전송에서는 1000건 중 1건
+            log_summary_interval_ms: 1000,
         }
     }
 
@@ -142,20 +697,74 @@ impl Config {
     pub fn unstable_network() -> Self {
         Self {
             chunk_size: 1000,                 // 작은 청크
+            max_datagram_size: 1472,
             segment_size: 32768,              // 32KB
             base_redundancy_ratio: 0.35,      // 35%
             max_redundancy_ratio: 0.80,
             min_redundancy_ratio: 0.20,
+            redundancy_rtt_weight: 0.10,
+            redundancy_policy: None,
             nack_timeout_ms: 200,
+            uplink_silent_nack_rounds: 10,
+            max_stalled_nacks: 32,
+            pmtu_blackhole_nacks: 4,
+            max_chunk_retransmits: 64,
+            delta_nacks: true,
+            nack_resync_interval: 4,
             segment_timeout_ms: 15000,
+            assembly_soft_deadline_ms: 0,
+            assembly_hard_deadline_ms: 0,
+            delivery_window_segments: 64,
+            receive_segment_retries: 16,
+            segment_digests: false,
             chunk_interval_us: 50,
+            total_egress_limit: 0,
+            egress_weight: 1.0,
+            segment_cache_bytes: 0,
+            readahead_segments: 4,
             stats_window_size: 30,
             ratio_adjust_interval_ms: 150,
             max_concurrent_segments: 8,
             recv_buffer_size: 1024 * 1024,
             send_buffer_size: 1024 * 1024,
+            auto_buffer_tuning: false,
+            min_socket_buffer_size: 256 * 1024,
+            max_socket_buffer_size: 64 * 1024 * 1024,
+            max_accept_file_size: 0,
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
             encryption_enabled: false,
+            pad_datagrams: false,
+            coalesce_chunks: false,
+            connect_socket: false,
+            key_exchange_retry_ms: 1_000,
+            key_exchange_max_retries: 10,
+            key_exchange_timeout_ms: 15_000,
+            crypto_context: None,
+            key_provider: None,
             parallel_workers: 4,
+            local_port: 0,
+            dscp: None,
+            nat_keepalive_interval_ms: 15000, // NAT 타임아웃 대비 15초
+            control_rate_per_sec: 200.0,
+            control_burst: 64,
+            require_init_cookie: true,
+            init_cookie_lifetime_ms: 15_000,
+            segment_pool_capacity: 16,
+            segment_pool_pretouch: false,
+            fec_loss_threshold: 0.10,
+            fec_switch_hold_ms: 1000,
+            min_segment_size: 0,
+            max_segment_size: 0,
+            flowcontrol_min_interval_ms: 20,
+            flowcontrol_max_interval_ms: 500,
+            initial_window_chunks: 0,
+            slow_start_initial_rtt_ms: 300,
+            bandwidth_probe: false,
+            network_change_delay_step_ms: 100, // Wi-Fi/셀룰러 전환 감지
+            sparse_segments: false,
+            log_sample_every: 1,
+            log_summary_interval_ms: 1000,
         }
     }
 }
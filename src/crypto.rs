@@ -5,14 +5,52 @@
 //! 2. 공개키 교환
 //! 3. 공유 비밀(shared secret) 계산
 //! 4. ChaCha20-Poly1305로 세그먼트 암호화/복호화
+//!
+//! 애플리케이션 컨텍스트(`Config::crypto_context`, 예: 세션 토큰/리소스 ID)를 주면 모든 세그먼트의
+//! AEAD 연관 데이터로 사용. 양측 컨텍스트가 다르면 복호화가 실패하므로 유효한 암호문이라도
+//! 다른 컨텍스트의 전송에 재사용할 수 없음
+//!
+//! `CryptoSession::channel_binding`은 이 핸드셰이크에만 속한 32바이트 값(TLS exporter, SSH 지문과
+//! 같은 개념)으로, 상위 인증을 세션에 묶거나 양측 값을 별도 경로로 비교해 중간자를 찾는 데 사용
+//!
+//! 암호화하지 않고 보내는 메타데이터(균일 세그먼트 표시)는 `CryptoSession::metadata_auth`의
+//! HMAC 태그로 인증. 키는 공유 비밀에서 따로 유도해 세그먼트 암호화 키와 섞이지 않음
+//!
+//! 키쌍 생성과 DH 계산은 `KeyProvider`가 맡음. 기본은 프로세스 안의 x25519-dalek
+//! (`X25519KeyProvider`)이고, `Config::with_key_provider`로 HSM/KMS 같은 외부 키 관리에 넘기면
+//! 비밀키가 프로세스 메모리에 올라오지 않음
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU64, Ordering};
 
 use chacha20poly1305::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     ChaCha20Poly1305, Nonce,
 };
+#[cfg(feature = "std")]
 use rand_core::OsRng;
+#[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "std")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
 use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "std")]
+use parking_lot::Mutex;
+
+#[cfg(feature = "std")]
+use crate::Config;
 
 /// X25519 공개키 (32 bytes)
 pub const PUBLIC_KEY_SIZE: usize = 32;
@@ -23,43 +61,71 @@ pub const NONCE_SIZE: usize = 12;
 /// ChaCha20-Poly1305 태그 크기 (16 bytes)
 pub const TAG_SIZE: usize = 16;
 
+/// 채널 바인딩 해시의 도메인 구분 라벨
+#[cfg(feature = "std")]
+const CHANNEL_BINDING_LABEL: &[u8] = b"SFP channel binding v1";
+
+/// 메타데이터 인증 키 유도 레이블
+#[cfg(feature = "std")]
+const METADATA_KEY_LABEL: &[u8] = b"SFP metadata auth v1";
+
 /// 암호화 오류
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug)]
 pub enum CryptoError {
-    #[error("키 교환 실패")]
     KeyExchangeFailed,
-    #[error("암호화 실패: {0}")]
     EncryptionFailed(String),
-    #[error("복호화 실패: {0}")]
     DecryptionFailed(String),
-    #[error("잘못된 키 크기")]
     InvalidKeySize,
-    #[error("잘못된 nonce")]
     InvalidNonce,
+    /// 외부 키 제공자의 실패 (HSM/KMS 오류 등)
+    ProviderFailed(String),
+}
+
+// no_std에서도 쓰이므로 thiserror 대신 직접 구현
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::KeyExchangeFailed => write!(f, "키 교환 실패"),
+            CryptoError::EncryptionFailed(e) => write!(f, "암호화 실패: {}", e),
+            CryptoError::DecryptionFailed(e) => write!(f, "복호화 실패: {}", e),
+            CryptoError::InvalidKeySize => write!(f, "잘못된 키 크기"),
+            CryptoError::InvalidNonce => write!(f, "잘못된 nonce"),
+            CryptoError::ProviderFailed(e) => write!(f, "키 제공자 실패: {}", e),
+        }
+    }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for CryptoError {}
+
 /// 키 교환을 위한 공개키 메시지
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyExchangeMessage {
     pub public_key: [u8; PUBLIC_KEY_SIZE],
 }
 
+#[cfg(feature = "std")]
 impl KeyExchangeMessage {
     pub fn to_bytes(&self) -> Vec<u8> {
-        bincode::serialize(self).unwrap_or_default()
+        self.public_key.to_vec()
     }
 
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        bincode::deserialize(bytes).ok()
+        Some(Self {
+            public_key: bytes.get(..PUBLIC_KEY_SIZE)?.try_into().ok()?,
+        })
     }
 }
 
 /// 서버/클라이언트 측 키쌍 (장기 키)
+#[cfg(feature = "std")]
 pub struct KeyPair {
     secret: StaticSecret,
     public: PublicKey,
 }
 
+#[cfg(feature = "std")]
 impl KeyPair {
     /// 새 키쌍 생성
     pub fn generate() -> Self {
@@ -87,11 +153,13 @@ impl KeyPair {
 }
 
 /// 임시 키쌍 (일회성 세션용)
+#[cfg(feature = "std")]
 pub struct EphemeralKeyPair {
     secret: EphemeralSecret,
     public: PublicKey,
 }
 
+#[cfg(feature = "std")]
 impl EphemeralKeyPair {
     /// 새 임시 키쌍 생성
     pub fn generate() -> Self {
@@ -118,20 +186,140 @@ impl EphemeralKeyPair {
     }
 }
 
+/// 키 제공자가 만든 임시 키쌍 (비밀키는 제공자 안에 있고 `handle`로 가리킴)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProvidedKey {
+    /// 제공자가 비밀키를 찾는 값 (HSM 키 핸들, KMS 키 버전 등)
+    pub handle: u64,
+    /// X25519 공개키
+    pub public_key: [u8; PUBLIC_KEY_SIZE],
+}
+
+/// 키 교환의 X25519 연산을 맡는 제공자
+///
+/// `KeyExchange`는 핸드셰이크마다 `generate_keypair`를 한 번, 상대 공개키를 받으면
+/// `compute_shared_secret`을 한 번 호출. 공유 비밀을 계산하지 못하고 끝난 핸드셰이크의 키는
+/// `discard`로 돌려줌
+pub trait KeyProvider: Send + Sync {
+    /// 핸드셰이크 하나에 쓸 임시 키쌍 생성
+    fn generate_keypair(&self) -> Result<ProvidedKey, CryptoError>;
+
+    /// `key`의 비밀키와 상대 공개키로 공유 비밀 계산 (이후 `key`는 다시 쓰지 않음)
+    fn compute_shared_secret(
+        &self,
+        key: &ProvidedKey,
+        peer_public: &[u8; PUBLIC_KEY_SIZE],
+    ) -> Result<[u8; 32], CryptoError>;
+
+    /// 쓰지 않고 끝난 키 폐기 (기본: 아무것도 안 함)
+    fn discard(&self, key: &ProvidedKey) {
+        let _ = key;
+    }
+}
+
+/// `Config`에 담는 공유 키 제공자 핸들 (설정 복제 시 같은 제공자를 공유)
+#[derive(Clone)]
+pub struct SharedKeyProvider(Arc<dyn KeyProvider>);
+
+impl SharedKeyProvider {
+    pub fn new(provider: impl KeyProvider + 'static) -> Self {
+        Self(Arc::new(provider))
+    }
+}
+
+impl KeyProvider for SharedKeyProvider {
+    fn generate_keypair(&self) -> Result<ProvidedKey, CryptoError> {
+        self.0.generate_keypair()
+    }
+
+    fn compute_shared_secret(
+        &self,
+        key: &ProvidedKey,
+        peer_public: &[u8; PUBLIC_KEY_SIZE],
+    ) -> Result<[u8; 32], CryptoError> {
+        self.0.compute_shared_secret(key, peer_public)
+    }
+
+    fn discard(&self, key: &ProvidedKey) {
+        self.0.discard(key)
+    }
+}
+
+impl fmt::Debug for SharedKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedKeyProvider(..)")
+    }
+}
+
+/// 프로세스 안에서 x25519-dalek로 계산하는 기본 키 제공자
+///
+/// `EphemeralKeyPair`를 핸들별로 보관하다 공유 비밀을 계산할 때 꺼내 소비
+#[cfg(feature = "std")]
+#[derive(Default)]
+pub struct X25519KeyProvider {
+    keys: Mutex<HashMap<u64, EphemeralKeyPair>>,
+    next_handle: AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl KeyProvider for X25519KeyProvider {
+    fn generate_keypair(&self) -> Result<ProvidedKey, CryptoError> {
+        let keypair = EphemeralKeyPair::generate();
+        let key = ProvidedKey {
+            handle: self.next_handle.fetch_add(1, Ordering::Relaxed),
+            public_key: keypair.public_key_bytes(),
+        };
+        self.keys.lock().insert(key.handle, keypair);
+        Ok(key)
+    }
+
+    fn compute_shared_secret(
+        &self,
+        key: &ProvidedKey,
+        peer_public: &[u8; PUBLIC_KEY_SIZE],
+    ) -> Result<[u8; 32], CryptoError> {
+        let keypair = self
+            .keys
+            .lock()
+            .remove(&key.handle)
+            .ok_or(CryptoError::KeyExchangeFailed)?;
+        Ok(keypair.compute_shared_secret(peer_public))
+    }
+
+    fn discard(&self, key: &ProvidedKey) {
+        self.keys.lock().remove(&key.handle);
+    }
+}
+
 /// 세그먼트 암호화기
 pub struct SegmentCipher {
     cipher: ChaCha20Poly1305,
     nonce_counter: u64,
+    /// 모든 세그먼트에 적용하는 AEAD 연관 데이터 (애플리케이션 컨텍스트)
+    context: Vec<u8>,
 }
 
 impl SegmentCipher {
     /// 공유 비밀로 암호화기 생성
     pub fn new(shared_secret: &[u8; 32]) -> Self {
+        Self::with_context(shared_secret, &[])
+    }
+
+    /// 애플리케이션 컨텍스트에 묶인 암호화기 생성 (빈 컨텍스트 = 컨텍스트 없음)
+    pub fn with_context(shared_secret: &[u8; 32], context: &[u8]) -> Self {
         let cipher = ChaCha20Poly1305::new_from_slice(shared_secret)
             .expect("Invalid key size");
         Self {
             cipher,
             nonce_counter: 0,
+            context: context.to_vec(),
+        }
+    }
+
+    fn payload<'a>(&'a self, msg: &'a [u8]) -> Payload<'a, 'a> {
+        Payload {
+            msg,
+            aad: &self.context,
         }
     }
 
@@ -161,7 +349,7 @@ impl SegmentCipher {
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         let ciphertext = self.cipher
-            .encrypt(nonce, plaintext)
+            .encrypt(nonce, self.payload(plaintext))
             .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))?;
 
         // nonce + ciphertext 형태로 반환
@@ -182,10 +370,45 @@ impl SegmentCipher {
         let ciphertext = &encrypted[NONCE_SIZE..];
 
         self.cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(nonce, self.payload(ciphertext))
             .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))
     }
 
+    /// 암호문이 `block`의 배수가 되도록 평문을 채워 암호화 (패딩도 AEAD로 보호)
+    ///
+    /// 평문 뒤에 0x80 한 바이트와 0을 붙인 뒤 봉인. `block`에 청크 크기를 주면 세그먼트의
+    /// 마지막 청크도 꽉 차 데이터그램 크기로 세그먼트 경계가 드러나지 않음 (`Config::pad_datagrams`).
+    /// `block`이 0이면 표시 바이트만 붙임
+    pub fn encrypt_segment_padded(
+        &mut self,
+        segment_id: u64,
+        plaintext: &[u8],
+        block: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let sealed_len = NONCE_SIZE + plaintext.len() + 1 + TAG_SIZE;
+        let padded_len = match block {
+            0 => sealed_len,
+            block => sealed_len.div_ceil(block) * block,
+        };
+        let mut padded = Vec::with_capacity(padded_len - NONCE_SIZE - TAG_SIZE);
+        padded.extend_from_slice(plaintext);
+        padded.push(0x80);
+        padded.resize(padded_len - NONCE_SIZE - TAG_SIZE, 0);
+        self.encrypt_segment(segment_id, &padded)
+    }
+
+    /// `encrypt_segment_padded`로 만든 세그먼트 복호화 (인증 후 패딩 제거)
+    pub fn decrypt_segment_padded(&self, encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let mut plaintext = self.decrypt_segment(encrypted)?;
+        let end = plaintext
+            .iter()
+            .rposition(|&b| b != 0)
+            .filter(|&i| plaintext[i] == 0x80)
+            .ok_or_else(|| CryptoError::DecryptionFailed("패딩 표시 없음".into()))?;
+        plaintext.truncate(end);
+        Ok(plaintext)
+    }
+
     /// segment_id와 counter로 특정 세그먼트 복호화
     pub fn decrypt_segment_with_id(
         &self,
@@ -197,12 +420,13 @@ impl SegmentCipher {
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         self.cipher
-            .decrypt(nonce, ciphertext)
+            .decrypt(nonce, self.payload(ciphertext))
             .map_err(|e| CryptoError::DecryptionFailed(e.to_string()))
     }
 }
 
 /// 암호화 세션 (양방향)
+#[cfg(feature = "std")]
 pub struct CryptoSession {
     /// 세그먼트 암호화/복호화기
     pub cipher: SegmentCipher,
@@ -210,10 +434,16 @@ pub struct CryptoSession {
     pub local_public_key: [u8; PUBLIC_KEY_SIZE],
     /// 상대방 공개키
     pub peer_public_key: [u8; PUBLIC_KEY_SIZE],
+    /// 채널 바인딩 값 (공유 비밀은 보관하지 않음)
+    channel_binding: [u8; 32],
+    /// 메타데이터 인증 키 (공유 비밀에서 유도)
+    metadata_key: [u8; 32],
 }
 
+#[cfg(feature = "std")]
 impl CryptoSession {
     /// 새 세션 시작 (키쌍 생성)
+    #[allow(clippy::new_ret_no_self)]
     pub fn new() -> (EphemeralKeyPair, [u8; PUBLIC_KEY_SIZE]) {
         let keypair = EphemeralKeyPair::generate();
         let public_key = keypair.public_key_bytes();
@@ -224,18 +454,70 @@ impl CryptoSession {
     pub fn establish(
         keypair: EphemeralKeyPair,
         peer_public_key: [u8; PUBLIC_KEY_SIZE],
+    ) -> Self {
+        Self::establish_with_context(keypair, peer_public_key, &[])
+    }
+
+    /// 키 교환 완료 및 애플리케이션 컨텍스트에 묶인 세션 생성
+    pub fn establish_with_context(
+        keypair: EphemeralKeyPair,
+        peer_public_key: [u8; PUBLIC_KEY_SIZE],
+        context: &[u8],
     ) -> Self {
         let local_public_key = keypair.public_key_bytes();
         let shared_secret = keypair.compute_shared_secret(&peer_public_key);
-        let cipher = SegmentCipher::new(&shared_secret);
+        Self::from_shared_secret(local_public_key, peer_public_key, &shared_secret, context)
+    }
+
+    /// 키 제공자의 키로 세션 생성 (공유 비밀 계산을 제공자에 맡김)
+    pub fn establish_with_provider(
+        provider: &dyn KeyProvider,
+        key: &ProvidedKey,
+        peer_public_key: [u8; PUBLIC_KEY_SIZE],
+        context: &[u8],
+    ) -> Result<Self, CryptoError> {
+        let shared_secret = provider.compute_shared_secret(key, &peer_public_key)?;
+        Ok(Self::from_shared_secret(
+            key.public_key,
+            peer_public_key,
+            &shared_secret,
+            context,
+        ))
+    }
+
+    fn from_shared_secret(
+        local_public_key: [u8; PUBLIC_KEY_SIZE],
+        peer_public_key: [u8; PUBLIC_KEY_SIZE],
+        shared_secret: &[u8; 32],
+        context: &[u8],
+    ) -> Self {
+        let cipher = SegmentCipher::with_context(shared_secret, context);
+        let channel_binding =
+            channel_binding(&local_public_key, &peer_public_key, shared_secret, context);
+        let metadata_key = metadata_key(shared_secret, context);
 
         Self {
             cipher,
             local_public_key,
             peer_public_key,
+            channel_binding,
+            metadata_key,
         }
     }
 
+    /// 이 핸드셰이크의 채널 바인딩 값
+    ///
+    /// 두 공개키(순서 무관), 공유 비밀, 애플리케이션 컨텍스트의 SHA-256이라 양측이 같은 값을 얻고,
+    /// 키 자체는 드러나지 않음. 중간자가 끼어들면 양측이 서로 다른 키로 협상하므로 값이 달라짐
+    pub fn channel_binding(&self) -> [u8; 32] {
+        self.channel_binding
+    }
+
+    /// 암호화하지 않는 메타데이터의 인증기 (`Sender::set_metadata_auth`/`Receiver::set_metadata_auth`)
+    pub fn metadata_auth(&self) -> MetadataAuth {
+        MetadataAuth::new(&self.metadata_key)
+    }
+
     /// 세그먼트 암호화
     pub fn encrypt(&mut self, segment_id: u64, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
         self.cipher.encrypt_segment(segment_id, data)
@@ -245,8 +527,101 @@ impl CryptoSession {
     pub fn decrypt(&self, encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
         self.cipher.decrypt_segment(encrypted)
     }
+
+    /// 암호문이 `block`(보통 청크 크기)의 배수가 되도록 패딩해 세그먼트 암호화
+    pub fn encrypt_padded(
+        &mut self,
+        segment_id: u64,
+        data: &[u8],
+        block: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.cipher.encrypt_segment_padded(segment_id, data, block)
+    }
+
+    /// `encrypt_padded`로 만든 세그먼트 복호화
+    pub fn decrypt_padded(&self, encrypted: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.cipher.decrypt_segment_padded(encrypted)
+    }
+}
+
+/// 채널 바인딩 값 계산 (공개키는 정렬해 양측 순서를 맞춤)
+#[cfg(feature = "std")]
+fn channel_binding(
+    local_public_key: &[u8; PUBLIC_KEY_SIZE],
+    peer_public_key: &[u8; PUBLIC_KEY_SIZE],
+    shared_secret: &[u8; 32],
+    context: &[u8],
+) -> [u8; 32] {
+    let (first, second) = match local_public_key <= peer_public_key {
+        true => (local_public_key, peer_public_key),
+        false => (peer_public_key, local_public_key),
+    };
+    let mut hasher = Sha256::new();
+    hasher.update(CHANNEL_BINDING_LABEL);
+    hasher.update(first);
+    hasher.update(second);
+    hasher.update(shared_secret);
+    hasher.update((context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.finalize().into()
 }
 
+/// 메타데이터 인증 키 계산 (양측이 같은 공유 비밀/컨텍스트로 같은 키를 얻음)
+#[cfg(feature = "std")]
+fn metadata_key(shared_secret: &[u8; 32], context: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(METADATA_KEY_LABEL);
+    hasher.update(shared_secret);
+    hasher.update((context.len() as u64).to_le_bytes());
+    hasher.update(context);
+    hasher.finalize().into()
+}
+
+/// 메타데이터 인증기
+///
+/// 암호화하지 않고 보내는 메타데이터에 HMAC-SHA256의 앞 16바이트를 태그로 붙임.
+/// 태그 비교는 상수 시간
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct MetadataAuth {
+    key: [u8; 32],
+}
+
+#[cfg(feature = "std")]
+impl MetadataAuth {
+    pub fn new(key: &[u8; 32]) -> Self {
+        Self { key: *key }
+    }
+
+    fn mac(&self, data: &[u8]) -> Hmac<Sha256> {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(&self.key).expect("HMAC은 모든 키 길이 허용");
+        mac.update(data);
+        mac
+    }
+
+    /// `data`의 인증 태그
+    pub fn tag(&self, data: &[u8]) -> [u8; TAG_SIZE] {
+        let mut tag = [0u8; TAG_SIZE];
+        tag.copy_from_slice(&self.mac(data).finalize().into_bytes()[..TAG_SIZE]);
+        tag
+    }
+
+    /// 태그 검증
+    pub fn verify(&self, data: &[u8], tag: &[u8; TAG_SIZE]) -> bool {
+        self.mac(data).verify_truncated_left(tag).is_ok()
+    }
+}
+
+// 키가 로그에 남지 않도록 직접 구현
+#[cfg(feature = "std")]
+impl fmt::Debug for MetadataAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MetadataAuth(..)")
+    }
+}
+
+#[cfg(feature = "std")]
 impl Default for CryptoSession {
     fn default() -> Self {
         // 테스트용 기본 세션 (실제 사용 시 키 교환 필요)
@@ -256,6 +631,222 @@ impl Default for CryptoSession {
     }
 }
 
+/// 키 교환 진행기 (전송 계층과 분리: 호출자가 받은 데이터와 현재 시각으로 구동)
+///
+/// - 개시자(서버): 상대 공개키를 받을 때까지 재전송 간격마다 자신의 공개키 재전송
+/// - 응답자(클라이언트): 상대 공개키를 받으면 세션 수립 후 응답, 같은 키가 다시 오면
+///   응답이 유실된 것으로 보고 재응답. 상대가 응답을 받았음이 확인(`confirm`)될 때까지 기한 적용
+/// - 기한이 지나거나 재전송 횟수를 소진하면 `Error::KeyExchangeTimeout`
+/// - 키 제공자가 실패하면 다음 `poll_transmit`에서 `Error::Crypto`
+#[cfg(feature = "std")]
+pub struct KeyExchange {
+    provider: SharedKeyProvider,
+    /// 공유 비밀을 계산하기 전의 키
+    key: Option<ProvidedKey>,
+    /// 아직 알리지 않은 키 제공자 실패
+    error: Option<CryptoError>,
+    message: Vec<u8>,
+    initiator: bool,
+    peer_public_key: Option<[u8; PUBLIC_KEY_SIZE]>,
+    reply_pending: bool,
+    confirmed: bool,
+    attempts: u32,
+    max_retries: u32,
+    retry_interval: Duration,
+    next_send: Instant,
+    deadline: Instant,
+    /// 수립할 세션의 애플리케이션 컨텍스트
+    context: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl KeyExchange {
+    /// 개시자 (공개키를 먼저 보내는 쪽)
+    pub fn initiator(config: &Config, now: Instant) -> Self {
+        Self::from_config(true, config, now)
+    }
+
+    /// 응답자 (상대 공개키를 받은 뒤 응답하는 쪽)
+    pub fn responder(config: &Config, now: Instant) -> Self {
+        Self::from_config(false, config, now)
+    }
+
+    /// 설정의 기한/컨텍스트/키 제공자 (없으면 `X25519KeyProvider`) 적용
+    fn from_config(initiator: bool, config: &Config, now: Instant) -> Self {
+        let provider = config
+            .key_provider
+            .clone()
+            .unwrap_or_else(|| SharedKeyProvider::new(X25519KeyProvider::default()));
+        Self::with_provider(
+            initiator,
+            Duration::from_millis(config.key_exchange_retry_ms),
+            config.key_exchange_max_retries,
+            Duration::from_millis(config.key_exchange_timeout_ms),
+            provider,
+            now,
+        )
+        .with_context(config.crypto_context.as_deref().unwrap_or_default())
+    }
+
+    /// 재전송 간격/횟수/전체 기한 직접 지정
+    pub fn with_limits(
+        initiator: bool,
+        retry_interval: Duration,
+        max_retries: u32,
+        timeout: Duration,
+        now: Instant,
+    ) -> Self {
+        let provider = SharedKeyProvider::new(X25519KeyProvider::default());
+        Self::with_provider(initiator, retry_interval, max_retries, timeout, provider, now)
+    }
+
+    /// 재전송 간격/횟수/전체 기한과 키 제공자 직접 지정
+    pub fn with_provider(
+        initiator: bool,
+        retry_interval: Duration,
+        max_retries: u32,
+        timeout: Duration,
+        provider: SharedKeyProvider,
+        now: Instant,
+    ) -> Self {
+        let (key, error) = match provider.generate_keypair() {
+            Ok(key) => (Some(key), None),
+            Err(e) => (None, Some(e)),
+        };
+        let message = key
+            .map(|key| {
+                KeyExchangeMessage {
+                    public_key: key.public_key,
+                }
+                .to_bytes()
+            })
+            .unwrap_or_default();
+        Self {
+            provider,
+            key,
+            error,
+            message,
+            initiator,
+            peer_public_key: None,
+            reply_pending: false,
+            confirmed: false,
+            attempts: 0,
+            max_retries,
+            retry_interval: retry_interval.max(Duration::from_millis(1)),
+            next_send: now,
+            deadline: now + timeout,
+            context: Vec::new(),
+        }
+    }
+
+    /// 수립할 세션을 애플리케이션 컨텍스트에 묶음 (양측이 같은 값을 써야 복호화 성공)
+    pub fn with_context(mut self, context: &[u8]) -> Self {
+        self.context = context.to_vec();
+        self
+    }
+
+    /// 세션 수립 여부
+    pub fn is_established(&self) -> bool {
+        self.peer_public_key.is_some()
+    }
+
+    /// 상대가 우리 공개키를 받았음을 확인 (예: 응답자가 InitAck 수신). 이후 기한 해제
+    pub fn confirm(&mut self) {
+        self.confirmed = true;
+    }
+
+    /// 지금 보낼 공개키 메시지. 확인 전 기한/재전송 횟수 초과 시 `KeyExchangeTimeout`
+    pub fn poll_transmit(&mut self, now: Instant) -> crate::Result<Option<Vec<u8>>> {
+        if let Some(e) = self.error.take() {
+            return Err(e.into());
+        }
+        if self.confirmed {
+            return Ok(None);
+        }
+        if now >= self.deadline {
+            return Err(crate::Error::KeyExchangeTimeout {
+                attempts: self.attempts,
+            });
+        }
+        if self.is_established() {
+            let reply = core::mem::take(&mut self.reply_pending);
+            if reply {
+                self.attempts += 1;
+            }
+            return Ok(reply.then(|| self.message.clone()));
+        }
+        if !self.initiator || now < self.next_send {
+            return Ok(None);
+        }
+        if self.attempts > self.max_retries {
+            return Err(crate::Error::KeyExchangeTimeout {
+                attempts: self.attempts,
+            });
+        }
+
+        self.attempts += 1;
+        self.next_send = now + self.retry_interval;
+        Ok(Some(self.message.clone()))
+    }
+
+    /// 다음 `poll_transmit`까지 대기할 시간 (수신 대기 타임아웃으로 사용)
+    pub fn poll_timeout(&self, now: Instant) -> Duration {
+        if self.confirmed {
+            return self.retry_interval;
+        }
+        let wake = if self.initiator && !self.is_established() {
+            self.next_send.min(self.deadline)
+        } else {
+            self.deadline
+        };
+        wake.saturating_duration_since(now)
+    }
+
+    /// 수신 데이터 처리. 상대 공개키를 처음 받으면 세션 반환
+    pub fn on_datagram(&mut self, data: &[u8]) -> Option<CryptoSession> {
+        let msg = KeyExchangeMessage::from_bytes(data)?;
+        match self.peer_public_key {
+            Some(peer) => {
+                // 응답자: 상대가 재전송 중이면 우리 응답이 유실된 것
+                if !self.initiator && peer == msg.public_key {
+                    self.reply_pending = true;
+                }
+                None
+            }
+            None => {
+                let key = self.key.take()?;
+                let session = CryptoSession::establish_with_provider(
+                    &self.provider,
+                    &key,
+                    msg.public_key,
+                    &self.context,
+                );
+                match session {
+                    Ok(session) => {
+                        self.peer_public_key = Some(msg.public_key);
+                        self.reply_pending = !self.initiator;
+                        self.confirmed = self.initiator;
+                        Some(session)
+                    }
+                    Err(e) => {
+                        self.error = Some(e);
+                        None
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for KeyExchange {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.provider.discard(&key);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -276,6 +867,23 @@ mod tests {
         assert_eq!(alice_shared, bob_shared);
     }
 
+    #[test]
+    fn test_padded_segment_round_trip() {
+        let mut cipher = SegmentCipher::new(&[5u8; 32]);
+        for (len, block) in [(0usize, 1000usize), (971, 1000), (972, 1000), (10_500, 1000), (7, 0)] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let sealed = cipher.encrypt_segment_padded(1, &plaintext, block).unwrap();
+            if block > 0 {
+                assert_eq!(sealed.len() % block, 0);
+            }
+            assert_eq!(cipher.decrypt_segment_padded(&sealed).unwrap(), plaintext);
+        }
+
+        // 표시 바이트 없이 봉인한 평문은 거부
+        let sealed = cipher.encrypt_segment(1, &[1, 2, 0, 0]).unwrap();
+        assert!(cipher.decrypt_segment_padded(&sealed).is_err());
+    }
+
     #[test]
     fn test_encrypt_decrypt() {
         // 세션 설정
@@ -301,6 +909,90 @@ mod tests {
         assert_eq!(plaintext.as_slice(), decrypted.as_slice());
     }
 
+    #[test]
+    fn test_channel_binding_detects_mitm() {
+        let alice = EphemeralKeyPair::generate();
+        let bob = EphemeralKeyPair::generate();
+        let (alice_public, bob_public) = (alice.public_key_bytes(), bob.public_key_bytes());
+
+        // 정상 핸드셰이크: 양측 값이 같음
+        let alice_session = CryptoSession::establish_with_context(alice, bob_public, b"ctx");
+        let bob_session = CryptoSession::establish_with_context(bob, alice_public, b"ctx");
+        assert_eq!(alice_session.channel_binding(), bob_session.channel_binding());
+        assert_ne!(alice_session.channel_binding(), [0u8; 32]);
+
+        // 중간자가 양쪽과 따로 키를 교환하면 Alice와 Bob의 값이 다름
+        let alice = EphemeralKeyPair::generate();
+        let bob = EphemeralKeyPair::generate();
+        let mallory_a = EphemeralKeyPair::generate();
+        let mallory_b = EphemeralKeyPair::generate();
+        let (alice_public, bob_public) = (alice.public_key_bytes(), bob.public_key_bytes());
+        let alice_session = CryptoSession::establish(alice, mallory_a.public_key_bytes());
+        let bob_session = CryptoSession::establish(bob, mallory_b.public_key_bytes());
+        let mallory_alice = CryptoSession::establish(mallory_a, alice_public);
+        let mallory_bob = CryptoSession::establish(mallory_b, bob_public);
+        assert_ne!(alice_session.channel_binding(), bob_session.channel_binding());
+        assert_eq!(alice_session.channel_binding(), mallory_alice.channel_binding());
+        assert_eq!(bob_session.channel_binding(), mallory_bob.channel_binding());
+
+        // 같은 키라도 컨텍스트가 다르면 다른 값
+        let secret = [7u8; 32];
+        assert_ne!(
+            channel_binding(&alice_public, &bob_public, &secret, b"token-1"),
+            channel_binding(&alice_public, &bob_public, &secret, b"token-2")
+        );
+        assert_eq!(
+            channel_binding(&alice_public, &bob_public, &secret, b""),
+            channel_binding(&bob_public, &alice_public, &secret, b"")
+        );
+    }
+
+    #[test]
+    fn test_metadata_auth_shared_by_both_sides() {
+        use crate::message::{
+            HeartbeatMessage, PathChallengeMessage, PathResponseMessage, SparseSegmentMessage,
+        };
+
+        let alice = EphemeralKeyPair::generate();
+        let bob = EphemeralKeyPair::generate();
+        let (alice_public, bob_public) = (alice.public_key_bytes(), bob.public_key_bytes());
+        let alice_session = CryptoSession::establish_with_context(alice, bob_public, b"ctx");
+        let bob_session = CryptoSession::establish_with_context(bob, alice_public, b"ctx");
+
+        // 송신측이 붙인 태그를 수신측 키로 확인
+        let marker = SparseSegmentMessage::new(9, 5, 1, 65536, 0)
+            .authenticated(&alice_session.metadata_auth());
+        assert!(marker.verify(&bob_session.metadata_auth()));
+
+        // 필드를 바꾸거나 다른 세션/컨텍스트의 키면 실패
+        let tampered = SparseSegmentMessage { fill: 0xFF, ..marker };
+        assert!(!tampered.verify(&bob_session.metadata_auth()));
+        let replayed = SparseSegmentMessage { connection_id: 10, ..marker };
+        assert!(!replayed.verify(&bob_session.metadata_auth()));
+        assert!(!marker.verify(&CryptoSession::default().metadata_auth()));
+
+        // 주소 이전 응답과 그 계기가 되는 Heartbeat도 같은 키로 확인 (토큰/연결 ID까지 덮음)
+        let challenge = PathChallengeMessage::new(9, 0xDEAD);
+        let response =
+            PathResponseMessage::answer(&challenge).authenticated(&bob_session.metadata_auth());
+        assert!(response.verify(&alice_session.metadata_auth()));
+        assert!(!PathResponseMessage { token: 0xBEEF, ..response }
+            .verify(&alice_session.metadata_auth()));
+        assert!(!PathResponseMessage::answer(&challenge).verify(&alice_session.metadata_auth()));
+        let heartbeat = HeartbeatMessage::new(3)
+            .with_connection_id(9)
+            .authenticated(&bob_session.metadata_auth());
+        assert!(heartbeat.verify(&alice_session.metadata_auth()));
+        assert!(!HeartbeatMessage { sequence: 4, ..heartbeat.clone() }
+            .verify(&alice_session.metadata_auth()));
+
+        let secret = [7u8; 32];
+        let auth = MetadataAuth::new(&metadata_key(&secret, b"token-1"));
+        let tag = auth.tag(b"data");
+        assert!(auth.verify(b"data", &tag));
+        assert!(!MetadataAuth::new(&metadata_key(&secret, b"token-2")).verify(b"data", &tag));
+    }
+
     #[test]
     fn test_large_segment_encryption() {
         let alice_keypair = EphemeralKeyPair::generate();
@@ -320,4 +1012,185 @@ mod tests {
 
         assert_eq!(plaintext, decrypted);
     }
+
+    #[test]
+    fn test_key_exchange_times_out_when_peer_silent() {
+        let t0 = Instant::now();
+        let retry = Duration::from_millis(100);
+        let mut exchange = KeyExchange::with_limits(true, retry, 3, Duration::from_secs(2), t0);
+
+        // 첫 전송 + 재전송 3회 후, 상대 공개키가 없으면 기한(2초) 전에 중단
+        let mut sent = 0;
+        let mut now = t0;
+        let err = loop {
+            match exchange.poll_transmit(now) {
+                Ok(Some(_)) => sent += 1,
+                Ok(None) => {}
+                Err(e) => break e,
+            }
+            now += exchange.poll_timeout(now);
+        };
+        assert!(matches!(err, crate::Error::KeyExchangeTimeout { attempts: 4 }));
+        assert_eq!(sent, 4);
+        assert!(now - t0 < Duration::from_secs(2), "{:?}", now - t0);
+
+        // 재전송 횟수가 남아 있어도 전체 기한은 넘지 않음
+        let mut exchange = KeyExchange::with_limits(true, retry, 100, Duration::from_millis(350), t0);
+        let mut now = t0;
+        while exchange.poll_transmit(now).is_ok() {
+            now += exchange.poll_timeout(now).max(Duration::from_millis(1));
+        }
+        assert_eq!(now - t0, Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_key_exchange_recovers_lost_reply() {
+        let t0 = Instant::now();
+        let config = Config::default();
+        let mut server = KeyExchange::initiator(&config, t0);
+        let mut client = KeyExchange::responder(&config, t0);
+
+        // 응답자는 상대 공개키 전에는 보내지 않음
+        assert!(client.poll_transmit(t0).unwrap().is_none());
+
+        let server_key = server.poll_transmit(t0).unwrap().unwrap();
+        let mut client_session = client.on_datagram(&server_key).unwrap();
+        // 첫 응답 유실
+        assert!(client.poll_transmit(t0).unwrap().is_some());
+
+        // 서버 재전송 → 클라이언트 재응답
+        let retry = t0 + Duration::from_millis(config.key_exchange_retry_ms);
+        let server_key = server.poll_transmit(retry).unwrap().unwrap();
+        assert!(client.on_datagram(&server_key).is_none());
+        let reply = client.poll_transmit(retry).unwrap().unwrap();
+        let server_session = server.on_datagram(&reply).unwrap();
+
+        // 확인 후에는 기한이 지나도 오류 없음
+        let late = t0 + Duration::from_millis(config.key_exchange_timeout_ms * 2);
+        assert!(server.poll_transmit(late).unwrap().is_none());
+        client.confirm();
+        assert!(client.poll_transmit(late).unwrap().is_none());
+
+        let encrypted = client_session.encrypt(1, b"hello").unwrap();
+        assert_eq!(server_session.decrypt(&encrypted).unwrap(), b"hello");
+    }
+
+    #[test]
+    fn test_crypto_context_binds_transfer() {
+        let t0 = Instant::now();
+        let exchange = |server_context: &[u8], client_context: &[u8]| {
+            let server_config = Config {
+                crypto_context: Some(server_context.to_vec()),
+                ..Config::default()
+            };
+            let client_config = Config {
+                crypto_context: Some(client_context.to_vec()),
+                ..Config::default()
+            };
+            let mut server = KeyExchange::initiator(&server_config, t0);
+            let mut client = KeyExchange::responder(&client_config, t0);
+            let server_key = server.poll_transmit(t0).unwrap().unwrap();
+            let client_session = client.on_datagram(&server_key).unwrap();
+            let reply = client.poll_transmit(t0).unwrap().unwrap();
+            (server.on_datagram(&reply).unwrap(), client_session)
+        };
+
+        // 같은 컨텍스트: 정상 복호화
+        let (mut server, client) = exchange(b"token-1", b"token-1");
+        let encrypted = server.encrypt(1, b"payload").unwrap();
+        assert_eq!(client.decrypt(&encrypted).unwrap(), b"payload");
+
+        // 다른 컨텍스트: 키가 같아도 복호화 실패
+        let (mut server, client) = exchange(b"token-1", b"token-2");
+        let encrypted = server.encrypt(1, b"payload").unwrap();
+        assert!(client.decrypt(&encrypted).is_err());
+
+        // 컨텍스트 없는 세션과도 섞이지 않음
+        let (mut server, client) = exchange(b"token-1", b"");
+        let encrypted = server.encrypt(1, b"payload").unwrap();
+        assert!(client.decrypt(&encrypted).is_err());
+    }
+
+    /// 비밀키를 자기 쪽에만 두고 호출을 기록하는 HSM 흉내
+    #[derive(Default)]
+    struct MockHsm {
+        secrets: Mutex<HashMap<u64, StaticSecret>>,
+        generated: AtomicU64,
+        computed: AtomicU64,
+        discarded: AtomicU64,
+        fail: bool,
+    }
+
+    impl KeyProvider for Arc<MockHsm> {
+        fn generate_keypair(&self) -> Result<ProvidedKey, CryptoError> {
+            if self.fail {
+                return Err(CryptoError::ProviderFailed("slot locked".into()));
+            }
+            let handle = self.generated.fetch_add(1, Ordering::SeqCst);
+            let secret = StaticSecret::random_from_rng(OsRng);
+            let public_key = *PublicKey::from(&secret).as_bytes();
+            self.secrets.lock().insert(handle, secret);
+            Ok(ProvidedKey { handle, public_key })
+        }
+
+        fn compute_shared_secret(
+            &self,
+            key: &ProvidedKey,
+            peer_public: &[u8; PUBLIC_KEY_SIZE],
+        ) -> Result<[u8; 32], CryptoError> {
+            self.computed.fetch_add(1, Ordering::SeqCst);
+            let secrets = self.secrets.lock();
+            let secret = secrets.get(&key.handle).ok_or(CryptoError::KeyExchangeFailed)?;
+            Ok(*secret.diffie_hellman(&PublicKey::from(*peer_public)).as_bytes())
+        }
+
+        fn discard(&self, key: &ProvidedKey) {
+            self.discarded.fetch_add(1, Ordering::SeqCst);
+            self.secrets.lock().remove(&key.handle);
+        }
+    }
+
+    #[test]
+    fn test_key_exchange_delegates_to_key_provider() {
+        let t0 = Instant::now();
+        let hsm = Arc::new(MockHsm::default());
+        let server_config = Config::default().with_key_provider(hsm.clone());
+        let client_config = Config::default();
+
+        // 서버는 HSM 키로, 클라이언트는 기본 x25519-dalek로 교환
+        let mut server = KeyExchange::initiator(&server_config, t0);
+        let mut client = KeyExchange::responder(&client_config, t0);
+        let server_key = server.poll_transmit(t0).unwrap().unwrap();
+        let sent = KeyExchangeMessage::from_bytes(&server_key).unwrap().public_key;
+        let hsm_public = *PublicKey::from(&hsm.secrets.lock()[&0]).as_bytes();
+        assert_eq!(sent, hsm_public);
+
+        let client_session = client.on_datagram(&server_key).unwrap();
+        let reply = client.poll_transmit(t0).unwrap().unwrap();
+        let mut server_session = server.on_datagram(&reply).unwrap();
+        assert_eq!(hsm.generated.load(Ordering::SeqCst), 1);
+        assert_eq!(hsm.computed.load(Ordering::SeqCst), 1);
+        assert_eq!(server_session.local_public_key, hsm_public);
+        assert_eq!(server_session.channel_binding(), client_session.channel_binding());
+
+        let encrypted = server_session.encrypt(1, b"payload").unwrap();
+        assert_eq!(client_session.decrypt(&encrypted).unwrap(), b"payload");
+
+        // 끝나지 않은 핸드셰이크의 키는 제공자에게 돌려줌
+        drop(KeyExchange::initiator(&server_config, t0));
+        assert_eq!(hsm.discarded.load(Ordering::SeqCst), 1);
+        assert_eq!(hsm.secrets.lock().len(), 1);
+
+        // 제공자 실패는 다음 전송 시점에 에러로
+        let locked = Arc::new(MockHsm {
+            fail: true,
+            ..MockHsm::default()
+        });
+        let config = Config::default().with_key_provider(locked);
+        let mut failed = KeyExchange::initiator(&config, t0);
+        assert!(matches!(
+            failed.poll_transmit(t0),
+            Err(crate::Error::Crypto(CryptoError::ProviderFailed(_)))
+        ));
+    }
 }
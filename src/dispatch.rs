@@ -0,0 +1,160 @@
+//! 수신 데이터그램의 워커별 분배
+//!
+//! - 수신 태스크 하나가 데이터그램을 받아 세그먼트 ID로 워커 큐를 골라 넣음
+//! - 같은 세그먼트의 청크는 항상 같은 워커로 가므로 워커는 자기 세그먼트 맵만 다루고
+//!   워커 간 잠금이 필요 없음 (공유 수신 큐를 `Mutex`로 나눠 읽을 때의 직렬화 제거)
+//! - 청크 묶음(`ChunkBatch`)은 레코드별로 나눠 각 세그먼트의 워커로 보냄
+//! - 청크가 아닌 데이터그램(컨트롤 메시지)은 호출자에게 돌려줌
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tokio::sync::mpsc;
+
+use crate::chunk::{Chunk, ChunkBatch, SegmentId};
+use crate::stats::QueueDepth;
+
+/// 분배 결과
+#[derive(Debug, PartialEq, Eq)]
+pub enum Dispatched {
+    /// 워커 큐에 넣음
+    Queued,
+    /// 워커 큐가 가득 찼거나 닫혀 버림 (묶음은 레코드 하나라도 버리면)
+    Dropped,
+    /// 청크가 아님 (호출자가 처리)
+    NotChunk(Vec<u8>),
+}
+
+/// 세그먼트 ID 기준 워커 큐 분배기
+#[derive(Debug)]
+pub struct ShardedDispatcher {
+    shards: Vec<mpsc::Sender<Vec<u8>>>,
+    dropped: AtomicU64,
+}
+
+impl ShardedDispatcher {
+    /// `workers`개 워커 큐 생성 (큐마다 `capacity`개). 반환된 수신기를 워커마다 하나씩 넘김
+    pub fn new(workers: usize, capacity: usize) -> (Self, Vec<mpsc::Receiver<Vec<u8>>>) {
+        let (shards, receivers) = (0..workers.max(1))
+            .map(|_| mpsc::channel(capacity.max(1)))
+            .unzip();
+        (
+            Self {
+                shards,
+                dropped: AtomicU64::new(0),
+            },
+            receivers,
+        )
+    }
+
+    /// 워커 수
+    pub fn workers(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// 세그먼트를 맡는 워커 번호
+    pub fn shard_of(&self, segment_id: SegmentId) -> usize {
+        (segment_id % self.shards.len() as u64) as usize
+    }
+
+    /// 데이터그램을 세그먼트 담당 워커 큐에 넣음 (대기하지 않음)
+    pub fn dispatch(&self, datagram: Vec<u8>) -> Dispatched {
+        if ChunkBatch::has_magic(&datagram) {
+            return self.dispatch_batch(datagram);
+        }
+        let Some(segment_id) = Chunk::peek_segment_id(&datagram) else {
+            return Dispatched::NotChunk(datagram);
+        };
+        match self.shards[self.shard_of(segment_id)].try_send(datagram) {
+            Ok(()) => Dispatched::Queued,
+            Err(_) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Dispatched::Dropped
+            }
+        }
+    }
+
+    fn dispatch_batch(&self, datagram: Vec<u8>) -> Dispatched {
+        let Some(records) = ChunkBatch::records(&datagram) else {
+            return Dispatched::NotChunk(datagram);
+        };
+        let mut result = Dispatched::Queued;
+        for record in records {
+            if Chunk::peek_segment_id(record).is_none() {
+                continue;
+            }
+            if self.dispatch(record.to_vec()) == Dispatched::Dropped {
+                result = Dispatched::Dropped;
+            }
+        }
+        result
+    }
+
+    /// 워커 큐가 가득 차 버린 데이터그램 수
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// 워커 큐별 적재량 (`dispatch`, 순번 = 워커 번호)
+    pub fn queue_depths(&self) -> Vec<QueueDepth> {
+        self.shards
+            .iter()
+            .enumerate()
+            .map(|(worker, tx)| QueueDepth::of("dispatch", worker, tx))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::SegmentBuilder;
+    use crate::message::{MessageHeader, MessageType};
+
+    #[test]
+    fn test_segment_chunks_stay_on_one_worker() {
+        let (dispatcher, mut receivers) = ShardedDispatcher::new(4, 64);
+        let builder = SegmentBuilder::new(100);
+        for segment_id in 1..=8u64 {
+            for chunk in builder.split_into_chunks(segment_id, &[segment_id as u8; 500], 0) {
+                assert_eq!(dispatcher.dispatch(chunk.to_bytes()), Dispatched::Queued);
+            }
+        }
+
+        for (worker, rx) in receivers.iter_mut().enumerate() {
+            let mut count = 0;
+            while let Ok(datagram) = rx.try_recv() {
+                let chunk = Chunk::from_bytes(&datagram).unwrap();
+                assert_eq!(dispatcher.shard_of(chunk.header.segment_id), worker);
+                count += 1;
+            }
+            // 워커마다 세그먼트 2개 × 청크 5개
+            assert_eq!(count, 10);
+        }
+
+        // 묶음은 레코드별로 풀어 각 세그먼트 워커로
+        let records: Vec<Vec<u8>> = (1..=4u64)
+            .map(|id| builder.split_into_chunks(id, &[0u8; 50], 0).remove(0).to_bytes())
+            .collect();
+        let batch = ChunkBatch::encode(records.iter().map(Vec::as_slice));
+        assert_eq!(dispatcher.dispatch(batch), Dispatched::Queued);
+        for (worker, rx) in receivers.iter_mut().enumerate() {
+            let datagram = rx.try_recv().unwrap();
+            let chunk = Chunk::from_bytes(&datagram).unwrap();
+            assert_eq!(dispatcher.shard_of(chunk.header.segment_id), worker);
+        }
+
+        // 컨트롤 메시지는 돌려받음
+        let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+        assert_eq!(dispatcher.dispatch(close.clone()), Dispatched::NotChunk(close));
+
+        // 가득 찬 큐는 기다리지 않고 버림
+        let (dispatcher, _receivers) = ShardedDispatcher::new(1, 1);
+        let chunk = builder.split_into_chunks(1, &[0u8; 100], 0).remove(0);
+        assert_eq!(dispatcher.dispatch(chunk.to_bytes()), Dispatched::Queued);
+        assert_eq!(dispatcher.dispatch(chunk.to_bytes()), Dispatched::Dropped);
+        assert_eq!(dispatcher.dropped(), 1);
+        let depths = dispatcher.queue_depths();
+        assert_eq!((depths[0].len, depths[0].capacity), (1, 1));
+        assert_eq!(depths[0].utilization(), 1.0);
+    }
+}
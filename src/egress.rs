@@ -0,0 +1,223 @@
+//! 서버 전체 송신 대역폭 상한과 세션 간 공정 분배
+//!
+//! - 여러 세션(송신자)이 하나의 `EgressLimiter`를 공유하고 각자 `EgressShare`로 페이싱
+//! - 세션 몫 = 전체 상한 × 가중치 / 활성 세션 가중치 합
+//! - 최근 `ACTIVE_WINDOW` 동안 보내지 않은 세션은 합에서 빠지므로 남는 대역폭은 다른 세션이 사용
+//! - 세션마다 가상 송신 시각을 유지해 한 세션이 몰아서 보내도 다른 세션 몫을 빼앗지 못함
+//! - 시각은 tokio 시계 기준 (`tokio::time::pause`로 멈춘 테스트에서도 대기와 같은 시계)
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+use crate::Config;
+
+/// 이 시간 동안 보내지 않은 세션은 비활성으로 보고 몫을 나누지 않음
+const ACTIVE_WINDOW: Duration = Duration::from_millis(100);
+
+/// 늦게 깨어난 만큼 따라잡을 수 있는 최대 버스트 시간
+const MAX_BURST: Duration = Duration::from_millis(5);
+
+/// 이보다 짧은 대기는 묶어서 즉시 전송 (타이머 해상도)
+const PACING_GRANULARITY: Duration = Duration::from_millis(1);
+
+#[derive(Debug)]
+struct ShareState {
+    weight: f64,
+    next_send: Option<Instant>,
+    last_active: Option<Instant>,
+}
+
+/// 세션들이 공유하는 전체 송신 상한
+#[derive(Debug)]
+pub struct EgressLimiter {
+    /// 전체 상한 (bytes/s)
+    total_rate: f64,
+    shares: Mutex<HashMap<u64, ShareState>>,
+    next_id: AtomicU64,
+}
+
+impl EgressLimiter {
+    /// 전체 상한 `total_bytes_per_sec`로 생성
+    pub fn new(total_bytes_per_sec: u64) -> Arc<Self> {
+        Arc::new(Self {
+            total_rate: total_bytes_per_sec.max(1) as f64,
+            shares: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// `Config::total_egress_limit` 기반 생성 (0이면 제한 없음)
+    pub fn from_config(config: &Config) -> Option<Arc<Self>> {
+        (config.total_egress_limit > 0).then(|| Self::new(config.total_egress_limit))
+    }
+
+    /// 전체 상한 (bytes/s)
+    pub fn total_rate(&self) -> f64 {
+        self.total_rate
+    }
+
+    /// 세션 등록. 반환된 몫이 해제되면 자동으로 등록 해제
+    pub fn register(self: &Arc<Self>, weight: f64) -> EgressShare {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.shares.lock().insert(
+            id,
+            ShareState {
+                weight: sanitize_weight(weight),
+                next_send: None,
+                last_active: None,
+            },
+        );
+        EgressShare {
+            limiter: self.clone(),
+            id,
+        }
+    }
+
+    /// 등록된 세션 수
+    pub fn session_count(&self) -> usize {
+        self.shares.lock().len()
+    }
+
+    /// 세션의 현재 몫 (bytes/s). 세션 자신은 활성으로 간주
+    fn rate_of(shares: &HashMap<u64, ShareState>, id: u64, total_rate: f64, now: Instant) -> f64 {
+        let active_weight: f64 = shares
+            .iter()
+            .filter(|(&other, state)| {
+                other == id
+                    || state
+                        .last_active
+                        .is_some_and(|at| now.saturating_duration_since(at) <= ACTIVE_WINDOW)
+            })
+            .map(|(_, state)| state.weight)
+            .sum();
+        let weight = shares.get(&id).map_or(0.0, |state| state.weight);
+        if active_weight <= 0.0 {
+            return total_rate;
+        }
+        total_rate * weight / active_weight
+    }
+
+    /// `bytes` 전송 예약. 보내기 전까지 기다려야 할 시간 반환
+    fn reserve(&self, id: u64, bytes: usize, now: Instant) -> Duration {
+        let mut shares = self.shares.lock();
+        let rate = Self::rate_of(&shares, id, self.total_rate, now);
+        let Some(state) = shares.get_mut(&id) else {
+            return Duration::ZERO;
+        };
+
+        // 유휴 후에는 지금부터, 늦게 깨어난 경우는 MAX_BURST까지만 따라잡음
+        let floor = now.checked_sub(MAX_BURST).unwrap_or(now);
+        let start = state.next_send.map_or(now, |at| at.max(floor));
+        state.next_send = Some(start + Duration::from_secs_f64(bytes as f64 / rate));
+        state.last_active = Some(now);
+        start.saturating_duration_since(now)
+    }
+}
+
+/// 세션 하나의 송신 몫
+#[derive(Debug)]
+pub struct EgressShare {
+    limiter: Arc<EgressLimiter>,
+    id: u64,
+}
+
+impl EgressShare {
+    /// `bytes`를 보낼 차례까지 대기
+    pub async fn acquire(&self, bytes: usize) {
+        let wait = self.reserve(bytes, Instant::now());
+        if wait >= PACING_GRANULARITY {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// `bytes` 전송을 예약하고 대기 시간 반환 (직접 대기하는 호출자용)
+    pub fn reserve(&self, bytes: usize, now: Instant) -> Duration {
+        self.limiter.reserve(self.id, bytes, now)
+    }
+
+    /// 가중치 변경
+    pub fn set_weight(&self, weight: f64) {
+        if let Some(state) = self.limiter.shares.lock().get_mut(&self.id) {
+            state.weight = sanitize_weight(weight);
+        }
+    }
+
+    /// 현재 이 세션에 배정된 속도 (bytes/s)
+    pub fn rate(&self) -> f64 {
+        let shares = self.limiter.shares.lock();
+        EgressLimiter::rate_of(&shares, self.id, self.limiter.total_rate, Instant::now())
+    }
+
+    /// 공유 중인 상한
+    pub fn limiter(&self) -> &Arc<EgressLimiter> {
+        &self.limiter
+    }
+}
+
+impl Drop for EgressShare {
+    fn drop(&mut self) {
+        self.limiter.shares.lock().remove(&self.id);
+    }
+}
+
+fn sanitize_weight(weight: f64) -> f64 {
+    if weight.is_finite() && weight > 0.0 {
+        weight
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1ms 단위 가상 시간으로 `duration_ms` 동안 각 세션이 보낸 바이트 수
+    fn simulate(shares: &[&EgressShare], t0: Instant, duration_ms: u64, packet: usize) -> Vec<usize> {
+        let mut sent = vec![0; shares.len()];
+        let mut ready = vec![t0; shares.len()];
+        for ms in 0..duration_ms {
+            let now = t0 + Duration::from_millis(ms);
+            for (idx, share) in shares.iter().enumerate() {
+                while ready[idx] <= now {
+                    // 예약한 패킷은 대기 후 전송
+                    let wait = share.reserve(packet, now);
+                    sent[idx] += packet;
+                    if wait >= PACING_GRANULARITY {
+                        ready[idx] = now + wait;
+                    }
+                }
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn test_weighted_shares_and_idle_redistribution() {
+        let limiter = EgressLimiter::new(10_000_000);
+        let a = limiter.register(1.0);
+        let b = limiter.register(3.0);
+        let t0 = Instant::now();
+
+        // 1초 동안 둘 다 최대한 전송 → 1:3 분배, 합계는 상한 이내
+        let sent = simulate(&[&a, &b], t0, 1000, 1400);
+        let total = (sent[0] + sent[1]) as f64;
+        assert!(total <= 10_000_000.0 * 1.01, "{:?}", sent);
+        assert!(total >= 10_000_000.0 * 0.95, "{:?}", sent);
+        let ratio = sent[1] as f64 / sent[0] as f64;
+        assert!((2.7..3.3).contains(&ratio), "{:?}", sent);
+
+        // b가 멈추면 a가 전체 상한 사용
+        let t1 = t0 + Duration::from_millis(1200);
+        let sent = simulate(&[&a], t1, 1000, 1400);
+        assert!(sent[0] as f64 >= 10_000_000.0 * 0.95, "{:?}", sent);
+
+        drop(b);
+        assert_eq!(limiter.session_count(), 1);
+    }
+}
@@ -2,6 +2,10 @@
 
 use thiserror::Error;
 
+use crate::crypto::CryptoError;
+use crate::message::MessageType;
+use crate::state::ConnectionState;
+
 /// SLS 프로토콜 에러 타입
 #[derive(Error, Debug)]
 pub enum Error {
@@ -17,9 +21,34 @@ pub enum Error {
     #[error("유효하지 않은 프로토콜 버전: expected {expected}, got {got}")]
     InvalidVersion { expected: u8, got: u8 },
 
+    #[error(
+        "청크가 데이터그램에 들어가지 않음: max_datagram_size={max_datagram_size}, 청크 프레이밍 {overhead}바이트"
+    )]
+    ChunkExceedsDatagram {
+        max_datagram_size: usize,
+        overhead: usize,
+    },
+
+    #[error("이미 전달된 세그먼트: segment_id={segment_id}")]
+    SegmentAlreadyDelivered { segment_id: u64 },
+
     #[error("세그먼트 타임아웃: segment_id={segment_id}")]
     SegmentTimeout { segment_id: u64 },
 
+    #[error("세그먼트 전달 불가: segment_id={segment_id} (재전송 요청에 진전 없음)")]
+    SegmentUndeliverable { segment_id: u64 },
+
+    #[error("순서 수신 중 빈 구간: segment_id={segment_id}, offset={offset}")]
+    SegmentGap { segment_id: u64, offset: u64 },
+
+    #[error("불완전 전송: 빠진 세그먼트 {missing_segments:?}, 바이트 구간 {missing_bytes:?}")]
+    IncompleteTransfer {
+        /// 빠진 세그먼트 구간 (`[start, end)`)
+        missing_segments: Vec<(u64, u64)>,
+        /// 빠진 바이트 구간 (`[start, end)`)
+        missing_bytes: Vec<(u64, u64)>,
+    },
+
     #[error("청크 누락: segment_id={segment_id}, missing_chunks={missing_count}")]
     ChunksMissing {
         segment_id: u64,
@@ -41,15 +70,61 @@ pub enum Error {
     #[error("NIC 없음")]
     NoNicAvailable,
 
+    #[error("NIC ID 소진: 경로 관리자 하나에 NIC는 최대 256번 추가 가능")]
+    NicIdsExhausted,
+
     #[error("채널 에러")]
     ChannelError,
 
     #[error("연결 종료")]
     ConnectionClosed,
 
+    #[error("잘못된 상태 전이: {from:?} → {to:?}")]
+    InvalidStateTransition {
+        from: ConnectionState,
+        to: ConnectionState,
+    },
+
     #[error("메시지 타입 불일치: expected {expected}, got {got}")]
     MessageTypeMismatch { expected: String, got: String },
 
+    #[error("키 교환 시간 초과: 공개키 {attempts}회 전송 후 응답 없음")]
+    KeyExchangeTimeout { attempts: u32 },
+
+    #[error("암호화 에러: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error("전송 제안 시간 초과: Offer {attempts}회 전송 후 응답 없음")]
+    OfferTimeout { attempts: u32 },
+
+    #[error("업링크 단절 의심: NACK {nack_rounds}회 동안 재전송 없음")]
+    UplinkLikelyBroken { nack_rounds: u32 },
+
+    #[error("전송 크기 초과: 서버가 {size} bytes를 알렸으나 한도는 {max} bytes")]
+    FileTooLarge { size: u64, max: u64 },
+
+    #[error("세그먼트 크기 초과: {size} bytes > 수신자 한도 {max} bytes")]
+    SegmentTooLarge { size: usize, max: usize },
+
+    #[error("오프셋 범위 초과: {offset} > 데이터 {len} bytes")]
+    OffsetOutOfRange { offset: usize, len: usize },
+
+    #[error("컨트롤 메시지 크기 초과: {msg_type:?} {size} bytes > {max} bytes")]
+    MessageTooLarge {
+        msg_type: MessageType,
+        size: usize,
+        max: usize,
+    },
+
+    #[error("잘못된 InitAck: {0}")]
+    InvalidInitAck(String),
+
+    #[error("잘못된 매니페스트: {0}")]
+    InvalidManifest(String),
+
+    #[error("잘못된 재개 상태: {0}")]
+    InvalidResumeState(String),
+
     #[error("알 수 없는 에러: {0}")]
     Unknown(String),
 }
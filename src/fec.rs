@@ -0,0 +1,320 @@
+//! Reed-Solomon FEC 및 중복/FEC 모드 전환 정책
+//!
+//! - 세그먼트의 원본 청크를 데이터 샤드로, 패리티 청크를 추가 생성
+//! - 패리티 청크: `chunk_id >= total_chunks`, `is_redundant = true`, 헤더의 `parity_shards`에
+//!   세그먼트의 패리티 샤드 수. 스스로 패리티임을 알리므로 수신측은 모드 변경 통보(ModeChange)를
+//!   놓쳐도 패리티를 받아들임
+//! - 수신측은 원본 + 패리티가 원본 청크 수 이상이면 NACK 없이 복원
+
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+
+use crate::chunk::{Chunk, ChunkId, Segment};
+use crate::Config;
+
+/// GF(2^8) Reed-Solomon의 최대 샤드 수 (데이터 + 패리티)
+pub const MAX_SHARDS: usize = 256;
+
+/// 손실 보정 방식
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RedundancyMode {
+    /// 원본 청크 일부를 그대로 중복 전송
+    Duplicate,
+
+    /// Reed-Solomon 패리티 청크 전송
+    Fec,
+}
+
+/// 중복률에 해당하는 패리티 샤드 수
+pub fn parity_count(data_shards: usize, redundancy_ratio: f64) -> usize {
+    let wanted = (data_shards as f64 * redundancy_ratio).ceil() as usize;
+    wanted.max(1).min(MAX_SHARDS.saturating_sub(data_shards))
+}
+
+/// 패리티 청크 여부
+pub fn is_parity(chunk: &Chunk) -> bool {
+    chunk.header.parity_shards > 0
+}
+
+/// 원본 청크들로부터 패리티 청크 생성
+///
+/// 모든 샤드를 첫 청크 크기로 0 패딩. 샤드 수 한도를 넘으면 빈 목록
+pub fn encode_parity(chunks: &[Chunk], redundancy_ratio: f64) -> Vec<Chunk> {
+    let data_shards = chunks.len();
+    let parity_shards = parity_count(data_shards, redundancy_ratio);
+    if data_shards == 0 || parity_shards == 0 {
+        return Vec::new();
+    }
+
+    let rs = match ReedSolomon::new(data_shards, parity_shards) {
+        Ok(rs) => rs,
+        Err(_) => return Vec::new(),
+    };
+
+    let shard_len = chunks[0].data.len();
+    let mut shards: Vec<Vec<u8>> = chunks
+        .iter()
+        .map(|chunk| {
+            let mut shard = chunk.data.to_vec();
+            shard.resize(shard_len, 0);
+            shard
+        })
+        .chain((0..parity_shards).map(|_| vec![0u8; shard_len]))
+        .collect();
+
+    if rs.encode(&mut shards).is_err() {
+        return Vec::new();
+    }
+
+    let header = &chunks[0].header;
+    shards
+        .into_iter()
+        .skip(data_shards)
+        .enumerate()
+        .map(|(idx, shard)| {
+            let mut parity = Chunk::new(
+                header.segment_id,
+                (data_shards + idx) as ChunkId,
+                data_shards as u32,
+                0,
+                header.segment_size,
+                Bytes::from(shard),
+                header.nic_id,
+                true,
+            );
+            parity.header.file_id = header.file_id;
+            parity.header.boundary = header.boundary;
+            parity.header.end_of_stream = header.end_of_stream;
+            parity.header.parity_shards = parity_shards as u8;
+            parity
+        })
+        .collect()
+}
+
+/// 수신한 패리티로 누락 청크 복원
+///
+/// `parity[i]`는 패리티 샤드 i (없으면 None). 복원한 청크 수 반환
+pub fn reconstruct(segment: &mut Segment, parity: &[Option<Bytes>]) -> usize {
+    let data_shards = segment.total_chunks as usize;
+    let received = segment.received_count as usize + parity.iter().flatten().count();
+    if segment.is_complete() || received < data_shards || parity.is_empty() {
+        return 0;
+    }
+
+    let shard_len = match parity.iter().flatten().next() {
+        Some(p) => p.len(),
+        None => return 0,
+    };
+    let rs = match ReedSolomon::new(data_shards, parity.len()) {
+        Ok(rs) => rs,
+        Err(_) => return 0,
+    };
+
+    let total_size = segment.total_size;
+    let mut shards: Vec<Option<Vec<u8>>> = (0..data_shards)
+        .map(|id| {
+            if !segment.has_chunk(id as ChunkId) {
+                return None;
+            }
+            let offset = id * shard_len;
+            if offset >= total_size {
+                return None;
+            }
+            let end = (offset + shard_len).min(total_size);
+            let mut shard = segment.data[offset..end].to_vec();
+            shard.resize(shard_len, 0);
+            Some(shard)
+        })
+        .chain(
+            parity
+                .iter()
+                .map(|p| p.as_ref().filter(|p| p.len() == shard_len).map(|p| p.to_vec())),
+        )
+        .collect();
+
+    if rs.reconstruct_data(&mut shards).is_err() {
+        return 0;
+    }
+
+    let mut recovered = 0;
+    for (id, shard) in shards.into_iter().take(data_shards).enumerate() {
+        if segment.has_chunk(id as ChunkId) {
+            continue;
+        }
+        let Some(shard) = shard else { continue };
+
+        let offset = id * shard_len;
+        if offset >= total_size {
+            continue;
+        }
+        let len = shard_len.min(total_size - offset);
+        let chunk = Chunk::new(
+            segment.id,
+            id as ChunkId,
+            segment.total_chunks,
+            offset as u32,
+            total_size as u32,
+            Bytes::copy_from_slice(&shard[..len]),
+            0,
+            true,
+        );
+        if segment.insert_chunk(&chunk) {
+            recovered += 1;
+        }
+    }
+    recovered
+}
+
+/// 지속 손실 기반 중복/FEC 모드 전환 정책
+///
+/// 손실률이 임계값 이상인 상태가 `hold` 동안 유지되면 FEC로,
+/// 임계값 미만이 `hold` 동안 유지되면 중복 전송으로 복귀
+#[derive(Debug, Clone)]
+pub struct FecModePolicy {
+    threshold: f64,
+    hold: Duration,
+    mode: RedundancyMode,
+    crossed_since: Option<Instant>,
+}
+
+impl FecModePolicy {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            threshold: config.fec_loss_threshold,
+            hold: Duration::from_millis(config.fec_switch_hold_ms),
+            mode: RedundancyMode::Duplicate,
+            crossed_since: None,
+        }
+    }
+
+    /// 현재 모드
+    pub fn mode(&self) -> RedundancyMode {
+        self.mode
+    }
+
+    /// 손실률 관측 반영. 모드가 바뀌면 새 모드 반환
+    pub fn update(&mut self, loss_rate: f64, now: Instant) -> Option<RedundancyMode> {
+        if self.threshold <= 0.0 {
+            return None;
+        }
+
+        let wanted = if loss_rate >= self.threshold {
+            RedundancyMode::Fec
+        } else {
+            RedundancyMode::Duplicate
+        };
+
+        if wanted == self.mode {
+            self.crossed_since = None;
+            return None;
+        }
+
+        let since = *self.crossed_since.get_or_insert(now);
+        if now.duration_since(since) >= self.hold {
+            self.mode = wanted;
+            self.crossed_since = None;
+            Some(wanted)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::SegmentBuilder;
+
+    #[test]
+    fn test_reconstruct_without_nack() {
+        let builder = SegmentBuilder::new(100);
+        let data: Vec<u8> = (0..950).map(|i| (i % 251) as u8).collect();
+        let chunks = builder.split_into_chunks(3, &data, 0);
+        let parity = encode_parity(&chunks, 0.3);
+        assert_eq!(parity.len(), 3);
+        assert!(parity.iter().all(is_parity));
+        assert!(parity.iter().all(|p| p.header.parity_shards == 3 && p.header.offset == 0));
+        assert!(!chunks.iter().any(is_parity));
+        // 헤더만으로 패리티임을 앎 (직렬화 후에도)
+        let decoded = Chunk::from_bytes(&parity[0].to_bytes()).unwrap();
+        assert!(is_parity(&decoded));
+
+        // 원본 3개 손실 (마지막 짧은 청크 포함), 패리티 1개 손실
+        let mut segment = Segment::new_for_receive(3, data.len(), chunks.len() as u32);
+        for chunk in chunks.iter().filter(|c| ![1, 4, 9].contains(&c.header.chunk_id)) {
+            segment.insert_chunk(chunk);
+        }
+        let received: Vec<Option<Bytes>> = parity
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i != 0).then(|| p.data.clone()))
+            .collect();
+
+        // 패리티 2개로는 3개 손실을 복원할 수 없음
+        assert_eq!(reconstruct(&mut segment, &received), 0);
+
+        let mut all = received;
+        all[0] = Some(parity[0].data.clone());
+        assert_eq!(reconstruct(&mut segment, &all), 3);
+        assert!(segment.is_complete());
+        assert_eq!(segment.into_data().as_ref(), data.as_slice());
+    }
+
+    #[test]
+    fn test_oversized_parity_rejected() {
+        let builder = SegmentBuilder::new(100);
+        let data = vec![7u8; 150];
+        let chunks = builder.split_into_chunks(5, &data, 0);
+        assert_eq!(chunks.len(), 2);
+
+        // CRC는 맞지만 분할과 맞지 않는 길이의 패리티
+        let mut forged = Chunk::new(5, 2, 2, 0, 150, Bytes::from(vec![1u8; 400]), 0, true);
+        forged.header.parity_shards = 1;
+        let mut segment = Segment::new_for_receive(5, data.len(), 2);
+        segment.insert_chunk(&chunks[0]);
+        assert!(!segment.insert_parity(&forged));
+        assert!(!segment.try_complete());
+
+        // 검사를 거치지 않은 패리티로도 복원이 패닉하지 않음
+        assert_eq!(reconstruct(&mut segment, &[Some(forged.data.clone())]), 0);
+        assert!(!segment.is_complete());
+
+        let parity = encode_parity(&chunks, 0.5);
+        assert!(segment.insert_parity(&parity[0]));
+        assert!(segment.try_complete());
+        assert_eq!(segment.into_data().as_ref(), data.as_slice());
+    }
+
+    #[test]
+    fn test_policy_requires_sustained_loss() {
+        let config = Config {
+            fec_loss_threshold: 0.2,
+            fec_switch_hold_ms: 100,
+            ..Config::default()
+        };
+        let mut policy = FecModePolicy::new(&config);
+        let t0 = Instant::now();
+
+        // 짧은 손실 급증은 무시
+        assert_eq!(policy.update(0.5, t0), None);
+        assert_eq!(policy.update(0.05, t0 + Duration::from_millis(50)), None);
+        assert_eq!(policy.update(0.5, t0 + Duration::from_millis(120)), None);
+        assert_eq!(policy.mode(), RedundancyMode::Duplicate);
+
+        // 지속되면 FEC
+        assert_eq!(
+            policy.update(0.5, t0 + Duration::from_millis(230)),
+            Some(RedundancyMode::Fec)
+        );
+
+        // 임계값 미만이 지속되면 복귀
+        assert_eq!(policy.update(0.01, t0 + Duration::from_millis(300)), None);
+        assert_eq!(
+            policy.update(0.01, t0 + Duration::from_millis(400)),
+            Some(RedundancyMode::Duplicate)
+        );
+    }
+}
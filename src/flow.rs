@@ -0,0 +1,115 @@
+//! FlowControl 전송 스케줄링
+//!
+//! - 큰 변화(손실 급증, 버퍼 압박 임계값 통과)는 최소 간격 후 즉시 전송
+//! - 변화가 없으면 최대 간격마다 하트비트로만 전송 (±10% 지터로 클라이언트 간 동기화 방지)
+//! - NACK 중심 설계에 맞춰 유휴 업링크 트래픽 최소화
+
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::message::FlowControlMessage;
+use crate::Config;
+
+/// 즉시 보고할 손실률 변화량
+pub const LOSS_DELTA_THRESHOLD: f32 = 0.02;
+
+/// 버퍼 압박 임계값 (처리 중 / (처리 중 + 여유))
+pub const BUFFER_PRESSURE_THRESHOLD: f32 = 0.75;
+
+/// 하트비트 간격 지터 비율
+const HEARTBEAT_JITTER: f64 = 0.1;
+
+/// FlowControl 전송 시점 결정기
+#[derive(Debug, Clone)]
+pub struct FlowControlScheduler {
+    min_interval: Duration,
+    max_interval: Duration,
+    last_sent_at: Option<Instant>,
+    last_sent: Option<FlowControlMessage>,
+    next_heartbeat: Duration,
+}
+
+impl FlowControlScheduler {
+    pub fn new(config: &Config) -> Self {
+        let min_interval = Duration::from_millis(config.flowcontrol_min_interval_ms);
+        let max_interval =
+            Duration::from_millis(config.flowcontrol_max_interval_ms).max(min_interval);
+        Self {
+            min_interval,
+            max_interval,
+            last_sent_at: None,
+            last_sent: None,
+            next_heartbeat: max_interval,
+        }
+    }
+
+    /// 현재 상태를 지금 보내야 하는지 판단. true면 전송한 것으로 기록
+    pub fn poll(&mut self, current: &FlowControlMessage, now: Instant) -> bool {
+        let due = match (self.last_sent_at, &self.last_sent) {
+            (None, _) | (_, None) => true,
+            (Some(at), Some(last)) => {
+                let elapsed = now.saturating_duration_since(at);
+                elapsed >= self.next_heartbeat
+                    || (elapsed >= self.min_interval && is_significant_change(last, current))
+            }
+        };
+
+        if due {
+            self.last_sent_at = Some(now);
+            self.last_sent = Some(current.clone());
+            self.next_heartbeat = self.jittered_heartbeat();
+        }
+        due
+    }
+
+    fn jittered_heartbeat(&self) -> Duration {
+        let factor = 1.0 + rand::thread_rng().gen_range(-HEARTBEAT_JITTER..=HEARTBEAT_JITTER);
+        self.max_interval.mul_f64(factor).max(self.min_interval)
+    }
+}
+
+/// 즉시 보고할 만한 변화인지 여부
+fn is_significant_change(last: &FlowControlMessage, current: &FlowControlMessage) -> bool {
+    let loss_spike = (current.loss_rate - last.loss_rate).abs() >= LOSS_DELTA_THRESHOLD;
+    let pressure_crossed = (last.buffer_pressure() >= BUFFER_PRESSURE_THRESHOLD)
+        != (current.buffer_pressure() >= BUFFER_PRESSURE_THRESHOLD);
+    loss_spike || pressure_crossed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steady_state_sends_far_fewer_than_fixed_interval() {
+        let mut scheduler = FlowControlScheduler::new(&Config::default());
+        let t0 = Instant::now();
+        let tick = Duration::from_millis(10);
+
+        // 10초 정상 상태 (완료 세그먼트만 증가)
+        let mut sent = 0;
+        for i in 0..1000u32 {
+            let msg = FlowControlMessage::new(16, i as u64, 2, 0.01, 1_000_000);
+            if scheduler.poll(&msg, t0 + tick * i) {
+                sent += 1;
+            }
+        }
+        // 고정 100ms 방식이면 100회
+        assert!(sent <= 12, "sent {}", sent);
+        assert!(sent >= 9, "sent {}", sent);
+
+        // 손실 급증은 최소 간격 후 즉시 보고
+        let now = t0 + tick * 1000;
+        let spike = FlowControlMessage::new(16, 1000, 2, 0.20, 1_000_000);
+        let _ = scheduler.poll(&spike, now);
+        let later = now + Duration::from_millis(25);
+        let spike2 = FlowControlMessage::new(16, 1000, 2, 0.30, 1_000_000);
+        assert!(scheduler.poll(&spike2, later));
+
+        // 최소 간격 이내에는 변화가 있어도 보내지 않음
+        let pressure = FlowControlMessage::new(1, 1000, 15, 0.30, 1_000_000);
+        assert!(!scheduler.poll(&pressure, later + Duration::from_millis(5)));
+        assert!(scheduler.poll(&pressure, later + Duration::from_millis(20)));
+    }
+}
@@ -0,0 +1,339 @@
+//! 컨트롤 경로 보호 (UDP 증폭 공격 방지)
+//!
+//! - 출발지 주소별 토큰 버킷으로 컨트롤 메시지 속도 제한
+//! - Init 쿠키로 왕복 도달성을 확인한 뒤에만 대량 전송 허용
+//! - 쿠키는 주소와 발급 시각에 묶인 HMAC이라 세션 상태 없이 검증되고,
+//!   유효 기간이 지난 Init(캡처 후 재전송)은 거부 (QUIC Retry 방식)
+
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::message::InitCookie;
+use crate::Config;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// 추적할 최대 출발지 수 (가득 차면 유휴 버킷 정리, 그래도 가득 차면 새 출발지 거부)
+const MAX_TRACKED_SOURCES: usize = 4096;
+
+/// 유휴 버킷 정리 기준
+const IDLE_BUCKET_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 유휴 버킷 정리 최소 간격 (새 출발지마다 전체를 훑지 않도록)
+const PRUNE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 토큰 버킷
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// 초당 `rate_per_sec`개 보충, 최대 `burst`개 누적
+    pub fn new(rate_per_sec: f64, burst: u32) -> Self {
+        let capacity = burst.max(1) as f64;
+        Self {
+            tokens: capacity,
+            capacity,
+            refill_per_sec: rate_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// 토큰 1개 소비 시도
+    pub fn try_acquire(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// 쿠키 거부 사유
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieRejection {
+    /// 쿠키 없는 첫 Init
+    Missing,
+    /// 태그 불일치 (다른 주소로 발급됐거나 위조)
+    Invalid,
+    /// 유효 기간 초과 (재전송된 오래된 Init)
+    Stale,
+}
+
+/// 쿠키 발급/거부 누계
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CookieStats {
+    pub issued: u64,
+    pub accepted: u64,
+    pub rejected_invalid: u64,
+    pub rejected_stale: u64,
+}
+
+/// 컨트롤 메시지 가드
+///
+/// 출발지별 속도 제한과 Init 쿠키 발급/검증을 담당
+pub struct ControlGuard {
+    /// 출발지별 토큰 버킷
+    buckets: DashMap<SocketAddr, TokenBucket>,
+
+    /// 쿠키 HMAC 키 (프로세스마다 무작위)
+    cookie_key: [u8; 32],
+
+    /// 쿠키 발급 시각 기준점
+    epoch: Instant,
+
+    /// 마지막 유휴 버킷 정리 시각 (`epoch` 기준 마이크로초)
+    last_prune_us: AtomicU64,
+
+    /// 쿠키 유효 기간
+    cookie_lifetime: Duration,
+
+    cookies_issued: AtomicU64,
+    cookies_accepted: AtomicU64,
+    cookies_invalid: AtomicU64,
+    cookies_stale: AtomicU64,
+
+    /// 초당 허용 컨트롤 메시지 수 (0이면 제한 없음)
+    rate_per_sec: f64,
+
+    /// 버스트 허용량
+    burst: u32,
+}
+
+impl ControlGuard {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            buckets: DashMap::new(),
+            cookie_key: rand::random(),
+            epoch: Instant::now(),
+            last_prune_us: AtomicU64::new(0),
+            cookie_lifetime: Duration::from_millis(config.init_cookie_lifetime_ms),
+            cookies_issued: AtomicU64::new(0),
+            cookies_accepted: AtomicU64::new(0),
+            cookies_invalid: AtomicU64::new(0),
+            cookies_stale: AtomicU64::new(0),
+            rate_per_sec: config.control_rate_per_sec,
+            burst: config.control_burst,
+        }
+    }
+
+    /// 이 출발지의 컨트롤 메시지를 처리해도 되는지 확인
+    ///
+    /// 추적 중인 출발지가 가득 차 있으면 처음 보는 출발지는 거부 (위조 출발지 폭주 대비)
+    pub fn allow(&self, addr: SocketAddr) -> bool {
+        if self.rate_per_sec <= 0.0 {
+            return true;
+        }
+
+        if self.buckets.len() >= MAX_TRACKED_SOURCES && !self.buckets.contains_key(&addr) {
+            self.prune_idle();
+            if self.buckets.len() >= MAX_TRACKED_SOURCES {
+                return false;
+            }
+        }
+
+        self.buckets
+            .entry(addr)
+            .or_insert_with(|| TokenBucket::new(self.rate_per_sec, self.burst))
+            .try_acquire()
+    }
+
+    /// 유휴 버킷 정리 (`PRUNE_INTERVAL`에 한 번만)
+    fn prune_idle(&self) {
+        let now_us = self.epoch.elapsed().as_micros() as u64;
+        let last_us = self.last_prune_us.load(Ordering::Relaxed);
+        if now_us.saturating_sub(last_us) < PRUNE_INTERVAL.as_micros() as u64
+            || self
+                .last_prune_us
+                .compare_exchange(last_us, now_us, Ordering::Relaxed, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        self.buckets
+            .retain(|_, bucket| bucket.last_refill.elapsed() < IDLE_BUCKET_TIMEOUT);
+    }
+
+    /// 출발지 주소에 대한 새 쿠키 발급
+    pub fn cookie_for(&self, addr: SocketAddr) -> InitCookie {
+        // 0은 "쿠키 없음"으로 예약
+        let issued_at_us = (self.epoch.elapsed().as_micros() as u64).max(1);
+        let mut tag = [0u8; 16];
+        tag.copy_from_slice(&self.cookie_mac(addr, issued_at_us).finalize().into_bytes()[..16]);
+        self.cookies_issued.fetch_add(1, Ordering::Relaxed);
+        InitCookie { issued_at_us, tag }
+    }
+
+    /// Init에 실린 쿠키 검증. 성공하면 발급 후 경과 시간(쿠키 왕복 RTT) 반환
+    pub fn verify_cookie(
+        &self,
+        addr: SocketAddr,
+        cookie: &InitCookie,
+    ) -> std::result::Result<Duration, CookieRejection> {
+        if cookie.is_empty() {
+            return Err(CookieRejection::Missing);
+        }
+
+        if self
+            .cookie_mac(addr, cookie.issued_at_us)
+            .verify_truncated_left(&cookie.tag)
+            .is_err()
+        {
+            self.cookies_invalid.fetch_add(1, Ordering::Relaxed);
+            return Err(CookieRejection::Invalid);
+        }
+
+        let age = self
+            .epoch
+            .elapsed()
+            .saturating_sub(Duration::from_micros(cookie.issued_at_us));
+        if age > self.cookie_lifetime {
+            self.cookies_stale.fetch_add(1, Ordering::Relaxed);
+            return Err(CookieRejection::Stale);
+        }
+
+        self.cookies_accepted.fetch_add(1, Ordering::Relaxed);
+        Ok(age)
+    }
+
+    /// 쿠키 발급/검증 누계
+    pub fn cookie_stats(&self) -> CookieStats {
+        CookieStats {
+            issued: self.cookies_issued.load(Ordering::Relaxed),
+            accepted: self.cookies_accepted.load(Ordering::Relaxed),
+            rejected_invalid: self.cookies_invalid.load(Ordering::Relaxed),
+            rejected_stale: self.cookies_stale.load(Ordering::Relaxed),
+        }
+    }
+
+    fn cookie_mac(&self, addr: SocketAddr, issued_at_us: u64) -> HmacSha256 {
+        let mut mac =
+            HmacSha256::new_from_slice(&self.cookie_key).expect("HMAC은 모든 키 길이 허용");
+        match addr.ip() {
+            IpAddr::V4(ip) => mac.update(&ip.octets()),
+            IpAddr::V6(ip) => mac.update(&ip.octets()),
+        }
+        mac.update(&addr.port().to_be_bytes());
+        mac.update(&issued_at_us.to_be_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket_burst() {
+        let mut bucket = TokenBucket::new(0.0, 3);
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(bucket.try_acquire());
+        assert!(!bucket.try_acquire());
+    }
+
+    #[test]
+    fn test_guard_per_source_limit() {
+        let config = Config {
+            control_rate_per_sec: 0.001,
+            control_burst: 2,
+            ..Config::default()
+        };
+        let guard = ControlGuard::new(&config);
+        let a: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let b: SocketAddr = "10.0.0.2:1000".parse().unwrap();
+
+        assert!(guard.allow(a));
+        assert!(guard.allow(a));
+        assert!(!guard.allow(a));
+        // 다른 출발지는 독립적으로 제한
+        assert!(guard.allow(b));
+    }
+
+    #[test]
+    fn test_source_flood_bounded() {
+        let config = Config {
+            control_rate_per_sec: 10.0,
+            control_burst: 2,
+            ..Config::default()
+        };
+        let guard = ControlGuard::new(&config);
+        let known: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        assert!(guard.allow(known));
+
+        // 모두 유휴 기준보다 새로운 위조 출발지 폭주
+        let flooded = (0..MAX_TRACKED_SOURCES as u32 + 1000)
+            .map(|i| SocketAddr::new(IpAddr::from((0x0b00_0000 + i).to_be_bytes()), 1000))
+            .filter(|&addr| guard.allow(addr))
+            .count();
+        assert_eq!(flooded, MAX_TRACKED_SOURCES - 1);
+        assert_eq!(guard.buckets.len(), MAX_TRACKED_SOURCES);
+
+        // 이미 추적 중인 출발지는 계속 허용
+        assert!(guard.allow(known));
+    }
+
+    #[test]
+    fn test_cookie_bound_to_addr() {
+        let guard = ControlGuard::new(&Config::default());
+        let a: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+        let b: SocketAddr = "10.0.0.1:1001".parse().unwrap();
+
+        let cookie = guard.cookie_for(a);
+        assert!(guard.verify_cookie(a, &cookie).is_ok());
+        assert_eq!(guard.verify_cookie(b, &cookie), Err(CookieRejection::Invalid));
+        assert_eq!(
+            guard.verify_cookie(a, &InitCookie::default()),
+            Err(CookieRejection::Missing)
+        );
+
+        // 발급 시각을 바꾸면 태그가 맞지 않음 (유효 기간 연장 불가)
+        let mut forged = cookie;
+        forged.issued_at_us += 1;
+        assert_eq!(guard.verify_cookie(a, &forged), Err(CookieRejection::Invalid));
+
+        // 다른 서버 프로세스(키)의 쿠키도 거부
+        let other = ControlGuard::new(&Config::default());
+        assert_eq!(other.verify_cookie(a, &cookie), Err(CookieRejection::Invalid));
+    }
+
+    #[test]
+    fn test_stale_cookie_rejected() {
+        let config = Config {
+            init_cookie_lifetime_ms: 20,
+            ..Config::default()
+        };
+        let guard = ControlGuard::new(&config);
+        let a: SocketAddr = "10.0.0.1:1000".parse().unwrap();
+
+        let cookie = guard.cookie_for(a);
+        assert!(guard.verify_cookie(a, &cookie).unwrap() < Duration::from_millis(20));
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(guard.verify_cookie(a, &cookie), Err(CookieRejection::Stale));
+        assert_eq!(
+            guard.cookie_stats(),
+            CookieStats {
+                issued: 1,
+                accepted: 1,
+                rejected_invalid: 0,
+                rejected_stale: 1,
+            }
+        );
+    }
+}
@@ -10,27 +10,139 @@
 //! - **저사양 최적화**: 클라이언트 부담 최소화
 //! - **BBR-lite 혼잡제어**: RTT/대역폭 기반 동적 pacing
 //! - **백프레셔**: 큐 기반 자동 흐름 제어
+//!
+//! ## 기능 플래그
+//! - `std` (기본): 비동기 송수신자, 통계, 멀티패스, 키 교환
+//! - `sim`: 결정적 네트워크 시뮬레이터 (`SimNetwork`, 통합 테스트용)
+//! - `quic`: quinn 연결의 QUIC 데이터그램 위로 전송 (`QuicTunnel`)
+//! - `std` 비활성화 시: 청크/메시지 코덱과 세그먼트 암호화만 `no_std + alloc`으로 제공
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub mod buffertune;
+#[cfg(feature = "std")]
+pub mod cache;
 pub mod chunk;
+mod codec;
 pub mod config;
 pub mod crypto;
+#[cfg(feature = "std")]
+pub mod dispatch;
+#[cfg(feature = "std")]
+pub mod egress;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "std")]
+pub mod fec;
+#[cfg(feature = "std")]
+pub mod flow;
+#[cfg(feature = "std")]
+pub mod guard;
 pub mod message;
+#[cfg(feature = "std")]
+pub mod lifecycle;
+pub mod plan;
+#[cfg(feature = "std")]
+pub mod logsample;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
 pub mod multipath;
+#[cfg(feature = "std")]
+pub mod netchange;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod probe;
+#[cfg(feature = "std")]
+pub mod readahead;
+#[cfg(feature = "std")]
 pub mod receiver;
+#[cfg(feature = "std")]
+pub mod recorder;
+#[cfg(feature = "std")]
+pub mod resume;
+#[cfg(feature = "std")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod rtt;
+#[cfg(feature = "std")]
 pub mod sender;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(all(feature = "std", any(test, feature = "sim")))]
+pub mod sim;
+#[cfg(feature = "std")]
+pub mod sink;
+#[cfg(feature = "std")]
+pub mod slowstart;
+#[cfg(feature = "std")]
+pub mod state;
+#[cfg(feature = "std")]
 pub mod stats;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "std")]
+pub mod tunnel;
+#[cfg(feature = "std")]
 pub mod bbr;
+#[cfg(feature = "std")]
+pub mod writer;
 
-pub use chunk::{Chunk, ChunkId, Segment, SegmentId, SegmentBuilder};
-pub use config::Config;
-pub use crypto::{CryptoSession, EphemeralKeyPair, KeyExchangeMessage, SegmentCipher};
+#[cfg(feature = "std")]
+pub use cache::SegmentCache;
+pub use chunk::{Chunk, ChunkBatch, ChunkId, FileId, SegmentBuilder, SegmentId};
+#[cfg(feature = "std")]
+pub use chunk::{PreparedSegment, Segment};
+pub use config::{Config, RedundancyPolicy, SharedRedundancyPolicy};
+pub use crypto::{KeyProvider, ProvidedKey, SegmentCipher, SharedKeyProvider};
+#[cfg(feature = "std")]
+pub use crypto::{
+    CryptoSession, EphemeralKeyPair, KeyExchange, KeyExchangeMessage, X25519KeyProvider,
+};
+#[cfg(feature = "std")]
+pub use dispatch::{Dispatched, ShardedDispatcher};
+#[cfg(feature = "std")]
+pub use egress::{EgressLimiter, EgressShare};
+#[cfg(feature = "std")]
 pub use error::{Error, Result};
-pub use message::{Message, NackMessage};
-pub use multipath::{NicInfo, PathManager};
+#[cfg(feature = "std")]
+pub use fec::RedundancyMode;
+#[cfg(feature = "std")]
+pub use lifecycle::{Connected, ConnectedSession, Listening, TypedSender, Unbound};
+#[cfg(feature = "std")]
+pub use logsample::LogSampler;
+pub use message::NackMessage;
+#[cfg(feature = "std")]
+pub use message::Message;
+#[cfg(feature = "std")]
+pub use multipath::{NicInfo, PathDescription, PathManager};
+pub use plan::{PlanWarning, TransferPlan};
+#[cfg(feature = "std")]
+pub use pool::{PoolStats, SegmentBufferPool};
+#[cfg(feature = "std")]
+pub use readahead::Readahead;
+#[cfg(feature = "std")]
 pub use receiver::Receiver;
+#[cfg(feature = "std")]
 pub use sender::Sender;
-pub use stats::TransferStats;
+#[cfg(feature = "std")]
+pub use sink::{DiscardSink, FileSink, OutputSink};
+#[cfg(feature = "std")]
+pub use state::ConnectionState;
+#[cfg(feature = "std")]
+pub use stats::{QueueDepth, TransferReport, TransferStats};
+#[cfg(feature = "std")]
+pub use transport::{DatagramSocket, PeerUdpSocket};
+#[cfg(feature = "std")]
+pub use tunnel::{DatagramTunnel, TunnelSocket};
+#[cfg(feature = "quic")]
+pub use tunnel::QuicTunnel;
+#[cfg(feature = "std")]
+pub use writer::SenderSink;
 
 /// 프로토콜 버전
 pub const PROTOCOL_VERSION: u8 = 1;
@@ -43,3 +155,9 @@ pub const DEFAULT_SEGMENT_SIZE: usize = 65536; // 64KB
 
 /// 매직 넘버 (패킷 식별용)
 pub const MAGIC_NUMBER: u32 = 0x53465050; // "SFPP"
+
+/// 청크 데이터그램 매직 넘버 (컨트롤 메시지와 구분)
+pub const CHUNK_MAGIC: u32 = 0x53465043; // "SFPC"
+
+/// 청크 묶음 데이터그램 매직 넘버 (`ChunkBatch`)
+pub const CHUNK_BATCH_MAGIC: u32 = 0x53465042; // "SFPB"
@@ -0,0 +1,329 @@
+//! 타입 상태로 검사하는 송신자 수명 주기
+//!
+//! `Sender::send_data`는 클라이언트가 연결되기 전에 호출해도 컴파일되고 실행 중에
+//! `Error::ConnectionClosed`로만 알려줌. `TypedSender`는 상태를 타입 매개변수로 들고 있어
+//! 연결 전 전송 같은 오용을 컴파일 단계에서 막음
+//!
+//! ```text
+//! TypedSender<Unbound> ─bind─▶ TypedSender<Listening> ─accept/offer─▶ TypedSender<Connected>
+//! ```
+//!
+//! - `send_data`/`send_files`는 `Connected`에만 있고, `client_addr`는 `Option`이 아님
+//! - 전이는 값을 소비하므로 이전 상태의 핸들을 계속 쓸 수 없음
+//! - 저수준 API가 필요하면 `sender()`로 안쪽 `Sender`를 그대로 사용
+//! - 핸들을 버리면 서버 루프를 멈춤
+//!
+//! ```no_run
+//! # async fn run() -> sfp::Result<()> {
+//! use sfp::TypedSender;
+//!
+//! let listening = TypedSender::new(sfp::Config::default())
+//!     .bind("0.0.0.0:9000".parse().unwrap())
+//!     .await?;
+//! let session = listening.accept().await?;
+//! session.send_data(bytes::Bytes::from_static(b"hello")).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! 연결 전에는 전송 메서드가 없음:
+//!
+//! ```compile_fail
+//! # async fn run() -> sfp::Result<()> {
+//! let listening = sfp::TypedSender::new(sfp::Config::default())
+//!     .bind("0.0.0.0:9000".parse().unwrap())
+//!     .await?;
+//! listening.send_data(bytes::Bytes::from_static(b"hello")).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ```compile_fail
+//! let unbound = sfp::TypedSender::new(sfp::Config::default());
+//! let _ = unbound.send_data(bytes::Bytes::from_static(b"hello"));
+//! ```
+//!
+//! 전이한 뒤에는 이전 상태를 쓸 수 없음:
+//!
+//! ```compile_fail
+//! # async fn run() -> sfp::Result<()> {
+//! let listening = sfp::TypedSender::new(sfp::Config::default())
+//!     .bind("0.0.0.0:9000".parse().unwrap())
+//!     .await?;
+//! let session = listening.accept().await?;
+//! let _ = listening.local_addr();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::chunk::SegmentId;
+use crate::manifest::Manifest;
+use crate::multipath::PathManager;
+use crate::sender::Sender;
+use crate::stats::TransferStats;
+use crate::transport::{self, DatagramSocket};
+use crate::writer::SenderSink;
+use crate::{Config, Error, Result};
+
+/// 연결 대기 중 상태 확인 간격
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// 소켓이 없는 상태
+#[derive(Debug)]
+pub struct Unbound;
+
+/// 소켓에서 서버 루프가 돌며 클라이언트를 기다리는 상태
+pub struct Listening<S: DatagramSocket = UdpSocket> {
+    running: Running<S>,
+}
+
+/// 클라이언트와 핸드셰이크가 끝나 데이터를 보낼 수 있는 상태
+pub struct Connected<S: DatagramSocket = UdpSocket> {
+    running: Running<S>,
+    client_addr: SocketAddr,
+}
+
+/// 연결된 세션 하나
+pub type ConnectedSession<S = UdpSocket> = TypedSender<Connected<S>>;
+
+/// 서버 루프 태스크와 소켓 (버리면 루프 정지)
+struct Running<S: DatagramSocket> {
+    sender: Arc<Sender>,
+    socket: Arc<S>,
+    task: JoinHandle<Result<()>>,
+}
+
+impl<S: DatagramSocket> Drop for Running<S> {
+    fn drop(&mut self) {
+        self.sender.stop();
+        self.task.abort();
+    }
+}
+
+/// 상태를 타입으로 구분하는 송신자
+pub struct TypedSender<State> {
+    sender: Arc<Sender>,
+    state: State,
+}
+
+impl<State> TypedSender<State> {
+    /// 안쪽 저수준 송신자 (통계, 드레인, 멀티패스 등)
+    pub fn sender(&self) -> &Arc<Sender> {
+        &self.sender
+    }
+
+    /// 송신 통계
+    pub fn stats(&self) -> TransferStats {
+        self.sender.get_stats()
+    }
+}
+
+impl TypedSender<Unbound> {
+    /// 설정의 NIC 구성으로 송신자 생성
+    pub fn new(config: Config) -> Self {
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        Self::with_path_manager(config, path_manager)
+    }
+
+    /// 주어진 경로 관리자로 송신자 생성
+    pub fn with_path_manager(config: Config, path_manager: Arc<PathManager>) -> Self {
+        Self::from_sender(Arc::new(Sender::new(config, path_manager)))
+    }
+
+    /// 이미 만든 송신자를 감쌈 (공유 송신 상한 등 저수준 생성자를 쓴 경우)
+    pub fn from_sender(sender: Arc<Sender>) -> Self {
+        Self {
+            sender,
+            state: Unbound,
+        }
+    }
+
+    /// UDP 소켓을 바인딩하고 서버 루프 시작
+    pub async fn bind(self, bind_addr: SocketAddr) -> Result<TypedSender<Listening>> {
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.set_broadcast(true)?;
+        transport::apply_dscp(&socket, self.sender.config().dscp)?;
+        info!("SLS Sender started on {}", socket.local_addr()?);
+        Ok(self.bind_socket(Arc::new(socket)))
+    }
+
+    /// 주어진 소켓으로 서버 루프 시작 (시뮬레이터, 다른 전송 계층 등)
+    pub fn bind_socket<S: DatagramSocket>(self, socket: Arc<S>) -> TypedSender<Listening<S>> {
+        let sender = self.sender.clone();
+        let loop_socket = socket.clone();
+        let task = tokio::spawn(async move { sender.start_with_socket(loop_socket).await });
+        TypedSender {
+            state: Listening {
+                running: Running {
+                    sender: self.sender.clone(),
+                    socket,
+                    task,
+                },
+            },
+            sender: self.sender,
+        }
+    }
+}
+
+impl<S: DatagramSocket> TypedSender<Listening<S>> {
+    /// 바인딩한 소켓 주소
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.state.running.socket.local_addr()?)
+    }
+
+    /// 클라이언트가 핸드셰이크를 마칠 때까지 대기
+    ///
+    /// 제한 시간이 필요하면 `tokio::time::timeout`으로 감쌈 (시간이 지나 버려지면 서버도 정지).
+    /// 서버 루프가 먼저 끝나면 그 에러를, 정상 종료면 `Error::ConnectionClosed` 반환
+    pub async fn accept(mut self) -> Result<TypedSender<Connected<S>>> {
+        loop {
+            if let Some(client_addr) = self.connected_client() {
+                return Ok(self.into_connected(client_addr));
+            }
+            if self.state.running.task.is_finished() {
+                return Err(match (&mut self.state.running.task).await {
+                    Ok(Err(e)) => e,
+                    _ => Error::ConnectionClosed,
+                });
+            }
+            tokio::time::sleep(ACCEPT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// 주소를 아는 수신자에게 전송을 제안하고 수락되면 연결 상태로 전이
+    pub async fn offer(
+        self,
+        addr: SocketAddr,
+        total_file_size: u64,
+    ) -> Result<TypedSender<Connected<S>>> {
+        let socket = self.state.running.socket.clone();
+        self.sender.offer(addr, total_file_size, &*socket).await?;
+        Ok(self.into_connected(addr))
+    }
+
+    fn connected_client(&self) -> Option<SocketAddr> {
+        let addr = self.sender.client_addr()?;
+        self.sender.connection_state().is_established().then_some(addr)
+    }
+
+    fn into_connected(self, client_addr: SocketAddr) -> TypedSender<Connected<S>> {
+        TypedSender {
+            sender: self.sender,
+            state: Connected {
+                running: self.state.running,
+                client_addr,
+            },
+        }
+    }
+}
+
+impl<S: DatagramSocket> TypedSender<Connected<S>> {
+    /// 연결된 클라이언트 주소
+    pub fn client_addr(&self) -> SocketAddr {
+        self.state.client_addr
+    }
+
+    /// 바인딩한 소켓 주소
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.state.running.socket.local_addr()?)
+    }
+
+    /// 데이터를 세그먼트 하나로 전송
+    ///
+    /// 연결 후 클라이언트가 끊기거나 드레인을 시작하면 여전히 `Error::ConnectionClosed`
+    pub async fn send_data(&self, data: Bytes) -> Result<SegmentId> {
+        self.sender.send_data(data, &*self.state.running.socket).await
+    }
+
+    /// 여러 파일을 매니페스트와 함께 전송
+    pub async fn send_files(&self, files: &[(String, Bytes)]) -> Result<Manifest> {
+        self.sender.send_files(files, &*self.state.running.socket).await
+    }
+
+    /// 새 데이터를 막고 진행 중인 세그먼트가 끝나면 연결 종료
+    pub fn drain(&self) -> Result<()> {
+        self.sender.drain()
+    }
+
+    /// 이 연결로 스트림을 써 보내는 `AsyncWrite` (끝에 `shutdown`으로 스트림 끝 표시)
+    pub fn sink(&self) -> SenderSink<S> {
+        SenderSink::new(self.sender.clone(), self.state.running.socket.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::receiver::FileReceiver;
+    use crate::sim::{SimConfig, SimNetwork};
+    use crate::state::ConnectionState;
+    use crate::Receiver;
+
+    #[tokio::test]
+    async fn test_typed_sender_lifecycle_over_sim() {
+        let net = SimNetwork::new(SimConfig::default());
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let config = Config::default();
+
+        let listening =
+            TypedSender::new(config.clone()).bind_socket(Arc::new(net.bind(server_addr)));
+        assert_eq!(listening.local_addr().unwrap(), server_addr);
+
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind(client_addr)),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        let session = tokio::time::timeout(Duration::from_secs(5), listening.accept())
+            .await
+            .expect("핸드셰이크 시간 초과")
+            .unwrap();
+        assert_eq!(session.client_addr(), client_addr);
+
+        let payload = Bytes::from(vec![0x5Au8; 10_000]);
+        session.send_data(payload.clone()).await.unwrap();
+        let data = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_file(1))
+            .await
+            .expect("전송 시간 초과")
+            .unwrap();
+        assert_eq!(data, payload);
+
+        file_receiver.receiver().stop().await;
+
+        // 핸들을 버리면 서버 루프도 정지
+        let sender = session.sender().clone();
+        drop(session);
+        assert_eq!(sender.connection_state(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_accept_reports_server_loop_error() {
+        let config = Config {
+            chunk_size: 0,
+            ..Config::default()
+        };
+        let net = SimNetwork::new(SimConfig::default());
+        let socket = Arc::new(net.bind("10.0.0.1:9000".parse().unwrap()));
+        let listening = TypedSender::new(config).bind_socket(socket);
+
+        let result = tokio::time::timeout(Duration::from_secs(1), listening.accept())
+            .await
+            .expect("서버 루프 에러를 기다리지 않아야 함");
+        assert!(matches!(result, Err(Error::ChunkExceedsDatagram { .. })));
+    }
+}
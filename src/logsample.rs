@@ -0,0 +1,142 @@
+//! 청크/NACK 단위 디버그 로그 표본 추출
+//!
+//! 고속 전송 중 `debug` 레벨로 이벤트마다 로그를 남기면 로그 I/O가 비동기 태스크를 막아
+//! 그 자체가 병목이 됨. 종류별 `LogSampler`로 N건 중 1건만 기록하고, 나머지는 주기적인
+//! 요약 한 줄(구간 내 이벤트 수)로 대신함
+//!
+//! - `every == 1`: 모든 이벤트 기록 (표본 추출 안 함)
+//! - `every == 0`: 개별 이벤트는 기록하지 않고 요약만
+//! - 디버그 레벨이 꺼져 있으면 카운터도 건드리지 않음
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, Level};
+
+use crate::Config;
+
+/// 이벤트 종류 하나의 로그 표본기
+#[derive(Debug)]
+pub struct LogSampler {
+    /// 요약 로그에 붙는 이벤트 이름
+    name: &'static str,
+    every: u64,
+    summary_interval_ms: u64,
+    epoch: Instant,
+    events: AtomicU64,
+    /// 직전 요약까지 반영한 이벤트 수
+    summarized: AtomicU64,
+    /// 다음 요약 시각 (`epoch` 기준 밀리초)
+    next_summary_ms: AtomicU64,
+}
+
+impl LogSampler {
+    /// `every`건 중 1건 기록, `summary_interval`마다 요약 (0이면 요약 안 함)
+    pub fn new(name: &'static str, every: u32, summary_interval: Duration) -> Self {
+        let summary_interval_ms = summary_interval.as_millis() as u64;
+        Self {
+            name,
+            every: every as u64,
+            summary_interval_ms,
+            epoch: Instant::now(),
+            events: AtomicU64::new(0),
+            summarized: AtomicU64::new(0),
+            next_summary_ms: AtomicU64::new(summary_interval_ms),
+        }
+    }
+
+    /// `Config::log_sample_every`/`log_summary_interval_ms` 기반 생성
+    pub fn from_config(name: &'static str, config: &Config) -> Self {
+        Self::new(
+            name,
+            config.log_sample_every,
+            Duration::from_millis(config.log_summary_interval_ms),
+        )
+    }
+
+    /// 이번 이벤트를 기록해야 하면 true. 요약 주기가 지났으면 요약 로그도 남김
+    pub fn sample(&self) -> bool {
+        if !tracing::enabled!(Level::DEBUG) {
+            return false;
+        }
+        let admitted = self.admit();
+        if let Some(events) = self.due_summary(self.epoch.elapsed()) {
+            if events > 0 {
+                debug!(
+                    event = self.name,
+                    events,
+                    every = self.every,
+                    "최근 {}ms 이벤트 요약",
+                    self.summary_interval_ms
+                );
+            }
+        }
+        admitted
+    }
+
+    /// 이벤트 수를 세고 표본에 포함되는지 판정 (디버그 레벨과 무관)
+    fn admit(&self) -> bool {
+        let seen = self.events.fetch_add(1, Ordering::Relaxed);
+        self.every != 0 && seen.is_multiple_of(self.every)
+    }
+
+    /// `elapsed` 시점에 요약 주기가 지났으면 직전 요약 이후 이벤트 수
+    /// (동시에 불려도 한 호출만 요약을 가져감)
+    fn due_summary(&self, elapsed: Duration) -> Option<u64> {
+        if self.summary_interval_ms == 0 {
+            return None;
+        }
+        let now_ms = elapsed.as_millis() as u64;
+        let due = self.next_summary_ms.load(Ordering::Relaxed);
+        if now_ms < due {
+            return None;
+        }
+        self.next_summary_ms
+            .compare_exchange(
+                due,
+                now_ms + self.summary_interval_ms,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .ok()?;
+        let total = self.events.load(Ordering::Relaxed);
+        Some(total - self.summarized.swap(total, Ordering::Relaxed))
+    }
+
+    /// 지금까지 센 이벤트 수
+    pub fn events(&self) -> u64 {
+        self.events.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_in_n_events_admitted() {
+        let sampler = LogSampler::new("chunk", 100, Duration::from_millis(1000));
+        let admitted = (0..10_000).filter(|_| sampler.admit()).count();
+        assert_eq!(admitted, 100);
+        assert_eq!(sampler.events(), 10_000);
+
+        // 주기 전에는 요약 없음, 지나면 구간 이벤트 수를 한 번만 보고
+        assert_eq!(sampler.due_summary(Duration::from_millis(500)), None);
+        assert_eq!(
+            sampler.due_summary(Duration::from_millis(1000)),
+            Some(10_000)
+        );
+        assert_eq!(sampler.due_summary(Duration::from_millis(1001)), None);
+        for _ in 0..42 {
+            sampler.admit();
+        }
+        assert_eq!(sampler.due_summary(Duration::from_millis(2000)), Some(42));
+
+        let every = LogSampler::new("nack", 1, Duration::ZERO);
+        assert!((0..10).all(|_| every.admit()));
+        assert_eq!(every.due_summary(Duration::from_secs(60)), None);
+
+        let summary_only = LogSampler::new("nack", 0, Duration::from_millis(1000));
+        assert!((0..10).all(|_| !summary_only.admit()));
+    }
+}
@@ -0,0 +1,415 @@
+//! 다중 파일 전송 매니페스트
+//!
+//! - 한 세션(핸드셰이크/키 교환 1회)으로 여러 파일 전송
+//! - 매니페스트는 예약 세그먼트(`MANIFEST_SEGMENT_ID`)로 먼저 전송되어 NACK 복구를 그대로 사용
+//! - 파일마다 연속된 세그먼트 ID 구간을 예약: 세그먼트 ID만으로 파일 내 오프셋 계산
+//! - 청크 헤더/NACK/SegmentComplete의 `file_id`로 파일 범위를 구분
+//! - 수신자는 파일별 SHA-256 다이제스트로 독립 검증
+//! - `Config::segment_digests`면 세그먼트별 SHA-256도 실어, 수신자가 세그먼트를 전달하기 전에
+//!   검증하고 불일치한 세그먼트만 다시 받음 (청크 CRC를 통과한 손상은 파일 끝까지 가지 않음)
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::chunk::{FileId, SegmentId};
+use crate::{Error, Result};
+
+/// 매니페스트를 싣는 예약 세그먼트 ID (일반 세그먼트 ID는 1부터 시작)
+pub const MANIFEST_SEGMENT_ID: SegmentId = 0;
+
+/// 매니페스트/단일 스트림 데이터의 파일 ID (파일은 1부터 시작)
+pub const MANIFEST_FILE_ID: FileId = 0;
+
+/// 매니페스트 하나가 실을 수 있는 세그먼트 다이제스트 수 (수신자가 32바이트씩 보관)
+pub const MAX_SEGMENT_DIGESTS: usize = 1 << 20;
+
+/// 매니페스트 하나가 예약할 수 있는 파일 세그먼트 수 (수신자가 세그먼트마다 수신 여부를 보관)
+pub const MAX_MANIFEST_SEGMENTS: u64 = 1 << 24;
+
+/// 매니페스트의 파일 항목
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileEntry {
+    pub file_id: FileId,
+
+    /// 수신 디렉터리 기준 상대 경로
+    pub path: String,
+
+    pub size: u64,
+
+    /// 파일 전체 SHA-256
+    pub digest: [u8; 32],
+
+    /// 이 파일의 첫 세그먼트 ID (이후 `segment_count`개 연속)
+    pub first_segment: SegmentId,
+
+    /// 세그먼트별 SHA-256 (파일 내 순서, 싣지 않았으면 비어 있음)
+    pub segment_digests: Vec<[u8; 32]>,
+}
+
+impl FileEntry {
+    /// 파일이 차지하는 세그먼트 수
+    pub fn segment_count(&self, segment_size: usize) -> u64 {
+        self.size.div_ceil(segment_size as u64)
+    }
+}
+
+/// 세션에서 전송할 파일 목록
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 파일 데이터 세그먼트 크기 (마지막 세그먼트만 짧을 수 있음)
+    pub segment_size: u32,
+
+    pub files: Vec<FileEntry>,
+}
+
+impl Manifest {
+    /// 파일 목록으로 매니페스트 생성. 세그먼트 ID는 `first_segment`부터 파일 순서대로 배정
+    pub fn build(files: &[(String, Bytes)], segment_size: usize, first_segment: SegmentId) -> Self {
+        let mut next_segment = first_segment;
+        let files = files
+            .iter()
+            .enumerate()
+            .map(|(idx, (path, data))| {
+                let entry = FileEntry {
+                    file_id: idx as FileId + 1,
+                    path: path.clone(),
+                    size: data.len() as u64,
+                    digest: digest(data),
+                    first_segment: next_segment,
+                    segment_digests: Vec::new(),
+                };
+                next_segment += entry.segment_count(segment_size);
+                entry
+            })
+            .collect();
+
+        Self {
+            segment_size: segment_size as u32,
+            files,
+        }
+    }
+
+    /// 파일 세그먼트마다 SHA-256 계산 (`build`에 넘긴 것과 같은 파일 목록)
+    pub fn with_segment_digests(mut self, files: &[(String, Bytes)]) -> Self {
+        let segment_size = self.segment_size as usize;
+        for (entry, (_, data)) in self.files.iter_mut().zip(files) {
+            entry.segment_digests = data.chunks(segment_size).map(digest).collect();
+        }
+        self
+    }
+
+    /// 세그먼트 ID별 기대 다이제스트 (세그먼트 다이제스트를 실은 파일만)
+    pub fn segment_digests(&self) -> impl Iterator<Item = (SegmentId, [u8; 32])> + '_ {
+        self.files
+            .iter()
+            .flat_map(|file| (file.first_segment..).zip(file.segment_digests.iter().copied()))
+    }
+
+    /// 모든 파일이 차지하는 세그먼트 수 (u64를 넘으면 None)
+    pub fn total_segments(&self) -> Option<u64> {
+        self.files.iter().try_fold(0u64, |total, file| {
+            total.checked_add(file.segment_count(self.segment_size as usize))
+        })
+    }
+
+    /// 모든 파일 크기의 합 (u64를 넘으면 None)
+    pub fn total_size(&self) -> Option<u64> {
+        self.files
+            .iter()
+            .try_fold(0u64, |total, file| total.checked_add(file.size))
+    }
+
+    /// 세그먼트가 속한 파일과 파일 내 세그먼트 순번
+    pub fn locate(&self, segment_id: SegmentId) -> Option<(usize, u64)> {
+        let segment_size = self.segment_size as usize;
+        self.files.iter().enumerate().find_map(|(idx, file)| {
+            let index = segment_id.checked_sub(file.first_segment)?;
+            (index < file.segment_count(segment_size)).then_some((idx, index))
+        })
+    }
+
+    /// `dest` 아래의 파일 경로. 절대 경로나 `..`로 디렉터리를 벗어나는 항목은 거부
+    pub fn destination(&self, dest: &Path, file: &FileEntry) -> Result<PathBuf> {
+        let relative = Path::new(&file.path);
+        let escapes = relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+        if file.path.is_empty() || escapes {
+            return Err(Error::InvalidManifest(format!("허용되지 않는 경로: {}", file.path)));
+        }
+        Ok(dest.join(relative))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap_or_default()
+    }
+
+    /// 네트워크에서 받은 매니페스트 검증:
+    ///
+    /// - 파일 세그먼트 구간은 예약 세그먼트 뒤에서 파일 순서대로 빈틈 없이 이어지고 합쳐서
+    ///   `MAX_MANIFEST_SEGMENTS` 이하 (구간 끝 계산이 넘치지 않음)
+    /// - 세그먼트 다이제스트는 파일의 세그먼트 수와 같아야 하고 합쳐서 `MAX_SEGMENT_DIGESTS` 이하
+    ///
+    /// 파일 크기 한도는 수신자 설정에 따라 `check_size`로 따로 확인
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let manifest: Self = bincode::deserialize(bytes)?;
+        if manifest.segment_size == 0 {
+            return Err(Error::InvalidManifest("세그먼트 크기 0".into()));
+        }
+        let segment_size = manifest.segment_size as usize;
+        if let Some(first) = manifest.files.first() {
+            let mut next_segment = first.first_segment;
+            for file in &manifest.files {
+                let end = file
+                    .first_segment
+                    .checked_add(file.segment_count(segment_size))
+                    .filter(|_| file.first_segment == next_segment);
+                next_segment = end.ok_or_else(|| {
+                    Error::InvalidManifest(format!(
+                        "파일 {}의 세그먼트 구간이 이어지지 않음: 첫 세그먼트 {}",
+                        file.file_id, file.first_segment
+                    ))
+                })?;
+            }
+            let total = next_segment - first.first_segment;
+            if first.first_segment == MANIFEST_SEGMENT_ID || total > MAX_MANIFEST_SEGMENTS {
+                return Err(Error::InvalidManifest(format!(
+                    "세그먼트 구간 {}부터 {}개 (한도 {}개)",
+                    first.first_segment, total, MAX_MANIFEST_SEGMENTS
+                )));
+            }
+        }
+        let mut digests = 0usize;
+        for file in &manifest.files {
+            let count = file.segment_digests.len();
+            if count > 0 && count as u64 != file.segment_count(segment_size) {
+                return Err(Error::InvalidManifest(format!(
+                    "파일 {}의 세그먼트 다이제스트 {}개 != 세그먼트 {}개",
+                    file.file_id,
+                    count,
+                    file.segment_count(segment_size)
+                )));
+            }
+            digests += count;
+        }
+        if digests > MAX_SEGMENT_DIGESTS {
+            return Err(Error::InvalidManifest(format!(
+                "세그먼트 다이제스트 {}개 > 한도 {}개",
+                digests, MAX_SEGMENT_DIGESTS
+            )));
+        }
+        Ok(manifest)
+    }
+
+    /// 파일 크기 합이 `max_size` 이하인지 확인 (0이면 제한 없음, `Config::max_accept_file_size`).
+    /// 출력 파일을 만들기 전에 확인해 상대가 정한 크기로 디스크를 잡지 않음
+    pub fn check_size(&self, max_size: u64) -> Result<()> {
+        if max_size == 0 {
+            return Ok(());
+        }
+        match self.total_size() {
+            Some(size) if size <= max_size => Ok(()),
+            size => Err(Error::FileTooLarge {
+                size: size.unwrap_or(u64::MAX),
+                max: max_size,
+            }),
+        }
+    }
+}
+
+/// 파일 데이터의 SHA-256
+pub fn digest(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// 파일별 수신 결과
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    pub file_id: FileId,
+
+    /// 기록한 경로
+    pub path: PathBuf,
+
+    pub bytes: u64,
+
+    /// 매니페스트 다이제스트와 일치 여부
+    pub verified: bool,
+}
+
+/// 수신 중인 파일 하나 (세그먼트를 제자리에 기록하고 순서대로 다이제스트 계산)
+pub(crate) struct FileWriter {
+    file_id: FileId,
+    path: PathBuf,
+    file: File,
+    size: u64,
+    expected: [u8; 32],
+    hasher: Sha256,
+    received: Vec<bool>,
+    /// 다이제스트에 아직 반영하지 못한 앞선 세그먼트
+    reordered: BTreeMap<u64, Bytes>,
+    next_index: u64,
+    written: u64,
+}
+
+impl FileWriter {
+    pub(crate) fn create(path: PathBuf, entry: &FileEntry, segment_size: usize) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = File::create(&path)?;
+        file.set_len(entry.size)?;
+
+        Ok(Self {
+            file_id: entry.file_id,
+            path,
+            file,
+            size: entry.size,
+            expected: entry.digest,
+            hasher: Sha256::new(),
+            received: vec![false; entry.segment_count(segment_size) as usize],
+            reordered: BTreeMap::new(),
+            next_index: 0,
+            written: 0,
+        })
+    }
+
+    /// 파일 내 `index`번째 세그먼트 기록. 처음 받은 세그먼트면 true
+    pub(crate) fn write_segment(
+        &mut self,
+        index: u64,
+        segment_size: usize,
+        data: Bytes,
+    ) -> Result<bool> {
+        match self.received.get(index as usize) {
+            Some(false) => {}
+            Some(true) => return Ok(false),
+            None => return Err(Error::InvalidSegmentId { segment_id: index }),
+        }
+
+        let offset = index * segment_size as u64;
+        if offset + data.len() as u64 > self.size {
+            return Err(Error::BufferOverflow {
+                max_size: self.size as usize,
+            });
+        }
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&data)?;
+        self.received[index as usize] = true;
+        self.written += data.len() as u64;
+
+        self.reordered.insert(index, data);
+        while let Some(data) = self.reordered.remove(&self.next_index) {
+            self.hasher.update(&data);
+            self.next_index += 1;
+        }
+        Ok(true)
+    }
+
+    /// 기록 마무리 및 다이제스트 검증
+    pub(crate) fn finish(mut self) -> Result<FileReport> {
+        self.file.flush()?;
+        let complete =
+            self.next_index as usize == self.received.len() && self.written == self.size;
+        let digest: [u8; 32] = self.hasher.finalize().into();
+        Ok(FileReport {
+            file_id: self.file_id,
+            path: self.path,
+            bytes: self.written,
+            verified: complete && digest == self.expected,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_ranges_and_paths() {
+        let files = vec![
+            ("a.bin".to_string(), Bytes::from(vec![1u8; 2500])),
+            ("empty".to_string(), Bytes::new()),
+            ("dir/b.bin".to_string(), Bytes::from(vec![2u8; 1000])),
+        ];
+        let manifest = Manifest::build(&files, 1000, 5);
+
+        assert_eq!(manifest.total_segments(), Some(4));
+        assert_eq!(manifest.files[2].first_segment, 8);
+        assert_eq!(manifest.locate(5), Some((0, 0)));
+        assert_eq!(manifest.locate(7), Some((0, 2)));
+        assert_eq!(manifest.locate(8), Some((2, 0)));
+        assert_eq!(manifest.locate(9), None);
+        assert_eq!(manifest.locate(MANIFEST_SEGMENT_ID), None);
+
+        let restored = Manifest::from_bytes(&manifest.to_bytes()).unwrap();
+        assert_eq!(restored, manifest);
+
+        let dest = Path::new("/tmp/out");
+        assert_eq!(
+            manifest.destination(dest, &manifest.files[2]).unwrap(),
+            dest.join("dir/b.bin")
+        );
+        let mut evil = manifest.files[0].clone();
+        evil.path = "../etc/passwd".into();
+        assert!(manifest.destination(dest, &evil).is_err());
+        evil.path = "/etc/passwd".into();
+        assert!(manifest.destination(dest, &evil).is_err());
+
+        // 세그먼트 수와 맞지 않는 다이제스트 목록은 거부
+        let mut digested = manifest.with_segment_digests(&files);
+        assert!(Manifest::from_bytes(&digested.to_bytes()).is_ok());
+        digested.files[0].segment_digests.push([0u8; 32]);
+        assert!(matches!(
+            Manifest::from_bytes(&digested.to_bytes()),
+            Err(Error::InvalidManifest(_))
+        ));
+    }
+
+    #[test]
+    fn test_untrusted_manifest_bounds() {
+        let files = vec![
+            ("a.bin".to_string(), Bytes::from(vec![1u8; 2500])),
+            ("b.bin".to_string(), Bytes::from(vec![2u8; 1000])),
+        ];
+        let manifest = Manifest::build(&files, 1000, 5);
+        let rejected = |edit: &dyn Fn(&mut Manifest)| {
+            let mut evil = manifest.clone();
+            edit(&mut evil);
+            matches!(Manifest::from_bytes(&evil.to_bytes()), Err(Error::InvalidManifest(_)))
+        };
+
+        // 구간 끝이 넘치거나, 구간이 어긋나거나, 예약 세그먼트와 겹치거나, 너무 많은 세그먼트
+        assert!(rejected(&|m| m.files[0].first_segment = u64::MAX - 1));
+        assert!(rejected(&|m| m.files[1].first_segment = 100));
+        assert!(rejected(&|m| m.files[1].first_segment = 7));
+        assert!(rejected(&|m| {
+            m.files[0].first_segment = MANIFEST_SEGMENT_ID;
+            m.files[1].first_segment = 3;
+        }));
+        assert!(rejected(&|m| {
+            m.files[1].size = MAX_MANIFEST_SEGMENTS * 1000;
+        }));
+        assert!(rejected(&|m| m.files[1].size = u64::MAX));
+
+        // 파일 크기 합은 수신자 한도 이하 (합이 넘쳐도 한도 초과로 보고)
+        assert!(manifest.check_size(0).is_ok());
+        assert!(manifest.check_size(3500).is_ok());
+        assert!(matches!(
+            manifest.check_size(3499),
+            Err(Error::FileTooLarge { size: 3500, max: 3499 })
+        ));
+        let mut huge = manifest.clone();
+        huge.files[0].size = u64::MAX;
+        assert_eq!(huge.total_size(), None);
+        assert!(matches!(
+            huge.check_size(1 << 40),
+            Err(Error::FileTooLarge { size: u64::MAX, .. })
+        ));
+    }
+}
@@ -1,10 +1,21 @@
 //! 프로토콜 메시지 정의
 //!
-//! NACK 기반 프로토콜이므로 메시지는 최소화됨
+//! NACK 기반 프로토콜이므로 메시지는 최소화됨.
+//! 모든 메시지는 구조체 배치나 bincode와 무관하게 문서화된 바이트 배치로 직접 인코딩하며,
+//! 여러 바이트 정수/실수는 호스트와 상관없이 항상 리틀 엔디언 (`codec` 참고).
+//! 핸드셰이크 메시지(Init/InitAck)는 페이로드 첫 바이트에 형식 버전
+//! (`HANDSHAKE_FORMAT_VERSION`)을 실음.
+//! 새 필드는 형식 버전을 올려 뒤에만 덧붙이며, 디코더는 모르는 뒤쪽 바이트를 무시하고
+//! 이전 버전에는 없는 필드를 기본값으로 채우므로 구조체가 바뀌어도 다른 버전과 통신 가능
+
+use alloc::vec::Vec;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{ChunkId, SegmentId, MAGIC_NUMBER, PROTOCOL_VERSION};
+use crate::codec::{Reader, Writer};
+#[cfg(feature = "std")]
+use crate::fec::RedundancyMode;
+use crate::{ChunkId, FileId, SegmentId, MAGIC_NUMBER, PROTOCOL_VERSION};
 
 /// 메시지 타입
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -40,6 +51,47 @@ pub enum MessageType {
     /// 흐름 제어 피드백 (클라이언트 → 서버)
     FlowControl = 10,
 
+    /// Init 쿠키 발급 (서버 → 클라이언트, 도달성 확인용)
+    Cookie = 11,
+
+    /// 손실 보정 모드 변경 (서버 → 클라이언트)
+    ModeChange = 12,
+
+    /// NACK 철회 (클라이언트 → 서버, 중복 전송 사본이 이미 채운 청크)
+    NackCancel = 13,
+
+    /// 전송 제안 (서버 → 클라이언트, 서버가 먼저 연결을 시작하는 역방향 핸드셰이크)
+    Offer = 14,
+
+    /// 델타 NACK (클라이언트 → 서버, 직전 보고 이후 바뀐 누락 청크만)
+    NackDelta = 15,
+
+    /// 대역폭 측정 프로브 (서버 → 클라이언트, 핸드셰이크 직후 짧은 버스트)
+    Probe = 16,
+
+    /// 프로브 측정 결과 (클라이언트 → 서버)
+    ProbeReport = 17,
+
+    /// 주소 이전 도달성 확인 (서버 → 클라이언트의 새 주소)
+    PathChallenge = 18,
+
+    /// 주소 이전 확인 응답 (클라이언트 → 서버)
+    PathResponse = 19,
+
+    /// 전송 일시 정지 (서버 → 클라이언트, 정지 중 주기적으로 다시 보냄)
+    Pause = 20,
+
+    /// 전송 재개 (서버 → 클라이언트)
+    Resume = 21,
+
+    /// 세그먼트 재전송 포기 (서버 → 클라이언트, 재전송 한도를 넘긴 청크가 있음)
+    GiveUp = 22,
+
+    /// 네트워크 전환 (클라이언트 → 서버, 이전 경로의 추정치를 버리고 다시 측정)
+    NetworkChange = 23,
+
+    /// 균일 세그먼트 표시 (서버 → 클라이언트, 모든 바이트가 같은 세그먼트를 청크 없이 전달)
+    SparseSegment = 24,
 }
 
 /// 메시지 헤더
@@ -58,7 +110,72 @@ pub struct MessageHeader {
     pub payload_len: u32,
 }
 
+impl MessageType {
+    /// 와이어 상의 타입 인덱스 (선언 순서, bincode 호환)
+    fn wire_index(self) -> u32 {
+        match self {
+            MessageType::Chunk => 0,
+            MessageType::Nack => 1,
+            MessageType::SegmentComplete => 2,
+            MessageType::Init => 3,
+            MessageType::InitAck => 4,
+            MessageType::Close => 5,
+            MessageType::Heartbeat => 6,
+            MessageType::HeartbeatAck => 7,
+            MessageType::Stats => 8,
+            MessageType::FlowControl => 9,
+            MessageType::Cookie => 10,
+            MessageType::ModeChange => 11,
+            MessageType::NackCancel => 12,
+            MessageType::Offer => 13,
+            MessageType::NackDelta => 14,
+            MessageType::Probe => 15,
+            MessageType::ProbeReport => 16,
+            MessageType::PathChallenge => 17,
+            MessageType::PathResponse => 18,
+            MessageType::Pause => 19,
+            MessageType::Resume => 20,
+            MessageType::GiveUp => 21,
+            MessageType::NetworkChange => 22,
+            MessageType::SparseSegment => 23,
+        }
+    }
+
+    fn from_wire_index(index: u32) -> Option<Self> {
+        Some(match index {
+            0 => MessageType::Chunk,
+            1 => MessageType::Nack,
+            2 => MessageType::SegmentComplete,
+            3 => MessageType::Init,
+            4 => MessageType::InitAck,
+            5 => MessageType::Close,
+            6 => MessageType::Heartbeat,
+            7 => MessageType::HeartbeatAck,
+            8 => MessageType::Stats,
+            9 => MessageType::FlowControl,
+            10 => MessageType::Cookie,
+            11 => MessageType::ModeChange,
+            12 => MessageType::NackCancel,
+            13 => MessageType::Offer,
+            14 => MessageType::NackDelta,
+            15 => MessageType::Probe,
+            16 => MessageType::ProbeReport,
+            17 => MessageType::PathChallenge,
+            18 => MessageType::PathResponse,
+            19 => MessageType::Pause,
+            20 => MessageType::Resume,
+            21 => MessageType::GiveUp,
+            22 => MessageType::NetworkChange,
+            23 => MessageType::SparseSegment,
+            _ => return None,
+        })
+    }
+}
+
 impl MessageHeader {
+    /// 인코딩된 헤더 크기 (바이트)
+    pub const ENCODED_LEN: usize = 13;
+
     pub fn new(msg_type: MessageType, payload_len: u32) -> Self {
         Self {
             magic: MAGIC_NUMBER,
@@ -67,6 +184,56 @@ impl MessageHeader {
             payload_len,
         }
     }
+
+    fn encode(&self, w: &mut Writer) {
+        w.put_u32(self.magic);
+        w.put_u8(self.version);
+        w.put_u32(self.msg_type.wire_index());
+        w.put_u32(self.payload_len);
+    }
+
+    fn decode(r: &mut Reader<'_>) -> Option<Self> {
+        Some(Self {
+            magic: r.get_u32()?,
+            version: r.get_u8()?,
+            msg_type: MessageType::from_wire_index(r.get_u32()?)?,
+            payload_len: r.get_u32()?,
+        })
+    }
+
+    /// 페이로드 없는 메시지(Close 등)는 헤더만으로 전송
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(Self::ENCODED_LEN);
+        self.encode(&mut w);
+        w.into_inner()
+    }
+
+    /// 바이트 앞부분에서 헤더 파싱
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::decode(&mut Reader::new(bytes))
+    }
+}
+
+/// 핸드셰이크 메시지(Init/InitAck) 와이어 형식 버전
+pub const HANDSHAKE_FORMAT_VERSION: u8 = 4;
+
+/// 나눌 수 없는 컨트롤 메시지가 데이터그램 하나에 들어가는지 확인
+///
+/// 경로에서 잘리거나 조용히 버려지는 대신 보내기 전에 `Error::MessageTooLarge`로 거부
+#[cfg(feature = "std")]
+pub fn bounded_datagram(
+    msg_type: MessageType,
+    bytes: Vec<u8>,
+    max_datagram_size: usize,
+) -> crate::Result<Vec<u8>> {
+    if bytes.len() > max_datagram_size {
+        return Err(crate::Error::MessageTooLarge {
+            msg_type,
+            size: bytes.len(),
+            max: max_datagram_size,
+        });
+    }
+    Ok(bytes)
 }
 
 /// NACK 메시지 (누락 청크 요청)
@@ -87,9 +254,24 @@ pub struct NackMessage {
 
     /// NIC ID (어느 경로로 재전송 요청인지)
     pub nic_id: u8,
+
+    /// 세그먼트가 속한 파일 ID (다중 파일 세션)
+    pub file_id: FileId,
+
+    /// 분할된 NACK의 조각 순번 (0부터)
+    pub fragment_index: u16,
+
+    /// 분할 조각 수 (분할하지 않았으면 1)
+    pub fragment_count: u16,
+
+    /// 핸드셰이크에서 정한 연결 ID (Init의 `session_id`, 0이면 없음)
+    pub connection_id: u64,
 }
 
 impl NackMessage {
+    /// 누락 청크 목록을 제외한 인코딩 크기 (헤더 포함)
+    const FIXED_LEN: usize = MessageHeader::ENCODED_LEN + 8 + 8 + 4 + 1 + 4 + 2 + 2 + 8;
+
     pub fn new(
         segment_id: SegmentId,
         missing_chunk_ids: Vec<ChunkId>,
@@ -101,77 +283,504 @@ impl NackMessage {
             missing_chunk_ids,
             receive_ratio,
             nic_id,
+            file_id: 0,
+            fragment_index: 0,
+            fragment_count: 1,
+            connection_id: 0,
+        }
+    }
+
+    /// 세그먼트 전체 재요청 (빈 누락 목록)
+    ///
+    /// 청크를 하나도 받지 못해 청크 수를 모르는 세그먼트용. 송신자는 원본 청크를 모두 바로 재전송
+    pub fn whole_segment(segment_id: SegmentId) -> Self {
+        Self::new(segment_id, Vec::new(), 0.0, 0)
+    }
+
+    /// 세그먼트 전체 재요청인지
+    pub fn is_whole_segment(&self) -> bool {
+        self.missing_chunk_ids.is_empty()
+    }
+
+    /// 인코딩된 크기 (바이트)
+    pub fn encoded_len(&self) -> usize {
+        Self::FIXED_LEN + 4 * self.missing_chunk_ids.len()
+    }
+
+    /// 데이터그램 하나(`max_datagram_size`)에 들어가도록 누락 청크 목록을 나눔
+    ///
+    /// 조각마다 세그먼트/파일 정보를 그대로 싣기 때문에 송신자는 도착한 조각만으로
+    /// 해당 청크를 재전송할 수 있음 (일부 조각이 유실돼도 나머지는 유효)
+    pub fn fragments(self, max_datagram_size: usize) -> Vec<Self> {
+        if self.encoded_len() <= max_datagram_size {
+            return alloc::vec![self];
         }
+
+        let per_fragment = (max_datagram_size.saturating_sub(Self::FIXED_LEN) / 4).max(1);
+        let count = self.missing_chunk_ids.len().div_ceil(per_fragment);
+        self.missing_chunk_ids
+            .chunks(per_fragment)
+            .enumerate()
+            .map(|(index, ids)| Self {
+                missing_chunk_ids: ids.to_vec(),
+                fragment_index: index as u16,
+                fragment_count: count as u16,
+                ..self.clone()
+            })
+            .collect()
+    }
+
+    /// 파일 범위 지정
+    pub fn with_file_id(mut self, file_id: FileId) -> Self {
+        self.file_id = file_id;
+        self
+    }
+
+    /// 연결 ID 지정 (주소가 바뀌어도 서버가 같은 연결로 알아봄)
+    pub fn with_connection_id(mut self, connection_id: u64) -> Self {
+        self.connection_id = connection_id;
+        self
     }
 
     /// 바이트로 직렬화 (최소 크기)
     pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::Nack, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
-
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+        let payload_len = self.encoded_len() - MessageHeader::ENCODED_LEN;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+
+        MessageHeader::new(MessageType::Nack, payload_len as u32).encode(&mut w);
+        w.put_u64(self.segment_id);
+        w.put_u64(self.missing_chunk_ids.len() as u64);
+        for &chunk_id in &self.missing_chunk_ids {
+            w.put_u32(chunk_id);
+        }
+        w.put_f32(self.receive_ratio);
+        w.put_u8(self.nic_id);
+        w.put_u32(self.file_id);
+        w.put_u16(self.fragment_index);
+        w.put_u16(self.fragment_count);
+        w.put_u64(self.connection_id);
+
+        debug_assert_eq!(w.len(), MessageHeader::ENCODED_LEN + payload_len);
+        w.into_inner()
     }
 
     /// 바이트에서 역직렬화
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        // bincode는 가변 길이이므로 직접 역직렬화 시도
-        // 헤더: magic(4) + version(1) + msg_type(1) + payload_len(4) = 약 14~20바이트
-        if bytes.len() < 14 {
+        let mut r = Reader::new(bytes);
+
+        let header = MessageHeader::decode(&mut r)?;
+        if header.msg_type != MessageType::Nack {
             return None;
         }
 
-        // 헤더 파싱 시도 (bincode는 앞에서부터 읽음)
-        let header: MessageHeader = match bincode::deserialize(bytes) {
-            Ok(h) => h,
-            Err(_) => return None,
-        };
+        let segment_id = r.get_u64()?;
+        let count = r.get_u64()? as usize;
+        // 길이 필드가 실제 남은 바이트보다 크면 거부 (과대 할당 방지)
+        if count > r.remaining() / 4 {
+            return None;
+        }
+        let mut missing_chunk_ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            missing_chunk_ids.push(r.get_u32()?);
+        }
 
-        if header.msg_type != MessageType::Nack {
+        Some(Self {
+            segment_id,
+            missing_chunk_ids,
+            receive_ratio: r.get_f32()?,
+            nic_id: r.get_u8()?,
+            file_id: r.get_u32().unwrap_or(0),
+            fragment_index: r.get_u16().unwrap_or(0),
+            fragment_count: r.get_u16().unwrap_or(1),
+            connection_id: r.get_u64().unwrap_or(0),
+        })
+    }
+}
+
+/// NACK 철회 메시지 (클라이언트 → 서버)
+///
+/// NACK을 보낸 청크를 중복 전송 사본이 먼저 채웠을 때 전송. 송신자는 아직 재전송하지 않은
+/// 해당 청크를 재전송 큐에서 뺌 (이미 재전송했으면 무시)
+///
+/// ```text
+/// header(13) | segment_id(8) | file_id(4) | count(2) | chunk_id(4) × count
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NackCancelMessage {
+    pub segment_id: SegmentId,
+
+    pub file_id: FileId,
+
+    /// 더 이상 필요 없는 청크 ID
+    pub chunk_ids: Vec<ChunkId>,
+}
+
+impl NackCancelMessage {
+    pub fn new(segment_id: SegmentId, file_id: FileId, chunk_ids: Vec<ChunkId>) -> Self {
+        Self {
+            segment_id,
+            file_id,
+            chunk_ids,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = 8 + 4 + 2 + 4 * self.chunk_ids.len();
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::NackCancel, payload_len as u32).encode(&mut w);
+        w.put_u64(self.segment_id);
+        w.put_u32(self.file_id);
+        w.put_u16(self.chunk_ids.len() as u16);
+        for &chunk_id in &self.chunk_ids {
+            w.put_u32(chunk_id);
+        }
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.msg_type != MessageType::NackCancel {
+            return None;
+        }
+
+        let segment_id = r.get_u64()?;
+        let file_id = r.get_u32()?;
+        let count = r.get_u16()? as usize;
+        if count > r.remaining() / 4 {
+            return None;
+        }
+        let chunk_ids = (0..count).map(|_| r.get_u32()).collect::<Option<_>>()?;
+        Some(Self {
+            segment_id,
+            file_id,
+            chunk_ids,
+        })
+    }
+}
+
+/// 델타 NACK 메시지 (클라이언트 → 서버)
+///
+/// 같은 세그먼트의 직전 보고(`base_seq`) 이후 새로 누락된 청크(`added`)와 채워진 청크
+/// (`removed`)만 보냄. 송신자는 세그먼트마다 마지막으로 들은 누락 집합을 갖고 있다가
+/// 델타를 적용한 전체 집합을 재전송. `full`이면 `added`가 전체 누락 집합이고 이전 상태를 대체.
+/// 기준이 맞지 않는 델타(앞선 델타 유실)는 적용하지 않으며, 수신자가 주기적으로 보내는
+/// 전체 집합으로 다시 맞춰짐
+///
+/// ```text
+/// header(13) | segment_id(8) | file_id(4) | seq(2) | base_seq(2) | full(1) | receive_ratio(f32, 4)
+/// | nic_id(1) | added_count(2) | removed_count(2) | chunk_id(4) × (added + removed)
+/// | connection_id(8)
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct NackDeltaMessage {
+    pub segment_id: SegmentId,
+
+    pub file_id: FileId,
+
+    /// 이 보고의 순번 (세그먼트별, 랩어라운드)
+    pub seq: u16,
+
+    /// 델타의 기준이 되는 직전 보고 순번 (`full`이면 무시)
+    pub base_seq: u16,
+
+    /// 전체 누락 집합인지
+    pub full: bool,
+
+    /// 현재 수신률 (재전송 우선순위)
+    pub receive_ratio: f32,
+
+    pub nic_id: u8,
+
+    /// 새로 누락된 청크 (`full`이면 전체 누락 청크)
+    pub added: Vec<ChunkId>,
+
+    /// 직전 보고 이후 채워진 청크
+    pub removed: Vec<ChunkId>,
+
+    /// 연결 ID (0이면 없음, 없는 이전 형식도 0으로 읽음)
+    pub connection_id: u64,
+}
+
+impl NackDeltaMessage {
+    /// 청크 목록을 제외한 인코딩 크기 (헤더 포함)
+    const FIXED_LEN: usize = MessageHeader::ENCODED_LEN + 8 + 4 + 2 + 2 + 1 + 4 + 1 + 2 + 2 + 8;
+
+    /// 전체 누락 집합 보고
+    pub fn full(segment_id: SegmentId, seq: u16, missing: Vec<ChunkId>, receive_ratio: f32) -> Self {
+        Self {
+            segment_id,
+            file_id: 0,
+            seq,
+            base_seq: seq,
+            full: true,
+            receive_ratio,
+            nic_id: 0,
+            added: missing,
+            removed: Vec::new(),
+            connection_id: 0,
+        }
+    }
+
+    /// `base_seq` 보고 이후의 변화
+    pub fn delta(
+        segment_id: SegmentId,
+        seq: u16,
+        base_seq: u16,
+        added: Vec<ChunkId>,
+        removed: Vec<ChunkId>,
+        receive_ratio: f32,
+    ) -> Self {
+        Self {
+            segment_id,
+            file_id: 0,
+            seq,
+            base_seq,
+            full: false,
+            receive_ratio,
+            nic_id: 0,
+            added,
+            removed,
+            connection_id: 0,
+        }
+    }
+
+    /// 파일 범위 지정
+    pub fn with_file_id(mut self, file_id: FileId) -> Self {
+        self.file_id = file_id;
+        self
+    }
+
+    /// 연결 ID 지정
+    pub fn with_connection_id(mut self, connection_id: u64) -> Self {
+        self.connection_id = connection_id;
+        self
+    }
+
+    /// 인코딩된 크기 (바이트)
+    pub fn encoded_len(&self) -> usize {
+        Self::FIXED_LEN + 4 * (self.added.len() + self.removed.len())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = self.encoded_len() - MessageHeader::ENCODED_LEN;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::NackDelta, payload_len as u32).encode(&mut w);
+        w.put_u64(self.segment_id);
+        w.put_u32(self.file_id);
+        w.put_u16(self.seq);
+        w.put_u16(self.base_seq);
+        w.put_bool(self.full);
+        w.put_f32(self.receive_ratio);
+        w.put_u8(self.nic_id);
+        w.put_u16(self.added.len() as u16);
+        w.put_u16(self.removed.len() as u16);
+        for &chunk_id in self.added.iter().chain(&self.removed) {
+            w.put_u32(chunk_id);
+        }
+        w.put_u64(self.connection_id);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::NackDelta {
+            return None;
+        }
+
+        let segment_id = r.get_u64()?;
+        let file_id = r.get_u32()?;
+        let seq = r.get_u16()?;
+        let base_seq = r.get_u16()?;
+        let full = r.get_bool()?;
+        let receive_ratio = r.get_f32()?;
+        let nic_id = r.get_u8()?;
+        let added_count = r.get_u16()? as usize;
+        let removed_count = r.get_u16()? as usize;
+        if added_count + removed_count > r.remaining() / 4 {
+            return None;
+        }
+        let added = (0..added_count).map(|_| r.get_u32()).collect::<Option<_>>()?;
+        let removed = (0..removed_count).map(|_| r.get_u32()).collect::<Option<_>>()?;
+        Some(Self {
+            segment_id,
+            file_id,
+            seq,
+            base_seq,
+            full,
+            receive_ratio,
+            nic_id,
+            added,
+            removed,
+            connection_id: r.get_u64().unwrap_or(0),
+        })
+    }
+}
+
+/// 대역폭 측정 프로브 (서버 → 클라이언트)
+///
+/// 서버가 `count`개를 연달아 보내고 클라이언트는 도착 간격으로 병목 대역폭을 잼.
+/// 데이터 청크와 같은 경로 특성을 보도록 청크 데이터그램 크기까지 0으로 채움
+///
+/// ```text
+/// header(13) | seq(2) | count(2) | 0 패딩
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeMessage {
+    /// 버스트 내 순번 (0부터)
+    pub seq: u16,
+
+    /// 버스트 전체 프로브 수
+    pub count: u16,
+}
+
+impl ProbeMessage {
+    /// 패딩을 제외한 인코딩 크기 (헤더 포함)
+    pub const ENCODED_LEN: usize = MessageHeader::ENCODED_LEN + 2 + 2;
+
+    pub fn new(seq: u16, count: u16) -> Self {
+        Self { seq, count }
+    }
+
+    /// `datagram_len`까지 0으로 채운 데이터그램 (더 짧으면 패딩 없음)
+    pub fn to_bytes(&self, datagram_len: usize) -> Vec<u8> {
+        let len = datagram_len.max(Self::ENCODED_LEN);
+        let mut w = Writer::with_capacity(len);
+        MessageHeader::new(MessageType::Probe, (len - MessageHeader::ENCODED_LEN) as u32)
+            .encode(&mut w);
+        w.put_u16(self.seq);
+        w.put_u16(self.count);
+        let mut bytes = w.into_inner();
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::Probe {
             return None;
         }
 
-        // 헤더 직렬화해서 실제 크기 확인
-        let header_bytes = bincode::serialize(&header).ok()?;
-        let header_size = header_bytes.len();
+        let seq = r.get_u16()?;
+        let count = r.get_u16()?;
+        (seq < count).then_some(Self { seq, count })
+    }
+}
+
+/// 프로브 측정 결과 (클라이언트 → 서버)
+///
+/// ```text
+/// header(13) | received(2) | rate(8, bytes/s)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeReportMessage {
+    /// 도착한 프로브 수
+    pub received: u16,
+
+    /// 측정한 전달 속도 (bytes/s, 측정 불가면 0)
+    pub rate: u64,
+}
+
+impl ProbeReportMessage {
+    pub fn new(received: u16, rate: u64) -> Self {
+        Self { received, rate }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = 2 + 8;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::ProbeReport, payload_len as u32).encode(&mut w);
+        w.put_u16(self.received);
+        w.put_u64(self.rate);
+        w.into_inner()
+    }
 
-        if bytes.len() < header_size {
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::ProbeReport {
             return None;
         }
 
-        // 페이로드 파싱
-        bincode::deserialize(&bytes[header_size..]).ok()
+        Some(Self {
+            received: r.get_u16()?,
+            rate: r.get_u64()?,
+        })
     }
 }
 
 /// 세그먼트 완료 메시지
+///
+/// 연결 ID는 페이로드 뒤에 덧붙임 (없는 이전 형식은 0)
+///
+/// ```text
+/// header(13) | segment_id(8) | file_id(4) | total_chunks_received(4)
+/// | duplicates_received(4) | elapsed_ms(8) | connection_id(8)
+/// ```
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SegmentCompleteMessage {
     pub segment_id: SegmentId,
+    pub file_id: FileId,
     pub total_chunks_received: u32,
     pub duplicates_received: u32,
     pub elapsed_ms: u64,
+    #[serde(skip)]
+    pub connection_id: u64,
 }
 
+#[cfg(feature = "std")]
 impl SegmentCompleteMessage {
+    const PAYLOAD_LEN: usize = 8 + 4 + 4 + 4 + 8 + 8;
+
     pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::SegmentComplete, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + Self::PAYLOAD_LEN);
+        MessageHeader::new(MessageType::SegmentComplete, Self::PAYLOAD_LEN as u32).encode(&mut w);
+        w.put_u64(self.segment_id);
+        w.put_u32(self.file_id);
+        w.put_u32(self.total_chunks_received);
+        w.put_u32(self.duplicates_received);
+        w.put_u64(self.elapsed_ms);
+        w.put_u64(self.connection_id);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::SegmentComplete {
+            return None;
+        }
 
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+        Some(Self {
+            segment_id: r.get_u64()?,
+            file_id: r.get_u32()?,
+            total_chunks_received: r.get_u32()?,
+            duplicates_received: r.get_u32()?,
+            elapsed_ms: r.get_u64()?,
+            connection_id: r.get_u64().unwrap_or(0),
+        })
     }
 }
 
 /// 연결 초기화 메시지 (클라이언트 → 서버)
 ///
-/// 클라이언트가 서버에 연결 시 보내는 초기 핸드쉐이크 메시지
+/// 클라이언트가 서버에 연결 시 보내는 초기 핸드쉐이크 메시지.
+/// 와이어 배치 v4 (헤더 13바이트 뒤, 정수는 리틀 엔디언):
+///
+/// ```text
+/// format_version(1) | client_public_key(32) | encryption_enabled(1) | nic_count(1)
+/// | chunk_size(2) | segment_size(4) | buffer_size(4) | protocol_version(1)
+/// | timestamp_us(8) | cookie.issued_at_us(8) | cookie.tag(16) | session_id(8)
+/// | max_supported_chunk_size(2, v2) | max_supported_segment_size(4, v2)
+/// | sparse_supported(1, v3) | delta_nacks(1, v4)
+/// ```
+///
+/// v1 Init에는 한도 필드가 없으므로 0(제한 없음)으로, v2 이하는 균일 세그먼트 미지원으로,
+/// v3 이하는 델타 NACK 미사용으로 읽음
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitMessage {
     /// 클라이언트 공개키 (X25519, 32바이트)
@@ -198,8 +807,29 @@ pub struct InitMessage {
     
     /// 클라이언트 타임스탬프 (microseconds since epoch) - RTT 측정용
     pub timestamp_us: u64,
+
+    /// 서버가 발급한 쿠키 에코 (비어 있으면 첫 Init)
+    pub cookie: InitCookie,
+
+    /// 클라이언트 세션 ID (수신자 시작마다 무작위)
+    /// 같은 주소에서 다른 세션 ID의 Init이 오면 서버는 재시작으로 보고 이전 세션 정리
+    pub session_id: u64,
+
+    /// 클라이언트가 받을 수 있는 최대 청크 크기 (0이면 제한 없음)
+    pub max_supported_chunk_size: u16,
+
+    /// 클라이언트가 조립할 수 있는 최대 세그먼트 크기 (0이면 제한 없음)
+    /// 서버는 세그먼트 크기를 이 값 이하로 줄임
+    pub max_supported_segment_size: u32,
+
+    /// 균일 세그먼트 표시(`SparseSegmentMessage`)를 펼칠 수 있음
+    pub sparse_supported: bool,
+
+    /// 델타 NACK(`NackDeltaMessage`)을 보내려 함 (서버가 InitAck으로 수락해야 사용)
+    pub delta_nacks: bool,
 }
 
+#[cfg(feature = "std")]
 impl InitMessage {
     pub fn new(encryption_enabled: bool, client_public_key: [u8; 32]) -> Self {
         use std::time::{SystemTime, UNIX_EPOCH};
@@ -217,45 +847,231 @@ impl InitMessage {
             buffer_size: 2 * 1024 * 1024,
             protocol_version: crate::PROTOCOL_VERSION,
             timestamp_us,
+            cookie: InitCookie::default(),
+            session_id: rand::random::<u64>().max(1),
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
+            sparse_supported: false,
+            delta_nacks: false,
         }
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::Init, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
+    /// 설정의 수신 한도를 와이어 필드로 (필드 범위를 넘는 한도는 제한 없음과 같음)
+    pub fn with_supported_limits(mut self, config: &crate::Config) -> Self {
+        self.max_supported_chunk_size = u16::try_from(config.max_supported_chunk_size).unwrap_or(0);
+        self.max_supported_segment_size =
+            u32::try_from(config.max_supported_segment_size).unwrap_or(0);
+        self
+    }
+
+    /// 균일 세그먼트 표시 지원 광고
+    pub fn with_sparse_support(mut self) -> Self {
+        self.sparse_supported = true;
+        self
+    }
+
+    /// 델타 NACK 사용 요청 (`Config::delta_nacks`)
+    pub fn with_delta_nacks(mut self, enabled: bool) -> Self {
+        self.delta_nacks = enabled;
+        self
+    }
+
+    /// v4 페이로드 길이 (형식 버전 바이트 포함)
+    const PAYLOAD_LEN_V4: usize = 1 + 32 + 1 + 1 + 2 + 4 + 4 + 1 + 8 + 8 + 16 + 8 + 2 + 4 + 1 + 1;
 
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + Self::PAYLOAD_LEN_V4);
+        MessageHeader::new(MessageType::Init, Self::PAYLOAD_LEN_V4 as u32).encode(&mut w);
+        w.put_u8(HANDSHAKE_FORMAT_VERSION);
+        w.put_slice(&self.client_public_key);
+        w.put_bool(self.encryption_enabled);
+        w.put_u8(self.nic_count);
+        w.put_u16(self.chunk_size);
+        w.put_u32(self.segment_size);
+        w.put_u32(self.buffer_size);
+        w.put_u8(self.protocol_version);
+        w.put_u64(self.timestamp_us);
+        w.put_u64(self.cookie.issued_at_us);
+        w.put_slice(&self.cookie.tag);
+        w.put_u64(self.session_id);
+        w.put_u16(self.max_supported_chunk_size);
+        w.put_u32(self.max_supported_segment_size);
+        w.put_bool(self.sparse_supported);
+        w.put_bool(self.delta_nacks);
+        w.into_inner()
     }
 
+    /// 형식 버전이 더 높은 피어가 덧붙인 필드는 무시
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() < 10 {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::Init {
             return None;
         }
-        
-        let header: MessageHeader = bincode::deserialize(bytes).ok()?;
-        if header.msg_type != MessageType::Init {
+        let version = r.get_u8()?;
+        if version == 0 {
             return None;
         }
-        
-        let header_bytes = bincode::serialize(&header).ok()?;
-        let header_size = header_bytes.len();
-        
-        if bytes.len() < header_size {
+
+        let mut init = Self {
+            client_public_key: r.get_array()?,
+            encryption_enabled: r.get_bool()?,
+            nic_count: r.get_u8()?,
+            chunk_size: r.get_u16()?,
+            segment_size: r.get_u32()?,
+            buffer_size: r.get_u32()?,
+            protocol_version: r.get_u8()?,
+            timestamp_us: r.get_u64()?,
+            cookie: InitCookie {
+                issued_at_us: r.get_u64()?,
+                tag: r.get_array()?,
+            },
+            session_id: r.get_u64()?,
+            max_supported_chunk_size: 0,
+            max_supported_segment_size: 0,
+            sparse_supported: false,
+            delta_nacks: false,
+        };
+        if version >= 2 {
+            init.max_supported_chunk_size = r.get_u16()?;
+            init.max_supported_segment_size = r.get_u32()?;
+        }
+        if version >= 3 {
+            init.sparse_supported = r.get_bool()?;
+        }
+        if version >= 4 {
+            init.delta_nacks = r.get_bool()?;
+        }
+        Some(init)
+    }
+}
+
+/// Init 쿠키 (서버만 검증 가능한 무상태 토큰)
+///
+/// `tag` = HMAC-SHA256(서버 비밀, 클라이언트 주소 ‖ 발급 시각)의 앞 16바이트
+/// 서버는 세션 상태 없이 발급 시각과 태그만으로 신선도/주소를 확인
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InitCookie {
+    /// 발급 시각 (서버 기준 마이크로초, 0이면 쿠키 없음)
+    pub issued_at_us: u64,
+    /// 인증 태그
+    pub tag: [u8; 16],
+}
+
+#[cfg(feature = "std")]
+impl InitCookie {
+    /// 쿠키 없음 여부
+    pub fn is_empty(&self) -> bool {
+        self.issued_at_us == 0
+    }
+}
+
+/// Init 쿠키 메시지 (서버 → 클라이언트)
+///
+/// 쿠키 없는 Init에 대한 응답. Init보다 작게 유지하여 증폭이 일어나지 않도록 함
+/// 클라이언트는 이 쿠키를 담아 Init을 다시 보내야 InitAck을 받음
+///
+/// ```text
+/// header(13) | cookie.issued_at_us(8) | cookie.tag(16)
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CookieMessage {
+    pub cookie: InitCookie,
+}
+
+#[cfg(feature = "std")]
+impl CookieMessage {
+    pub fn new(cookie: InitCookie) -> Self {
+        Self { cookie }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 8 + 16);
+        MessageHeader::new(MessageType::Cookie, 8 + 16).encode(&mut w);
+        w.put_u64(self.cookie.issued_at_us);
+        w.put_slice(&self.cookie.tag);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::Cookie {
             return None;
         }
-        
-        bincode::deserialize(&bytes[header_size..]).ok()
+
+        Some(Self::new(InitCookie {
+            issued_at_us: r.get_u64()?,
+            tag: r.get_array()?,
+        }))
+    }
+}
+
+/// 손실 보정 모드 변경 메시지 (서버 → 클라이언트)
+///
+/// 지속 손실로 서버가 중복 전송과 FEC 사이를 전환할 때 전송
+/// 수신자는 이 모드에 맞춰 패리티 청크 처리 여부를 결정
+///
+/// ```text
+/// header(13) | mode(4, 0 = Duplicate, 1 = Fec)
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeChangeMessage {
+    pub mode: RedundancyMode,
+}
+
+#[cfg(feature = "std")]
+impl ModeChangeMessage {
+    pub fn new(mode: RedundancyMode) -> Self {
+        Self { mode }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 4);
+        MessageHeader::new(MessageType::ModeChange, 4).encode(&mut w);
+        w.put_u32(match self.mode {
+            RedundancyMode::Duplicate => 0,
+            RedundancyMode::Fec => 1,
+        });
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::ModeChange {
+            return None;
+        }
+
+        let mode = match r.get_u32()? {
+            0 => RedundancyMode::Duplicate,
+            1 => RedundancyMode::Fec,
+            _ => return None,
+        };
+        Some(Self::new(mode))
     }
 }
 
 /// 연결 초기화 응답 (서버 → 클라이언트)
 ///
 /// 서버가 클라이언트의 Init에 응답하여 보내는 메시지
-/// 이 메시지를 받으면 클라이언트는 데이터 수신 준비 완료
+/// 이 메시지를 받으면 클라이언트는 데이터 수신 준비 완료.
+/// 와이어 배치 v4 (v2는 v1과 같음, 헤더 13바이트 뒤, 정수/실수는 리틀 엔디언):
+///
+/// ```text
+/// format_version(1) | server_public_key(32) | session_key(32) | encryption_enabled(1)
+/// | nic_count(1) | chunk_size(2) | segment_size(4) | redundancy_ratio(f32, 4)
+/// | total_file_size(8) | total_segments(8) | chunks_per_segment(4)
+/// | protocol_version(1) | client_timestamp_us(8) | server_timestamp_us(8)
+/// | sparse_segments(1, v3) | delta_nacks(1, v4)
+/// ```
+///
+/// v2 이하 InitAck은 균일 세그먼트 표시를 보내지 않는 것으로, v3 이하는 델타 NACK을 해석하지
+/// 못하는 것으로 읽음
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InitAckMessage {
     /// 서버 공개키 (X25519, 32바이트)
@@ -299,8 +1115,15 @@ pub struct InitAckMessage {
     
     /// 서버 타임스탬프 (서버에서 응답 보낼 때 시간)
     pub server_timestamp_us: u64,
+
+    /// 균일 세그먼트를 `SparseSegmentMessage`로 보낼 수 있음
+    pub sparse_segments: bool,
+
+    /// Init의 델타 NACK 요청을 수락함 (꺼져 있으면 클라이언트는 전체 NACK만 보냄)
+    pub delta_nacks: bool,
 }
 
+#[cfg(feature = "std")]
 impl InitAckMessage {
     pub fn new(
         total_file_size: u64,
@@ -326,7 +1149,7 @@ impl InitAckMessage {
             .unwrap_or(0);
         
         let chunks_per_segment = (segment_size as usize / chunk_size as usize) as u32;
-        let total_segments = (total_file_size + segment_size as u64 - 1) / segment_size as u64;
+        let total_segments = total_file_size.div_ceil(segment_size as u64);
         
         Self {
             server_public_key: [0u8; 32],
@@ -342,48 +1165,179 @@ impl InitAckMessage {
             protocol_version: crate::PROTOCOL_VERSION,
             client_timestamp_us,
             server_timestamp_us,
+            sparse_segments: false,
+            delta_nacks: false,
         }
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::InitAck, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
-
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+    /// 크기 필드의 내부 일관성 검증
+    ///
+    /// 세그먼트 수와 세그먼트당 청크 수를 파일 크기/청크 크기/세그먼트 크기로 다시 계산해
+    /// 서버가 보낸 값과 비교. 세그먼트 수를 부풀린 InitAck를 믿으면 오지 않을 세그먼트를 계속 기다림
+    pub fn validate(&self) -> crate::Result<()> {
+        let invalid = |reason: String| Err(crate::Error::InvalidInitAck(reason));
+        if self.chunk_size == 0 || self.segment_size == 0 {
+            return invalid(format!(
+                "청크 크기 {} / 세그먼트 크기 {}",
+                self.chunk_size, self.segment_size
+            ));
+        }
+        let total_segments = self.total_file_size.div_ceil(self.segment_size as u64);
+        if self.total_segments != total_segments {
+            return invalid(format!(
+                "세그먼트 수 {} != {} ({} bytes / {} bytes)",
+                self.total_segments, total_segments, self.total_file_size, self.segment_size
+            ));
+        }
+        let chunks_per_segment = self.segment_size / self.chunk_size as u32;
+        if self.chunks_per_segment != chunks_per_segment {
+            return invalid(format!(
+                "세그먼트당 청크 수 {} != {}",
+                self.chunks_per_segment, chunks_per_segment
+            ));
+        }
+        Ok(())
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() < 10 {
-            return None;
+    /// 클라이언트가 Init에 실은 수신 한도 안인지 확인 (0이면 제한 없음)
+    pub fn validate_limits(&self, max_chunk_size: u16, max_segment_size: u32) -> crate::Result<()> {
+        if max_chunk_size > 0 && self.chunk_size > max_chunk_size {
+            return Err(crate::Error::InvalidInitAck(format!(
+                "청크 크기 {} > 수신 한도 {}",
+                self.chunk_size, max_chunk_size
+            )));
         }
-        
-        let header: MessageHeader = bincode::deserialize(bytes).ok()?;
-        if header.msg_type != MessageType::InitAck {
+        if max_segment_size > 0 && self.segment_size > max_segment_size {
+            return Err(crate::Error::InvalidInitAck(format!(
+                "세그먼트 크기 {} > 수신 한도 {}",
+                self.segment_size, max_segment_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// v4 페이로드 길이 (형식 버전 바이트 포함)
+    const PAYLOAD_LEN_V4: usize = 1 + 32 + 32 + 1 + 1 + 2 + 4 + 4 + 8 + 8 + 4 + 1 + 8 + 8 + 1 + 1;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + Self::PAYLOAD_LEN_V4);
+        MessageHeader::new(MessageType::InitAck, Self::PAYLOAD_LEN_V4 as u32).encode(&mut w);
+        w.put_u8(HANDSHAKE_FORMAT_VERSION);
+        w.put_slice(&self.server_public_key);
+        w.put_slice(&self.session_key);
+        w.put_bool(self.encryption_enabled);
+        w.put_u8(self.nic_count);
+        w.put_u16(self.chunk_size);
+        w.put_u32(self.segment_size);
+        w.put_f32(self.redundancy_ratio);
+        w.put_u64(self.total_file_size);
+        w.put_u64(self.total_segments);
+        w.put_u32(self.chunks_per_segment);
+        w.put_u8(self.protocol_version);
+        w.put_u64(self.client_timestamp_us);
+        w.put_u64(self.server_timestamp_us);
+        w.put_bool(self.sparse_segments);
+        w.put_bool(self.delta_nacks);
+        w.into_inner()
+    }
+
+    /// 형식 버전이 더 높은 피어가 덧붙인 필드는 무시
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::InitAck {
             return None;
         }
-        
-        let header_bytes = bincode::serialize(&header).ok()?;
-        let header_size = header_bytes.len();
-        
-        if bytes.len() < header_size {
+        let version = r.get_u8()?;
+        if version == 0 {
             return None;
         }
-        
-        bincode::deserialize(&bytes[header_size..]).ok()
+
+        let mut ack = Self {
+            server_public_key: r.get_array()?,
+            session_key: r.get_array()?,
+            encryption_enabled: r.get_bool()?,
+            nic_count: r.get_u8()?,
+            chunk_size: r.get_u16()?,
+            segment_size: r.get_u32()?,
+            redundancy_ratio: r.get_f32()?,
+            total_file_size: r.get_u64()?,
+            total_segments: r.get_u64()?,
+            chunks_per_segment: r.get_u32()?,
+            protocol_version: r.get_u8()?,
+            client_timestamp_us: r.get_u64()?,
+            server_timestamp_us: r.get_u64()?,
+            sparse_segments: false,
+            delta_nacks: false,
+        };
+        if version >= 3 {
+            ack.sparse_segments = r.get_bool()?;
+        }
+        if version >= 4 {
+            ack.delta_nacks = r.get_bool()?;
+        }
+        Some(ack)
     }
 }
 
-/// Heartbeat 메시지
+/// 전송 제안 메시지 (서버 → 클라이언트)
+///
+/// 수신자 주소를 미리 아는 서버가 먼저 보냄. 수신자는 보낸 주소로 일반 Init을 보내
+/// 수락하고, 이후 핸드셰이크(쿠키, InitAck)와 전송은 클라이언트가 시작한 경우와 같음
+///
+/// ```text
+/// header(13) | total_file_size(8, 0이면 미정)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfferMessage {
+    /// 보낼 데이터 크기 (수신자가 수락 전에 한도 확인)
+    pub total_file_size: u64,
+}
+
+impl OfferMessage {
+    pub fn new(total_file_size: u64) -> Self {
+        Self { total_file_size }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 8);
+        MessageHeader::new(MessageType::Offer, 8).encode(&mut w);
+        w.put_u64(self.total_file_size);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::Offer {
+            return None;
+        }
+        Some(Self {
+            total_file_size: r.get_u64()?,
+        })
+    }
+}
+
+/// Heartbeat 메시지 (연결 ID와 태그는 세그먼트 완료 메시지처럼 페이로드 뒤에 덧붙임)
+///
+/// 메타데이터 키로 태그를 붙인 Heartbeat만 새 주소로의 연결 이전을 시작함 (태그 없으면 0)
+///
+/// ```text
+/// header(13) | sequence(8) | timestamp_us(8) | connection_id(8) | tag(16)
+/// ```
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HeartbeatMessage {
     pub sequence: u64,
     pub timestamp_us: u64,
+    #[serde(skip)]
+    pub connection_id: u64,
+    /// 메타데이터 인증 태그
+    #[serde(skip)]
+    pub tag: [u8; 16],
 }
 
+#[cfg(feature = "std")]
 impl HeartbeatMessage {
     pub fn new(sequence: u64) -> Self {
         let timestamp_us = std::time::SystemTime::now()
@@ -394,22 +1348,506 @@ impl HeartbeatMessage {
         Self {
             sequence,
             timestamp_us,
+            connection_id: 0,
+            tag: [0u8; 16],
+        }
+    }
+
+    /// 인증 태그를 뺀 인코딩 크기 (헤더 포함)
+    const SIGNED_LEN: usize = MessageHeader::ENCODED_LEN + 8 + 8 + 8;
+
+    /// 연결 ID 지정
+    pub fn with_connection_id(mut self, connection_id: u64) -> Self {
+        self.connection_id = connection_id;
+        self
+    }
+
+    /// 인증 대상 바이트 (헤더부터 연결 ID까지)
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        bytes.truncate(Self::SIGNED_LEN);
+        bytes
+    }
+
+    /// 메타데이터 키로 태그를 채움
+    pub fn authenticated(mut self, auth: &crate::crypto::MetadataAuth) -> Self {
+        self.tag = auth.tag(&self.signed_bytes());
+        self
+    }
+
+    /// 태그가 메타데이터 키로 만든 것인지 확인
+    pub fn verify(&self, auth: &crate::crypto::MetadataAuth) -> bool {
+        auth.verify(&self.signed_bytes(), &self.tag)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = Self::SIGNED_LEN - MessageHeader::ENCODED_LEN + 16;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::Heartbeat, payload_len as u32).encode(&mut w);
+        w.put_u64(self.sequence);
+        w.put_u64(self.timestamp_us);
+        w.put_u64(self.connection_id);
+        w.put_slice(&self.tag);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::Heartbeat {
+            return None;
+        }
+
+        Some(Self {
+            sequence: r.get_u64()?,
+            timestamp_us: r.get_u64()?,
+            connection_id: r.get_u64().unwrap_or(0),
+            tag: r.get_array().unwrap_or([0u8; 16]),
+        })
+    }
+}
+
+/// 주소 이전 도달성 확인 (서버 → 클라이언트)
+///
+/// 확인된 클라이언트 주소가 아닌 곳에서 현재 연결 ID를 담은 메시지가 오면 서버가 그 주소로
+/// 무작위 토큰을 보냄. 같은 토큰이 그 주소에서 돌아와야 연결을 옮기므로, 연결 ID를 엿본
+/// 공격자가 출발지만 위조해서는 전송을 가로채거나 다른 주소로 돌릴 수 없음.
+///
+/// PMTU 탐침으로도 씀: 0 바이트를 덧붙여 원하는 데이터그램 크기로 보내고, 응답이 오면 그 크기가
+/// 경로를 통과한 것 (수신자는 덧붙인 바이트를 무시)
+///
+/// ```text
+/// header(13) | connection_id(8) | token(8) | padding
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathChallengeMessage {
+    pub connection_id: u64,
+    pub token: u64,
+}
+
+impl PathChallengeMessage {
+    pub fn new(connection_id: u64, token: u64) -> Self {
+        Self {
+            connection_id,
+            token,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_padded_bytes(0)
+    }
+
+    /// `datagram_len`까지 0으로 채워 직렬화 (PMTU 탐침, 이미 그보다 길면 패딩 없음)
+    pub fn to_padded_bytes(&self, datagram_len: usize) -> Vec<u8> {
+        let payload_len = datagram_len.saturating_sub(MessageHeader::ENCODED_LEN).max(16);
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::PathChallenge, payload_len as u32).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.put_u64(self.token);
+        let mut bytes = w.into_inner();
+        bytes.resize(MessageHeader::ENCODED_LEN + payload_len, 0);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::PathChallenge {
+            return None;
+        }
+        Some(Self::new(r.get_u64()?, r.get_u64()?))
+    }
+}
+
+/// 주소 이전 확인 응답 (클라이언트 → 서버, 받은 토큰을 그대로 돌려줌)
+///
+/// 연결 이전은 메타데이터 키로 만든 태그가 맞는 응답으로만 끝남. 연결 ID와 토큰을 엿본
+/// 제3자는 태그를 만들 수 없음 (PMTU 탐침 응답은 태그를 보지 않음)
+///
+/// ```text
+/// header(13) | connection_id(8) | token(8) | tag(16)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathResponseMessage {
+    pub connection_id: u64,
+    pub token: u64,
+    /// 메타데이터 인증 태그 (태그 없는 이전 형식은 0)
+    pub tag: [u8; 16],
+}
+
+impl PathResponseMessage {
+    /// 인증 태그를 뺀 인코딩 크기 (헤더 포함)
+    const SIGNED_LEN: usize = MessageHeader::ENCODED_LEN + 8 + 8;
+
+    /// 확인 요청에 대한 응답
+    pub fn answer(challenge: &PathChallengeMessage) -> Self {
+        Self {
+            connection_id: challenge.connection_id,
+            token: challenge.token,
+            tag: [0u8; 16],
+        }
+    }
+
+    /// 인증 대상 바이트 (헤더부터 토큰까지)
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        bytes.truncate(Self::SIGNED_LEN);
+        bytes
+    }
+
+    /// 메타데이터 키로 태그를 채움
+    #[cfg(feature = "std")]
+    pub fn authenticated(mut self, auth: &crate::crypto::MetadataAuth) -> Self {
+        self.tag = auth.tag(&self.signed_bytes());
+        self
+    }
+
+    /// 태그가 메타데이터 키로 만든 것인지 확인
+    #[cfg(feature = "std")]
+    pub fn verify(&self, auth: &crate::crypto::MetadataAuth) -> bool {
+        auth.verify(&self.signed_bytes(), &self.tag)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = Self::SIGNED_LEN - MessageHeader::ENCODED_LEN + 16;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::PathResponse, payload_len as u32).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.put_u64(self.token);
+        w.put_slice(&self.tag);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::PathResponse {
+            return None;
+        }
+        Some(Self {
+            connection_id: r.get_u64()?,
+            token: r.get_u64()?,
+            tag: r.get_array().unwrap_or([0u8; 16]),
+        })
+    }
+}
+
+/// 세그먼트 재전송 포기 통보 (서버 → 클라이언트)
+///
+/// 송신자가 `Config::max_chunk_retransmits`를 넘긴 청크(`chunk_ids`)를 더 보내지 않고
+/// 세그먼트 상태를 버렸음을 알림. 수신자는 NACK을 멈추고 세그먼트를 실패로 처리.
+/// 위조된 통보로 세그먼트를 실패시키지 못하도록 연결 ID가 맞는 통보만 받음
+///
+/// ```text
+/// header(13) | connection_id(8) | segment_id(8) | file_id(4) | count(2) | chunk_id(4) × count
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GiveUpMessage {
+    pub connection_id: u64,
+
+    pub segment_id: SegmentId,
+
+    pub file_id: FileId,
+
+    /// 재전송 한도를 넘긴 청크 ID
+    pub chunk_ids: Vec<ChunkId>,
+}
+
+impl GiveUpMessage {
+    pub fn new(
+        connection_id: u64,
+        segment_id: SegmentId,
+        file_id: FileId,
+        chunk_ids: Vec<ChunkId>,
+    ) -> Self {
+        Self {
+            connection_id,
+            segment_id,
+            file_id,
+            chunk_ids,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = 8 + 8 + 4 + 2 + 4 * self.chunk_ids.len();
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::GiveUp, payload_len as u32).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.put_u64(self.segment_id);
+        w.put_u32(self.file_id);
+        w.put_u16(self.chunk_ids.len() as u16);
+        for &chunk_id in &self.chunk_ids {
+            w.put_u32(chunk_id);
+        }
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::GiveUp {
+            return None;
+        }
+
+        let connection_id = r.get_u64()?;
+        let segment_id = r.get_u64()?;
+        let file_id = r.get_u32()?;
+        let count = r.get_u16()? as usize;
+        if count > r.remaining() / 4 {
+            return None;
+        }
+        let chunk_ids = (0..count).map(|_| r.get_u32()).collect::<Option<_>>()?;
+        Some(Self {
+            connection_id,
+            segment_id,
+            file_id,
+            chunk_ids,
+        })
+    }
+}
+
+/// 일시 정지/재개 통보 (서버 → 클라이언트)
+///
+/// `paused`가 참이면 `Pause`, 거짓이면 `Resume`. 위조된 Pause로 NACK을 막지 못하도록
+/// 수신자는 서버 주소에서 온, 연결 ID가 맞는 통보만 받음
+///
+/// ```text
+/// header(13) | connection_id(8)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PauseMessage {
+    pub paused: bool,
+
+    pub connection_id: u64,
+}
+
+impl PauseMessage {
+    pub fn pause(connection_id: u64) -> Self {
+        Self {
+            paused: true,
+            connection_id,
+        }
+    }
+
+    pub fn resume(connection_id: u64) -> Self {
+        Self {
+            paused: false,
+            connection_id,
+        }
+    }
+
+    pub fn msg_type(&self) -> MessageType {
+        if self.paused {
+            MessageType::Pause
+        } else {
+            MessageType::Resume
         }
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::Heartbeat, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 8);
+        MessageHeader::new(self.msg_type(), 8).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.into_inner()
+    }
 
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        let paused = match header.msg_type {
+            MessageType::Pause => true,
+            MessageType::Resume => false,
+            _ => return None,
+        };
+        if header.magic != MAGIC_NUMBER {
+            return None;
+        }
+        Some(Self {
+            paused,
+            connection_id: r.get_u64()?,
+        })
+    }
+}
+
+/// 네트워크 전환 통보 (클라이언트 → 서버)
+///
+/// Wi-Fi에서 셀룰러로 옮기는 것처럼 경로가 바뀌었을 때 전송 (`Receiver::notify_network_change`
+/// 또는 단방향 지연 계단 감지). 송신자는 이전 경로에서 쌓은 혼잡/중복률 추정치를 버리고
+/// 슬로 스타트부터 다시 측정
+///
+/// ```text
+/// header(13) | connection_id(8) | rtt_us(8, 수신자가 추정한 새 경로 RTT, 0이면 모름)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkChangeMessage {
+    pub connection_id: u64,
+
+    pub rtt_us: u64,
+}
+
+impl NetworkChangeMessage {
+    pub fn new(connection_id: u64) -> Self {
+        Self {
+            connection_id,
+            rtt_us: 0,
+        }
+    }
+
+    /// 새 경로 RTT 추정치 지정 (마이크로초)
+    pub fn with_rtt_us(mut self, rtt_us: u64) -> Self {
+        self.rtt_us = rtt_us;
+        self
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 16);
+        MessageHeader::new(MessageType::NetworkChange, 16).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.put_u64(self.rtt_us);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::NetworkChange {
+            return None;
+        }
+        Some(Self {
+            connection_id: r.get_u64()?,
+            rtt_us: r.get_u64()?,
+        })
+    }
+}
+
+/// 균일 세그먼트 표시 (서버 → 클라이언트)
+///
+/// 모든 바이트가 같은 세그먼트(0으로 채운 구멍, 같은 값 반복)를 청크 대신 보냄. 수신자는
+/// `segment_size` 바이트를 `fill`로 채워 청크로 받은 세그먼트처럼 완료하고 SegmentComplete로 응답.
+/// 핸드셰이크에서 양측이 지원할 때만 사용 (`InitMessage::sparse_supported`,
+/// `InitAckMessage::sparse_segments`)
+///
+/// 암호화 시에도 데이터가 아닌 메타데이터라 암호화하지 않지만, 위조한 표시로 세그먼트를
+/// 덮어쓸 수 없도록 `tag`(태그를 뺀 앞부분의 HMAC, `crypto::MetadataAuth`)로 인증.
+/// 인증 대상에 `connection_id`가 들어 있어 다른 연결의 표시를 재사용할 수 없음.
+/// 암호화를 끄면 `tag`는 0
+///
+/// ```text
+/// header(13) | connection_id(8) | segment_id(8) | file_id(4) | segment_size(4) | fill(1)
+///   | boundary(1) | tag(16)
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparseSegmentMessage {
+    /// 연결 ID (Init 세션 ID)
+    pub connection_id: u64,
+
+    pub segment_id: SegmentId,
+
+    pub file_id: FileId,
+
+    /// 펼칠 세그먼트 크기 (바이트)
+    pub segment_size: u32,
+
+    /// 세그먼트를 채울 바이트
+    pub fill: u8,
+
+    /// 세그먼트 끝이 메시지 경계 (청크 헤더의 `boundary`와 같음)
+    pub boundary: bool,
+
+    /// 메타데이터 인증 태그
+    pub tag: [u8; 16],
+}
+
+impl SparseSegmentMessage {
+    /// 인증 태그를 뺀 인코딩 크기 (헤더 포함)
+    const SIGNED_LEN: usize = MessageHeader::ENCODED_LEN + 8 + 8 + 4 + 4 + 1 + 1;
+
+    pub fn new(
+        connection_id: u64,
+        segment_id: SegmentId,
+        file_id: FileId,
+        segment_size: u32,
+        fill: u8,
+    ) -> Self {
+        Self {
+            connection_id,
+            segment_id,
+            file_id,
+            segment_size,
+            fill,
+            boundary: false,
+            tag: [0u8; 16],
+        }
+    }
+
+    /// 메시지 경계 지정
+    pub fn with_boundary(mut self, boundary: bool) -> Self {
+        self.boundary = boundary;
+        self
+    }
+
+    /// 인증 대상 바이트 (헤더부터 `boundary`까지)
+    pub fn signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        bytes.truncate(Self::SIGNED_LEN);
+        bytes
+    }
+
+    /// 메타데이터 키로 태그를 채움
+    #[cfg(feature = "std")]
+    pub fn authenticated(mut self, auth: &crate::crypto::MetadataAuth) -> Self {
+        self.tag = auth.tag(&self.signed_bytes());
+        self
+    }
+
+    /// 태그가 메타데이터 키로 만든 것인지 확인
+    #[cfg(feature = "std")]
+    pub fn verify(&self, auth: &crate::crypto::MetadataAuth) -> bool {
+        auth.verify(&self.signed_bytes(), &self.tag)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let payload_len = Self::SIGNED_LEN - MessageHeader::ENCODED_LEN + 16;
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + payload_len);
+        MessageHeader::new(MessageType::SparseSegment, payload_len as u32).encode(&mut w);
+        w.put_u64(self.connection_id);
+        w.put_u64(self.segment_id);
+        w.put_u32(self.file_id);
+        w.put_u32(self.segment_size);
+        w.put_u8(self.fill);
+        w.put_bool(self.boundary);
+        w.put_slice(&self.tag);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::SparseSegment {
+            return None;
+        }
+        Some(Self {
+            connection_id: r.get_u64()?,
+            segment_id: r.get_u64()?,
+            file_id: r.get_u32()?,
+            segment_size: r.get_u32()?,
+            fill: r.get_u8()?,
+            boundary: r.get_bool()?,
+            tag: r.get_array()?,
+        })
     }
 }
 
 /// 흐름 제어 메시지 (클라이언트 → 서버)
+///
+/// 필드마다 단위가 하나: 속도는 모두 bytes/s, 개수는 세그먼트 수. 모르는 값은 0
+///
+/// ```text
+/// header(13) | buffer_available(4) | last_completed_segment(8) | segments_in_progress(4)
+/// | loss_rate(f32, 4) | processing_rate_bytes_per_sec(8) | completed_segment_count(8)
+/// | estimated_bandwidth_bytes_per_sec(8) | suggested_rate_bytes_per_sec(8)
+/// ```
+#[cfg(feature = "std")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowControlMessage {
     /// 수신 버퍼 여유 공간 (세그먼트 단위)
@@ -420,32 +1858,37 @@ pub struct FlowControlMessage {
     pub segments_in_progress: u32,
     /// 최근 패킷 손실률 (0.0 ~ 1.0)
     pub loss_rate: f32,
-    /// 현재 처리 속도 (세그먼트/초)
-    pub processing_rate: f32,
-    /// 권장 전송 속도 (세그먼트/초, 0이면 서버 판단)
-    pub suggested_rate: f32,
+    /// 수신측이 최근 조립을 마친 속도 (bytes/s)
+    pub processing_rate_bytes_per_sec: u64,
+    /// 지금까지 완료한 세그먼트 수 (ID가 아니라 개수, 순서 없이 완료돼도 정확)
+    pub completed_segment_count: u64,
+    /// 수신측이 추정한 경로 대역폭 (bytes/s, 대역폭 프로브 등)
+    pub estimated_bandwidth_bytes_per_sec: u64,
+    /// 권장 전송 속도 (bytes/s, 처리 속도 기준, 0이면 서버 판단)
+    pub suggested_rate_bytes_per_sec: u64,
 }
 
+#[cfg(feature = "std")]
 impl FlowControlMessage {
     pub fn new(
         buffer_available: u32,
         last_completed_segment: u64,
         segments_in_progress: u32,
         loss_rate: f32,
-        processing_rate: f32,
+        processing_rate_bytes_per_sec: u64,
     ) -> Self {
         // 손실률과 처리 속도 기반으로 권장 속도 계산
-        let suggested_rate = if loss_rate > 0.1 {
+        let factor = if loss_rate > 0.1 {
             // 손실률 10% 이상이면 속도 절반
-            processing_rate * 0.5
+            0.5
         } else if loss_rate > 0.05 {
             // 손실률 5% 이상이면 속도 유지
-            processing_rate
+            1.0
         } else if buffer_available > 100 {
             // 버퍼 여유 있고 손실률 낮으면 속도 증가
-            processing_rate * 1.2
+            1.2
         } else {
-            processing_rate
+            1.0
         };
 
         Self {
@@ -453,44 +1896,72 @@ impl FlowControlMessage {
             last_completed_segment,
             segments_in_progress,
             loss_rate,
-            processing_rate,
-            suggested_rate,
+            processing_rate_bytes_per_sec,
+            completed_segment_count: 0,
+            estimated_bandwidth_bytes_per_sec: 0,
+            suggested_rate_bytes_per_sec: (processing_rate_bytes_per_sec as f64 * factor) as u64,
         }
     }
 
-    pub fn to_bytes(&self) -> Vec<u8> {
-        let payload = bincode::serialize(self).unwrap_or_default();
-        let header = MessageHeader::new(MessageType::FlowControl, payload.len() as u32);
-        let header_bytes = bincode::serialize(&header).unwrap_or_default();
+    /// 완료한 세그먼트 수
+    pub fn with_completed_segments(mut self, count: u64) -> Self {
+        self.completed_segment_count = count;
+        self
+    }
 
-        let mut buf = Vec::with_capacity(header_bytes.len() + payload.len());
-        buf.extend_from_slice(&header_bytes);
-        buf.extend_from_slice(&payload);
-        buf
+    /// 추정 경로 대역폭 (bytes/s)
+    pub fn with_estimated_bandwidth(mut self, bytes_per_sec: u64) -> Self {
+        self.estimated_bandwidth_bytes_per_sec = bytes_per_sec;
+        self
     }
 
-    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        if bytes.len() < 16 {
-            return None;
+    /// 버퍼 압박 비율 (처리 중 / (처리 중 + 여유))
+    pub fn buffer_pressure(&self) -> f32 {
+        let total = self.segments_in_progress as f32 + self.buffer_available as f32;
+        if total == 0.0 {
+            return 0.0;
         }
-        
-        let header: MessageHeader = bincode::deserialize(bytes).ok()?;
-        if header.msg_type != MessageType::FlowControl {
-            return None;
-        }
-        
-        let header_bytes = bincode::serialize(&header).ok()?;
-        let header_size = header_bytes.len();
-        
-        if bytes.len() < header_size {
+        self.segments_in_progress as f32 / total
+    }
+
+    const PAYLOAD_LEN: usize = 4 + 8 + 4 + 4 + 8 + 8 + 8 + 8;
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + Self::PAYLOAD_LEN);
+        MessageHeader::new(MessageType::FlowControl, Self::PAYLOAD_LEN as u32).encode(&mut w);
+        w.put_u32(self.buffer_available);
+        w.put_u64(self.last_completed_segment);
+        w.put_u32(self.segments_in_progress);
+        w.put_f32(self.loss_rate);
+        w.put_u64(self.processing_rate_bytes_per_sec);
+        w.put_u64(self.completed_segment_count);
+        w.put_u64(self.estimated_bandwidth_bytes_per_sec);
+        w.put_u64(self.suggested_rate_bytes_per_sec);
+        w.into_inner()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut r = Reader::new(bytes);
+        let header = MessageHeader::decode(&mut r)?;
+        if header.magic != MAGIC_NUMBER || header.msg_type != MessageType::FlowControl {
             return None;
         }
-        
-        bincode::deserialize(&bytes[header_size..]).ok()
+
+        Some(Self {
+            buffer_available: r.get_u32()?,
+            last_completed_segment: r.get_u64()?,
+            segments_in_progress: r.get_u32()?,
+            loss_rate: r.get_f32()?,
+            processing_rate_bytes_per_sec: r.get_u64()?,
+            completed_segment_count: r.get_u64()?,
+            estimated_bandwidth_bytes_per_sec: r.get_u64()?,
+            suggested_rate_bytes_per_sec: r.get_u64()?,
+        })
     }
 }
 
 /// 통합 메시지 enum
+#[cfg(feature = "std")]
 #[derive(Debug, Clone)]
 pub enum Message {
     Nack(NackMessage),
@@ -499,9 +1970,24 @@ pub enum Message {
     InitAck(InitAckMessage),
     Heartbeat(HeartbeatMessage),
     FlowControl(FlowControlMessage),
+    Cookie(CookieMessage),
+    ModeChange(ModeChangeMessage),
+    NackCancel(NackCancelMessage),
+    Offer(OfferMessage),
+    NackDelta(NackDeltaMessage),
+    Probe(ProbeMessage),
+    ProbeReport(ProbeReportMessage),
+    PathChallenge(PathChallengeMessage),
+    PathResponse(PathResponseMessage),
+    GiveUp(GiveUpMessage),
+    NetworkChange(NetworkChangeMessage),
+    SparseSegment(SparseSegmentMessage),
+    Pause(PauseMessage),
+    Resume(PauseMessage),
     Close,
 }
 
+#[cfg(feature = "std")]
 impl Message {
     /// 메시지 타입 반환
     pub fn msg_type(&self) -> MessageType {
@@ -512,7 +1998,552 @@ impl Message {
             Message::InitAck(_) => MessageType::InitAck,
             Message::Heartbeat(_) => MessageType::Heartbeat,
             Message::FlowControl(_) => MessageType::FlowControl,
+            Message::Cookie(_) => MessageType::Cookie,
+            Message::ModeChange(_) => MessageType::ModeChange,
+            Message::NackCancel(_) => MessageType::NackCancel,
+            Message::Offer(_) => MessageType::Offer,
+            Message::NackDelta(_) => MessageType::NackDelta,
+            Message::Probe(_) => MessageType::Probe,
+            Message::ProbeReport(_) => MessageType::ProbeReport,
+            Message::PathChallenge(_) => MessageType::PathChallenge,
+            Message::PathResponse(_) => MessageType::PathResponse,
+            Message::GiveUp(_) => MessageType::GiveUp,
+            Message::NetworkChange(_) => MessageType::NetworkChange,
+            Message::SparseSegment(_) => MessageType::SparseSegment,
+            Message::Pause(_) => MessageType::Pause,
+            Message::Resume(_) => MessageType::Resume,
             Message::Close => MessageType::Close,
         }
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nack_codec_matches_bincode() {
+        let nack = NackMessage::new(42, vec![0, 3, 17, 54], 0.75, 2).with_file_id(9);
+        let bytes = nack.to_bytes();
+
+        // 헤더와 페이로드 모두 기존 bincode 인코딩과 동일해야 함
+        let payload = bincode::serialize(&nack).unwrap();
+        let header = MessageHeader::new(MessageType::Nack, payload.len() as u32);
+        let mut expected = bincode::serialize(&header).unwrap();
+        expected.extend_from_slice(&payload);
+        assert_eq!(bytes, expected);
+
+        let restored = NackMessage::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.segment_id, 42);
+        assert_eq!(restored.missing_chunk_ids, vec![0, 3, 17, 54]);
+        assert_eq!(restored.receive_ratio, 0.75);
+        assert_eq!(restored.nic_id, 2);
+        assert_eq!(restored.file_id, 9);
+
+        // 연결 ID 없는 이전 형식은 0, 분할 정보도 없으면 단일 조각,
+        // file_id도 없으면 단일 스트림(0)으로 해석
+        let legacy = NackMessage::from_bytes(&bytes[..bytes.len() - 8]).unwrap();
+        assert_eq!(legacy.connection_id, 0);
+        let legacy = NackMessage::from_bytes(&bytes[..bytes.len() - 12]).unwrap();
+        assert_eq!((legacy.file_id, legacy.fragment_index, legacy.fragment_count), (9, 0, 1));
+        let legacy = NackMessage::from_bytes(&bytes[..bytes.len() - 16]).unwrap();
+        assert_eq!(legacy.file_id, 0);
+    }
+
+    #[test]
+    fn test_nack_rejects_oversized_count() {
+        let mut bytes = NackMessage::new(1, vec![1, 2], 0.0, 0).to_bytes();
+        // missing_chunk_ids 길이 필드를 과도한 값으로 변조
+        let count_offset = MessageHeader::ENCODED_LEN + 8;
+        bytes[count_offset..count_offset + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(NackMessage::from_bytes(&bytes).is_none());
+    }
+
+    #[test]
+    fn test_oversized_nack_fragmented_under_limit() {
+        let max_datagram_size = 1472;
+        let missing: Vec<ChunkId> = (0..5000).collect();
+        let nack = NackMessage::new(7, missing.clone(), 0.1, 0).with_file_id(3);
+        assert!(nack.encoded_len() > max_datagram_size);
+
+        let fragments = nack.fragments(max_datagram_size);
+        assert!(fragments.len() > 1);
+        let mut reassembled = Vec::new();
+        for (index, fragment) in fragments.iter().enumerate() {
+            let bytes = fragment.to_bytes();
+            assert!(bytes.len() <= max_datagram_size, "{}", bytes.len());
+
+            let restored = NackMessage::from_bytes(&bytes).unwrap();
+            assert_eq!((restored.segment_id, restored.file_id), (7, 3));
+            assert_eq!(restored.fragment_index as usize, index);
+            assert_eq!(restored.fragment_count as usize, fragments.len());
+            reassembled.extend(restored.missing_chunk_ids);
+        }
+        assert_eq!(reassembled, missing);
+
+        // 한도 안이면 그대로 한 조각
+        let small = NackMessage::new(7, vec![1, 2], 0.9, 0).fragments(max_datagram_size);
+        assert_eq!(small.len(), 1);
+        assert_eq!((small[0].fragment_index, small[0].fragment_count), (0, 1));
+
+        // 나눌 수 없는 메시지는 한도를 넘으면 보내기 전에 거부
+        let ack = InitAckMessage::new(0, 1200, 65536, 0.15).to_bytes();
+        assert!(bounded_datagram(MessageType::InitAck, ack.clone(), max_datagram_size).is_ok());
+        assert!(matches!(
+            bounded_datagram(MessageType::InitAck, ack, 64),
+            Err(crate::Error::MessageTooLarge { max: 64, .. })
+        ));
+    }
+
+    /// 공백을 무시하고 16진 문자열을 바이트로
+    pub(crate) fn hex(s: &str) -> Vec<u8> {
+        let digits: Vec<u8> = s.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        digits
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(core::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .collect()
+    }
+
+    /// 형식 버전을 올린 피어가 뒤에 필드를 덧붙여도 기존 필드는 그대로 읽혀야 함
+    fn as_future_version(golden: &[u8]) -> Vec<u8> {
+        let mut bytes = golden.to_vec();
+        bytes[MessageHeader::ENCODED_LEN] = HANDSHAKE_FORMAT_VERSION + 1;
+        bytes.extend_from_slice(&[0xEE; 7]);
+        bytes
+    }
+
+    #[test]
+    fn test_init_v4_golden_bytes() {
+        let golden = hex(
+            "50504653 01 03000000 5e000000
+             04
+             1111111111111111111111111111111111111111111111111111111111111111
+             01 02 b004 00000100 00002000 01
+             0807060504030201
+             8877665544332211 abababababababababababababababab
+             11100f0e0d0c0b0a
+             5802 00400000
+             01 01",
+        );
+        let init = InitMessage {
+            client_public_key: [0x11; 32],
+            encryption_enabled: true,
+            nic_count: 2,
+            chunk_size: 1200,
+            segment_size: 65536,
+            buffer_size: 2 * 1024 * 1024,
+            protocol_version: 1,
+            timestamp_us: 0x0102_0304_0506_0708,
+            cookie: InitCookie {
+                issued_at_us: 0x1122_3344_5566_7788,
+                tag: [0xAB; 16],
+            },
+            session_id: 0x0A0B_0C0D_0E0F_1011,
+            max_supported_chunk_size: 600,
+            max_supported_segment_size: 16384,
+            sparse_supported: true,
+            delta_nacks: true,
+        };
+        assert_eq!(init.to_bytes(), golden);
+
+        let decoded = InitMessage::from_bytes(&golden).unwrap();
+        assert_eq!(decoded.to_bytes(), golden);
+        assert_eq!(decoded.cookie.issued_at_us, 0x1122_3344_5566_7788);
+        assert_eq!(decoded.max_supported_segment_size, 16384);
+
+        // v3 Init (델타 NACK 필드 없음) → 사용 안 함
+        let mut v3 = golden[..golden.len() - 1].to_vec();
+        v3[MessageHeader::ENCODED_LEN] = 3;
+        let old = InitMessage::from_bytes(&v3).unwrap();
+        assert!(old.sparse_supported);
+        assert!(!old.delta_nacks);
+
+        // v2 Init (균일 세그먼트 필드 없음) → 미지원
+        let mut v2 = golden[..golden.len() - 2].to_vec();
+        v2[MessageHeader::ENCODED_LEN] = 2;
+        let old = InitMessage::from_bytes(&v2).unwrap();
+        assert_eq!(old.max_supported_segment_size, 16384);
+        assert!(!old.sparse_supported);
+
+        // v1 Init (한도 필드 없음) → 제한 없음
+        let mut v1 = golden[..golden.len() - 8].to_vec();
+        v1[MessageHeader::ENCODED_LEN] = 1;
+        let old = InitMessage::from_bytes(&v1).unwrap();
+        assert_eq!(old.session_id, 0x0A0B_0C0D_0E0F_1011);
+        assert_eq!((old.max_supported_chunk_size, old.max_supported_segment_size), (0, 0));
+
+        let newer = InitMessage::from_bytes(&as_future_version(&golden)).unwrap();
+        assert_eq!(newer.to_bytes(), golden);
+
+        let mut unversioned = golden.clone();
+        unversioned[MessageHeader::ENCODED_LEN] = 0;
+        assert!(InitMessage::from_bytes(&unversioned).is_none());
+        assert!(InitMessage::from_bytes(&golden[..golden.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn test_init_ack_golden_bytes() {
+        let golden = hex(
+            "50504653 01 04000000 74000000
+             04
+             2222222222222222222222222222222222222222222222222222222222222222
+             3333333333333333333333333333333333333333333333333333333333333333
+             01 01 b004 00000100 0000803e
+             40420f0000000000 1000000000000000 36000000 01
+             0807060504030201 1817161514131211
+             01 01",
+        );
+        let ack = InitAckMessage {
+            server_public_key: [0x22; 32],
+            session_key: [0x33; 32],
+            encryption_enabled: true,
+            nic_count: 1,
+            chunk_size: 1200,
+            segment_size: 65536,
+            redundancy_ratio: 0.25,
+            total_file_size: 1_000_000,
+            total_segments: 16,
+            chunks_per_segment: 54,
+            protocol_version: 1,
+            client_timestamp_us: 0x0102_0304_0506_0708,
+            server_timestamp_us: 0x1112_1314_1516_1718,
+            sparse_segments: true,
+            delta_nacks: true,
+        };
+        assert_eq!(ack.to_bytes(), golden);
+
+        let decoded = InitAckMessage::from_bytes(&golden).unwrap();
+        assert_eq!(decoded.to_bytes(), golden);
+        assert_eq!(decoded.redundancy_ratio, 0.25);
+
+        let newer = InitAckMessage::from_bytes(&as_future_version(&golden)).unwrap();
+        assert_eq!(newer.to_bytes(), golden);
+
+        // v3 InitAck (델타 NACK 필드 없음) → 델타를 해석하지 못함
+        let mut v3 = golden[..golden.len() - 1].to_vec();
+        v3[MessageHeader::ENCODED_LEN] = 3;
+        let old = InitAckMessage::from_bytes(&v3).unwrap();
+        assert!(old.sparse_segments);
+        assert!(!old.delta_nacks);
+
+        // v2 InitAck (균일 세그먼트 필드 없음) → 표시를 보내지 않음
+        let mut v2 = golden[..golden.len() - 2].to_vec();
+        v2[MessageHeader::ENCODED_LEN] = 2;
+        let old = InitAckMessage::from_bytes(&v2).unwrap();
+        assert_eq!(old.server_timestamp_us, 0x1112_1314_1516_1718);
+        assert!(!old.sparse_segments);
+
+        assert!(ack.validate_limits(0, 0).is_ok());
+        assert!(ack.validate_limits(1200, 65536).is_ok());
+        assert!(matches!(
+            ack.validate_limits(600, 0),
+            Err(crate::Error::InvalidInitAck(_))
+        ));
+        assert!(ack.validate_limits(0, 16384).is_err());
+
+        // 다른 메시지 타입/잘린 페이로드는 거부
+        assert!(InitMessage::from_bytes(&golden).is_none());
+        assert!(InitAckMessage::from_bytes(&golden[..golden.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn test_header_codec_matches_bincode() {
+        for msg_type in [
+            MessageType::Chunk,
+            MessageType::Init,
+            MessageType::FlowControl,
+        ] {
+            let header = MessageHeader::new(msg_type, 123);
+            let encoded = bincode::serialize(&header).unwrap();
+            assert_eq!(encoded.len(), MessageHeader::ENCODED_LEN);
+
+            let decoded = MessageHeader::from_bytes(&encoded).unwrap();
+            assert_eq!(decoded.msg_type, msg_type);
+            assert_eq!(decoded.payload_len, 123);
+        }
+    }
+
+    #[test]
+    fn test_flow_control_fields_round_trip() {
+        // 처리 속도 4 MB/s, 완료 37개 (최대 ID 40과 다름), 프로브 추정 12.5 MB/s
+        let fc = FlowControlMessage::new(200, 40, 3, 0.02, 4_000_000)
+            .with_completed_segments(37)
+            .with_estimated_bandwidth(12_500_000);
+        let decoded = FlowControlMessage::from_bytes(&fc.to_bytes()).unwrap();
+
+        assert_eq!(decoded.buffer_available, 200);
+        assert_eq!(decoded.last_completed_segment, 40);
+        assert_eq!(decoded.segments_in_progress, 3);
+        assert_eq!(decoded.loss_rate, 0.02);
+        assert_eq!(decoded.processing_rate_bytes_per_sec, 4_000_000);
+        assert_eq!(decoded.completed_segment_count, 37);
+        assert_eq!(decoded.estimated_bandwidth_bytes_per_sec, 12_500_000);
+        // 버퍼 여유 + 낮은 손실: 처리 속도의 1.2배, 같은 bytes/s 단위
+        assert_eq!(decoded.suggested_rate_bytes_per_sec, 4_800_000);
+
+        // 손실이 크면 처리 속도의 절반, 추정/완료 개수는 알려주지 않으면 0
+        let lossy = FlowControlMessage::new(200, 40, 3, 0.2, 4_000_000);
+        assert_eq!(lossy.suggested_rate_bytes_per_sec, 2_000_000);
+        assert_eq!(lossy.completed_segment_count, 0);
+        assert_eq!(lossy.estimated_bandwidth_bytes_per_sec, 0);
+        assert!((fc.buffer_pressure() - 3.0 / 203.0).abs() < 1e-6);
+    }
+
+    /// 이전 버전이 bincode로 만들던 메시지도 같은 바이트를 유지해야 함
+    #[test]
+    fn test_control_messages_match_bincode() {
+        fn bincode_frame<T: Serialize>(
+            msg_type: MessageType,
+            payload: &T,
+            trailer: &[u8],
+        ) -> Vec<u8> {
+            let mut payload = bincode::serialize(payload).unwrap();
+            payload.extend_from_slice(trailer);
+            let header = MessageHeader::new(msg_type, payload.len() as u32);
+            let mut bytes = bincode::serialize(&header).unwrap();
+            bytes.extend_from_slice(&payload);
+            bytes
+        }
+
+        let complete = SegmentCompleteMessage {
+            segment_id: 11,
+            file_id: 2,
+            total_chunks_received: 60,
+            duplicates_received: 4,
+            elapsed_ms: 900,
+            connection_id: 0xC0FFEE,
+        };
+        assert_eq!(
+            complete.to_bytes(),
+            bincode_frame(MessageType::SegmentComplete, &complete, &0xC0FFEEu64.to_le_bytes())
+        );
+
+        let heartbeat = HeartbeatMessage::new(5).with_connection_id(77);
+        assert_eq!(
+            heartbeat.to_bytes(),
+            bincode_frame(
+                MessageType::Heartbeat,
+                &heartbeat,
+                &[&77u64.to_le_bytes()[..], &[0; 16]].concat()
+            )
+        );
+
+        let cookie = CookieMessage::new(InitCookie {
+            issued_at_us: 123,
+            tag: [7; 16],
+        });
+        assert_eq!(cookie.to_bytes(), bincode_frame(MessageType::Cookie, &cookie, &[]));
+
+        for mode in [RedundancyMode::Duplicate, RedundancyMode::Fec] {
+            let change = ModeChangeMessage::new(mode);
+            assert_eq!(change.to_bytes(), bincode_frame(MessageType::ModeChange, &change, &[]));
+        }
+
+        let fc = FlowControlMessage::new(10, 20, 3, 0.1, 1_000_000).with_completed_segments(19);
+        assert_eq!(fc.to_bytes(), bincode_frame(MessageType::FlowControl, &fc, &[]));
+    }
+
+    #[test]
+    fn test_message_header_golden_bytes() {
+        // 매직 "SFPP" | 버전 | 타입 인덱스(u32) | 페이로드 길이(u32), 모두 리틀 엔디언
+        for (msg_type, golden) in [
+            (MessageType::Close, "50504653 01 05000000 00000000"),
+            (MessageType::Pause, "50504653 01 13000000 00000000"),
+        ] {
+            let header = MessageHeader::new(msg_type, 0);
+            assert_eq!(header.to_bytes(), hex(golden));
+            assert_eq!(MessageHeader::from_bytes(&hex(golden)).unwrap().msg_type, msg_type);
+        }
+
+        let header = MessageHeader::from_bytes(&hex("50504653 01 09000000 34120000")).unwrap();
+        assert_eq!(header.magic, MAGIC_NUMBER);
+        assert_eq!(header.msg_type, MessageType::FlowControl);
+        assert_eq!(header.payload_len, 0x1234);
+    }
+
+    #[test]
+    fn test_control_messages_golden_bytes() {
+        let nack = hex(
+            "50504653 01 01000000 2d000000
+             2a00000000000000 0200000000000000 03000000 11000000
+             0000403f 01 09000000 0000 0100 5500000000000000",
+        );
+        let msg = NackMessage::new(42, vec![3, 17], 0.75, 1)
+            .with_file_id(9)
+            .with_connection_id(0x55);
+        assert_eq!(msg.to_bytes(), nack);
+        assert_eq!(NackMessage::from_bytes(&nack).unwrap().to_bytes(), nack);
+
+        let complete = hex(
+            "50504653 01 02000000 24000000
+             0807060504030201 07000000 36000000 02000000 dc05000000000000 1817161514131211",
+        );
+        let msg = SegmentCompleteMessage {
+            segment_id: 0x0102_0304_0506_0708,
+            file_id: 7,
+            total_chunks_received: 54,
+            duplicates_received: 2,
+            elapsed_ms: 1500,
+            connection_id: 0x1112_1314_1516_1718,
+        };
+        assert_eq!(msg.to_bytes(), complete);
+        let decoded = SegmentCompleteMessage::from_bytes(&complete).unwrap();
+        assert_eq!(decoded.segment_id, 0x0102_0304_0506_0708);
+        assert_eq!(decoded.connection_id, 0x1112_1314_1516_1718);
+        // 연결 ID 없는 이전 형식
+        let legacy = SegmentCompleteMessage::from_bytes(&complete[..complete.len() - 8]).unwrap();
+        assert_eq!((legacy.elapsed_ms, legacy.connection_id), (1500, 0));
+
+        let heartbeat = hex(
+            "50504653 01 06000000 28000000
+             0900000000000000 0807060504030201 a8a7a6a5a4a3a2a1
+             5a5a5a5a5a5a5a5a 5a5a5a5a5a5a5a5a",
+        );
+        let msg = HeartbeatMessage {
+            sequence: 9,
+            timestamp_us: 0x0102_0304_0506_0708,
+            connection_id: 0xA1A2_A3A4_A5A6_A7A8,
+            tag: [0x5A; 16],
+        };
+        assert_eq!(msg.to_bytes(), heartbeat);
+        let decoded = HeartbeatMessage::from_bytes(&heartbeat).unwrap();
+        assert_eq!((decoded.connection_id, decoded.tag), (0xA1A2_A3A4_A5A6_A7A8, [0x5A; 16]));
+        let untagged = HeartbeatMessage::from_bytes(&heartbeat[..heartbeat.len() - 16]).unwrap();
+        assert_eq!(untagged.tag, [0; 16]);
+        let legacy = HeartbeatMessage::from_bytes(&heartbeat[..heartbeat.len() - 24]).unwrap();
+        assert_eq!((legacy.sequence, legacy.connection_id), (9, 0));
+
+        let flow = hex(
+            "50504653 01 09000000 34000000
+             c8000000 2800000000000000 03000000 0000803e
+             00093d0000000000 2500000000000000 20bcbe0000000000 80841e0000000000",
+        );
+        let msg = FlowControlMessage::new(200, 40, 3, 0.25, 4_000_000)
+            .with_completed_segments(37)
+            .with_estimated_bandwidth(12_500_000);
+        assert_eq!(msg.to_bytes(), flow);
+        let decoded = FlowControlMessage::from_bytes(&flow).unwrap();
+        assert_eq!(decoded.loss_rate, 0.25);
+        assert_eq!(decoded.suggested_rate_bytes_per_sec, 2_000_000);
+        assert!(FlowControlMessage::from_bytes(&flow[..flow.len() - 1]).is_none());
+
+        let cookie = hex(
+            "50504653 01 0a000000 18000000 8877665544332211 abababababababababababababababab",
+        );
+        let msg = CookieMessage::new(InitCookie {
+            issued_at_us: 0x1122_3344_5566_7788,
+            tag: [0xAB; 16],
+        });
+        assert_eq!(msg.to_bytes(), cookie);
+        assert_eq!(CookieMessage::from_bytes(&cookie).unwrap().cookie, msg.cookie);
+
+        let mode = hex("50504653 01 0b000000 04000000 01000000");
+        assert_eq!(ModeChangeMessage::new(RedundancyMode::Fec).to_bytes(), mode);
+        assert_eq!(ModeChangeMessage::from_bytes(&mode).unwrap().mode, RedundancyMode::Fec);
+        let mut unknown = mode.clone();
+        unknown[MessageHeader::ENCODED_LEN] = 2;
+        assert!(ModeChangeMessage::from_bytes(&unknown).is_none());
+
+        let cancel = hex(
+            "50504653 01 0c000000 16000000 0500000000000000 02000000 0200 03000000 09000000",
+        );
+        let msg = NackCancelMessage::new(5, 2, vec![3, 9]);
+        assert_eq!(msg.to_bytes(), cancel);
+        assert_eq!(NackCancelMessage::from_bytes(&cancel).unwrap(), msg);
+
+        let offer = hex("50504653 01 0d000000 08000000 0000000001000000");
+        assert_eq!(OfferMessage::new(1 << 32).to_bytes(), offer);
+        assert_eq!(OfferMessage::from_bytes(&offer).unwrap().total_file_size, 1 << 32);
+
+        let delta = hex(
+            "50504653 01 0e000000 2a000000
+             0600000000000000 01000000 0a00 0800 00 0000003f 02 0100 0100
+             04000000 07000000 7700000000000000",
+        );
+        let mut msg = NackDeltaMessage::delta(6, 10, 8, vec![4], vec![7], 0.5)
+            .with_file_id(1)
+            .with_connection_id(0x77);
+        msg.nic_id = 2;
+        assert_eq!(msg.to_bytes(), delta);
+        assert_eq!(NackDeltaMessage::from_bytes(&delta).unwrap(), msg);
+
+        let probe = hex("50504653 01 0f000000 04000000 0100 0400");
+        assert_eq!(ProbeMessage::new(1, 4).to_bytes(0), probe);
+        assert_eq!(ProbeMessage::from_bytes(&probe).unwrap(), ProbeMessage::new(1, 4));
+
+        let report = hex("50504653 01 10000000 0a000000 0300 40420f0000000000");
+        assert_eq!(ProbeReportMessage::new(3, 1_000_000).to_bytes(), report);
+        assert_eq!(
+            ProbeReportMessage::from_bytes(&report).unwrap(),
+            ProbeReportMessage::new(3, 1_000_000)
+        );
+
+        let challenge = hex("50504653 01 11000000 10000000 0b0a000000000000 adde000000000000");
+        let msg = PathChallengeMessage::new(0x0A0B, 0xDEAD);
+        assert_eq!(msg.to_bytes(), challenge);
+        assert_eq!(PathChallengeMessage::from_bytes(&challenge).unwrap(), msg);
+        let probe = msg.to_padded_bytes(40);
+        assert_eq!(probe.len(), 40);
+        assert_eq!(&probe[9..13], &27u32.to_le_bytes());
+        assert_eq!(PathChallengeMessage::from_bytes(&probe).unwrap(), msg);
+        assert_eq!(msg.to_padded_bytes(0), challenge);
+
+        let response = hex(
+            "50504653 01 12000000 20000000 0b0a000000000000 adde000000000000
+             0000000000000000 0000000000000000",
+        );
+        assert_eq!(PathResponseMessage::answer(&msg).to_bytes(), response);
+        assert_eq!(PathResponseMessage::from_bytes(&response).unwrap().token, 0xDEAD);
+        let legacy = PathResponseMessage::from_bytes(&response[..29]).unwrap();
+        assert_eq!(legacy, PathResponseMessage::answer(&msg));
+
+        let give_up = hex(
+            "50504653 01 15000000 1a000000 0b0a000000000000 0800000000000000 03000000 0100 0c000000",
+        );
+        let msg = GiveUpMessage::new(0x0A0B, 8, 3, vec![12]);
+        assert_eq!(msg.to_bytes(), give_up);
+        assert_eq!(GiveUpMessage::from_bytes(&give_up).unwrap(), msg);
+
+        let pause = hex("50504653 01 13000000 08000000 0b0a000000000000");
+        let msg = PauseMessage::pause(0x0A0B);
+        assert_eq!(msg.to_bytes(), pause);
+        assert_eq!(PauseMessage::from_bytes(&pause).unwrap(), msg);
+        let resume = hex("50504653 01 14000000 08000000 0b0a000000000000");
+        assert_eq!(PauseMessage::resume(0x0A0B).to_bytes(), resume);
+        assert!(!PauseMessage::from_bytes(&resume).unwrap().paused);
+
+        let change = hex("50504653 01 16000000 10000000 0b0a000000000000 50c3000000000000");
+        let msg = NetworkChangeMessage::new(0x0A0B).with_rtt_us(50_000);
+        assert_eq!(msg.to_bytes(), change);
+        assert_eq!(NetworkChangeMessage::from_bytes(&change).unwrap(), msg);
+
+        let sparse = hex(
+            "50504653 01 17000000 2a000000 0b0a000000000000 1000000000000000 03000000 00000100 ff 01
+             00000000000000000000000000000000",
+        );
+        let msg = SparseSegmentMessage::new(0x0A0B, 0x10, 3, 65536, 0xFF).with_boundary(true);
+        assert_eq!(msg.to_bytes(), sparse);
+        assert_eq!(SparseSegmentMessage::from_bytes(&sparse).unwrap(), msg);
+        assert_eq!(msg.signed_bytes(), sparse[..sparse.len() - 16]);
+    }
+
+    /// 빅 엔디언 호스트가 자기 바이트 순서로 쓴 값은 같은 필드로 읽히지 않아야 함
+    /// (와이어 값이 호스트 순서를 따르지 않고 항상 리틀 엔디언이라는 확인)
+    #[test]
+    fn test_big_endian_layout_not_accepted_as_native() {
+        let mut w = Writer::with_capacity(MessageHeader::ENCODED_LEN + 8);
+        w.put_slice(&MAGIC_NUMBER.to_be_bytes());
+        w.put_u8(PROTOCOL_VERSION);
+        w.put_slice(&MessageType::Offer.wire_index().to_be_bytes());
+        w.put_slice(&8u32.to_be_bytes());
+        w.put_slice(&0x0102_0304u64.to_be_bytes());
+        let big_endian = w.into_inner();
+        assert!(MessageHeader::from_bytes(&big_endian).is_none());
+        assert!(OfferMessage::from_bytes(&big_endian).is_none());
+
+        // 리틀 엔디언으로 쓴 같은 값은 어느 호스트에서든 같은 필드
+        let offer = OfferMessage::new(0x0102_0304).to_bytes();
+        assert_eq!(&offer[..4], b"PPFS");
+        assert_eq!(&offer[MessageHeader::ENCODED_LEN..], &[4, 3, 2, 1, 0, 0, 0, 0]);
+    }
+}
@@ -1,17 +1,29 @@
 //! 멀티패스 관리
 //!
 //! 여러 NIC를 통한 동시 전송 및 비율 조정
+//!
+//! NIC ID는 추가 순서대로 배정되고 제거된 NIC의 ID는 다시 쓰지 않음. 그래서 NIC를 빼고
+//! 더해도 남은 NIC의 ID가 바뀌지 않고, 비율/통계 조회는 항상 ID 오름차순
+//!
+//! `set_manual_ratio`로 고정한 NIC는 균등화/자동 조정에서 빠지고, 나머지 NIC가 남은 몫
+//! (1 − 고정 비율 합)을 나눠 가짐
+//!
+//! MTU가 다른 링크를 함께 쓰면 `set_path_mtu`로 경로별 최대 데이터그램 크기를 지정. 송신자는
+//! 청크마다 경로를 고른 뒤 그 경로에 맞는 크기로 잘라, 작은 경로에서는 IP 단편화가 없고 큰
+//! 경로는 `chunk_size`를 그대로 씀
 
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU16, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Instant;
 
 use parking_lot::RwLock;
 use tokio::net::UdpSocket;
 
+use crate::chunk::{Chunk, ChunkHeader};
 use crate::stats::NicStats;
-use crate::{Config, Result};
+use crate::transport::{self, DatagramSocket};
+use crate::{Config, Error, Result};
 
 /// NIC 정보
 #[derive(Debug, Clone)]
@@ -31,6 +43,12 @@ pub struct NicInfo {
     /// 활성 상태
     pub active: bool,
 
+    /// 운영자가 고정한 비율 (None이면 자동 조정)
+    pub manual_ratio: Option<f64>,
+
+    /// 경로 최대 데이터그램 크기 (경로 MTU - IP/UDP 헤더, None이면 `chunk_size` 그대로)
+    pub max_datagram_size: Option<usize>,
+
     /// 마지막 활동 시간
     pub last_activity: Instant,
 }
@@ -43,22 +61,48 @@ impl NicInfo {
             remote_addr,
             ratio: 1.0,
             active: true,
+            manual_ratio: None,
+            max_datagram_size: None,
             last_activity: Instant::now(),
         }
     }
 }
 
-/// 멀티패스 경로 관리자
-pub struct PathManager {
-    /// NIC 정보 목록
+/// 경로 하나의 현재 상태 (`PathManager::describe_paths`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathDescription {
+    pub nic_id: u8,
+    pub local_addr: SocketAddr,
+    pub remote_addr: SocketAddr,
+    pub active: bool,
+    /// 현재 전송 비율
+    pub ratio: f64,
+    /// 수신 처리율 (bytes/sec)
+    pub throughput: f64,
+    /// 전달이 확인된 고유 데이터 처리율 (bytes/sec, 재전송/중복 제외)
+    pub goodput: f64,
+    /// NACK 기반 손실률
+    pub loss_rate: f64,
+    /// 운영자가 비율을 고정했는지
+    pub pinned: bool,
+    /// 경로 최대 데이터그램 크기 (`set_path_mtu`)
+    pub max_datagram_size: Option<usize>,
+}
+
+/// 멀티패스 경로 관리자 (`S`: NIC별 소켓 타입, 기본 UDP)
+pub struct PathManager<S = UdpSocket> {
+    /// NIC 정보 목록 (ID 오름차순)
     nics: RwLock<Vec<NicInfo>>,
 
-    /// NIC별 소켓
-    sockets: RwLock<Vec<Arc<UdpSocket>>>,
+    /// NIC별 소켓 (ID 오름차순)
+    sockets: RwLock<Vec<(u8, Arc<S>)>>,
 
-    /// NIC별 통계
+    /// NIC별 통계 (ID 오름차순)
     stats: RwLock<Vec<NicStats>>,
 
+    /// 다음에 배정할 NIC ID (제거된 ID는 재사용하지 않음)
+    next_nic_id: AtomicU16,
+
     /// 설정
     config: Config,
 
@@ -72,48 +116,86 @@ pub struct PathManager {
 impl PathManager {
     /// 새 PathManager 생성
     pub fn new(config: Config) -> Self {
+        Self::for_transport(config)
+    }
+
+    /// NIC 추가 (UDP 소켓 바인딩)
+    pub async fn add_nic(&self, local_addr: SocketAddr, remote_addr: SocketAddr) -> Result<u8> {
+        let socket = UdpSocket::bind(local_addr).await?;
+        socket.connect(remote_addr).await?;
+        transport::apply_dscp(&socket, self.config.dscp)?;
+
+        // 버퍼 크기 설정 (socket2 사용 시 가능)
+        // tokio UdpSocket은 직접 버퍼 설정 불가, 생성 전 socket2로 설정 필요
+
+        self.add_nic_socket(Arc::new(socket), remote_addr)
+    }
+}
+
+impl<S: DatagramSocket> PathManager<S> {
+    /// 임의의 소켓 타입으로 PathManager 생성
+    pub fn for_transport(config: Config) -> Self {
         Self {
             nics: RwLock::new(Vec::new()),
             sockets: RwLock::new(Vec::new()),
             stats: RwLock::new(Vec::new()),
+            next_nic_id: AtomicU16::new(0),
             config,
             chunk_counter: AtomicU64::new(0),
             last_ratio_adjust: RwLock::new(Instant::now()),
         }
     }
 
-    /// NIC 추가
-    pub async fn add_nic(&self, local_addr: SocketAddr, remote_addr: SocketAddr) -> Result<u8> {
-        let socket = UdpSocket::bind(local_addr).await?;
-        socket.connect(remote_addr).await?;
-
-        // 버퍼 크기 설정 (socket2 사용 시 가능)
-        // tokio UdpSocket은 직접 버퍼 설정 불가, 생성 전 socket2로 설정 필요
+    /// 이미 준비된 소켓으로 NIC 추가
+    pub fn add_nic_socket(&self, socket: Arc<S>, remote_addr: SocketAddr) -> Result<u8> {
+        let local_addr = socket.local_addr()?;
 
+        // 세 목록이 같은 ID 집합을 유지하도록 함께 잠금 (잠금 순서: 통계 → NIC → 소켓)
         let id = {
+            let mut stats = self.stats.write();
             let mut nics = self.nics.write();
-            let id = nics.len() as u8;
+            let mut sockets = self.sockets.write();
+
+            let id = self.next_nic_id.load(Ordering::Relaxed);
+            let id = u8::try_from(id).map_err(|_| Error::NicIdsExhausted)?;
+            self.next_nic_id.store(id as u16 + 1, Ordering::Relaxed);
+
             nics.push(NicInfo::new(id, local_addr, remote_addr));
+            sockets.push((id, socket));
+            stats.push(NicStats::new(id, self.config.stats_window_size));
             id
         };
 
-        {
-            let mut sockets = self.sockets.write();
-            sockets.push(Arc::new(socket));
-        }
+        // 비율 재조정
+        self.equalize_ratios();
+
+        Ok(id)
+    }
 
+    /// NIC 제거. 다른 NIC의 ID는 그대로 유지. 없는 ID면 false
+    pub fn remove_nic(&self, nic_id: u8) -> bool {
         {
             let mut stats = self.stats.write();
-            stats.push(NicStats::new(id, self.config.stats_window_size));
+            let mut nics = self.nics.write();
+            let mut sockets = self.sockets.write();
+
+            let Some(index) = find(&nics, nic_id, |n| n.id) else {
+                return false;
+            };
+            nics.remove(index);
+            if let Some(index) = find(&sockets, nic_id, |(id, _)| *id) {
+                sockets.remove(index);
+            }
+            if let Some(index) = find(&stats, nic_id, |s| s.nic_id) {
+                stats.remove(index);
+            }
         }
 
-        // 비율 재조정
         self.equalize_ratios();
-
-        Ok(id)
+        true
     }
 
-    /// 비율 균등화
+    /// 비율 균등화 (고정 비율 NIC는 그대로, 나머지가 남은 몫을 똑같이 나눔)
     fn equalize_ratios(&self) {
         let mut nics = self.nics.write();
         let active_count = nics.iter().filter(|n| n.active).count();
@@ -121,16 +203,123 @@ impl PathManager {
             return;
         }
 
-        let equal_ratio = 1.0 / active_count as f64;
+        let free_count = nics
+            .iter()
+            .filter(|n| n.active && n.manual_ratio.is_none())
+            .count();
+        let equal_ratio = unpinned_share(&nics) / free_count.max(1) as f64;
         for nic in nics.iter_mut() {
-            if nic.active {
-                nic.ratio = equal_ratio;
-            } else {
-                nic.ratio = 0.0;
-            }
+            nic.ratio = match (nic.active, nic.manual_ratio) {
+                (false, _) => 0.0,
+                (true, Some(ratio)) => ratio,
+                (true, None) => equal_ratio,
+            };
+        }
+    }
+
+    /// NIC 비율을 고정 (자동 조정에서 제외, `release_manual_ratio`로 해제).
+    /// 다른 고정 비율과 합이 1을 넘지 않도록 잘라 적용하며 없는 ID면 false
+    pub fn set_manual_ratio(&self, nic_id: u8, ratio: f64) -> bool {
+        {
+            let mut nics = self.nics.write();
+            let Some(index) = find(&nics, nic_id, |n| n.id) else {
+                return false;
+            };
+            let others: f64 = nics
+                .iter()
+                .filter(|n| n.id != nic_id)
+                .filter_map(|n| n.manual_ratio)
+                .sum();
+            let ratio = ratio.clamp(0.0, (1.0 - others).max(0.0));
+            nics[index].manual_ratio = Some(ratio);
+        }
+        self.equalize_ratios();
+        true
+    }
+
+    /// 고정 비율 해제 (다시 자동 조정). 고정되어 있지 않았거나 없는 ID면 false
+    pub fn release_manual_ratio(&self, nic_id: u8) -> bool {
+        let released = {
+            let mut nics = self.nics.write();
+            find(&nics, nic_id, |n| n.id)
+                .is_some_and(|index| nics[index].manual_ratio.take().is_some())
+        };
+        if released {
+            self.equalize_ratios();
+        }
+        released
+    }
+
+    /// 경로 최대 데이터그램 크기 지정 (None이면 해제). 없는 ID면 false
+    pub fn set_path_mtu(&self, nic_id: u8, max_datagram_size: Option<usize>) -> bool {
+        let mut nics = self.nics.write();
+        let Some(index) = find(&nics, nic_id, |n| n.id) else {
+            return false;
+        };
+        nics[index].max_datagram_size = max_datagram_size;
+        true
+    }
+
+    /// 경로 최대 데이터그램 크기가 지정된 NIC가 있는지
+    pub fn has_path_mtus(&self) -> bool {
+        self.nics.read().iter().any(|n| n.max_datagram_size.is_some())
+    }
+
+    /// `nic_id` 경로로 보낼 청크의 데이터 크기 (`chunk_size` 이하, 최소 1)
+    pub fn path_chunk_size(&self, nic_id: u8, chunk_size: usize) -> usize {
+        let nics = self.nics.read();
+        let mtu = find(&nics, nic_id, |n| n.id).and_then(|index| nics[index].max_datagram_size);
+        self.fit_chunk(mtu, chunk_size)
+    }
+
+    /// 모든 활성 경로에 들어가는 청크 데이터 크기 (같은 크기 청크가 필요한 FEC/패딩용)
+    pub fn smallest_path_chunk_size(&self, chunk_size: usize) -> usize {
+        let mtu = self.smallest_path_mtu();
+        self.fit_chunk(mtu, chunk_size)
+    }
+
+    /// 활성 경로 중 가장 작은 최대 데이터그램 크기 (지정된 경로가 없으면 None)
+    pub fn smallest_path_mtu(&self) -> Option<usize> {
+        self.nics
+            .read()
+            .iter()
+            .filter(|n| n.active)
+            .filter_map(|n| n.max_datagram_size)
+            .min()
+    }
+
+    /// 최대 데이터그램 크기에 청크 프레이밍(프리픽스 + 헤더)을 뺀 데이터 크기
+    fn fit_chunk(&self, max_datagram_size: Option<usize>, chunk_size: usize) -> usize {
+        let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        match max_datagram_size {
+            Some(mtu) => chunk_size.min(mtu.saturating_sub(framing)).max(1),
+            None => chunk_size.max(1),
         }
     }
 
+    /// 경로별 현재 상태 (NIC ID 오름차순)
+    pub fn describe_paths(&self) -> Vec<PathDescription> {
+        let stats = self.stats.read();
+        let nics = self.nics.read();
+        nics.iter()
+            .map(|nic| {
+                let stat = find(&stats, nic.id, |s| s.nic_id).map(|index| &stats[index]);
+                PathDescription {
+                    nic_id: nic.id,
+                    local_addr: nic.local_addr,
+                    remote_addr: nic.remote_addr,
+                    active: nic.active,
+                    ratio: nic.ratio,
+                    throughput: stat.map_or(0.0, NicStats::throughput),
+                    goodput: stat.map_or(0.0, NicStats::goodput),
+                    loss_rate: stat.map_or(0.0, NicStats::loss_rate),
+                    pinned: nic.manual_ratio.is_some(),
+                    max_datagram_size: nic.max_datagram_size,
+                }
+            })
+            .collect()
+    }
+
     /// 다음 청크를 전송할 NIC 선택
     pub fn select_nic_for_chunk(&self) -> Option<u8> {
         let nics = self.nics.read();
@@ -159,34 +348,49 @@ impl PathManager {
     }
 
     /// 소켓 가져오기
-    pub fn get_socket(&self, nic_id: u8) -> Option<Arc<UdpSocket>> {
+    pub fn get_socket(&self, nic_id: u8) -> Option<Arc<S>> {
         let sockets = self.sockets.read();
-        sockets.get(nic_id as usize).cloned()
+        find(&sockets, nic_id, |(id, _)| *id).map(|index| sockets[index].1.clone())
     }
 
-    /// 모든 소켓 가져오기
-    pub fn get_all_sockets(&self) -> Vec<Arc<UdpSocket>> {
-        self.sockets.read().clone()
+    /// 모든 소켓 가져오기 (ID 오름차순)
+    pub fn get_all_sockets(&self) -> Vec<Arc<S>> {
+        self.sockets.read().iter().map(|(_, socket)| socket.clone()).collect()
     }
 
     /// 청크 수신 기록
     pub fn record_chunk_arrival(&self, nic_id: u8, size: usize) {
         let mut stats = self.stats.write();
-        if let Some(stat) = stats.get_mut(nic_id as usize) {
-            stat.record_arrival(size);
+        if let Some(index) = find(&stats, nic_id, |s| s.nic_id) {
+            stats[index].record_arrival(size);
         }
 
         let mut nics = self.nics.write();
-        if let Some(nic) = nics.get_mut(nic_id as usize) {
-            nic.last_activity = Instant::now();
+        if let Some(index) = find(&nics, nic_id, |n| n.id) {
+            nics[index].last_activity = Instant::now();
+        }
+    }
+
+    /// 고유 데이터 전달 기록 (송신자: 완료가 확인된 세그먼트의 원본 청크)
+    pub fn record_delivery(&self, nic_id: u8, size: usize) {
+        let mut stats = self.stats.write();
+        if let Some(index) = find(&stats, nic_id, |s| s.nic_id) {
+            stats[index].record_delivery(size);
         }
     }
 
     /// 손실 기록
     pub fn record_loss(&self, nic_id: u8, count: u64) {
         let mut stats = self.stats.write();
-        if let Some(stat) = stats.get_mut(nic_id as usize) {
-            stat.record_loss(count);
+        if let Some(index) = find(&stats, nic_id, |s| s.nic_id) {
+            stats[index].record_loss(count);
+        }
+    }
+
+    /// 모든 NIC의 처리율/RTT/손실률 추정치 재시작 (네트워크 전환 후)
+    pub fn restart_estimates(&self) {
+        for stat in self.stats.write().iter_mut() {
+            stat.restart_estimates();
         }
     }
 
@@ -207,36 +411,46 @@ impl PathManager {
         let stats = self.stats.read();
         let mut nics = self.nics.write();
 
-        // 각 NIC의 처리율 계산
-        let throughputs: Vec<f64> = stats.iter().map(|s| s.throughput()).collect();
-        let total_throughput: f64 = throughputs.iter().sum();
+        // 각 NIC의 유효 처리율: 재전송/중복은 선로를 쓰기만 하므로 전달된 고유 바이트로 비교.
+        // 전달 기록이 아직 없으면 (수신측, 첫 완료 전) 원시 처리율
+        let use_goodput = stats.iter().any(|s| s.goodput() > 0.0);
+        let rate = |s: &NicStats| match use_goodput {
+            true => s.goodput(),
+            false => s.throughput(),
+        };
+        let total_rate: f64 = stats.iter().map(rate).sum();
+
+        if total_rate > 0.0 {
+            let share = unpinned_share(&nics);
+            let adjustable = |n: &NicInfo| n.active && n.manual_ratio.is_none();
 
-        if total_throughput > 0.0 {
-            // 처리율 기반 비율 조정
-            for (i, nic) in nics.iter_mut().enumerate() {
-                if nic.active {
-                    let loss_rate = stats[i].loss_rate();
+            // 처리율 기반 비율 조정 (고정 비율 NIC 제외)
+            for nic in nics.iter_mut() {
+                let Some(index) = find(&stats, nic.id, |s| s.nic_id) else {
+                    continue;
+                };
+                if adjustable(nic) {
+                    let stat = &stats[index];
                     // 손실률이 높은 NIC는 비율 감소
-                    let adjusted_throughput = throughputs[i] * (1.0 - loss_rate);
-                    nic.ratio = adjusted_throughput / total_throughput;
+                    nic.ratio = rate(stat) * (1.0 - stat.loss_rate()) / total_rate;
                 }
             }
 
             // 최소 비율 보장 (0.1)
-            let active_count = nics.iter().filter(|n| n.active).count();
-            let min_ratio = 0.1 / active_count as f64;
+            let adjustable_count = nics.iter().filter(|n| adjustable(n)).count();
+            let min_ratio = 0.1 / adjustable_count.max(1) as f64;
             for nic in nics.iter_mut() {
-                if nic.active && nic.ratio < min_ratio {
+                if adjustable(nic) && nic.ratio < min_ratio {
                     nic.ratio = min_ratio;
                 }
             }
 
-            // 정규화
-            let total: f64 = nics.iter().filter(|n| n.active).map(|n| n.ratio).sum();
+            // 남은 몫으로 정규화
+            let total: f64 = nics.iter().filter(|n| adjustable(n)).map(|n| n.ratio).sum();
             if total > 0.0 {
                 for nic in nics.iter_mut() {
-                    if nic.active {
-                        nic.ratio /= total;
+                    if adjustable(nic) {
+                        nic.ratio = nic.ratio / total * share;
                     }
                 }
             }
@@ -245,7 +459,7 @@ impl PathManager {
         *self.last_ratio_adjust.write() = now;
     }
 
-    /// 현재 비율 반환
+    /// 현재 비율 반환 (NIC ID 오름차순)
     pub fn get_ratios(&self) -> Vec<(u8, f64)> {
         self.nics
             .read()
@@ -257,9 +471,9 @@ impl PathManager {
     /// NIC 비활성화
     pub fn deactivate_nic(&self, nic_id: u8) {
         let mut nics = self.nics.write();
-        if let Some(nic) = nics.get_mut(nic_id as usize) {
-            nic.active = false;
-            nic.ratio = 0.0;
+        if let Some(index) = find(&nics, nic_id, |n| n.id) {
+            nics[index].active = false;
+            nics[index].ratio = 0.0;
         }
         drop(nics);
         self.equalize_ratios();
@@ -268,8 +482,8 @@ impl PathManager {
     /// NIC 활성화
     pub fn activate_nic(&self, nic_id: u8) {
         let mut nics = self.nics.write();
-        if let Some(nic) = nics.get_mut(nic_id as usize) {
-            nic.active = true;
+        if let Some(index) = find(&nics, nic_id, |n| n.id) {
+            nics[index].active = true;
         }
         drop(nics);
         self.equalize_ratios();
@@ -285,20 +499,34 @@ impl PathManager {
         self.nics.read().len()
     }
 
-    /// NIC 통계 복사
+    /// 현재 NIC ID 목록 (오름차순)
+    pub fn nic_ids(&self) -> Vec<u8> {
+        self.nics.read().iter().map(|n| n.id).collect()
+    }
+
+    /// 지금까지 배정한 NIC ID의 상한 (모든 ID < 반환값).
+    /// NIC ID를 인덱스로 쓰는 표(`TransferStats::nic_stats`)의 크기로 사용
+    pub fn nic_id_bound(&self) -> usize {
+        self.next_nic_id.load(Ordering::Relaxed) as usize
+    }
+
+    /// NIC 통계 복사 (NIC ID 오름차순)
     pub fn get_stats(&self) -> Vec<NicStats> {
         self.stats.read().clone()
     }
 
-    /// 손실률 기반 중복률 계산
-    pub fn calculate_redundancy(&self) -> f64 {
-        let stats = self.stats.read();
-        let max_loss_rate = stats
+    /// NIC 중 최대 손실률
+    pub fn max_loss_rate(&self) -> f64 {
+        self.stats
+            .read()
             .iter()
             .map(|s| s.loss_rate())
-            .fold(0.0f64, |a, b| a.max(b));
+            .fold(0.0f64, |a, b| a.max(b))
+    }
 
-        self.config.calculate_redundancy(max_loss_rate)
+    /// 손실률 기반 중복률 계산
+    pub fn calculate_redundancy(&self) -> f64 {
+        self.config.calculate_redundancy(self.max_loss_rate())
     }
 
     /// 전체 처리율
@@ -307,6 +535,21 @@ impl PathManager {
     }
 }
 
+/// 고정 비율이 없는 활성 NIC가 나눠 가질 몫
+fn unpinned_share(nics: &[NicInfo]) -> f64 {
+    let pinned: f64 = nics
+        .iter()
+        .filter(|n| n.active)
+        .filter_map(|n| n.manual_ratio)
+        .sum();
+    (1.0 - pinned).max(0.0)
+}
+
+/// ID 오름차순 목록에서 NIC 위치 찾기
+fn find<T>(items: &[T], nic_id: u8, id_of: impl Fn(&T) -> u8) -> Option<usize> {
+    items.binary_search_by_key(&nic_id, id_of).ok()
+}
+
 /// 간단한 단일 경로 관리자 생성
 pub async fn create_single_path(
     local_addr: SocketAddr,
@@ -322,6 +565,7 @@ pub async fn create_single_path(
 mod tests {
     use super::*;
     use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_path_manager_single_nic() {
@@ -335,6 +579,132 @@ mod tests {
         assert_eq!(manager.nic_count(), 0);
     }
 
+    #[tokio::test]
+    async fn test_nic_ids_stable_across_remove() {
+        let manager = PathManager::new(Config::default());
+        let remote: SocketAddr = "127.0.0.1:9999".parse().unwrap();
+        let mut locals = Vec::new();
+        for _ in 0..3 {
+            let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+            locals.push(socket.local_addr().unwrap());
+            manager.add_nic_socket(socket, remote).unwrap();
+        }
+
+        // 가운데 NIC를 빼도 나머지 ID는 그대로, 새 NIC는 새 ID
+        assert!(manager.remove_nic(1));
+        assert!(!manager.remove_nic(1));
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let added = socket.local_addr().unwrap();
+        assert_eq!(manager.add_nic_socket(socket, remote).unwrap(), 3);
+        assert_eq!(manager.nic_ids(), vec![0, 2, 3]);
+        assert_eq!(manager.nic_id_bound(), 4);
+
+        let third = 1.0 / 3.0;
+        assert_eq!(manager.get_ratios(), vec![(0, third), (2, third), (3, third)]);
+        assert!(manager.get_socket(1).is_none());
+        assert_eq!(manager.get_socket(2).unwrap().local_addr().unwrap(), locals[2]);
+        assert_eq!(manager.get_socket(3).unwrap().local_addr().unwrap(), added);
+
+        // 통계도 ID로 기록/조회
+        manager.record_chunk_arrival(3, 1200);
+        manager.record_loss(2, 5);
+        let stats: Vec<(u8, u64, u64)> = manager
+            .get_stats()
+            .iter()
+            .map(|s| (s.nic_id, s.total_chunks, s.lost_chunks))
+            .collect();
+        assert_eq!(stats, vec![(0, 0, 0), (2, 0, 5), (3, 1, 0)]);
+
+        manager.deactivate_nic(2);
+        assert_eq!(manager.get_ratios(), vec![(0, 0.5), (2, 0.0), (3, 0.5)]);
+    }
+
+    #[tokio::test]
+    async fn test_pinned_ratio_survives_auto_adjust() {
+        let manager = PathManager::new(Config {
+            ratio_adjust_interval_ms: 0,
+            ..Config::default()
+        });
+        let remote: SocketAddr = "127.0.0.1:9999".parse().unwrap();
+        for _ in 0..3 {
+            let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+            manager.add_nic_socket(socket, remote).unwrap();
+        }
+
+        assert!(!manager.set_manual_ratio(9, 0.5));
+        assert!(manager.set_manual_ratio(0, 0.7));
+        let ratios = manager.get_ratios();
+        assert_eq!(ratios[0], (0, 0.7));
+        assert!((ratios[1].1 - 0.15).abs() < 1e-9 && (ratios[2].1 - 0.15).abs() < 1e-9);
+
+        // 다른 고정 비율과 합쳐 1을 넘으면 남은 몫으로 자름
+        assert!(manager.set_manual_ratio(2, 0.5));
+        assert!((manager.get_ratios()[2].1 - 0.3).abs() < 1e-9);
+        assert!(manager.release_manual_ratio(2));
+        assert!(!manager.release_manual_ratio(2));
+
+        // NIC 1로만 트래픽이 와도 고정된 NIC 0은 그대로, 나머지 몫만 재분배
+        for _ in 0..10 {
+            manager.record_chunk_arrival(1, 1200);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        manager.adjust_ratios();
+        let ratios = manager.get_ratios();
+        assert_eq!(ratios[0], (0, 0.7));
+        assert!(ratios[1].1 > ratios[2].1, "{:?}", ratios);
+        assert!((ratios.iter().map(|&(_, r)| r).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        let paths = manager.describe_paths();
+        assert_eq!(paths.len(), 3);
+        assert!(paths[0].pinned && !paths[1].pinned);
+        assert_eq!(paths[0].ratio, 0.7);
+        assert!(paths[1].throughput > 0.0 && paths[0].throughput == 0.0);
+        assert_eq!(paths[1].remote_addr, remote);
+
+        // 비활성화 동안은 0, 다시 켜면 고정 비율 복원. 해제하면 균등 분배
+        manager.deactivate_nic(0);
+        assert_eq!(manager.get_ratios()[0], (0, 0.0));
+        manager.activate_nic(0);
+        assert_eq!(manager.get_ratios()[0], (0, 0.7));
+        assert!(manager.release_manual_ratio(0));
+        let third = 1.0 / 3.0;
+        assert_eq!(manager.get_ratios(), vec![(0, third), (1, third), (2, third)]);
+    }
+
+    #[tokio::test]
+    async fn test_lossy_path_weighted_by_goodput() {
+        let manager = PathManager::new(Config {
+            ratio_adjust_interval_ms: 0,
+            ..Config::default()
+        });
+        let remote: SocketAddr = "127.0.0.1:9999".parse().unwrap();
+        for _ in 0..2 {
+            let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+            manager.add_nic_socket(socket, remote).unwrap();
+        }
+
+        // NIC 0: 선로엔 두 배를 쏟지만 대부분 재전송이라 전달된 고유 데이터는 1/4
+        // NIC 1: 보낸 만큼 그대로 전달
+        for _ in 0..20 {
+            manager.record_chunk_arrival(0, 1200);
+            manager.record_chunk_arrival(0, 1200);
+            manager.record_chunk_arrival(1, 1200);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        manager.record_loss(0, 20);
+        // 송신자는 세그먼트 완료 보고 때 전달을 한꺼번에 기록 (같은 시각)
+        manager.record_delivery(0, 5 * 1200);
+        manager.record_delivery(1, 20 * 1200);
+        manager.adjust_ratios();
+
+        let paths = manager.describe_paths();
+        assert!(paths[0].throughput > paths[1].throughput, "{:?}", paths);
+        assert!(paths[0].goodput < paths[1].goodput, "{:?}", paths);
+        let ratios = manager.get_ratios();
+        assert!(ratios[0].1 < ratios[1].1 / 2.0, "{:?}", ratios);
+        assert!((ratios.iter().map(|&(_, r)| r).sum::<f64>() - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_nic_selection() {
         let config = Config::default();
@@ -0,0 +1,166 @@
+//! 네트워크 전환 감지 (`Config::network_change_delay_step_ms`)
+//!
+//! Wi-Fi에서 셀룰러로 옮기는 것처럼 경로가 바뀌면 RTT/대역폭/손실이 계단식으로 달라지지만,
+//! 평활화된 추정치는 천천히 따라가 한동안 이전 경로의 값으로 전송함. 수신자는 청크 헤더의
+//! 송신 시각으로 단방향 지연을 재고, 기준선(최솟값)을 임계값 이상 벗어난 표본이
+//! `STEP_CONFIRM_SAMPLES`개 이상, `STEP_CONFIRM` 이상 이어지면 경로가 바뀐 것으로 봄
+//!
+//! - 양측 시계 차이는 모든 표본에 똑같이 더해지므로 기준선과의 차이만 봄
+//! - 큐 지연으로 잠깐 늘어난 구간은 기준선 근처 표본이 하나라도 오면 취소
+//! - 벽시계가 건너뛰어도 같은 계단으로 보여 재시작되지만, 추정치를 다시 재는 것뿐이라 무해
+
+use std::time::{Duration, Instant};
+
+use crate::Config;
+
+/// 계단으로 확정하기까지 벗어난 표본이 이어져야 하는 시간
+pub const STEP_CONFIRM: Duration = Duration::from_millis(200);
+
+/// 계단으로 확정하기까지 필요한 연속 표본 수
+pub const STEP_CONFIRM_SAMPLES: u32 = 8;
+
+/// 기준선을 벗어난 채 이어지는 표본 구간
+#[derive(Debug, Clone, Copy)]
+struct PendingStep {
+    since: Instant,
+    samples: u32,
+    above: bool,
+    /// 구간의 최소 지연 (확정되면 새 기준선)
+    min_us: i64,
+}
+
+/// 단방향 지연 계단 감지기
+#[derive(Debug)]
+pub struct DelayStepDetector {
+    threshold_us: i64,
+    baseline_us: Option<i64>,
+    pending: Option<PendingStep>,
+}
+
+impl DelayStepDetector {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold_us: (threshold.as_micros() as i64).max(1),
+            baseline_us: None,
+            pending: None,
+        }
+    }
+
+    /// 설정의 임계값으로 생성 (0이면 자동 감지 안 함)
+    pub fn from_config(config: &Config) -> Option<Self> {
+        (config.network_change_delay_step_ms > 0)
+            .then(|| Self::new(Duration::from_millis(config.network_change_delay_step_ms)))
+    }
+
+    /// 현재 기준선 (마이크로초, 시계 차이 포함)
+    pub fn baseline_us(&self) -> Option<i64> {
+        self.baseline_us
+    }
+
+    /// 지연 표본 반영 (`delay_us`: 수신 시각 − 청크 송신 시각)
+    ///
+    /// 계단을 확정하면 기준선 변화량(마이크로초, 늘었으면 양수)을 반환하고 새 기준선에서 다시 잼
+    pub fn on_sample(&mut self, delay_us: i64, now: Instant) -> Option<i64> {
+        let Some(baseline) = self.baseline_us else {
+            self.baseline_us = Some(delay_us);
+            return None;
+        };
+
+        let diff = delay_us - baseline;
+        if diff.abs() < self.threshold_us {
+            self.pending = None;
+            self.baseline_us = Some(baseline.min(delay_us));
+            return None;
+        }
+
+        let above = diff > 0;
+        let step = match &mut self.pending {
+            Some(step) if step.above == above => {
+                step.samples += 1;
+                step.min_us = step.min_us.min(delay_us);
+                step
+            }
+            pending => pending.insert(PendingStep {
+                since: now,
+                samples: 1,
+                above,
+                min_us: delay_us,
+            }),
+        };
+        if step.samples < STEP_CONFIRM_SAMPLES
+            || now.saturating_duration_since(step.since) < STEP_CONFIRM
+        {
+            return None;
+        }
+
+        let new_baseline = step.min_us;
+        self.pending = None;
+        self.baseline_us = Some(new_baseline);
+        Some(new_baseline - baseline)
+    }
+
+    /// 기준선을 버리고 다음 표본부터 다시 잼 (수동 통보 후)
+    pub fn reset(&mut self) {
+        self.baseline_us = None;
+        self.pending = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `from_ms`부터 1ms 간격으로 같은 지연 표본을 넣고 마지막 결과 반환
+    fn feed(
+        detector: &mut DelayStepDetector,
+        t0: Instant,
+        from_ms: u64,
+        count: u64,
+        delay_us: i64,
+    ) -> Option<i64> {
+        let mut result = None;
+        for i in 0..count {
+            let now = t0 + Duration::from_millis(from_ms + i);
+            result = result.or(detector.on_sample(delay_us, now));
+        }
+        result
+    }
+
+    #[test]
+    fn test_sustained_step_detected_spike_ignored() {
+        let t0 = Instant::now();
+        let mut detector = DelayStepDetector::new(Duration::from_millis(30));
+        // 시계 차이 때문에 음수인 지연도 기준선 차이만 봄
+        let offset = -5_000_000;
+        assert_eq!(feed(&mut detector, t0, 0, 100, offset + 10_000), None);
+        assert_eq!(detector.baseline_us(), Some(offset + 10_000));
+
+        // 100ms짜리 큐 지연 급증은 기준선 근처 표본이 오면 취소
+        assert_eq!(feed(&mut detector, t0, 100, 100, offset + 80_000), None);
+        assert_eq!(feed(&mut detector, t0, 200, 1, offset + 12_000), None);
+        assert_eq!(detector.baseline_us(), Some(offset + 10_000));
+
+        // 셀룰러로 전환: 편도 지연 10ms → 60ms가 계속되면 +50ms 계단
+        let step = feed(&mut detector, t0, 300, 300, offset + 60_000);
+        assert_eq!(step, Some(50_000));
+        assert_eq!(detector.baseline_us(), Some(offset + 60_000));
+        assert_eq!(feed(&mut detector, t0, 600, 100, offset + 61_000), None);
+
+        // 다시 빠른 경로로 돌아오면 줄어든 계단
+        let step = feed(&mut detector, t0, 700, 300, offset + 15_000);
+        assert_eq!(step, Some(-45_000));
+
+        detector.reset();
+        assert_eq!(detector.baseline_us(), None);
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        assert!(DelayStepDetector::from_config(&Config::default()).is_none());
+        let config = Config {
+            network_change_delay_step_ms: 40,
+            ..Config::default()
+        };
+        assert!(DelayStepDetector::from_config(&config).is_some());
+    }
+}
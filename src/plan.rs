@@ -0,0 +1,233 @@
+//! 전송 계획 (`Config::plan_transfer`)
+//!
+//! 보내기 전에 설정만으로 세그먼트/청크 수, 중복 청크 수, 선로 바이트, 송수신측 메모리를 계산.
+//! 청크·세그먼트 크기와 중복률을 고르거나, 아주 작은 세그먼트 수백만 개 같은 설정을 실제로
+//! 돌리기 전에 걸러내는 데 씀
+//!
+//! - 손실이 없을 때의 중복률(`Config::redundancy(0.0, ..)`)과 중복 전송 모드 기준. 전송 중
+//!   손실이 생기면 중복률, 재전송, FEC 패리티만큼 늘어남
+//! - 세그먼트 크기는 `segment_size` 고정. 적응형 크기면 하한(`min_segment_size`)에서 시작하므로
+//!   세그먼트 수는 상한 추정
+//! - 중복 청크는 무작위로 고르므로 그 선로 바이트는 세그먼트의 평균 청크 크기로 추정
+
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::time::Duration;
+
+use crate::chunk::{Chunk, ChunkHeader};
+use crate::Config;
+
+/// 이보다 세그먼트가 많으면 `PlanWarning::TooManySegments`
+pub const MAX_PLANNED_SEGMENTS: u64 = 1_000_000;
+
+/// 청크 프레이밍(프리픽스 + 헤더 + 암호화 오버헤드)이 데이터그램에서 이 비율을 넘으면 경고
+pub const MAX_FRAMING_SHARE: f64 = 0.25;
+
+/// 계획에서 발견한 설정 문제
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanWarning {
+    /// 세그먼트 수가 `MAX_PLANNED_SEGMENTS`를 넘음 (세그먼트 크기가 너무 작음)
+    TooManySegments(u64),
+    /// 청크 데이터그램에서 프레이밍이 차지하는 비율 (청크 크기가 너무 작음)
+    FramingOverhead(f64),
+    /// 수신자의 `max_accept_file_size`를 넘어 전송이 거부됨
+    ExceedsAcceptLimit,
+}
+
+/// 설정으로 계산한 전송 계획
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferPlan {
+    /// 보낼 데이터 크기 (bytes)
+    pub file_size: u64,
+    /// 세그먼트에 담는 데이터 크기 (bytes, 암호화 시 봉인 오버헤드를 뺀 평문 크기)
+    pub segment_size: usize,
+    /// 청크 데이터 크기 (`effective_chunk_size`)
+    pub chunk_size: usize,
+    /// 세그먼트 수
+    pub segments: u64,
+    /// 원본 청크 수
+    pub chunks: u64,
+    /// 적용한 중복률
+    pub redundancy_ratio: f64,
+    /// 중복 청크 수
+    pub redundant_chunks: u64,
+    /// 원본 + 중복 청크 데이터그램의 UDP 페이로드 합 (bytes)
+    pub wire_bytes: u64,
+    /// 송신측이 재전송용으로 붙잡는 메모리 추정 (동시 세그먼트 + 세그먼트 캐시)
+    pub sender_memory: u64,
+    /// 수신측 메모리 추정 (조립 중 + 전달 대기 + 버퍼 풀 세그먼트)
+    pub receiver_memory: u64,
+    /// 설정 문제 (없으면 빈 목록)
+    pub warnings: Vec<PlanWarning>,
+}
+
+impl TransferPlan {
+    /// `config`로 `file_size` 바이트를 보낼 때의 계획
+    pub fn new(config: &Config, file_size: u64) -> Self {
+        let adaptive =
+            config.min_segment_size > 0 && config.min_segment_size < config.max_segment_size;
+        let segment_size = config
+            .plaintext_segment_size(if adaptive {
+                config.min_segment_size
+            } else {
+                config.segment_size
+            })
+            .max(1);
+        let chunk_size = config.effective_chunk_size().max(1);
+        let redundancy_ratio = config.redundancy(0.0, config.slow_start_initial_rtt_ms * 1000, 0.0);
+
+        let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        let datagram = |data_len: usize| match config.padded_datagram_len() {
+            0 => (framing + data_len) as u64,
+            padded => padded as u64,
+        };
+
+        // 가득 찬 세그먼트와 마지막 세그먼트 두 종류만 계산
+        let full_segments = file_size / segment_size as u64;
+        let tail = (file_size % segment_size as u64) as usize;
+        let mut plan = Self {
+            file_size,
+            segment_size,
+            chunk_size,
+            segments: 0,
+            chunks: 0,
+            redundancy_ratio,
+            redundant_chunks: 0,
+            wire_bytes: 0,
+            sender_memory: 0,
+            receiver_memory: 0,
+            warnings: Vec::new(),
+        };
+        let mut per_segment = (0, 0);
+        for (count, len) in [(full_segments, segment_size), ((tail > 0) as u64, tail)] {
+            if count == 0 {
+                continue;
+            }
+            // 암호화 시 세그먼트마다 봉인 오버헤드가 한 번 붙음
+            let len = config.sealed_segment_len(len);
+            let chunks = len.div_ceil(chunk_size);
+            let redundant = ceil(chunks as f64 * redundancy_ratio);
+            let full_chunks = len / chunk_size;
+            let originals = full_chunks as u64 * datagram(chunk_size)
+                + !len.is_multiple_of(chunk_size) as u64 * datagram(len % chunk_size);
+            let copies = redundant as u64 * datagram(len / chunks);
+
+            plan.segments += count;
+            plan.chunks += count * chunks as u64;
+            plan.redundant_chunks += count * redundant as u64;
+            plan.wire_bytes += count * (originals + copies);
+            per_segment = per_segment.max((chunks, redundant));
+        }
+
+        // 송신측: 완료 보고 전까지 청크 헤더와 세그먼트 데이터를 보관
+        let (chunks, redundant) = per_segment;
+        let segment_bytes = segment_size.min(file_size as usize) as u64;
+        let in_flight = plan.segments.min(config.max_concurrent_segments.max(1) as u64);
+        let tracked = segment_bytes + ((chunks + redundant) * size_of::<Chunk>()) as u64;
+        plan.sender_memory = in_flight * tracked + config.segment_cache_bytes as u64;
+
+        // 수신측: 조립 중 세그먼트 + 전달을 기다리는 완료 세그먼트 + 풀에 남는 버퍼
+        let waiting = match config.delivery_window_segments {
+            0 => plan.segments,
+            window => plan.segments.min(window as u64),
+        };
+        let pooled = config.segment_pool_capacity as u64;
+        plan.receiver_memory = (in_flight + waiting + pooled) * segment_size as u64;
+
+        if plan.segments > MAX_PLANNED_SEGMENTS {
+            plan.warnings.push(PlanWarning::TooManySegments(plan.segments));
+        }
+        let framing_share = framing as f64 / (framing + chunk_size) as f64;
+        if framing_share > MAX_FRAMING_SHARE {
+            plan.warnings.push(PlanWarning::FramingOverhead(framing_share));
+        }
+        if config.max_accept_file_size > 0 && file_size > config.max_accept_file_size {
+            plan.warnings.push(PlanWarning::ExceedsAcceptLimit);
+        }
+        plan
+    }
+
+    /// 보낼 데이터그램 수 (원본 + 중복)
+    pub fn datagrams(&self) -> u64 {
+        self.chunks + self.redundant_chunks
+    }
+
+    /// 데이터 대비 추가 선로 바이트 비율 (프레이밍 + 중복)
+    pub fn overhead(&self) -> f64 {
+        if self.file_size == 0 {
+            return 0.0;
+        }
+        self.wire_bytes as f64 / self.file_size as f64 - 1.0
+    }
+
+    /// `rate` (bytes/sec) 처리율에서 선로 바이트를 모두 보내는 시간 (0 이하면 None)
+    ///
+    /// 처리율은 이전 전송의 `TransferReport::throughput()`이나 링크 속도를 넣음
+    pub fn estimated_duration(&self, rate: f64) -> Option<Duration> {
+        (rate > 0.0).then(|| Duration::from_secs_f64(self.wire_bytes as f64 / rate))
+    }
+
+    /// 경고가 없는지
+    pub fn is_sane(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// 양수 올림 (`f64::ceil`은 no_std에 없음), 송신자의 `ceil() as usize`와 같은 값
+fn ceil(value: f64) -> usize {
+    let whole = value as usize;
+    whole + (value > whole as f64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::run_selftest;
+
+    #[tokio::test]
+    async fn test_plan_matches_realized_transfer() {
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 16_000,
+            base_redundancy_ratio: 0.1,
+            min_redundancy_ratio: 0.0,
+            max_redundancy_ratio: 0.5,
+            ..Config::default()
+        };
+        // 가득 찬 세그먼트 6개 + 청크가 짧게 끝나는 마지막 세그먼트
+        let payload = 6 * 16_000 + 2_500;
+        let plan = config.plan_transfer(payload as u64);
+        assert_eq!(plan.segments, 7);
+        assert_eq!(plan.chunks, 6 * 16 + 3);
+        assert_eq!(plan.redundant_chunks, 6 * 2 + 1);
+        assert!(plan.is_sane());
+
+        let report = run_selftest(&config, payload).await.unwrap();
+        assert!(report.verified);
+        let sent = &report.sender;
+        assert_eq!(sent.total_segments, plan.segments);
+        assert_eq!(sent.redundant_chunks, plan.redundant_chunks);
+        let originals = sent.total_chunks - sent.redundant_chunks - sent.retransmitted_chunks;
+        assert_eq!(originals, plan.chunks);
+
+        // 선로 바이트: 원본 데이터 + 데이터그램마다 프레이밍 + 중복 사본
+        let framing = (Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN) as u64;
+        let originals = payload as u64 + plan.chunks * framing;
+        assert!(plan.wire_bytes > originals);
+        assert!(plan.wire_bytes <= originals + plan.redundant_chunks * (1000 + framing));
+        let duration = plan.estimated_duration(plan.wire_bytes as f64).unwrap();
+        assert_eq!(duration, Duration::from_secs(1));
+
+        // 아주 작은 세그먼트와 청크는 경고
+        let tiny = Config {
+            chunk_size: 16,
+            segment_size: 16,
+            max_accept_file_size: 1 << 20,
+            ..Config::default()
+        };
+        let warnings = tiny.plan_transfer(1 << 30).warnings;
+        assert!(matches!(warnings[0], PlanWarning::TooManySegments(n) if n == 1 << 26));
+        assert!(matches!(warnings[1], PlanWarning::FramingOverhead(share) if share > 0.7));
+        assert_eq!(warnings[2], PlanWarning::ExceedsAcceptLimit);
+    }
+}
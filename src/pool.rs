@@ -0,0 +1,194 @@
+//! 세그먼트 수신 버퍼 풀
+//!
+//! - 세그먼트마다 `BytesMut`를 새로 할당/0 초기화하는 대신 재사용
+//! - 전달된 세그먼트는 사용자가 `Bytes`를 해제하면 회수
+//! - 시작 시 미리 할당+페이지 터치(pre-touch)로 수신 중 페이지 폴트 감소
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bytes::{Bytes, BytesMut};
+use parking_lot::Mutex;
+
+use crate::Config;
+
+/// 버퍼 풀 통계
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// 풀에서 재사용한 횟수
+    pub hits: u64,
+
+    /// 새로 할당한 횟수
+    pub misses: u64,
+
+    /// 현재 풀에 대기 중인 버퍼 수
+    pub pooled: usize,
+
+    /// 사용자에게 전달되어 회수 대기 중인 버퍼 수
+    pub in_flight: usize,
+}
+
+/// 세그먼트 버퍼 풀
+pub struct SegmentBufferPool {
+    /// 버퍼 하나의 용량 (설정된 세그먼트 크기)
+    buffer_size: usize,
+
+    /// 풀에 보관할 최대 버퍼 수 (0이면 풀 비활성화)
+    max_buffers: usize,
+
+    /// 재사용 가능한 버퍼
+    free: Mutex<Vec<BytesMut>>,
+
+    /// 전달되어 사용자 해제를 기다리는 버퍼
+    in_flight: Mutex<VecDeque<Bytes>>,
+
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SegmentBufferPool {
+    pub fn new(buffer_size: usize, max_buffers: usize) -> Self {
+        Self {
+            buffer_size,
+            max_buffers,
+            free: Mutex::new(Vec::with_capacity(max_buffers)),
+            in_flight: Mutex::new(VecDeque::with_capacity(max_buffers)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// 설정 기반 생성 (pre-touch 설정 시 미리 채움)
+    pub fn from_config(config: &Config) -> Self {
+        let pool = Self::new(config.segment_size, config.segment_pool_capacity);
+        if config.segment_pool_pretouch {
+            pool.prefill(config.segment_pool_capacity);
+        }
+        pool
+    }
+
+    /// 버퍼 `count`개를 미리 할당하고 페이지를 터치
+    pub fn prefill(&self, count: usize) {
+        let mut free = self.free.lock();
+        while free.len() < count.min(self.max_buffers) {
+            let mut buf = BytesMut::with_capacity(self.buffer_size);
+            buf.resize(self.buffer_size, 0);
+            buf.clear();
+            free.push(buf);
+        }
+    }
+
+    /// `total_size` 바이트의 0으로 채워진 버퍼 획득
+    pub fn acquire(&self, total_size: usize) -> BytesMut {
+        if total_size <= self.buffer_size && self.max_buffers > 0 {
+            let reused = self.free.lock().pop().or_else(|| self.reclaim_one());
+            if let Some(mut buf) = reused {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buf.clear();
+                buf.resize(total_size, 0);
+                return buf;
+            }
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let mut buf = BytesMut::with_capacity(total_size.max(self.buffer_size));
+        buf.resize(total_size, 0);
+        buf
+    }
+
+    /// 버퍼 반납 (풀이 가득 찼거나 크기가 작으면 해제)
+    pub fn release(&self, buf: BytesMut) {
+        if buf.capacity() < self.buffer_size {
+            return;
+        }
+        let mut free = self.free.lock();
+        if free.len() < self.max_buffers {
+            free.push(buf);
+        }
+    }
+
+    /// 전달한 세그먼트 데이터 추적 (사용자가 해제하면 재사용)
+    pub fn track_delivered(&self, data: &Bytes) {
+        if self.max_buffers == 0 || data.len() > self.buffer_size {
+            return;
+        }
+        let mut in_flight = self.in_flight.lock();
+        if in_flight.len() >= self.max_buffers {
+            in_flight.pop_front();
+        }
+        in_flight.push_back(data.clone());
+    }
+
+    /// 사용자가 해제한 전달 버퍼 하나 회수
+    fn reclaim_one(&self) -> Option<BytesMut> {
+        let mut in_flight = self.in_flight.lock();
+        let idx = in_flight.iter().position(|b| b.is_unique())?;
+        let bytes = in_flight.remove(idx)?;
+        bytes.try_into_mut().ok().filter(|b| b.capacity() >= self.buffer_size)
+    }
+
+    /// 통계 스냅샷
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            pooled: self.free.lock().len(),
+            in_flight: self.in_flight.lock().len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_release_and_reuse() {
+        let pool = SegmentBufferPool::new(1024, 4);
+
+        let mut buf = pool.acquire(1000);
+        assert_eq!(buf.len(), 1000);
+        buf[0] = 0xAB;
+        pool.release(buf);
+
+        let buf = pool.acquire(512);
+        assert_eq!(buf.len(), 512);
+        assert!(buf.iter().all(|&b| b == 0));
+
+        let stats = pool.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.hits, 1);
+    }
+
+    #[test]
+    fn test_reclaim_after_user_drop() {
+        let pool = SegmentBufferPool::new(1024, 4);
+        let data = pool.acquire(1024).freeze();
+        pool.track_delivered(&data);
+
+        // 사용자가 보유 중이면 회수하지 않음
+        let other = pool.acquire(1024);
+        assert_eq!(pool.stats().misses, 2);
+        pool.release(other);
+        let _ = pool.acquire(1024);
+
+        drop(data);
+        let _ = pool.acquire(1024);
+        let stats = pool.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.in_flight, 0);
+    }
+
+    #[test]
+    fn test_prefill_and_disabled_pool() {
+        let pool = SegmentBufferPool::new(256, 2);
+        pool.prefill(8);
+        assert_eq!(pool.stats().pooled, 2);
+
+        let disabled = SegmentBufferPool::new(256, 0);
+        disabled.release(disabled.acquire(256));
+        let _ = disabled.acquire(256);
+        assert_eq!(disabled.stats().hits, 0);
+        assert_eq!(disabled.stats().misses, 2);
+    }
+}
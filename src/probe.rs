@@ -0,0 +1,136 @@
+//! 핸드셰이크 직후 대역폭 측정 (`Config::bandwidth_probe`)
+//!
+//! InitAck 한 번의 RTT로는 대역폭을 알 수 없으므로, 서버가 청크 크기의 프로브를
+//! `PROBE_TRAIN_LEN`개 연달아 보내고 클라이언트가 도착 간격으로 병목 링크의 전달 속도를 잼.
+//! 결과는 `ProbeReport`로 돌려보내 송신측 초기 윈도우를 실제 용량 근처에서 시작하게 함
+//!
+//! - 속도 = 첫 프로브 이후 도착한 바이트 / (마지막 도착 − 첫 도착)
+//! - 일부가 유실되어도 도착한 프로브만으로 계산 (두 개 미만이면 측정 불가)
+
+use std::time::Instant;
+
+use crate::chunk::{Chunk, ChunkHeader};
+use crate::message::{ProbeMessage, ProbeReportMessage};
+use crate::Config;
+
+/// 한 번에 보내는 프로브 수
+pub const PROBE_TRAIN_LEN: u16 = 32;
+
+/// 프로브 하나의 크기 (가득 찬 청크 데이터그램과 같음)
+pub fn probe_datagram_len(config: &Config) -> usize {
+    Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN + config.effective_chunk_size()
+}
+
+/// 프로브 버스트 데이터그램
+pub fn probe_train(config: &Config) -> Vec<Vec<u8>> {
+    let datagram_len = probe_datagram_len(config);
+    (0..PROBE_TRAIN_LEN)
+        .map(|seq| ProbeMessage::new(seq, PROBE_TRAIN_LEN).to_bytes(datagram_len))
+        .collect()
+}
+
+/// 수신측 프로브 측정기
+#[derive(Debug, Default)]
+pub struct ProbeMeter {
+    first: Option<Instant>,
+    last: Option<Instant>,
+    /// 첫 프로브 이후 도착한 바이트
+    bytes_after_first: u64,
+    received: u16,
+    expected: u16,
+    reported: bool,
+}
+
+impl ProbeMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 프로브 도착 기록 (`len`: 데이터그램 크기)
+    pub fn on_probe(&mut self, probe: &ProbeMessage, len: usize, now: Instant) {
+        if self.reported {
+            return;
+        }
+        if self.first.is_none() {
+            self.first = Some(now);
+        } else {
+            self.bytes_after_first += len as u64;
+        }
+        self.last = Some(now);
+        self.received = self.received.saturating_add(1);
+        self.expected = probe.count;
+    }
+
+    /// 프로브를 하나라도 받았는지
+    pub fn started(&self) -> bool {
+        self.first.is_some()
+    }
+
+    /// 마지막 프로브 도착 시각
+    pub fn last_arrival(&self) -> Option<Instant> {
+        self.last
+    }
+
+    /// 버스트를 모두 받았는지
+    pub fn is_complete(&self) -> bool {
+        self.received > 0 && self.received >= self.expected
+    }
+
+    /// 측정한 전달 속도 (bytes/s)
+    pub fn rate(&self) -> Option<f64> {
+        let elapsed = self.last?.saturating_duration_since(self.first?).as_secs_f64();
+        (self.bytes_after_first > 0 && elapsed > 0.0)
+            .then(|| self.bytes_after_first as f64 / elapsed)
+    }
+
+    /// 보고 메시지 (한 번만 반환, 이후 도착한 프로브는 무시)
+    pub fn take_report(&mut self) -> Option<ProbeReportMessage> {
+        if self.reported || !self.started() {
+            return None;
+        }
+        self.reported = true;
+        let rate = self.rate().map_or(0, |rate| rate as u64);
+        Some(ProbeReportMessage::new(self.received, rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_meter_measures_train_spacing() {
+        let config = Config::default();
+        let train = probe_train(&config);
+        assert_eq!(train.len(), PROBE_TRAIN_LEN as usize);
+        let len = train[0].len();
+        assert_eq!(len, probe_datagram_len(&config));
+
+        // 1ms 간격 도착 → len bytes/ms, 중간 하나 유실
+        let t0 = Instant::now();
+        let mut meter = ProbeMeter::new();
+        for (i, datagram) in train.iter().enumerate() {
+            let probe = ProbeMessage::from_bytes(datagram).unwrap();
+            assert_eq!(probe.seq as usize, i);
+            if i == 5 {
+                continue;
+            }
+            meter.on_probe(&probe, datagram.len(), t0 + Duration::from_millis(i as u64));
+        }
+        assert!(!meter.is_complete());
+
+        let report = meter.take_report().unwrap();
+        assert_eq!(report.received, PROBE_TRAIN_LEN - 1);
+        let expected = (PROBE_TRAIN_LEN as usize - 2) as f64 * len as f64 / 0.031;
+        assert!((report.rate as f64 - expected).abs() < 1.0, "{:?}", report);
+        assert_eq!(ProbeReportMessage::from_bytes(&report.to_bytes()), Some(report));
+        assert!(meter.take_report().is_none());
+
+        // 프로브 하나로는 측정 불가
+        let mut single = ProbeMeter::new();
+        single.on_probe(&ProbeMessage::new(0, 1), len, t0);
+        assert!(single.is_complete());
+        assert_eq!(single.take_report().unwrap().rate, 0);
+    }
+}
@@ -0,0 +1,210 @@
+//! 소스 미리 읽기 (`Config::readahead_segments`, `FileSender::send_stream_prefetched`)
+//!
+//! `send_stream`은 세그먼트 하나를 다 보낸 뒤에야 다음 세그먼트를 읽으므로 디스크가 느리면
+//! 읽는 동안 선로가 놀고 페이싱이 끊김. 별도 IO 작업이 다음 세그먼트들을 먼저 읽어 두어 디스크
+//! 지연과 네트워크 페이싱을 떼어 놓음
+//!
+//! - 미리 읽는 양은 세그먼트 `depth`개 (+ 읽는 중인 하나). 큐가 차면 IO 작업이 멈추므로 읽기
+//!   속도는 송신 속도를 따라가고 파일 전체를 메모리에 올리지 않음
+//! - 세그먼트 크기는 읽는 시점의 값(`segment_size()`)이라 적응형 크기도 그대로 따름
+//! - 다음 세그먼트가 아직 준비되지 않아 송신이 기다린 횟수와 시간을 `stalls`/`stall_time`으로 셈
+//!   (미리 읽기가 따라가면 첫 세그먼트 외에는 0에 가까움)
+
+use std::io;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// 소스에서 `segment_size` 바이트를 채울 때까지 읽음 (EOF면 덜 찬 채로)
+pub(crate) async fn read_segment<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    segment_size: usize,
+) -> io::Result<Vec<u8>> {
+    let mut segment = vec![0u8; segment_size];
+    let mut filled = 0;
+    while filled < segment_size {
+        match reader.read(&mut segment[filled..]).await? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    segment.truncate(filled);
+    Ok(segment)
+}
+
+/// 백그라운드에서 세그먼트를 미리 읽는 소스
+#[derive(Debug)]
+pub struct Readahead {
+    rx: mpsc::Receiver<io::Result<Bytes>>,
+    task: JoinHandle<()>,
+    stalls: u64,
+    stall_time: Duration,
+}
+
+impl Readahead {
+    /// `reader`를 읽는 IO 작업 시작. 세그먼트 크기는 읽을 때마다 `segment_size()`로 정함
+    pub fn spawn<R, F>(mut reader: R, depth: usize, segment_size: F) -> Self
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+        F: Fn() -> usize + Send + 'static,
+    {
+        let (tx, rx) = mpsc::channel(depth.max(1));
+        let task = tokio::spawn(async move {
+            loop {
+                let size = segment_size().max(1);
+                let segment = match read_segment(&mut reader, size).await {
+                    Ok(segment) => segment,
+                    Err(e) => {
+                        let _ = tx.send(Err(e)).await;
+                        return;
+                    }
+                };
+                let last = segment.len() < size;
+                if !segment.is_empty() && tx.send(Ok(Bytes::from(segment))).await.is_err() {
+                    return;
+                }
+                if last {
+                    return;
+                }
+            }
+        });
+        Self {
+            rx,
+            task,
+            stalls: 0,
+            stall_time: Duration::ZERO,
+        }
+    }
+
+    /// 다음 세그먼트 (소스가 끝나면 None)
+    pub async fn next_segment(&mut self) -> Option<io::Result<Bytes>> {
+        match self.rx.try_recv() {
+            Ok(segment) => Some(segment),
+            Err(mpsc::error::TryRecvError::Disconnected) => None,
+            Err(mpsc::error::TryRecvError::Empty) => {
+                let started = Instant::now();
+                let segment = self.rx.recv().await;
+                if segment.is_some() {
+                    self.stalls += 1;
+                    self.stall_time += started.elapsed();
+                }
+                segment
+            }
+        }
+    }
+
+    /// 세그먼트가 준비되지 않아 기다린 횟수
+    pub fn stalls(&self) -> u64 {
+        self.stalls
+    }
+
+    /// 기다린 시간 합
+    pub fn stall_time(&self) -> Duration {
+        self.stall_time
+    }
+}
+
+impl Drop for Readahead {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use std::task::{ready, Context, Poll};
+
+    use tokio::io::ReadBuf;
+    use tokio::time::Sleep;
+
+    use crate::multipath::PathManager;
+    use crate::receiver::FileReceiver;
+    use crate::sender::FileSender;
+    use crate::sim::{SimConfig, SimNetwork};
+    use crate::{Config, Receiver};
+
+    /// 읽을 때마다 `latency`만큼 늦게 돌려주는 소스 (느린 디스크)
+    struct SlowReader {
+        data: Vec<u8>,
+        pos: usize,
+        latency: Duration,
+        delay: Option<Pin<Box<Sleep>>>,
+    }
+
+    impl AsyncRead for SlowReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let latency = self.latency;
+            let delay = self.delay.get_or_insert_with(|| Box::pin(tokio::time::sleep(latency)));
+            ready!(delay.as_mut().poll(cx));
+            self.delay = None;
+            let n = (self.data.len() - self.pos).min(buf.remaining());
+            buf.put_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_readahead_hides_slow_source() {
+        // 1 MB/s 송신 상한: 16KB 세그먼트 하나에 (중복 청크 포함) 19ms쯤, 읽기는 세그먼트당 15ms
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 16_000,
+            total_egress_limit: 1_000_000,
+            ..Config::default()
+        };
+        let latency = Duration::from_millis(15);
+        // 읽기 지연과 송신 상한 페이싱이 모두 멈춘 tokio 시계 위에서 흐름 (부하와 무관)
+        let net = SimNetwork::new(SimConfig::default());
+        let file_sender =
+            FileSender::with_socket(config.clone(), Arc::new(net.bind("10.0.0.1:9000".parse().unwrap())));
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+            file_sender.local_addr().unwrap(),
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        let data: Vec<u8> = (0..168_000u32).map(|i| (i * 13 % 251) as u8).collect();
+        let source = SlowReader {
+            data: data.clone(),
+            pos: 0,
+            latency,
+            delay: None,
+        };
+        let receiver_addr = file_receiver.receiver().local_addr();
+        let started = tokio::time::Instant::now();
+        let sent = file_sender.send_stream_prefetched(source, receiver_addr).await.unwrap();
+        let elapsed = started.elapsed();
+        assert_eq!(sent, data.len() as u64);
+
+        // 읽기와 전송이 번갈아 가면 적어도 (읽기 11번 + 상한에서의 전송 시간)
+        let serial = latency * 11 + Duration::from_secs_f64(data.len() as f64 / 1_000_000.0);
+        assert!(elapsed < serial, "{:?} >= {:?}", elapsed, serial);
+        // 첫 세그먼트만 읽기를 기다림
+        let stats = file_sender.stats();
+        assert_eq!(stats.readahead_stalls, 1, "{:?}", stats);
+        assert_eq!(stats.total_segments, 11);
+
+        let received = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_file(11))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, data);
+        file_receiver.receiver().stop().await;
+    }
+}
@@ -4,72 +4,498 @@
 //! - NACK 기반 재전송 요청
 //! - 최소 업링크 부담
 
-use std::collections::HashMap;
+use std::collections::{btree_map, BTreeMap, HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use bytes::Bytes;
 use tokio::net::UdpSocket;
-use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, info, warn};
+use parking_lot::Mutex;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot, Notify, RwLock};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, info, info_span, warn, Instrument, Span};
 
-use crate::chunk::{Chunk, ChunkId, Segment, SegmentId};
-use crate::message::{InitMessage, NackMessage, SegmentCompleteMessage};
+use crate::buffertune::{self, BufferTuner};
+use crate::chunk::{Chunk, ChunkBatch, ChunkId, FileId, Segment, SegmentId};
+use crate::crypto::MetadataAuth;
+use crate::fec::{self, RedundancyMode};
+use crate::logsample::LogSampler;
+use crate::manifest::{
+    self, FileReport, FileWriter, Manifest, MANIFEST_FILE_ID, MANIFEST_SEGMENT_ID,
+};
+use crate::rtt::{self, RttSampleFilter};
+use crate::sink::OutputSink;
+use crate::message::{
+    CookieMessage, GiveUpMessage, HeartbeatMessage, InitAckMessage, InitMessage, ModeChangeMessage,
+    NackCancelMessage, NackDeltaMessage, NackMessage, NetworkChangeMessage, OfferMessage,
+    PathChallengeMessage, PathResponseMessage, PauseMessage, ProbeMessage, SegmentCompleteMessage,
+    SparseSegmentMessage,
+};
 use crate::multipath::PathManager;
-use crate::stats::TransferStats;
-use crate::{Config, Error, Result};
+use crate::netchange::DelayStepDetector;
+use crate::pool::{PoolStats, SegmentBufferPool};
+use crate::probe::ProbeMeter;
+use crate::state::{ConnectionState, ConnectionStateCell};
+use crate::stats::{QueueDepth, TransferReport, TransferStats};
+use crate::message::{MessageHeader, MessageType};
+use crate::transport::{self, DatagramSocket};
+use crate::{Config, Error, Result, MAGIC_NUMBER};
 
 /// 완료된 세그먼트 채널 수신기 타입
 pub type SegmentReceiver = mpsc::Receiver<(SegmentId, Bytes)>;
 
+/// InitAck/첫 청크를 받기 전 Init 재전송 간격
+const INIT_RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 마지막 프로브 이후 이 시간 동안 남은 프로브가 오지 않으면 받은 만큼으로 보고
+const PROBE_IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// 연속 완료 구간 밖에서 따로 기억하는 완료 세그먼트 수 (넘으면 오래된 것부터 잊음)
+const COMPLETED_HISTORY: usize = 1024;
+
+/// 관측된 순서 역전 지연 대비 NACK 보류 배율 (RACK 방식의 여유분)
+const REORDER_WINDOW_GAIN: f64 = 1.25;
+
+/// 순서 역전 대기의 상한 (nack_timeout 배수)
+const MAX_REORDER_WINDOW_FACTOR: u32 = 8;
+
+/// Pause 한 번이 유지하는 정지 시간 (송신자는 정지 중 250ms마다 다시 알리므로 Resume이
+/// 유실되거나 송신자가 사라져도 이만큼 뒤에는 평소대로 NACK)
+const PAUSE_LEASE: Duration = Duration::from_secs(1);
+
+/// 특정 세그먼트 완료를 기다리는 호출자
+type SegmentWaiter = oneshot::Sender<Result<Bytes>>;
+
 /// 내부 명령
 enum ReceiverCmd {
     Chunk(Chunk),
+    Sparse(SparseSegmentMessage),
+    ModeChange(RedundancyMode),
+    AwaitSegment(SegmentId, SegmentWaiter),
+    NackSegment(SegmentId),
+    GiveUp(SegmentId, FileId),
     SendNacks,
     Stop,
 }
 
+/// 조립 샤드별 명령 큐
+///
+/// 세그먼트 명령(청크, 대기, 즉시 NACK)은 `segment_id % 샤드 수` 샤드로 보내 샤드끼리 잠그지
+/// 않고, NACK 라운드/모드 변경/정지는 모든 샤드에 보냄
+#[derive(Clone)]
+struct ShardQueues {
+    queues: Vec<mpsc::Sender<ReceiverCmd>>,
+}
+
+impl ShardQueues {
+    /// 명령 전달. 받을 샤드가 모두 끝났으면 false
+    async fn send(&self, cmd: ReceiverCmd) -> bool {
+        let segment_id = match &cmd {
+            ReceiverCmd::Chunk(chunk) => chunk.header.segment_id,
+            ReceiverCmd::Sparse(marker) => marker.segment_id,
+            ReceiverCmd::AwaitSegment(segment_id, _)
+            | ReceiverCmd::NackSegment(segment_id)
+            | ReceiverCmd::GiveUp(segment_id, _) => *segment_id,
+            &ReceiverCmd::ModeChange(mode) => {
+                return self.broadcast(|| ReceiverCmd::ModeChange(mode)).await
+            }
+            ReceiverCmd::SendNacks => return self.broadcast(|| ReceiverCmd::SendNacks).await,
+            ReceiverCmd::Stop => return self.broadcast(|| ReceiverCmd::Stop).await,
+        };
+        let shard = (segment_id % self.queues.len() as SegmentId) as usize;
+        self.queues[shard].send(cmd).await.is_ok()
+    }
+
+    async fn broadcast(&self, cmd: impl Fn() -> ReceiverCmd) -> bool {
+        let mut delivered = false;
+        for queue in &self.queues {
+            delivered |= queue.send(cmd()).await.is_ok();
+        }
+        delivered
+    }
+
+    fn downgrade(&self) -> Vec<mpsc::WeakSender<ReceiverCmd>> {
+        self.queues.iter().map(mpsc::Sender::downgrade).collect()
+    }
+}
+
+/// 수신 조립 샤드 수 (`parallel_workers`, 0이면 CPU 코어 수)
+fn assembly_shards(config: &Config) -> usize {
+    match config.parallel_workers {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        workers => workers,
+    }
+}
+
 /// 세그먼트 상태
 struct SegmentState {
     segment: Segment,
+    /// 세그먼트가 속한 파일 (첫 청크 기준)
+    file_id: FileId,
     last_nack_time: Instant,
+    /// 도착한 청크 수 (중복/패리티 포함)
+    chunks_received: u32,
+    /// 이미 받은 청크가 다시 도착한 수
+    duplicates: u32,
+    /// 지금까지 받은 가장 큰 데이터 청크 ID
+    highest_chunk: Option<ChunkId>,
+    /// 더 뒤 청크가 먼저 도착해 빈 구간이 생긴 시각 (구간이 모두 채워지면 해제)
+    gap_since: Option<Instant>,
+    /// NACK을 보낸 적이 있는지 (이후 늦은 청크는 재전송으로 간주)
+    nack_sent: bool,
+    /// 마지막 NACK에서 요청했고 아직 도착하지 않은 청크
+    pending_nack: HashSet<ChunkId>,
+    /// 마지막 델타 NACK 순번
+    nack_seq: u16,
+    /// 송신자에게 마지막으로 보고한 누락 집합 (없으면 다음 델타 NACK은 전체 보고)
+    reported: Option<HashSet<ChunkId>>,
+    /// 마지막 전체 보고 이후 보낸 델타 수
+    nacks_since_full: u32,
+    /// 마지막으로 새 청크가 들어온 뒤 보낸 NACK 수
+    stalled_nacks: u32,
+    /// 마지막으로 새 청크가 들어온 시각 (진전 없는 NACK 포기를 RTT에 맞춤)
+    last_progress: Instant,
+    /// 원본보다 먼저 도착해 빈자리를 채운 중복 전송 사본 (원본이 오면 낭비로 재분류)
+    redundant_filled: HashSet<ChunkId>,
+    /// 조립 소프트 마감을 넘겨 NACK을 격상했는지
+    escalated: bool,
+    /// 세그먼트 끝이 메시지 경계 (청크 헤더의 `boundary`)
+    boundary: bool,
+    /// 스트림의 마지막 세그먼트 (청크 헤더의 `end_of_stream`)
+    end_of_stream: bool,
+}
+
+impl SegmentState {
+    fn new(segment: Segment, file_id: FileId) -> Self {
+        Self {
+            segment,
+            file_id,
+            last_nack_time: Instant::now(),
+            chunks_received: 0,
+            duplicates: 0,
+            highest_chunk: None,
+            gap_since: None,
+            nack_sent: false,
+            pending_nack: HashSet::new(),
+            nack_seq: 0,
+            reported: None,
+            nacks_since_full: 0,
+            stalled_nacks: 0,
+            last_progress: Instant::now(),
+            redundant_filled: HashSet::new(),
+            escalated: false,
+            boundary: false,
+            end_of_stream: false,
+        }
+    }
+
+    /// 세그먼트를 처음부터 다시 받을 때 도착 순서/NACK 추적 초기화
+    fn restart(&mut self) {
+        self.highest_chunk = None;
+        self.gap_since = None;
+        self.redundant_filled.clear();
+        self.nack_sent = false;
+        self.pending_nack.clear();
+        self.reported = None;
+        self.nacks_since_full = 0;
+        self.stalled_nacks = 0;
+        self.last_progress = Instant::now();
+    }
+}
+
+/// 영구 실패로 포기한 세그먼트 (수신 태스크 → `FileReceiver`)
+#[derive(Debug, Default)]
+struct FailedSegments {
+    /// 포기한 순서대로
+    ids: Mutex<Vec<SegmentId>>,
+    /// 청크 헤더로 알고 있던 세그먼트 크기 (빈 구간의 바이트 범위 보고용)
+    sizes: Mutex<HashMap<SegmentId, usize>>,
+    notify: Notify,
+}
+
+impl FailedSegments {
+    fn push(&self, segment_id: SegmentId, size: usize) {
+        self.sizes.lock().insert(segment_id, size);
+        self.ids.lock().push(segment_id);
+        self.notify.notify_one();
+    }
+
+    fn size(&self, segment_id: SegmentId) -> Option<usize> {
+        self.sizes.lock().get(&segment_id).copied()
+    }
+
+    fn contains(&self, segment_id: SegmentId) -> bool {
+        self.ids.lock().contains(&segment_id)
+    }
+
+    /// `cursor`번째 이후 새로 포기한 세그먼트
+    fn since(&self, cursor: usize) -> Vec<SegmentId> {
+        self.ids.lock().get(cursor..).map_or_else(Vec::new, <[_]>::to_vec)
+    }
+}
+
+/// 송신자가 알린 일시 정지 (Pause마다 `PAUSE_LEASE`만큼 연장, Resume이나 청크가 오면 해제)
+#[derive(Debug, Default)]
+struct PauseLease {
+    until: Mutex<Option<Instant>>,
+}
+
+impl PauseLease {
+    fn extend(&self) {
+        *self.until.lock() = Some(Instant::now() + PAUSE_LEASE);
+    }
+
+    fn release(&self) {
+        *self.until.lock() = None;
+    }
+
+    fn is_active(&self) -> bool {
+        self.until.lock().is_some_and(|until| Instant::now() < until)
+    }
+}
+
+/// 완료된 세그먼트 집합
+///
+/// 1부터 연속으로 완료된 구간은 상한(`through`) 하나로, 그 뒤에 먼저 완료된 세그먼트만
+/// 집합으로 기억. 집합의 항목은 시간이 지나도 잊지 않고 앞의 빈 세그먼트가 완료(또는 실패)되어
+/// 연속 구간에 합쳐질 때 비워짐. 시간으로 잊으면 그 뒤에 온 늦은 재전송이 세그먼트를 다시 만들어
+/// 한 번 더 전달함. `COMPLETED_HISTORY`를 넘을 때만 오래된 것부터 잊음.
+/// 조립 샤드는 자기 몫의 세그먼트(`stride` 간격)만 보므로 연속 구간도 그 간격으로 셈
+#[derive(Debug, Default)]
+struct CompletedSegments {
+    /// 1..=through 중 이 샤드 몫은 모두 완료
+    through: SegmentId,
+    /// 이 샤드가 받는 첫 세그먼트 ID
+    first: SegmentId,
+    /// 이 샤드가 받는 세그먼트 ID 간격 (샤드 수)
+    stride: SegmentId,
+    /// 연속 구간 밖에서 완료된 세그먼트 (매니페스트 세그먼트 포함)
+    ids: HashSet<SegmentId>,
+    /// 집합에 들어간 순서
+    order: VecDeque<SegmentId>,
+}
+
+impl CompletedSegments {
+    fn new() -> Self {
+        Self::for_shard(0, 1)
+    }
+
+    /// `segment_id % count == index`인 세그먼트만 받는 샤드용
+    fn for_shard(index: usize, count: usize) -> Self {
+        let stride = count.max(1) as SegmentId;
+        Self {
+            first: match index as SegmentId {
+                0 => stride,
+                index => index,
+            },
+            stride,
+            ..Self::default()
+        }
+    }
+
+    /// 연속 구간에 이어 붙는 다음 세그먼트
+    fn next(&self) -> SegmentId {
+        match self.through {
+            0 => self.first,
+            through => through + self.stride,
+        }
+    }
+
+    fn contains(&self, segment_id: SegmentId) -> bool {
+        (1..=self.through).contains(&segment_id) || self.ids.contains(&segment_id)
+    }
+
+    fn insert(&mut self, segment_id: SegmentId) {
+        if self.contains(segment_id) {
+            return;
+        }
+        if segment_id == self.next() {
+            self.through = segment_id;
+            while self.ids.remove(&self.next()) {
+                self.through += self.stride;
+            }
+            return;
+        }
+
+        self.ids.insert(segment_id);
+        self.order.push_back(segment_id);
+        self.expire();
+    }
+
+    /// 연속 구간에 합쳐진 항목은 건너뛰고, 개수 한도를 넘은 항목부터 잊음
+    fn expire(&mut self) {
+        while let Some(&oldest) = self.order.front() {
+            if self.order.len() <= COMPLETED_HISTORY && self.ids.contains(&oldest) {
+                break;
+            }
+            self.order.pop_front();
+            self.ids.remove(&oldest);
+        }
+    }
 }
 
 /// 수신자 내부 상태 (단일 태스크에서만 접근)
-struct ReceiverInner {
+struct ReceiverInner<S> {
     config: Config,
     segments: HashMap<SegmentId, SegmentState>,
     stats: TransferStats,
     server_addr: SocketAddr,
-    socket: Arc<UdpSocket>,
+    socket: Arc<S>,
     completed_tx: mpsc::Sender<(SegmentId, Bytes)>,
+    /// 완료 채널이 차서 아직 넘기지 못한 완료 세그먼트 (완료 순서대로)
+    undelivered: VecDeque<(SegmentId, Bytes)>,
     completed_count: u64,
     path_manager: Arc<PathManager>,
+    pool: Arc<SegmentBufferPool>,
+    fec_active: Arc<AtomicBool>,
+    /// 완료된 세그먼트 (완료 후 도착한 중복 청크가 세그먼트를 다시 만들지 않도록)
+    completed: CompletedSegments,
+    /// 연결 전체에 도착한 청크 수 (중복/패리티 포함, NACK 응답 감시용, 샤드 간 공유).
+    /// 다른 샤드의 세그먼트로 청크가 오고 있으면 업링크는 살아 있음
+    chunk_arrivals: Arc<AtomicU64>,
+    /// 직전 NACK 라운드 시점의 `chunk_arrivals`
+    nack_round_arrivals: Option<u64>,
+    /// 청크 도착 없이 이어진 NACK 라운드 수
+    silent_nack_rounds: u32,
+    /// 청크가 도착한 마지막 NACK 라운드 시각
+    last_answered_round: Instant,
+    /// 핸드셰이크 RTT (마이크로초, 0이면 모름, 샤드 간 공유)
+    rtt_us: Arc<AtomicU64>,
+    /// NACK을 보내는 NIC (None이면 기본 소켓)
+    nack_nic: Option<u8>,
+    /// 모든 경로로 NACK이 응답받지 못함
+    uplink_broken: Arc<AtomicBool>,
+    /// 청크/세그먼트 단위 디버그 로그 표본
+    chunk_log: LogSampler,
+    /// NACK/NACK 철회 디버그 로그 표본
+    nack_log: LogSampler,
+    /// 완료를 기다리는 호출자가 있는 세그먼트 (NACK 우선 대상)
+    waiters: HashMap<SegmentId, Vec<SegmentWaiter>>,
+    /// 영구 실패로 포기한 세그먼트
+    failed: Arc<FailedSegments>,
+    /// 메시지 경계로 완료했지만 아직 소비자에게 넘기지 않은 세그먼트
+    boundaries: Arc<Mutex<HashSet<SegmentId>>>,
+    /// 스트림 끝으로 표시된 세그먼트 (0이면 아직 없음)
+    end_of_stream: Arc<AtomicU64>,
+    /// NACK/완료 보고에 싣는 연결 ID (Init의 세션 ID, 주소가 바뀌어도 서버가 알아봄)
+    connection_id: u64,
+    /// 매니페스트로 받은 세그먼트별 기대 SHA-256 (검증을 통과하면 제거, 샤드 간 공유)
+    segment_digests: Arc<Mutex<HashMap<SegmentId, [u8; 32]>>>,
+    /// 매니페스트 세그먼트를 완료함 (샤드 간 공유)
+    manifest_received: Arc<AtomicBool>,
+    /// 매니페스트보다 먼저 완료해 다이제스트 확인을 기다리는 파일 세그먼트
+    held: HashSet<SegmentId>,
+    /// 송신자의 일시 정지 (샤드 간 공유)
+    pause: Arc<PauseLease>,
+    /// 이 샤드가 정지를 처음 본 시각 (재개하면 그만큼 타임아웃을 미룸)
+    paused_since: Option<Instant>,
+    /// 서버가 InitAck으로 델타 NACK을 수락함 (샤드 간 공유)
+    delta_nacks: Arc<AtomicBool>,
 }
 
-impl ReceiverInner {
+impl<S: DatagramSocket> ReceiverInner<S> {
     fn new(
         config: Config,
         server_addr: SocketAddr,
-        socket: Arc<UdpSocket>,
+        socket: Arc<S>,
         completed_tx: mpsc::Sender<(SegmentId, Bytes)>,
         path_manager: Arc<PathManager>,
+        pool: Arc<SegmentBufferPool>,
+        fec_active: Arc<AtomicBool>,
     ) -> Self {
-        let nic_count = path_manager.nic_count().max(1);
+        let nic_count = path_manager.nic_id_bound().max(1);
         Self {
             stats: TransferStats::new(nic_count, config.stats_window_size),
+            chunk_log: LogSampler::from_config("chunk", &config),
+            nack_log: LogSampler::from_config("nack", &config),
             config,
             segments: HashMap::new(),
             server_addr,
             socket,
             completed_tx,
+            undelivered: VecDeque::new(),
             completed_count: 0,
             path_manager,
+            pool,
+            fec_active,
+            completed: CompletedSegments::new(),
+            chunk_arrivals: Arc::default(),
+            nack_round_arrivals: None,
+            silent_nack_rounds: 0,
+            last_answered_round: Instant::now(),
+            rtt_us: Arc::default(),
+            nack_nic: None,
+            uplink_broken: Arc::new(AtomicBool::new(false)),
+            waiters: HashMap::new(),
+            failed: Arc::default(),
+            boundaries: Arc::default(),
+            end_of_stream: Arc::default(),
+            connection_id: 0,
+            segment_digests: Arc::default(),
+            manifest_received: Arc::default(),
+            held: HashSet::new(),
+            pause: Arc::default(),
+            paused_since: None,
+            delta_nacks: Arc::default(),
+        }
+    }
+
+    /// 같은 연결의 `index`번째 조립 샤드 (`segment_id % count == index`인 세그먼트 담당)
+    ///
+    /// 실패/경계/다이제스트 기록과 업링크 상태는 공유하고, 세그먼트 맵과 통계는 샤드마다 따로 둠.
+    /// 완료 세그먼트 보관 창은 샤드 수로 나눠 연결 전체 한도를 유지
+    fn shard(&self, index: usize, count: usize) -> Self {
+        let mut config = self.config.clone();
+        config.delivery_window_segments = config.delivery_window_segments.div_ceil(count);
+        let mut shard = Self::new(
+            config,
+            self.server_addr,
+            self.socket.clone(),
+            self.completed_tx.clone(),
+            self.path_manager.clone(),
+            self.pool.clone(),
+            self.fec_active.clone(),
+        );
+        shard.completed = CompletedSegments::for_shard(index, count);
+        shard.uplink_broken = self.uplink_broken.clone();
+        shard.chunk_arrivals = self.chunk_arrivals.clone();
+        shard.rtt_us = self.rtt_us.clone();
+        shard.failed = self.failed.clone();
+        shard.boundaries = self.boundaries.clone();
+        shard.end_of_stream = self.end_of_stream.clone();
+        shard.connection_id = self.connection_id;
+        shard.segment_digests = self.segment_digests.clone();
+        shard.manifest_received = self.manifest_received.clone();
+        shard.pause = self.pause.clone();
+        shard.delta_nacks = self.delta_nacks.clone();
+        shard
+    }
+
+    /// 세그먼트 완료 대기 등록. 이미 전달한 세그먼트면 바로 실패
+    fn await_segment(&mut self, segment_id: SegmentId, waiter: SegmentWaiter) {
+        if self.failed.contains(segment_id) {
+            let _ = waiter.send(Err(Error::SegmentUndeliverable { segment_id }));
+            return;
         }
+        if self.completed.contains(segment_id) {
+            let _ = waiter.send(Err(Error::SegmentAlreadyDelivered { segment_id }));
+            return;
+        }
+        self.waiters.entry(segment_id).or_default().push(waiter);
     }
 
+    #[tracing::instrument(
+        level = "debug",
+        name = "segment",
+        skip_all,
+        fields(segment_id = chunk.header.segment_id, nic_id = chunk.header.nic_id)
+    )]
     async fn handle_chunk(&mut self, chunk: Chunk) {
         let segment_id = chunk.header.segment_id;
         let nic_id = chunk.header.nic_id;
@@ -77,118 +503,650 @@ impl ReceiverInner {
 
         // NIC 통계 기록
         self.path_manager.record_chunk_arrival(nic_id, chunk_size);
+        self.chunk_arrivals.fetch_add(1, Ordering::Relaxed);
+
+        // 이미 완료된 세그먼트의 늦은 중복/재전송 청크
+        if self.completed.contains(segment_id) {
+            self.stats.redundant_chunks += 1;
+            self.stats.late_chunks += 1;
+            if chunk.header.is_redundant && !fec::is_parity(&chunk) {
+                self.stats.redundant_wasted += 1;
+            }
+            return;
+        }
+
+        // 패리티는 모드와 무관하게 받음 (FEC로 바꾼다는 ModeChange가 유실될 수 있음)
+        let is_parity = fec::is_parity(&chunk);
+
+        // 한도를 무시한 서버의 큰 세그먼트는 버퍼를 잡기 전에 버림
+        let max_segment = self.config.max_supported_segment_size;
+        if max_segment > 0 && chunk.header.segment_size as usize > max_segment {
+            if self.chunk_log.sample() {
+                debug!(
+                    "수신 한도 초과 세그먼트 청크 무시: {} bytes > {} bytes",
+                    chunk.header.segment_size, max_segment
+                );
+            }
+            return;
+        }
+
+        // 세그먼트 바이트 수보다 청크가 많다는 헤더는 어떤 분할로도 나오지 않음 (비트맵을 잡기 전에 버림)
+        if chunk.header.total_chunks as usize > (chunk.header.segment_size as usize).max(1) {
+            self.reject_inconsistent_chunk(&chunk, None);
+            return;
+        }
+
+        // 소비자가 밀려 보관 창이 차 있으면 새 세그먼트는 열지 않음 (송신자가 다시 보냄).
+        // 조립 중인 세그먼트와 호출자가 직접 기다리는 세그먼트(`await_segment`)의 청크는 그대로 처리
+        if !self.segments.contains_key(&segment_id)
+            && !self.waiters.contains_key(&segment_id)
+            && self.delivery_window_full()
+        {
+            self.stats.deferred_admissions += 1;
+            if self.chunk_log.sample() {
+                debug!(segment_id, "완료 보관 창 가득 참: 새 세그먼트 청크 보류");
+            }
+            return;
+        }
 
         // 세그먼트 가져오기 또는 생성
         let state = self.segments.entry(segment_id).or_insert_with(|| {
             self.stats.total_segments += 1;
-            SegmentState {
-                segment: Segment::new_for_receive(
-                    segment_id,
-                    chunk.header.segment_size as usize,
-                    chunk.header.total_chunks,
-                ),
-                last_nack_time: Instant::now(),
-            }
+            self.stats.record_first_chunk(segment_id);
+            let segment = Segment::new_for_receive_pooled(
+                segment_id,
+                chunk.header.segment_size as usize,
+                chunk.header.total_chunks,
+                &self.pool,
+            );
+            SegmentState::new(segment, chunk.header.file_id)
         });
 
-        // 청크 삽입
-        let inserted = state.segment.insert_chunk(&chunk);
+        // 같은 세그먼트 ID라도 다른 파일 범위의 청크는 조립하지 않음
+        if state.file_id != chunk.header.file_id {
+            if self.chunk_log.sample() {
+                debug!(
+                    "파일 범위 불일치 청크 무시: file_id {} (세그먼트는 {})",
+                    chunk.header.file_id, state.file_id
+                );
+            }
+            return;
+        }
 
-        if inserted {
-            self.stats.total_chunks += 1;
-            self.stats.total_bytes += chunk_size as u64;
+        // 세그먼트 크기가 다른 청크는 어떤 재분할로도 설명되지 않음: 세그먼트를 건드리지 않고 버림
+        if chunk.header.segment_size as usize != state.segment.total_size {
+            let established = (state.segment.total_chunks, state.segment.total_size);
+            self.reject_inconsistent_chunk(&chunk, Some(established));
+            return;
+        }
 
-            if let Some(nic_stat) = self.stats.nic_stats.get_mut(nic_id as usize) {
-                nic_stat.record_arrival(chunk_size);
+        // 송신측이 PMTU 블랙홀을 감지해 더 작은 청크로 다시 나눈 세그먼트: 조립을 처음부터 다시 함
+        if chunk.header.total_chunks != state.segment.total_chunks {
+            if is_parity || chunk.header.total_chunks < state.segment.total_chunks {
+                // 다른 분할 기준의 패리티나 다시 나누기 전에 보낸 늦은 청크
+                return;
             }
-        } else if chunk.header.is_redundant {
+            debug!(
+                segment_id,
+                "세그먼트 재분할 감지: 청크 {} → {}",
+                state.segment.total_chunks,
+                chunk.header.total_chunks
+            );
+            let resized = Segment::new_for_receive_pooled(
+                segment_id,
+                chunk.header.segment_size as usize,
+                chunk.header.total_chunks,
+                &self.pool,
+            );
+            std::mem::replace(&mut state.segment, resized).recycle(&self.pool);
+            state.restart();
+        }
+        state.chunks_received += 1;
+        state.boundary |= chunk.header.boundary;
+        state.end_of_stream |= chunk.header.end_of_stream;
+        let mut withdrawn = None;
+
+        if is_parity {
+            state.segment.insert_parity(&chunk);
+            state.stalled_nacks = 0;
+            state.last_progress = Instant::now();
             self.stats.redundant_chunks += 1;
+        } else {
+            // 청크 삽입
+            if state.segment.has_chunk(chunk.header.chunk_id) {
+                state.duplicates += 1;
+            }
+            let inserted = state.segment.insert_chunk(&chunk);
+
+            // 중복 전송 사본의 효과: 빈자리를 채웠으면 유효, 이미 있던 청크면 낭비
+            let chunk_id = chunk.header.chunk_id;
+            if chunk.header.is_redundant {
+                if inserted {
+                    self.stats.redundant_useful += 1;
+                    state.redundant_filled.insert(chunk_id);
+                } else {
+                    self.stats.redundant_wasted += 1;
+                }
+            } else if state.redundant_filled.remove(&chunk_id) {
+                // 원본은 손실이 아니라 늦게 도착했을 뿐
+                self.stats.redundant_useful -= 1;
+                self.stats.redundant_wasted += 1;
+            }
+
+            if inserted {
+                state.stalled_nacks = 0;
+                state.last_progress = Instant::now();
+                self.stats.total_chunks += 1;
+                self.stats.total_bytes += chunk_size as u64;
+
+                if let Some(nic_stat) = self.stats.nic_stats.get_mut(nic_id as usize) {
+                    nic_stat.record_arrival(chunk_size);
+                }
+
+                // NACK한 청크를 중복 전송 사본이 채웠으면 그 NACK은 철회 대상
+                if state.pending_nack.remove(&chunk_id) && chunk.header.is_redundant {
+                    withdrawn = Some(chunk_id);
+                }
+
+                let now = Instant::now();
+                match state.highest_chunk {
+                    Some(highest) if chunk_id < highest => {
+                        // 손실이 아니라 순서가 뒤바뀐 도착 (중복 전송 사본/재전송은 제외)
+                        if !chunk.header.is_redundant && !state.nack_sent {
+                            let delay = state
+                                .gap_since
+                                .map(|since| now.duration_since(since))
+                                .unwrap_or_default();
+                            if let Some(nic_stat) = self.stats.nic_stats.get_mut(nic_id as usize) {
+                                nic_stat.record_reorder(highest - chunk_id, delay);
+                            }
+                        }
+                        let hole_left = state
+                            .segment
+                            .missing_ranges()
+                            .next()
+                            .is_some_and(|(start, _)| start < highest);
+                        if !hole_left {
+                            state.gap_since = None;
+                        }
+                    }
+                    previous => {
+                        let expected = previous.map_or(0, |highest| highest + 1);
+                        if chunk_id > expected && state.gap_since.is_none() {
+                            state.gap_since = Some(now);
+                        }
+                        state.highest_chunk = Some(chunk_id);
+                    }
+                }
+            } else if chunk.header.is_redundant {
+                self.stats.redundant_chunks += 1;
+            }
+        }
+
+        // 누락분을 패리티로 먼저 복원 (NACK 없이)
+        let before = state.segment.received_count;
+        let complete = state.segment.try_complete();
+        let recovered = state.segment.received_count - before;
+        if recovered > 0 {
+            debug!("세그먼트 FEC 복원: {} 청크", recovered);
+            self.stats.fec_recovered_chunks += recovered as u64;
+        }
+
+        // 세그먼트 완료 처리 (완료 보고가 남은 재전송을 모두 정리하므로 철회 불필요)
+        if complete {
+            self.handle_segment_complete(segment_id).await;
+        } else if let Some(chunk_id) = withdrawn {
+            let file_id = state.file_id;
+            self.withdraw_nack(segment_id, file_id, chunk_id).await;
+        }
+    }
+
+    /// 분할 기준이 세그먼트(`established`: 총 청크 수, 크기)나 자기 자신과 맞지 않는 청크를 이상으로
+    /// 세고 경고 (프로토콜 버그나 주입된 패킷)
+    fn reject_inconsistent_chunk(&mut self, chunk: &Chunk, established: Option<(u32, usize)>) {
+        self.stats.inconsistent_chunks += 1;
+        if !self.chunk_log.sample() {
+            return;
+        }
+        let header = &chunk.header;
+        match established {
+            Some((total_chunks, total_size)) => warn!(
+                segment_id = header.segment_id,
+                "분할 기준이 다른 청크 거부: {}청크/{} bytes (세그먼트는 {}청크/{} bytes)",
+                header.total_chunks,
+                header.segment_size,
+                total_chunks,
+                total_size
+            ),
+            None => warn!(
+                segment_id = header.segment_id,
+                "말이 안 되는 청크 헤더 거부: {}청크/{} bytes",
+                header.total_chunks,
+                header.segment_size
+            ),
+        }
+    }
+
+    /// 호출자가 기한 넘게 기다린 세그먼트를 NACK 주기와 관계없이 바로 재전송 요청
+    ///
+    /// 청크가 하나도 오지 않은 세그먼트는 청크 수를 모르므로 세그먼트 전체를 요청
+    /// (`NackMessage::whole_segment`)
+    async fn nack_segment(&mut self, segment_id: SegmentId) {
+        if self.completed.contains(segment_id) {
+            return;
+        }
+        let nack = match self.segments.get_mut(&segment_id) {
+            Some(state) => {
+                state.last_nack_time = Instant::now();
+                // 송신자의 누락 집합이 바뀌므로 다음 델타 NACK은 전체 보고
+                state.reported = None;
+                let missing: Vec<ChunkId> = state
+                    .segment
+                    .missing_ranges()
+                    .flat_map(|(start, end)| start..end)
+                    .collect();
+                if missing.is_empty() {
+                    return;
+                }
+                NackMessage::new(segment_id, missing, state.segment.receive_ratio() as f32, 0)
+                    .with_file_id(state.file_id)
+            }
+            None => NackMessage::whole_segment(segment_id),
+        }
+        .with_connection_id(self.connection_id);
+        for fragment in nack.fragments(self.config.max_datagram_size) {
+            if let Err(e) = self.send_nack_datagram(&fragment.to_bytes()).await {
+                warn!(segment_id, "대기 세그먼트 NACK 전송 실패: {}", e);
+                return;
+            }
+        }
+        self.stats.total_nacks += 1;
+        debug!(segment_id, "기한을 넘긴 세그먼트 재전송 요청");
+    }
+
+    /// 송신자에게 재전송이 더 이상 필요 없는 청크 통보
+    async fn withdraw_nack(&mut self, segment_id: SegmentId, file_id: FileId, chunk_id: ChunkId) {
+        let cancel = NackCancelMessage::new(segment_id, file_id, vec![chunk_id]);
+        match self.send_nack_datagram(&cancel.to_bytes()).await {
+            Ok(_) => {
+                if self.nack_log.sample() {
+                    debug!(segment_id, chunk_id, "중복 사본 도착으로 NACK 철회");
+                }
+            }
+            Err(e) => warn!(segment_id, "NACK 철회 전송 실패: {}", e),
+        }
+    }
+
+    /// 조립된 세그먼트를 매니페스트의 세그먼트 다이제스트와 비교
+    ///
+    /// 청크 CRC를 통과했어도 내용이 다르면(CRC 충돌, 잘못된 오프셋) 전달하지 않고 버퍼를
+    /// 비워 세그먼트 전체를 다시 요청. `segment_digests`를 켰으면 매니페스트보다 먼저 완료한
+    /// 파일 세그먼트는 기대값을 알 때까지 보관 (`release_held_segments`). 매니페스트가 온 뒤에도
+    /// 다이제스트가 없는 세그먼트는 그대로 통과
+    async fn verify_segment_digest(&mut self, segment_id: SegmentId) -> bool {
+        let Some(state) = self.segments.get_mut(&segment_id) else {
+            return true;
+        };
+        if self.config.segment_digests
+            && state.file_id != MANIFEST_FILE_ID
+            && !self.manifest_received.load(Ordering::SeqCst)
+        {
+            if self.held.insert(segment_id) {
+                debug!(segment_id, "매니페스트 도착 전 완료: 다이제스트 확인까지 보관");
+            }
+            return false;
+        }
+        let Some(expected) = self.segment_digests.lock().get(&segment_id).copied() else {
+            return true;
+        };
+        if manifest::digest(state.segment.data()) == expected {
+            self.segment_digests.lock().remove(&segment_id);
+            return true;
         }
 
-        // 세그먼트 완료 처리
-        if state.segment.is_complete() {
+        warn!(segment_id, "세그먼트 다이제스트 불일치: 세그먼트 전체 재요청");
+        state.segment.reset();
+        state.restart();
+        self.stats.digest_mismatches += 1;
+        self.nack_segment(segment_id).await;
+        false
+    }
+
+    /// 매니페스트가 도착했으면 보관한 세그먼트를 다이제스트로 확인해 전달
+    async fn release_held_segments(&mut self) {
+        if self.held.is_empty() || !self.manifest_received.load(Ordering::SeqCst) {
+            return;
+        }
+        for segment_id in std::mem::take(&mut self.held) {
             self.handle_segment_complete(segment_id).await;
         }
     }
 
+    /// 균일 세그먼트 표시 처리 (인증은 수신 태스크에서 확인)
+    ///
+    /// 세그먼트 전체를 `fill`로 채워 청크로 받은 세그먼트처럼 완료. 이미 완료한 세그먼트의 표시는
+    /// 완료 보고가 유실된 것이므로 보고만 다시 보냄
+    #[tracing::instrument(
+        level = "debug",
+        name = "segment",
+        skip_all,
+        fields(segment_id = marker.segment_id)
+    )]
+    async fn handle_sparse(&mut self, marker: SparseSegmentMessage) {
+        let segment_id = marker.segment_id;
+        if self.completed.contains(segment_id) {
+            let complete_msg = SegmentCompleteMessage {
+                segment_id,
+                file_id: marker.file_id,
+                total_chunks_received: 0,
+                duplicates_received: 0,
+                elapsed_ms: 0,
+                connection_id: self.connection_id,
+            };
+            let _ = self
+                .socket
+                .send_to(&complete_msg.to_bytes(), self.server_addr)
+                .await;
+            return;
+        }
+
+        let max_segment = self.config.max_supported_segment_size;
+        if max_segment > 0 && marker.segment_size as usize > max_segment {
+            debug!(
+                "수신 한도 초과 균일 세그먼트 무시: {} bytes > {} bytes",
+                marker.segment_size, max_segment
+            );
+            return;
+        }
+        // 송신자는 세그먼트를 청크나 표시 중 하나로만 보내므로, 청크로 받고 있는 세그먼트의
+        // 표시는 오래됐거나 위조된 것: 받은 청크를 덮어쓰지 않도록 버림
+        if self.segments.contains_key(&segment_id) {
+            debug!("청크로 받는 중인 세그먼트의 균일 표시 무시");
+            return;
+        }
+        if !self.waiters.contains_key(&segment_id) && self.delivery_window_full() {
+            self.stats.deferred_admissions += 1;
+            return;
+        }
+
+        self.stats.total_segments += 1;
+        let segment = Segment::new_for_receive_pooled(
+            segment_id,
+            marker.segment_size as usize,
+            1,
+            &self.pool,
+        );
+        let state = self
+            .segments
+            .entry(segment_id)
+            .or_insert(SegmentState::new(segment, marker.file_id));
+        state.segment.fill_uniform(marker.fill);
+        state.boundary |= marker.boundary;
+        self.stats.sparse_segments += 1;
+        self.handle_segment_complete(segment_id).await;
+    }
+
     async fn handle_segment_complete(&mut self, segment_id: SegmentId) {
+        if !self.verify_segment_digest(segment_id).await {
+            return;
+        }
         if let Some(state) = self.segments.remove(&segment_id) {
+            self.completed.insert(segment_id);
+            // 소비자가 데이터를 받기 전에 경계를 알 수 있도록 먼저 기록
+            if state.boundary {
+                self.boundaries.lock().insert(segment_id);
+            }
+            if state.end_of_stream {
+                self.end_of_stream.store(segment_id, Ordering::SeqCst);
+            }
+
             let elapsed = state.segment.created_at.elapsed();
             let data = state.segment.into_data();
+            self.pool.track_delivered(&data);
+            if segment_id == MANIFEST_SEGMENT_ID {
+                if let Ok(manifest) = Manifest::from_bytes(&data) {
+                    self.segment_digests.lock().extend(manifest.segment_digests());
+                }
+                self.manifest_received.store(true, Ordering::SeqCst);
+            }
 
-            debug!(
-                "세그먼트 {} 완료: {} bytes, {:.2}ms",
-                segment_id,
-                data.len(),
-                elapsed.as_secs_f64() * 1000.0
-            );
+            if self.chunk_log.sample() {
+                debug!(
+                    "세그먼트 {} 완료: {} bytes, {:.2}ms",
+                    segment_id,
+                    data.len(),
+                    elapsed.as_secs_f64() * 1000.0
+                );
+            }
 
             // 완료 메시지 전송
             let complete_msg = SegmentCompleteMessage {
                 segment_id,
-                total_chunks_received: 0,
-                duplicates_received: 0,
+                file_id: state.file_id,
+                total_chunks_received: state.chunks_received,
+                duplicates_received: state.duplicates,
                 elapsed_ms: elapsed.as_millis() as u64,
+                connection_id: self.connection_id,
             };
             let _ = self
                 .socket
                 .send_to(&complete_msg.to_bytes(), self.server_addr)
                 .await;
 
-            // 완료 채널로 전송
-            let _ = self.completed_tx.send((segment_id, data)).await;
+            // 대기 중인 호출자에게 먼저 전달 (완료 채널에도 그대로 보냄)
+            for waiter in self.waiters.remove(&segment_id).unwrap_or_default() {
+                let _ = waiter.send(Ok(data.clone()));
+            }
+
+            // 완료 채널로 전송 (소비자가 느려도 처리 태스크는 기다리지 않음)
+            self.undelivered.push_back((segment_id, data));
+            self.deliver_undelivered();
 
-            self.stats.completed_segments += 1;
+            self.stats.record_segment_completed();
             self.completed_count += 1;
         }
     }
 
+    /// 보관한 완료 세그먼트를 완료 채널에 자리가 나는 만큼 순서대로 넘김
+    /// (소비자가 채널을 버렸으면 모두 버림)
+    fn deliver_undelivered(&mut self) {
+        while let Some(item) = self.undelivered.pop_front() {
+            match self.completed_tx.try_send(item) {
+                Ok(()) => {}
+                Err(TrySendError::Full(item)) => {
+                    self.undelivered.push_front(item);
+                    break;
+                }
+                Err(TrySendError::Closed(item)) => {
+                    self.undelivered.push_front(item);
+                    self.discard_undelivered("소비자가 완료 채널을 닫음");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// 넘기지 못한 완료 세그먼트를 버리고 `dropped_deliveries`에 기록
+    fn discard_undelivered(&mut self, reason: &str) {
+        let dropped = self.undelivered.len();
+        if dropped == 0 {
+            return;
+        }
+        let first = self.undelivered.front().map(|(id, _)| *id);
+        self.undelivered.clear();
+        self.stats.dropped_deliveries += dropped as u64;
+        warn!(dropped, first_segment = first, "완료 세그먼트 전달 못하고 버림: {}", reason);
+    }
+
+    /// 넘기지 못한 완료 세그먼트가 `delivery_window_segments`에 이름
+    fn delivery_window_full(&self) -> bool {
+        let window = self.config.delivery_window_segments;
+        window > 0 && self.undelivered.len() >= window
+    }
+
+    /// 빈 구간을 손실로 판단하기 전 기다릴 시간.
+    /// 순서 역전이 없었으면 0 (기존 nack_timeout만 적용)
+    fn reorder_window(&self, nack_timeout: Duration) -> Duration {
+        let delay = self
+            .stats
+            .nic_stats
+            .iter()
+            .map(|nic| nic.reorder_delay())
+            .max()
+            .unwrap_or(Duration::ZERO);
+        if delay.is_zero() {
+            return Duration::ZERO;
+        }
+        delay
+            .mul_f64(REORDER_WINDOW_GAIN)
+            .max(nack_timeout)
+            .min(nack_timeout * MAX_REORDER_WINDOW_FACTOR)
+    }
+
     async fn send_nacks(&mut self) {
+        self.release_held_segments().await;
         let now = Instant::now();
+        if self.pause.is_active() {
+            self.paused_since.get_or_insert(now);
+            return;
+        }
+        if let Some(since) = self.paused_since.take() {
+            self.defer_timers(now - since);
+        }
         let nack_timeout = Duration::from_millis(self.config.nack_timeout_ms);
+        let reorder_window = self.reorder_window(nack_timeout);
 
         // NACK 전송할 세그먼트 수집
-        let mut nacks_to_send: Vec<(SegmentId, Vec<ChunkId>, f32)> = Vec::new();
+        let mut nacks_to_send: Vec<(SegmentId, FileId, Vec<ChunkId>, f32)> = Vec::new();
+        let mut undeliverable: Vec<SegmentId> = Vec::new();
+        let max_stalled = self.config.max_stalled_nacks;
+        let stall_limit = Duration::from_micros(self.rtt_us.load(Ordering::Relaxed)) * max_stalled;
+        let soft_deadline = Duration::from_millis(self.config.assembly_soft_deadline_ms);
+        let fec_active = self.fec_active.load(Ordering::Relaxed);
+
+        for (&segment_id, state) in self.segments.iter_mut() {
+            // 소프트 마감을 넘긴 세그먼트는 한 번만 격상으로 표시
+            if !state.escalated
+                && !soft_deadline.is_zero()
+                && state.segment.created_at.elapsed() >= soft_deadline
+            {
+                state.escalated = true;
+                self.stats.escalated_segments += 1;
+                debug!(
+                    segment_id,
+                    "조립 소프트 마감 초과: NACK 격상 ({:.1}% 수신)",
+                    state.segment.receive_ratio() * 100.0
+                );
+            }
 
-        for (&segment_id, state) in &self.segments {
             // 타임아웃 확인
             if now.duration_since(state.last_nack_time) < nack_timeout {
                 continue;
             }
 
-            let missing = state.segment.missing_chunk_ids();
+            // 호출자가 기다리거나 격상한 세그먼트는 보류 없이 바로 요청
+            let awaited = self.waiters.contains_key(&segment_id) || state.escalated;
+
+            // 순서 역전이 관측됐으면 빈 구간이 그만큼 오래 유지될 때까지 보류
+            if let Some(since) = state.gap_since {
+                if !awaited && now.duration_since(since) < reorder_window {
+                    continue;
+                }
+            }
+
+            // 받은 패리티로 복원할 수 있는 만큼은 빼고 부족분만 요청
+            // (부족분 0인데 미완료거나 격상한 세그먼트면 전부 요청)
+            let deficit = match state.segment.fec_deficit() {
+                0 => usize::MAX,
+                _ if state.escalated => usize::MAX,
+                deficit => deficit,
+            };
+            let mut missing: Vec<ChunkId> = state
+                .segment
+                .missing_ranges()
+                .flat_map(|(start, end)| start..end)
+                .take(deficit)
+                .collect();
             if missing.is_empty() {
                 continue;
             }
 
+            // 격상한 FEC 세그먼트는 받지 못한 패리티도 요청 (샤드 수를 모르면 누락 수만큼)
+            if state.escalated && fec_active {
+                let total = state.segment.total_chunks;
+                let parity = state.segment.missing_parity().unwrap_or_else(|| {
+                    let room = fec::MAX_SHARDS.saturating_sub(total as usize);
+                    (total..total + missing.len().min(room) as ChunkId).collect()
+                });
+                missing.extend(parity);
+            }
+
+            // 재전송을 여러 번 요청해도 청크가 하나도 오지 않으면 포기. NACK 주기가 RTT보다
+            // 짧으면 재전송이 돌아오기 전에 횟수가 차므로 NACK마다 RTT 하나씩은 기다림
+            if max_stalled > 0
+                && state.stalled_nacks >= max_stalled
+                && now.duration_since(state.last_progress) >= stall_limit
+            {
+                undeliverable.push(segment_id);
+                continue;
+            }
+
             // 수신률이 너무 낮으면 아직 전송 중
-            if state.segment.receive_ratio() < 0.5
+            if !awaited
+                && state.segment.receive_ratio() < 0.5
                 && state.segment.created_at.elapsed().as_millis() < 100
             {
                 continue;
             }
 
-            nacks_to_send.push((segment_id, missing, state.segment.receive_ratio() as f32));
+            nacks_to_send.push((
+                segment_id,
+                state.file_id,
+                missing,
+                state.segment.receive_ratio() as f32,
+            ));
+        }
+
+        // 기다리는 세그먼트, 격상한 세그먼트의 NACK을 먼저 전송
+        nacks_to_send.sort_by_key(|&(segment_id, ..)| {
+            let escalated = self.segments.get(&segment_id).is_some_and(|state| state.escalated);
+            !(self.waiters.contains_key(&segment_id) || escalated)
+        });
+
+        if !nacks_to_send.is_empty() && !self.uplink_responsive() {
+            return;
         }
 
         // NACK 전송
-        for (segment_id, missing, receive_ratio) in nacks_to_send {
-            let nack = NackMessage::new(segment_id, missing.clone(), receive_ratio, 0);
+        for (segment_id, file_id, missing, receive_ratio) in nacks_to_send {
+            let datagrams = self.nack_datagrams(segment_id, file_id, &missing, receive_ratio);
 
-            if let Err(e) = self.socket.send_to(&nack.to_bytes(), self.server_addr).await {
-                warn!("NACK 전송 실패: {}", e);
+            let mut failed = false;
+            for datagram in datagrams {
+                if let Err(e) = self.send_nack_datagram(&datagram).await {
+                    warn!(segment_id, "NACK 전송 실패: {}", e);
+                    failed = true;
+                    break;
+                }
+            }
+            if failed {
+                // 송신자가 받았는지 모르므로 다음 델타 NACK은 전체 보고
+                if let Some(state) = self.segments.get_mut(&segment_id) {
+                    state.reported = None;
+                }
                 continue;
             }
 
-            debug!(
-                "NACK 전송: segment={}, missing={} chunks",
-                segment_id,
-                missing.len()
-            );
+            if self.nack_log.sample() {
+                debug!(segment_id, missing = missing.len(), "NACK 전송");
+            }
 
             // NACK 시간 업데이트
             if let Some(state) = self.segments.get_mut(&segment_id) {
                 state.last_nack_time = now;
+                state.nack_sent = true;
+                state.stalled_nacks += 1;
+                state.pending_nack = missing.iter().copied().collect();
             }
 
             // 통계 업데이트
@@ -202,33 +1160,255 @@ impl ReceiverInner {
             self.path_manager.record_loss(0, missing.len() as u64);
         }
 
-        // 타임아웃 세그먼트 정리
-        let segment_timeout = Duration::from_millis(self.config.segment_timeout_ms);
-        self.segments.retain(|segment_id, state| {
-            if state.segment.created_at.elapsed() > segment_timeout {
-                warn!(
-                    "세그먼트 {} 타임아웃: {:.1}% 수신",
-                    segment_id,
-                    state.segment.receive_ratio() * 100.0
-                );
-                false
-            } else {
-                true
+        // 타임아웃 세그먼트 정리 (조립 하드 마감이 있으면 더 이른 쪽)
+        let mut segment_timeout = Duration::from_millis(self.config.segment_timeout_ms);
+        if self.config.assembly_hard_deadline_ms > 0 {
+            segment_timeout =
+                segment_timeout.min(Duration::from_millis(self.config.assembly_hard_deadline_ms));
+        }
+        let expired: Vec<SegmentId> = self
+            .segments
+            .iter()
+            .filter(|(_, state)| state.segment.created_at.elapsed() > segment_timeout)
+            .map(|(&segment_id, _)| segment_id)
+            .collect();
+        for segment_id in expired {
+            self.fail_segment(segment_id, true);
+        }
+        for segment_id in undeliverable {
+            self.fail_segment(segment_id, false);
+        }
+    }
+
+    /// 정지한 시간만큼 NACK/순서 역전/세그먼트 타임아웃 기준 시각을 미룸
+    fn defer_timers(&mut self, paused_for: Duration) {
+        for state in self.segments.values_mut() {
+            state.segment.created_at += paused_for;
+            state.last_nack_time += paused_for;
+            if let Some(since) = state.gap_since.as_mut() {
+                *since += paused_for;
             }
-        });
+        }
+        debug!("정지 {:.1}s 후 재개: 타임아웃 연장", paused_for.as_secs_f64());
     }
 
-    fn get_stats(&self) -> TransferStats {
-        self.stats.clone()
+    /// 송신자가 재전송을 포기한 세그먼트 실패 처리 (다른 파일 범위의 통보는 무시)
+    fn handle_give_up(&mut self, segment_id: SegmentId, file_id: FileId) {
+        if self
+            .segments
+            .get(&segment_id)
+            .is_some_and(|state| state.file_id == file_id)
+        {
+            debug!(segment_id, "송신자 재전송 포기 통보");
+            self.fail_segment(segment_id, false);
+        }
     }
-}
+
+    /// 세그먼트를 영구 실패로 포기하고 나머지 전송 계속
+    ///
+    /// 늦게 도착한 청크가 세그먼트를 다시 만들지 않도록 완료 집합에도 넣고,
+    /// 기다리던 호출자에게는 만료(`SegmentTimeout`)인지 진전 없음(`SegmentUndeliverable`)인지 통보
+    fn fail_segment(&mut self, segment_id: SegmentId, timed_out: bool) {
+        let Some(state) = self.segments.remove(&segment_id) else {
+            return;
+        };
+        let reason = || match timed_out {
+            true => Error::SegmentTimeout { segment_id },
+            false => Error::SegmentUndeliverable { segment_id },
+        };
+        warn!(
+            segment_id,
+            "세그먼트 포기: {:.1}% 수신 ({})",
+            state.segment.receive_ratio() * 100.0,
+            reason()
+        );
+        let size = state.segment.total_size;
+        state.segment.recycle(&self.pool);
+        self.completed.insert(segment_id);
+        self.failed.push(segment_id, size);
+        let waiters = self.waiters.remove(&segment_id).unwrap_or_default();
+        for waiter in waiters {
+            let _ = waiter.send(Err(reason()));
+        }
+    }
+
+    /// 세그먼트 NACK 데이터그램 생성
+    ///
+    /// 델타 NACK을 쓰면 직전 보고 이후 바뀐 부분만 보내고 `nack_resync_interval`마다 전체 집합.
+    /// 전체 집합이 데이터그램 하나에 들어가지 않으면 분할된 일반 NACK으로 대신함
+    fn nack_datagrams(
+        &mut self,
+        segment_id: SegmentId,
+        file_id: FileId,
+        missing: &[ChunkId],
+        receive_ratio: f32,
+    ) -> Vec<Vec<u8>> {
+        let max_datagram_size = self.config.max_datagram_size;
+        let connection_id = self.connection_id;
+        // 누락 청크가 많으면 데이터그램 한도에 맞춰 여러 조각으로
+        let plain = || {
+            NackMessage::new(segment_id, missing.to_vec(), receive_ratio, 0)
+                .with_file_id(file_id)
+                .with_connection_id(connection_id)
+                .fragments(max_datagram_size)
+                .iter()
+                .map(NackMessage::to_bytes)
+                .collect()
+        };
+        if !self.config.delta_nacks || !self.delta_nacks.load(Ordering::Relaxed) {
+            return plain();
+        }
+        let Some(state) = self.segments.get_mut(&segment_id) else {
+            return plain();
+        };
+
+        let seq = state.nack_seq.wrapping_add(1);
+        let message = match &state.reported {
+            Some(reported) if state.nacks_since_full + 1 < self.config.nack_resync_interval => {
+                let current: HashSet<ChunkId> = missing.iter().copied().collect();
+                let added = missing
+                    .iter()
+                    .copied()
+                    .filter(|id| !reported.contains(id))
+                    .collect();
+                let mut removed: Vec<ChunkId> = reported.difference(&current).copied().collect();
+                removed.sort_unstable();
+                NackDeltaMessage::delta(segment_id, seq, state.nack_seq, added, removed, receive_ratio)
+            }
+            _ => NackDeltaMessage::full(segment_id, seq, missing.to_vec(), receive_ratio),
+        }
+        .with_file_id(file_id)
+        .with_connection_id(connection_id);
+
+        if message.encoded_len() > max_datagram_size {
+            state.reported = None;
+            return plain();
+        }
+        state.nacks_since_full = if message.full { 0 } else { state.nacks_since_full + 1 };
+        state.nack_seq = seq;
+        state.reported = Some(missing.iter().copied().collect());
+        vec![message.to_bytes()]
+    }
+
+    /// NACK 라운드마다 호출. 직전 라운드 이후 연결 전체에 청크가 하나도 오지 않은 라운드가
+    /// `uplink_silent_nack_rounds`번 이어지고 NACK의 응답이 돌아올 시간(RTT의 두 배)도
+    /// 지나면 다른 NIC로 NACK을 옮기고, 옮길 NIC가 없으면 업링크 단절로 표시하고 false
+    fn uplink_responsive(&mut self) -> bool {
+        let limit = self.config.uplink_silent_nack_rounds;
+        if limit == 0 {
+            return true;
+        }
+
+        let arrivals = self.chunk_arrivals.load(Ordering::Relaxed);
+        if self.nack_round_arrivals == Some(arrivals) {
+            self.silent_nack_rounds += 1;
+        } else {
+            self.silent_nack_rounds = 0;
+            self.last_answered_round = Instant::now();
+        }
+        self.nack_round_arrivals = Some(arrivals);
+        // RTT가 NACK 라운드 여러 번보다 긴 경로에서는 재전송이 돌아오기 전에 라운드 수가 참
+        let answer_time = Duration::from_micros(self.rtt_us.load(Ordering::Relaxed)) * 2;
+        if self.silent_nack_rounds < limit || self.last_answered_round.elapsed() < answer_time {
+            return true;
+        }
+        self.silent_nack_rounds = 0;
+        self.last_answered_round = Instant::now();
+
+        // 아직 시도하지 않은 NIC로 전환 (기본 소켓 → ID가 낮은 NIC부터)
+        let next = self.nack_nic.map_or(0, |id| id as u16 + 1);
+        if let Some(nic_id) = self
+            .path_manager
+            .nic_ids()
+            .into_iter()
+            .find(|&id| id as u16 >= next && self.path_manager.get_socket(id).is_some())
+        {
+            warn!(nic_id, "NACK에 응답이 없어 다른 NIC로 NACK 전송");
+            self.nack_nic = Some(nic_id);
+            return true;
+        }
+
+        warn!("NACK {}회에 응답 없음: 업링크 단절 의심", limit);
+        self.uplink_broken.store(true, Ordering::SeqCst);
+        false
+    }
+
+    async fn send_nack_datagram(&self, data: &[u8]) -> std::io::Result<usize> {
+        match self.nack_nic.and_then(|id| self.path_manager.get_socket(id)) {
+            Some(socket) => socket.send_to(data, self.server_addr).await,
+            None => self.socket.send_to(data, self.server_addr).await,
+        }
+    }
+
+    fn set_redundancy_mode(&mut self, mode: RedundancyMode) {
+        info!("손실 보정 모드 변경: {:?}", mode);
+        self.fec_active
+            .store(mode == RedundancyMode::Fec, Ordering::Relaxed);
+    }
+
+    fn get_stats(&self) -> TransferStats {
+        self.stats.clone()
+    }
+}
 
 /// 수신자 핸들 (외부에서 제어용)
 pub struct Receiver {
-    cmd_tx: mpsc::Sender<ReceiverCmd>,
-    stats: Arc<RwLock<TransferStats>>,
-    running: Arc<AtomicBool>,
+    cmd_tx: ShardQueues,
+    /// 완료 채널 (적재량 조회용, 채널을 붙잡지 않음)
+    completed_queue: mpsc::WeakSender<(SegmentId, Bytes)>,
+    /// 조립 샤드별 통계
+    stats: Vec<Arc<RwLock<TransferStats>>>,
+    /// 연결의 모든 태스크 종료 신호 (처리 태스크가 끝나도 취소됨)
+    cancel: CancellationToken,
+    /// 연결이 띄운 태스크 (정지 시 모두 끝날 때까지 대기)
+    tasks: TaskTracker,
     completed_count: Arc<AtomicU64>,
+    keepalives_sent: Arc<AtomicU64>,
+    foreign_packets: Arc<AtomicU64>,
+    /// 대역폭 프로브로 잰 전달 속도 (bytes/s, 0이면 측정 전)
+    probed_rate: Arc<AtomicU64>,
+    /// 자동 조정 중인 소켓 버퍼 크기 (0이면 자동 조정 꺼짐)
+    socket_buffer: Arc<AtomicUsize>,
+    /// 핸드셰이크 RTT 표본 검증 (시계 점프로 버린 표본 수)
+    rtt_filter: Arc<RttSampleFilter>,
+    /// 통과한 핸드셰이크 RTT (마이크로초, 0이면 측정 전, 경로 전환 시 다시 추정)
+    handshake_rtt_us: Arc<AtomicU64>,
+    /// 애플리케이션이 통보한 네트워크 전환 (수신 태스크가 처리하면 내림)
+    network_change_requested: Arc<AtomicBool>,
+    /// 송신자에게 알린 네트워크 전환 수 (수동 통보 + 자동 감지)
+    network_changes: Arc<AtomicU64>,
+    /// 서버가 InitAck에서 균일 세그먼트 표시를 보낼 수 있다고 알림
+    sparse_segments: Arc<AtomicBool>,
+    /// 균일 세그먼트 표시 인증 키
+    metadata_auth: Arc<Mutex<Option<MetadataAuth>>>,
+    /// 인증에 실패해 버린 균일 세그먼트 표시 수
+    rejected_sparse: Arc<AtomicU64>,
+    local_addr: SocketAddr,
+    pool: Arc<SegmentBufferPool>,
+    fec_active: Arc<AtomicBool>,
+    state: Arc<ConnectionStateCell>,
+    uplink_broken: Arc<AtomicBool>,
+    uplink_silent_nack_rounds: u32,
+    /// 송신자의 일시 정지
+    pause: Arc<PauseLease>,
+    /// NACK 주기 (세그먼트 수신 기한의 하한 기준)
+    nack_timeout: Duration,
+    /// `receive_file`이 기다리는 세그먼트를 다시 요청하는 횟수
+    receive_segment_retries: u32,
+    /// 영구 실패로 포기한 세그먼트
+    failed: Arc<FailedSegments>,
+    /// 메시지 경계 세그먼트 (`FileReceiver`가 세그먼트를 넘길 때 꺼내 감)
+    boundaries: Arc<Mutex<HashSet<SegmentId>>>,
+    /// 스트림 끝으로 표시된 세그먼트 (0이면 아직 없음)
+    end_of_stream: Arc<AtomicU64>,
+    /// 한도를 넘어 거부한 전송 크기 (0이면 거부 안 함)
+    refused_size: Arc<AtomicU64>,
+    /// 크기 정보가 맞지 않아 거부한 InitAck의 사유
+    rejected_ack: Arc<Mutex<Option<String>>>,
+    max_accept_file_size: u64,
+
+    /// 연결 스팬 (서버 주소, 세션 ID)
+    span: Span,
 }
 
 impl Receiver {
@@ -239,54 +1419,383 @@ impl Receiver {
         server_addr: SocketAddr,
         path_manager: Arc<PathManager>,
     ) -> Result<(Self, SegmentReceiver)> {
+        // 고정 포트가 설정되면 자동 할당(0) 대신 해당 포트로 바인딩
+        let bind_addr = if config.local_port != 0 && bind_addr.port() == 0 {
+            SocketAddr::new(bind_addr.ip(), config.local_port)
+        } else {
+            bind_addr
+        };
+
         // 소켓 생성
         let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
+        transport::apply_dscp(&socket, config.dscp)?;
+
+        Self::start_with_socket(config, socket, server_addr, path_manager).await
+    }
+
+    /// 서버의 전송 제안(Offer)을 기다렸다가 수락하고 시작 (역방향 핸드셰이크)
+    ///
+    /// 미리 알려진 주소로 바인딩해 대기하다가 Offer를 보낸 주소를 서버로 삼아 Init 전송.
+    /// `max_accept_file_size`를 넘는 제안은 무시하고 계속 대기
+    pub async fn accept_offer(
+        config: Config,
+        bind_addr: SocketAddr,
+        path_manager: Arc<PathManager>,
+    ) -> Result<(Self, SegmentReceiver)> {
+        let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
+        transport::apply_dscp(&socket, config.dscp)?;
+
+        let mut buf = vec![0u8; 2048];
+        let server_addr = loop {
+            let (len, addr) = socket.recv_from(&mut buf).await?;
+            let Some(offer) = OfferMessage::from_bytes(&buf[..len]) else {
+                continue;
+            };
+            let max = config.max_accept_file_size;
+            if max > 0 && offer.total_file_size > max {
+                warn!(
+                    "전송 제안 거부: {} bytes > 한도 {} bytes ({})",
+                    offer.total_file_size, max, addr
+                );
+                continue;
+            }
+            info!("전송 제안 수신: {} ({} bytes)", addr, offer.total_file_size);
+            break addr;
+        };
+
+        Self::start_with_socket(config, socket, server_addr, path_manager).await
+    }
+
+    /// 주어진 소켓으로 수신자 시작 (시뮬레이터, 다른 전송 계층 등)
+    pub async fn start_with_socket<S: DatagramSocket>(
+        config: Config,
+        socket: Arc<S>,
+        server_addr: SocketAddr,
+        path_manager: Arc<PathManager>,
+    ) -> Result<(Self, SegmentReceiver)> {
+        let local_addr = socket.local_addr()?;
 
         // 채널 생성
-        let (cmd_tx, mut cmd_rx) = mpsc::channel::<ReceiverCmd>(1000);
+        let shards = assembly_shards(&config);
+        let (queues, cmd_rxs): (Vec<_>, Vec<_>) =
+            (0..shards).map(|_| mpsc::channel::<ReceiverCmd>(1000)).unzip();
+        let cmd_tx = ShardQueues { queues };
         let (completed_tx, completed_rx) = mpsc::channel::<(SegmentId, Bytes)>(100);
+        let completed_queue = completed_tx.downgrade();
 
         // 공유 상태
-        let stats = Arc::new(RwLock::new(TransferStats::new(
-            path_manager.nic_count().max(1),
-            config.stats_window_size,
-        )));
-        let running = Arc::new(AtomicBool::new(true));
+        let stats: Vec<_> = (0..shards)
+            .map(|_| {
+                Arc::new(RwLock::new(TransferStats::new(
+                    path_manager.nic_id_bound().max(1),
+                    config.stats_window_size,
+                )))
+            })
+            .collect();
+        let cancel = CancellationToken::new();
+        let tasks = TaskTracker::new();
         let completed_count = Arc::new(AtomicU64::new(0));
+        let keepalives_sent = Arc::new(AtomicU64::new(0));
+        let foreign_packets = Arc::new(AtomicU64::new(0));
+        let probed_rate = Arc::new(AtomicU64::new(0));
+        let socket_buffer = Arc::new(AtomicUsize::new(0));
+        let rtt_filter = Arc::new(RttSampleFilter::new());
+        let handshake_rtt_us = Arc::new(AtomicU64::new(0));
+        let network_change_requested = Arc::new(AtomicBool::new(false));
+        let network_changes = Arc::new(AtomicU64::new(0));
+        let sparse_segments = Arc::new(AtomicBool::new(false));
+        let metadata_auth: Arc<Mutex<Option<MetadataAuth>>> = Arc::default();
+        let rejected_sparse = Arc::new(AtomicU64::new(0));
+        let pool = Arc::new(SegmentBufferPool::from_config(&config));
+        let fec_active = Arc::new(AtomicBool::new(false));
+        let state = Arc::new(ConnectionStateCell::default());
+
+        // 마지막 패킷 수신 시각 (started 기준 밀리초)
+        let started = Instant::now();
+        let last_recv_ms = Arc::new(AtomicU64::new(0));
 
         // 초기화 메시지 전송
-        let init = InitMessage::new(false, [0u8; 32]);
-        socket.send_to(&init.to_bytes(), server_addr).await?;
+        let mut init = InitMessage::new(false, [0u8; 32])
+            .with_supported_limits(&config)
+            .with_sparse_support()
+            .with_delta_nacks(config.delta_nacks);
+        let init_bytes = crate::message::bounded_datagram(
+            MessageType::Init,
+            init.to_bytes(),
+            config.max_datagram_size,
+        )?;
+        socket.send_to(&init_bytes, server_addr).await?;
+        state.transition(ConnectionState::Handshaking)?;
 
-        info!("SLS Receiver started on {}, server: {}", bind_addr, server_addr);
+        // 이 연결의 모든 태스크 로그를 묶는 스팬
+        let span = info_span!(
+            parent: None,
+            "connection",
+            peer = %server_addr,
+            local = %local_addr,
+            session = init.session_id
+        );
+        span.in_scope(|| {
+            info!(
+                "SLS Receiver started on {}, server: {}",
+                local_addr, server_addr
+            )
+        });
 
         // 내부 상태
-        let mut inner = ReceiverInner::new(
+        let nic_sockets = path_manager.get_all_sockets();
+        let mut base = ReceiverInner::new(
             config.clone(),
             server_addr,
             socket.clone(),
             completed_tx,
             path_manager,
+            pool.clone(),
+            fec_active.clone(),
         );
+        let connection_id = init.session_id;
+        base.connection_id = connection_id;
+        base.rtt_us = handshake_rtt_us.clone();
+        let uplink_broken = base.uplink_broken.clone();
+        let pause = base.pause.clone();
+        let peer_delta_nacks = base.delta_nacks.clone();
+        let failed = base.failed.clone();
+        let boundaries = base.boundaries.clone();
+        let end_of_stream = base.end_of_stream.clone();
+        let refused_size = Arc::new(AtomicU64::new(0));
+        let rejected_ack = Arc::new(Mutex::new(None));
 
         // 수신 태스크
         let socket_recv = socket.clone();
         let cmd_tx_recv = cmd_tx.clone();
-        let running_recv = running.clone();
+        let refused_recv = refused_size.clone();
+        let rejected_recv = rejected_ack.clone();
+        let max_accept_file_size = config.max_accept_file_size;
+        let last_recv_recv = last_recv_ms.clone();
+        let foreign_recv = foreign_packets.clone();
+        let state_recv = state.clone();
+        let pause_recv = pause.clone();
+        let probed_rate_recv = probed_rate.clone();
+        let socket_buffer_recv = socket_buffer.clone();
+        let rtt_filter_recv = rtt_filter.clone();
+        let handshake_rtt_recv = handshake_rtt_us.clone();
+        let network_change_recv = network_change_requested.clone();
+        let network_changes_recv = network_changes.clone();
+        let sparse_segments_recv = sparse_segments.clone();
+        let metadata_auth_recv = metadata_auth.clone();
+        let rejected_sparse_recv = rejected_sparse.clone();
+        let encryption_enabled = config.encryption_enabled;
+        let mut delay_steps = DelayStepDetector::from_config(&config);
+        // 도착 속도 × 핸드셰이크 RTT로 수신 버퍼 조정
+        let mut buffer_tuner = config.auto_buffer_tuning.then(|| BufferTuner::new(&config));
+        if let Some(tuner) = &buffer_tuner {
+            buffertune::apply(&*socket, tuner.current());
+            socket_buffer.store(tuner.current(), Ordering::Relaxed);
+        }
 
-        tokio::spawn(async move {
+        let recv_task = async move {
             let mut buf = vec![0u8; 65535];
+            let mut last_init = Instant::now();
+            let mut probe_meter = ProbeMeter::new();
+
+            loop {
+                let mut delay_step = None;
+
+                // InitAck 또는 첫 청크 수신 전까지 Init 재전송 (핸드셰이크 손실 대비)
+                let state = state_recv.get();
+                if state == ConnectionState::Handshaking
+                    && last_init.elapsed() >= INIT_RETRY_INTERVAL
+                {
+                    debug!("Init 재전송: {}", server_addr);
+                    // 새 타임스탬프로 어느 Init에 대한 응답인지 구분
+                    init.timestamp_us = rtt::wall_clock_now_us();
+                    if let Err(e) = socket_recv.send_to(&init.to_bytes(), server_addr).await {
+                        warn!("Init 재전송 실패: {}", e);
+                    }
+                    last_init = Instant::now();
+                }
 
-            while running_recv.load(Ordering::SeqCst) {
                 match tokio::time::timeout(
                     Duration::from_millis(10),
                     socket_recv.recv_from(&mut buf),
                 )
                 .await
                 {
-                    Ok(Ok((len, _addr))) => {
-                        if let Some(chunk) = Chunk::from_bytes(&buf[..len]) {
-                            let _ = cmd_tx_recv.send(ReceiverCmd::Chunk(chunk)).await;
+                    Ok(Ok((len, addr))) => {
+                        last_recv_recv.store(started.elapsed().as_millis() as u64, Ordering::Relaxed);
+                        if let Some(chunks) = decode_chunks(&buf[..len]) {
+                            // 첫 청크는 InitAck 손실 시에도 전송 시작으로 간주
+                            if state == ConnectionState::Handshaking {
+                                let _ = state_recv.transition(ConnectionState::Transferring);
+                            } else if !state.is_established() {
+                                debug!("{:?} 상태에서 청크 무시", state);
+                                continue;
+                            }
+                            if let Some(size) = buffer_tuner
+                                .as_mut()
+                                .and_then(|tuner| tuner.on_delivered(len, Instant::now()))
+                            {
+                                buffertune::apply(&*socket_recv, size);
+                                socket_buffer_recv.store(size, Ordering::Relaxed);
+                            }
+                            // 단방향 지연은 묶음에서 가장 최근에 찍힌 청크로 (재전송 청크는 다시 보낸 시각)
+                            let sent_us = chunks.iter().map(|c| c.header.timestamp_us).max();
+                            if let (Some(detector), Some(sent_us)) =
+                                (delay_steps.as_mut(), sent_us.filter(|&us| us > 0))
+                            {
+                                let delay_us = rtt::wall_clock_now_us() as i64 - sent_us as i64;
+                                delay_step = detector.on_sample(delay_us, Instant::now());
+                            }
+                            // 청크가 다시 오면 Resume을 받지 못했어도 재개
+                            pause_recv.release();
+                            for chunk in chunks {
+                                let _ = cmd_tx_recv.send(ReceiverCmd::Chunk(chunk)).await;
+                            }
+                        } else if let Some(ack) = InitAckMessage::from_bytes(&buf[..len]) {
+                            // 믿을 수 없는 서버가 알린 크기가 한도를 넘거나 서로 맞지 않으면 받기 전에 종료
+                            let too_large =
+                                max_accept_file_size > 0 && ack.total_file_size > max_accept_file_size;
+                            let inconsistent = match ack.validate().and_then(|()| {
+                                ack.validate_limits(
+                                    init.max_supported_chunk_size,
+                                    init.max_supported_segment_size,
+                                )
+                            }) {
+                                Err(Error::InvalidInitAck(reason)) => Some(reason),
+                                _ => None,
+                            };
+                            if too_large || inconsistent.is_some() {
+                                if let Some(reason) = inconsistent {
+                                    warn!("전송 거부: 잘못된 InitAck ({})", reason);
+                                    *rejected_recv.lock() = Some(reason);
+                                } else {
+                                    warn!(
+                                        "전송 거부: 서버가 알린 크기 {} bytes > 한도 {} bytes",
+                                        ack.total_file_size, max_accept_file_size
+                                    );
+                                    refused_recv.store(ack.total_file_size, Ordering::SeqCst);
+                                }
+                                let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+                                let _ = socket_recv.send_to(&close, server_addr).await;
+                                let _ = cmd_tx_recv.send(ReceiverCmd::Stop).await;
+                                break;
+                            }
+                            sparse_segments_recv.store(ack.sparse_segments, Ordering::Relaxed);
+                            peer_delta_nacks.store(ack.delta_nacks, Ordering::Relaxed);
+                            if state == ConnectionState::Handshaking {
+                                // 마지막 Init의 응답이면 단조 시계 왕복, 이전 Init의 응답이면
+                                // 에코된 벽시계 타임스탬프 차이 (시계 점프 검사)
+                                let sample = if ack.client_timestamp_us == 0
+                                    || ack.client_timestamp_us == init.timestamp_us
+                                {
+                                    rtt::check_rtt(last_init.elapsed())
+                                } else {
+                                    rtt::wall_clock_rtt(
+                                        ack.client_timestamp_us,
+                                        rtt::wall_clock_now_us(),
+                                    )
+                                };
+                                if let Some(rtt) = rtt_filter_recv.accept(sample) {
+                                    handshake_rtt_recv
+                                        .store((rtt.as_micros() as u64).max(1), Ordering::Relaxed);
+                                    if let Some(tuner) = buffer_tuner.as_mut() {
+                                        tuner.on_rtt_sample(rtt);
+                                    }
+                                }
+                                let _ = state_recv.transition(ConnectionState::Transferring);
+                            }
+                        } else if let Some(msg) = ModeChangeMessage::from_bytes(&buf[..len]) {
+                            if state != ConnectionState::Transferring {
+                                debug!("{:?} 상태에서 ModeChange 무시", state);
+                                continue;
+                            }
+                            let _ = cmd_tx_recv.send(ReceiverCmd::ModeChange(msg.mode)).await;
+                        } else if let Some(msg) = GiveUpMessage::from_bytes(&buf[..len]) {
+                            if !state.is_established() || msg.connection_id != connection_id {
+                                debug!("{:?} 상태에서 GiveUp 무시 (연결 {})", state, msg.connection_id);
+                                continue;
+                            }
+                            let give_up = ReceiverCmd::GiveUp(msg.segment_id, msg.file_id);
+                            let _ = cmd_tx_recv.send(give_up).await;
+                        } else if let Some(marker) =
+                            SparseSegmentMessage::from_bytes(&buf[..len])
+                        {
+                            if !state.is_established() || marker.connection_id != connection_id {
+                                debug!(
+                                    "{:?} 상태에서 균일 세그먼트 표시 무시 (연결 {})",
+                                    state, marker.connection_id
+                                );
+                                continue;
+                            }
+                            // 암호화 중에는 인증된 표시만 (키를 지정했으면 암호화와 무관하게 확인)
+                            let authentic = match &*metadata_auth_recv.lock() {
+                                Some(auth) => marker.verify(auth),
+                                None => !encryption_enabled,
+                            };
+                            if !authentic {
+                                rejected_sparse_recv.fetch_add(1, Ordering::Relaxed);
+                                warn!(
+                                    segment_id = marker.segment_id,
+                                    "인증되지 않은 균일 세그먼트 표시 무시"
+                                );
+                                continue;
+                            }
+                            let _ = cmd_tx_recv.send(ReceiverCmd::Sparse(marker)).await;
+                        } else if let Some(probe) = ProbeMessage::from_bytes(&buf[..len]) {
+                            if state.is_established() || state == ConnectionState::Handshaking {
+                                probe_meter.on_probe(&probe, len, Instant::now());
+                            }
+                        } else if let Some(challenge) =
+                            PathChallengeMessage::from_bytes(&buf[..len])
+                        {
+                            // 주소가 바뀐 뒤 서버가 새 주소로의 도달성을 확인
+                            if !state.is_established() || challenge.connection_id != connection_id {
+                                continue;
+                            }
+                            // 키가 있으면 태그를 붙임 (서버는 태그가 맞는 응답으로만 연결을 옮김)
+                            let mut response = PathResponseMessage::answer(&challenge);
+                            if let Some(auth) = &*metadata_auth_recv.lock() {
+                                response = response.authenticated(auth);
+                            }
+                            match socket_recv.send_to(&response.to_bytes(), server_addr).await {
+                                Ok(_) => debug!("주소 이전 확인 응답: token={:x}", challenge.token),
+                                Err(e) => warn!("주소 이전 확인 응답 실패: {}", e),
+                            }
+                        } else if let Some(msg) = PauseMessage::from_bytes(&buf[..len]) {
+                            // 서버가 아닌 곳에서 온 통보나 다른 연결의 통보로는 NACK을 멈추지 않음
+                            if !state.is_established()
+                                || addr != server_addr
+                                || msg.connection_id != connection_id
+                            {
+                                debug!("{:?} 상태에서 {:?} 무시 ({})", state, msg.msg_type(), addr);
+                                continue;
+                            }
+                            if msg.paused {
+                                pause_recv.extend();
+                            } else {
+                                pause_recv.release();
+                            }
+                        } else if let Some(cookie) = CookieMessage::from_bytes(&buf[..len]) {
+                            if state != ConnectionState::Handshaking {
+                                debug!("{:?} 상태에서 쿠키 무시", state);
+                                continue;
+                            }
+
+                            // 서버 쿠키를 담아 Init 재전송
+                            init.cookie = cookie.cookie;
+                            init.timestamp_us = rtt::wall_clock_now_us();
+                            if let Err(e) = socket_recv.send_to(&init.to_bytes(), server_addr).await {
+                                warn!("쿠키 Init 전송 실패: {}", e);
+                            }
+                            last_init = Instant::now();
+                        } else if !Chunk::has_magic(&buf[..len])
+                            && !ChunkBatch::has_magic(&buf[..len])
+                            && MessageHeader::from_bytes(&buf[..len])
+                                .is_none_or(|h| h.magic != MAGIC_NUMBER)
+                        {
+                            // 공유 포트의 다른 송신자/스캐너 패킷
+                            foreign_recv.fetch_add(1, Ordering::Relaxed);
+                            debug!("외부 패킷 무시: {} bytes from {}", len, addr);
                         }
                     }
                     Ok(Err(e)) => {
@@ -296,67 +1805,308 @@ impl Receiver {
                         // 타임아웃, 계속
                     }
                 }
+
+                // 네트워크 전환: 수신측 추정치를 버리고 송신자에게 재시작 요청
+                let requested = network_change_recv.swap(false, Ordering::AcqRel);
+                if (requested || delay_step.is_some()) && state_recv.get().is_established() {
+                    probe_meter = ProbeMeter::new();
+                    probed_rate_recv.store(0, Ordering::Relaxed);
+                    if requested {
+                        if let Some(detector) = delay_steps.as_mut() {
+                            detector.reset();
+                        }
+                    }
+                    // 편도 지연 변화의 두 배만큼 RTT가 바뀐 것으로 추정 (수동 통보는 다음 표본 대기)
+                    let rtt_us = match (delay_step, handshake_rtt_recv.load(Ordering::Relaxed)) {
+                        (Some(step), rtt_us) if rtt_us > 0 => {
+                            let rtt_us = (rtt_us as i64 + 2 * step).max(1) as u64;
+                            handshake_rtt_recv.store(rtt_us, Ordering::Relaxed);
+                            if let Some(tuner) = buffer_tuner.as_mut() {
+                                tuner.on_rtt_sample(Duration::from_micros(rtt_us));
+                            }
+                            rtt_us
+                        }
+                        _ => 0,
+                    };
+                    network_changes_recv.fetch_add(1, Ordering::Relaxed);
+                    info!(
+                        step_us = delay_step,
+                        "네트워크 전환{}, 송신자 추정치 재시작 요청",
+                        if requested { " 통보" } else { " 감지" }
+                    );
+                    let msg = NetworkChangeMessage::new(connection_id).with_rtt_us(rtt_us);
+                    if let Err(e) = socket_recv.send_to(&msg.to_bytes(), server_addr).await {
+                        warn!("네트워크 전환 통보 실패: {}", e);
+                    }
+                }
+
+                // 버스트를 다 받았거나 남은 프로브가 유실되었으면 한 번 보고
+                let probe_settled = probe_meter.is_complete()
+                    || probe_meter
+                        .last_arrival()
+                        .is_some_and(|at| at.elapsed() >= PROBE_IDLE_TIMEOUT);
+                if probe_settled {
+                    if let Some(report) = probe_meter.take_report() {
+                        probed_rate_recv.store(report.rate, Ordering::Relaxed);
+                        if let Some(tuner) = buffer_tuner.as_mut() {
+                            tuner.on_rate_estimate(report.rate as f64);
+                        }
+                        debug!(
+                            "대역폭 프로브 {}개 수신: {} bytes/s",
+                            report.received, report.rate
+                        );
+                        if let Err(e) = socket_recv.send_to(&report.to_bytes(), server_addr).await {
+                            warn!("프로브 보고 전송 실패: {}", e);
+                        }
+                    }
+                }
             }
-        });
+        };
+        spawn_cancellable(&tasks, &cancel, &span, recv_task);
+
+        // NIC 소켓 수신 태스크 (NACK을 다른 NIC로 보내면 재전송도 그 NIC로 도착)
+        for nic_socket in nic_sockets {
+            let cmd_tx_nic = cmd_tx.clone();
+            let nic_task = async move {
+                let mut buf = vec![0u8; 65535];
+                loop {
+                    let Ok((len, _)) = nic_socket.recv_from(&mut buf).await else {
+                        continue;
+                    };
+                    for chunk in decode_chunks(&buf[..len]).unwrap_or_default() {
+                        let _ = cmd_tx_nic.send(ReceiverCmd::Chunk(chunk)).await;
+                    }
+                }
+            };
+            spawn_cancellable(&tasks, &cancel, &span, nic_task);
+        }
 
         // NACK 타이머 태스크
         let cmd_tx_nack = cmd_tx.clone();
-        let running_nack = running.clone();
         let nack_timeout = config.nack_timeout_ms;
 
-        tokio::spawn(async move {
-            while running_nack.load(Ordering::SeqCst) {
+        let nack_task = async move {
+            loop {
                 tokio::time::sleep(Duration::from_millis(nack_timeout)).await;
                 let _ = cmd_tx_nack.send(ReceiverCmd::SendNacks).await;
             }
-        });
+        };
+        spawn_cancellable(&tasks, &cancel, &span, nack_task);
 
-        // 메인 처리 태스크
-        let stats_main = stats.clone();
-        let running_main = running.clone();
-        let completed_count_main = completed_count.clone();
+        // NAT keepalive 태스크 (유휴 구간에 Heartbeat 전송으로 매핑 유지)
+        if config.nat_keepalive_interval_ms > 0 {
+            let socket_keepalive = socket.clone();
+            let keepalives_sent_task = keepalives_sent.clone();
+            let last_recv_keepalive = last_recv_ms.clone();
+            let metadata_auth_keepalive = metadata_auth.clone();
+            let interval_ms = config.nat_keepalive_interval_ms;
 
-        tokio::spawn(async move {
-            while let Some(cmd) = cmd_rx.recv().await {
-                match cmd {
-                    ReceiverCmd::Chunk(chunk) => {
-                        inner.handle_chunk(chunk).await;
+            let keepalive_task = async move {
+                let mut sequence = 0u64;
+                let mut last_sent_ms = 0u64;
+
+                loop {
+                    tokio::time::sleep(Duration::from_millis((interval_ms / 4).max(1))).await;
+
+                    let now_ms = started.elapsed().as_millis() as u64;
+                    let last_activity = last_recv_keepalive.load(Ordering::Relaxed).max(last_sent_ms);
+                    if now_ms.saturating_sub(last_activity) < interval_ms {
+                        continue;
                     }
-                    ReceiverCmd::SendNacks => {
-                        inner.send_nacks().await;
+
+                    // 태그 붙은 keepalive는 주소가 바뀐 뒤 서버가 새 주소를 확인하는 계기가 됨
+                    let mut heartbeat =
+                        HeartbeatMessage::new(sequence).with_connection_id(connection_id);
+                    if let Some(auth) = &*metadata_auth_keepalive.lock() {
+                        heartbeat = heartbeat.authenticated(auth);
                     }
-                    ReceiverCmd::Stop => {
-                        break;
+                    match socket_keepalive.send_to(&heartbeat.to_bytes(), server_addr).await {
+                        Ok(_) => {
+                            debug!("NAT keepalive 전송: seq={}", sequence);
+                            sequence += 1;
+                            keepalives_sent_task.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => warn!("NAT keepalive 전송 실패: {}", e),
                     }
+                    last_sent_ms = now_ms;
                 }
+            };
+            spawn_cancellable(&tasks, &cancel, &span, keepalive_task);
+        }
 
-                // 통계 업데이트
-                *stats_main.write().await = inner.get_stats();
-                completed_count_main.store(inner.completed_count, Ordering::Relaxed);
-            }
+        // 조립 샤드 태스크 (샤드마다 자기 세그먼트만 조립, 마지막으로 끝난 샤드가 연결을 닫음)
+        let live_shards = Arc::new(AtomicUsize::new(shards));
+        let cmd_queues = cmd_tx.downgrade();
+        for (index, (mut cmd_rx, cmd_queue)) in cmd_rxs.into_iter().zip(cmd_queues).enumerate() {
+            let mut inner = base.shard(index, shards);
+            let stats_main = stats[index].clone();
+            let cancel_main = cancel.clone();
+            let completed_count_main = completed_count.clone();
+            let state_main = state.clone();
+            let live_main = live_shards.clone();
+            // 완료 채널 적재량은 첫 샤드만 보고 (샤드 통계를 합칠 때 한 번만 나오도록)
+            let completed_queue_main = (index == 0).then(|| completed_queue.clone());
+            // 보관한 완료 세그먼트가 있으면 완료 채널에 자리가 날 때 깨어남
+            let delivery = inner.completed_tx.clone();
 
-            running_main.store(false, Ordering::SeqCst);
-        });
+            let main_task = async move {
+                let mut published = 0;
+                loop {
+                    let cmd = tokio::select! {
+                        biased;
+                        _ = cancel_main.cancelled() => break,
+                        permit = delivery.reserve(), if !inner.undelivered.is_empty() => {
+                            drop(permit);
+                            inner.deliver_undelivered();
+                            None
+                        }
+                        cmd = cmd_rx.recv() => match cmd {
+                            Some(cmd) => Some(cmd),
+                            None => break,
+                        },
+                    };
+                    match cmd {
+                        None => {}
+                        Some(ReceiverCmd::Chunk(chunk)) => {
+                            inner.handle_chunk(chunk).await;
+                        }
+                        Some(ReceiverCmd::Sparse(marker)) => {
+                            inner.handle_sparse(marker).await;
+                        }
+                        Some(ReceiverCmd::ModeChange(mode)) => {
+                            inner.set_redundancy_mode(mode);
+                        }
+                        Some(ReceiverCmd::AwaitSegment(segment_id, waiter)) => {
+                            inner.await_segment(segment_id, waiter);
+                        }
+                        Some(ReceiverCmd::NackSegment(segment_id)) => {
+                            inner.nack_segment(segment_id).await;
+                        }
+                        Some(ReceiverCmd::GiveUp(segment_id, file_id)) => {
+                            inner.handle_give_up(segment_id, file_id);
+                        }
+                        Some(ReceiverCmd::SendNacks) => {
+                            inner.send_nacks().await;
+                            // 완료 채널을 닫아 수신 대기 중인 호출자에게 알림
+                            // (다른 샤드가 단절을 감지했으면 이 샤드도 이번 라운드에 끝남)
+                            if inner.uplink_broken.load(Ordering::SeqCst) {
+                                break;
+                            }
+                        }
+                        Some(ReceiverCmd::Stop) => {
+                            break;
+                        }
+                    }
+
+                    // 통계 업데이트
+                    let mut stats = inner.get_stats();
+                    let completed_depth = completed_queue_main
+                        .as_ref()
+                        .and_then(|queue| QueueDepth::of_weak("completed", 0, queue));
+                    stats.queue_depths = QueueDepth::of_weak("receiver_cmd", index, &cmd_queue)
+                        .into_iter()
+                        .chain(completed_depth)
+                        .collect();
+                    stats.queue_depths.push(QueueDepth {
+                        name: "completed_window",
+                        index,
+                        len: inner.undelivered.len(),
+                        capacity: inner.config.delivery_window_segments,
+                    });
+                    *stats_main.write().await = stats;
+                    let completed = inner.completed_count;
+                    completed_count_main.fetch_add(completed - published, Ordering::Relaxed);
+                    published = completed;
+                }
+
+                // 보관한 완료 세그먼트를 넘긴 뒤 채널을 닫음 (정지 요청이면 버림)
+                while !inner.undelivered.is_empty() {
+                    let permit = tokio::select! {
+                        biased;
+                        _ = cancel_main.cancelled() => break,
+                        permit = delivery.reserve() => match permit {
+                            Ok(permit) => permit,
+                            Err(_) => break,
+                        },
+                    };
+                    if let Some(item) = inner.undelivered.pop_front() {
+                        permit.send(item);
+                    }
+                }
+                inner.discard_undelivered("수신 종료");
+                stats_main.write().await.dropped_deliveries = inner.stats.dropped_deliveries;
+
+                // 마지막 샤드가 끝나면 나머지 태스크도 함께 종료
+                if live_main.fetch_sub(1, Ordering::SeqCst) == 1 {
+                    cancel_main.cancel();
+                    state_main.force(ConnectionState::Closed);
+                }
+            };
+            tasks.spawn(main_task.instrument(span.clone()));
+        }
+        // 샤드에 나눠 준 원본은 완료 채널을 붙잡지 않도록 버림
+        drop(base);
+        tasks.close();
 
         let receiver = Self {
             cmd_tx,
+            completed_queue,
             stats,
-            running,
+            cancel,
+            tasks,
             completed_count,
+            keepalives_sent,
+            foreign_packets,
+            probed_rate,
+            socket_buffer,
+            rtt_filter,
+            handshake_rtt_us,
+            network_change_requested,
+            network_changes,
+            sparse_segments,
+            metadata_auth,
+            rejected_sparse,
+            local_addr,
+            pool,
+            fec_active,
+            state,
+            uplink_broken,
+            pause,
+            uplink_silent_nack_rounds: config.uplink_silent_nack_rounds,
+            nack_timeout: Duration::from_millis(config.nack_timeout_ms),
+            receive_segment_retries: config.receive_segment_retries,
+            failed,
+            boundaries,
+            end_of_stream,
+            refused_size,
+            rejected_ack,
+            max_accept_file_size: config.max_accept_file_size,
+            span,
         };
 
         Ok((receiver, completed_rx))
     }
 
-    /// 정지
+    /// 연결 스팬. 애플리케이션 로그를 같은 연결로 묶을 때 사용
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// 정지. 연결의 모든 태스크를 취소하고 끝날 때까지 대기
     pub async fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
-        let _ = self.cmd_tx.send(ReceiverCmd::Stop).await;
+        // 처리 태스크가 끝나면 Closed
+        let _ = self.state.transition(ConnectionState::Draining);
+        self.cancel.cancel();
+        self.tasks.wait().await;
     }
 
-    /// 통계 반환
+    /// 통계 반환 (조립 샤드 통계의 합)
     pub async fn get_stats(&self) -> TransferStats {
-        self.stats.read().await.clone()
+        let mut merged = self.stats[0].read().await.clone();
+        for shard in &self.stats[1..] {
+            merged.merge(&*shard.read().await);
+        }
+        merged
     }
 
     /// 완료된 세그먼트 수
@@ -364,16 +2114,272 @@ impl Receiver {
         self.completed_count.load(Ordering::Relaxed)
     }
 
+    /// 현재 연결 상태
+    pub fn connection_state(&self) -> ConnectionState {
+        self.state.get()
+    }
+
     /// 실행 중 여부
     pub fn is_running(&self) -> bool {
-        self.running.load(Ordering::SeqCst)
+        !self.cancel.is_cancelled()
+    }
+
+    /// 아직 끝나지 않은 연결 태스크 수
+    pub fn active_tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// 전송한 NAT keepalive 수
+    pub fn keepalives_sent(&self) -> u64 {
+        self.keepalives_sent.load(Ordering::Relaxed)
+    }
+
+    /// 매직 넘버가 맞지 않아 무시한 패킷 수
+    pub fn foreign_packets(&self) -> u64 {
+        self.foreign_packets.load(Ordering::Relaxed)
+    }
+
+    /// 대역폭 프로브로 잰 전달 속도 (bytes/s, 프로브를 받지 못했거나 측정 불가면 None)
+    pub fn probed_rate(&self) -> Option<u64> {
+        Some(self.probed_rate.load(Ordering::Relaxed)).filter(|&rate| rate > 0)
+    }
+
+    /// 네트워크가 바뀌었음을 통보 (예: Wi-Fi → 셀룰러)
+    ///
+    /// 송신자가 혼잡/중복률 추정치를 보수적인 초기 상태로 되돌려 새 경로를 다시 잼.
+    /// 지연 변화로 자동 감지하려면 `Config::network_change_delay_step_ms`
+    pub fn notify_network_change(&self) {
+        self.network_change_requested.store(true, Ordering::Release);
+    }
+
+    /// 송신자에게 알린 네트워크 전환 수 (수동 통보 + 자동 감지)
+    pub fn network_changes(&self) -> u64 {
+        self.network_changes.load(Ordering::Relaxed)
+    }
+
+    /// 서버가 균일 세그먼트(0으로 채운 구멍 등)를 청크 대신 표시로 보낼 수 있다고 알렸는지
+    pub fn sparse_segments(&self) -> bool {
+        self.sparse_segments.load(Ordering::Relaxed)
+    }
+
+    /// 균일 세그먼트 표시 인증 키 지정 (보통 `CryptoSession::metadata_auth`)
+    ///
+    /// `Config::encryption_enabled`이면 키가 없거나 태그가 맞지 않는 표시는 버림.
+    /// keepalive와 주소 이전 확인 응답에도 이 키로 태그를 붙임 (키가 없으면 서버가 연결을
+    /// 새 주소로 옮기지 않음)
+    pub fn set_metadata_auth(&self, auth: MetadataAuth) {
+        *self.metadata_auth.lock() = Some(auth);
+    }
+
+    /// 인증에 실패해 버린 균일 세그먼트 표시 수
+    pub fn rejected_sparse_markers(&self) -> u64 {
+        self.rejected_sparse.load(Ordering::Relaxed)
+    }
+
+    /// 자동 조정 중인 소켓 수신 버퍼 크기 (자동 조정을 끄면 None)
+    pub fn socket_buffer_size(&self) -> Option<usize> {
+        Some(self.socket_buffer.load(Ordering::Relaxed)).filter(|&size| size > 0)
+    }
+
+    /// 핸드셰이크에서 잰 RTT (검증을 통과한 표본이 없으면 None)
+    pub fn handshake_rtt(&self) -> Option<Duration> {
+        Some(self.handshake_rtt_us.load(Ordering::Relaxed))
+            .filter(|&us| us > 0)
+            .map(Duration::from_micros)
+    }
+
+    /// 세그먼트 하나를 기다리는 기한: 핸드셰이크 RTT의 `SEGMENT_DEADLINE_RTTS`배
+    /// (RTT를 모르거나 짧으면 NACK 주기의 `SEGMENT_DEADLINE_NACKS`배, 최대 `SEGMENT_WAIT`)
+    pub fn segment_deadline(&self) -> Duration {
+        let floor = self.nack_timeout * SEGMENT_DEADLINE_NACKS;
+        self.handshake_rtt()
+            .map_or(floor, |rtt| rtt * SEGMENT_DEADLINE_RTTS)
+            .max(floor)
+            .min(SEGMENT_WAIT)
+    }
+
+    /// 진행을 반영한 세그먼트 기한: `segment_deadline`과 최근 세그먼트 도착 간격(`interval`)의
+    /// `SEGMENT_DEADLINE_INTERVALS`배 중 큰 값 (최대 `SEGMENT_WAIT`)
+    ///
+    /// 세그먼트가 원래 드문드문 오는 느린 전송은 도착 간격만큼 더 기다려 재요청을 남발하지 않음
+    pub fn segment_deadline_after(&self, interval: Option<Duration>) -> Duration {
+        let base = self.segment_deadline();
+        interval
+            .map_or(base, |interval| base.max(interval * SEGMENT_DEADLINE_INTERVALS))
+            .min(SEGMENT_WAIT)
+    }
+
+    /// 세그먼트 재전송을 NACK 주기를 기다리지 않고 바로 요청
+    pub async fn nack_segment(&self, segment_id: SegmentId) {
+        let _ = self.cmd_tx.send(ReceiverCmd::NackSegment(segment_id)).await;
+    }
+
+    /// 시계 점프 등으로 버린 RTT 표본 수
+    pub fn rejected_rtt_samples(&self) -> u64 {
+        self.rtt_filter.rejected()
+    }
+
+    /// 실제 바인딩된 로컬 주소
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// 서버가 알린 현재 손실 보정 모드
+    pub fn redundancy_mode(&self) -> RedundancyMode {
+        if self.fec_active.load(Ordering::Relaxed) {
+            RedundancyMode::Fec
+        } else {
+            RedundancyMode::Duplicate
+        }
+    }
+
+    /// 세그먼트 버퍼 풀 통계
+    pub fn pool_stats(&self) -> PoolStats {
+        self.pool.stats()
+    }
+
+    /// NACK에 응답이 없어 업링크 단절로 판단하고 수신을 멈췄는지
+    pub fn uplink_broken(&self) -> bool {
+        self.uplink_broken.load(Ordering::SeqCst)
+    }
+
+    /// 송신자가 전송을 일시 정지했는지 (`Sender::pause`, 정지 중에는 NACK하지 않음)
+    pub fn is_paused(&self) -> bool {
+        self.pause.is_active()
+    }
+
+    /// 송신자가 스트림 끝으로 표시한 세그먼트가 완료됐으면 그 ID (`Sender::send_end_of_stream`)
+    pub fn end_of_stream(&self) -> Option<SegmentId> {
+        Some(self.end_of_stream.load(Ordering::SeqCst)).filter(|&id| id != 0)
+    }
+
+    /// 재전송 요청에 진전이 없거나 만료되어 포기한 세그먼트 (포기한 순서대로)
+    pub fn failed_segments(&self) -> Vec<SegmentId> {
+        self.failed.since(0)
+    }
+
+    /// 완료된 세그먼트가 메시지 경계였으면 기록을 지우고 true
+    fn take_boundary(&self, segment_id: SegmentId) -> bool {
+        self.boundaries.lock().remove(&segment_id)
+    }
+
+    /// 특정 세그먼트가 완료될 때까지 대기 (전체 진행과 무관하게 순서 밖 접근용)
+    ///
+    /// 기다리는 동안 이 세그먼트의 NACK을 다른 세그먼트보다 먼저, 보류 없이 보냄.
+    /// 완료된 데이터는 완료 채널에도 그대로 전달되며, 이미 전달된 세그먼트는
+    /// `Error::SegmentAlreadyDelivered`, 완료 전에 만료되면 `Error::SegmentTimeout`
+    pub async fn await_segment(&self, segment_id: SegmentId) -> Result<Bytes> {
+        let (waiter, done) = oneshot::channel();
+        if !self
+            .cmd_tx
+            .send(ReceiverCmd::AwaitSegment(segment_id, waiter))
+            .await
+        {
+            return Err(self.closed_error());
+        }
+        match done.await {
+            Ok(result) => result,
+            Err(_) => Err(self.closed_error()),
+        }
+    }
+
+    /// 조립 샤드별 명령 큐(`receiver_cmd`)와 완료 채널(`completed`)의 현재 적재량
+    ///
+    /// 명령 큐가 차 있으면 그 샤드가, 완료 채널이 차 있으면 세그먼트를 꺼내는 쪽이 병목
+    pub fn queue_depths(&self) -> Vec<QueueDepth> {
+        let mut depths: Vec<QueueDepth> = (self.cmd_tx.queues.iter().enumerate())
+            .map(|(index, queue)| QueueDepth::of("receiver_cmd", index, queue))
+            .collect();
+        depths.extend(QueueDepth::of_weak("completed", 0, &self.completed_queue));
+        depths
+    }
+
+    /// 완료 채널이 닫힌 이유
+    fn closed_error(&self) -> Error {
+        let refused = self.refused_size.load(Ordering::SeqCst);
+        if let Some(reason) = self.rejected_ack.lock().clone() {
+            Error::InvalidInitAck(reason)
+        } else if refused > 0 {
+            Error::FileTooLarge {
+                size: refused,
+                max: self.max_accept_file_size,
+            }
+        } else if self.uplink_broken() {
+            Error::UplinkLikelyBroken {
+                nack_rounds: self.uplink_silent_nack_rounds,
+            }
+        } else {
+            Error::ConnectionClosed
+        }
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        // 정지하지 않고 버려도 태스크가 남지 않도록
+        self.cancel.cancel();
     }
 }
 
+/// 연결 태스크 실행. 취소되면 대기 중인 지점에서 바로 끝남
+fn spawn_cancellable<F>(tasks: &TaskTracker, cancel: &CancellationToken, span: &Span, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let cancel = cancel.clone();
+    tasks.spawn(
+        async move {
+            tokio::select! {
+                _ = cancel.cancelled() => {}
+                _ = task => {}
+            }
+        }
+        .instrument(span.clone()),
+    );
+}
+
+/// 청크 데이터그램 또는 청크 묶음(`ChunkBatch`)을 청크로 나눔. 청크가 아니면 None
+fn decode_chunks(datagram: &[u8]) -> Option<Vec<Chunk>> {
+    match Chunk::from_bytes(datagram) {
+        Some(chunk) => Some(vec![chunk]),
+        None => ChunkBatch::decode(datagram),
+    }
+}
+
+/// 파일 수신 중 아무 세그먼트도 오지 않으면 수신을 포기하는 시간
+const SEGMENT_WAIT: Duration = Duration::from_secs(30);
+
+/// 세그먼트 수신 기한 (핸드셰이크 RTT 배수)
+const SEGMENT_DEADLINE_RTTS: u32 = 64;
+
+/// 세그먼트 수신 기한 하한 (NACK 주기 배수)
+const SEGMENT_DEADLINE_NACKS: u32 = 16;
+
+/// 세그먼트 수신 기한 (평균 세그먼트 도착 간격 배수)
+const SEGMENT_DEADLINE_INTERVALS: u32 = 8;
+
+/// `receive_files`가 매니페스트보다 먼저 완료된 파일 세그먼트를 보관하는 최대 수
+/// (차면 매니페스트가 올 때까지 완료 채널을 꺼내지 않아 수신자의 완료 보관 창이 송신을 늦춤)
+const MAX_EARLY_SEGMENTS: usize = 1024;
+
+/// 수신 대기 중 일어난 일
+enum Delivery {
+    /// 완료된 세그먼트와 메시지 경계 여부
+    Segment(SegmentId, Bytes, bool),
+    /// 새로 영구 실패로 포기한 세그먼트
+    Failed(Vec<SegmentId>),
+}
+
 /// 간단한 파일 수신용 수신자
 pub struct FileReceiver {
     receiver: Receiver,
     segment_rx: Option<SegmentReceiver>,
+    /// 이미 반영한 실패 세그먼트 수 (`Receiver::failed_segments` 기준)
+    failed_seen: usize,
+    /// `poll_contiguous`가 앞에서부터 꺼내 간 마지막 세그먼트
+    contiguous_through: SegmentId,
+    /// 연속 구간 뒤에 먼저 완료되어 빈틈이 채워지길 기다리는 세그먼트
+    ahead: BTreeMap<SegmentId, Bytes>,
 }
 
 impl FileReceiver {
@@ -385,56 +2391,2973 @@ impl FileReceiver {
         let path_manager = Arc::new(PathManager::new(config.clone()));
         let (receiver, segment_rx) = Receiver::start(config, bind_addr, server_addr, path_manager).await?;
 
-        Ok(Self {
+        Ok(Self::from_receiver(receiver, segment_rx))
+    }
+
+    /// 이미 시작한 수신자로 생성 (시뮬레이터 등 다른 전송 계층)
+    pub fn from_receiver(receiver: Receiver, segment_rx: SegmentReceiver) -> Self {
+        Self {
             receiver,
             segment_rx: Some(segment_rx),
-        })
+            failed_seen: 0,
+            contiguous_through: 0,
+            ahead: BTreeMap::new(),
+        }
+    }
+
+    /// 다음 완료 세그먼트나 새로 포기한 세그먼트 대기 (`SEGMENT_WAIT` 동안 아무 일 없으면 None)
+    async fn next_delivery(&mut self, segment_rx: &mut SegmentReceiver) -> Result<Option<Delivery>> {
+        self.next_delivery_within(segment_rx, SEGMENT_WAIT).await
+    }
+
+    /// `wait` 동안 아무 일 없으면 None
+    async fn next_delivery_within(
+        &mut self,
+        segment_rx: &mut SegmentReceiver,
+        wait: Duration,
+    ) -> Result<Option<Delivery>> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            let failed = self.receiver.failed.since(self.failed_seen);
+            if !failed.is_empty() {
+                self.failed_seen += failed.len();
+                return Ok(Some(Delivery::Failed(failed)));
+            }
+
+            tokio::select! {
+                biased;
+                segment = segment_rx.recv() => {
+                    return match segment {
+                        Some((segment_id, data)) => {
+                            // 넘기는 순간 경계 기록을 지워 메시지로 꺼내지 않는 소비자도 쌓이지 않게
+                            let boundary = self.receiver.take_boundary(segment_id);
+                            Ok(Some(Delivery::Segment(segment_id, data, boundary)))
+                        }
+                        None => Err(self.receiver.closed_error()),
+                    };
+                }
+                _ = self.receiver.failed.notify.notified() => {}
+                _ = tokio::time::sleep_until(deadline) => return Ok(None),
+            }
+        }
     }
 
     /// 파일 데이터 수신 (모든 세그먼트 조합)
+    ///
+    /// 포기한 세그먼트가 있으면 나머지를 다 받은 뒤 빠진 세그먼트/바이트 구간을 모두 담은
+    /// `Error::IncompleteTransfer` (구멍 난 데이터를 돌려주지 않음, 받으려면 `receive_file_partial`).
+    /// `Receiver::segment_deadline_after`(RTT와 세그먼트 도착 간격으로 정한 기한) 동안 세그먼트도
+    /// 청크도 오지 않으면 아직 받지 못한 첫 세그먼트 전체를 다시 요청하고,
+    /// `receive_segment_retries`번 요청해도 오지 않으면 그 세그먼트로 `Error::SegmentTimeout`
     pub async fn receive_file(&mut self, expected_segments: usize) -> Result<Vec<u8>> {
+        let (data, report) = self.receive_file_partial(expected_segments).await?;
+        if !report.complete {
+            return Err(Error::IncompleteTransfer {
+                missing_segments: report.failed_segments,
+                missing_bytes: report.missing_bytes,
+            });
+        }
+        Ok(data)
+    }
+
+    /// 불완전해도 받은 데이터를 돌려주는 `receive_file` (부분 전달을 직접 고른 경우)
+    ///
+    /// 포기한 세그먼트 자리는 0으로 채워 뒤 세그먼트의 오프셋을 지키고, 그 자리는 보고서의
+    /// `missing_bytes`. 세그먼트 크기를 모르는 빈 자리(청크를 하나도 못 받음)는 받은 세그먼트 중
+    /// 가장 큰 크기로 가정 (고정 크기 세그먼트면 정확)
+    pub async fn receive_file_partial(
+        &mut self,
+        expected_segments: usize,
+    ) -> Result<(Vec<u8>, TransferReport)> {
+        let started = Instant::now();
+        let mut output = PartialOutput::new(expected_segments);
+        self.receive_ordered(&mut output).await?;
+
+        let failed: Vec<SegmentId> = output
+            .layout
+            .iter()
+            .filter(|&&(_, _, received)| !received)
+            .map(|&(id, ..)| id)
+            .collect();
+        let report = TransferReport {
+            segments: output.segments,
+            bytes: output.data.len() as u64,
+            elapsed: started.elapsed(),
+            digest: output.digest,
+            complete: failed.is_empty(),
+            failed_segments: TransferReport::segment_ranges(failed),
+            missing_bytes: TransferReport::missing_byte_ranges(output.layout),
+            stats: self.receiver.get_stats().await,
+        };
+        Ok((output.data, report))
+    }
+
+    /// 검증 전용 수신: 세그먼트를 다이제스트에 반영한 뒤 즉시 해제
+    ///
+    /// 페이로드를 보관하지 않고 앞 세그먼트를 기다리는 세그먼트도 길이만 기억하므로 장시간 소크
+    /// 테스트도 메모리가 일정. 포기한 세그먼트도 `expected_segments`에 포함해 끝내고
+    /// `failed_segments`/`missing_bytes`로 보고. 기다리는 세그먼트가 오지 않을 때의
+    /// 재요청/타임아웃은 `receive_file`과 같음
+    pub async fn receive_discard(&mut self, expected_segments: usize) -> Result<TransferReport> {
+        let started = Instant::now();
+        let mut output = DiscardOutput {
+            last_id: expected_segments as SegmentId,
+            segments: 0,
+            bytes: 0,
+            digest: 0,
+            largest: 0,
+            failed: Vec::new(),
+            missing_bytes: Vec::new(),
+        };
+        self.receive_ordered(&mut output).await?;
+
+        Ok(TransferReport {
+            segments: output.segments,
+            bytes: output.bytes,
+            elapsed: started.elapsed(),
+            digest: output.digest,
+            complete: output.failed.is_empty(),
+            failed_segments: TransferReport::segment_ranges(output.failed),
+            missing_bytes: output.missing_bytes,
+            stats: self.receiver.get_stats().await,
+        })
+    }
+
+    /// 호출자 버퍼에 직접 조립 (실제 세그먼트 길이의 누적 합으로 오프셋 계산)
+    ///
+    /// 세그먼트 ID 순서대로 이어 붙이므로 세그먼트마다 크기가 달라도 됨
+    /// (적응형 세그먼트 크기). 차례가 된 세그먼트는 제자리에 복사한 즉시 해제되어
+    /// 버퍼 풀로 회수되고, 앞 세그먼트를 기다리는 세그먼트만 잠시 보관.
+    /// 버퍼가 가득 차면 종료하며 기록한 바이트 수 반환.
+    /// 차례가 된 세그먼트를 포기했으면 그 오프셋에서 `Error::SegmentGap`
+    pub async fn receive_into(&mut self, buf: &mut [u8]) -> Result<usize> {
         let mut segment_rx = self
             .segment_rx
             .take()
             .ok_or_else(|| Error::Unknown("이미 수신 중".into()))?;
 
-        let mut received_segments: HashMap<SegmentId, Bytes> = HashMap::new();
+        // 세그먼트 ID는 1부터 시작
+        let mut next_id: SegmentId = 1;
+        let mut pending: BTreeMap<SegmentId, Bytes> = BTreeMap::new();
+        let mut pending_bytes = 0;
+        let mut written = 0;
+        let mut failed: HashSet<SegmentId> = HashSet::new();
 
-        while received_segments.len() < expected_segments {
-            match tokio::time::timeout(Duration::from_secs(30), segment_rx.recv()).await {
-                Ok(Some((segment_id, data))) => {
-                    received_segments.insert(segment_id, data);
-                    info!(
-                        "세그먼트 수신: {}/{} 완료",
-                        received_segments.len(),
-                        expected_segments
-                    );
-                }
-                Ok(None) => {
-                    return Err(Error::ConnectionClosed);
-                }
-                Err(_) => {
-                    return Err(Error::SegmentTimeout {
-                        segment_id: received_segments.len() as u64,
-                    });
+        while written < buf.len() {
+            let (segment_id, data) = match self.next_delivery(&mut segment_rx).await? {
+                Some(Delivery::Segment(segment_id, data, _)) => (segment_id, data),
+                Some(Delivery::Failed(ids)) => {
+                    failed.extend(ids);
+                    if failed.contains(&next_id) {
+                        return Err(Error::SegmentGap {
+                            segment_id: next_id,
+                            offset: written as u64,
+                        });
+                    }
+                    continue;
                 }
+                None => return Err(Error::SegmentTimeout { segment_id: next_id }),
+            };
+
+            if segment_id == 0 {
+                return Err(Error::InvalidSegmentId { segment_id });
             }
-        }
+            if segment_id < next_id || pending.contains_key(&segment_id) {
+                continue;
+            }
+            if written + pending_bytes + data.len() > buf.len() {
+                return Err(Error::BufferOverflow { max_size: buf.len() });
+            }
+            pending_bytes += data.len();
+            pending.insert(segment_id, data);
 
-        // 세그먼트 순서대로 조합
-        let mut result = Vec::new();
-        for i in 1..=(expected_segments as u64) {
-            if let Some(data) = received_segments.remove(&i) {
-                result.extend_from_slice(&data);
+            while let Some(data) = pending.remove(&next_id) {
+                let end = written + data.len();
+                buf[written..end].copy_from_slice(&data);
+                pending_bytes -= data.len();
+                written = end;
+                next_id += 1;
+            }
+            if failed.contains(&next_id) {
+                return Err(Error::SegmentGap {
+                    segment_id: next_id,
+                    offset: written as u64,
+                });
             }
         }
 
         self.segment_rx = Some(segment_rx);
-        Ok(result)
+        Ok(written)
     }
 
-    /// 통계 반환
-    pub async fn stats(&self) -> TransferStats {
-        self.receiver.get_stats().await
+    /// `total_size` 바이트 버퍼를 한 번만 할당해 직접 조립
+    pub async fn receive_into_vec_with_capacity(&mut self, total_size: usize) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; total_size];
+        let written = self.receive_into(&mut buf).await?;
+        buf.truncate(written);
+        Ok(buf)
+    }
+
+    /// 출력 대상에 세그먼트 ID 순서대로 기록 (`OutputSink`)
+    ///
+    /// 앞 세그먼트를 기다리는 세그먼트만 잠시 보관하고, 차례가 되면 누적 오프셋과 함께
+    /// 전달한 즉시 해제. `expected_segments`개를 모두 기록하면 `finish`를 호출하고
+    /// 기록한 바이트 수 반환. 차례가 된 세그먼트를 포기했으면 그 오프셋에서 `Error::SegmentGap`.
+    /// 기다리는 세그먼트가 오지 않을 때의 재요청/타임아웃은 `receive_file`과 같음
+    pub async fn receive_to_sink<K: OutputSink + ?Sized>(
+        &mut self,
+        sink: &mut K,
+        expected_segments: usize,
+    ) -> Result<u64> {
+        let mut output = SinkOutput {
+            sink,
+            last_id: expected_segments as SegmentId,
+        };
+        let written = self.receive_ordered(&mut output).await?;
+        output.sink.finish()?;
+        Ok(written)
+    }
+
+    /// 다중 파일 세션 수신: 매니페스트의 파일들을 `dest` 아래에 기록하고 파일별로 검증
+    ///
+    /// 매니페스트보다 먼저 완료된 세그먼트는 보관했다가 매니페스트를 받은 뒤 기록.
+    /// `MAX_EARLY_SEGMENTS`개가 차면 매니페스트가 올 때까지 더 꺼내지 않음 (송신을 늦출 뿐
+    /// 실패하지 않음). 매니페스트의 파일 크기 합이 `Config::max_accept_file_size`를 넘으면
+    /// 파일을 만들기 전에 `Error::FileTooLarge`.
+    /// 다이제스트가 맞지 않는 파일도 다른 파일과 독립적으로 `verified = false`로 보고.
+    /// 파일 단위로 빈 구간을 기록할 수 없으므로 포기한 세그먼트가 있으면
+    /// `Error::SegmentUndeliverable`
+    pub async fn receive_files(&mut self, dest: impl AsRef<Path>) -> Result<Vec<FileReport>> {
+        let mut output = ManifestOutput {
+            dest: dest.as_ref(),
+            max_size: self.receiver.max_accept_file_size,
+            manifest: None,
+            writers: Vec::new(),
+        };
+        self.receive_ordered(&mut output).await?;
+        output.writers.into_iter().map(FileWriter::finish).collect()
+    }
+
+    /// `receive_file`/`receive_to_sink`/`receive_files`/`receive_discard`의 공통 수신 루프
+    ///
+    /// 완료 세그먼트를 `output`이 정한 ID 순서대로 넘기고 (앞 세그먼트를 기다리는 세그먼트만
+    /// `OrderedOutput::arrive`를 거쳐 보관), 포기한 세그먼트 자리는 `output`이 채우거나 거부.
+    /// 차례가 된 세그먼트가 `Receiver::segment_deadline_after` 동안 오지 않으면 세그먼트 전체를
+    /// 다시 요청하고, `receive_segment_retries`번 요청해도 오지 않으면 `Error::SegmentTimeout`.
+    /// 넘긴 바이트 수 반환
+    async fn receive_ordered<O: OrderedOutput + ?Sized>(&mut self, output: &mut O) -> Result<u64> {
+        let mut segment_rx = self
+            .segment_rx
+            .take()
+            .ok_or_else(|| Error::Unknown("이미 수신 중".into()))?;
+        let Some(mut next_id) = output.next_after(None) else {
+            self.segment_rx = Some(segment_rx);
+            return Ok(0);
+        };
+
+        // 차례를 기다리는 세그먼트 (보관할 데이터, 원래 길이)
+        let mut pending: BTreeMap<SegmentId, (Bytes, u64)> = BTreeMap::new();
+        let mut failed: HashSet<SegmentId> = HashSet::new();
+        let mut offset = 0u64;
+        let mut retries = 0;
+        let mut chunks_seen = self.receiver.get_stats().await.total_chunks;
+        // 평활 세그먼트 도착 간격 (1/8 가중)
+        let mut interval: Option<Duration> = None;
+        let mut last_delivery = Instant::now();
+        // 차례가 된 세그먼트가 이미 완료 채널에 들어가 있음 (보관 한도를 넘어서라도 꺼내야 함)
+        let mut queued_ahead: Option<SegmentId> = None;
+
+        loop {
+            // 차례가 된 세그먼트를 순서대로 넘김 (포기한 자리는 출력 대상이 처리)
+            loop {
+                if let Some((data, len)) = pending.remove(&next_id) {
+                    output.write(next_id, offset, data)?;
+                    offset += len;
+                } else if failed.contains(&next_id) {
+                    let size = self.receiver.failed.size(next_id);
+                    offset += output.skip(next_id, offset, size)?;
+                } else {
+                    break;
+                }
+                match output.next_after(Some(next_id)) {
+                    Some(id) => next_id = id,
+                    None => {
+                        self.segment_rx = Some(segment_rx);
+                        return Ok(offset);
+                    }
+                }
+            }
+
+            let deadline = self.receiver.segment_deadline_after(interval);
+            let held_full = output.pending_limit().is_some_and(|limit| pending.len() >= limit);
+            let delivery = if held_full && queued_ahead != Some(next_id) {
+                // 보관 한도에 이르면 완료 채널을 더 꺼내지 않고 (수신자의 완료 보관 창이 새 세그먼트를
+                // 보류해 송신을 늦춤) 차례가 된 세그먼트만 직접 기다림
+                match tokio::time::timeout(deadline, self.receiver.await_segment(next_id)).await {
+                    Ok(Ok(data)) => Some(Delivery::Segment(next_id, data, false)),
+                    Ok(Err(Error::SegmentAlreadyDelivered { .. })) => {
+                        queued_ahead = Some(next_id);
+                        continue;
+                    }
+                    Ok(Err(Error::SegmentUndeliverable { .. } | Error::SegmentTimeout { .. })) => {
+                        Some(Delivery::Failed(vec![next_id]))
+                    }
+                    Ok(Err(e)) => return Err(e),
+                    Err(_) => None,
+                }
+            } else {
+                self.next_delivery_within(&mut segment_rx, deadline).await?
+            };
+            match delivery {
+                Some(Delivery::Segment(segment_id, data, _)) => {
+                    retries = 0;
+                    let gap = last_delivery.elapsed();
+                    last_delivery = Instant::now();
+                    interval = Some(interval.map_or(gap, |smoothed| (smoothed * 7 + gap) / 8));
+                    if output.wants(segment_id)? && segment_id >= next_id {
+                        if let btree_map::Entry::Vacant(entry) = pending.entry(segment_id) {
+                            let len = data.len() as u64;
+                            entry.insert((output.arrive(segment_id, data), len));
+                        }
+                    }
+                }
+                Some(Delivery::Failed(ids)) => {
+                    failed.extend(ids);
+                    retries = 0;
+                }
+                None => {
+                    // 송신자가 정지 중이면 기한을 세지 않고 계속 대기
+                    if self.receiver.is_paused() {
+                        retries = 0;
+                        continue;
+                    }
+                    // 조립 중인 세그먼트에 청크가 계속 오면 느릴 뿐이므로 계속 대기
+                    let chunks = self.receiver.get_stats().await.total_chunks;
+                    if chunks != chunks_seen {
+                        chunks_seen = chunks;
+                        retries = 0;
+                        continue;
+                    }
+                    if retries >= self.receiver.receive_segment_retries {
+                        return Err(Error::SegmentTimeout { segment_id: next_id });
+                    }
+                    retries += 1;
+                    debug!(segment_id = next_id, retries, "세그먼트 수신 기한 초과: 재요청");
+                    self.receiver.nack_segment(next_id).await;
+                }
+            }
+        }
+    }
+
+    /// 앞에서부터 빈틈 없이 이어진 데이터 중 아직 꺼내 가지 않은 부분 (없으면 None, 기다리지 않음)
+    ///
+    /// 완료 채널에 와 있는 세그먼트를 모두 가져와, 마지막으로 꺼낸 세그먼트 바로 다음부터 이어지는
+    /// 세그먼트들을 한 덩어리로 돌려주고 커서를 그만큼 옮김. 순서 밖에서 먼저 완료된 세그먼트는
+    /// 앞의 빈틈이 채워질 때까지 보관. tar 추출처럼 앞에서부터 처리하는 소비자가 파일 전체를
+    /// 기다리지 않고 시작할 수 있음
+    ///
+    /// `receive_file` 등과 같은 완료 채널을 쓰므로 섞어 쓰지 않음. 포기한 세그먼트 뒤로는
+    /// 나아가지 않으므로 진행이 멈추면 `Receiver::failed_segments`를 확인
+    pub fn poll_contiguous(&mut self) -> Option<Bytes> {
+        if let Some(segment_rx) = self.segment_rx.as_mut() {
+            while let Ok((segment_id, data)) = segment_rx.try_recv() {
+                if segment_id > self.contiguous_through {
+                    self.ahead.insert(segment_id, data);
+                }
+            }
+        }
+
+        let mut ready = Vec::new();
+        while let Some(data) = self.ahead.remove(&(self.contiguous_through + 1)) {
+            self.contiguous_through += 1;
+            ready.push(data);
+        }
+        match ready.len() {
+            0 => None,
+            1 => ready.pop(),
+            _ => Some(ready.concat().into()),
+        }
+    }
+
+    /// `poll_contiguous`로 꺼내 간 세그먼트 수 (1부터 이 ID까지 모두 꺼냄)
+    pub fn contiguous_segments(&self) -> SegmentId {
+        self.contiguous_through
+    }
+
+    /// 수신자 핸들
+    pub fn receiver(&self) -> &Receiver {
+        &self.receiver
+    }
+
+    /// 통계 반환
+    pub async fn stats(&self) -> TransferStats {
+        self.receiver.get_stats().await
+    }
+
+    /// 메시지 단위 수신으로 전환 (`Sender::send_message`로 보낸 세션)
+    pub fn into_messages(mut self) -> Result<MessageReceiver> {
+        let segment_rx = self
+            .segment_rx
+            .take()
+            .ok_or_else(|| Error::Unknown("이미 수신 중".into()))?;
+        Ok(MessageReceiver {
+            files: self,
+            segment_rx,
+            next_segment: 1,
+            pending: BTreeMap::new(),
+            failed: HashSet::new(),
+            message: Vec::new(),
+            discarding: false,
+        })
+    }
+}
+
+/// 메시지 단위 수신자 (`FileReceiver::into_messages`)
+///
+/// 완료된 세그먼트를 ID 순서대로 이어 붙이다가 경계가 표시된 세그먼트에서 메시지 하나를
+/// 돌려줌. 순서보다 먼저 완료된 세그먼트는 앞 세그먼트가 올 때까지 보관
+pub struct MessageReceiver {
+    files: FileReceiver,
+    segment_rx: SegmentReceiver,
+    /// 다음에 이어 붙일 세그먼트
+    next_segment: SegmentId,
+    /// 순서보다 먼저 완료된 세그먼트 (데이터, 메시지 경계 여부)
+    pending: BTreeMap<SegmentId, (Bytes, bool)>,
+    /// 포기한 세그먼트
+    failed: HashSet<SegmentId>,
+    /// 조립 중인 메시지
+    message: Vec<u8>,
+    /// 빈 구간을 보고한 메시지의 나머지 세그먼트를 다음 경계까지 버리는 중
+    discarding: bool,
+}
+
+impl MessageReceiver {
+    /// 이미 시작한 수신자로 생성
+    pub fn from_receiver(receiver: Receiver, segment_rx: SegmentReceiver) -> Self {
+        let files = FileReceiver::from_receiver(receiver, segment_rx);
+        files.into_messages().expect("새 FileReceiver는 완료 채널을 갖고 있음")
+    }
+
+    /// 다음 메시지 대기. 스트림 끝 표시가 있는 메시지까지 돌려줬거나 연결이 메시지 경계에서
+    /// 정상 종료되면 `None`
+    ///
+    /// 메시지 사이의 유휴 구간은 기다림. 메시지의 세그먼트를 포기하면 그 메시지는 복원할 수
+    /// 없으므로 `Error::SegmentGap`(`offset`은 메시지 안의 위치)을 돌려주고, 다음 호출은 그
+    /// 메시지의 나머지를 다음 경계까지 버린 뒤 이어감 (포기한 세그먼트가 경계였으면 다음
+    /// 메시지까지 함께 버려짐)
+    pub async fn next_message(&mut self) -> Result<Option<Bytes>> {
+        loop {
+            if self
+                .files
+                .receiver
+                .end_of_stream()
+                .is_some_and(|last| self.next_segment > last)
+            {
+                return Ok(None);
+            }
+            while let Some((data, boundary)) = self.pending.remove(&self.next_segment) {
+                self.next_segment += 1;
+                if self.discarding {
+                    self.discarding = !boundary;
+                    continue;
+                }
+                if boundary && self.message.is_empty() {
+                    // 세그먼트 하나짜리 메시지는 복사 없이 그대로
+                    return Ok(Some(data));
+                }
+                self.message.extend_from_slice(&data);
+                if boundary {
+                    return Ok(Some(Bytes::from(std::mem::take(&mut self.message))));
+                }
+            }
+            if self.failed.remove(&self.next_segment) {
+                let segment_id = self.next_segment;
+                self.next_segment += 1;
+                let offset = std::mem::take(&mut self.message).len() as u64;
+                self.discarding = true;
+                return Err(Error::SegmentGap { segment_id, offset });
+            }
+
+            match self.files.next_delivery(&mut self.segment_rx).await {
+                Ok(Some(Delivery::Segment(segment_id, data, boundary))) => {
+                    if segment_id >= self.next_segment {
+                        self.pending.insert(segment_id, (data, boundary));
+                    }
+                }
+                Ok(Some(Delivery::Failed(ids))) => self.failed.extend(ids),
+                Ok(None) => {}
+                Err(Error::ConnectionClosed)
+                    if self.message.is_empty() && self.pending.is_empty() =>
+                {
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 수신자 핸들
+    pub fn receiver(&self) -> &Receiver {
+        &self.files.receiver
+    }
+}
+
+/// `FileReceiver::receive_ordered`가 세그먼트를 넘기는 대상
+trait OrderedOutput {
+    /// `after` 다음에 넘겨받을 세그먼트 ID (None이면 첫 세그먼트, 반환이 None이면 수신 끝)
+    fn next_after(&self, after: Option<SegmentId>) -> Option<SegmentId>;
+
+    /// 차례가 되기 전에 도착한 세그먼트를 보관할지 (범위 밖이면 버림)
+    fn wants(&self, _segment_id: SegmentId) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// 도착한 세그먼트를 차례가 될 때까지 보관하기 전에 넘겨받음. 돌려준 데이터를 보관했다가
+    /// `write`에 넘김 (기본은 그대로)
+    fn arrive(&mut self, _segment_id: SegmentId, data: Bytes) -> Bytes {
+        data
+    }
+
+    /// 보관할 세그먼트 수. 차면 완료 채널을 더 꺼내지 않고 차례가 된 세그먼트만 기다림
+    /// (None이면 `wants` 범위만큼)
+    fn pending_limit(&self) -> Option<usize> {
+        None
+    }
+
+    /// 차례가 된 세그먼트 (`offset`: 앞 세그먼트 길이의 누적 합)
+    fn write(&mut self, segment_id: SegmentId, offset: u64, data: Bytes) -> Result<()>;
+
+    /// 차례가 된 세그먼트를 포기함 (`size`: 청크 헤더로 알던 크기). 채운 바이트 수 반환,
+    /// 기본은 그 오프셋에서 `Error::SegmentGap`
+    fn skip(&mut self, segment_id: SegmentId, offset: u64, _size: Option<usize>) -> Result<u64> {
+        Err(Error::SegmentGap { segment_id, offset })
+    }
+}
+
+/// 1부터 `last_id`까지 사용자 출력 대상에 (`receive_to_sink`)
+struct SinkOutput<'a, K: OutputSink + ?Sized> {
+    sink: &'a mut K,
+    last_id: SegmentId,
+}
+
+impl<K: OutputSink + ?Sized> OrderedOutput for SinkOutput<'_, K> {
+    fn next_after(&self, after: Option<SegmentId>) -> Option<SegmentId> {
+        Some(after.map_or(1, |id| id + 1)).filter(|&id| id <= self.last_id)
+    }
+
+    fn wants(&self, segment_id: SegmentId) -> Result<bool> {
+        if segment_id == 0 {
+            return Err(Error::InvalidSegmentId { segment_id });
+        }
+        Ok(segment_id <= self.last_id)
+    }
+
+    fn write(&mut self, segment_id: SegmentId, offset: u64, data: Bytes) -> Result<()> {
+        self.sink.write_segment(segment_id, offset, &data)
+    }
+}
+
+/// 1부터 `last_id`까지 메모리에 조립하고 포기한 자리는 0으로 채움 (`receive_file_partial`)
+struct PartialOutput {
+    last_id: SegmentId,
+    data: Vec<u8>,
+    /// 받은 세그먼트 수
+    segments: u64,
+    digest: u64,
+    /// (세그먼트 ID, 길이, 받았는지)
+    layout: Vec<(SegmentId, u64, bool)>,
+    /// 받은 세그먼트 중 가장 큰 크기 (크기를 모르는 빈 자리의 추정값)
+    largest: usize,
+}
+
+impl PartialOutput {
+    fn new(expected_segments: usize) -> Self {
+        Self {
+            last_id: expected_segments as SegmentId,
+            data: Vec::new(),
+            segments: 0,
+            digest: 0,
+            layout: Vec::with_capacity(expected_segments),
+            largest: 0,
+        }
+    }
+}
+
+impl OrderedOutput for PartialOutput {
+    fn next_after(&self, after: Option<SegmentId>) -> Option<SegmentId> {
+        Some(after.map_or(1, |id| id + 1)).filter(|&id| id <= self.last_id)
+    }
+
+    fn wants(&self, segment_id: SegmentId) -> Result<bool> {
+        Ok(segment_id <= self.last_id)
+    }
+
+    fn write(&mut self, segment_id: SegmentId, _offset: u64, data: Bytes) -> Result<()> {
+        self.digest = self
+            .digest
+            .wrapping_add(TransferReport::segment_digest(segment_id, &data) as u64);
+        self.layout.push((segment_id, data.len() as u64, true));
+        self.largest = self.largest.max(data.len());
+        self.data.extend_from_slice(&data);
+        self.segments += 1;
+        info!("세그먼트 수신: {}/{} 완료", self.segments, self.last_id);
+        Ok(())
+    }
+
+    /// 세그먼트 크기를 모르는 빈 자리(청크를 하나도 못 받음)는 앞서 받은 가장 큰 크기로 가정
+    /// (고정 크기 세그먼트면 정확)
+    fn skip(&mut self, segment_id: SegmentId, _offset: u64, size: Option<usize>) -> Result<u64> {
+        let len = size.unwrap_or(self.largest);
+        self.layout.push((segment_id, len as u64, false));
+        self.data.resize(self.data.len() + len, 0);
+        Ok(len as u64)
+    }
+}
+
+/// 1부터 `last_id`까지 다이제스트만 계산하고 페이로드는 도착 즉시 해제 (`receive_discard`)
+struct DiscardOutput {
+    last_id: SegmentId,
+    /// 받은 세그먼트 수
+    segments: u64,
+    bytes: u64,
+    digest: u64,
+    /// 받은 세그먼트 중 가장 큰 크기 (크기를 모르는 빈 자리의 추정값)
+    largest: u64,
+    /// 포기한 세그먼트
+    failed: Vec<SegmentId>,
+    /// 포기한 세그먼트의 바이트 구간 (맞닿은 구간은 합침)
+    missing_bytes: Vec<(u64, u64)>,
+}
+
+impl OrderedOutput for DiscardOutput {
+    fn next_after(&self, after: Option<SegmentId>) -> Option<SegmentId> {
+        Some(after.map_or(1, |id| id + 1)).filter(|&id| id <= self.last_id)
+    }
+
+    fn wants(&self, segment_id: SegmentId) -> Result<bool> {
+        Ok(segment_id <= self.last_id)
+    }
+
+    /// 다이제스트에 반영하고 데이터는 여기서 해제되어 버퍼 풀로 회수 (길이만 보관)
+    fn arrive(&mut self, segment_id: SegmentId, data: Bytes) -> Bytes {
+        self.bytes += data.len() as u64;
+        self.largest = self.largest.max(data.len() as u64);
+        self.digest = self
+            .digest
+            .wrapping_add(TransferReport::segment_digest(segment_id, &data) as u64);
+        Bytes::new()
+    }
+
+    fn write(&mut self, _segment_id: SegmentId, _offset: u64, _data: Bytes) -> Result<()> {
+        self.segments += 1;
+        Ok(())
+    }
+
+    /// 세그먼트 크기를 모르는 빈 자리는 `PartialOutput`과 같이 가장 큰 크기로 가정
+    fn skip(&mut self, segment_id: SegmentId, offset: u64, size: Option<usize>) -> Result<u64> {
+        let len = size.map_or(self.largest, |size| size as u64);
+        self.failed.push(segment_id);
+        if len > 0 {
+            match self.missing_bytes.last_mut() {
+                Some((_, end)) if *end == offset => *end += len,
+                _ => self.missing_bytes.push((offset, offset + len)),
+            }
+        }
+        Ok(len)
+    }
+}
+
+/// 매니페스트(세그먼트 0)를 먼저 받고 그 구간의 세그먼트를 파일에 기록 (`receive_files`)
+struct ManifestOutput<'a> {
+    dest: &'a Path,
+    /// 파일 크기 합 한도 (`Config::max_accept_file_size`, 0이면 제한 없음)
+    max_size: u64,
+    manifest: Option<Manifest>,
+    writers: Vec<FileWriter>,
+}
+
+impl ManifestOutput<'_> {
+    /// 파일 세그먼트 구간 [첫 ID, 끝 ID) (세그먼트가 없으면 None)
+    fn file_segments(&self) -> Option<(SegmentId, SegmentId)> {
+        let manifest = self.manifest.as_ref()?;
+        let first = manifest.files.first()?.first_segment;
+        let end = first.checked_add(manifest.total_segments()?)?;
+        Some((first, end)).filter(|(first, end)| first < end)
+    }
+}
+
+impl OrderedOutput for ManifestOutput<'_> {
+    fn next_after(&self, after: Option<SegmentId>) -> Option<SegmentId> {
+        let Some(after) = after else {
+            return Some(MANIFEST_SEGMENT_ID);
+        };
+        let (first, end) = self.file_segments()?;
+        Some(if after < first { first } else { after + 1 }).filter(|&id| id < end)
+    }
+
+    fn wants(&self, segment_id: SegmentId) -> Result<bool> {
+        // 매니페스트 재수신과 구간 밖 세그먼트는 무시
+        Ok(match self.manifest {
+            Some(_) => self
+                .file_segments()
+                .is_some_and(|(first, end)| (first..end).contains(&segment_id)),
+            None => true,
+        })
+    }
+
+    fn pending_limit(&self) -> Option<usize> {
+        self.manifest.is_none().then_some(MAX_EARLY_SEGMENTS)
+    }
+
+    fn write(&mut self, segment_id: SegmentId, _offset: u64, data: Bytes) -> Result<()> {
+        let Some(manifest) = &self.manifest else {
+            let received = Manifest::from_bytes(&data)?;
+            received.check_size(self.max_size)?;
+            self.writers = received
+                .files
+                .iter()
+                .map(|file| {
+                    let path = received.destination(self.dest, file)?;
+                    FileWriter::create(path, file, received.segment_size as usize)
+                })
+                .collect::<Result<_>>()?;
+            info!(
+                "매니페스트 수신: {} 파일, {} 세그먼트",
+                self.writers.len(),
+                received.total_segments().unwrap_or_default()
+            );
+            self.manifest = Some(received);
+            return Ok(());
+        };
+        write_file_segment(manifest, &mut self.writers, segment_id, data).map(|_| ())
+    }
+
+    fn skip(&mut self, segment_id: SegmentId, _offset: u64, _size: Option<usize>) -> Result<u64> {
+        Err(Error::SegmentUndeliverable { segment_id })
+    }
+}
+
+/// 매니페스트 구간에 따라 세그먼트를 해당 파일에 기록. 새로 기록했으면 true
+fn write_file_segment(
+    manifest: &Manifest,
+    writers: &mut [FileWriter],
+    segment_id: SegmentId,
+    data: Bytes,
+) -> Result<bool> {
+    let (file, index) = manifest
+        .locate(segment_id)
+        .ok_or(Error::InvalidSegmentId { segment_id })?;
+    writers[file].write_segment(index, manifest.segment_size as usize, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nat_keepalive_during_idle() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+
+        let config = Config {
+            nat_keepalive_interval_ms: 20,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let (receiver, _segment_rx) = Receiver::start(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server_addr,
+            path_manager,
+        )
+        .await
+        .unwrap();
+
+        // 청크를 보내지 않는 유휴 구간 동안 Heartbeat가 도착해야 함
+        let mut buf = vec![0u8; 2048];
+        let mut heartbeats = 0;
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while heartbeats < 2 && Instant::now() < deadline {
+            if let Ok(Ok((len, addr))) =
+                tokio::time::timeout(Duration::from_millis(100), server.recv_from(&mut buf)).await
+            {
+                assert_eq!(addr, receiver.local_addr());
+                let header = MessageHeader::from_bytes(&buf[..len]).unwrap();
+                if header.msg_type == MessageType::Heartbeat {
+                    heartbeats += 1;
+                }
+            }
+        }
+
+        assert!(heartbeats >= 2);
+        assert!(receiver.keepalives_sent() >= 2);
+        receiver.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_stop_ends_all_tasks_promptly() {
+        // 타이머가 길어 깃발 확인 방식이면 수 초간 남았을 태스크들
+        let config = Config {
+            nack_timeout_ms: 10_000,
+            nat_keepalive_interval_ms: 40_000,
+            ..Config::default()
+        };
+        let server_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let server_addr = server_socket.local_addr().unwrap();
+        let sender = Arc::new(crate::sender::Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let sender_loop = tokio::spawn(async move { sender_task.start_with_socket(server_socket).await });
+        let shards = assembly_shards(&config);
+
+        let (receiver, mut segment_rx) = Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server_addr,
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        while sender.client_addr().is_none() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // 수신, NACK 타이머, keepalive, 조립 샤드마다 처리 태스크
+        assert_eq!(receiver.active_tasks(), 3 + shards);
+
+        tokio::time::timeout(Duration::from_millis(50), receiver.stop())
+            .await
+            .expect("정지 시간 초과");
+        assert_eq!(receiver.active_tasks(), 0);
+        assert!(!receiver.is_running());
+        assert_eq!(receiver.connection_state(), ConnectionState::Closed);
+        assert!(segment_rx.recv().await.is_none());
+
+        sender.stop();
+        tokio::time::timeout(Duration::from_millis(50), sender_loop)
+            .await
+            .expect("송신 루프 종료 시간 초과")
+            .unwrap()
+            .unwrap();
+    }
+
+    /// 루프백 송신자와 수신자를 띄우고 핸드셰이크 결과를 기다림
+    async fn connect_pair(
+        server_config: Config,
+        client_config: Config,
+    ) -> (Arc<crate::sender::Sender>, Arc<UdpSocket>, Receiver, SegmentReceiver) {
+        let server_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let server_addr = server_socket.local_addr().unwrap();
+        let sender = Arc::new(crate::sender::Sender::new(
+            server_config.clone(),
+            Arc::new(PathManager::new(server_config)),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, segment_rx) = Receiver::start(
+            client_config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server_addr,
+            Arc::new(PathManager::new(client_config)),
+        )
+        .await
+        .unwrap();
+        (sender, server_socket, receiver, segment_rx)
+    }
+
+    #[tokio::test]
+    async fn test_segment_size_clamped_to_receiver_limit() {
+        let server_config = Config {
+            segment_size: 256 * 1024,
+            ..Config::default()
+        };
+        let client_config = Config {
+            max_supported_segment_size: 16 * 1024,
+            ..Config::default()
+        };
+        let (sender, socket, receiver, mut segment_rx) =
+            connect_pair(server_config, client_config).await;
+        while receiver.connection_state() != ConnectionState::Transferring {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        assert_eq!(sender.negotiated_segment_size(), 16 * 1024);
+        assert!(sender.current_segment_size() <= 16 * 1024);
+
+        // 한도를 넘는 세그먼트는 보내기 전에 거부, 한도 안은 그대로 전달
+        let result = sender.send_data(Bytes::from(vec![1u8; 64 * 1024]), &*socket).await;
+        assert!(
+            matches!(result, Err(Error::SegmentTooLarge { size, max }) if size == 64 * 1024 && max == 16 * 1024),
+            "{:?}",
+            result
+        );
+        let payload: Bytes = (0..16 * 1024).map(|i| (i % 251) as u8).collect();
+        let id = sender.send_data(payload.clone(), &*socket).await.unwrap();
+        let (received_id, data) = tokio::time::timeout(Duration::from_secs(2), segment_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!((received_id, data), (id, payload));
+
+        receiver.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_manifest_rejected_before_sending() {
+        let server_config = Config {
+            segment_digests: true,
+            ..Config::default()
+        };
+        let client_config = Config {
+            max_supported_segment_size: 16 * 1024,
+            ..Config::default()
+        };
+        let (sender, socket, receiver, _segment_rx) =
+            connect_pair(server_config, client_config).await;
+        while receiver.connection_state() != ConnectionState::Transferring {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        // 세그먼트 1024개분 다이제스트(32KB)는 16KB 한도에 들어가지 않음
+        let big = vec![("big.bin".to_string(), Bytes::from(vec![0u8; 1024 * 16 * 1024]))];
+        let result = sender.send_files(&big, &*socket).await;
+        assert!(
+            matches!(result, Err(Error::SegmentTooLarge { size, max: 16384 }) if size > 32 * 1024),
+            "{:?}",
+            result.map(|manifest| manifest.files.len())
+        );
+
+        // 거부된 매니페스트는 세그먼트 ID도 세션의 매니페스트 기회도 쓰지 않음
+        let small = vec![("small.bin".to_string(), Bytes::from_static(b"small"))];
+        let manifest = sender.send_files(&small, &*socket).await.unwrap();
+        assert_eq!(manifest.files[0].first_segment, 1);
+
+        receiver.stop().await;
+        sender.stop();
+    }
+
+    #[tokio::test]
+    async fn test_incompatible_chunk_size_fails_handshake() {
+        let client_config = Config {
+            max_supported_chunk_size: 512,
+            ..Config::default()
+        };
+        let (sender, _socket, receiver, mut segment_rx) =
+            connect_pair(Config::default(), client_config).await;
+
+        // 서버는 연결하지 않고, 수신자는 사유와 함께 종료
+        let closed = tokio::time::timeout(Duration::from_secs(2), segment_rx.recv()).await;
+        assert!(matches!(closed, Ok(None)));
+        assert!(
+            matches!(&receiver.closed_error(), Error::InvalidInitAck(reason) if reason.contains("512")),
+            "{:?}",
+            receiver.closed_error()
+        );
+        assert_eq!(receiver.connection_state(), ConnectionState::Closed);
+        assert!(sender.client_addr().is_none());
+        sender.stop();
+    }
+
+    #[tokio::test]
+    async fn test_foreign_packets_not_assembled() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default();
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let (receiver, mut segment_rx) = Receiver::start(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+            path_manager,
+        )
+        .await
+        .unwrap();
+
+        // 매직 넘버 없는 이전 형식 청크 (완전한 1청크 세그먼트)
+        let chunk = Chunk::new(1, 0, 1, 0, 4, Bytes::from_static(b"evil"), 0, false);
+        let foreign = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        foreign
+            .send_to(&chunk.to_bytes()[4..], receiver.local_addr())
+            .await
+            .unwrap();
+        foreign
+            .send_to(b"GET / HTTP/1.1\r\n\r\n", receiver.local_addr())
+            .await
+            .unwrap();
+
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while receiver.foreign_packets() < 2 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        assert_eq!(receiver.foreign_packets(), 2);
+        assert!(segment_rx.try_recv().is_err());
+        assert_eq!(receiver.get_stats().await.total_chunks, 0);
+        receiver.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_withheld_segment_times_out_by_id() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            nack_timeout_ms: 10,
+            receive_segment_retries: 3,
+            ..Config::default()
+        };
+        let mut file_receiver =
+            FileReceiver::new(config, "127.0.0.1:0".parse().unwrap(), server.local_addr().unwrap())
+                .await
+                .unwrap();
+        let client = file_receiver.receiver().local_addr();
+        let deadline = file_receiver.receiver().segment_deadline();
+        assert_eq!(deadline, Duration::from_millis(160));
+        // 세그먼트가 드문드문 오던 전송은 도착 간격에 맞춰 더 기다림
+        assert_eq!(
+            file_receiver.receiver().segment_deadline_after(Some(Duration::from_millis(100))),
+            Duration::from_millis(800)
+        );
+
+        // 세그먼트 1, 2, 4만 보내고 3은 보내지 않음
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        for segment_id in [1, 2, 4] {
+            for chunk in builder.split_into_chunks(segment_id, &[segment_id as u8; 2500], 0) {
+                server.send_to(&chunk.to_bytes(), client).await.unwrap();
+            }
+        }
+
+        let started = Instant::now();
+        let result = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_file(4))
+            .await
+            .expect("재요청 횟수를 다 쓰면 포기해야 함");
+        assert!(matches!(result, Err(Error::SegmentTimeout { segment_id: 3 })), "{:?}", result);
+        assert!(started.elapsed() >= deadline * 4);
+
+        // 포기하기 전에 빠진 세그먼트 전체를 직접 요청
+        let mut buf = vec![0u8; 2048];
+        let mut requested = 0;
+        while let Ok((len, _)) = server.try_recv_from(&mut buf) {
+            if let Some(nack) = NackMessage::from_bytes(&buf[..len]) {
+                assert_eq!(nack.segment_id, 3);
+                assert!(nack.is_whole_segment());
+                requested += 1;
+            }
+        }
+        assert_eq!(requested, 3);
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_discard_withheld_segment_times_out_by_id() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            nack_timeout_ms: 10,
+            receive_segment_retries: 2,
+            ..Config::default()
+        };
+        let mut file_receiver =
+            FileReceiver::new(config, "127.0.0.1:0".parse().unwrap(), server.local_addr().unwrap())
+                .await
+                .unwrap();
+        let client = file_receiver.receiver().local_addr();
+        let deadline = file_receiver.receiver().segment_deadline();
+
+        // 세그먼트 1, 3, 4만 보내고 2는 보내지 않음 (받은 수가 아니라 빠진 ID로 보고)
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        for segment_id in [1, 3, 4] {
+            for chunk in builder.split_into_chunks(segment_id, &[segment_id as u8; 2500], 0) {
+                server.send_to(&chunk.to_bytes(), client).await.unwrap();
+            }
+        }
+
+        let started = Instant::now();
+        let result = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_discard(4))
+            .await
+            .expect("재요청 횟수를 다 쓰면 포기해야 함");
+        assert!(
+            matches!(result, Err(Error::SegmentTimeout { segment_id: 2 })),
+            "{:?}",
+            result.map(|report| report.segments)
+        );
+        assert!(started.elapsed() >= deadline * 3);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let mut buf = vec![0u8; 2048];
+        let mut requested = 0;
+        while let Ok((len, _)) = server.try_recv_from(&mut buf) {
+            if let Some(nack) = NackMessage::from_bytes(&buf[..len]) {
+                assert_eq!(nack.segment_id, 2);
+                assert!(nack.is_whole_segment());
+                requested += 1;
+            }
+        }
+        assert_eq!(requested, 2);
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_segment_complete_reports_counts() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default();
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let (receiver, mut segment_rx) = Receiver::start(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+            path_manager,
+        )
+        .await
+        .unwrap();
+
+        // 청크 0은 중복 전송 청크로 한 번 더 도착
+        let data = vec![9u8; 3000];
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(4, &data, 0);
+        let mut redundant = chunks[0].clone();
+        redundant.header.is_redundant = true;
+        for chunk in [&chunks[0], &redundant, &chunks[1], &chunks[2]] {
+            server
+                .send_to(&chunk.to_bytes(), receiver.local_addr())
+                .await
+                .unwrap();
+        }
+
+        let (id, _) = tokio::time::timeout(Duration::from_secs(1), segment_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(id, 4);
+
+        let mut buf = vec![0u8; 2048];
+        let complete = loop {
+            let (len, _) = tokio::time::timeout(Duration::from_secs(1), server.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            if let Some(complete) = SegmentCompleteMessage::from_bytes(&buf[..len]) {
+                break complete;
+            }
+        };
+        assert_eq!(complete.segment_id, 4);
+        assert_eq!(complete.total_chunks_received, 4);
+        assert_eq!(complete.duplicates_received, 1);
+        receiver.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_reordered_chunks_suppress_premature_nack() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 20,
+            ..Config::default()
+        };
+        let (completed_tx, mut completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let data = vec![5u8; 10_000];
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+
+        // 세그먼트 1: 청크 3이 손실 없이 80ms 늦게 도착 → 순서 역전 학습
+        let chunks = builder.split_into_chunks(1, &data, 0);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id != 3) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        inner.handle_chunk(chunks[3].clone()).await;
+        assert_eq!(completed_rx.try_recv().unwrap().0, 1);
+        assert_eq!(inner.stats.nic_stats[0].reorder_degree(), 6);
+        assert!(inner.stats.nic_stats[0].reorder_delay() >= Duration::from_millis(80));
+
+        // 세그먼트 2: nack_timeout은 지났지만 순서 역전 허용 시간 이내 → NACK 보류
+        let chunks = builder.split_into_chunks(2, &data, 0);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id != 3) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        inner.send_nacks().await;
+        assert_eq!(inner.stats.total_nacks, 0);
+        inner.handle_chunk(chunks[3].clone()).await;
+        assert_eq!(completed_rx.try_recv().unwrap().0, 2);
+
+        // 세그먼트 3: 허용 시간이 지나도 채워지지 않으면 손실로 보고 NACK
+        let chunks = builder.split_into_chunks(3, &data, 0);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id != 3) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        inner.send_nacks().await;
+        assert_eq!(inner.stats.total_nacks, 1);
+        assert_eq!(inner.stats.nic_stats[0].reordered_chunks, 2);
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_defers_only_new_segments() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 10,
+            delivery_window_segments: 2,
+            ..Config::default()
+        };
+        // 소비자가 가져가지 않는 완료 채널 (자리 1개)
+        let (completed_tx, mut completed_rx) = mpsc::channel(1);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let data = vec![3u8; 5000];
+        let segments: Vec<Vec<Chunk>> =
+            (1..=5).map(|id| builder.split_into_chunks(id, &data, 0)).collect();
+
+        // 세그먼트 4는 창이 차기 전에 열림
+        inner.handle_chunk(segments[3][0].clone()).await;
+        for chunks in &segments[..3] {
+            for chunk in chunks {
+                tokio::time::timeout(Duration::from_secs(1), inner.handle_chunk(chunk.clone()))
+                    .await
+                    .expect("느린 소비자 때문에 청크 처리가 멈추면 안 됨");
+            }
+        }
+        assert_eq!(inner.undelivered.len(), 2);
+        assert!(inner.delivery_window_full());
+
+        // 열린 세그먼트는 계속 조립, 새 세그먼트 5는 보류
+        for chunk in &segments[3][1..] {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        for chunk in &segments[4] {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        assert_eq!(inner.stats.deferred_admissions, 5);
+        assert!(!inner.segments.contains_key(&5));
+
+        // 제때 도착한 청크에 대한 NACK 없음
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        inner.send_nacks().await;
+        assert_eq!(inner.stats.total_nacks, 0);
+
+        // 직접 기다리는 세그먼트는 창이 차 있어도 받음
+        let (waiter, mut awaited) = oneshot::channel();
+        inner.await_segment(8, waiter);
+        for chunk in builder.split_into_chunks(8, &data, 0) {
+            inner.handle_chunk(chunk).await;
+        }
+        assert_eq!(awaited.try_recv().unwrap().unwrap().as_ref(), data.as_slice());
+
+        // 소비자가 따라잡으면 완료 순서대로 전달되고 새 세그먼트를 다시 받음
+        let mut delivered = Vec::new();
+        while let Ok((id, received)) = completed_rx.try_recv() {
+            assert_eq!(received.as_ref(), data.as_slice());
+            delivered.push(id);
+            inner.deliver_undelivered();
+        }
+        assert_eq!(delivered, vec![1, 2, 3, 4, 8]);
+        for chunk in &segments[4] {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        assert_eq!(completed_rx.try_recv().unwrap().0, 5);
+        assert_eq!(inner.stats.dropped_deliveries, 0);
+
+        // 소비자가 채널을 닫으면 넘기지 못한 세그먼트는 버린 수로 남음
+        drop(completed_rx);
+        let more: Vec<Vec<Chunk>> =
+            (6..=7).map(|id| builder.split_into_chunks(id, &data, 0)).collect();
+        for chunk in more.iter().flatten() {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        assert!(inner.undelivered.is_empty());
+        assert_eq!(inner.stats.dropped_deliveries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_redundant_copy_withdraws_pending_nack() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 10,
+            ..Config::default()
+        };
+        let (completed_tx, mut completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let chunks = builder.split_into_chunks(1, &[9u8; 10_000], 0);
+        for chunk in chunks.iter().filter(|c| ![3, 7].contains(&c.header.chunk_id)) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+
+        // 청크 3, 7 NACK
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        inner.send_nacks().await;
+        let mut buf = [0u8; 2048];
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!(NackMessage::from_bytes(&buf[..len]).unwrap().missing_chunk_ids, vec![3, 7]);
+
+        // NACK 직후 도착한 중복 전송 사본이 청크 3을 채움 → 3만 철회
+        let mut redundant = chunks[3].clone();
+        redundant.header.is_redundant = true;
+        inner.handle_chunk(redundant).await;
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        let cancel = NackCancelMessage::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(cancel, NackCancelMessage::new(1, 0, vec![3]));
+        let pending = &inner.segments[&1].pending_nack;
+        assert_eq!(pending.iter().copied().collect::<Vec<_>>(), vec![7]);
+
+        // 재전송으로 채워진 청크는 철회하지 않음 (세그먼트 완료 보고만)
+        inner.handle_chunk(chunks[7].clone()).await;
+        assert_eq!(completed_rx.try_recv().unwrap().0, 1);
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert!(NackCancelMessage::from_bytes(&buf[..len]).is_none());
+        assert!(SegmentCompleteMessage::from_bytes(&buf[..len]).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_awaited_segment_nacked_first_and_returned_early() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 10,
+            ..Config::default()
+        };
+        let (completed_tx, mut completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 8)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let segments: Vec<Vec<Chunk>> = (1..=4)
+            .map(|id| builder.split_into_chunks(id, &[id as u8; 10_000], 0))
+            .collect();
+        for chunks in &segments {
+            for chunk in chunks.iter().filter(|c| c.header.chunk_id != 3) {
+                inner.handle_chunk(chunk.clone()).await;
+            }
+        }
+
+        // 마지막 세그먼트를 기다리면 그 NACK이 먼저 나감
+        let (waiter, mut done) = oneshot::channel();
+        inner.await_segment(4, waiter);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        inner.send_nacks().await;
+        let mut buf = [0u8; 2048];
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!(NackMessage::from_bytes(&buf[..len]).unwrap().segment_id, 4);
+        assert_eq!(inner.stats.total_nacks, 4);
+
+        // 재전송이 도착하면 나머지 세그먼트가 미완료여도 바로 반환
+        inner.handle_chunk(segments[3][3].clone()).await;
+        let data = done.try_recv().unwrap().unwrap();
+        assert_eq!(data.as_ref(), &[4u8; 10_000][..]);
+        assert_eq!(completed_rx.try_recv().unwrap().0, 4);
+        assert_eq!(inner.segments.len(), 3);
+
+        // 이미 전달한 세그먼트는 기다리지 않음
+        let (waiter, mut done) = oneshot::channel();
+        inner.await_segment(4, waiter);
+        assert!(matches!(
+            done.try_recv().unwrap(),
+            Err(Error::SegmentAlreadyDelivered { segment_id: 4 })
+        ));
+    }
+
+    /// 청크 60%가 손실된 세그먼트를 라운드마다 3개씩 복구하며 NACK 업링크 바이트 합
+    /// (`accepted`: 서버가 InitAck으로 델타 NACK을 수락함)
+    async fn nack_uplink_bytes(delta_nacks: bool, accepted: bool) -> usize {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 5,
+            delta_nacks,
+            nack_resync_interval: 8,
+            ..Config::default()
+        };
+        let (completed_tx, _completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        inner.delta_nacks.store(accepted, Ordering::Relaxed);
+        let chunks = crate::chunk::SegmentBuilder::new(100).split_into_chunks(1, &[3u8; 10_000], 0);
+        let (received, mut lost): (Vec<_>, Vec<_>) =
+            chunks.into_iter().partition(|c| c.header.chunk_id % 5 >= 3);
+        for chunk in received {
+            inner.handle_chunk(chunk).await;
+        }
+        // 수신률이 낮아도 아직 전송 중으로 보지 않을 만큼 지난 뒤부터
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let mut bytes = 0;
+        let mut buf = [0u8; 2048];
+        for round in 0..10 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            inner.send_nacks().await;
+            let (len, _) = server.recv_from(&mut buf).await.unwrap();
+            bytes += len;
+
+            let expected: Vec<ChunkId> = lost.iter().map(|c| c.header.chunk_id).collect();
+            if delta_nacks && accepted {
+                let nack = NackDeltaMessage::from_bytes(&buf[..len]).unwrap();
+                // 첫 보고와 resync 주기마다 전체, 그 사이는 직전 라운드에 채워진 3개만 제거
+                assert_eq!(nack.full, round % 8 == 0, "round {}", round);
+                if nack.full {
+                    assert_eq!(nack.added, expected);
+                } else {
+                    assert_eq!((nack.added.len(), nack.removed.len()), (0, 3));
+                }
+            } else {
+                assert_eq!(NackMessage::from_bytes(&buf[..len]).unwrap().missing_chunk_ids, expected);
+            }
+            for chunk in lost.drain(..3) {
+                inner.handle_chunk(chunk).await;
+            }
+        }
+        bytes
+    }
+
+    #[tokio::test]
+    async fn test_delta_nacks_shrink_uplink_under_steady_loss() {
+        let full = nack_uplink_bytes(false, true).await;
+        let delta = nack_uplink_bytes(true, true).await;
+        assert!(delta * 2 < full, "delta {} bytes vs full {} bytes", delta, full);
+
+        // 서버가 수락하지 않으면 (델타를 모르는 서버) 전체 NACK만
+        assert_eq!(nack_uplink_bytes(true, false).await, full);
+    }
+
+    #[tokio::test]
+    async fn test_loss_within_fec_capacity_needs_no_nack() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 10,
+            ..Config::default()
+        };
+        let (completed_tx, mut completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            // FEC로 바꾼다는 ModeChange를 받지 못한 상태
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 253) as u8).collect();
+
+        // 세그먼트 1: 원본 2개 손실, 패리티 3개 → NACK 없이 복원
+        let chunks = builder.split_into_chunks(1, &data, 0);
+        let parity = fec::encode_parity(&chunks, 0.3);
+        assert_eq!(parity.len(), 3);
+        for chunk in chunks.iter().filter(|c| ![2, 8].contains(&c.header.chunk_id)) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        for chunk in &parity {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        let (id, received) = completed_rx.try_recv().unwrap();
+        assert_eq!((id, received.as_ref()), (1, data.as_slice()));
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        inner.send_nacks().await;
+        assert_eq!(inner.stats.total_nacks, 0);
+        assert_eq!(inner.stats.fec_recovered_chunks, 2);
+        let mut buf = [0u8; 2048];
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        assert!(SegmentCompleteMessage::from_bytes(&buf[..len]).is_some());
+
+        // 세그먼트 2: 원본 5개 손실, 패리티 3개 → 패리티로 못 채우는 2개만 NACK
+        let chunks = builder.split_into_chunks(2, &data, 0);
+        let parity = fec::encode_parity(&chunks, 0.3);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id >= 5) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        for chunk in &parity {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        inner.send_nacks().await;
+        assert_eq!(inner.stats.total_nacks, 1);
+        let (len, _) = server.recv_from(&mut buf).await.unwrap();
+        let nack = NackMessage::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(nack.missing_chunk_ids, vec![0, 1]);
+
+        // 재전송 2개가 도착하면 나머지는 패리티로 복원
+        inner.handle_chunk(chunks[0].clone()).await;
+        inner.handle_chunk(chunks[1].clone()).await;
+        let (id, received) = completed_rx.try_recv().unwrap();
+        assert_eq!((id, received.as_ref()), (2, data.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_late_chunks_for_completed_segments_dropped() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config::default();
+        let total = COMPLETED_HISTORY as u64 + 100;
+        let (completed_tx, mut completed_rx) = mpsc::channel(total as usize + 8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(1000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let chunk_of = |id| builder.split_into_chunks(id, &[id as u8; 1000], 0).remove(0);
+
+        // 최근 완료 기록 한도보다 많은 세그먼트 완료, 마지막 하나는 순서를 앞질러 완료
+        inner.handle_chunk(chunk_of(total + 1)).await;
+        for id in 1..=total {
+            inner.handle_chunk(chunk_of(id)).await;
+        }
+        let mut delivered = 0;
+        while completed_rx.try_recv().is_ok() {
+            delivered += 1;
+        }
+        assert_eq!(delivered, total + 1);
+        assert_eq!(inner.completed.through, total + 1);
+        assert!(inner.completed.ids.is_empty());
+
+        // 오래전/최근 완료 세그먼트의 늦은 청크: 상태를 다시 만들지 않고 중복으로 집계
+        let redundant = inner.stats.redundant_chunks;
+        for id in [1, 2, total / 2, total, total + 1] {
+            inner.handle_chunk(chunk_of(id)).await;
+        }
+        assert!(inner.segments.is_empty());
+        assert_eq!(inner.stats.total_segments, total + 1);
+        assert_eq!(inner.stats.redundant_chunks, redundant + 5);
+        assert!(completed_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_uplink_silence_counts_whole_connection_and_rtt() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            uplink_silent_nack_rounds: 3,
+            ..Config::default()
+        };
+        let (completed_tx, _completed_rx) = mpsc::channel(16);
+        let base = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(1000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let mut idle = base.shard(0, 2);
+        let mut busy = base.shard(1, 2);
+        let builder = crate::chunk::SegmentBuilder::new(100);
+
+        // 다른 샤드의 세그먼트로 청크가 오는 동안에는 조용한 라운드로 세지 않음
+        for id in 0..8u64 {
+            busy.handle_chunk(builder.split_into_chunks(id * 2 + 1, &[1u8; 1000], 0).remove(0))
+                .await;
+            assert!(idle.uplink_responsive());
+        }
+        assert!(!base.uplink_broken.load(Ordering::SeqCst));
+
+        // RTT가 길면 라운드 수를 채워도 재전송이 돌아올 시간(RTT × 2)까지 기다림
+        base.rtt_us.store(50_000, Ordering::Relaxed);
+        for _ in 0..5 {
+            assert!(idle.uplink_responsive());
+        }
+        tokio::time::sleep(Duration::from_millis(110)).await;
+        assert!(!idle.uplink_responsive());
+        assert!(base.uplink_broken.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_ids_kept_until_gap_fills() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config::default();
+        let (completed_tx, mut completed_rx) = mpsc::channel(16);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(1000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let chunk_of = |id| builder.split_into_chunks(id, &[id as u8; 1000], 0).remove(0);
+
+        // 1은 연속 구간, 3은 순서를 앞질러 완료
+        inner.handle_chunk(chunk_of(1)).await;
+        inner.handle_chunk(chunk_of(3)).await;
+        assert_eq!(inner.completed.through, 1);
+        assert!(inner.completed.ids.contains(&3));
+        while completed_rx.try_recv().is_ok() {}
+
+        // 빈 세그먼트 2가 완료될 때까지 3을 잊지 않으므로 늦은 재전송은 다시 전달되지 않음
+        inner.handle_chunk(chunk_of(3)).await;
+        inner.handle_chunk(chunk_of(1)).await;
+        assert_eq!(inner.stats.late_chunks, 2);
+        assert!(inner.segments.is_empty());
+        assert!(completed_rx.try_recv().is_err());
+
+        // 2가 완료되면 3까지 연속 구간에 합쳐져 집합이 비워짐
+        inner.handle_chunk(chunk_of(2)).await;
+        assert_eq!(completed_rx.try_recv().unwrap().0, 2);
+        assert_eq!(inner.completed.through, 3);
+        assert!(inner.completed.ids.is_empty());
+        inner.handle_chunk(chunk_of(3)).await;
+        assert_eq!(inner.stats.late_chunks, 3);
+        assert!(completed_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_segment_digest_catches_corruption_past_chunk_crc() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config::default();
+        let (completed_tx, mut completed_rx) = mpsc::channel(16);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(3000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let data: Vec<u8> = (0..3000u32).map(|i| (i / 1000) as u8 + 1).collect();
+        let files = vec![("a.bin".to_string(), Bytes::from(data.clone()))];
+        let manifest = Manifest::build(&files, 3000, 1).with_segment_digests(&files);
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+
+        for chunk in builder.split_into_chunks(MANIFEST_SEGMENT_ID, &manifest.to_bytes(), 0) {
+            inner.handle_chunk(chunk).await;
+        }
+        assert_eq!(completed_rx.try_recv().unwrap().0, MANIFEST_SEGMENT_ID);
+
+        let file_chunks = || {
+            let mut chunks = builder.split_into_chunks(1, &data, 0);
+            for chunk in &mut chunks {
+                chunk.header.file_id = 1;
+            }
+            chunks
+        };
+        // 오프셋이 뒤바뀐 두 청크: 데이터와 CRC는 그대로라 청크 검증은 통과
+        let mut corrupted = file_chunks();
+        let (first, second) = (corrupted[0].header.offset, corrupted[1].header.offset);
+        corrupted[0].header.offset = second;
+        corrupted[1].header.offset = first;
+        assert!(corrupted.iter().all(Chunk::verify_crc));
+        for chunk in corrupted {
+            inner.handle_chunk(chunk).await;
+        }
+
+        // 전달하지 않고 세그먼트 전체를 다시 요청
+        assert!(completed_rx.try_recv().is_err());
+        assert_eq!(inner.stats.digest_mismatches, 1);
+        let mut buf = [0u8; 2048];
+        let nack = loop {
+            let (len, _) = tokio::time::timeout(Duration::from_secs(1), server.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            // 매니페스트 세그먼트의 완료 보고는 건너뜀
+            if let Some(nack) = NackMessage::from_bytes(&buf[..len]) {
+                break nack;
+            }
+        };
+        assert_eq!((nack.segment_id, nack.missing_chunk_ids), (1, vec![0, 1, 2]));
+
+        // 재전송된 올바른 청크로 조립하면 전달
+        for chunk in file_chunks() {
+            inner.handle_chunk(chunk).await;
+        }
+        let (segment_id, delivered) = completed_rx.try_recv().unwrap();
+        assert_eq!((segment_id, delivered.as_ref()), (1, data.as_slice()));
+        assert!(inner.segment_digests.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_segment_completed_before_manifest_held_until_verified() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            segment_digests: true,
+            ..Config::default()
+        };
+        let (completed_tx, mut completed_rx) = mpsc::channel(16);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(3000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let data: Vec<u8> = (0..3000u32).map(|i| (i / 1000) as u8 + 1).collect();
+        let files = vec![("a.bin".to_string(), Bytes::from(data.clone()))];
+        let manifest = Manifest::build(&files, 3000, 1).with_segment_digests(&files);
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let file_chunks = || {
+            let mut chunks = builder.split_into_chunks(1, &data, 0);
+            for chunk in &mut chunks {
+                chunk.header.file_id = 1;
+            }
+            chunks
+        };
+
+        // 매니페스트가 유실돼 늦게 오는 사이 오프셋이 뒤바뀐 세그먼트가 완료
+        let mut corrupted = file_chunks();
+        let (first, second) = (corrupted[0].header.offset, corrupted[1].header.offset);
+        corrupted[0].header.offset = second;
+        corrupted[1].header.offset = first;
+        for chunk in corrupted {
+            inner.handle_chunk(chunk).await;
+        }
+        inner.send_nacks().await;
+        assert!(completed_rx.try_recv().is_err());
+        assert_eq!(inner.held.len(), 1);
+
+        // 매니페스트가 오면 보관한 세그먼트를 검증해 불일치를 잡음
+        for chunk in builder.split_into_chunks(MANIFEST_SEGMENT_ID, &manifest.to_bytes(), 0) {
+            inner.handle_chunk(chunk).await;
+        }
+        assert_eq!(completed_rx.try_recv().unwrap().0, MANIFEST_SEGMENT_ID);
+        inner.send_nacks().await;
+        assert!(completed_rx.try_recv().is_err());
+        assert!(inner.held.is_empty());
+        assert_eq!(inner.stats.digest_mismatches, 1);
+
+        for chunk in file_chunks() {
+            inner.handle_chunk(chunk).await;
+        }
+        let (segment_id, delivered) = completed_rx.try_recv().unwrap();
+        assert_eq!((segment_id, delivered.as_ref()), (1, data.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_contradictory_chunk_rejected_without_corrupting_segment() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config::default();
+        let (completed_tx, mut completed_rx) = mpsc::channel(16);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(3000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let data: Vec<u8> = (0..3000u32).map(|i| i as u8).collect();
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(1, &data, 0);
+        for chunk in &chunks[..2] {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+
+        // 같은 청크 ID로 세그먼트 크기를 다르게 말하는 청크 (CRC는 유효)
+        let mut resized = chunks[2].clone();
+        resized.header.segment_size = 5000;
+        resized.data = Bytes::from(vec![0xEEu8; 1000]);
+        resized.header.crc32 = crc32fast::hash(&resized.data);
+        assert!(resized.verify_crc());
+        inner.handle_chunk(resized.clone()).await;
+        let segment = &inner.segments[&1].segment;
+        assert_eq!((segment.received_count, segment.total_size), (2, 3000));
+
+        // 세그먼트 자체도 분할 기준이 다른 청크는 넣지 않음
+        let mut recounted = chunks[2].clone();
+        recounted.header.total_chunks = 4;
+        let mut segment = Segment::new_for_receive(1, 3000, 3);
+        assert!(segment.contradicts(&recounted) && segment.contradicts(&resized));
+        assert!(!segment.insert_chunk(&recounted));
+        assert_eq!(segment.received_count, 0);
+
+        // 바이트 수보다 청크가 많다는 헤더는 세그먼트를 만들지 않음
+        let mut forged = chunks[0].clone();
+        forged.header.segment_id = 2;
+        forged.header.total_chunks = u32::MAX;
+        inner.handle_chunk(forged).await;
+        assert!(!inner.segments.contains_key(&2));
+        assert_eq!(inner.stats.inconsistent_chunks, 2);
+
+        // 진짜 마지막 청크로 원래 데이터 그대로 완료
+        inner.handle_chunk(chunks[2].clone()).await;
+        let (segment_id, delivered) = completed_rx.try_recv().unwrap();
+        assert_eq!((segment_id, delivered.as_ref()), (1, data.as_slice()));
+    }
+
+    #[tokio::test]
+    async fn test_sparse_marker_does_not_overwrite_received_chunks() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config::default();
+        let (completed_tx, mut completed_rx) = mpsc::channel(16);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(3000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let data: Vec<u8> = (0..3000u32).map(|i| i as u8).collect();
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(1, &data, 0);
+        inner.handle_chunk(chunks[0].clone()).await;
+
+        // 청크로 받는 중인 세그먼트는 표시로 채우지 않음
+        inner.handle_sparse(SparseSegmentMessage::new(0, 1, 0, 3000, 0)).await;
+        assert_eq!(inner.segments[&1].segment.received_count, 1);
+        assert!(completed_rx.try_recv().is_err());
+        for chunk in &chunks[1..] {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+        let (segment_id, delivered) = completed_rx.try_recv().unwrap();
+        assert_eq!((segment_id, delivered.as_ref()), (1, data.as_slice()));
+
+        // 처음 보는 세그먼트의 표시는 그대로 펼침
+        inner.handle_sparse(SparseSegmentMessage::new(0, 2, 0, 3000, 0)).await;
+        let (segment_id, delivered) = completed_rx.try_recv().unwrap();
+        assert_eq!((segment_id, delivered.as_ref()), (2, &[0u8; 3000][..]));
+        assert_eq!(inner.stats.sparse_segments, 1);
+    }
+
+    #[tokio::test]
+    async fn test_sharded_assembly_routes_segments_by_id() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            parallel_workers: 4,
+            nack_timeout_ms: 20,
+            uplink_silent_nack_rounds: 0,
+            ..Config::default()
+        };
+        let (receiver, mut segment_rx) = Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+
+        // 세그먼트 1~8을 섞어 보내되 세그먼트 6의 청크 3은 뺌
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let segments: Vec<Vec<Chunk>> = (1..=8u64)
+            .map(|id| builder.split_into_chunks(id, &[id as u8; 10_000], 0))
+            .collect();
+        for i in 0..10 {
+            for chunks in &segments {
+                if (chunks[i].header.segment_id, i) != (6, 3) {
+                    server.send_to(&chunks[i].to_bytes(), client_addr).await.unwrap();
+                }
+            }
+        }
+
+        // 빠진 청크가 있는 샤드만 NACK
+        let nack = loop {
+            let (len, _) = tokio::time::timeout(Duration::from_secs(1), server.recv_from(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            if let Some(nack) = NackMessage::from_bytes(&buf[..len]) {
+                break nack;
+            }
+        };
+        assert_eq!((nack.segment_id, nack.missing_chunk_ids), (6, vec![3]));
+        server.send_to(&segments[5][3].to_bytes(), client_addr).await.unwrap();
+
+        let mut received = Vec::new();
+        while received.len() < 8 {
+            let (segment_id, data) = tokio::time::timeout(Duration::from_secs(1), segment_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(data.as_ref(), &[segment_id as u8; 10_000]);
+            received.push(segment_id);
+        }
+        received.sort_unstable();
+        assert_eq!(received, (1..=8).collect::<Vec<_>>());
+
+        // 완료 후 늦은 사본은 담당 샤드가 버림
+        server.send_to(&segments[4][0].to_bytes(), client_addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // 통계와 완료 수는 샤드를 합친 값
+        let stats = receiver.get_stats().await;
+        assert_eq!(receiver.completed_segments(), 8);
+        assert_eq!((stats.total_segments, stats.completed_segments), (8, 8));
+        assert_eq!(stats.total_chunks, 80);
+        assert_eq!(stats.late_chunks, 1);
+        let shard_queues = |depths: &[QueueDepth]| {
+            depths.iter().filter(|depth| depth.name == "receiver_cmd").count()
+        };
+        assert_eq!(shard_queues(&receiver.queue_depths()), 4);
+        assert_eq!(shard_queues(&stats.queue_depths), 4);
+        receiver.stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_out_of_state_messages_ignored() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default();
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let (receiver, _segment_rx) = Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+            path_manager,
+        )
+        .await
+        .unwrap();
+        assert_eq!(receiver.connection_state(), ConnectionState::Handshaking);
+
+        // 핸드셰이크 전 ModeChange는 무시
+        let mode_change = ModeChangeMessage::new(RedundancyMode::Fec).to_bytes();
+        server.send_to(&mode_change, receiver.local_addr()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(receiver.redundancy_mode(), RedundancyMode::Duplicate);
+
+        // InitAck 이후에는 반영
+        let ack = InitAckMessage::new(0, config.chunk_size as u16, config.segment_size as u32, 0.1);
+        server.send_to(&ack.to_bytes(), receiver.local_addr()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(receiver.connection_state(), ConnectionState::Transferring);
+
+        server.send_to(&mode_change, receiver.local_addr()).await.unwrap();
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while receiver.redundancy_mode() != RedundancyMode::Fec && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(receiver.redundancy_mode(), RedundancyMode::Fec);
+
+        receiver.stop().await;
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while receiver.connection_state() != ConnectionState::Closed && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(receiver.connection_state(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_pause_requires_server_and_connection_id() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let spoofer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default();
+        let (receiver, _segment_rx) = Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut buf = [0u8; 2048];
+        let (len, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let connection_id = InitMessage::from_bytes(&buf[..len]).unwrap().session_id;
+        let ack = InitAckMessage::new(0, config.chunk_size as u16, config.segment_size as u32, 0.1);
+        server.send_to(&ack.to_bytes(), client_addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(receiver.connection_state(), ConnectionState::Transferring);
+
+        // 다른 연결 ID나 서버가 아닌 주소에서 온 Pause로는 멈추지 않음
+        let forged = PauseMessage::pause(connection_id ^ 1).to_bytes();
+        server.send_to(&forged, client_addr).await.unwrap();
+        let spoofed = PauseMessage::pause(connection_id).to_bytes();
+        spoofer.send_to(&spoofed, client_addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!receiver.is_paused());
+
+        server.send_to(&spoofed, client_addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(receiver.is_paused());
+        let resume = PauseMessage::resume(connection_id).to_bytes();
+        server.send_to(&resume, client_addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!receiver.is_paused());
+
+        receiver.stop().await;
+    }
+
+    /// 시뮬레이터 위 송신자 + FileReceiver (핸드셰이크 완료 후 반환)
+    async fn sim_file_transfer(
+        config: &Config,
+    ) -> (
+        Arc<crate::sender::Sender>,
+        Arc<crate::sim::SimSocket>,
+        FileReceiver,
+        tokio::task::JoinHandle<Result<()>>,
+    ) {
+        use crate::sender::Sender;
+        use crate::sim::{SimConfig, SimNetwork};
+
+        let net = SimNetwork::new(SimConfig::default());
+        let server_addr: SocketAddr = "10.2.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.2.0.2:5000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind(client_addr)),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sender.client_addr() != Some(client_addr) && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        (sender, server_socket, file_receiver, handle)
+    }
+
+    /// 세그먼트를 1ms 간격으로 전송하는 태스크 (세그먼트 ID 목록 반환)
+    fn spawn_segments(
+        sender: &Arc<crate::sender::Sender>,
+        socket: &Arc<crate::sim::SimSocket>,
+        segments: Vec<Bytes>,
+    ) -> tokio::task::JoinHandle<Vec<SegmentId>> {
+        let sender = sender.clone();
+        let socket = socket.clone();
+        tokio::spawn(async move {
+            let mut ids = Vec::new();
+            for segment in segments {
+                ids.push(sender.send_data(segment, &*socket).await.unwrap());
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            ids
+        })
+    }
+
+    #[tokio::test]
+    async fn test_discrete_messages_keep_boundaries() {
+        let config = Config {
+            segment_size: 4096,
+            ..Config::default()
+        };
+        let (sender, server_socket, file_receiver, handle) = sim_file_transfer(&config).await;
+        let mut messages = file_receiver.into_messages().unwrap();
+
+        // 세그먼트 하나, 여러 세그먼트, 빈 메시지, 세그먼트 크기와 같은 메시지
+        let sent: Vec<Bytes> = vec![
+            Bytes::from_static(b"hello"),
+            (0..10_000).map(|i| (i % 251) as u8).collect(),
+            Bytes::new(),
+            Bytes::from(vec![7u8; 4096]),
+            Bytes::from_static(b"!"),
+        ];
+        let mut last_segments = Vec::new();
+        for message in &sent {
+            let segment_id = sender.send_message(message.clone(), &*server_socket).await.unwrap();
+            last_segments.push(segment_id);
+        }
+        assert_eq!(last_segments, vec![1, 4, 5, 6, 7]);
+
+        for expected in &sent {
+            let message = tokio::time::timeout(Duration::from_secs(5), messages.next_message())
+                .await
+                .expect("메시지 수신 시간 초과")
+                .unwrap();
+            assert_eq!(message.as_ref(), Some(expected));
+        }
+        assert!(messages.receiver().boundaries.lock().is_empty());
+
+        // 메시지 경계에서 연결이 끝나면 정상 종료
+        messages.receiver().stop().await;
+        assert_eq!(messages.next_message().await.unwrap(), None);
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_boundaries_pruned_without_message_consumer() {
+        let config = Config {
+            segment_size: 4096,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        // 메시지로 보냈어도 세그먼트 단위로 소비하면 경계 기록은 넘길 때 지워짐
+        for _ in 0..3 {
+            sender.send_message(Bytes::from_static(b"msg"), &*server_socket).await.unwrap();
+        }
+        let report = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_discard(3))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.segments, 3);
+        assert!(file_receiver.receiver().boundaries.lock().is_empty());
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_discard_mode_keeps_memory_flat() {
+        let config = Config {
+            segment_pool_capacity: 8,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        // 64KB × 200 = 12.8MB 전송
+        const SEGMENTS: usize = 200;
+        let payload: Vec<u8> = (0..config.segment_size).map(|i| (i % 249) as u8).collect();
+        let send_task = spawn_segments(
+            &sender,
+            &server_socket,
+            vec![Bytes::from(payload.clone()); SEGMENTS],
+        );
+
+        let report = file_receiver.receive_discard(SEGMENTS).await.unwrap();
+        let ids = send_task.await.unwrap();
+
+        assert_eq!(report.segments, SEGMENTS as u64);
+        assert_eq!(report.bytes, (SEGMENTS * config.segment_size) as u64);
+        assert_eq!(
+            report.digest,
+            TransferReport::expected_digest(ids.iter().map(|&id| (id, payload.as_slice())))
+        );
+
+        // 수신 버퍼는 풀 용량 안에서 재사용 (세그먼트 수만큼 할당하지 않음)
+        let pool = file_receiver.receiver().pool_stats();
+        assert!(pool.misses <= 2 * config.segment_pool_capacity as u64, "{:?}", pool);
+        assert!(pool.hits >= (SEGMENTS - 2 * config.segment_pool_capacity) as u64);
+        assert!(pool.in_flight <= config.segment_pool_capacity);
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_receive_into_assembles_in_place() {
+        let config = Config {
+            segment_size: 16 * 1024,
+            segment_pool_capacity: 4,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        // 마지막 세그먼트는 짧음
+        let data: Vec<u8> = (0..50 * config.segment_size + 777)
+            .map(|i| (i * 31 % 256) as u8)
+            .collect();
+        let segments = data
+            .chunks(config.segment_size)
+            .map(Bytes::copy_from_slice)
+            .collect();
+        let send_task = spawn_segments(&sender, &server_socket, segments);
+
+        let mut buf = vec![0u8; data.len()];
+        let written = file_receiver.receive_into(&mut buf).await.unwrap();
+        send_task.await.unwrap();
+
+        assert_eq!(written, data.len());
+        assert!(buf == data);
+
+        // 제자리 복사 후 세그먼트 버퍼는 모두 회수되어 재사용됨
+        let pool = file_receiver.receiver().pool_stats();
+        assert!(pool.misses <= 2 * config.segment_pool_capacity as u64, "{:?}", pool);
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_receive_into_variable_segment_sizes() {
+        let config = Config {
+            segment_size: 16 * 1024,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        // 적응형 세그먼트 크기처럼 세그먼트마다 길이가 다름 (고정 크기 가정이면 어긋남)
+        let sizes = [4096, 16384, 1024, 9000, 16384, 3, 12000, 777];
+        let data: Vec<u8> = (0..sizes.iter().sum::<usize>())
+            .map(|i| (i * 17 % 253) as u8)
+            .collect();
+        let mut offset = 0;
+        let segments = sizes
+            .iter()
+            .map(|&len| {
+                let segment = Bytes::copy_from_slice(&data[offset..offset + len]);
+                offset += len;
+                segment
+            })
+            .collect();
+        let send_task = spawn_segments(&sender, &server_socket, segments);
+
+        let mut buf = vec![0u8; data.len()];
+        let written = file_receiver.receive_into(&mut buf).await.unwrap();
+        send_task.await.unwrap();
+
+        assert_eq!(written, data.len());
+        assert!(buf == data);
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_custom_sink_hashes_segments_in_order() {
+        use sha2::{Digest, Sha256};
+
+        /// 도착한 세그먼트를 바로 해시에 반영하는 사용자 정의 출력 대상
+        #[derive(Default)]
+        struct HashSink {
+            hasher: Sha256,
+            writes: Vec<(SegmentId, u64, usize)>,
+            digest: Option<[u8; 32]>,
+        }
+
+        impl OutputSink for HashSink {
+            fn write_segment(&mut self, segment_id: SegmentId, offset: u64, data: &[u8]) -> Result<()> {
+                self.hasher.update(data);
+                self.writes.push((segment_id, offset, data.len()));
+                Ok(())
+            }
+
+            fn finish(&mut self) -> Result<()> {
+                self.digest = Some(self.hasher.clone().finalize().into());
+                Ok(())
+            }
+        }
+
+        let config = Config {
+            segment_size: 16 * 1024,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        let sizes = [16384, 4096, 16384, 1, 9000];
+        let data: Vec<u8> = (0..sizes.iter().sum::<usize>())
+            .map(|i| (i * 13 % 241) as u8)
+            .collect();
+        let mut offset = 0;
+        let segments = sizes
+            .iter()
+            .map(|&len| {
+                let segment = Bytes::copy_from_slice(&data[offset..offset + len]);
+                offset += len;
+                segment
+            })
+            .collect();
+        let send_task = spawn_segments(&sender, &server_socket, segments);
+
+        let mut sink = HashSink::default();
+        let written = file_receiver
+            .receive_to_sink(&mut sink, sizes.len())
+            .await
+            .unwrap();
+        send_task.await.unwrap();
+
+        assert_eq!(written, data.len() as u64);
+        let expected: [u8; 32] = Sha256::digest(&data).into();
+        assert_eq!(sink.digest, Some(expected));
+
+        // ID 순서, 오프셋은 앞 세그먼트 길이의 누적 합
+        let mut offset = 0;
+        for (i, &(segment_id, at, len)) in sink.writes.iter().enumerate() {
+            assert_eq!((segment_id, at, len), (i as SegmentId + 1, offset, sizes[i]));
+            offset += len as u64;
+        }
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_three_files_in_one_session() {
+        let config = Config {
+            segment_size: 16 * 1024,
+            ..Config::default()
+        };
+        let (sender, server_socket, mut file_receiver, handle) = sim_file_transfer(&config).await;
+
+        let pattern = |len: usize, seed: usize| -> Bytes {
+            (0..len).map(|i| ((i * seed + seed) % 251) as u8).collect::<Vec<_>>().into()
+        };
+        let files = vec![
+            // 여러 세그먼트 + 짧은 마지막 세그먼트
+            ("big.bin".to_string(), pattern(5 * config.segment_size + 321, 7)),
+            ("nested/small.txt".to_string(), pattern(100, 13)),
+            ("empty".to_string(), Bytes::new()),
+        ];
+
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let files_task = files.clone();
+        let send_task =
+            tokio::spawn(async move { sender_task.send_files(&files_task, &*socket_task).await });
+
+        let dest = tempfile::tempdir().unwrap();
+        let reports = file_receiver.receive_files(dest.path()).await.unwrap();
+        let manifest = send_task.await.unwrap().unwrap();
+
+        assert_eq!(reports.len(), 3);
+        for ((path, data), (report, entry)) in files.iter().zip(reports.iter().zip(&manifest.files)) {
+            assert_eq!(report.file_id, entry.file_id);
+            assert_eq!(report.path, dest.path().join(path));
+            assert_eq!(report.bytes, data.len() as u64);
+            assert!(report.verified, "{:?}", report);
+            assert_eq!(std::fs::read(&report.path).unwrap(), data.as_ref());
+        }
+
+        // 한 세션에 매니페스트는 하나
+        assert!(matches!(
+            sender.send_files(&files, &*server_socket).await,
+            Err(Error::InvalidManifest(_))
+        ));
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_early_segments_wait_for_manifest() {
+        let config = Config {
+            segment_size: 1024,
+            ..Config::default()
+        };
+        let (sender, server_socket, file_receiver, handle) = sim_file_transfer(&config).await;
+        let FileReceiver { receiver, .. } = file_receiver;
+        let dest = tempfile::tempdir().unwrap();
+        let data: Bytes = (0..(MAX_EARLY_SEGMENTS + 1) * 1024).map(|i| (i % 251) as u8).collect();
+        let files = vec![("early.bin".to_string(), data.clone())];
+
+        // 매니페스트보다 파일 세그먼트가 먼저 완료 채널에 쌓임
+        let (segment_tx, segment_rx) = mpsc::channel(MAX_EARLY_SEGMENTS + 1);
+        for (index, segment) in data.chunks(1024).enumerate() {
+            let segment_id = index as SegmentId + 1;
+            segment_tx.send((segment_id, data.slice_ref(segment))).await.unwrap();
+        }
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+        let dest_path = dest.path().to_path_buf();
+        let receive = tokio::spawn(async move {
+            let reports = file_receiver.receive_files(&dest_path).await;
+            (file_receiver, reports)
+        });
+
+        // 보관 한도에서 완료 채널을 더 꺼내지 않고 기다림 (실패하지 않음)
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while segment_tx.capacity() < MAX_EARLY_SEGMENTS && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(segment_tx.capacity(), MAX_EARLY_SEGMENTS);
+        assert!(!receive.is_finished());
+
+        // 매니페스트가 완료되면 보관한 세그먼트부터 이어서 기록
+        let send_task = tokio::spawn(async move { sender.send_files(&files, &*server_socket).await });
+        let (file_receiver, reports) = tokio::time::timeout(Duration::from_secs(10), receive)
+            .await
+            .unwrap()
+            .unwrap();
+        let reports = reports.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].verified, "{:?}", reports[0]);
+        assert_eq!(std::fs::read(&reports[0].path).unwrap(), data.as_ref());
+
+        send_task.abort();
+        file_receiver.receiver().stop().await;
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_untrusted_manifest_bounded_before_writing() {
+        let config = Config {
+            segment_size: 16 * 1024,
+            max_accept_file_size: 64 * 1024,
+            ..Config::default()
+        };
+        let (sender, _server_socket, file_receiver, handle) = sim_file_transfer(&config).await;
+        let FileReceiver { receiver, .. } = file_receiver;
+        let dest = tempfile::tempdir().unwrap();
+
+        // 한도를 넘는 파일 크기는 출력 파일을 만들기 전에 거부
+        let (segment_tx, segment_rx) = mpsc::channel(1);
+        let big = vec![("big.bin".to_string(), Bytes::from(vec![0u8; 64 * 1024 + 1]))];
+        let manifest = Manifest::build(&big, config.segment_size, 1);
+        segment_tx
+            .send((MANIFEST_SEGMENT_ID, Bytes::from(manifest.to_bytes())))
+            .await
+            .unwrap();
+        let mut files = FileReceiver::from_receiver(receiver, segment_rx);
+        assert!(matches!(
+            files.receive_files(dest.path()).await,
+            Err(Error::FileTooLarge { size, max: 65536 }) if size == 64 * 1024 + 1
+        ));
+        assert_eq!(std::fs::read_dir(dest.path()).unwrap().count(), 0);
+
+        files.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_fixed_local_port() {
+        let probe = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = probe.local_addr().unwrap().port();
+        drop(probe);
+
+        let config = Config {
+            local_port: port,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let (receiver, _segment_rx) = Receiver::start(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            "127.0.0.1:9".parse().unwrap(),
+            path_manager,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(receiver.local_addr().port(), port);
+        receiver.stop().await;
+    }
+
+    /// 세그먼트 1에서 청크 4만 빼고 보냄 (첫 패킷 Init에서 클라이언트 주소 확인)
+    async fn send_segment_with_gap(server: &UdpSocket) -> (SocketAddr, Vec<Chunk>) {
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(1, &[3u8; 10_000], 0);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id != 4) {
+            server.send_to(&chunk.to_bytes(), client_addr).await.unwrap();
+        }
+        (client_addr, chunks)
+    }
+
+    #[tokio::test]
+    async fn test_redundancy_effectiveness_splits_useful_and_wasted() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut file_receiver = FileReceiver::new(
+            Config::default(),
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(1, &[7u8; 10_000], 0);
+        let copy = |id: usize| {
+            let mut chunk = chunks[id].clone();
+            chunk.header.is_redundant = true;
+            chunk.to_bytes()
+        };
+
+        // 원본 2, 5 손실, 9는 사본보다 늦게 도착
+        for chunk in chunks.iter().filter(|c| ![2, 5, 9].contains(&c.header.chunk_id)) {
+            server.send_to(&chunk.to_bytes(), client_addr).await.unwrap();
+        }
+        for id in [7, 8, 9, 2] {
+            server.send_to(&copy(id), client_addr).await.unwrap();
+        }
+        server.send_to(&chunks[9].to_bytes(), client_addr).await.unwrap();
+        server.send_to(&copy(5), client_addr).await.unwrap();
+
+        let report = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_discard(1))
+            .await
+            .expect("수신 시간 초과")
+            .unwrap();
+        assert_eq!(report.stats.redundant_useful, 2);
+        assert_eq!(report.stats.redundant_wasted, 3);
+        assert_eq!(report.redundancy_effectiveness(), Some(0.4));
+    }
+
+    #[tokio::test]
+    async fn test_stubborn_segment_escalates_then_fails_at_hard_deadline() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            nack_timeout_ms: 20,
+            uplink_silent_nack_rounds: 0,
+            max_stalled_nacks: 0,
+            assembly_soft_deadline_ms: 150,
+            assembly_hard_deadline_ms: 400,
+            ..Config::default()
+        };
+        let mut file_receiver = FileReceiver::new(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        // FEC 모드에서 청크 4가 끝내 오지 않는 세그먼트 (패리티도 손실)
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let chunks = crate::chunk::SegmentBuilder::new(1000).split_into_chunks(1, &[3u8; 10_000], 0);
+        let started = Instant::now();
+        server.send_to(&chunks[0].to_bytes(), client_addr).await.unwrap();
+        let mode = ModeChangeMessage::new(RedundancyMode::Fec).to_bytes();
+        server.send_to(&mode, client_addr).await.unwrap();
+        for chunk in chunks.iter().skip(1).filter(|c| c.header.chunk_id != 4) {
+            server.send_to(&chunk.to_bytes(), client_addr).await.unwrap();
+        }
+
+        // 1단계: 누락 청크만 요청, 2단계(소프트 마감 후): 패리티도 요청
+        let mut stages = Vec::new();
+        let receive = tokio::spawn(async move { file_receiver.receive_discard(1).await });
+        while let Ok(Ok((len, _))) =
+            tokio::time::timeout(Duration::from_millis(200), server.recv_from(&mut buf)).await
+        {
+            let Some(nack) = NackMessage::from_bytes(&buf[..len]) else {
+                continue;
+            };
+            let stage = if nack.missing_chunk_ids == [4] { 1 } else { 2 };
+            if stage == 2 {
+                assert_eq!(nack.missing_chunk_ids, [4, 10]);
+                assert!(started.elapsed() >= Duration::from_millis(150));
+            }
+            if stages.last() != Some(&stage) {
+                stages.push(stage);
+            }
+        }
+        assert_eq!(stages, [1, 2]);
+
+        // 3단계: 하드 마감에 영구 실패
+        let report = receive.await.unwrap().unwrap();
+        assert_eq!(report.failed_segments, vec![(1, 2)]);
+        assert_eq!(report.stats.escalated_segments, 1);
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(400) && elapsed < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_broken_uplink_detected_quickly() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            nack_timeout_ms: 20,
+            uplink_silent_nack_rounds: 3,
+            ..Config::default()
+        };
+        let mut file_receiver = FileReceiver::new(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        // 이후 NACK은 서버가 읽지 않음 (업링크 단절)
+        send_segment_with_gap(&server).await;
+        let started = Instant::now();
+        let result = file_receiver.receive_discard(1).await;
+        assert!(
+            matches!(result, Err(Error::UplinkLikelyBroken { nack_rounds: 3 })),
+            "{:?}",
+            result.map(|report| report.segments)
+        );
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(file_receiver.receiver().uplink_broken());
+        assert!(!file_receiver.receiver().is_running());
+    }
+
+    #[tokio::test]
+    async fn test_stalled_nacks_give_up_scaled_by_rtt() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let config = Config {
+            nack_timeout_ms: 5,
+            uplink_silent_nack_rounds: 0,
+            max_stalled_nacks: 2,
+            ..Config::default()
+        };
+        let (completed_tx, _completed_rx) = mpsc::channel(8);
+        let mut inner = ReceiverInner::new(
+            config.clone(),
+            server.local_addr().unwrap(),
+            socket,
+            completed_tx,
+            Arc::new(PathManager::new(config)),
+            Arc::new(SegmentBufferPool::new(10_000, 4)),
+            Arc::new(AtomicBool::new(false)),
+        );
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        let chunks = builder.split_into_chunks(1, &[9u8; 10_000], 0);
+        for chunk in chunks.iter().filter(|c| c.header.chunk_id != 4) {
+            inner.handle_chunk(chunk.clone()).await;
+        }
+
+        // RTT 100ms 경로: NACK 주기(5ms)로 횟수를 채워도 RTT × 2가 지나기 전에는 포기하지 않음
+        inner.rtt_us.store(100_000, Ordering::Relaxed);
+        for _ in 0..6 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            inner.send_nacks().await;
+        }
+        assert!(!inner.failed.contains(1));
+        assert!(inner.segments.contains_key(&1));
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        inner.send_nacks().await;
+        assert!(inner.failed.contains(1));
+    }
+
+    /// 세그먼트 1~3을 보내되 세그먼트 2의 청크 4는 끝내 보내지 않는 수신자
+    async fn receiver_with_undeliverable_segment() -> (FileReceiver, UdpSocket) {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            nack_timeout_ms: 20,
+            uplink_silent_nack_rounds: 0,
+            max_stalled_nacks: 3,
+            ..Config::default()
+        };
+        let file_receiver = FileReceiver::new(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let builder = crate::chunk::SegmentBuilder::new(1000);
+        for segment_id in 1..=3 {
+            let chunks = builder.split_into_chunks(segment_id, &[segment_id as u8; 10_000], 0);
+            for chunk in &chunks {
+                if (segment_id, chunk.header.chunk_id) != (2, 4) {
+                    server.send_to(&chunk.to_bytes(), client_addr).await.unwrap();
+                }
+            }
+        }
+        (file_receiver, server)
+    }
+
+    #[tokio::test]
+    async fn test_undeliverable_segment_reported_not_hung() {
+        let (mut file_receiver, _server) = receiver_with_undeliverable_segment().await;
+
+        let started = Instant::now();
+        let report = file_receiver.receive_discard(3).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!((report.segments, report.bytes), (2, 20_000));
+        assert_eq!(report.failed_segments, vec![(2, 3)]);
+        assert_eq!(report.missing_bytes, vec![(10_000, 20_000)]);
+        assert!(!report.is_complete());
+        assert_eq!(file_receiver.receiver().failed_segments(), vec![2]);
+        assert!(matches!(
+            file_receiver.receiver().await_segment(2).await,
+            Err(Error::SegmentUndeliverable { segment_id: 2 })
+        ));
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_file_reports_exact_gaps() {
+        let (mut file_receiver, _server) = receiver_with_undeliverable_segment().await;
+        match file_receiver.receive_file(3).await {
+            Err(Error::IncompleteTransfer {
+                missing_segments,
+                missing_bytes,
+            }) => {
+                assert_eq!(missing_segments, vec![(2, 3)]);
+                assert_eq!(missing_bytes, vec![(10_000, 20_000)]);
+            }
+            other => panic!("{:?}", other.map(|data| data.len())),
+        }
+        file_receiver.receiver().stop().await;
+
+        // 부분 전달을 고르면 빈 자리를 0으로 채운 데이터와 같은 구간을 받음
+        let (mut file_receiver, _server) = receiver_with_undeliverable_segment().await;
+        let (data, report) = file_receiver.receive_file_partial(3).await.unwrap();
+        assert!(!report.complete);
+        assert_eq!((report.segments, report.failed_segments), (2, vec![(2, 3)]));
+        assert_eq!(report.missing_bytes, vec![(10_000, 20_000)]);
+        assert_eq!(data.len(), 30_000);
+        assert!(data[..10_000].iter().all(|&b| b == 1));
+        assert!(data[10_000..20_000].iter().all(|&b| b == 0));
+        assert!(data[20_000..].iter().all(|&b| b == 3));
+        file_receiver.receiver().stop().await;
+
+        // 맞닿은 빈 세그먼트는 한 구간으로, 크기가 달라도 누적 오프셋 기준
+        let layout = [(3, 5, false), (1, 10, true), (2, 4, false), (4, 7, true), (5, 2, false)];
+        assert_eq!(TransferReport::missing_byte_ranges(layout), vec![(10, 19), (26, 28)]);
+    }
+
+    #[tokio::test]
+    async fn test_poll_contiguous_waits_for_gap() {
+        let config = Config::default();
+        let (receiver, _segment_rx) = Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            "127.0.0.1:9".parse().unwrap(),
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        // 완료 순서를 정하려고 완료 채널을 직접 채움
+        let (completed_tx, completed_rx) = mpsc::channel(16);
+        let mut file_receiver = FileReceiver::from_receiver(receiver, completed_rx);
+        let segment = |id: u8| Bytes::from(vec![id; 4]);
+
+        // 2, 3이 먼저 완료: 1이 빠져 있으므로 아무것도 내주지 않음
+        completed_tx.send((2, segment(2))).await.unwrap();
+        completed_tx.send((3, segment(3))).await.unwrap();
+        assert!(file_receiver.poll_contiguous().is_none());
+        assert_eq!(file_receiver.contiguous_segments(), 0);
+
+        // 1이 채워지면 1..=3을 한 번에
+        completed_tx.send((1, segment(1))).await.unwrap();
+        completed_tx.send((5, segment(5))).await.unwrap();
+        let prefix = file_receiver.poll_contiguous().unwrap();
+        assert_eq!(&prefix[..], &[1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3]);
+        assert_eq!(file_receiver.contiguous_segments(), 3);
+        // 이미 꺼낸 부분은 다시 내주지 않고, 4가 빠진 5는 보관만
+        assert!(file_receiver.poll_contiguous().is_none());
+
+        completed_tx.send((4, segment(4))).await.unwrap();
+        let next = file_receiver.poll_contiguous().unwrap();
+        assert_eq!(&next[..], &[4, 4, 4, 4, 5, 5, 5, 5]);
+        assert_eq!(file_receiver.contiguous_segments(), 5);
+        assert!(file_receiver.poll_contiguous().is_none());
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_ordered_receive_stops_at_gap_offset() {
+        let (mut file_receiver, _server) = receiver_with_undeliverable_segment().await;
+
+        let mut buf = vec![0u8; 30_000];
+        let result = file_receiver.receive_into(&mut buf).await;
+        assert!(
+            matches!(result, Err(Error::SegmentGap { segment_id: 2, offset: 10_000 })),
+            "{:?}",
+            result
+        );
+        assert!(buf[..10_000].iter().all(|&b| b == 1));
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_nack_moves_to_other_nic_when_uplink_silent() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let config = Config {
+            nack_timeout_ms: 20,
+            uplink_silent_nack_rounds: 3,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let nic = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let nic_addr = nic.local_addr().unwrap();
+        path_manager.add_nic_socket(Arc::new(nic), server_addr).unwrap();
+        let (receiver, segment_rx) = Receiver::start(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server_addr,
+            path_manager,
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        // 기본 경로의 NACK은 버리고, 다른 NIC로 온 NACK에만 응답
+        let (_, chunks) = send_segment_with_gap(&server).await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            loop {
+                let (len, addr) = server.recv_from(&mut buf).await.unwrap();
+                let Some(nack) = NackMessage::from_bytes(&buf[..len]) else {
+                    continue;
+                };
+                if addr != nic_addr {
+                    continue;
+                }
+                for &chunk_id in &nack.missing_chunk_ids {
+                    let data = chunks[chunk_id as usize].to_bytes();
+                    server.send_to(&data, addr).await.unwrap();
+                }
+                return;
+            }
+        });
+
+        let report = file_receiver.receive_discard(1).await.unwrap();
+        assert_eq!(report.segments, 1);
+        assert!(!file_receiver.receiver().uplink_broken());
+        responder.await.unwrap();
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_transfer_rejected_at_handshake() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config {
+            max_accept_file_size: 1024 * 1024,
+            ..Config::default()
+        };
+        let mut file_receiver = FileReceiver::new(
+            config,
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let ack = InitAckMessage::new(1 << 40, 1200, 65536, 0.15);
+        server.send_to(&ack.to_bytes(), client_addr).await.unwrap();
+
+        let result = file_receiver.receive_discard(1).await;
+        assert!(
+            matches!(result, Err(Error::FileTooLarge { size, max }) if size == 1 << 40 && max == 1024 * 1024),
+            "{:?}",
+            result.map(|report| report.bytes)
+        );
+        assert_eq!(file_receiver.receiver().connection_state(), ConnectionState::Closed);
+
+        // 서버에 Close로 알림
+        let close = loop {
+            let (len, _) = server.recv_from(&mut buf).await.unwrap();
+            let header = MessageHeader::from_bytes(&buf[..len]).unwrap();
+            if header.msg_type != MessageType::Init {
+                break header;
+            }
+        };
+        assert_eq!(close.msg_type, MessageType::Close);
+    }
+
+    #[tokio::test]
+    async fn test_inconsistent_init_ack_rejected() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut file_receiver = FileReceiver::new(
+            Config::default(),
+            "127.0.0.1:0".parse().unwrap(),
+            server.local_addr().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 2048];
+        let (_, client_addr) = server.recv_from(&mut buf).await.unwrap();
+
+        // 1MB 파일인데 세그먼트 100만 개로 부풀린 InitAck
+        let mut ack = InitAckMessage::new(1024 * 1024, 1200, 65536, 0.15);
+        assert!(ack.validate().is_ok());
+        ack.total_segments = 1_000_000;
+        assert!(ack.validate().is_err());
+        server.send_to(&ack.to_bytes(), client_addr).await.unwrap();
+
+        let result = file_receiver.receive_discard(16).await;
+        assert!(
+            matches!(&result, Err(Error::InvalidInitAck(reason)) if reason.contains("1000000")),
+            "{:?}",
+            result.map(|report| report.bytes)
+        );
+        assert_eq!(file_receiver.receiver().connection_state(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_clock_jump_rtt_sample_rejected() {
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = Config::default();
+        let start = || {
+            Receiver::start(
+                config.clone(),
+                "127.0.0.1:0".parse().unwrap(),
+                server.local_addr().unwrap(),
+                Arc::new(PathManager::new(config.clone())),
+            )
+        };
+        let mut buf = [0u8; 2048];
+
+        // 응답을 받기 전 클라이언트 시계가 1시간 뒤로 간 것처럼 미래 타임스탬프 에코
+        let (jumped, _rx) = start().await.unwrap();
+        let (len, client_addr) = server.recv_from(&mut buf).await.unwrap();
+        let init = InitMessage::from_bytes(&buf[..len]).unwrap();
+        let ack = InitAckMessage::with_client_timestamp(
+            0,
+            1200,
+            65536,
+            0.1,
+            init.timestamp_us + 3_600_000_000,
+        );
+        server.send_to(&ack.to_bytes(), client_addr).await.unwrap();
+        while jumped.connection_state() != ConnectionState::Transferring {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(jumped.rejected_rtt_samples(), 1);
+        assert_eq!(jumped.handshake_rtt(), None);
+        jumped.stop().await;
+
+        // 마지막 Init 에코는 단조 시계 왕복으로 측정
+        let (normal, _rx) = start().await.unwrap();
+        let init = loop {
+            let (len, addr) = server.recv_from(&mut buf).await.unwrap();
+            if addr != client_addr {
+                break InitMessage::from_bytes(&buf[..len]).map(|init| (init, addr));
+            }
+        };
+        let (init, addr) = init.unwrap();
+        let ack =
+            InitAckMessage::with_client_timestamp(0, 1200, 65536, 0.1, init.timestamp_us);
+        server.send_to(&ack.to_bytes(), addr).await.unwrap();
+        while normal.connection_state() != ConnectionState::Transferring {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(normal.rejected_rtt_samples(), 0);
+        assert!(normal.handshake_rtt().is_some_and(|rtt| rtt < Duration::from_secs(1)));
+        normal.stop().await;
     }
 }
@@ -0,0 +1,82 @@
+//! NDJSON 이벤트 기록기
+//!
+//! 한 줄에 JSON 객체 하나: `{"t_ms":..,"event":"..","data":{..}}`
+//! 혼잡제어 스냅샷 등을 주기적으로 남겨 사후 분석/튜닝에 사용
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Record<'a, T: Serialize> {
+    t_ms: u64,
+    event: &'a str,
+    data: &'a T,
+}
+
+/// NDJSON 기록기
+pub struct NdjsonRecorder<W: Write> {
+    writer: W,
+    started: Instant,
+}
+
+impl NdjsonRecorder<BufWriter<File>> {
+    /// 파일로 기록 (기존 파일은 덮어씀)
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> NdjsonRecorder<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            started: Instant::now(),
+        }
+    }
+
+    /// 이벤트 한 줄 기록
+    pub fn record<T: Serialize>(&mut self, event: &str, data: &T) -> io::Result<()> {
+        let record = Record {
+            t_ms: self.started.elapsed().as_millis() as u64,
+            event,
+            data,
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bbr::BbrLite;
+
+    #[test]
+    fn test_records_bbr_snapshot_lines() {
+        let mut recorder = NdjsonRecorder::new(Vec::new());
+        let bbr = BbrLite::new(0.001, 300_000_000.0);
+        recorder.record("bbr", &bbr.snapshot()).unwrap();
+        recorder.record("bbr", &bbr.snapshot()).unwrap();
+
+        let out = String::from_utf8(recorder.into_inner()).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(value["event"], "bbr");
+        assert_eq!(value["data"]["pacing_rate"], 300_000_000.0);
+        assert!(value["data"]["queue_ratio"].is_number());
+    }
+}
@@ -0,0 +1,295 @@
+//! 전송 재개 상태 파일
+//!
+//! 받은 세그먼트 비트맵을 디스크에 보관해 중단된 전송을 이어받을 때 사용.
+//! 충돌로 반쯤 기록된 파일 때문에 받지 않은 세그먼트를 건너뛰지 않도록 형식을 고정하고 검증:
+//!
+//! ```text
+//! magic(4) | version(1) | transfer_id(8) | file_digest(32) | segment_count(8)
+//! | bitmap_len(4) | bitmap | crc32(4, 앞의 모든 바이트)
+//! ```
+//!
+//! - 저장은 임시 파일에 쓰고 동기화한 뒤 이름 변경 (기존 상태 파일은 항상 완전한 형태로 남음)
+//! - 손상되었거나 다른 전송/파일의 상태 파일은 거부하고 처음부터 전송
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use tracing::warn;
+
+use crate::chunk::SegmentId;
+use crate::codec::{Reader, Writer};
+use crate::{Error, Result};
+
+/// 상태 파일 매직 넘버
+const RESUME_MAGIC: u32 = u32::from_le_bytes(*b"SFPR");
+
+/// 상태 파일 형식 버전
+const RESUME_VERSION: u8 = 1;
+
+/// 비트맵 앞 고정 헤더 길이
+const HEADER_LEN: usize = 4 + 1 + 8 + 32 + 8 + 4;
+
+/// 전송 재개 상태 (세그먼트 ID 1..=segment_count의 수신 여부)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResumeState {
+    transfer_id: u64,
+    file_digest: [u8; 32],
+    segment_count: u64,
+    bitmap: Vec<u8>,
+}
+
+impl ResumeState {
+    /// 아무 세그먼트도 받지 않은 상태
+    pub fn new(transfer_id: u64, file_digest: [u8; 32], segment_count: u64) -> Self {
+        Self {
+            transfer_id,
+            file_digest,
+            segment_count,
+            bitmap: vec![0u8; segment_count.div_ceil(8) as usize],
+        }
+    }
+
+    pub fn transfer_id(&self) -> u64 {
+        self.transfer_id
+    }
+
+    pub fn file_digest(&self) -> &[u8; 32] {
+        &self.file_digest
+    }
+
+    pub fn segment_count(&self) -> u64 {
+        self.segment_count
+    }
+
+    fn bit(&self, segment_id: SegmentId) -> Option<(usize, u8)> {
+        let index = segment_id.checked_sub(1).filter(|&i| i < self.segment_count)?;
+        Some(((index / 8) as usize, 1 << (index % 8)))
+    }
+
+    /// 세그먼트 수신 기록. 처음 기록한 경우 true
+    pub fn mark_received(&mut self, segment_id: SegmentId) -> bool {
+        let Some((byte, mask)) = self.bit(segment_id) else {
+            return false;
+        };
+        let fresh = self.bitmap[byte] & mask == 0;
+        self.bitmap[byte] |= mask;
+        fresh
+    }
+
+    pub fn is_received(&self, segment_id: SegmentId) -> bool {
+        self.bit(segment_id)
+            .is_some_and(|(byte, mask)| self.bitmap[byte] & mask != 0)
+    }
+
+    /// 받은 세그먼트 수
+    pub fn received_count(&self) -> u64 {
+        self.bitmap.iter().map(|b| b.count_ones() as u64).sum()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received_count() == self.segment_count
+    }
+
+    /// 아직 받지 않은 세그먼트 ID
+    pub fn missing(&self) -> impl Iterator<Item = SegmentId> + '_ {
+        (1..=self.segment_count).filter(|&id| !self.is_received(id))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer::with_capacity(HEADER_LEN + self.bitmap.len() + 4);
+        w.put_u32(RESUME_MAGIC);
+        w.put_u8(RESUME_VERSION);
+        w.put_u64(self.transfer_id);
+        w.put_slice(&self.file_digest);
+        w.put_u64(self.segment_count);
+        w.put_u32(self.bitmap.len() as u32);
+        w.put_slice(&self.bitmap);
+        let mut bytes = w.into_inner();
+        let crc = crc32fast::hash(&bytes);
+        bytes.extend_from_slice(&crc.to_le_bytes());
+        bytes
+    }
+
+    /// 형식/CRC 검증 후 복원
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidResumeState(reason.into());
+
+        let body_len = bytes
+            .len()
+            .checked_sub(4)
+            .ok_or_else(|| invalid("파일이 너무 짧음"))?;
+        let (body, crc) = bytes.split_at(body_len);
+        let mut r = Reader::new(body);
+
+        if r.get_u32() != Some(RESUME_MAGIC) {
+            return Err(invalid("매직 넘버 불일치"));
+        }
+        match r.get_u8() {
+            Some(RESUME_VERSION) => {}
+            Some(version) => return Err(invalid(&format!("지원하지 않는 버전 {}", version))),
+            None => return Err(invalid("파일이 너무 짧음")),
+        }
+        let truncated = || invalid("파일이 잘림");
+        let transfer_id = r.get_u64().ok_or_else(truncated)?;
+        let file_digest = r.get_array::<32>().ok_or_else(truncated)?;
+        let segment_count = r.get_u64().ok_or_else(truncated)?;
+        let bitmap_len = r.get_u32().ok_or_else(truncated)? as usize;
+        let bitmap = r.get_slice(bitmap_len).ok_or_else(truncated)?.to_vec();
+
+        if r.remaining() != 0 {
+            return Err(invalid("비트맵 뒤에 남는 바이트"));
+        }
+        if crc32fast::hash(body).to_le_bytes() != crc {
+            return Err(invalid("CRC 불일치"));
+        }
+        if bitmap_len as u64 != segment_count.div_ceil(8) {
+            return Err(invalid("비트맵 길이와 세그먼트 수 불일치"));
+        }
+
+        Ok(Self {
+            transfer_id,
+            file_digest,
+            segment_count,
+            bitmap,
+        })
+    }
+
+    /// 상태 파일 저장 (임시 파일 기록 → 동기화 → 이름 변경)
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let tmp = temp_path(path);
+        {
+            let mut file = File::create(&tmp)?;
+            file.write_all(&self.to_bytes())?;
+            file.sync_all()?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// 상태 파일 읽기. 손상되었거나 다른 전송/파일의 상태면 `InvalidResumeState`
+    pub fn load(path: impl AsRef<Path>, transfer_id: u64, file_digest: &[u8; 32]) -> Result<Self> {
+        let state = Self::from_bytes(&fs::read(path)?)?;
+        if state.transfer_id != transfer_id {
+            return Err(Error::InvalidResumeState(format!(
+                "다른 전송의 상태 (transfer_id {} != {})",
+                state.transfer_id, transfer_id
+            )));
+        }
+        if &state.file_digest != file_digest {
+            return Err(Error::InvalidResumeState("파일 다이제스트 불일치".into()));
+        }
+        Ok(state)
+    }
+
+    /// 이어받을 수 있으면 저장된 상태, 없거나 쓸 수 없으면 빈 상태 (전체 전송)
+    pub fn load_or_new(
+        path: impl AsRef<Path>,
+        transfer_id: u64,
+        file_digest: [u8; 32],
+        segment_count: u64,
+    ) -> Self {
+        let path = path.as_ref();
+        match Self::load(path, transfer_id, &file_digest) {
+            Ok(state) if state.segment_count == segment_count => state,
+            Ok(state) => {
+                warn!(
+                    "재개 상태의 세그먼트 수 불일치 ({} != {}), 전체 전송: {}",
+                    state.segment_count,
+                    segment_count,
+                    path.display()
+                );
+                Self::new(transfer_id, file_digest, segment_count)
+            }
+            Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                Self::new(transfer_id, file_digest, segment_count)
+            }
+            Err(e) => {
+                warn!("재개 상태 사용 불가, 전체 전송: {} ({})", path.display(), e);
+                Self::new(transfer_id, file_digest, segment_count)
+            }
+        }
+    }
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ResumeState {
+        let mut state = ResumeState::new(42, [7u8; 32], 20);
+        for id in [1, 2, 3, 9, 20] {
+            assert!(state.mark_received(id));
+        }
+        assert!(!state.mark_received(3));
+        assert!(!state.mark_received(0));
+        assert!(!state.mark_received(21));
+        state
+    }
+
+    #[test]
+    fn test_load_valid_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.resume");
+        let state = sample();
+        state.save(&path).unwrap();
+
+        let loaded = ResumeState::load(&path, 42, &[7u8; 32]).unwrap();
+        assert_eq!(loaded, state);
+        assert_eq!(loaded.received_count(), 5);
+        assert!(loaded.is_received(9) && !loaded.is_received(10));
+        assert_eq!(loaded.missing().count(), 15);
+        assert_eq!(ResumeState::load_or_new(&path, 42, [7u8; 32], 20), state);
+    }
+
+    #[test]
+    fn test_truncated_or_corrupt_state_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.resume");
+        let bytes = sample().to_bytes();
+
+        // 충돌로 잘린 파일: 어느 길이에서 잘려도 거부
+        for len in 0..bytes.len() {
+            fs::write(&path, &bytes[..len]).unwrap();
+            assert!(
+                matches!(ResumeState::load(&path, 42, &[7u8; 32]), Err(Error::InvalidResumeState(_))),
+                "len {}",
+                len
+            );
+        }
+
+        // 비트맵 비트 하나 뒤집힘 → CRC 불일치
+        let mut corrupt = bytes.clone();
+        corrupt[HEADER_LEN] ^= 0x10;
+        fs::write(&path, &corrupt).unwrap();
+        assert!(ResumeState::load(&path, 42, &[7u8; 32]).is_err());
+
+        // 전체 전송으로 대체
+        let fresh = ResumeState::load_or_new(&path, 42, [7u8; 32], 20);
+        assert_eq!(fresh.received_count(), 0);
+    }
+
+    #[test]
+    fn test_mismatched_digest_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("transfer.resume");
+        sample().save(&path).unwrap();
+
+        assert!(ResumeState::load(&path, 42, &[8u8; 32]).is_err());
+        assert!(ResumeState::load(&path, 43, &[7u8; 32]).is_err());
+        let fresh = ResumeState::load_or_new(&path, 42, [8u8; 32], 20);
+        assert_eq!(fresh.received_count(), 0);
+        assert_eq!(fresh.file_digest(), &[8u8; 32]);
+
+        // 상태 파일이 없으면 조용히 처음부터
+        let missing = ResumeState::load_or_new(dir.path().join("none"), 1, [0u8; 32], 3);
+        assert_eq!(missing.missing().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+}
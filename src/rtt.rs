@@ -0,0 +1,206 @@
+//! RTT 표본 검증
+//!
+//! 벽시계(`SystemTime`) 타임스탬프를 빼서 구한 RTT는 클라이언트 시계가 어긋나거나 전송 중
+//! NTP가 시각을 건너뛰면 음수나 터무니없는 값이 되어 혼잡 제어를 조용히 오염시킴.
+//! 왕복을 같은 프로세스에서 잴 수 있으면 단조 시계(`Instant`)를 쓰고, 벽시계 차이는
+//! 범위를 검사해 통과한 표본만 반영
+//!
+//! - 에코된 타임스탬프가 현재보다 뒤: 시계가 뒤로 감 (`RttRejection::Negative`)
+//! - `MAX_PLAUSIBLE_RTT` 초과: 시계가 앞으로 뛰었거나 엉뚱한 에코 (`RttRejection::Implausible`)
+//!
+//! 통과한 표본은 `SmoothedRtt`(RFC 6298의 srtt/rttvar)로 평활해 타이머 간격에 사용.
+//! 최소 RTT는 경로가 느려져도 줄기만 하므로 간격 계산에는 쓰지 않음
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+/// 받아들이는 RTT 상한
+pub const MAX_PLAUSIBLE_RTT: Duration = Duration::from_secs(10);
+
+/// RTT 표본을 버린 이유
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RttRejection {
+    /// 에코된 타임스탬프 없음
+    Missing,
+    /// 음수 RTT (시계가 뒤로 감)
+    Negative,
+    /// `MAX_PLAUSIBLE_RTT`를 넘는 RTT
+    Implausible,
+}
+
+/// 현재 벽시계 시각 (UNIX epoch 기준 마이크로초, 시계가 epoch 이전이면 0)
+pub fn wall_clock_now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+/// 단조 시계로 잰 왕복 시간 범위 검사
+pub fn check_rtt(rtt: Duration) -> Result<Duration, RttRejection> {
+    if rtt > MAX_PLAUSIBLE_RTT {
+        return Err(RttRejection::Implausible);
+    }
+    Ok(rtt)
+}
+
+/// 보낸 시각 `sent_us`가 에코되어 `now_us`에 돌아왔을 때의 벽시계 RTT
+pub fn wall_clock_rtt(sent_us: u64, now_us: u64) -> Result<Duration, RttRejection> {
+    if sent_us == 0 {
+        return Err(RttRejection::Missing);
+    }
+    let rtt_us = now_us.checked_sub(sent_us).ok_or(RttRejection::Negative)?;
+    check_rtt(Duration::from_micros(rtt_us))
+}
+
+/// RTT 표본 필터 (버린 표본 수 집계)
+#[derive(Debug, Default)]
+pub struct RttSampleFilter {
+    rejected: AtomicU64,
+}
+
+impl RttSampleFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 통과한 표본 반환. 시계 문제로 버린 표본은 경고와 함께 집계
+    /// (에코가 없는 것은 시계 문제가 아니므로 세지 않음)
+    pub fn accept(&self, sample: Result<Duration, RttRejection>) -> Option<Duration> {
+        match sample {
+            Ok(rtt) => Some(rtt),
+            Err(RttRejection::Missing) => None,
+            Err(reason) => {
+                self.rejected.fetch_add(1, Ordering::Relaxed);
+                warn!(?reason, "RTT 표본 거부: 시계 점프 의심");
+                None
+            }
+        }
+    }
+
+    /// 버린 표본 수
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+}
+
+/// 평활 RTT 이득 (RFC 6298 alpha)
+const SRTT_GAIN: f64 = 1.0 / 8.0;
+
+/// RTT 변동 이득 (RFC 6298 beta)
+const RTTVAR_GAIN: f64 = 1.0 / 4.0;
+
+/// 평활 RTT 추정 (RFC 6298)
+///
+/// 표본이 오기 전에는 초기값을 돌려주고 첫 표본이 초기값을 대체함
+#[derive(Debug, Clone, Copy)]
+pub struct SmoothedRtt {
+    srtt: Duration,
+    rttvar: Duration,
+    sampled: bool,
+}
+
+impl SmoothedRtt {
+    /// 표본 전에 쓸 초기 RTT로 생성
+    pub fn new(initial: Duration) -> Self {
+        Self {
+            srtt: initial,
+            rttvar: initial / 2,
+            sampled: false,
+        }
+    }
+
+    /// 표본 반영 (0은 무시)
+    pub fn on_sample(&mut self, rtt: Duration) {
+        if rtt.is_zero() {
+            return;
+        }
+        if !self.sampled {
+            self.srtt = rtt;
+            self.rttvar = rtt / 2;
+            self.sampled = true;
+            return;
+        }
+        let srtt = self.srtt.as_secs_f64();
+        let sample = rtt.as_secs_f64();
+        let rttvar = self.rttvar.as_secs_f64() * (1.0 - RTTVAR_GAIN)
+            + (srtt - sample).abs() * RTTVAR_GAIN;
+        self.rttvar = Duration::from_secs_f64(rttvar);
+        self.srtt = Duration::from_secs_f64(srtt * (1.0 - SRTT_GAIN) + sample * SRTT_GAIN);
+    }
+
+    /// 평활 RTT
+    pub fn srtt(&self) -> Duration {
+        self.srtt
+    }
+
+    /// RTT 변동
+    pub fn rttvar(&self) -> Duration {
+        self.rttvar
+    }
+
+    /// 재전송 판단 간격 (srtt + 4 × rttvar)
+    pub fn timeout(&self) -> Duration {
+        self.srtt + self.rttvar * 4
+    }
+
+    /// 표본을 받은 적이 있는지
+    pub fn is_sampled(&self) -> bool {
+        self.sampled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_jump_samples_rejected() {
+        let filter = RttSampleFilter::new();
+        let sent = 1_700_000_000_000_000;
+
+        // 정상 왕복 30ms
+        assert_eq!(
+            filter.accept(wall_clock_rtt(sent, sent + 30_000)),
+            Some(Duration::from_millis(30))
+        );
+
+        // 응답 전에 시계가 5초 뒤로 감 → 음수
+        assert_eq!(wall_clock_rtt(sent, sent - 5_000_000), Err(RttRejection::Negative));
+        assert_eq!(filter.accept(wall_clock_rtt(sent, sent - 5_000_000)), None);
+
+        // 시계가 1시간 앞으로 뜀
+        assert_eq!(
+            wall_clock_rtt(sent, sent + 3_600_000_000),
+            Err(RttRejection::Implausible)
+        );
+        assert_eq!(filter.accept(wall_clock_rtt(sent, sent + 3_600_000_000)), None);
+
+        // 에코 없음은 시계 문제로 세지 않음
+        assert_eq!(filter.accept(wall_clock_rtt(0, sent)), None);
+        assert_eq!(filter.accept(check_rtt(MAX_PLAUSIBLE_RTT)), Some(MAX_PLAUSIBLE_RTT));
+        assert_eq!(filter.rejected(), 2);
+    }
+
+    #[test]
+    fn test_smoothed_rtt_tracks_rising_rtt() {
+        let mut rtt = SmoothedRtt::new(Duration::from_millis(100));
+        assert!(!rtt.is_sampled());
+        assert_eq!(rtt.timeout(), Duration::from_millis(300));
+
+        // 첫 표본이 초기값을 대체
+        rtt.on_sample(Duration::from_millis(10));
+        assert_eq!((rtt.srtt(), rtt.rttvar()), (Duration::from_millis(10), Duration::from_millis(5)));
+
+        // 경로가 느려지면 최소값과 달리 따라 올라감
+        for _ in 0..50 {
+            rtt.on_sample(Duration::from_millis(80));
+        }
+        assert!(rtt.srtt() > Duration::from_millis(75), "{:?}", rtt.srtt());
+        assert!(rtt.timeout() >= rtt.srtt());
+        rtt.on_sample(Duration::ZERO);
+        assert!(rtt.srtt() > Duration::from_millis(75));
+    }
+}
@@ -0,0 +1,183 @@
+//! 루프백 자체 점검
+//!
+//! 같은 프로세스 안에서 송신자와 수신자를 루프백으로 연결해 생성한 페이로드를 보내고
+//! 무결성(세그먼트 다이제스트), 처리율, 손실률, 오버헤드를 보고.
+//! 사용자 진단(`sfp-server --selftest`)과 CI 스모크 테스트를 겸함
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bytes::Bytes;
+use tokio::net::UdpSocket;
+
+use crate::multipath::PathManager;
+use crate::receiver::{FileReceiver, Receiver};
+use crate::sender::Sender;
+use crate::stats::{TransferReport, TransferStats};
+use crate::{Config, Error, Result};
+
+/// 연결/완료 보고 대기 한도
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 완료 보고를 기다리지 않고 보낼 수 있는 데이터 양. 루프백 소켓의 기본 수신 버퍼 안에
+/// 들어가야 세그먼트가 통째로 버려지지 않음 (통째로 유실된 세그먼트는 수신자가 존재를 몰라
+/// NACK할 수 없음)
+const MAX_IN_FLIGHT_BYTES: usize = 64 * 1024;
+
+/// 자체 점검 결과
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    /// 수신측 결과 (세그먼트 수, 바이트, 소요 시간, 다이제스트)
+    pub transfer: TransferReport,
+
+    /// 송신측 통계 (중복/재전송 청크)
+    pub sender: TransferStats,
+
+    /// 수신 다이제스트가 원본과 일치하는지
+    pub verified: bool,
+}
+
+impl SelfTestReport {
+    /// 처리율 (bytes/sec)
+    pub fn throughput(&self) -> f64 {
+        self.transfer.throughput()
+    }
+
+    /// 수신자가 NACK으로 보고한 손실률
+    pub fn loss_rate(&self) -> f64 {
+        self.transfer.stats.overall_loss_rate()
+    }
+
+    /// 원본 청크 대비 추가로 보낸 청크 비율 (중복 + 재전송)
+    pub fn overhead(&self) -> f64 {
+        let extra = self.sender.redundant_chunks + self.sender.retransmitted_chunks;
+        let original = self.sender.total_chunks.saturating_sub(extra);
+        if original == 0 {
+            return 0.0;
+        }
+        extra as f64 / original as f64
+    }
+
+    /// 한 줄 요약
+    pub fn summary(&self) -> String {
+        format!(
+            "{} {} bytes / {:.3}s, {:.1} MB/s, 손실 {:.2}%, 오버헤드 {:.1}%",
+            if self.verified { "통과" } else { "무결성 실패" },
+            self.transfer.bytes,
+            self.transfer.elapsed.as_secs_f64(),
+            self.throughput() / 1_000_000.0,
+            self.loss_rate() * 100.0,
+            self.overhead() * 100.0
+        )
+    }
+}
+
+/// 루프백으로 `payload_bytes` 바이트를 전송하고 결과 반환
+pub async fn run_selftest(config: &Config, payload_bytes: usize) -> Result<SelfTestReport> {
+    let server_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+    let server_addr = server_socket.local_addr()?;
+
+    let sender = Arc::new(Sender::new(
+        config.clone(),
+        Arc::new(PathManager::new(config.clone())),
+    ));
+    let sender_task = sender.clone();
+    let socket_task = server_socket.clone();
+    let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+    let result = transfer(config, &sender, &server_socket, server_addr, payload_bytes).await;
+
+    sender.stop();
+    handle.abort();
+    result
+}
+
+async fn transfer(
+    config: &Config,
+    sender: &Arc<Sender>,
+    server_socket: &Arc<UdpSocket>,
+    server_addr: SocketAddr,
+    payload_bytes: usize,
+) -> Result<SelfTestReport> {
+    let (receiver, segment_rx) = Receiver::start(
+        config.clone(),
+        "127.0.0.1:0".parse().expect("루프백 주소"),
+        server_addr,
+        Arc::new(PathManager::new(config.clone())),
+    )
+    .await?;
+    let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+    if !settle(|| sender.client_addr().is_some()).await {
+        file_receiver.receiver().stop().await;
+        return Err(Error::ConnectionClosed);
+    }
+
+    let payload: Vec<u8> = (0..payload_bytes).map(|i| (i * 131 % 251) as u8).collect();
+    let segments: Vec<Bytes> = payload
+        .chunks(config.segment_size.max(1))
+        .map(Bytes::copy_from_slice)
+        .collect();
+    let expected_segments = segments.len();
+
+    let window = (MAX_IN_FLIGHT_BYTES / config.segment_size.max(1))
+        .clamp(1, config.max_concurrent_segments.max(1)) as u64;
+    let send_sender = sender.clone();
+    let send_socket = server_socket.clone();
+    let send_segments = segments.clone();
+    let send_task = tokio::spawn(async move {
+        let mut ids = Vec::with_capacity(send_segments.len());
+        for segment in send_segments {
+            while in_flight(&send_sender) >= window {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            ids.push(send_sender.send_data(segment, &*send_socket).await?);
+        }
+        Ok::<_, Error>(ids)
+    });
+
+    let received = file_receiver.receive_discard(expected_segments).await;
+    let ids = send_task
+        .await
+        .map_err(|e| Error::Unknown(format!("송신 태스크 실패: {}", e)))?;
+    let transfer = match (received, ids) {
+        (Ok(transfer), Ok(ids)) => {
+            let expected = TransferReport::expected_digest(
+                ids.iter().copied().zip(segments.iter().map(|s| s.as_ref())),
+            );
+            Ok((transfer, expected))
+        }
+        (Err(e), _) | (_, Err(e)) => Err(e),
+    };
+
+    // 송신측 통계에 마지막 완료 보고까지 반영
+    let expected_completed = expected_segments as u64;
+    settle(|| sender.get_stats().completed_segments >= expected_completed).await;
+    file_receiver.receiver().stop().await;
+
+    let (transfer, expected_digest) = transfer?;
+    Ok(SelfTestReport {
+        verified: transfer.digest == expected_digest && transfer.bytes == payload_bytes as u64,
+        transfer,
+        sender: sender.get_stats(),
+    })
+}
+
+/// 완료 보고를 받지 못한 세그먼트 수
+fn in_flight(sender: &Sender) -> u64 {
+    let stats = sender.get_stats();
+    stats.total_segments.saturating_sub(stats.completed_segments)
+}
+
+/// 조건이 참이 될 때까지 대기 (한도 초과 시 false)
+async fn settle(mut cond: impl FnMut() -> bool) -> bool {
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    while !cond() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    true
+}
@@ -4,27 +4,96 @@
 //! - Forward Redundancy
 //! - NIC 비율 기반 멀티패스
 
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::net::SocketAddr;
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use dashmap::DashMap;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use tokio::io::AsyncRead;
 use tokio::net::UdpSocket;
-use tracing::{debug, info, warn};
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, info_span, warn, Instrument, Span};
 
-use crate::chunk::{Chunk, ChunkId, SegmentBuilder, SegmentId};
-use crate::message::{InitAckMessage, MessageHeader, MessageType, NackMessage};
+use crate::buffertune::{self, BufferTuner};
+use crate::cache::SegmentCache;
+use crate::chunk::{
+    self, Chunk, ChunkBatch, ChunkHeader, ChunkId, FileId, PreparedSegment, SegmentBuilder, SegmentId,
+};
+use crate::crypto::MetadataAuth;
+use crate::egress::{EgressLimiter, EgressShare};
+use crate::fec::{self, FecModePolicy, RedundancyMode};
+use crate::guard::{ControlGuard, CookieStats};
+use crate::logsample::LogSampler;
+use crate::message::{
+    CookieMessage, GiveUpMessage, InitAckMessage, InitMessage, MessageHeader, MessageType,
+    ModeChangeMessage, HeartbeatMessage, NackCancelMessage, NackDeltaMessage, NackMessage,
+    NetworkChangeMessage, OfferMessage, PathChallengeMessage, PathResponseMessage, PauseMessage,
+    ProbeReportMessage, SegmentCompleteMessage, SparseSegmentMessage,
+};
+use crate::manifest::{Manifest, MANIFEST_FILE_ID, MANIFEST_SEGMENT_ID};
 use crate::multipath::PathManager;
+use crate::probe;
+use crate::readahead::{self, Readahead};
+use crate::rtt::{self, RttSampleFilter, SmoothedRtt};
+use crate::slowstart::SlowStart;
+use crate::state::{ConnectionState, ConnectionStateCell};
 use crate::stats::TransferStats;
+use crate::transport::{self, DatagramSocket, PeerUdpSocket};
 use crate::{Config, Error, Result, MAGIC_NUMBER};
 
+/// NACK의 수신률이 이 값 이상이면 즉시 재전송 (몇 청크만 채우면 완료되어 메모리 해제).
+/// 미만이면 재전송 큐에 넣고 주기 작업에서 수신률이 높은 세그먼트부터 처리
+const IMMEDIATE_RETRANSMIT_RATIO: f32 = 0.5;
+
+/// 수신자가 Init으로 응답할 때까지 Offer 재전송 간격
+const OFFER_RETRY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 응답 없는 수신자에게 Offer를 보내는 최대 횟수
+const OFFER_MAX_ATTEMPTS: u32 = 25;
+
+/// PMTU 블랙홀 대응으로 줄일 수 있는 최소 청크 크기
+const MIN_BLACKHOLE_CHUNK_SIZE: usize = 256;
+
+/// PMTU 탐침 크기마다 응답을 기다리며 보내는 횟수 (모두 응답이 없으면 크기를 절반으로)
+const PMTU_PROBE_ATTEMPTS: u32 = 2;
+
+/// 꼬리 손실 탐침 최소 간격 (RTT가 아주 짧아도 이보다 자주 보내지 않음)
+const TAIL_PROBE_MIN_INTERVAL: Duration = Duration::from_millis(20);
+
+/// 응답 없는 주소 이전 확인(PathChallenge) 재전송 간격
+const PATH_CHALLENGE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 연결된 소켓(`Config::connect_socket`)이 클라이언트 소식 없이 이만큼 지나면 연결 해제
+/// (커널이 버리던 새 주소의 Heartbeat를 받으려고)
+const CONNECTED_PEER_SILENCE: Duration = Duration::from_secs(1);
+
+/// 일시 정지 중 Pause 재통보 간격 (수신자는 통보가 끊기면 스스로 재개)
+const PAUSE_ANNOUNCE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 포기 통보를 다시 보내려고 기억하는 세그먼트 수 (오래된 것부터 잊음)
+const MAX_REMEMBERED_GIVE_UPS: usize = 1024;
+
+/// 포기 통보 하나에 싣는 청크 ID 상한
+const MAX_GIVE_UP_CHUNK_IDS: usize = 64;
+
+/// 균일 세그먼트 표시에 응답이 없을 때 청크로 다시 보내기 전까지의 꼬리 탐침 수
+const SPARSE_FALLBACK_PROBES: u32 = 3;
+
+/// 재전송 데이터그램 (처음 보낸 NIC ID, 바이트)
+type RetransmitDatagram = (u8, Vec<u8>);
+
 /// 세그먼트 전송 상태
 #[derive(Debug)]
 #[allow(dead_code)]
 struct SegmentState {
+    /// 세그먼트가 속한 파일 (매니페스트 세션)
+    file_id: FileId,
+
     /// 원본 청크들
     chunks: Vec<Chunk>,
 
@@ -42,6 +111,217 @@ struct SegmentState {
 
     /// 재전송 요청된 청크 ID
     retransmit_queue: Vec<ChunkId>,
+
+    /// NACK을 받은 적이 있는지 (손실 없는 전달 판정용)
+    nacked: bool,
+
+    /// 마지막 NACK이 보고한 수신률 (재전송 우선순위)
+    receive_ratio: f32,
+
+    /// 전송한 청크 수 (원본 + 중복 + 재전송)
+    sent_chunks: u32,
+
+    /// 이 세그먼트로 보낸 바이트 중 아직 미확인분 (`Sender::bytes_in_flight`에 합산됨)
+    bytes_in_flight: u64,
+
+    /// 델타 NACK으로 추적 중인 누락 집합과 마지막으로 적용한 보고 순번
+    nack_delta: Option<(u16, BTreeSet<ChunkId>)>,
+
+    /// 직전 NACK이 보고한 누락 청크 수
+    last_missing: usize,
+
+    /// 누락이 줄지 않은 채 이어진 NACK 수 (PMTU 블랙홀 감지)
+    stalled_nacks: u32,
+
+    /// 이 세그먼트의 데이터그램을 마지막으로 보낸 시각 (꼬리 손실 탐침)
+    last_sent: Instant,
+
+    /// 원본 청크를 마지막으로 보낸 시각 (완료 보고까지의 RTT 표본)
+    last_original_sent: Instant,
+
+    /// 보낸 꼬리 손실 탐침 수 (탐침 간격을 두 배씩 늘림)
+    tail_probes: u32,
+
+    /// 청크별 재전송 횟수와 마지막으로 센 시각 (`Config::max_chunk_retransmits`)
+    retransmits: HashMap<ChunkId, (u32, Instant)>,
+
+    /// 청크 대신 보낸 균일 세그먼트 표시와 그 데이터그램 (탐침/NACK에 다시 보냄)
+    sparse: Option<(SparseSegmentMessage, Vec<u8>)>,
+}
+
+impl SegmentState {
+    /// 재전송할 청크 (원본 범위 밖의 ID는 FEC 패리티 청크)
+    fn retransmit_chunk(&self, chunk_id: ChunkId) -> Option<&Chunk> {
+        self.chunks.get(chunk_id as usize).or_else(|| {
+            self.redundant_chunks
+                .iter()
+                .find(|chunk| fec::is_parity(chunk) && chunk.header.chunk_id == chunk_id)
+        })
+    }
+
+    /// 재전송할 청크마다 횟수를 세고 한도(`limit`, 0이면 무제한)를 넘긴 청크 반환
+    ///
+    /// 재전송이 도착하기 전(`srtt` 안)에 같은 청크를 다시 요청하는 NACK은 한 번으로 셈.
+    /// 그러지 않으면 NACK 주기가 RTT보다 짧은 경로에서 실제 손실 없이 한도를 넘김
+    fn charge_retransmits(
+        &mut self,
+        chunk_ids: &[ChunkId],
+        limit: u32,
+        srtt: Duration,
+    ) -> Vec<ChunkId> {
+        if limit == 0 {
+            return Vec::new();
+        }
+        let now = Instant::now();
+        chunk_ids
+            .iter()
+            .copied()
+            .filter(|&chunk_id| {
+                let (count, charged_at) = self.retransmits.entry(chunk_id).or_insert((0, now));
+                if *count == 0 || now.duration_since(*charged_at) >= srtt {
+                    *count += 1;
+                    *charged_at = now;
+                }
+                *count > limit
+            })
+            .collect()
+    }
+}
+
+/// 적응형 세그먼트 크기
+///
+/// 하한에서 시작해 손실 없이 완료된 세그먼트마다 25%씩 키우고,
+/// 손실(NACK) 시 절반으로 줄임. 크기는 청크 크기의 배수로 맞춤
+#[derive(Debug, Clone)]
+pub struct SegmentSizer {
+    current: usize,
+    min: usize,
+    max: usize,
+    chunk_size: usize,
+}
+
+impl SegmentSizer {
+    pub fn new(config: &Config) -> Self {
+        let adaptive =
+            config.min_segment_size > 0 && config.min_segment_size < config.max_segment_size;
+        let (min, max) = if adaptive {
+            (config.min_segment_size, config.max_segment_size)
+        } else {
+            (config.segment_size, config.segment_size)
+        };
+
+        Self {
+            current: min,
+            min,
+            max,
+            chunk_size: config.effective_chunk_size().max(1),
+        }
+    }
+
+    /// 다음 세그먼트 크기
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// 수신자가 알린 세그먼트 크기 한도 적용 (0이면 그대로)
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        if limit > 0 {
+            self.max = self.max.min(limit);
+            self.min = self.min.min(self.max);
+            self.current = self.current.min(self.max);
+        }
+        self
+    }
+
+    /// 적응 활성화 여부
+    pub fn is_adaptive(&self) -> bool {
+        self.min < self.max
+    }
+
+    /// 손실 없이 완료된 세그먼트
+    pub fn on_clean_delivery(&mut self) {
+        let grown = self.current + (self.current / 4).max(self.chunk_size);
+        self.current = self.align(grown);
+    }
+
+    /// 손실 발생 (세그먼트당 한 번)
+    pub fn on_loss(&mut self) {
+        self.current = self.align(self.current / 2);
+    }
+
+    fn align(&self, size: usize) -> usize {
+        let aligned = size.div_ceil(self.chunk_size) * self.chunk_size;
+        aligned.clamp(self.min, self.max)
+    }
+}
+
+/// 도달성 확인을 기다리는 새 클라이언트 주소 (한 번에 하나)
+#[derive(Debug, Clone, Copy)]
+struct PendingMigration {
+    addr: SocketAddr,
+    token: u64,
+    sent_at: Instant,
+}
+
+/// 진행 중인 PMTU 블랙홀 탐색 (한 번에 하나)
+///
+/// DPLPMTUD처럼 지금 청크 크기의 탐침부터 하나씩 보내고, 응답이 없으면 크기를 줄여 다시 보냄
+#[derive(Debug, Clone, Copy)]
+struct PmtuSearch {
+    /// 블랙홀을 의심한 세그먼트 (탐침이 통과한 크기로 다시 나눔)
+    segment_id: SegmentId,
+    /// 의심할 때의 청크 데이터 크기 (이 크기가 통과하면 블랙홀이 아님)
+    full_size: usize,
+    /// 지금 탐침하는 청크 데이터 크기
+    chunk_size: usize,
+    /// 응답(PathResponse)으로 돌아올 토큰
+    token: u64,
+    sent_at: Instant,
+    /// 지금 크기로 보낸 탐침 수
+    attempts: u32,
+}
+
+/// 일시 정지 상태 (`Sender::pause`)
+#[derive(Debug, Default)]
+struct PauseState {
+    /// 정지한 시각 (정지 중이 아니면 None)
+    since: Option<Instant>,
+    /// 마지막 Pause 통보 시각 (None이면 다음 주기 작업에서 바로 통보)
+    announced: Option<Instant>,
+    /// 재개 후 아직 Resume을 보내지 않음
+    resume_pending: bool,
+}
+
+/// 묶어 보낼 청크 데이터그램 (`Config::coalesce_chunks`)
+#[derive(Debug, Default)]
+struct CoalesceBuffer {
+    addr: Option<SocketAddr>,
+    records: Vec<Vec<u8>>,
+    /// 지금까지 묶은 데이터그램 크기 (비어 있으면 0)
+    len: usize,
+}
+
+impl CoalesceBuffer {
+    /// `record`를 더해도 `max_len` 안에 들어가는지
+    fn fits(&self, addr: SocketAddr, record: &[u8], max_len: usize) -> bool {
+        self.addr == Some(addr)
+            && self.len + ChunkBatch::RECORD_PREFIX_LEN + record.len() <= max_len
+    }
+
+    fn push(&mut self, addr: SocketAddr, record: &[u8]) {
+        if self.records.is_empty() {
+            self.addr = Some(addr);
+            self.len = ChunkBatch::PREFIX_LEN;
+        }
+        self.len += ChunkBatch::RECORD_PREFIX_LEN + record.len();
+        self.records.push(record.to_vec());
+    }
+
+    fn take(&mut self) -> Option<(SocketAddr, Vec<Vec<u8>>)> {
+        let addr = self.addr.take()?;
+        self.len = 0;
+        Some((addr, std::mem::take(&mut self.records)))
+    }
 }
 
 /// 송신자
@@ -49,6 +329,12 @@ pub struct Sender {
     /// 설정
     config: Config,
 
+    /// 일시 정지 상태
+    pause: Mutex<PauseState>,
+
+    /// 정지 여부 (재개를 기다리는 전송 경로를 깨움)
+    paused: watch::Sender<bool>,
+
     /// 경로 관리자
     path_manager: Arc<PathManager>,
 
@@ -58,6 +344,9 @@ pub struct Sender {
     /// 활성 세그먼트 상태
     segments: DashMap<SegmentId, SegmentState>,
 
+    /// 재전송을 포기한 세그먼트 → (파일, 한도를 넘긴 청크). 통보가 유실돼 NACK이 다시 오면 재통보
+    given_up: Mutex<BTreeMap<SegmentId, (FileId, Vec<ChunkId>)>>,
+
     /// 다음 세그먼트 ID
     next_segment_id: AtomicU64,
 
@@ -67,54 +356,216 @@ pub struct Sender {
     /// 현재 중복률
     current_redundancy: RwLock<f64>,
 
-    /// 실행 중 플래그
-    running: AtomicBool,
+    /// 수신 루프 종료 신호 (시작할 때마다 새로 만듦)
+    shutdown: RwLock<CancellationToken>,
+
+    /// 연결 상태
+    state: ConnectionStateCell,
 
     /// 클라이언트 주소
     client_addr: RwLock<Option<SocketAddr>>,
+
+    /// 컨트롤 경로 보호 (속도 제한 + Init 쿠키)
+    guard: ControlGuard,
+
+    /// 중복/FEC 모드 전환 정책
+    fec_policy: RwLock<FecModePolicy>,
+
+    /// 현재 클라이언트의 세션 ID (NACK/완료 보고에 실리는 연결 ID)
+    client_session: RwLock<Option<u64>>,
+
+    /// 같은 연결 ID로 새 주소에서 나타난 클라이언트 (PathResponse 대기)
+    pending_migration: Mutex<Option<PendingMigration>>,
+
+    /// 이전 확인을 시작할 수 있는 인증된 Heartbeat의 최소 sequence (엿본 Heartbeat 재전송 거부)
+    next_heartbeat_sequence: AtomicU64,
+
+    /// 소켓이 클라이언트 주소에 연결되어 있는지
+    peer_connected: AtomicBool,
+
+    /// 확인된 클라이언트 주소에서 마지막으로 메시지를 받은 시각
+    client_heard: Mutex<Instant>,
+
+    /// 적응형 세그먼트 크기
+    segment_sizer: RwLock<SegmentSizer>,
+
+    /// 클라이언트가 Init에 알린 세그먼트 크기 한도 (0이면 제한 없음)
+    peer_segment_limit: AtomicUsize,
+
+    /// 클라이언트가 Init에서 균일 세그먼트 표시 지원을 알렸는지
+    peer_sparse: AtomicBool,
+
+    /// 균일 세그먼트 표시 인증 키 (`Config::encryption_enabled`이면 없을 때 표시를 보내지 않음)
+    metadata_auth: RwLock<Option<MetadataAuth>>,
+
+    /// BDP 기반 소켓 버퍼 조정 (자동 조정을 끄면 None)
+    buffer_tuner: Option<RwLock<BufferTuner>>,
+
+    /// PMTU 블랙홀 감지로 낮춘 청크 크기 (0이면 설정값)
+    chunk_size_limit: AtomicUsize,
+
+    /// 진행 중인 PMTU 탐색
+    pmtu_search: Mutex<Option<PmtuSearch>>,
+
+    /// 묶어 보낼 작은 청크 데이터그램
+    coalesce: Mutex<CoalesceBuffer>,
+
+    /// 쿠키 왕복 RTT 표본 검증
+    rtt_filter: RttSampleFilter,
+
+    /// 평활 RTT (쿠키 왕복, 재전송 없이 완료된 세그먼트의 완료 보고)
+    rtt_estimate: Mutex<SmoothedRtt>,
+
+    /// 현재 클라이언트 연결 스팬 (클라이언트 주소, 세션 ID)
+    connection_span: RwLock<Span>,
+
+    /// 전송 시작 구간 페이싱
+    slow_start: RwLock<SlowStart>,
+
+    /// 이 세션에서 매니페스트를 보냈는지
+    manifest_sent: AtomicBool,
+
+    /// 서버 전체 송신 상한 중 이 세션의 몫 (상한이 없으면 None)
+    egress: Option<EgressShare>,
+
+    /// 보냈지만 완료 보고나 타임아웃으로 정리되지 않은 바이트 (세그먼트별 합)
+    bytes_in_flight: AtomicU64,
+
+    /// 데이터셋 오프셋별 준비된 세그먼트 (`Config::segment_cache_bytes`)
+    segment_cache: Option<Arc<SegmentCache>>,
+
+    /// 세그먼트 단위 디버그 로그 표본
+    segment_log: LogSampler,
+
+    /// NACK 단위 디버그 로그 표본
+    nack_log: LogSampler,
 }
 
 impl Sender {
     /// 새 송신자 생성
     pub fn new(config: Config, path_manager: Arc<PathManager>) -> Self {
-        let stats = TransferStats::new(path_manager.nic_count().max(1), config.stats_window_size);
+        let stats = TransferStats::new(path_manager.nic_id_bound().max(1), config.stats_window_size);
 
         Self {
-            segment_builder: SegmentBuilder::new(config.chunk_size),
+            segment_builder: SegmentBuilder::new(config.effective_chunk_size().max(1)),
+            guard: ControlGuard::new(&config),
+            fec_policy: RwLock::new(FecModePolicy::new(&config)),
+            segment_sizer: RwLock::new(SegmentSizer::new(&config)),
+            peer_segment_limit: AtomicUsize::new(0),
+            peer_sparse: AtomicBool::new(false),
+            metadata_auth: RwLock::new(None),
+            buffer_tuner: config
+                .auto_buffer_tuning
+                .then(|| RwLock::new(BufferTuner::new(&config))),
+            rtt_filter: RttSampleFilter::new(),
+            rtt_estimate: Mutex::new(SmoothedRtt::new(Duration::from_millis(
+                config.slow_start_initial_rtt_ms,
+            ))),
+            chunk_size_limit: AtomicUsize::new(0),
+            pmtu_search: Mutex::new(None),
+            coalesce: Mutex::new(CoalesceBuffer::default()),
+            slow_start: RwLock::new(SlowStart::new(&config)),
             current_redundancy: RwLock::new(config.base_redundancy_ratio),
+            egress: EgressLimiter::from_config(&config).map(|limiter| limiter.register(config.egress_weight)),
+            segment_cache: SegmentCache::from_config(&config),
+            segment_log: LogSampler::from_config("segment", &config),
+            nack_log: LogSampler::from_config("nack", &config),
             config,
             path_manager,
+            pause: Mutex::new(PauseState::default()),
+            paused: watch::Sender::new(false),
             segments: DashMap::new(),
+            given_up: Mutex::new(BTreeMap::new()),
             next_segment_id: AtomicU64::new(1),
             stats: RwLock::new(stats),
-            running: AtomicBool::new(false),
+            shutdown: RwLock::new(CancellationToken::new()),
+            state: ConnectionStateCell::default(),
             client_addr: RwLock::new(None),
+            client_session: RwLock::new(None),
+            pending_migration: Mutex::new(None),
+            next_heartbeat_sequence: AtomicU64::new(0),
+            peer_connected: AtomicBool::new(false),
+            client_heard: Mutex::new(Instant::now()),
+            connection_span: RwLock::new(Span::none()),
+            manifest_sent: AtomicBool::new(false),
+            bytes_in_flight: AtomicU64::new(0),
         }
     }
 
-    /// 서버 시작
-    pub async fn start(&self, bind_addr: SocketAddr) -> Result<()> {
-        self.running.store(true, Ordering::SeqCst);
+    /// 다른 세션들과 송신 상한을 공유하는 송신자 생성 (`egress_weight` 비율로 분배)
+    pub fn with_egress_limiter(
+        config: Config,
+        path_manager: Arc<PathManager>,
+        limiter: &Arc<EgressLimiter>,
+    ) -> Self {
+        let mut sender = Self::new(config, path_manager);
+        sender.egress = Some(limiter.register(sender.config.egress_weight));
+        sender
+    }
+
+    /// 다른 세션들과 세그먼트 캐시를 공유하는 송신자 생성 (같은 데이터셋을 보내는 서버)
+    pub fn with_segment_cache(
+        config: Config,
+        path_manager: Arc<PathManager>,
+        cache: &Arc<SegmentCache>,
+    ) -> Self {
+        let mut sender = Self::new(config, path_manager);
+        sender.segment_cache = Some(cache.clone());
+        sender
+    }
 
+    /// 서버 시작 (UDP 소켓 바인딩)
+    pub async fn start(&self, bind_addr: SocketAddr) -> Result<()> {
         // 메인 소켓 바인딩
-        let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
+        let socket = UdpSocket::bind(bind_addr).await?;
         socket.set_broadcast(true)?;
+        transport::apply_dscp(&socket, self.config.dscp)?;
 
         info!("SLS Sender started on {}", bind_addr);
 
-        // 수신 루프 (NACK 처리)
-        let socket_clone = socket.clone();
-        let self_ref = self;
-        
+        if self.config.connect_socket {
+            return self.start_with_socket(Arc::new(PeerUdpSocket::new(socket))).await;
+        }
+        self.start_with_socket(Arc::new(socket)).await
+    }
+
+    /// 주어진 소켓으로 서버 시작 (시뮬레이터, 다른 전송 계층 등)
+    pub async fn start_with_socket<S: DatagramSocket>(&self, socket: Arc<S>) -> Result<()> {
+        let chunk_size = self.config.effective_chunk_size();
+        if chunk_size == 0 {
+            return Err(Error::ChunkExceedsDatagram {
+                max_datagram_size: self.config.max_datagram_size,
+                overhead: Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN,
+            });
+        }
+        if chunk_size < self.config.chunk_size {
+            info!(
+                "데이터그램 크기에 맞춰 청크 크기 조정: {} → {} bytes",
+                self.config.chunk_size, chunk_size
+            );
+        }
+        let shutdown = CancellationToken::new();
+        *self.shutdown.write() = shutdown.clone();
+        if let Some(size) = self.socket_buffer_size() {
+            buffertune::apply(&*socket, size);
+        }
+
         let mut buf = vec![0u8; 65535];
 
-        while self.running.load(Ordering::SeqCst) {
+        loop {
             tokio::select! {
-                result = socket_clone.recv_from(&mut buf) => {
+                biased;
+                _ = shutdown.cancelled() => break,
+                result = socket.recv_from(&mut buf) => {
                     match result {
                         Ok((len, addr)) => {
-                            if let Err(e) = self_ref.handle_message(&buf[..len], addr, &socket_clone).await {
-                                warn!("메시지 처리 에러: {}", e);
+                            let span = self.span_for(addr);
+                            if let Err(e) = self
+                                .handle_message(&buf[..len], addr, &*socket)
+                                .instrument(span.clone())
+                                .await
+                            {
+                                span.in_scope(|| warn!("메시지 처리 에러: {}", e));
                             }
                         }
                         Err(e) => {
@@ -124,50 +575,259 @@ impl Sender {
                 }
                 _ = tokio::time::sleep(Duration::from_millis(10)) => {
                     // 주기적 작업
-                    self_ref.process_retransmits(&socket_clone).await;
-                    self_ref.path_manager.adjust_ratios();
-                    self_ref.update_redundancy();
+                    async {
+                        self.announce_pause(&*socket).await;
+                        self.release_silent_peer(&*socket);
+                        if !self.is_paused() {
+                            if let Err(e) = self.flush_coalesced(&*socket).await {
+                                warn!("청크 묶음 전송 에러: {}", e);
+                            }
+                            self.process_retransmits(&*socket).await;
+                        }
+                        self.path_manager.adjust_ratios();
+                        self.update_redundancy();
+                        self.update_redundancy_mode(&*socket).await;
+                        self.finish_drain(&*socket);
+                    }
+                    .instrument(self.connection_span())
+                    .await;
                 }
             }
         }
 
+        // `stop` 후 같은 소켓으로 다시 시작해도 모든 주소에서 받도록 연결 해제
+        self.disconnect_peer(&*socket);
         Ok(())
     }
 
+    /// 주소를 아는 수신자에게 먼저 전송 제안 (역방향 핸드셰이크)
+    ///
+    /// 수신자(`Receiver::accept_offer`)가 Init으로 수락해 연결될 때까지 Offer를 재전송.
+    /// Init은 `start_with_socket`이 처리하므로 같은 소켓으로 서버가 실행 중이어야 함
+    pub async fn offer<S: DatagramSocket>(
+        &self,
+        addr: SocketAddr,
+        total_file_size: u64,
+        socket: &S,
+    ) -> Result<()> {
+        let offer = self.control_datagram(
+            MessageType::Offer,
+            OfferMessage::new(total_file_size).to_bytes(),
+        )?;
+        let connected = || {
+            *self.client_addr.read() == Some(addr) && self.state.get().is_established()
+        };
+
+        for _ in 0..OFFER_MAX_ATTEMPTS {
+            socket.send_to(&offer, addr).await?;
+            let deadline = Instant::now() + OFFER_RETRY_INTERVAL;
+            while Instant::now() < deadline {
+                if connected() {
+                    info!("전송 제안 수락: {}", addr);
+                    return Ok(());
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }
+        Err(Error::OfferTimeout {
+            attempts: OFFER_MAX_ATTEMPTS,
+        })
+    }
+
     /// 데이터 전송 (비동기)
-    pub async fn send_data(&self, data: Bytes, socket: &UdpSocket) -> Result<SegmentId> {
+    pub async fn send_data<S: DatagramSocket>(&self, data: Bytes, socket: &S) -> Result<SegmentId> {
+        // 핸드셰이크 전이나 드레인/종료 후에는 새 데이터 거부
+        let state = self.state.get();
+        if state != ConnectionState::Transferring {
+            debug!("{:?} 상태에서 데이터 전송 거부", state);
+            return Err(Error::ConnectionClosed);
+        }
+        self.check_segment_size(data.len())?;
+
         let segment_id = self.next_segment_id.fetch_add(1, Ordering::SeqCst);
+        self.send_segment(segment_id, MANIFEST_FILE_ID, data, false, false, socket)
+            .instrument(self.connection_span())
+            .await
+    }
 
-        // 청크 분할
-        let nic_id = self.path_manager.select_nic_for_chunk().unwrap_or(0);
-        let chunks = self.segment_builder.split_into_chunks(segment_id, &data, nic_id);
+    /// 애플리케이션 메시지 하나 전송. 반환: 메시지를 끝내는 세그먼트 ID
+    ///
+    /// 현재 세그먼트 크기 단위의 연속 세그먼트로 나누고 마지막 세그먼트 청크에 경계를 표시해
+    /// 수신측 `MessageReceiver`가 메시지 단위로 돌려받음 (빈 메시지도 빈 세그먼트 하나).
+    /// 같은 세션에서 `send_data`로 보낸 데이터는 다음 메시지의 앞부분으로 합쳐짐
+    pub async fn send_message<S: DatagramSocket>(
+        &self,
+        data: Bytes,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        self.send_framed(data, false, socket).await
+    }
 
-        // 중복 청크 생성
-        let redundancy = *self.current_redundancy.read();
-        let redundant_chunks = self
-            .segment_builder
-            .create_redundant_chunks(&chunks, redundancy);
+    /// 마지막 메시지를 보내며 스트림 끝 표시. 반환: 스트림을 끝내는 세그먼트 ID
+    ///
+    /// `send_message`와 같고 마지막 세그먼트 청크에 `end_of_stream`도 표시해, 수신측이 연결
+    /// 종료를 기다리지 않고 스트림이 끝났음을 앎 (`Receiver::end_of_stream`). 표시는 데이터
+    /// 청크에 실려 재전송되므로 컨트롤 메시지처럼 유실되지 않음
+    pub async fn send_end_of_stream<S: DatagramSocket>(
+        &self,
+        data: Bytes,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        self.send_framed(data, true, socket).await
+    }
 
-        let total_chunks = chunks.len();
+    /// 메시지를 연속 세그먼트로 전송 (`end_of_stream`이면 마지막 세그먼트에 스트림 끝 표시)
+    async fn send_framed<S: DatagramSocket>(
+        &self,
+        data: Bytes,
+        end_of_stream: bool,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        let state = self.state.get();
+        if state != ConnectionState::Transferring {
+            debug!("{:?} 상태에서 메시지 전송 거부", state);
+            return Err(Error::ConnectionClosed);
+        }
 
-        // 상태 저장
-        let state = SegmentState {
-            chunks: chunks.clone(),
-            redundant_chunks: redundant_chunks.clone(),
-            sent_chunk_ids: vec![false; total_chunks],
-            created_at: Instant::now(),
-            completed: false,
-            retransmit_queue: Vec::new(),
-        };
-        self.segments.insert(segment_id, state);
+        let segment_size = self.current_segment_size().max(1);
+        let count = data.len().div_ceil(segment_size).max(1) as u64;
+        let first_segment = self.next_segment_id.fetch_add(count, Ordering::SeqCst);
+        let span = self.connection_span();
+        for index in 0..count {
+            let offset = index as usize * segment_size;
+            let end = (offset + segment_size).min(data.len());
+            self.send_segment(
+                first_segment + index,
+                MANIFEST_FILE_ID,
+                data.slice(offset..end),
+                index + 1 == count,
+                end_of_stream && index + 1 == count,
+                socket,
+            )
+            .instrument(span.clone())
+            .await?;
+        }
+        Ok(first_segment + count - 1)
+    }
 
-        // 통계 업데이트
-        {
-            let mut stats = self.stats.write();
-            stats.total_segments += 1;
-            stats.total_bytes += data.len() as u64;
+    /// 여러 파일을 한 세션으로 전송
+    ///
+    /// 매니페스트(경로, 크기, 다이제스트, 세그먼트 구간)를 예약 세그먼트로 먼저 보낸 뒤
+    /// 파일마다 `segment_size` 단위 세그먼트를 `file_id`를 붙여 전송. 세션당 한 번만 가능.
+    /// 매니페스트가 수신자의 세그먼트 한도를 넘으면 (세그먼트 다이제스트는 세그먼트당 32바이트)
+    /// 아무것도 보내지 않고 `Error::SegmentTooLarge`
+    pub async fn send_files<S: DatagramSocket>(
+        &self,
+        files: &[(String, Bytes)],
+        socket: &S,
+    ) -> Result<Manifest> {
+        let state = self.state.get();
+        if state != ConnectionState::Transferring {
+            debug!("{:?} 상태에서 파일 전송 거부", state);
+            return Err(Error::ConnectionClosed);
+        }
+
+        // 매니페스트도 세그먼트 하나이므로 수신자 한도를 넘으면 세그먼트 ID를 예약하기 전에 거부
+        // (인코딩 길이는 세그먼트 ID와 무관하므로 구간은 확인 뒤에 채움)
+        let segment_size = self.negotiated_segment_size();
+        let mut manifest = Manifest::build(files, segment_size, 0);
+        if self.config.segment_digests {
+            manifest = manifest.with_segment_digests(files);
+        }
+        self.check_segment_size(manifest.to_bytes().len())?;
+        if self.manifest_sent.swap(true, Ordering::SeqCst) {
+            return Err(Error::InvalidManifest("이 세션의 매니페스트는 이미 전송됨".into()));
+        }
+
+        // 파일 세그먼트 ID 구간 예약 (고정 세그먼트 크기로 오프셋 계산)
+        let total_segments: u64 = files
+            .iter()
+            .map(|(_, data)| (data.len() as u64).div_ceil(segment_size as u64))
+            .sum();
+        let first_segment = self.next_segment_id.fetch_add(total_segments, Ordering::SeqCst);
+        for entry in &mut manifest.files {
+            entry.first_segment += first_segment;
+        }
+
+        let span = self.connection_span();
+        self.send_segment(
+            MANIFEST_SEGMENT_ID,
+            MANIFEST_FILE_ID,
+            Bytes::from(manifest.to_bytes()),
+            false,
+            false,
+            socket,
+        )
+        .instrument(span.clone())
+        .await?;
+        info!(parent: &span, "매니페스트 전송: {} 파일, {} 세그먼트", files.len(), total_segments);
+
+        for ((_, data), entry) in files.iter().zip(&manifest.files) {
+            for index in 0..entry.segment_count(segment_size) {
+                let offset = index as usize * segment_size;
+                let end = (offset + segment_size).min(data.len());
+                self.send_segment(
+                    entry.first_segment + index,
+                    entry.file_id,
+                    data.slice(offset..end),
+                    false,
+                    false,
+                    socket,
+                )
+                .instrument(span.clone())
+                .await?;
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// 세그먼트 분할 및 전송
+    #[tracing::instrument(
+        level = "debug",
+        name = "segment",
+        skip(self, data, socket),
+        fields(nic_id = tracing::field::Empty)
+    )]
+    async fn send_segment<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        file_id: FileId,
+        data: Bytes,
+        boundary: bool,
+        end_of_stream: bool,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        // 모든 바이트가 같으면 청크 대신 표시 하나 (균일 표시에는 스트림 끝 필드가 없음)
+        if data.len() > self.chunk_size() && !end_of_stream && self.sends_sparse() {
+            if let Some(fill) = chunk::uniform_fill(&data) {
+                let connection_id = self.client_session.read().unwrap_or(0);
+                let marker =
+                    SparseSegmentMessage::new(connection_id, segment_id, file_id, data.len() as u32, fill)
+                        .with_boundary(boundary);
+                return self.send_sparse(marker, socket).await;
+            }
+        }
+
+        // 청크 분할
+        let mut chunks = self.split_segment(segment_id, data.clone());
+        Span::current().record("nic_id", chunks[0].header.nic_id);
+        for chunk in &mut chunks {
+            chunk.header.file_id = file_id;
+            chunk.header.boundary = boundary;
+            chunk.header.end_of_stream = end_of_stream;
         }
 
+        let redundant_chunks = self.create_redundant_chunks(&chunks);
+        let total_chunks = chunks.len();
+        self.register_segment(
+            segment_id,
+            file_id,
+            chunks.clone(),
+            redundant_chunks.clone(),
+            data.len(),
+        );
+
         // 클라이언트 주소 확인
         let client_addr = match *self.client_addr.read() {
             Some(addr) => addr,
@@ -178,310 +838,4478 @@ impl Sender {
         self.transmit_chunks(&chunks, &redundant_chunks, socket, client_addr)
             .await?;
 
-        debug!(
-            "세그먼트 {} 전송 완료: {} 청크 + {} 중복",
-            segment_id,
-            total_chunks,
-            redundant_chunks.len()
-        );
+        if self.segment_log.sample() {
+            debug!(
+                "세그먼트 전송 완료: {} 청크 + {} 중복",
+                total_chunks,
+                redundant_chunks.len()
+            );
+        }
 
         Ok(segment_id)
     }
 
-    /// 청크들 전송
-    async fn transmit_chunks(
+    /// 균일 세그먼트를 청크 없이 표시 하나로 전송
+    ///
+    /// 인증 키가 있으면 태그를 붙임. 완료 보고가 없으면 꼬리 탐침이, NACK이 오면 NACK 처리가
+    /// 같은 표시를 다시 보냄
+    async fn send_sparse<S: DatagramSocket>(
         &self,
-        chunks: &[Chunk],
-        redundant_chunks: &[Chunk],
-        socket: &UdpSocket,
-        addr: SocketAddr,
-    ) -> Result<()> {
-        // 원본 청크 전송
-        for chunk in chunks {
-            let data = chunk.to_bytes();
-            socket.send_to(&data, addr).await?;
+        marker: SparseSegmentMessage,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        let marker = match &*self.metadata_auth.read() {
+            Some(auth) => marker.authenticated(auth),
+            None => marker,
+        };
+        let datagram = self.control_datagram(MessageType::SparseSegment, marker.to_bytes())?;
+        let segment_id = marker.segment_id;
+        self.register_segment(
+            segment_id,
+            marker.file_id,
+            Vec::new(),
+            Vec::new(),
+            marker.segment_size as usize,
+        );
+        if let Some(mut state) = self.segments.get_mut(&segment_id) {
+            state.sparse = Some((marker, datagram.clone()));
+        }
 
-            self.path_manager
-                .record_chunk_arrival(chunk.header.nic_id, data.len());
+        let client_addr = match *self.client_addr.read() {
+            Some(addr) => addr,
+            None => return Err(Error::ConnectionClosed),
+        };
+        self.wait_resumed().await?;
+        self.acquire_egress(datagram.len()).await;
+        socket.send_to(&datagram, client_addr).await?;
+        self.record_in_flight(segment_id, datagram.len());
+        self.stats.write().sparse_segments += 1;
 
-            {
-                let mut stats = self.stats.write();
-                stats.total_chunks += 1;
-            }
+        if self.segment_log.sample() {
+            debug!(fill = marker.fill, "균일 세그먼트 표시 전송: {} bytes", marker.segment_size);
+        }
+        Ok(segment_id)
+    }
+
+    /// 반복 전송용 세그먼트 준비 (현재 중복률/손실 보정 모드로 한 번만 분할·직렬화)
+    ///
+    /// 같은 데이터를 여러 수신자에게 보낼 때 각 송신자에서 `send_prepared`로 재사용
+    pub fn prepare_segment(&self, data: Bytes) -> PreparedSegment {
+        let chunks = self.split_segment(0, data);
+        let redundant_chunks = self.create_redundant_chunks(&chunks);
+        PreparedSegment::padded(chunks, redundant_chunks, self.padded_datagram_len())
+    }
 
-            // 전송 간격
-            if self.config.chunk_interval_us > 0 {
-                tokio::time::sleep(Duration::from_micros(self.config.chunk_interval_us)).await;
+    /// 데이터셋 앞부분을 세그먼트 캐시에 미리 준비하고 준비한 바이트 수 반환
+    ///
+    /// 현재 세그먼트 크기로 나눠 캐시에 자리가 남아 있는 동안만 채움 (캐시가 없으면 0).
+    /// 이후 연결은 `send_segment_at`이 같은 오프셋·같은 내용의 세그먼트를 분할 없이 보냄
+    pub fn preload(&self, data: &[u8]) -> u64 {
+        let Some(cache) = &self.segment_cache else {
+            return 0;
+        };
+        let segment_size = self.current_segment_size().max(1);
+        let mut offset = 0;
+        while offset < data.len() {
+            let end = (offset + segment_size).min(data.len());
+            let prepared = self.prepare_segment(Bytes::copy_from_slice(&data[offset..end]));
+            if !cache.insert_if_room(offset as u64, Arc::new(prepared)) {
+                break;
             }
+            offset = end;
         }
+        info!("세그먼트 캐시 준비: {} bytes, {} 세그먼트", offset, cache.len());
+        offset as u64
+    }
 
-        // 중복 청크 전송
-        for chunk in redundant_chunks {
-            let data = chunk.to_bytes();
-            socket.send_to(&data, addr).await?;
+    /// 데이터셋 `data`의 `offset`부터 세그먼트 하나 전송. 반환: (세그먼트 ID, 보낸 바이트)
+    ///
+    /// 세그먼트 캐시에 같은 내용이 있으면 준비된 세그먼트로 바로 보내고, 없으면 현재 적응형
+    /// 크기로 준비해 캐시에 넣은 뒤 보냄. 캐시가 없으면 `send_data`로 분할 전송.
+    /// `offset`이 데이터 길이를 넘으면 `Error::OffsetOutOfRange`
+    pub async fn send_segment_at<S: DatagramSocket>(
+        &self,
+        data: &[u8],
+        offset: usize,
+        socket: &S,
+    ) -> Result<(SegmentId, usize)> {
+        let rest = data.get(offset..).ok_or(Error::OffsetOutOfRange {
+            offset,
+            len: data.len(),
+        })?;
+        let len = self.current_segment_size().min(rest.len());
+        let Some(cache) = &self.segment_cache else {
+            let segment_id = self.send_data(Bytes::copy_from_slice(&rest[..len]), socket).await?;
+            return Ok((segment_id, len));
+        };
 
-            {
-                let mut stats = self.stats.write();
-                stats.redundant_chunks += 1;
-                stats.total_chunks += 1;
+        let cached = cache.get(offset as u64, rest).filter(|p| self.can_send_cached(p));
+        let prepared = match cached {
+            Some(prepared) => prepared,
+            None => {
+                let prepared = Arc::new(self.prepare_segment(Bytes::copy_from_slice(&rest[..len])));
+                cache.insert(offset as u64, prepared.clone());
+                prepared
             }
+        };
+        let segment_id = self.send_prepared(&prepared, socket).await?;
+        Ok((segment_id, prepared.segment_size()))
+    }
 
-            if self.config.chunk_interval_us > 0 {
-                tokio::time::sleep(Duration::from_micros(self.config.chunk_interval_us)).await;
-            }
-        }
+    /// 캐시된 세그먼트가 지금 연결에 맞는지 (청크 크기가 줄었거나 수신자 한도를 넘으면 다시 준비)
+    fn can_send_cached(&self, prepared: &PreparedSegment) -> bool {
+        let chunk_size = self.chunk_size();
+        prepared.segment_size() > 0
+            && self.check_segment_size(prepared.segment_size()).is_ok()
+            && prepared.chunks().iter().all(|chunk| chunk.data.len() <= chunk_size)
+    }
 
-        Ok(())
+    /// 세그먼트 캐시 (`Config::segment_cache_bytes`가 0이고 공유 캐시도 없으면 None)
+    pub fn segment_cache(&self) -> Option<&Arc<SegmentCache>> {
+        self.segment_cache.as_ref()
     }
 
-    /// 메시지 처리
-    async fn handle_message(
+    /// 준비된 세그먼트 전송. 이 연결의 세그먼트 ID와 전송 시각만 기록
+    pub async fn send_prepared<S: DatagramSocket>(
         &self,
-        data: &[u8],
-        addr: SocketAddr,
-        socket: &UdpSocket,
-    ) -> Result<()> {
-        if data.len() < 4 {
-            return Ok(());
+        prepared: &PreparedSegment,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        let state = self.state.get();
+        if state != ConnectionState::Transferring {
+            debug!("{:?} 상태에서 데이터 전송 거부", state);
+            return Err(Error::ConnectionClosed);
         }
+        self.check_segment_size(prepared.segment_size())?;
 
-        // 매직 넘버 확인
-        let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
-        if magic != MAGIC_NUMBER {
-            // 청크 패킷이 아닌 메시지
-            return self.handle_control_message(data, addr, socket).await;
-        }
-
-        Ok(())
+        let segment_id = self.next_segment_id.fetch_add(1, Ordering::SeqCst);
+        self.transmit_prepared(segment_id, prepared, socket)
+            .instrument(self.connection_span())
+            .await
     }
 
-    /// 컨트롤 메시지 처리
-    async fn handle_control_message(
+    #[tracing::instrument(level = "debug", name = "segment", skip(self, prepared, socket))]
+    async fn transmit_prepared<S: DatagramSocket>(
         &self,
-        data: &[u8],
-        addr: SocketAddr,
-        socket: &UdpSocket,
-    ) -> Result<()> {
-        // 헤더 파싱
-        let header: MessageHeader = match bincode::deserialize(data) {
-            Ok(h) => h,
-            Err(_) => return Ok(()),
+        segment_id: SegmentId,
+        prepared: &PreparedSegment,
+        socket: &S,
+    ) -> Result<SegmentId> {
+        // NACK 재전송용 캐시는 청크 헤더만 복사 (데이터 공유)
+        let (chunks, redundant_chunks) = prepared.chunks_for(segment_id);
+        self.register_segment(
+            segment_id,
+            MANIFEST_FILE_ID,
+            chunks,
+            redundant_chunks,
+            prepared.segment_size(),
+        );
+
+        let client_addr = match *self.client_addr.read() {
+            Some(addr) => addr,
+            None => return Err(Error::ConnectionClosed),
         };
 
-        match header.msg_type {
-            MessageType::Init => {
-                // 연결 초기화
-                *self.client_addr.write() = Some(addr);
+        let originals = prepared.chunks();
+        let mut buf = Vec::new();
+        for index in 0..prepared.datagram_count() {
+            self.pace_slow_start().await;
+            prepared.write_datagram_now(index, segment_id, &mut buf);
+            let nic_id = originals.get(index).map(|chunk| chunk.header.nic_id);
+            let path = match nic_id {
+                Some(nic_id) => nic_id,
+                None => prepared.redundant_chunks()[index - originals.len()].header.nic_id,
+            };
+            self.send_datagram(segment_id, &buf, nic_id, path, socket, client_addr)
+                .await?;
+        }
 
-                let ack = InitAckMessage::new(
-                    0, // total_file_size - will be set when data is known
-                    self.config.chunk_size as u16,
-                    self.config.segment_size as u32,
+        if self.segment_log.sample() {
+            debug!(
+                "준비된 세그먼트 전송 완료: {} 청크 + {} 중복",
+                originals.len(),
+                prepared.redundant_chunks().len()
+            );
+        }
+
+        Ok(segment_id)
+    }
+
+    /// 세그먼트를 청크로 분할
+    ///
+    /// 경로별 MTU가 지정되어 있으면 청크마다 경로를 골라 그 경로에 맞는 크기로 자름.
+    /// FEC 복원과 데이터그램 패딩은 같은 크기 청크를 전제하므로 그때는 가장 작은 경로에 맞춤
+    fn split_segment(&self, segment_id: SegmentId, data: Bytes) -> Vec<Chunk> {
+        let paths = &self.path_manager;
+        let chunk_size = self.chunk_size();
+        let uniform = self.config.pad_datagrams || self.redundancy_mode() == RedundancyMode::Fec;
+        if paths.has_path_mtus() && !uniform {
+            return SegmentBuilder::new(chunk_size).split_bytes_for_paths(segment_id, data, || {
+                let nic_id = paths.select_nic_for_chunk().unwrap_or(0);
+                (nic_id, paths.path_chunk_size(nic_id, chunk_size))
+            });
+        }
+        let nic_id = paths.select_nic_for_chunk().unwrap_or(0);
+        SegmentBuilder::new(paths.smallest_path_chunk_size(chunk_size))
+            .split_bytes_into_chunks(segment_id, data, nic_id)
+    }
+
+    /// 중복 청크 생성 (FEC 모드에서는 패리티 청크)
+    fn create_redundant_chunks(&self, chunks: &[Chunk]) -> Vec<Chunk> {
+        let redundancy = *self.current_redundancy.read();
+        if redundancy <= 0.0 {
+            // 중복 전송 끔: FEC 모드도 최소 패리티를 만들지 않음
+            return Vec::new();
+        }
+        match self.redundancy_mode() {
+            RedundancyMode::Duplicate => self
+                .segment_builder
+                .create_redundant_chunks(chunks, redundancy),
+            RedundancyMode::Fec => fec::encode_parity(chunks, redundancy),
+        }
+    }
+
+    /// 재전송을 위한 세그먼트 상태 저장 및 통계 반영
+    fn register_segment(
+        &self,
+        segment_id: SegmentId,
+        file_id: FileId,
+        chunks: Vec<Chunk>,
+        redundant_chunks: Vec<Chunk>,
+        segment_bytes: usize,
+    ) {
+        let total_chunks = chunks.len();
+        let sent_chunks = (total_chunks + redundant_chunks.len()) as u32;
+        let state = SegmentState {
+            file_id,
+            chunks,
+            redundant_chunks,
+            sent_chunk_ids: vec![false; total_chunks],
+            created_at: Instant::now(),
+            completed: false,
+            retransmit_queue: Vec::new(),
+            nacked: false,
+            receive_ratio: 0.0,
+            sent_chunks,
+            bytes_in_flight: 0,
+            nack_delta: None,
+            last_missing: 0,
+            stalled_nacks: 0,
+            last_sent: Instant::now(),
+            last_original_sent: Instant::now(),
+            tail_probes: 0,
+            retransmits: HashMap::new(),
+            sparse: None,
+        };
+        self.segments.insert(segment_id, state);
+
+        let mut stats = self.stats.write();
+        stats.total_segments += 1;
+        stats.total_bytes += segment_bytes as u64;
+    }
+
+    /// 세그먼트로 보낸 바이트를 미확인 바이트에 합산 (이미 정리된 세그먼트는 제외)
+    fn record_in_flight(&self, segment_id: SegmentId, bytes: usize) {
+        if let Some(mut state) = self.segments.get_mut(&segment_id) {
+            state.bytes_in_flight += bytes as u64;
+            state.last_sent = Instant::now();
+            self.bytes_in_flight.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// 정리되는 세그먼트의 미확인 바이트 차감
+    fn release_in_flight(&self, state: &SegmentState) {
+        self.bytes_in_flight
+            .fetch_sub(state.bytes_in_flight, Ordering::Relaxed);
+    }
+
+    /// 데이터그램 하나 전송 (`nic_id`가 없으면 중복 청크, `path`는 청크 헤더의 NIC)
+    async fn send_datagram<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        data: &[u8],
+        nic_id: Option<u8>,
+        path: u8,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        self.wait_resumed().await?;
+        if data.len() <= self.coalesce_limit() {
+            return self.coalesce_datagram(segment_id, data, nic_id, socket, addr).await;
+        }
+        self.acquire_egress(data.len()).await;
+        self.send_on_path(data, path, socket, addr).await?;
+        self.record_sent(segment_id, data.len(), nic_id);
+        self.wait_chunk_interval().await;
+        Ok(())
+    }
+
+    /// 청크 데이터그램을 그 청크를 나눈 경로의 NIC 소켓으로 전송
+    ///
+    /// 경로 MTU가 지정되면 청크를 경로마다 다른 크기로 나누므로 (`split_segment`) 크기를 맞춘
+    /// 경로로 내보내야 함. 경로 MTU가 없거나 NIC 소켓이 없으면 `socket`으로
+    async fn send_on_path<S: DatagramSocket>(
+        &self,
+        data: &[u8],
+        nic_id: u8,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let nic_socket = self
+            .path_manager
+            .has_path_mtus()
+            .then(|| self.path_manager.get_socket(nic_id))
+            .flatten();
+        match nic_socket {
+            Some(nic_socket) => nic_socket.send_to(data, addr).await?,
+            None => socket.send_to(data, addr).await?,
+        };
+        Ok(())
+    }
+
+    /// 보낸(또는 묶음에 넣은) 청크 데이터그램 통계 반영
+    fn record_sent(&self, segment_id: SegmentId, len: usize, nic_id: Option<u8>) {
+        self.record_in_flight(segment_id, len);
+
+        match nic_id {
+            Some(nic_id) => {
+                if let Some(mut state) = self.segments.get_mut(&segment_id) {
+                    state.last_original_sent = Instant::now();
+                }
+                self.path_manager.record_chunk_arrival(nic_id, len);
+                self.stats.write().total_chunks += 1;
+            }
+            None => {
+                let mut stats = self.stats.write();
+                stats.redundant_chunks += 1;
+                stats.total_chunks += 1;
+            }
+        }
+    }
+
+    /// 재전송 기록: 경로의 원시 처리율에는 들어가지만 유효 처리율에는 들어가지 않음
+    fn record_retransmit(&self, segment_id: SegmentId, nic_id: u8, len: usize) {
+        self.record_in_flight(segment_id, len);
+        self.path_manager.record_chunk_arrival(nic_id, len);
+
+        let mut stats = self.stats.write();
+        stats.retransmitted_chunks += 1;
+        stats.total_chunks += 1;
+    }
+
+    /// 데이터그램 사이 전송 간격
+    async fn wait_chunk_interval(&self) {
+        if self.config.chunk_interval_us > 0 {
+            tokio::time::sleep(Duration::from_micros(self.config.chunk_interval_us)).await;
+        }
+    }
+
+    /// 묶음에 넣을 수 있는 청크 데이터그램 최대 크기 (묶지 않으면 0)
+    ///
+    /// 둘 이상 들어가야 묶는 의미가 있으므로 묶음 크기의 절반 이하만 대상
+    fn coalesce_limit(&self) -> usize {
+        if !self.config.coalesce_chunks || self.config.pad_datagrams {
+            return 0;
+        }
+        let room = self
+            .batch_datagram_size()
+            .saturating_sub(ChunkBatch::PREFIX_LEN);
+        (room / 2).saturating_sub(ChunkBatch::RECORD_PREFIX_LEN)
+    }
+
+    /// 묶음 데이터그램 최대 크기 (경로별 MTU 중 가장 작은 경로도 넘지 않음)
+    fn batch_datagram_size(&self) -> usize {
+        let limit = self.path_manager.smallest_path_mtu().unwrap_or(usize::MAX);
+        self.config.max_datagram_size.min(limit)
+    }
+
+    /// 작은 청크 데이터그램을 묶음에 넣고, 묶음이 가득 차면 먼저 전송
+    async fn coalesce_datagram<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        data: &[u8],
+        nic_id: Option<u8>,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let full = {
+            let mut buffer = self.coalesce.lock();
+            let full = if buffer.fits(addr, data, self.batch_datagram_size()) {
+                None
+            } else {
+                buffer.take()
+            };
+            buffer.push(addr, data);
+            full
+        };
+        self.record_sent(segment_id, data.len(), nic_id);
+
+        if let Some((addr, records)) = full {
+            self.send_coalesced(addr, &records, socket).await?;
+        }
+        Ok(())
+    }
+
+    /// 묶음 하나 전송 (레코드가 하나뿐이면 묶지 않고 그대로)
+    async fn send_coalesced<S: DatagramSocket>(
+        &self,
+        addr: SocketAddr,
+        records: &[Vec<u8>],
+        socket: &S,
+    ) -> Result<()> {
+        self.wait_resumed().await?;
+        let datagram = match records {
+            [] => return Ok(()),
+            [single] => single.clone(),
+            _ => {
+                self.stats.write().coalesced_datagrams += 1;
+                ChunkBatch::encode(records.iter().map(Vec::as_slice))
+            }
+        };
+        self.acquire_egress(datagram.len()).await;
+        socket.send_to(&datagram, addr).await?;
+        self.wait_chunk_interval().await;
+        Ok(())
+    }
+
+    /// 묶음에 남은 청크를 바로 전송 (`Config::coalesce_chunks`)
+    ///
+    /// 서버 루프가 주기 작업마다 호출하므로 보통은 필요 없고, 루프 없이 보내는
+    /// `FileSender`나 마지막 세그먼트를 지연 없이 내보낼 때 사용
+    pub async fn flush_coalesced<S: DatagramSocket>(&self, socket: &S) -> Result<()> {
+        let pending = self.coalesce.lock().take();
+        match pending {
+            Some((addr, records)) => self.send_coalesced(addr, &records, socket).await,
+            None => Ok(()),
+        }
+    }
+
+    /// 청크들 전송
+    async fn transmit_chunks<S: DatagramSocket>(
+        &self,
+        chunks: &[Chunk],
+        redundant_chunks: &[Chunk],
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        // 원본 청크 전송
+        for chunk in chunks {
+            let data = chunk.to_padded_bytes(self.padded_datagram_len());
+            self.pace_slow_start().await;
+            let segment_id = chunk.header.segment_id;
+            let nic_id = chunk.header.nic_id;
+            self.send_datagram(segment_id, &data, Some(nic_id), nic_id, socket, addr)
+                .await?;
+        }
+
+        // 중복 청크 전송
+        for chunk in redundant_chunks {
+            let data = chunk.to_padded_bytes(self.padded_datagram_len());
+            self.pace_slow_start().await;
+            let (segment_id, path) = (chunk.header.segment_id, chunk.header.nic_id);
+            self.send_datagram(segment_id, &data, None, path, socket, addr)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 메시지 처리
+    async fn handle_message<S: DatagramSocket>(
+        &self,
+        data: &[u8],
+        addr: SocketAddr,
+        socket: &S,
+    ) -> Result<()> {
+        if data.len() < 4 {
+            return Ok(());
+        }
+
+        // 매직 넘버 확인 (컨트롤 메시지만 처리)
+        let magic = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if magic != MAGIC_NUMBER {
+            return Ok(());
+        }
+
+        if *self.client_addr.read() == Some(addr) {
+            self.on_client_heard(addr, socket).await;
+        }
+
+        // 도달성이 확인되지 않은 출발지만 속도 제한
+        if *self.client_addr.read() != Some(addr) && !self.guard.allow(addr) {
+            debug!("컨트롤 메시지 속도 제한: {}", addr);
+            return Ok(());
+        }
+
+        self.handle_control_message(data, addr, socket).await
+    }
+
+    /// 컨트롤 메시지 처리
+    async fn handle_control_message<S: DatagramSocket>(
+        &self,
+        data: &[u8],
+        addr: SocketAddr,
+        socket: &S,
+    ) -> Result<()> {
+        // 헤더 파싱
+        let Some(header) = MessageHeader::from_bytes(data) else {
+            return Ok(());
+        };
+
+        // 확인된 주소가 아닌 곳에서 현재 연결 ID가 오면 무시하고, 키로 인증된 Heartbeat일 때만
+        // 도달성부터 확인 (평문 연결 ID를 엿본 제3자는 이전을 시작하지 못함)
+        if *self.client_addr.read() != Some(addr) && self.state.get().is_established() {
+            let connection_id = Self::connection_id_of(header.msg_type, data);
+            if connection_id != 0 && *self.client_session.read() == Some(connection_id) {
+                if !self.authentic_heartbeat(header.msg_type, data) {
+                    debug!("인증되지 않은 새 주소의 {:?} 무시: {}", header.msg_type, addr);
+                    return Ok(());
+                }
+                return self.challenge_path(connection_id, addr, socket).await;
+            }
+        }
+
+        match header.msg_type {
+            MessageType::Init => {
+                let init = match InitMessage::from_bytes(data) {
+                    Some(init) => init,
+                    None => return Ok(()),
+                };
+
+                let state = self.state.get();
+                if state == ConnectionState::Draining {
+                    debug!("드레인 중 Init 무시: {}", addr);
+                    return Ok(());
+                }
+
+                // 쿠키 확인 전에는 작은 쿠키 메시지만 응답 (증폭 방지)
+                // 세션 상태를 만들기 전에 무상태로 검증, 오래된 쿠키는 재전송된 Init으로 간주
+                let cookie_rtt = if self.config.require_init_cookie {
+                    match self.guard.verify_cookie(addr, &init.cookie) {
+                        Ok(age) => self.rtt_filter.accept(rtt::check_rtt(age)),
+                        Err(reason) => {
+                            // 진행 중인 전송은 미확인 출발지의 Init으로 중단하지 않음
+                            if !state.is_established() {
+                                let _ = self.state.transition(ConnectionState::Handshaking);
+                            }
+                            let cookie = CookieMessage::new(self.guard.cookie_for(addr));
+                            let cookie = self.control_datagram(MessageType::Cookie, cookie.to_bytes())?;
+                            socket.send_to(&cookie, addr).await?;
+                            debug!(?reason, "Init 쿠키 발급: {}", addr);
+                            return Ok(());
+                        }
+                    }
+                } else {
+                    None
+                };
+
+                // 청크 크기는 세션 중 바꿀 수 없으므로 클라이언트 한도를 넘으면 연결하지 않음.
+                // 실제 크기를 담은 InitAck으로 응답해 클라이언트가 사유를 알고 종료하게 함
+                let chunk_size = self.config.effective_chunk_size();
+                let max_chunk = init.max_supported_chunk_size as usize;
+                if max_chunk > 0 && chunk_size > max_chunk {
+                    warn!(
+                        "연결 거부: 청크 크기 {} > 클라이언트 한도 {} ({})",
+                        chunk_size, max_chunk, addr
+                    );
+                    let ack = InitAckMessage::new(
+                        0,
+                        chunk_size as u16,
+                        self.config.segment_size as u32,
+                        *self.current_redundancy.read() as f32,
+                    );
+                    let ack = self.control_datagram(MessageType::InitAck, ack.to_bytes())?;
+                    socket.send_to(&ack, addr).await?;
+                    return Ok(());
+                }
+
+                // 같은 주소의 새 세션 (수신자 재시작) → 이전 세션 전송 상태 정리
+                let same_addr = *self.client_addr.read() == Some(addr);
+                let previous = self.client_session.write().replace(init.session_id);
+                if previous != Some(init.session_id) {
+                    self.next_heartbeat_sequence.store(0, Ordering::SeqCst);
+                }
+                if same_addr && previous.is_some_and(|id| id != init.session_id) {
+                    info!("클라이언트 재시작 감지: {}, 이전 세션 정리", addr);
+                    self.segments.retain(|_, state| {
+                        self.release_in_flight(state);
+                        false
+                    });
+                }
+
+                // 새 연결은 초기 윈도우부터 다시 시작 (중복 Init은 진행 상태 유지)
+                let fresh = !same_addr || previous != Some(init.session_id);
+                if fresh {
+                    let mut slow_start = SlowStart::new(&self.config);
+                    let mut rtt_estimate = self.initial_rtt_estimate();
+                    if let Some(rtt) = cookie_rtt {
+                        slow_start.on_rtt_sample(rtt);
+                        rtt_estimate.on_sample(rtt);
+                    }
+                    *self.slow_start.write() = slow_start;
+                    *self.rtt_estimate.lock() = rtt_estimate;
+                    self.manifest_sent.store(false, Ordering::SeqCst);
+
+                    // 세그먼트 크기(적응형 상한 포함)를 클라이언트 한도 안으로
+                    let limit = init.max_supported_segment_size as usize;
+                    self.peer_segment_limit.store(limit, Ordering::SeqCst);
+                    *self.segment_sizer.write() = SegmentSizer::new(&self.config).with_limit(limit);
+                    self.peer_sparse.store(init.sparse_supported, Ordering::SeqCst);
+                }
+
+                // 연결 초기화
+                self.accept_client(addr)?;
+                self.connect_peer(addr, socket).await;
+
+                // Init 타임스탬프를 그대로 돌려줘 클라이언트가 어느 Init의 응답인지 구분
+                let mut ack = InitAckMessage::with_client_timestamp(
+                    0, // total_file_size - will be set when data is known
+                    chunk_size as u16,
+                    self.negotiated_segment_size() as u32,
                     *self.current_redundancy.read() as f32,
+                    init.timestamp_us,
                 );
+                ack.sparse_segments = self.config.sparse_segments && init.sparse_supported;
+                ack.delta_nacks = init.delta_nacks;
+
+                let ack = self.control_datagram(MessageType::InitAck, ack.to_bytes())?;
+                socket.send_to(&ack, addr).await?;
+                info!("클라이언트 연결: {}", addr);
+
+                // InitAck 바로 뒤에 프로브 버스트 (중복 Init에는 다시 보내지 않음)
+                if fresh && self.config.bandwidth_probe {
+                    for probe in probe::probe_train(&self.config) {
+                        socket.send_to(&probe, addr).await?;
+                    }
+                    debug!("대역폭 프로브 {}개 전송: {}", probe::PROBE_TRAIN_LEN, addr);
+                }
+            }
+
+            MessageType::Nack => {
+                if !self.accepts_established(header.msg_type) {
+                    return Ok(());
+                }
+
+                // 도달성이 확인된 클라이언트의 NACK만 처리
+                if *self.client_addr.read() != Some(addr) {
+                    debug!("미확인 출발지 NACK 무시: {}", addr);
+                    return Ok(());
+                }
+
+                if let Some(nack) = NackMessage::from_bytes(data) {
+                    // 전체 목록 NACK이 오면 수신자가 델타 보고를 멈춘 것
+                    if let Some(mut state) = self.segments.get_mut(&nack.segment_id) {
+                        state.nack_delta = None;
+                    }
+                    self.handle_nack(nack, socket, addr).await?;
+                }
+            }
+
+            MessageType::NackDelta => {
+                if !self.accepts_established(header.msg_type)
+                    || *self.client_addr.read() != Some(addr)
+                {
+                    return Ok(());
+                }
+
+                if let Some(delta) = NackDeltaMessage::from_bytes(data) {
+                    let nack = self.apply_nack_delta(delta);
+                    self.handle_nack(nack, socket, addr).await?;
+                }
+            }
+
+            MessageType::ProbeReport => {
+                if !self.accepts_established(header.msg_type)
+                    || *self.client_addr.read() != Some(addr)
+                {
+                    return Ok(());
+                }
+
+                if let Some(report) = ProbeReportMessage::from_bytes(data) {
+                    if let Some(tuner) = &self.buffer_tuner {
+                        tuner.write().on_rate_estimate(report.rate as f64);
+                    }
+                    let mut slow_start = self.slow_start.write();
+                    slow_start.on_capacity_estimate(
+                        report.rate as f64,
+                        probe::probe_datagram_len(&self.config),
+                    );
+                    info!(
+                        "대역폭 측정: {:.1} MB/s (프로브 {}/{}), 초기 윈도우 {} 청크",
+                        report.rate as f64 / 1_000_000.0,
+                        report.received,
+                        probe::PROBE_TRAIN_LEN,
+                        slow_start.window()
+                    );
+                }
+            }
+
+            MessageType::NackCancel => {
+                if !self.accepts_established(header.msg_type)
+                    || *self.client_addr.read() != Some(addr)
+                {
+                    return Ok(());
+                }
+
+                // 중복 전송 사본이 이미 채운 청크는 대기 중인 재전송에서 제외
+                if let Some(cancel) = NackCancelMessage::from_bytes(data) {
+                    if let Some(mut state) = self.segments.get_mut(&cancel.segment_id) {
+                        if state.file_id == cancel.file_id {
+                            state
+                                .retransmit_queue
+                                .retain(|id| !cancel.chunk_ids.contains(id));
+                        }
+                    }
+                }
+            }
+
+            MessageType::SegmentComplete => {
+                if !self.accepts_established(header.msg_type)
+                    || *self.client_addr.read() != Some(addr)
+                {
+                    return Ok(());
+                }
+
+                // 세그먼트 완료
+                if let Some(complete) = SegmentCompleteMessage::from_bytes(data) {
+                    let segment_id = complete.segment_id;
+                    let mut resize = None;
+                    let mut delivered = Vec::new();
+                    let mut stats = self.stats.write();
+                    if let Some((_, state)) = self
+                        .segments
+                        .remove_if(&segment_id, |_, state| state.file_id == complete.file_id)
+                    {
+                        self.release_in_flight(&state);
+                        // 재전송이나 탐침이 없었던 세그먼트만 RTT 표본 (Karn)
+                        if !state.nacked && state.tail_probes == 0 {
+                            self.rtt_estimate
+                                .lock()
+                                .on_sample(state.last_original_sent.elapsed());
+                        }
+                        // 원본 청크 데이터만 처음 보낸 경로의 유효 처리율로 (잠금 순서상 통계 해제 후)
+                        delivered.extend(
+                            state.chunks.iter().map(|c| (c.header.nic_id, c.data.len())),
+                        );
+                        if let Some(tuner) = &self.buffer_tuner {
+                            let mut tuner = tuner.write();
+                            tuner.on_rtt_sample(self.srtt());
+                            resize = tuner.on_delivered(state.bytes_in_flight as usize, Instant::now());
+                        }
+                        // 균일 세그먼트 표시는 청크를 보내지 않아 경로 추정에 쓰지 않음
+                        if state.sparse.is_none() {
+                            if !state.nacked {
+                                self.segment_sizer.write().on_clean_delivery();
+                            }
+                            self.slow_start
+                                .write()
+                                .on_delivered(complete.total_chunks_received);
+                            stats.record_segment_report(
+                                state.sent_chunks,
+                                complete.total_chunks_received,
+                                complete.duplicates_received,
+                            );
+                        }
+                    }
+                    stats.completed_segments += 1;
+                    drop(stats);
+                    // 완료 보고 하나는 NIC마다 한 번의 전달로 기록
+                    let mut per_nic: Vec<(u8, usize)> = Vec::new();
+                    for (nic_id, len) in delivered {
+                        match per_nic.iter_mut().find(|(id, _)| *id == nic_id) {
+                            Some((_, total)) => *total += len,
+                            None => per_nic.push((nic_id, len)),
+                        }
+                    }
+                    for (nic_id, len) in per_nic {
+                        self.path_manager.record_delivery(nic_id, len);
+                    }
+                    if let Some(size) = resize {
+                        buffertune::apply(socket, size);
+                    }
+                    if self.segment_log.sample() {
+                        debug!(
+                            segment_id,
+                            "세그먼트 완료 확인: 수신 {} 청크, 중복 {}",
+                            complete.total_chunks_received,
+                            complete.duplicates_received
+                        );
+                    }
+                }
+            }
+
+            MessageType::Heartbeat => {
+                if !self.accepts_established(header.msg_type) {
+                    return Ok(());
+                }
+
+                // 인증된 Heartbeat는 sequence를 기록해 나중에 새 주소에서 재전송되지 않게 함
+                self.authentic_heartbeat(header.msg_type, data);
+
+                // Heartbeat 응답
+                let response = crate::message::HeartbeatMessage::new(0);
+                socket.send_to(&response.to_bytes(), addr).await?;
+            }
+
+            MessageType::NetworkChange => {
+                if !self.accepts_established(header.msg_type)
+                    || *self.client_addr.read() != Some(addr)
+                {
+                    return Ok(());
+                }
+
+                // 경로가 바뀌었으면 이전 경로의 추정치를 버리고 다시 잼
+                if let Some(change) = NetworkChangeMessage::from_bytes(data) {
+                    let rtt = (change.rtt_us > 0).then(|| Duration::from_micros(change.rtt_us));
+                    self.restart_estimators(rtt);
+                    info!(?rtt, "네트워크 전환 통보, 추정치 재시작: {}", addr);
+
+                    if self.config.bandwidth_probe {
+                        for probe in probe::probe_train(&self.config) {
+                            socket.send_to(&probe, addr).await?;
+                        }
+                    }
+                }
+            }
+
+            MessageType::PathResponse => {
+                if let Some(response) = PathResponseMessage::from_bytes(data) {
+                    if !self.on_pmtu_probe_response(&response, socket).await? {
+                        self.complete_migration(response, addr, socket).await;
+                    }
+                }
+            }
+
+            MessageType::Close => {
+                if *self.client_addr.read() != Some(addr) {
+                    return Ok(());
+                }
+                let _ = self.state.transition(ConnectionState::Closed);
+                *self.client_addr.write() = None;
+                *self.client_session.write() = None;
+                *self.pending_migration.lock() = None;
+                self.clear_pause();
+                self.disconnect_peer(socket);
+                info!("클라이언트 연결 종료: {}", addr);
+            }
+
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// 클라이언트 → 서버 메시지에 실린 연결 ID (싣지 않는 메시지나 이전 형식은 0)
+    fn connection_id_of(msg_type: MessageType, data: &[u8]) -> u64 {
+        match msg_type {
+            MessageType::Nack => NackMessage::from_bytes(data).map(|m| m.connection_id),
+            MessageType::NackDelta => NackDeltaMessage::from_bytes(data).map(|m| m.connection_id),
+            MessageType::SegmentComplete => {
+                SegmentCompleteMessage::from_bytes(data).map(|m| m.connection_id)
+            }
+            MessageType::Heartbeat => HeartbeatMessage::from_bytes(data).map(|m| m.connection_id),
+            MessageType::NetworkChange => {
+                NetworkChangeMessage::from_bytes(data).map(|m| m.connection_id)
+            }
+            _ => None,
+        }
+        .unwrap_or(0)
+    }
+
+    /// 메타데이터 키로 태그가 맞고 지금까지 본 것보다 sequence가 큰 Heartbeat인지
+    ///
+    /// 맞으면 그 sequence까지 소비함. 키가 없으면 항상 거짓 (연결을 옮기지 않음)
+    fn authentic_heartbeat(&self, msg_type: MessageType, data: &[u8]) -> bool {
+        if msg_type != MessageType::Heartbeat {
+            return false;
+        }
+        let Some(heartbeat) = HeartbeatMessage::from_bytes(data) else {
+            return false;
+        };
+        let authentic = match &*self.metadata_auth.read() {
+            Some(auth) => heartbeat.verify(auth),
+            None => false,
+        };
+        authentic
+            && self
+                .next_heartbeat_sequence
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |next| {
+                    (heartbeat.sequence >= next).then(|| heartbeat.sequence.saturating_add(1))
+                })
+                .is_ok()
+    }
+
+    /// 새 주소로 도달성 확인 토큰 전송
+    ///
+    /// 대기 중인 확인은 하나뿐이고 `PATH_CHALLENGE_INTERVAL`보다 자주 다시 보내지 않음
+    /// (위조 출발지가 확인 메시지를 쏟아내게 만들 수 없음). 클라이언트에 연결된 소켓은 새
+    /// 주소의 응답을 커널이 버리므로 먼저 연결을 풂
+    async fn challenge_path<S: DatagramSocket>(
+        &self,
+        connection_id: u64,
+        addr: SocketAddr,
+        socket: &S,
+    ) -> Result<()> {
+        let challenge = {
+            let mut pending = self.pending_migration.lock();
+            if pending.is_some_and(|p| p.sent_at.elapsed() < PATH_CHALLENGE_INTERVAL) {
+                return Ok(());
+            }
+            let token = match *pending {
+                Some(p) if p.addr == addr => p.token,
+                _ => rand::random(),
+            };
+            *pending = Some(PendingMigration {
+                addr,
+                token,
+                sent_at: Instant::now(),
+            });
+            PathChallengeMessage::new(connection_id, token)
+        };
+
+        self.disconnect_peer(socket);
+        let challenge = self.control_datagram(MessageType::PathChallenge, challenge.to_bytes())?;
+        socket.send_to(&challenge, addr).await?;
+        debug!("새 주소에서 같은 연결 ID, 도달성 확인: {}", addr);
+        Ok(())
+    }
+
+    /// 확인 토큰이 메타데이터 키로 만든 태그와 함께 그 주소에서 돌아오면 연결을 새 주소로 옮김
+    async fn complete_migration<S: DatagramSocket>(
+        &self,
+        response: PathResponseMessage,
+        addr: SocketAddr,
+        socket: &S,
+    ) {
+        {
+            let mut pending = self.pending_migration.lock();
+            let authentic = match &*self.metadata_auth.read() {
+                Some(auth) => response.verify(auth),
+                None => false,
+            };
+            let valid = authentic
+                && pending.is_some_and(|p| p.addr == addr && p.token == response.token)
+                && *self.client_session.read() == Some(response.connection_id)
+                && self.state.get().is_established();
+            if !valid {
+                debug!("일치하지 않는 주소 이전 응답 무시: {}", addr);
+                return;
+            }
+            *pending = None;
+        }
+
+        let previous = *self.client_addr.read();
+        self.set_client(addr);
+        self.connect_peer(addr, socket).await;
+        self.stats.write().migrations += 1;
+        // 새 주소는 다른 경로일 수 있으므로 추정치도 다시 잼
+        self.restart_estimators(None);
+        if let Some(previous) = previous {
+            info!("연결 이전: {} → {}", previous, addr);
+        }
+    }
+
+    /// 네트워크 전환 후 혼잡/중복률 추정치를 보수적인 초기 상태로 되돌림
+    ///
+    /// 슬로 스타트를 초기 윈도우부터 다시 하고 (`rtt`가 있으면 그 RTT로), 적응형 세그먼트
+    /// 크기와 경로별 처리율/손실률, 최근 손실 기록을 비운 뒤 중복률을 기본값으로 되돌림.
+    /// 이후 새 경로의 보고로 다시 수렴
+    fn restart_estimators(&self, rtt: Option<Duration>) {
+        let mut slow_start = SlowStart::new(&self.config);
+        let mut rtt_estimate = self.initial_rtt_estimate();
+        if let Some(rtt) = rtt {
+            slow_start.on_rtt_sample(rtt);
+            rtt_estimate.on_sample(rtt);
+        }
+        *self.slow_start.write() = slow_start;
+        *self.rtt_estimate.lock() = rtt_estimate;
+
+        let limit = self.peer_segment_limit.load(Ordering::SeqCst);
+        *self.segment_sizer.write() = SegmentSizer::new(&self.config).with_limit(limit);
+
+        if let (Some(tuner), Some(rtt)) = (&self.buffer_tuner, rtt) {
+            tuner.write().on_rtt_sample(rtt);
+        }
+
+        self.path_manager.restart_estimates();
+        {
+            let mut stats = self.stats.write();
+            stats.restart_loss_window();
+            stats.network_changes += 1;
+        }
+        *self.current_redundancy.write() = self.config.base_redundancy_ratio;
+    }
+
+    /// NACK 처리
+    #[tracing::instrument(
+        level = "debug",
+        name = "segment",
+        skip_all,
+        fields(segment_id = nack.segment_id, nic_id = nack.nic_id)
+    )]
+    async fn handle_nack<S: DatagramSocket>(
+        &self,
+        nack: NackMessage,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        if self.nack_log.sample() {
+            debug!(missing = nack.missing_chunk_ids.len(), "NACK 수신");
+        }
+
+        // 다른 파일 범위의 NACK은 이 세그먼트에 적용하지 않음
+        if self
+            .segments
+            .get(&nack.segment_id)
+            .is_some_and(|state| state.file_id != nack.file_id)
+        {
+            debug!(file_id = nack.file_id, "파일 범위 불일치 NACK 무시");
+            return Ok(());
+        }
+
+        // 이미 포기한 세그먼트의 NACK: 포기 통보가 유실됐으므로 다시 알림
+        if !self.segments.contains_key(&nack.segment_id) {
+            let given_up = self.given_up.lock().get(&nack.segment_id).cloned();
+            if let Some((file_id, chunk_ids)) = given_up.filter(|(f, _)| *f == nack.file_id) {
+                return self
+                    .send_give_up(nack.segment_id, file_id, chunk_ids, socket, addr)
+                    .await;
+            }
+        }
+
+        // 균일 세그먼트는 청크가 없으므로 표시를 다시 보냄
+        let sparse = self
+            .segments
+            .get(&nack.segment_id)
+            .and_then(|state| state.sparse.clone());
+        if let Some((_, datagram)) = sparse {
+            self.wait_resumed().await?;
+            self.acquire_egress(datagram.len()).await;
+            socket.send_to(&datagram, addr).await?;
+            self.record_in_flight(nack.segment_id, datagram.len());
+            return Ok(());
+        }
+
+        // 세그먼트 전체 재요청: 수신자가 청크 수를 모르므로 원본 청크를 모두 바로 재전송
+        let mut nack = nack;
+        let whole = nack.is_whole_segment();
+        if whole {
+            let Some(ids) = self
+                .segments
+                .get(&nack.segment_id)
+                .map(|state| state.chunks.iter().map(|c| c.header.chunk_id).collect())
+            else {
+                return Ok(());
+            };
+            nack.missing_chunk_ids = ids;
+        }
+
+        // 정지 중 NACK은 손실 신호로 보지 않음: 재개 후 보내도록 큐에 넣고 정지를 다시 알림
+        if self.is_paused() {
+            if let Some(mut state) = self.segments.get_mut(&nack.segment_id) {
+                for chunk_id in &nack.missing_chunk_ids {
+                    if !state.retransmit_queue.contains(chunk_id) {
+                        state.retransmit_queue.push(*chunk_id);
+                    }
+                }
+            }
+            self.pause.lock().announced = None;
+            return Ok(());
+        }
+
+        // 통계 업데이트 (분할된 NACK은 첫 조각에서 한 번만 집계)
+        {
+            let mut stats = self.stats.write();
+            if nack.fragment_index == 0 {
+                stats.total_nacks += 1;
+            }
+            stats.last_nack_time = Some(Instant::now());
+            if let Some(nic_stat) = stats.nic_stats.get_mut(nack.nic_id as usize) {
+                nic_stat.record_loss(nack.missing_chunk_ids.len() as u64);
+            }
+        }
+
+        // 손실 기록
+        self.path_manager
+            .record_loss(nack.nic_id, nack.missing_chunk_ids.len() as u64);
+
+        let receive_ratio = if nack.receive_ratio.is_nan() {
+            0.0
+        } else {
+            nack.receive_ratio.clamp(0.0, 1.0)
+        };
+        let immediate = whole || receive_ratio >= IMMEDIATE_RETRANSMIT_RATIO;
+
+        let mut blackhole = false;
+        if let Some(mut state) = self.segments.get_mut(&nack.segment_id) {
+            if !state.nacked && !nack.missing_chunk_ids.is_empty() {
+                state.nacked = true;
+                self.segment_sizer.write().on_loss();
+                self.slow_start.write().on_loss();
+            }
+            state.receive_ratio = receive_ratio;
+
+            // 재전송해도 누락이 줄지 않으면 큰 데이터그램이 버려지는 경로일 수 있음 (탐침으로 확인)
+            if nack.fragment_index == 0 && self.config.pmtu_blackhole_nacks > 0 {
+                let missing = nack.missing_chunk_ids.len();
+                if state.last_missing > 0 && missing >= state.last_missing {
+                    state.stalled_nacks += 1;
+                } else {
+                    state.stalled_nacks = 0;
+                }
+                state.last_missing = missing;
+                blackhole = state.stalled_nacks >= self.config.pmtu_blackhole_nacks;
+            }
+        }
+        // 탐색 중인 세그먼트는 탐침 결과가 나올 때까지 같은 크기로 재전송하지 않음
+        if blackhole || self.pmtu_searching(nack.segment_id) {
+            return self.start_pmtu_search(nack.segment_id, socket, addr).await;
+        }
+
+        if let Some(mut state) = self.segments.get_mut(&nack.segment_id) {
+            // 막 시작한 세그먼트는 큐에서 대기
+            if !immediate {
+                for chunk_id in &nack.missing_chunk_ids {
+                    if !state.retransmit_queue.contains(chunk_id) {
+                        state.retransmit_queue.push(*chunk_id);
+                    }
+                }
+            }
+        }
+
+        // 거의 완료된 세그먼트는 즉시 재전송
+        if immediate {
+            self.retransmit_chunks(nack.segment_id, &nack.missing_chunk_ids, socket, addr)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// 이 세그먼트로 PMTU 탐색 중인지
+    fn pmtu_searching(&self, segment_id: SegmentId) -> bool {
+        self.pmtu_search
+            .lock()
+            .is_some_and(|search| search.segment_id == segment_id)
+    }
+
+    /// PMTU 블랙홀 의심: 지금 청크 크기의 탐침부터 보내 확인
+    ///
+    /// 이미 탐색 중이면 아무것도 하지 않음. 다른 세그먼트의 탐색으로 이미 줄인 크기가 있으면
+    /// 탐침 없이 그 크기로 다시 나눔. 응답이 없으면 주기 작업이 크기를 줄여 가며 다시 보냄
+    /// (`advance_pmtu_search`)
+    async fn start_pmtu_search<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        // 경로별 크기가 섞인 세그먼트는 가장 큰 청크 기준
+        let current = match self.segments.get(&segment_id) {
+            Some(state) => state.chunks.iter().map(|c| c.data.len()).max().unwrap_or(0),
+            None => return Ok(()),
+        };
+        let limit = self.chunk_size_limit.load(Ordering::SeqCst);
+        if limit > 0 && limit < current {
+            return self.resplit_and_resend(segment_id, limit, socket, addr).await;
+        }
+
+        let probe = {
+            let mut search = self.pmtu_search.lock();
+            if search.is_some() || current == 0 {
+                return Ok(());
+            }
+            let probe = PmtuSearch {
+                segment_id,
+                full_size: current,
+                chunk_size: current,
+                token: rand::random(),
+                sent_at: Instant::now(),
+                attempts: 1,
+            };
+            *search = Some(probe);
+            probe
+        };
+        self.send_pmtu_probe(probe, socket, addr).await
+    }
+
+    /// 청크 데이터그램과 같은 크기로 채운 PathChallenge를 탐침으로 전송 (다른 전송처럼 페이싱)
+    async fn send_pmtu_probe<S: DatagramSocket>(
+        &self,
+        probe: PmtuSearch,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let connection_id = self.client_session.read().unwrap_or(0);
+        let datagram_len = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN + probe.chunk_size;
+        let datagram =
+            PathChallengeMessage::new(connection_id, probe.token).to_padded_bytes(datagram_len);
+        self.pace_slow_start().await;
+        self.acquire_egress(datagram.len()).await;
+        socket.send_to(&datagram, addr).await?;
+        self.stats.write().pmtu_probes += 1;
+        debug!(
+            segment_id = probe.segment_id,
+            attempt = probe.attempts,
+            "PMTU 탐침: {} bytes",
+            datagram.len()
+        );
+        Ok(())
+    }
+
+    /// 응답 없는 PMTU 탐침을 다시 보내거나, 횟수를 다 쓰면 크기를 절반으로 줄여 보냄
+    ///
+    /// 최소 크기에서도 응답이 없으면 탐색을 멈추고 기존 재전송/포기 규칙에 맡김
+    async fn advance_pmtu_search<S: DatagramSocket>(&self, socket: &S, addr: SocketAddr) {
+        let interval = self.tail_probe_interval();
+        let Some(segment_id) = self.pmtu_search.lock().map(|search| search.segment_id) else {
+            return;
+        };
+        let alive = self.segments.contains_key(&segment_id);
+        let probe = {
+            let mut guard = self.pmtu_search.lock();
+            let Some(search) = guard.as_mut() else {
+                return;
+            };
+            if !alive {
+                *guard = None;
+                return;
+            }
+            if search.sent_at.elapsed() < interval {
+                return;
+            }
+            if search.attempts >= PMTU_PROBE_ATTEMPTS {
+                let smaller = (search.chunk_size / 2).max(MIN_BLACKHOLE_CHUNK_SIZE);
+                if smaller >= search.chunk_size {
+                    warn!(segment_id, "최소 크기 PMTU 탐침에도 응답 없음: 탐색 중단");
+                    *guard = None;
+                    return;
+                }
+                search.chunk_size = smaller;
+                search.attempts = 0;
+            }
+            search.attempts += 1;
+            search.sent_at = Instant::now();
+            *search
+        };
+        if let Err(e) = self.send_pmtu_probe(probe, socket, addr).await {
+            warn!(segment_id, "PMTU 탐침 실패: {}", e);
+        }
+    }
+
+    /// 탐침 응답이면 탐색을 끝내고 true
+    ///
+    /// 처음 크기가 통과했으면 블랙홀이 아닌 손실이므로 평소 재전송을 계속하고, 줄인 크기가
+    /// 통과했으면 세그먼트를 그 크기로 다시 나눠 보냄
+    async fn on_pmtu_probe_response<S: DatagramSocket>(
+        &self,
+        response: &PathResponseMessage,
+        socket: &S,
+    ) -> Result<bool> {
+        let search = {
+            let mut guard = self.pmtu_search.lock();
+            let matches = guard.is_some_and(|search| search.token == response.token)
+                && *self.client_session.read() == Some(response.connection_id);
+            if !matches {
+                return Ok(false);
+            }
+            guard.take()
+        };
+        let Some(search) = search else {
+            return Ok(false);
+        };
+        if search.chunk_size >= search.full_size {
+            if let Some(mut state) = self.segments.get_mut(&search.segment_id) {
+                state.stalled_nacks = 0;
+                state.last_missing = 0;
+            }
+            debug!(segment_id = search.segment_id, "PMTU 탐침 통과: 블랙홀 아님");
+            return Ok(true);
+        }
+        let Some(addr) = *self.client_addr.read() else {
+            return Ok(true);
+        };
+        self.resplit_and_resend(search.segment_id, search.chunk_size, socket, addr)
+            .await?;
+        Ok(true)
+    }
+
+    /// 세그먼트를 `chunk_size` 청크로 다시 나눠 전부 보냄 (원본 전송처럼 페이싱)
+    ///
+    /// 이후 세그먼트도 줄인 크기로 나눔
+    async fn resplit_and_resend<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        chunk_size: usize,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let chunks = {
+            let Some(mut state) = self.segments.get_mut(&segment_id) else {
+                return Ok(());
+            };
+            state.stalled_nacks = 0;
+            state.last_missing = 0;
+            let current = state
+                .chunks
+                .iter()
+                .map(|chunk| chunk.data.len())
+                .max()
+                .unwrap_or(0);
+            if chunk_size >= current {
+                return Ok(());
+            }
+
+            let mut data = BytesMut::with_capacity(state.chunks.iter().map(|c| c.data.len()).sum());
+            for chunk in &state.chunks {
+                data.extend_from_slice(&chunk.data);
+            }
+            let nic_id = state.chunks[0].header.nic_id;
+            let boundary = state.chunks[0].header.boundary;
+            let end_of_stream = state.chunks[0].header.end_of_stream;
+            let mut chunks = SegmentBuilder::new(chunk_size)
+                .split_bytes_into_chunks(segment_id, data.freeze(), nic_id);
+            for chunk in &mut chunks {
+                chunk.header.file_id = state.file_id;
+                chunk.header.boundary = boundary;
+                chunk.header.end_of_stream = end_of_stream;
+            }
+
+            state.sent_chunk_ids = vec![false; chunks.len()];
+            state.sent_chunks += chunks.len() as u32;
+            state.chunks = chunks.clone();
+            state.redundant_chunks.clear();
+            state.retransmit_queue.clear();
+            state.retransmits.clear();
+            state.nack_delta = None;
+            state.tail_probes = 0;
+            state.last_sent = Instant::now();
+
+            let limit = self.chunk_size_limit.load(Ordering::SeqCst);
+            if limit == 0 || chunk_size < limit {
+                self.chunk_size_limit.store(chunk_size, Ordering::SeqCst);
+            }
+            warn!(segment_id, "PMTU 블랙홀: 청크 크기 {} → {} bytes", current, chunk_size);
+            chunks
+        };
+
+        self.transmit_chunks(&chunks, &[], socket, addr).await
+    }
+
+    /// 델타 NACK을 세그먼트의 추적 집합에 적용하고 전체 누락 목록으로 변환
+    ///
+    /// 기준 순번이 맞지 않으면 (앞선 델타 유실) 추적을 멈추고 새로 누락된 청크만 재전송.
+    /// 수신자의 다음 전체 보고에서 다시 맞춰짐
+    fn apply_nack_delta(&self, delta: NackDeltaMessage) -> NackMessage {
+        let missing = match self.segments.get_mut(&delta.segment_id) {
+            Some(mut state) if state.file_id == delta.file_id => {
+                let tracked = &mut state.nack_delta;
+                match tracked {
+                    _ if delta.full => {
+                        *tracked = Some((delta.seq, delta.added.iter().copied().collect()));
+                        delta.added
+                    }
+                    Some((seq, set)) if *seq == delta.base_seq => {
+                        for chunk_id in &delta.removed {
+                            set.remove(chunk_id);
+                        }
+                        set.extend(&delta.added);
+                        *seq = delta.seq;
+                        set.iter().copied().collect()
+                    }
+                    _ => {
+                        debug!(
+                            segment_id = delta.segment_id,
+                            base_seq = delta.base_seq,
+                            "기준이 맞지 않는 델타 NACK, 전체 보고까지 추적 중단"
+                        );
+                        *tracked = None;
+                        delta.added
+                    }
+                }
+            }
+            _ => delta.added,
+        };
+
+        NackMessage::new(delta.segment_id, missing, delta.receive_ratio, delta.nic_id)
+            .with_file_id(delta.file_id)
+    }
+
+    /// 청크 재전송
+    async fn retransmit_chunks<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        chunk_ids: &[ChunkId],
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let pad_len = self.padded_datagram_len();
+        let limit = self.config.max_chunk_retransmits;
+        let srtt = self.srtt();
+        let datagrams: Vec<RetransmitDatagram> = match self.segments.get_mut(&segment_id) {
+            Some(mut state) => {
+                let dead = state.charge_retransmits(chunk_ids, limit, srtt);
+                if !dead.is_empty() {
+                    let file_id = state.file_id;
+                    drop(state);
+                    if let Some((_, state)) = self.segments.remove(&segment_id) {
+                        self.release_in_flight(&state);
+                    }
+                    let dead = self.abandon_segment(segment_id, file_id, dead);
+                    return self.send_give_up(segment_id, file_id, dead, socket, addr).await;
+                }
+                let datagrams: Vec<RetransmitDatagram> = chunk_ids
+                    .iter()
+                    .filter_map(|&chunk_id| state.retransmit_chunk(chunk_id))
+                    .map(|chunk| (chunk.header.nic_id, chunk.to_retransmit_bytes(pad_len)))
+                    .collect();
+                state.sent_chunks += datagrams.len() as u32;
+                datagrams
+            }
+            None => return Ok(()),
+        };
+
+        // 송신 상한 대기 중에 세그먼트 맵을 잠그고 있지 않도록 가드 해제 후 전송
+        for (nic_id, data) in datagrams {
+            self.acquire_egress(data.len()).await;
+            self.send_on_path(&data, nic_id, socket, addr).await?;
+            self.record_retransmit(segment_id, nic_id, data.len());
+        }
+
+        Ok(())
+    }
+
+    /// 슬로 스타트 중이면 다음 전송 슬롯까지 대기
+    async fn pace_slow_start(&self) {
+        loop {
+            let wait = self.slow_start.write().next_send(Instant::now());
+            if wait.is_zero() {
+                return;
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// 주기적 재전송 처리
+    async fn process_retransmits<S: DatagramSocket>(&self, socket: &S) {
+        let client_addr = match *self.client_addr.read() {
+            Some(addr) => addr,
+            None => return,
+        };
+
+        let pad_len = self.padded_datagram_len();
+        let probe_interval = self.tail_probe_interval();
+        let limit = self.config.max_chunk_retransmits;
+        let srtt = self.srtt();
+        let mut tail_probes = 0;
+        let mut give_ups = Vec::new();
+        let mut markers = Vec::new();
+        let mut fallbacks = Vec::new();
+        let mut suspects = Vec::new();
+        let mut pending: Vec<(f32, SegmentId, Vec<RetransmitDatagram>)> = Vec::new();
+        for mut entry in self.segments.iter_mut() {
+            let segment_id = *entry.key();
+            let state = entry.value_mut();
+
+            // 청크가 모두 손실되면 수신자는 세그먼트가 있는지 몰라 NACK도 완료 보고도 없음.
+            // 피드백 없이 조용하면 마지막 청크를 다시 보내 NACK을 유도 (완료 보고만 손실된
+            // 경우를 위해 간격은 탐침마다 두 배)
+            let backoff = probe_interval * (1u32 << state.tail_probes.min(10));
+            if !state.nacked && state.last_sent.elapsed() >= backoff {
+                if let Some(chunk) = state.chunks.last() {
+                    // 탐침에도 계속 응답이 없으면 모든 데이터그램이 버려지는 PMTU 블랙홀일 수 있음
+                    let blackhole = self.config.pmtu_blackhole_nacks;
+                    if blackhole > 0 && state.tail_probes >= blackhole {
+                        suspects.push(segment_id);
+                    }
+                    state.retransmit_queue.push(chunk.header.chunk_id);
+                    state.last_sent = Instant::now();
+                    state.tail_probes += 1;
+                    tail_probes += 1;
+                } else if let Some((marker, datagram)) = &state.sparse {
+                    if state.tail_probes >= SPARSE_FALLBACK_PROBES {
+                        fallbacks.push(*marker);
+                    } else {
+                        markers.push((segment_id, datagram.clone()));
+                    }
+                    state.last_sent = Instant::now();
+                    state.tail_probes += 1;
+                    tail_probes += 1;
+                }
+            }
+
+            if !state.retransmit_queue.is_empty() {
+                let chunks_to_retransmit: Vec<ChunkId> = state.retransmit_queue.drain(..).collect();
+                let dead = state.charge_retransmits(&chunks_to_retransmit, limit, srtt);
+                if !dead.is_empty() {
+                    // 정리 단계에서 미확인 바이트와 함께 제거
+                    state.completed = true;
+                    give_ups.push((segment_id, state.file_id, dead));
+                    continue;
+                }
+                let datagrams: Vec<RetransmitDatagram> = chunks_to_retransmit
+                    .iter()
+                    .filter_map(|&chunk_id| state.retransmit_chunk(chunk_id))
+                    .map(|chunk| (chunk.header.nic_id, chunk.to_retransmit_bytes(pad_len)))
+                    .collect();
+                state.sent_chunks += datagrams.len() as u32;
+                pending.push((state.receive_ratio, segment_id, datagrams));
+            }
+
+            // 타임아웃 확인
+            if state.created_at.elapsed() > Duration::from_millis(self.config.segment_timeout_ms) {
+                state.completed = true;
+            }
+        }
+
+        if tail_probes > 0 {
+            self.stats.write().tail_probes += tail_probes;
+        }
+
+        // 완료된 세그먼트 정리 (타임아웃은 손실로 보고 미확인 바이트에서 제외)
+        self.segments.retain(|_, state| {
+            if state.completed {
+                self.release_in_flight(state);
+            }
+            !state.completed
+        });
+        for (segment_id, file_id, dead) in give_ups {
+            let dead = self.abandon_segment(segment_id, file_id, dead);
+            let sent = self.send_give_up(segment_id, file_id, dead, socket, client_addr);
+            if let Err(e) = sent.await {
+                warn!(segment_id, "포기 통보 실패: {}", e);
+            }
+        }
+
+        for (segment_id, datagram) in markers {
+            self.acquire_egress(datagram.len()).await;
+            if let Err(e) = socket.send_to(&datagram, client_addr).await {
+                warn!(segment_id, "균일 세그먼트 표시 재전송 실패: {}", e);
+                continue;
+            }
+            self.record_in_flight(segment_id, datagram.len());
+        }
+        for marker in fallbacks {
+            if let Err(e) = self.resend_sparse_as_chunks(marker, socket, client_addr).await {
+                warn!(segment_id = marker.segment_id, "균일 세그먼트 청크 전송 실패: {}", e);
+            }
+        }
+        for segment_id in suspects {
+            if let Err(e) = self.start_pmtu_search(segment_id, socket, client_addr).await {
+                warn!(segment_id, "PMTU 탐색 시작 실패: {}", e);
+            }
+        }
+        self.advance_pmtu_search(socket, client_addr).await;
+
+        // 수신률이 높은 세그먼트부터 (같으면 먼저 보낸 세그먼트부터)
+        pending.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+        for (_, segment_id, datagrams) in pending {
+            for (nic_id, data) in datagrams {
+                self.acquire_egress(data.len()).await;
+                if let Err(e) = self.send_on_path(&data, nic_id, socket, client_addr).await {
+                    warn!(segment_id, "재전송 실패: {}", e);
+                    continue;
+                }
+                self.record_retransmit(segment_id, nic_id, data.len());
+            }
+        }
+    }
+
+    /// 탐침에도 응답이 없는 균일 세그먼트를 청크로 다시 보냄
+    ///
+    /// 수신자가 표시를 확인할 수 없으면(암호화 중 인증 키 없음 등) 표시를 버리고 NACK도 하지
+    /// 않으므로, 이 연결에서는 이후 균일 세그먼트도 청크로 보냄
+    async fn resend_sparse_as_chunks<S: DatagramSocket>(
+        &self,
+        marker: SparseSegmentMessage,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        self.peer_sparse.store(false, Ordering::SeqCst);
+        let data = Bytes::from(vec![marker.fill; marker.segment_size as usize]);
+        let mut chunks = self.split_segment(marker.segment_id, data);
+        for chunk in &mut chunks {
+            chunk.header.file_id = marker.file_id;
+            chunk.header.boundary = marker.boundary;
+        }
+        let redundant_chunks = self.create_redundant_chunks(&chunks);
+        match self.segments.get_mut(&marker.segment_id) {
+            Some(mut state) => {
+                state.sparse = None;
+                state.sent_chunk_ids = vec![false; chunks.len()];
+                state.sent_chunks += (chunks.len() + redundant_chunks.len()) as u32;
+                state.chunks = chunks.clone();
+                state.redundant_chunks = redundant_chunks.clone();
+                state.tail_probes = 0;
+                state.last_sent = Instant::now();
+                state.last_original_sent = Instant::now();
+            }
+            None => return Ok(()),
+        }
+        debug!(
+            segment_id = marker.segment_id,
+            "균일 세그먼트 표시에 응답 없음: 청크로 전송"
+        );
+        self.transmit_chunks(&chunks, &redundant_chunks, socket, addr).await
+    }
+
+    /// 첫 꼬리 손실 탐침까지의 간격: 평활 RTT 기반 재전송 판단 간격 (srtt + 4 × rttvar).
+    /// 최소 RTT는 경로가 느려져도 줄기만 해 완료 보고가 오는 중에도 탐침을 보내게 되므로 쓰지 않음
+    fn tail_probe_interval(&self) -> Duration {
+        self.rtt_estimate.lock().timeout().max(TAIL_PROBE_MIN_INTERVAL)
+    }
+
+    /// 재전송 한도를 넘긴 세그먼트 포기 기록 (상태는 호출자가 제거). 통보할 청크 ID 반환
+    fn abandon_segment(
+        &self,
+        segment_id: SegmentId,
+        file_id: FileId,
+        mut dead: Vec<ChunkId>,
+    ) -> Vec<ChunkId> {
+        warn!(
+            segment_id,
+            "청크 {:?} 재전송 {}회 초과: 세그먼트 포기",
+            dead,
+            self.config.max_chunk_retransmits
+        );
+        self.stats.write().abandoned_segments += 1;
+        dead.truncate(MAX_GIVE_UP_CHUNK_IDS);
+        let mut given_up = self.given_up.lock();
+        given_up.insert(segment_id, (file_id, dead.clone()));
+        while given_up.len() > MAX_REMEMBERED_GIVE_UPS {
+            given_up.pop_first();
+        }
+        dead
+    }
+
+    /// 수신자에게 세그먼트 포기 통보
+    async fn send_give_up<S: DatagramSocket>(
+        &self,
+        segment_id: SegmentId,
+        file_id: FileId,
+        chunk_ids: Vec<ChunkId>,
+        socket: &S,
+        addr: SocketAddr,
+    ) -> Result<()> {
+        let connection_id = self.client_session.read().unwrap_or(0);
+        let msg = GiveUpMessage::new(connection_id, segment_id, file_id, chunk_ids);
+        let data = self.control_datagram(MessageType::GiveUp, msg.to_bytes())?;
+        socket.send_to(&data, addr).await?;
+        Ok(())
+    }
+
+    /// 나눌 수 없는 컨트롤 메시지의 데이터그램 크기 확인
+    fn control_datagram(&self, msg_type: MessageType, bytes: Vec<u8>) -> Result<Vec<u8>> {
+        crate::message::bounded_datagram(msg_type, bytes, self.config.max_datagram_size)
+    }
+
+    /// 송신 상한이 있으면 이 세션 몫에 맞춰 대기
+    async fn acquire_egress(&self, bytes: usize) {
+        if let Some(egress) = &self.egress {
+            egress.acquire(bytes).await;
+        }
+    }
+
+    /// 도달성이 확인된 클라이언트로 전송 시작
+    fn accept_client(&self, addr: SocketAddr) -> Result<()> {
+        self.state.transition(ConnectionState::Transferring)?;
+        *self.pending_migration.lock() = None;
+        self.set_client(addr);
+        Ok(())
+    }
+
+    /// 클라이언트 주소와 연결 스팬 교체
+    fn set_client(&self, addr: SocketAddr) {
+        let span = info_span!(
+            parent: None,
+            "connection",
+            peer = %addr,
+            session = tracing::field::Empty
+        );
+        if let Some(session) = *self.client_session.read() {
+            span.record("session", session);
+        }
+        *self.connection_span.write() = span;
+        *self.client_addr.write() = Some(addr);
+    }
+
+    /// 소켓을 클라이언트에 연결 (`PeerUdpSocket`만 지원, 실패하면 `send_to`로 계속)
+    async fn connect_peer<S: DatagramSocket>(&self, addr: SocketAddr, socket: &S) {
+        match socket.connect_peer(addr).await {
+            Ok(true) => {
+                self.peer_connected.store(true, Ordering::SeqCst);
+                *self.client_heard.lock() = Instant::now();
+                debug!("소켓을 클라이언트에 연결: {}", addr);
+            }
+            Ok(false) => {}
+            Err(e) => warn!("소켓 연결 실패, send_to로 전송: {}", e),
+        }
+    }
+
+    /// 연결 종료 후 다른 클라이언트를 받을 수 있게 소켓 연결 해제
+    fn disconnect_peer<S: DatagramSocket>(&self, socket: &S) {
+        self.peer_connected.store(false, Ordering::SeqCst);
+        if let Err(e) = socket.disconnect_peer() {
+            warn!("소켓 연결 해제 실패: {}", e);
+        }
+    }
+
+    /// 확인된 클라이언트 주소에서 메시지 수신 (조용해서 소켓 연결을 풀었으면 다시 연결)
+    async fn on_client_heard<S: DatagramSocket>(&self, addr: SocketAddr, socket: &S) {
+        *self.client_heard.lock() = Instant::now();
+        if self.config.connect_socket
+            && !self.peer_connected.load(Ordering::SeqCst)
+            && self.state.get().is_established()
+        {
+            self.connect_peer(addr, socket).await;
+        }
+    }
+
+    /// 연결된 소켓으로 클라이언트 소식이 `CONNECTED_PEER_SILENCE` 동안 없으면 연결 해제
+    ///
+    /// 주소가 바뀐 클라이언트(NAT 재바인딩)의 Heartbeat가 커널에서 버려지지 않게 모든 주소에서
+    /// 받음. 옛 주소에서 다시 소식이 오면 `on_client_heard`가 다시 연결
+    fn release_silent_peer<S: DatagramSocket>(&self, socket: &S) {
+        if self.peer_connected.load(Ordering::SeqCst)
+            && self.client_heard.lock().elapsed() >= CONNECTED_PEER_SILENCE
+        {
+            self.disconnect_peer(socket);
+            debug!("클라이언트 소식 없음, 새 주소를 받도록 소켓 연결 해제");
+        }
+    }
+
+    /// 출발지에 해당하는 스팬 (미확인 출발지는 주소만 담은 임시 스팬)
+    fn span_for(&self, addr: SocketAddr) -> Span {
+        if *self.client_addr.read() == Some(addr) {
+            return self.connection_span();
+        }
+        info_span!(parent: None, "connection", peer = %addr)
+    }
+
+    /// 연결 수립 후에만 유효한 메시지인지 확인 (아니면 사유 기록)
+    fn accepts_established(&self, msg_type: MessageType) -> bool {
+        let state = self.state.get();
+        if !state.is_established() {
+            debug!("{:?} 상태에서 {:?} 무시", state, msg_type);
+            return false;
+        }
+        true
+    }
+
+    /// 드레인 중 남은 세그먼트가 모두 정리되면 종료
+    fn finish_drain<S: DatagramSocket>(&self, socket: &S) {
+        if self.state.get() == ConnectionState::Draining && self.segments.is_empty() {
+            let _ = self.state.transition(ConnectionState::Closed);
+            self.disconnect_peer(socket);
+            info!("드레인 완료, 연결 종료");
+        }
+    }
+
+    /// 중복/FEC 판단에 쓰는 손실률: 경로별 손실률과 최근 세그먼트 손실률 중 큰 값
+    /// (전체 평균은 짧은 손실 구간을 희석하므로 사용하지 않음)
+    fn loss_estimate(&self) -> f64 {
+        self.path_manager
+            .max_loss_rate()
+            .max(self.stats.read().recent_loss_rate())
+    }
+
+    /// 중복률 업데이트
+    fn update_redundancy(&self) {
+        let loss_rate = self.path_manager.max_loss_rate();
+        let recent_loss = self.stats.read().recent_loss_rate();
+        let rtt_us = self.slow_start.read().rtt().as_micros() as u64;
+        let new_redundancy = self.config.redundancy(loss_rate, rtt_us, recent_loss);
+        *self.current_redundancy.write() = new_redundancy;
+    }
+
+    /// 지속 손실에 따른 중복/FEC 모드 전환 및 클라이언트 통보
+    async fn update_redundancy_mode<S: DatagramSocket>(&self, socket: &S) {
+        if !self.config.redundancy_enabled() {
+            return;
+        }
+        // NIC 경로 통계가 없어도 완료 보고 기반 최근 손실률로 판단
+        let loss_rate = self.loss_estimate();
+        let changed = self.fec_policy.write().update(loss_rate, Instant::now());
+
+        if let Some(mode) = changed {
+            info!("손실 보정 모드 전환: {:?} (손실률 {:.1}%)", mode, loss_rate * 100.0);
+            let client_addr = *self.client_addr.read();
+            if let Some(addr) = client_addr {
+                let msg = ModeChangeMessage::new(mode);
+                match self.control_datagram(MessageType::ModeChange, msg.to_bytes()) {
+                    Ok(msg) => {
+                        if let Err(e) = socket.send_to(&msg, addr).await {
+                            warn!("모드 변경 전송 실패: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("모드 변경 전송 실패: {}", e),
+                }
+            }
+        }
+    }
+
+    /// 새 데이터 없이 남은 세그먼트 복구 후 종료
+    pub fn drain(&self) -> Result<()> {
+        self.state.transition(ConnectionState::Draining)?;
+        Ok(())
+    }
+
+    /// 진행 중인 전송 일시 정지
+    ///
+    /// 새 청크와 재전송을 멈추고 연결은 유지 (Heartbeat 응답은 계속). 수신자에게 Pause를
+    /// 주기적으로 알려 NACK을 멈추고 타임아웃을 미루게 함. 전송 중인 `send_data`는
+    /// `resume`까지 다음 청크에서 대기. 이미 정지 중이면 아무 일도 하지 않음
+    pub fn pause(&self) -> Result<()> {
+        if !self.state.get().is_established() {
+            return Err(Error::ConnectionClosed);
+        }
+        let mut pause = self.pause.lock();
+        if pause.since.is_none() {
+            *pause = PauseState {
+                since: Some(Instant::now()),
+                ..PauseState::default()
+            };
+            self.paused.send_replace(true);
+            info!("전송 일시 정지");
+        }
+        Ok(())
+    }
+
+    /// 정지한 곳부터 전송 재개
+    ///
+    /// 정지한 시간만큼 세그먼트 타임아웃과 꼬리 탐침 시각을 미뤄 정지 구간을 손실로 보지 않음
+    pub fn resume(&self) {
+        let paused_for = {
+            let mut pause = self.pause.lock();
+            let Some(since) = pause.since.take() else {
+                return;
+            };
+            pause.resume_pending = true;
+            since.elapsed()
+        };
+        for mut state in self.segments.iter_mut() {
+            state.created_at += paused_for;
+            state.last_sent += paused_for;
+        }
+        self.paused.send_replace(false);
+        info!("전송 재개 ({:.1}s 정지)", paused_for.as_secs_f64());
+    }
+
+    /// 일시 정지 중인지
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// 정지 중이면 재개될 때까지 대기 (그사이 연결이 닫히면 `Error::ConnectionClosed`)
+    async fn wait_resumed(&self) -> Result<()> {
+        if !self.is_paused() {
+            return Ok(());
+        }
+        let mut paused = self.paused.subscribe();
+        let _ = paused.wait_for(|paused| !paused).await;
+        if self.state.get() == ConnectionState::Closed {
+            return Err(Error::ConnectionClosed);
+        }
+        Ok(())
+    }
+
+    /// 연결이 끝나면 정지 상태를 버리고 대기 중인 전송을 깨움
+    fn clear_pause(&self) {
+        *self.pause.lock() = PauseState::default();
+        self.paused.send_replace(false);
+    }
+
+    /// 정지 중이면 주기적으로 Pause, 재개 직후 한 번 Resume 통보
+    async fn announce_pause<S: DatagramSocket>(&self, socket: &S) {
+        let Some(addr) = *self.client_addr.read() else {
+            return;
+        };
+        let connection_id = self.client_session.read().unwrap_or(0);
+        let msg = {
+            let mut pause = self.pause.lock();
+            if pause.since.is_some() {
+                if pause
+                    .announced
+                    .is_some_and(|at| at.elapsed() < PAUSE_ANNOUNCE_INTERVAL)
+                {
+                    return;
+                }
+                pause.announced = Some(Instant::now());
+                PauseMessage::pause(connection_id)
+            } else if pause.resume_pending {
+                pause.resume_pending = false;
+                PauseMessage::resume(connection_id)
+            } else {
+                return;
+            }
+        };
+        if let Err(e) = socket.send_to(&msg.to_bytes(), addr).await {
+            warn!("{:?} 전송 실패: {}", msg.msg_type(), e);
+        }
+    }
+
+    /// 정지
+    pub fn stop(&self) {
+        self.shutdown.read().cancel();
+        self.state.force(ConnectionState::Closed);
+        self.clear_pause();
+    }
+
+    /// 송신 설정
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 현재 연결 상태
+    pub fn connection_state(&self) -> ConnectionState {
+        self.state.get()
+    }
+
+    /// Init 쿠키 발급/거부 누계
+    pub fn cookie_stats(&self) -> CookieStats {
+        self.guard.cookie_stats()
+    }
+
+    /// 현재 배정된 송신 몫 (bytes/s, 상한이 없으면 None)
+    pub fn egress_rate(&self) -> Option<f64> {
+        self.egress.as_ref().map(EgressShare::rate)
+    }
+
+    /// 현재 클라이언트 연결 스팬 (연결 전에는 비활성 스팬)
+    pub fn connection_span(&self) -> Span {
+        self.connection_span.read().clone()
+    }
+
+    /// 통계 반환
+    pub fn get_stats(&self) -> TransferStats {
+        let mut stats = self.stats.read().clone();
+        stats.bytes_in_flight = self.bytes_in_flight();
+        stats
+    }
+
+    /// 보냈지만 완료 보고(SegmentComplete)나 타임아웃으로 정리되지 않은 바이트
+    pub fn bytes_in_flight(&self) -> u64 {
+        self.bytes_in_flight.load(Ordering::Relaxed)
+    }
+
+    /// 현재 중복률
+    pub fn current_redundancy_ratio(&self) -> f64 {
+        *self.current_redundancy.read()
+    }
+
+    /// 다음 세그먼트에 사용할 크기 (적응형)
+    pub fn current_segment_size(&self) -> usize {
+        self.segment_sizer.read().current()
+    }
+
+    /// 자동 조정 중인 소켓 버퍼 크기 (자동 조정을 끄면 None)
+    pub fn socket_buffer_size(&self) -> Option<usize> {
+        self.buffer_tuner.as_ref().map(|tuner| tuner.read().current())
+    }
+
+    /// 새 세그먼트를 나누는 청크 크기 (PMTU 블랙홀을 감지하면 설정값보다 작아짐)
+    pub fn chunk_size(&self) -> usize {
+        match self.chunk_size_limit.load(Ordering::SeqCst) {
+            0 => self.config.effective_chunk_size().max(1),
+            limit => limit,
+        }
+    }
+
+    /// 청크 데이터그램을 맞출 크기 (줄인 청크 크기 기준, 패딩을 끄면 0)
+    fn padded_datagram_len(&self) -> usize {
+        let padded = self.config.padded_datagram_len();
+        let shrunk = self.config.effective_chunk_size().saturating_sub(self.chunk_size());
+        padded.saturating_sub(shrunk)
+    }
+
+    /// 균일 세그먼트 표시 인증 키 지정 (보통 `CryptoSession::metadata_auth`)
+    ///
+    /// 키를 지정하면 표시에 태그를 붙이고, `Config::encryption_enabled`이면 키가 있어야 표시를 보냄.
+    /// 연결 이전도 이 키로 인증한 Heartbeat와 확인 응답으로만 함 (키가 없으면 이전하지 않음)
+    pub fn set_metadata_auth(&self, auth: MetadataAuth) {
+        *self.metadata_auth.write() = Some(auth);
+    }
+
+    /// 균일 세그먼트를 표시로 보낼 수 있는지 (설정, 암호화 시 인증 키)
+    fn sparse_enabled(&self) -> bool {
+        self.config.sparse_segments
+            && (!self.config.encryption_enabled || self.metadata_auth.read().is_some())
+    }
+
+    /// 현재 클라이언트에게 균일 세그먼트를 표시로 보내는지 (클라이언트 지원 포함)
+    pub fn sends_sparse(&self) -> bool {
+        self.sparse_enabled() && self.peer_sparse.load(Ordering::SeqCst)
+    }
+
+    /// 범위를 벗어나 버린 RTT 표본 수
+    pub fn rejected_rtt_samples(&self) -> u64 {
+        self.rtt_filter.rejected()
+    }
+
+    /// 클라이언트 한도를 반영한 고정 세그먼트 크기 (InitAck, 파일 전송 단위)
+    pub fn negotiated_segment_size(&self) -> usize {
+        match self.peer_segment_limit.load(Ordering::SeqCst) {
+            0 => self.config.segment_size,
+            limit => self.config.segment_size.min(limit),
+        }
+    }
+
+    /// 클라이언트가 조립할 수 없는 크기의 세그먼트는 보내기 전에 거부
+    fn check_segment_size(&self, size: usize) -> Result<()> {
+        let max = self.peer_segment_limit.load(Ordering::SeqCst);
+        if max > 0 && size > max {
+            return Err(Error::SegmentTooLarge { size, max });
+        }
+        Ok(())
+    }
+
+    /// 평활 RTT (표본이 없으면 `slow_start_initial_rtt_ms`)
+    pub fn srtt(&self) -> Duration {
+        self.rtt_estimate.lock().srtt()
+    }
+
+    /// 표본 전 초기 RTT 추정
+    fn initial_rtt_estimate(&self) -> SmoothedRtt {
+        SmoothedRtt::new(Duration::from_millis(self.config.slow_start_initial_rtt_ms))
+    }
+
+    /// 슬로 스타트 상태 (진행 중 여부, 윈도우, RTT)
+    pub fn slow_start(&self) -> SlowStart {
+        self.slow_start.read().clone()
+    }
+
+    /// 현재 손실 보정 모드
+    pub fn redundancy_mode(&self) -> RedundancyMode {
+        self.fec_policy.read().mode()
+    }
+
+    /// 도달성이 확인된 클라이언트 주소
+    pub fn client_addr(&self) -> Option<SocketAddr> {
+        *self.client_addr.read()
+    }
+}
+
+/// 간단한 파일 전송용 송신자
+pub struct FileSender<S: DatagramSocket = UdpSocket> {
+    sender: Arc<Sender>,
+    socket: Arc<S>,
+}
+
+impl FileSender {
+    pub async fn new(config: Config, bind_addr: SocketAddr) -> Result<Self> {
+        let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
+        Ok(Self::with_socket(config, socket))
+    }
+}
+
+impl<S: DatagramSocket> FileSender<S> {
+    /// 이미 바인딩된 전송 계층으로 생성 (가상 네트워크, 터널 등)
+    pub fn with_socket(config: Config, socket: Arc<S>) -> Self {
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let sender = Arc::new(Sender::new(config, path_manager));
+        Self { sender, socket }
+    }
+
+    /// 송신 소켓 주소
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// 파일 데이터 전송
+    pub async fn send_file(&self, data: &[u8], client_addr: SocketAddr) -> Result<()> {
+        // 클라이언트 주소 설정 (핸드셰이크 없이 바로 전송 상태)
+        self.sender.accept_client(client_addr)?;
+
+        let mut offset = 0;
+
+        while offset < data.len() {
+            // 세그먼트마다 현재 적응형 크기 사용 (헤더의 segment_size로 수신측에 전달)
+            let (_, len) = self
+                .sender
+                .send_segment_at(data, offset, &*self.socket)
+                .await?;
+            offset += len;
+        }
+
+        self.sender.flush_coalesced(&*self.socket).await
+    }
+
+    /// 비동기 소스에서 읽으며 전송 (전체 길이를 미리 알 필요 없음)
+    ///
+    /// 세그먼트 크기만큼 찰 때까지 읽어 한 세그먼트로 보내고, 소스가 끝나면(EOF) 남은
+    /// 데이터를 마지막 세그먼트로 보냄. 다음 세그먼트는 앞 세그먼트 전송(혼잡 제어 페이싱)이
+    /// 끝난 뒤에 읽으므로 송신이 느려지면 소스 읽기도 멈춤. 반환: 전송한 바이트 수
+    pub async fn send_stream<R: AsyncRead + Unpin>(
+        &self,
+        mut reader: R,
+        client_addr: SocketAddr,
+    ) -> Result<u64> {
+        self.sender.accept_client(client_addr)?;
+
+        let mut total = 0u64;
+        loop {
+            let segment_size = self.sender.current_segment_size();
+            let segment = readahead::read_segment(&mut reader, segment_size).await?;
+            let filled = segment.len();
+            if filled == 0 {
+                break;
+            }
+
+            self.sender
+                .send_data(Bytes::from(segment), &*self.socket)
+                .await?;
+            total += filled as u64;
+            if filled < segment_size {
+                break;
+            }
+        }
+
+        self.sender.flush_coalesced(&*self.socket).await?;
+        Ok(total)
+    }
+
+    /// `send_stream`과 같지만 별도 IO 작업이 `readahead_segments`개를 미리 읽으며 전송
+    ///
+    /// 소스 읽기 지연이 세그먼트 전송 시간보다 짧으면 송신이 소스를 기다리지 않음. 그래도
+    /// 기다린 횟수는 `TransferStats::readahead_stalls`
+    pub async fn send_stream_prefetched<R: AsyncRead + Unpin + Send + 'static>(
+        &self,
+        reader: R,
+        client_addr: SocketAddr,
+    ) -> Result<u64> {
+        self.sender.accept_client(client_addr)?;
+
+        let sender = self.sender.clone();
+        let depth = self.sender.config.readahead_segments;
+        let mut source = Readahead::spawn(reader, depth, move || sender.current_segment_size());
+        let mut total = 0u64;
+        let mut stalls = 0;
+        let result = async {
+            while let Some(segment) = source.next_segment().await {
+                let segment = segment?;
+                total += segment.len() as u64;
+                // 대기 횟수는 세그먼트마다 반영해 전송 중에도 보이게
+                self.sender.stats.write().readahead_stalls += source.stalls() - stalls;
+                stalls = source.stalls();
+                self.sender.send_data(segment, &*self.socket).await?;
+            }
+            self.sender.flush_coalesced(&*self.socket).await
+        }
+        .await;
+        debug!(stalls, "미리 읽기 대기 {:?}", source.stall_time());
+        result.map(|()| total)
+    }
+
+    /// 통계 반환
+    pub fn stats(&self) -> TransferStats {
+        self.sender.get_stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// 빈 로컬 포트 선택
+    fn free_local_addr() -> SocketAddr {
+        let probe = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        probe.local_addr().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_offer_starts_reverse_handshake() {
+        let config = Config::default();
+        let server_socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        // 수신자는 알려진 주소에서 제안을 기다림 (한도를 넘는 제안은 무시)
+        let receiver_addr = free_local_addr();
+        let accept_config = Config {
+            max_accept_file_size: 1024 * 1024,
+            ..config.clone()
+        };
+        let accepting = tokio::spawn(crate::Receiver::accept_offer(
+            accept_config,
+            receiver_addr,
+            Arc::new(PathManager::new(config)),
+        ));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let probe = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        probe
+            .send_to(&OfferMessage::new(1 << 40).to_bytes(), receiver_addr)
+            .await
+            .unwrap();
+
+        sender
+            .offer(receiver_addr, 20_000, &*server_socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.client_addr(), Some(receiver_addr));
+        let (receiver, mut segment_rx) = accepting.await.unwrap().unwrap();
+
+        let data = Bytes::from((0..20_000u32).map(|i| i as u8).collect::<Vec<u8>>());
+        let segment_id = sender.send_data(data.clone(), &*server_socket).await.unwrap();
+        let (received_id, received) =
+            tokio::time::timeout(Duration::from_secs(2), segment_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!((received_id, received), (segment_id, data));
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_stream_source_reconstructed() {
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 16_000,
+            ..Config::default()
+        };
+        let file_sender = FileSender::new(config.clone(), "127.0.0.1:0".parse().unwrap())
+            .await
+            .unwrap();
+        let (receiver, segment_rx) = crate::Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            file_sender.local_addr().unwrap(),
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = crate::receiver::FileReceiver::from_receiver(receiver, segment_rx);
+
+        // 세그먼트 경계와 맞지 않게 끊겨 읽히는 소스
+        let data: Vec<u8> = (0..50_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let source = (&data[..7_000]).chain(&data[7_000..]);
+        let receiver_addr = file_receiver.receiver().local_addr();
+        let sent = file_sender.send_stream(source, receiver_addr).await.unwrap();
+        assert_eq!(sent, data.len() as u64);
+        assert_eq!(file_sender.stats().total_segments, 4);
+
+        let received = tokio::time::timeout(Duration::from_secs(5), file_receiver.receive_file(4))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received, data);
+        file_receiver.receiver().stop().await;
+    }
+
+    #[tokio::test]
+    async fn test_boundary_file_sizes_transferred() {
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 16_000,
+            ..Config::default()
+        };
+        let chunk = config.effective_chunk_size();
+        for len in [0, 1, chunk, chunk + 1] {
+            let file_sender = FileSender::new(config.clone(), "127.0.0.1:0".parse().unwrap())
+                .await
+                .unwrap();
+            let (receiver, segment_rx) = crate::Receiver::start(
+                config.clone(),
+                "127.0.0.1:0".parse().unwrap(),
+                file_sender.local_addr().unwrap(),
+                Arc::new(PathManager::new(config.clone())),
+            )
+            .await
+            .unwrap();
+            let mut file_receiver =
+                crate::receiver::FileReceiver::from_receiver(receiver, segment_rx);
+
+            let data: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+            let receiver_addr = file_receiver.receiver().local_addr();
+            file_sender.send_file(&data, receiver_addr).await.unwrap();
+            let expected_segments = len.div_ceil(config.segment_size);
+            assert_eq!(file_sender.stats().total_segments, expected_segments as u64);
+
+            // 빈 파일은 세그먼트 없이 바로 끝남
+            let received = tokio::time::timeout(
+                Duration::from_secs(2),
+                file_receiver.receive_file(expected_segments),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+            assert_eq!(received, data, "len {}", len);
+            if expected_segments > 0 {
+                file_receiver.receiver().stop().await;
+                continue;
+            }
+
+            // 빈 세그먼트도 길이 0 청크 하나로 전달됨 (빈 파일 뒤라 세그먼트 1)
+            let id = file_sender
+                .sender
+                .send_data(Bytes::new(), &*file_sender.socket)
+                .await
+                .unwrap();
+            let report =
+                tokio::time::timeout(Duration::from_secs(2), file_receiver.receive_discard(1))
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!((id, report.segments, report.bytes), (1, 1, 0));
+            assert_eq!(
+                report.digest,
+                crate::stats::TransferReport::expected_digest([(id, &[][..])])
+            );
+            file_receiver.receiver().stop().await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_offer_times_out_without_receiver() {
+        let config = Config::default();
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let nobody = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let result = tokio::time::timeout(
+            Duration::from_secs(10),
+            sender.offer(nobody.local_addr().unwrap(), 0, &socket),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(result, Err(Error::OfferTimeout { attempts: OFFER_MAX_ATTEMPTS })));
+    }
+
+    #[tokio::test]
+    async fn test_init_requires_cookie_before_bulk() {
+        let server_addr = free_local_addr();
+        let config = Config::default();
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let sender = Arc::new(Sender::new(config, path_manager));
+
+        let sender_task = sender.clone();
+        let handle = tokio::spawn(async move { sender_task.start(server_addr).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut buf = vec![0u8; 2048];
+
+        // 1. 쿠키 없는 Init → 쿠키만 응답, 대량 전송 불가
+        let init = InitMessage::new(false, [0u8; 32]);
+        let init_bytes = init.to_bytes();
+        client.send_to(&init_bytes, server_addr).await.unwrap();
+
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), client.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        let cookie = CookieMessage::from_bytes(&buf[..len]).expect("쿠키 응답 필요");
+        assert!(len <= init_bytes.len());
+        assert!(sender.client_addr().is_none());
+
+        let data_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        assert!(matches!(
+            sender.send_data(Bytes::from(vec![0u8; 4096]), &data_socket).await,
+            Err(Error::ConnectionClosed)
+        ));
+
+        // 2. 쿠키 에코 Init → InitAck, 클라이언트 확정
+        let mut init = init;
+        init.cookie = cookie.cookie;
+        client.send_to(&init.to_bytes(), server_addr).await.unwrap();
+
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), client.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(InitAckMessage::from_bytes(&buf[..len]).is_some());
+        assert_eq!(sender.client_addr(), Some(client.local_addr().unwrap()));
+
+        sender.stop();
+        let _ = handle.await;
+    }
+
+    /// 쿠키 교환까지 마치고 InitAck 수신
+    async fn handshake(client: &UdpSocket, server_addr: SocketAddr) -> Option<InitAckMessage> {
+        let mut buf = vec![0u8; 2048];
+        let mut init = InitMessage::new(false, [0u8; 32]);
+        client.send_to(&init.to_bytes(), server_addr).await.unwrap();
+        let reply = tokio::time::timeout(Duration::from_millis(300), client.recv_from(&mut buf));
+        let (len, _) = reply.await.ok()?.unwrap();
+        init.cookie = CookieMessage::from_bytes(&buf[..len])?.cookie;
+        client.send_to(&init.to_bytes(), server_addr).await.unwrap();
+        let reply = tokio::time::timeout(Duration::from_millis(300), client.recv_from(&mut buf));
+        let (len, _) = reply.await.ok()?.unwrap();
+        InitAckMessage::from_bytes(&buf[..len])
+    }
+
+    #[tokio::test]
+    async fn test_connected_socket_filters_until_close() {
+        let server_addr = free_local_addr();
+        let config = Config {
+            connect_socket: true,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let sender = Arc::new(Sender::new(config, path_manager));
+
+        let sender_task = sender.clone();
+        let handle = tokio::spawn(async move { sender_task.start(server_addr).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let stranger = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        assert!(handshake(&client, server_addr).await.is_some());
+        assert_eq!(sender.client_addr(), Some(client.local_addr().unwrap()));
+
+        // 연결된 동안 다른 주소의 Init은 커널이 걸러냄
+        assert!(handshake(&stranger, server_addr).await.is_none());
+
+        // 연결 종료 후 해제되어 다음 클라이언트를 받음
+        let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+        client.send_to(&close, server_addr).await.unwrap();
+        assert!(wait_until(Duration::from_secs(1), || sender.client_addr().is_none()).await);
+        assert!(handshake(&stranger, server_addr).await.is_some());
+        assert_eq!(sender.client_addr(), Some(stranger.local_addr().unwrap()));
+
+        sender.stop();
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn test_stop_disconnects_wildcard_bound_socket() {
+        let config = Config {
+            connect_socket: true,
+            ..Config::default()
+        };
+        let socket = Arc::new(PeerUdpSocket::new(UdpSocket::bind("0.0.0.0:0").await.unwrap()));
+        let port = socket.local_addr().unwrap().port();
+        let sender = Arc::new(Sender::new(config.clone(), Arc::new(PathManager::new(config))));
+        let sender_task = sender.clone();
+        let socket_task = socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        assert!(handshake(&client, ([127, 0, 0, 1], port).into()).await.is_some());
+        assert_eq!(socket.peer(), Some(client.local_addr().unwrap()));
+
+        sender.stop();
+        handle.await.unwrap().unwrap();
+        assert_eq!(socket.peer(), None);
+        assert_eq!(socket.local_addr().unwrap().port(), port);
+    }
+
+    #[tokio::test]
+    async fn test_replayed_or_foreign_cookie_rejected() {
+        let server_addr = free_local_addr();
+        let config = Config {
+            init_cookie_lifetime_ms: 50,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let sender = Arc::new(Sender::new(config, path_manager));
+
+        let sender_task = sender.clone();
+        let handle = tokio::spawn(async move { sender_task.start(server_addr).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let spoofed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut buf = vec![0u8; 2048];
+
+        let mut init = InitMessage::new(false, [0u8; 32]);
+        client.send_to(&init.to_bytes(), server_addr).await.unwrap();
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), client.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        init.cookie = CookieMessage::from_bytes(&buf[..len]).unwrap().cookie;
+
+        // 다른 주소에서 같은 쿠키 → 새 쿠키만 응답
+        spoofed.send_to(&init.to_bytes(), server_addr).await.unwrap();
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), spoofed.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(CookieMessage::from_bytes(&buf[..len]).is_some());
+        assert!(sender.client_addr().is_none());
+
+        // 유효 기간이 지난 뒤 재전송된 Init → 거부
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        client.send_to(&init.to_bytes(), server_addr).await.unwrap();
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), client.recv_from(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(CookieMessage::from_bytes(&buf[..len]).is_some());
+        assert!(sender.client_addr().is_none());
+
+        let stats = sender.cookie_stats();
+        assert_eq!(stats.rejected_invalid, 1);
+        assert_eq!(stats.rejected_stale, 1);
+        assert_eq!(stats.accepted, 0);
+
+        sender.stop();
+        let _ = handle.await;
+    }
+
+    #[tokio::test]
+    async fn test_segment_size_adapts_to_delivery() {
+        let config = Config {
+            chunk_size: 1000,
+            min_segment_size: 8000,
+            max_segment_size: 64000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        assert_eq!(sender.current_segment_size(), 8000);
+
+        // 손실 없는 전달 → 증가 (상한까지)
+        let mut sizes = vec![sender.current_segment_size()];
+        for _ in 0..12 {
+            let size = sender.current_segment_size();
+            let segment_id = sender
+                .send_data(Bytes::from(vec![7u8; size]), &socket)
+                .await
+                .unwrap();
+            let complete = SegmentCompleteMessage {
+                segment_id,
+                file_id: 0,
+                total_chunks_received: 0,
+                duplicates_received: 0,
+                elapsed_ms: 1,
+                connection_id: 0,
+            };
+            sender
+                .handle_control_message(&complete.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+            sizes.push(sender.current_segment_size());
+        }
+        assert!(sizes.windows(2).all(|w| w[1] >= w[0]));
+        assert_eq!(sender.current_segment_size(), 64000);
+        assert!(sizes.iter().all(|s| s % 1000 == 0));
+
+        // 손실 → 감소 (세그먼트당 한 번), 하한 아래로는 내려가지 않음
+        let segment_id = sender
+            .send_data(Bytes::from(vec![7u8; 64000]), &socket)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            let nack = NackMessage::new(segment_id, vec![3, 5], 0.9, 0);
+            sender
+                .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        assert_eq!(sender.current_segment_size(), 32000);
+
+        for _ in 0..6 {
+            let id = sender
+                .send_data(Bytes::from(vec![7u8; 1000]), &socket)
+                .await
+                .unwrap();
+            let nack = NackMessage::new(id, vec![0], 0.0, 0);
+            sender
+                .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        assert_eq!(sender.current_segment_size(), 8000);
+    }
+
+    #[tokio::test]
+    async fn test_srtt_follows_rising_completion_delay() {
+        let config = Config {
+            base_redundancy_ratio: 0.0,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let complete = |segment_id| SegmentCompleteMessage {
+            segment_id,
+            file_id: 0,
+            total_chunks_received: 1,
+            duplicates_received: 0,
+            elapsed_ms: 1,
+            connection_id: 0,
+        };
+        // 완료 보고가 10ms 뒤에 오다가 경로가 느려져 40ms 뒤에 옴
+        for delay in [10, 40, 40, 40] {
+            let id = sender.send_data(Bytes::from(vec![1u8; 100]), &socket).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            sender
+                .handle_control_message(&complete(id).to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        // 최소 RTT(슬로 스타트 RTT)와 달리 평활 RTT는 늘어난 지연을 따라감
+        let srtt = sender.srtt();
+        assert!(srtt > Duration::from_millis(20), "{:?}", srtt);
+        assert!(srtt < Duration::from_millis(100), "{:?}", srtt);
+
+        // NACK을 받은 세그먼트는 표본에서 제외 (Karn)
+        let id = sender.send_data(Bytes::from(vec![1u8; 100]), &socket).await.unwrap();
+        let nack = NackMessage::new(id, vec![0], 0.0, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        sender
+            .handle_control_message(&complete(id).to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.srtt(), srtt);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_nacks_within_srtt_charged_once() {
+        let config = Config {
+            base_redundancy_ratio: 0.0,
+            max_chunk_retransmits: 2,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        // 재전송이 도착하기 전에 같은 청크를 거듭 요청해도 한도를 소모하지 않음
+        let id = sender.send_data(Bytes::from(vec![1u8; 100]), &socket).await.unwrap();
+        let nack = NackMessage::new(id, vec![0], 0.0, 0);
+        for _ in 0..5 {
+            sender
+                .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+            sender.process_retransmits(&socket).await;
+        }
+        assert!(sender.srtt() > Duration::from_millis(10));
+        assert_eq!(sender.get_stats().abandoned_segments, 0);
+        assert_eq!(sender.segments.get(&id).unwrap().retransmits[&0].0, 1);
+    }
+
+    #[tokio::test]
+    async fn test_prepared_segment_sent_to_many_peers() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 253) as u8).collect();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let mut senders = Vec::new();
+        for peer in 0..2 {
+            let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config.clone())));
+            let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            sender.accept_client(client.local_addr().unwrap()).unwrap();
+            // 수신자마다 세그먼트 ID가 달라도 같은 준비본 사용
+            for _ in 0..peer {
+                sender.send_data(Bytes::from_static(b"x"), &socket).await.unwrap();
+            }
+            let mut buf = [0u8; 2048];
+            while let Ok(Ok(_)) =
+                tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+            {}
+            senders.push((sender, client));
+        }
+
+        let prepared = senders[0].0.prepare_segment(Bytes::from(data.clone()));
+        let mut buf = vec![0u8; 2048];
+        for (peer, (sender, client)) in senders.iter().enumerate() {
+            let segment_id = sender.send_prepared(&prepared, &socket).await.unwrap();
+            assert_eq!(segment_id, peer as u64 + 1);
+
+            let mut segment = crate::chunk::Segment::new_for_receive(segment_id, data.len(), 10);
+            for _ in 0..prepared.datagram_count() {
+                let (len, _) = client.recv_from(&mut buf).await.unwrap();
+                let chunk = Chunk::from_bytes(&buf[..len]).unwrap();
+                assert_eq!(chunk.header.segment_id, segment_id);
+                segment.insert_chunk(&chunk);
+            }
+            assert!(segment.is_complete());
+            assert_eq!(segment.into_data().as_ref(), data.as_slice());
+
+            // NACK 재전송도 이 연결의 세그먼트 ID로
+            let nack = NackMessage::new(segment_id, vec![4], 0.9, 0);
+            sender
+                .handle_control_message(&nack.to_bytes(), client.local_addr().unwrap(), &socket)
+                .await
+                .unwrap();
+            let (len, _) = client.recv_from(&mut buf).await.unwrap();
+            let chunk = Chunk::from_bytes(&buf[..len]).unwrap();
+            assert_eq!((chunk.header.segment_id, chunk.header.chunk_id), (segment_id, 4));
+            assert_eq!(sender.get_stats().total_segments, peer as u64 + 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_small_segments_coalesced_into_batches() {
+        let config = Config {
+            segment_size: 100,
+            base_redundancy_ratio: 0.0,
+            coalesce_chunks: true,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config.clone())));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        const SEGMENTS: u8 = 20;
+        let mut expected = std::collections::HashMap::new();
+        for i in 0..SEGMENTS {
+            let data = vec![i; 100];
+            let id = sender.send_data(Bytes::from(data.clone()), &socket).await.unwrap();
+            expected.insert(id, data);
+        }
+        sender.flush_coalesced(&socket).await.unwrap();
+
+        let mut buf = [0u8; 2048];
+        let mut datagrams = 0;
+        while let Ok(Ok((len, _))) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {
+            assert!(len <= config.max_datagram_size);
+            datagrams += 1;
+            let chunks = match Chunk::from_bytes(&buf[..len]) {
+                Some(chunk) => vec![chunk],
+                None => ChunkBatch::decode(&buf[..len]).unwrap(),
+            };
+            for chunk in chunks {
+                let mut segment = crate::chunk::Segment::new_for_receive(
+                    chunk.header.segment_id,
+                    chunk.header.segment_size as usize,
+                    chunk.header.total_chunks,
+                );
+                assert!(segment.insert_chunk(&chunk) && segment.is_complete());
+                let data = expected.remove(&chunk.header.segment_id).unwrap();
+                assert_eq!(segment.into_data().as_ref(), data.as_slice());
+            }
+        }
+
+        // 150바이트 청크 데이터그램이 1472바이트 묶음에 9개씩 → 20개가 3개로
+        assert!(expected.is_empty());
+        assert_eq!(datagrams, 3);
+        let stats = sender.get_stats();
+        assert_eq!(stats.total_chunks, SEGMENTS as u64);
+        assert_eq!(stats.coalesced_datagrams, 3);
+    }
+
+    #[tokio::test]
+    async fn test_silent_segment_gets_tail_probe_with_backoff() {
+        let config = Config {
+            base_redundancy_ratio: 0.0,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        // 청크 3개가 모두 손실되었다고 보고 피드백 없이 기다림
+        let segment_id = sender.send_data(Bytes::from(vec![7u8; 3000]), &socket).await.unwrap();
+        let mut buf = [0u8; 2048];
+        for _ in 0..3 {
+            client.recv_from(&mut buf).await.unwrap();
+        }
+
+        // 표본이 없으면 초기 RTT 100ms 기준 srtt + 4 × rttvar
+        let interval = sender.tail_probe_interval();
+        assert_eq!(interval, Duration::from_millis(300));
+        tokio::time::sleep(interval).await;
+        sender.process_retransmits(&socket).await;
+        let (len, _) = client.recv_from(&mut buf).await.unwrap();
+        let probe = Chunk::from_bytes(&buf[..len]).unwrap();
+        assert_eq!((probe.header.segment_id, probe.header.chunk_id), (segment_id, 2));
+
+        // 다음 탐침은 두 배 간격 뒤
+        tokio::time::sleep(interval).await;
+        sender.process_retransmits(&socket).await;
+        assert_eq!(sender.get_stats().tail_probes, 1);
+        tokio::time::sleep(interval).await;
+        sender.process_retransmits(&socket).await;
+        assert_eq!(sender.get_stats().tail_probes, 2);
+    }
+
+    #[tokio::test]
+    async fn test_preloaded_segments_served_without_chunking() {
+        let config = Config {
+            segment_size: 8192,
+            segment_cache_bytes: 1024 * 1024,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        assert_eq!(sender.preload(&data), 20_000);
+        let cache = sender.segment_cache().unwrap().clone();
+        assert_eq!(cache.len(), 3);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let mut segment_ids = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let (segment_id, len) = sender.send_segment_at(&data, offset, &socket).await.unwrap();
+            segment_ids.push(segment_id);
+            offset += len;
+        }
+        assert_eq!(segment_ids.len(), 3);
+        assert_eq!((cache.hits(), cache.misses()), (3, 0));
+
+        // 데이터 끝을 넘는 오프셋은 패닉 없이 거부
+        assert!(matches!(
+            sender.send_segment_at(&data, data.len() + 1, &socket).await,
+            Err(Error::OffsetOutOfRange { offset: 20_001, len: 20_000 })
+        ));
+
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok(_)) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {}
+
+        // 첫 NACK도 미리 준비한 청크로 재전송
+        let nack = NackMessage::new(segment_ids[0], vec![2], 0.9, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let (len, _) = client.recv_from(&mut buf).await.unwrap();
+        let chunk = Chunk::from_bytes(&buf[..len]).unwrap();
+        assert_eq!((chunk.header.segment_id, chunk.header.chunk_id), (segment_ids[0], 2));
+        let start = chunk.header.offset as usize;
+        assert_eq!(&chunk.data[..], &data[start..start + chunk.data.len()]);
+        assert_eq!(cache.misses(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_padded_datagrams_uniform_and_stripped() {
+        let config = Config {
+            chunk_size: 1000,
+            pad_datagrams: true,
+            ..Config::default()
+        };
+        let pad_len = config.padded_datagram_len();
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        // 마지막 청크가 짧은 세그먼트 (10청크 + 500바이트), 중복 청크 포함
+        let data: Vec<u8> = (0..10_500).map(|i| (i % 251) as u8).collect();
+        let segment_id = sender
+            .send_data(Bytes::from(data.clone()), &socket)
+            .await
+            .unwrap();
+        let mut segment = crate::chunk::Segment::new_for_receive(segment_id, data.len(), 11);
+        let mut buf = [0u8; 2048];
+        let mut datagrams = 0;
+        while let Ok(Ok((len, _))) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {
+            assert_eq!(len, pad_len);
+            let chunk = Chunk::from_bytes(&buf[..len]).unwrap();
+            segment.insert_chunk(&chunk);
+            datagrams += 1;
+        }
+        assert!(datagrams > 11);
+        assert!(segment.is_complete());
+        assert_eq!(segment.into_data().as_ref(), data.as_slice());
+
+        // 재전송도 같은 크기, 패딩이 변조된 청크는 거부
+        let nack = NackMessage::new(segment_id, vec![10], 0.9, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let (len, _) = client.recv_from(&mut buf).await.unwrap();
+        assert_eq!(len, pad_len);
+        assert_eq!(Chunk::from_bytes(&buf[..len]).unwrap().data.len(), 500);
+        buf[len - 1] = 1;
+        assert!(Chunk::from_bytes(&buf[..len]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_pmtu_probe_confirms_before_shrinking() {
+        let config = Config::default();
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config.clone())));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+        *sender.client_session.write() = Some(7);
+
+        let data: Vec<u8> = (0..10_000).map(|i| i as u8).collect();
+        let segment_id = sender.send_data(Bytes::from(data), &socket).await.unwrap();
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok(_)) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {}
+
+        // 누락이 줄지 않는 NACK이 이어지면 청크를 줄이지 않고 지금 크기의 탐침 하나로 확인
+        let nack = NackMessage::new(segment_id, vec![0, 1], 0.1, 0);
+        for _ in 0..=config.pmtu_blackhole_nacks {
+            sender
+                .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        let (len, _) = client.recv_from(&mut buf).await.unwrap();
+        let challenge = PathChallengeMessage::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(len, Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN + sender.chunk_size());
+        assert!(sender.pmtu_searching(segment_id));
+
+        // 그 크기가 통과하면 블랙홀이 아닌 손실: 청크 크기 그대로 평소 재전송
+        let response = PathResponseMessage::answer(&challenge);
+        sender
+            .handle_control_message(&response.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert!(!sender.pmtu_searching(segment_id));
+        assert_eq!(sender.chunk_size(), config.effective_chunk_size());
+        assert_eq!(sender.get_stats().pmtu_probes, 1);
+        assert!(sender.client_addr() == Some(client_addr));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_padding_sealed_inside_aead() {
+        use crate::crypto::{CryptoSession, EphemeralKeyPair};
+
+        let config = Config {
+            chunk_size: 1000,
+            pad_datagrams: true,
+            encryption_enabled: true,
+            ..Config::default()
+        };
+        // 송신자는 암호문 뒤에 인증되지 않는 패딩을 붙이지 않음
+        assert_eq!(config.padded_datagram_len(), 0);
+        let block = config.effective_chunk_size();
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        let (server_keys, client_keys) = (EphemeralKeyPair::generate(), EphemeralKeyPair::generate());
+        let (server_public, client_public) =
+            (server_keys.public_key_bytes(), client_keys.public_key_bytes());
+        let mut server = CryptoSession::establish(server_keys, client_public);
+        let receiver = CryptoSession::establish(client_keys, server_public);
+
+        // 평문 안에서 채웠으므로 마지막 청크까지 모든 데이터그램이 같은 크기
+        let data: Vec<u8> = (0..10_500).map(|i| (i % 251) as u8).collect();
+        let sealed = server.encrypt_padded(1, &data, block).unwrap();
+        assert_eq!(sealed.len() % block, 0);
+        let segment_id = sender
+            .send_data(Bytes::from(sealed.clone()), &socket)
+            .await
+            .unwrap();
+        let chunks = (sealed.len() / block) as u32;
+        let mut segment = crate::chunk::Segment::new_for_receive(segment_id, sealed.len(), chunks);
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok((len, _))) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {
+            assert_eq!(len, Chunk::PREFIX_LEN + crate::chunk::ChunkHeader::ENCODED_LEN + block);
+            segment.insert_chunk(&Chunk::from_bytes(&buf[..len]).unwrap());
+        }
+        assert!(segment.is_complete());
+        let received = segment.into_data();
+        assert_eq!(receiver.decrypt_padded(&received).unwrap(), data);
+
+        // 패딩을 바꾸면 인증 실패
+        let mut tampered = received.to_vec();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(receiver.decrypt_padded(&tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chunks_sized_per_path_mtu() {
+        use crate::chunk::{ChunkHeader, Segment};
+        use std::collections::HashSet;
+
+        let config = Config::default();
+        // 큰 경로는 chunk_size(1200)가 그대로 들어가고, 작은 경로는 900바이트 데이터그램
+        let paths = Arc::new(PathManager::new(config.clone()));
+        let remote = free_local_addr();
+        let mut nic_addrs = Vec::new();
+        for _ in 0..2 {
+            let nic = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+            nic_addrs.push(nic.local_addr().unwrap());
+            paths.add_nic_socket(nic, remote).unwrap();
+        }
+        let mtus = [1500, 900];
+        assert!(paths.set_path_mtu(0, Some(mtus[0])));
+        assert!(paths.set_path_mtu(1, Some(mtus[1])));
+        assert!(!paths.set_path_mtu(7, Some(600)));
+
+        let sender = Sender::new(config.clone(), paths);
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        // 가중 라운드로빈은 100청크 주기로 경로를 나눔: 첫 세그먼트(50청크)는 큰 경로만,
+        // 두 번째 세그먼트는 작은 경로로 시작해 큰 경로로 돌아옴
+        let data: Vec<u8> = (0..60_000).map(|i| (i % 251) as u8).collect();
+        let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        let mut buf = [0u8; 2048];
+        for round in 0..2 {
+            let segment_id = sender
+                .send_data(Bytes::from(data.clone()), &socket)
+                .await
+                .unwrap();
+
+            let mut segment = None;
+            let mut sizes = [HashSet::new(), HashSet::new()];
+            while let Ok(Ok((len, from))) =
+                tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+            {
+                let chunk = Chunk::from_bytes(&buf[..len]).unwrap();
+                let nic = chunk.header.nic_id as usize;
+                // 청크 크기를 맞춘 경로의 NIC 소켓으로 나감
+                assert_eq!(from, nic_addrs[nic]);
+                assert!(len <= mtus[nic], "NIC {} 경로에 {} bytes 데이터그램", nic, len);
+                sizes[nic].insert(chunk.data.len());
+                let total_chunks = chunk.header.total_chunks;
+                segment
+                    .get_or_insert_with(|| {
+                        Segment::new_for_receive(segment_id, data.len(), total_chunks)
+                    })
+                    .insert_chunk(&chunk);
+            }
+
+            assert!(sizes[0].contains(&config.chunk_size), "{:?}", sizes);
+            if round == 1 {
+                assert!(sizes[1].contains(&(mtus[1] - framing)), "{:?}", sizes);
+            }
+            // 크기가 섞인 청크로도 원본 그대로 조립
+            let segment = segment.unwrap();
+            assert!(segment.is_complete());
+            assert_eq!(segment.into_data().as_ref(), data.as_slice());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_rtt_limited_to_initial_window() {
+        let config = Config {
+            chunk_size: 1000,
+            initial_window_chunks: 10,
+            slow_start_initial_rtt_ms: 200,
+            ..Config::default()
+        };
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let socket = Arc::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        // 100 청크 세그먼트를 보내도 첫 RTT에는 초기 윈도우만 나감
+        let sender_task = sender.clone();
+        let socket_task = socket.clone();
+        let send = tokio::spawn(async move {
+            sender_task
+                .send_data(Bytes::from(vec![5u8; 100_000]), &*socket_task)
+                .await
+        });
+
+        let mut buf = [0u8; 2048];
+        let mut first_rtt = 0u32;
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(150);
+        while let Ok(Ok(_)) = tokio::time::timeout_at(deadline, client.recv_from(&mut buf)).await {
+            first_rtt += 1;
+        }
+        assert!(first_rtt > 0);
+        assert!(first_rtt <= config.initial_window_chunks, "first RTT sent {}", first_rtt);
+        assert!(sender.slow_start().is_active());
+
+        // 손실 신호 → 슬로 스타트 종료, 나머지는 제한 없이 전송
+        sender.slow_start.write().on_loss();
+        send.await.unwrap().unwrap();
+        assert!(!sender.slow_start().is_active());
+    }
+
+    #[tokio::test]
+    async fn test_custom_redundancy_policy_drives_sender() {
+        // 손실 10% 이상이면 50%, 아니면 중복 없음 (계단형)
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        }
+        .with_redundancy_policy(|loss: f64, rtt_us: u64, recent: f64| {
+            assert!(rtt_us > 0);
+            if loss.max(recent) >= 0.1 {
+                0.5
+            } else {
+                0.0
+            }
+        });
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        sender.update_redundancy();
+        assert_eq!(sender.current_redundancy_ratio(), 0.0);
+        let segment_id = sender
+            .send_data(Bytes::from(vec![7u8; 10_000]), &socket)
+            .await
+            .unwrap();
+        assert!(sender.segments.get(&segment_id).unwrap().redundant_chunks.is_empty());
+
+        sender.stats.write().record_segment_report(100, 80, 0);
+        sender.update_redundancy();
+        assert_eq!(sender.current_redundancy_ratio(), 0.5);
+        let segment_id = sender
+            .send_data(Bytes::from(vec![7u8; 10_000]), &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.segments.get(&segment_id).unwrap().redundant_chunks.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_high_rtt_raises_redundancy_floor() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut redundant = Vec::new();
+        for rtt_ms in [2, 400] {
+            let config = Config {
+                chunk_size: 1000,
+                slow_start_initial_rtt_ms: rtt_ms,
+                ..Config::default()
+            };
+            let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+            sender.accept_client(client.local_addr().unwrap()).unwrap();
+            // 둘 다 손실 1%
+            sender.stats.write().record_segment_report(100, 99, 0);
+            sender.update_redundancy();
+            let segment_id = sender
+                .send_data(Bytes::from(vec![7u8; 20_000]), &socket)
+                .await
+                .unwrap();
+            let redundant_chunks = sender.segments.get(&segment_id).unwrap().redundant_chunks.len();
+            redundant.push((sender.current_redundancy_ratio(), redundant_chunks));
+        }
+
+        // 가까운 경로는 손실률 선형 조정 그대로 (0.15 + 0.01 × 2)
+        let (near, near_chunks) = redundant[0];
+        assert!((near - 0.17).abs() < 1e-9, "{}", near);
+        // 먼 경로는 손실이 같아도 RTT 하한 0.05 + 0.05 × 4 = 0.25
+        let (far, far_chunks) = redundant[1];
+        assert!((far - 0.25).abs() < 1e-9, "{}", far);
+        assert!(far_chunks > near_chunks, "{:?}", redundant);
+
+        // 하한도 max_redundancy_ratio를 넘지 않음
+        let config = Config::default();
+        assert_eq!(config.redundancy(0.0, 10_000_000, 0.0), config.max_redundancy_ratio);
+    }
+
+    #[tokio::test]
+    async fn test_network_change_restarts_estimators() {
+        let config = Config {
+            chunk_size: 1000,
+            initial_window_chunks: 10,
+            slow_start_initial_rtt_ms: 20,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        let nic = path_manager
+            .add_nic("127.0.0.1:0".parse().unwrap(), client_addr)
+            .await
+            .unwrap();
+        let sender = Sender::new(config.clone(), path_manager.clone());
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        // 이전 경로(Wi-Fi)에서 수렴: 손실 40%로 중복률이 오르고 손실로 슬로 스타트 종료
+        for _ in 0..10 {
+            sender.stats.write().record_segment_report(100, 60, 0);
+        }
+        path_manager.record_chunk_arrival(nic, 1000);
+        path_manager.record_loss(nic, 4);
+        sender.slow_start.write().on_loss();
+        sender.update_redundancy();
+        let converged = sender.current_redundancy_ratio();
+        assert!(converged > config.base_redundancy_ratio, "{}", converged);
+        assert!(!sender.slow_start().is_active());
 
-                socket.send_to(&ack.to_bytes(), addr).await?;
-                info!("클라이언트 연결: {}", addr);
-            }
+        // 셀룰러로 전환: 수신자가 새 RTT 추정치와 함께 통보
+        let change = NetworkChangeMessage::new(7).with_rtt_us(300_000);
+        sender
+            .handle_control_message(&change.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let slow_start = sender.slow_start();
+        assert!(slow_start.is_active());
+        assert_eq!(slow_start.window(), config.initial_window_chunks);
+        assert_eq!(slow_start.rtt(), Duration::from_millis(300));
+        assert_eq!(sender.current_redundancy_ratio(), config.base_redundancy_ratio);
+        assert_eq!(sender.stats.read().recent_loss_rate(), 0.0);
+        assert_eq!(path_manager.max_loss_rate(), 0.0);
+        assert_eq!(sender.get_stats().network_changes, 1);
 
-            MessageType::Nack => {
-                // NACK 처리
-                if let Some(nack) = NackMessage::from_bytes(data) {
-                    self.handle_nack(nack, socket, addr).await?;
-                }
-            }
+        // 새 경로의 보고로 다시 수렴: 손실은 없지만 RTT가 길어 중복률 하한이 오름
+        for _ in 0..10 {
+            sender.stats.write().record_segment_report(100, 100, 0);
+        }
+        path_manager.record_chunk_arrival(nic, 1000);
+        sender.update_redundancy();
+        let expected = config.redundancy(0.0, 300_000, 0.0);
+        assert_eq!(sender.current_redundancy_ratio(), expected);
+        assert!(expected < converged);
 
-            MessageType::SegmentComplete => {
-                // 세그먼트 완료
-                // payload에서 segment_id 추출 (간단 구현)
-                if data.len() > 16 {
-                    if let Ok(segment_id) = bincode::deserialize::<u64>(&data[16..24]) {
-                        self.segments.remove(&segment_id);
-                        let mut stats = self.stats.write();
-                        stats.completed_segments += 1;
-                        debug!("세그먼트 {} 완료 확인", segment_id);
+        // 다른 주소에서 온 통보는 무시
+        let stranger = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender
+            .handle_control_message(&change.to_bytes(), stranger.local_addr().unwrap(), &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.get_stats().network_changes, 1);
+    }
+
+    #[tokio::test]
+    async fn test_zero_redundancy_skips_redundant_path() {
+        let config = Config {
+            chunk_size: 1000,
+            base_redundancy_ratio: 0.0,
+            fec_loss_threshold: 0.1,
+            fec_switch_hold_ms: 0,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+        // 손실이 보고되어도 min_redundancy_ratio로 끌어올리거나 FEC로 전환하지 않음
+        sender.stats.write().record_segment_report(100, 50, 0);
+        sender.update_redundancy();
+        sender.update_redundancy_mode(&socket).await;
+        assert_eq!(sender.current_redundancy_ratio(), 0.0);
+        assert_eq!(sender.redundancy_mode(), RedundancyMode::Duplicate);
+
+        for _ in 0..4 {
+            let segment_id = sender
+                .send_data(Bytes::from(vec![7u8; 10_000]), &socket)
+                .await
+                .unwrap();
+            let state = sender.segments.get(&segment_id).unwrap();
+            assert_eq!(state.chunks.len(), 10);
+            // 중복 청크 목록은 할당조차 하지 않음
+            assert_eq!(state.redundant_chunks.capacity(), 0);
+        }
+        assert_eq!(sender.get_stats().redundant_chunks, 0);
+
+        let prepared = sender.prepare_segment(Bytes::from(vec![7u8; 10_000]));
+        assert!(prepared.redundant_chunks().is_empty());
+        assert_eq!(prepared.datagram_count(), 10);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_concurrent_sessions_share_egress_limit() {
+        use crate::sim::{SimConfig, SimNetwork};
+
+        const LIMIT: u64 = 4_000_000;
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let limiter = EgressLimiter::new(LIMIT);
+        let duration = Duration::from_millis(800);
+        // 멈춘 tokio 시계 위의 가상 네트워크: 상한 페이싱만 시간을 흘려 보냄
+        let net = SimNetwork::new(SimConfig::default());
+
+        let mut tasks = Vec::new();
+        for i in 0..2u8 {
+            let sender = Arc::new(Sender::with_egress_limiter(
+                config.clone(),
+                Arc::new(PathManager::new(config.clone())),
+                &limiter,
+            ));
+            let socket = net.bind(SocketAddr::from(([10, 0, 0, 1], 9000 + i as u16)));
+            let client = net.bind(SocketAddr::from(([10, 0, 1, i], 5000)));
+            sender.accept_client(client.local_addr().unwrap()).unwrap();
+
+            let end = tokio::time::Instant::now() + duration;
+            let send_task = tokio::spawn({
+                let sender = sender.clone();
+                async move {
+                    while tokio::time::Instant::now() < end {
+                        sender
+                            .send_data(Bytes::from(vec![3u8; 16 * 1024]), &socket)
+                            .await
+                            .unwrap();
                     }
                 }
-            }
+            });
+            let recv_task = tokio::spawn(async move {
+                let mut buf = vec![0u8; 2048];
+                let mut bytes = 0usize;
+                while let Ok(Ok((len, _))) =
+                    tokio::time::timeout_at(end, client.recv_from(&mut buf)).await
+                {
+                    bytes += len;
+                }
+                bytes
+            });
+            tasks.push((sender, send_task, recv_task));
+        }
 
-            MessageType::Heartbeat => {
-                // Heartbeat 응답
-                let response = crate::message::HeartbeatMessage::new(0);
-                socket.send_to(&response.to_bytes(), addr).await?;
-            }
+        let mut received = Vec::new();
+        for (sender, send_task, recv_task) in tasks {
+            received.push(recv_task.await.unwrap());
+            send_task.await.unwrap();
+            assert!(sender.egress_rate().is_some());
+        }
 
-            MessageType::Close => {
-                *self.client_addr.write() = None;
-                info!("클라이언트 연결 종료: {}", addr);
-            }
+        // 합계는 상한 이내, 두 세션이 반씩 사용 (가상 시계라 부하와 무관)
+        let total = received.iter().sum::<usize>() as f64;
+        let cap = LIMIT as f64 * duration.as_secs_f64();
+        assert!(total <= cap * 1.02, "{:?}", received);
+        assert!(total >= cap * 0.95, "{:?}", received);
+        for &bytes in &received {
+            let share = bytes as f64 / total;
+            assert!((0.48..0.52).contains(&share), "{:?}", received);
+        }
+    }
 
-            _ => {}
+    #[tokio::test]
+    async fn test_bytes_in_flight_returns_to_zero() {
+        let config = Config {
+            chunk_size: 1000,
+            segment_timeout_ms: 50,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let mut ids = Vec::new();
+        let mut expected = 0u64;
+        for _ in 0..3 {
+            let segment_id = sender
+                .send_data(Bytes::from(vec![3u8; 10_000]), &socket)
+                .await
+                .unwrap();
+            let state = sender.segments.get(&segment_id).unwrap();
+            expected += state
+                .chunks
+                .iter()
+                .chain(&state.redundant_chunks)
+                .map(|c| c.to_bytes().len() as u64)
+                .sum::<u64>();
+            ids.push(segment_id);
         }
+        assert_eq!(sender.bytes_in_flight(), expected);
 
-        Ok(())
+        // 재전송한 바이트도 미확인분에 포함
+        let nack = NackMessage::new(ids[0], vec![1, 2], 0.8, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert!(sender.bytes_in_flight() > expected);
+
+        // 완료 보고는 그 세그먼트로 보낸 바이트 전부를 차감
+        for &segment_id in &ids[..2] {
+            let complete = SegmentCompleteMessage {
+                segment_id,
+                file_id: 0,
+                total_chunks_received: 10,
+                duplicates_received: 0,
+                elapsed_ms: 1,
+                connection_id: 0,
+            };
+            sender
+                .handle_control_message(&complete.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        let remaining = sender.bytes_in_flight();
+        assert!(remaining > 0 && remaining < expected / 2, "{}", remaining);
+        assert_eq!(sender.get_stats().bytes_in_flight, remaining);
+
+        // 보고 없이 타임아웃된 세그먼트는 손실로 보고 정리
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        sender.process_retransmits(&socket).await;
+        assert_eq!(sender.bytes_in_flight(), 0);
+        assert_eq!(sender.get_stats().bytes_in_flight, 0);
     }
 
-    /// NACK 처리
-    async fn handle_nack(
-        &self,
-        nack: NackMessage,
-        socket: &UdpSocket,
-        addr: SocketAddr,
-    ) -> Result<()> {
-        debug!(
-            "NACK 수신: segment={}, missing={} chunks",
-            nack.segment_id,
-            nack.missing_chunk_ids.len()
-        );
+    #[tokio::test]
+    async fn test_segment_complete_counts_recorded() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let segment_id = sender
+            .send_data(Bytes::from(vec![1u8; 10_000]), &socket)
+            .await
+            .unwrap();
+        let sent = {
+            let state = sender.segments.get(&segment_id).unwrap();
+            state.chunks.len() + state.redundant_chunks.len()
+        } as u32;
+
+        // 청크 2개 재전송 → 송신 수 증가
+        let nack = NackMessage::new(segment_id, vec![1, 2], 0.8, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+
+        let complete = SegmentCompleteMessage {
+            segment_id,
+            file_id: 0,
+            total_chunks_received: sent - 1,
+            duplicates_received: 1,
+            elapsed_ms: 3,
+            connection_id: 0,
+        };
+        sender
+            .handle_control_message(&complete.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+
+        let stats = sender.get_stats();
+        assert_eq!(stats.reported_sent_chunks, (sent + 2) as u64);
+        assert_eq!(stats.reported_received_chunks, (sent - 1) as u64);
+        assert_eq!(stats.reported_duplicate_chunks, 1);
+        let expected_loss = 3.0 / (sent + 2) as f64;
+        assert!((stats.reported_loss_rate() - expected_loss).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_near_complete_segments_retransmitted_first() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let mut ids = Vec::new();
+        for _ in 0..3 {
+            ids.push(
+                sender
+                    .send_data(Bytes::from(vec![5u8; 10_000]), &socket)
+                    .await
+                    .unwrap(),
+            );
+        }
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok(_)) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {}
+
+        // 막 시작한 세그먼트 두 개는 대기, 거의 완료된 세그먼트는 즉시
+        for (segment_id, ratio) in [(ids[0], 0.1), (ids[1], 0.3), (ids[2], 0.9)] {
+            let nack = NackMessage::new(segment_id, vec![2], ratio, 0);
+            sender
+                .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+                .await
+                .unwrap();
+        }
+        assert_eq!(sender.get_stats().retransmitted_chunks, 1);
+
+        sender.process_retransmits(&socket).await;
+        assert_eq!(sender.get_stats().retransmitted_chunks, 3);
+
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            let (len, _) = client.recv_from(&mut buf).await.unwrap();
+            order.push(Chunk::from_bytes(&buf[..len]).unwrap().header.segment_id);
+        }
+        assert_eq!(order, vec![ids[2], ids[1], ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn test_nack_cancel_drops_queued_retransmit() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
+
+        let segment_id = sender
+            .send_data(Bytes::from(vec![5u8; 10_000]), &socket)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok(_)) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {}
+
+        // 대기열에 들어간 청크 2, 4 중 2는 수신자가 철회
+        let nack = NackMessage::new(segment_id, vec![2, 4], 0.1, 0);
+        let cancel = NackCancelMessage::new(segment_id, 0, vec![2]);
+        for msg in [nack.to_bytes(), cancel.to_bytes()] {
+            sender
+                .handle_control_message(&msg, client_addr, &socket)
+                .await
+                .unwrap();
+        }
+
+        sender.process_retransmits(&socket).await;
+        assert_eq!(sender.get_stats().retransmitted_chunks, 1);
+        let (len, _) = client.recv_from(&mut buf).await.unwrap();
+        assert_eq!(Chunk::from_bytes(&buf[..len]).unwrap().header.chunk_id, 4);
+    }
+
+    #[tokio::test]
+    async fn test_whole_segment_nack_resends_every_chunk() {
+        let config = Config {
+            chunk_size: 1000,
+            base_redundancy_ratio: 0.0,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        sender.accept_client(client_addr).unwrap();
 
-        // 통계 업데이트
+        let segment_id = sender
+            .send_data(Bytes::from(vec![5u8; 5_000]), &socket)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 2048];
+        while let Ok(Ok(_)) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
+        {}
+
+        // 청크를 하나도 받지 못한 수신자의 전체 요청: 수신률 0이어도 대기열 없이 바로 모두
+        let nack = NackMessage::whole_segment(segment_id);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let mut resent = Vec::new();
+        while let Ok(Ok((len, _))) =
+            tokio::time::timeout(Duration::from_millis(20), client.recv_from(&mut buf)).await
         {
-            let mut stats = self.stats.write();
-            stats.total_nacks += 1;
-            stats.last_nack_time = Some(Instant::now());
+            resent.push(Chunk::from_bytes(&buf[..len]).unwrap().header.chunk_id);
         }
+        resent.sort_unstable();
+        assert_eq!(resent, vec![0, 1, 2, 3, 4]);
+    }
 
-        // 손실 기록
-        self.path_manager
-            .record_loss(nack.nic_id, nack.missing_chunk_ids.len() as u64);
+    #[tokio::test]
+    async fn test_nack_delta_applied_to_tracked_set() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        sender.accept_client(client.local_addr().unwrap()).unwrap();
+        let segment_id = sender
+            .send_data(Bytes::from(vec![5u8; 10_000]), &socket)
+            .await
+            .unwrap();
+        let missing = |delta| sender.apply_nack_delta(delta).missing_chunk_ids;
 
-        // 재전송 큐에 추가
-        if let Some(mut state) = self.segments.get_mut(&nack.segment_id) {
-            for chunk_id in &nack.missing_chunk_ids {
-                if !state.retransmit_queue.contains(chunk_id) {
-                    state.retransmit_queue.push(*chunk_id);
-                }
+        let full = NackDeltaMessage::full(segment_id, 1, vec![1, 2, 3], 0.7);
+        assert_eq!(missing(full), vec![1, 2, 3]);
+        let delta = NackDeltaMessage::delta(segment_id, 2, 1, vec![5], vec![2], 0.8);
+        assert_eq!(missing(delta), vec![1, 3, 5]);
+
+        // 순번 3이 유실된 뒤의 델타는 새로 누락된 청크만, 다음 전체 보고로 복구
+        let stale = NackDeltaMessage::delta(segment_id, 4, 3, vec![7], vec![1], 0.8);
+        assert_eq!(missing(stale), vec![7]);
+        assert!(sender.segments.get(&segment_id).unwrap().nack_delta.is_none());
+        let full = NackDeltaMessage::full(segment_id, 5, vec![3, 7], 0.9);
+        assert_eq!(missing(full), vec![3, 7]);
+    }
+
+    /// 이벤트마다 상위 스팬 경로(`이름{필드}:...`)를 기록하는 레이어
+    #[derive(Clone, Default)]
+    struct ScopeCapture(Arc<parking_lot::Mutex<Vec<String>>>);
+
+    #[derive(Default)]
+    struct SpanFields(Vec<String>);
+
+    impl tracing::field::Visit for SpanFields {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for ScopeCapture
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = SpanFields::default();
+            attrs.record(&mut fields);
+            ctx.span(id).unwrap().extensions_mut().insert(fields);
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            if let Some(fields) = ctx.span(id).unwrap().extensions_mut().get_mut::<SpanFields>() {
+                values.record(fields);
             }
         }
 
-        // 즉시 재전송
-        self.retransmit_chunks(nack.segment_id, &nack.missing_chunk_ids, socket, addr)
-            .await?;
+        fn on_event(&self, event: &tracing::Event<'_>, ctx: tracing_subscriber::layer::Context<'_, S>) {
+            let scope = ctx
+                .event_scope(event)
+                .map(|scope| {
+                    scope
+                        .from_root()
+                        .map(|span| {
+                            let fields = span
+                                .extensions()
+                                .get::<SpanFields>()
+                                .map(|f| f.0.join(","))
+                                .unwrap_or_default();
+                            format!("{}{{{}}}", span.name(), fields)
+                        })
+                        .collect::<Vec<_>>()
+                        .join(":")
+                })
+                .unwrap_or_default();
+            self.0.lock().push(scope);
+        }
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn test_events_carry_connection_and_segment_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = ScopeCapture::default();
+        let subscriber = tracing_subscriber::registry()
+            .with(capture.clone())
+            .with(tracing_subscriber::filter::LevelFilter::DEBUG);
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let config = Config::default();
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr: SocketAddr = "127.0.0.1:40404".parse().unwrap();
+        *sender.client_session.write() = Some(77);
+        sender.accept_client(client_addr).unwrap();
+
+        // 호출자가 스팬 밖에서 보내도 연결 스팬 아래 세그먼트 스팬으로 기록
+        let segment_id = sender
+            .send_data(Bytes::from(vec![3u8; 4096]), &socket)
+            .await
+            .unwrap();
+        let nack = NackMessage::new(segment_id, vec![0], 0.9, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .instrument(sender.span_for(client_addr))
+            .await
+            .unwrap();
+
+        let connection = "connection{peer=127.0.0.1:40404,session=77}";
+        let segment = format!("{}:segment{{segment_id={}", connection, segment_id);
+        let scopes = capture.0.lock().clone();
+        assert_eq!(
+            scopes.iter().filter(|scope| scope.starts_with(&segment)).count(),
+            2,
+            "{:?}",
+            scopes
+        );
+        assert!(scopes
+            .iter()
+            .any(|scope| scope == &format!("{},nic_id=0}}", segment)));
+
+        // 미확인 출발지는 주소만 담은 별도 스팬
+        let stranger: SocketAddr = "127.0.0.1:50505".parse().unwrap();
+        let span = sender.span_for(stranger);
+        span.in_scope(|| debug!("probe"));
+        assert_eq!(
+            capture.0.lock().last().unwrap(),
+            "connection{peer=127.0.0.1:50505}"
+        );
     }
 
-    /// 청크 재전송
-    async fn retransmit_chunks(
-        &self,
-        segment_id: SegmentId,
-        chunk_ids: &[ChunkId],
-        socket: &UdpSocket,
-        addr: SocketAddr,
-    ) -> Result<()> {
-        if let Some(state) = self.segments.get(&segment_id) {
-            for &chunk_id in chunk_ids {
-                if let Some(chunk) = state.chunks.get(chunk_id as usize) {
-                    let data = chunk.to_bytes();
-                    socket.send_to(&data, addr).await?;
+    #[tokio::test]
+    async fn test_out_of_state_messages_ignored() {
+        let config = Config {
+            chunk_size: 1000,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config)));
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        assert_eq!(sender.connection_state(), ConnectionState::Idle);
 
-                    {
-                        let mut stats = self.stats.write();
-                        stats.retransmitted_chunks += 1;
-                        stats.total_chunks += 1;
-                    }
-                }
+        // 핸드셰이크 전 NACK은 무시
+        let nack = NackMessage::new(1, vec![0], 0.5, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.get_stats().total_nacks, 0);
+
+        sender.accept_client(client_addr).unwrap();
+        let segment_id = sender
+            .send_data(Bytes::from(vec![1u8; 4000]), &socket)
+            .await
+            .unwrap();
+
+        // 드레인 중에는 새 데이터 거부, NACK 복구는 계속
+        sender.drain().unwrap();
+        assert!(matches!(
+            sender.send_data(Bytes::from(vec![1u8; 10]), &socket).await,
+            Err(Error::ConnectionClosed)
+        ));
+        let nack = NackMessage::new(segment_id, vec![1], 0.5, 0);
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.get_stats().retransmitted_chunks, 1);
+
+        // 종료 후 NACK/SegmentComplete는 무시
+        let close = MessageHeader::new(MessageType::Close, 0).to_bytes();
+        sender
+            .handle_control_message(&close, client_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.connection_state(), ConnectionState::Closed);
+
+        sender
+            .handle_control_message(&nack.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let complete = SegmentCompleteMessage {
+            segment_id,
+            file_id: 0,
+            total_chunks_received: 4,
+            duplicates_received: 0,
+            elapsed_ms: 1,
+            connection_id: 0,
+        };
+        sender
+            .handle_control_message(&complete.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        let stats = sender.get_stats();
+        assert_eq!(stats.total_nacks, 1);
+        assert_eq!(stats.completed_segments, 0);
+    }
+
+    /// 조건이 참이 될 때까지 대기
+    async fn wait_until(timeout: Duration, mut cond: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if cond() {
+                return true;
             }
+            tokio::time::sleep(Duration::from_millis(10)).await;
         }
+        cond()
+    }
 
-        Ok(())
+    #[tokio::test]
+    async fn test_sustained_loss_switches_to_fec() {
+        let server_addr = free_local_addr();
+        let config = Config {
+            fec_loss_threshold: 0.2,
+            fec_switch_hold_ms: 50,
+            ..Config::default()
+        };
+        let path_manager = Arc::new(PathManager::new(config.clone()));
+        let sender = Arc::new(Sender::new(config.clone(), path_manager));
+
+        let sender_task = sender.clone();
+        let handle = tokio::spawn(async move { sender_task.start(server_addr).await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let (receiver, mut segment_rx) = crate::Receiver::start(
+            config.clone(),
+            "127.0.0.1:0".parse().unwrap(),
+            server_addr,
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+        assert!(wait_until(Duration::from_secs(1), || sender.client_addr().is_some()).await);
+
+        // 완료 보고된 손실이 지속되면 FEC로 전환하고 수신자도 따라감
+        sender.stats.write().record_segment_report(100, 50, 0);
+        assert!(
+            wait_until(Duration::from_secs(1), || {
+                receiver.redundancy_mode() == RedundancyMode::Fec
+            })
+            .await
+        );
+        assert_eq!(sender.redundancy_mode(), RedundancyMode::Fec);
+
+        // FEC 모드에서도 세그먼트가 정상 조립됨
+        let data = Bytes::from((0..20_000u32).map(|i| i as u8).collect::<Vec<u8>>());
+        let data_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let segment_id = sender.send_data(data.clone(), &data_socket).await.unwrap();
+        let (received_id, received) =
+            tokio::time::timeout(Duration::from_secs(2), segment_rx.recv())
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(received_id, segment_id);
+        assert_eq!(received, data);
+
+        // 손실이 임계값 아래로 유지되면 중복 전송으로 복귀
+        for _ in 0..10 {
+            sender.stats.write().record_segment_report(100, 100, 0);
+        }
+        assert!(
+            wait_until(Duration::from_secs(1), || {
+                receiver.redundancy_mode() == RedundancyMode::Duplicate
+            })
+            .await
+        );
+        assert_eq!(sender.redundancy_mode(), RedundancyMode::Duplicate);
+
+        receiver.stop().await;
+        sender.stop();
+        let _ = handle.await;
     }
 
-    /// 주기적 재전송 처리
-    async fn process_retransmits(&self, socket: &UdpSocket) {
-        let client_addr = match *self.client_addr.read() {
-            Some(addr) => addr,
-            None => return,
+    #[tokio::test]
+    async fn test_session_migrates_to_validated_address() {
+        use crate::crypto::{CryptoSession, EphemeralKeyPair};
+        use crate::receiver::FileReceiver;
+        use crate::sim::{SimConfig, SimNetwork};
+
+        // 8 Mbit/s 링크에서 200KB 세그먼트 하나가 약 200ms 동안 흐름
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(5),
+            bandwidth_bps: Some(8_000_000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let migrated_addr: SocketAddr = "10.0.0.3:6000".parse().unwrap();
+        // 슬로 스타트로 RTT당 10 청크씩 흘려 전송 도중에 주소가 바뀌게 함.
+        // 서버 소식이 끊기면 보내는 keepalive가 새 주소를 알림
+        let config = Config {
+            segment_size: 200_000,
+            initial_window_chunks: 10,
+            nat_keepalive_interval_ms: 100,
+            ..Config::default()
         };
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind(client_addr));
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
 
-        for mut entry in self.segments.iter_mut() {
-            let _segment_id = *entry.key();
-            let state = entry.value_mut();
+        let (receiver, segment_rx) = crate::Receiver::start_with_socket(
+            config.clone(),
+            client_socket.clone(),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+        assert!(wait_until(Duration::from_secs(2), || sender.client_addr().is_some()).await);
+        let (server_keys, client_keys) = (EphemeralKeyPair::generate(), EphemeralKeyPair::generate());
+        let (server_public, client_public) =
+            (server_keys.public_key_bytes(), client_keys.public_key_bytes());
+        sender.set_metadata_auth(CryptoSession::establish(server_keys, client_public).metadata_auth());
+        file_receiver
+            .receiver()
+            .set_metadata_auth(CryptoSession::establish(client_keys, server_public).metadata_auth());
 
-            if !state.retransmit_queue.is_empty() {
-                let chunks_to_retransmit: Vec<ChunkId> = state.retransmit_queue.drain(..).collect();
+        // 연결 ID를 엿본 제3자는 키가 없어 이전을 시작하거나 확인 응답을 만들지 못함
+        let connection_id = sender.client_session.read().unwrap();
+        let attacker = net.bind("10.0.0.66:5000".parse().unwrap());
+        let nack = NackMessage::new(1, vec![0], 0.0, 0).with_connection_id(connection_id);
+        attacker.send_to(&nack.to_bytes(), server_addr).await.unwrap();
+        let heartbeat = HeartbeatMessage::new(u64::MAX).with_connection_id(connection_id);
+        attacker.send_to(&heartbeat.to_bytes(), server_addr).await.unwrap();
+        let forged = PathResponseMessage {
+            connection_id,
+            token: 0,
+            tag: [0; 16],
+        };
+        attacker.send_to(&forged.to_bytes(), server_addr).await.unwrap();
+        let mut buf = [0u8; 64];
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), attacker.recv_from(&mut buf))
+                .await
+                .is_err(),
+            "인증되지 않은 계기로 확인 토큰을 보냄"
+        );
+        assert_eq!(sender.client_addr(), Some(client_addr));
 
-                for chunk_id in chunks_to_retransmit {
-                    if let Some(chunk) = state.chunks.get(chunk_id as usize) {
-                        let data = chunk.to_bytes();
-                        if let Err(e) = socket.send_to(&data, client_addr).await {
-                            warn!("재전송 실패: {}", e);
-                        }
-                    }
-                }
+        let payload = Bytes::from((0..200_000u32).map(|i| i as u8).collect::<Vec<u8>>());
+        let send_sender = sender.clone();
+        let send_socket = server_socket.clone();
+        let send_payload = payload.clone();
+        let send = tokio::spawn(async move {
+            send_sender.send_data(send_payload, &*send_socket).await.unwrap();
+        });
+
+        // 일부 청크가 도착한 뒤 클라이언트 주소가 바뀜 (NAT 재바인딩)
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while file_receiver.stats().await.total_chunks < 20 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        client_socket.rebind(migrated_addr);
+
+        let data = tokio::time::timeout(Duration::from_secs(10), file_receiver.receive_file(1))
+            .await
+            .expect("이전 후 전송 시간 초과")
+            .unwrap();
+        assert_eq!(data, payload);
+        assert_eq!(sender.client_addr(), Some(migrated_addr));
+        assert_eq!(sender.get_stats().migrations, 1);
+        assert_eq!(sender.get_stats().network_changes, 1);
+
+        send.await.unwrap();
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_connected_socket_released_for_migration() {
+        use crate::crypto::MetadataAuth;
+        use crate::transport::PeerUdpSocket;
+
+        let config = Config {
+            connect_socket: true,
+            ..Config::default()
+        };
+        let sender = Sender::new(config.clone(), Arc::new(PathManager::new(config.clone())));
+        let socket = PeerUdpSocket::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let server_addr = socket.local_addr().unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let migrated = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let (client_addr, migrated_addr) =
+            (client.local_addr().unwrap(), migrated.local_addr().unwrap());
+        let auth = MetadataAuth::new(&[9; 32]);
+        sender.set_metadata_auth(auth.clone());
+        sender.accept_client(client_addr).unwrap();
+        *sender.client_session.write() = Some(7);
+        sender.connect_peer(client_addr, &socket).await;
+        assert_eq!(socket.peer(), Some(client_addr));
+
+        // 클라이언트 소식이 끊기면 연결을 풀어 다른 주소의 패킷도 받음
+        sender.release_silent_peer(&socket);
+        assert_eq!(socket.peer(), Some(client_addr));
+        *sender.client_heard.lock() -= CONNECTED_PEER_SILENCE;
+        sender.release_silent_peer(&socket);
+        assert_eq!(socket.peer(), None);
+
+        // 새 주소의 인증된 Heartbeat → 확인 토큰, 태그 붙은 응답 → 새 주소로 이전해 다시 연결
+        let heartbeat = HeartbeatMessage::new(1).with_connection_id(7).authenticated(&auth);
+        migrated.send_to(&heartbeat.to_bytes(), server_addr).await.unwrap();
+        let mut buf = [0u8; 2048];
+        let (len, addr) = socket.recv_from(&mut buf).await.unwrap();
+        sender.handle_message(&buf[..len], addr, &socket).await.unwrap();
+        let (len, _) = migrated.recv_from(&mut buf).await.unwrap();
+        let challenge = PathChallengeMessage::from_bytes(&buf[..len]).unwrap();
+        let response = PathResponseMessage::answer(&challenge);
+        sender
+            .handle_message(&response.to_bytes(), migrated_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.client_addr(), Some(client_addr));
+        sender
+            .handle_message(&response.authenticated(&auth).to_bytes(), migrated_addr, &socket)
+            .await
+            .unwrap();
+        assert_eq!(sender.client_addr(), Some(migrated_addr));
+        assert_eq!(socket.peer(), Some(migrated_addr));
+
+        // 엿본 Heartbeat를 다른 주소에서 다시 보내도 이전을 시작하지 않음
+        sender
+            .handle_message(&heartbeat.to_bytes(), client_addr, &socket)
+            .await
+            .unwrap();
+        assert!(sender.pending_migration.lock().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delay_step_restarts_sender_estimators() {
+        use crate::sim::{SimConfig, SimNetwork};
+
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(5),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let config = Config {
+            network_change_delay_step_ms: 30,
+            ..Config::default()
+        };
+        let server_socket = Arc::new(net.bind(server_addr));
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, mut segment_rx) = crate::Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind(client_addr)),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        assert!(wait_until(Duration::from_secs(2), || sender.client_addr().is_some()).await);
+        let drain = tokio::spawn(async move { while segment_rx.recv().await.is_some() {} });
+
+        // 10ms마다 작은 세그먼트를 계속 보냄
+        let send_sender = sender.clone();
+        let send = tokio::spawn(async move {
+            loop {
+                send_sender
+                    .send_data(Bytes::from(vec![3u8; 8_000]), &*server_socket)
+                    .await
+                    .unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
             }
+        });
 
-            // 타임아웃 확인
-            if state.created_at.elapsed() > Duration::from_millis(self.config.segment_timeout_ms) {
-                state.completed = true;
+        // 지연이 일정한 동안은 전환으로 보지 않음
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert_eq!(receiver.network_changes(), 0);
+        let old_rtt = receiver.handshake_rtt().unwrap();
+
+        // 셀룰러로 전환: 편도 지연 5ms → 60ms
+        net.set_config(SimConfig {
+            delay: Duration::from_millis(60),
+            ..net.config()
+        });
+        assert!(
+            wait_until(Duration::from_secs(3), || {
+                receiver.network_changes() == 1 && sender.get_stats().network_changes == 1
+            })
+            .await
+        );
+
+        // 수신자가 편도 지연 변화로 추정한 RTT로 슬로 스타트부터 다시 시작
+        let new_rtt = receiver.handshake_rtt().unwrap();
+        assert!(new_rtt >= old_rtt + Duration::from_millis(100), "{:?}", new_rtt);
+        assert_eq!(sender.slow_start().rtt(), new_rtt);
+        assert_eq!(sender.current_redundancy_ratio(), config.base_redundancy_ratio);
+
+        // 새 경로에서 계속 전달되고, 새 기준선에서는 다시 감지하지 않음
+        let completed = receiver.completed_segments();
+        assert!(
+            wait_until(Duration::from_secs(3), || receiver.completed_segments() >= completed + 10)
+                .await
+        );
+        assert_eq!(receiver.network_changes(), 1);
+
+        send.abort();
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+        drain.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pause_holds_transfer_without_timeouts() {
+        use crate::receiver::FileReceiver;
+        use crate::sim::{SimConfig, SimNetwork};
+
+        // 8 Mbit/s 링크에서 200KB 세그먼트 두 개가 약 400ms 동안 흐름
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(5),
+            bandwidth_bps: Some(8_000_000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        // 정지 시간보다 짧은 타임아웃: 정지를 모르면 NACK을 쏟아내고 세그먼트를 포기함
+        // 슬로 스타트로 천천히 흘려 전송 도중에 정지
+        let config = Config {
+            segment_size: 200_000,
+            nack_timeout_ms: 20,
+            segment_timeout_ms: 500,
+            receive_segment_retries: 1,
+            initial_window_chunks: 10,
+            ..Config::default()
+        };
+        let server_socket = Arc::new(net.bind(server_addr));
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        assert!(matches!(sender.pause(), Err(Error::ConnectionClosed)));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, segment_rx) = crate::Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+        assert!(wait_until(Duration::from_secs(2), || sender.client_addr().is_some()).await);
+
+        let payload = Bytes::from((0..400_000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>());
+        let send_sender = sender.clone();
+        let send_socket = server_socket.clone();
+        let send_payload = payload.clone();
+        let send = tokio::spawn(async move {
+            for part in [send_payload.slice(..200_000), send_payload.slice(200_000..)] {
+                send_sender.send_data(part, &*send_socket).await.unwrap();
+            }
+        });
+
+        let control = async {
+            let started = || sender.get_stats().total_chunks >= 40;
+            assert!(wait_until(Duration::from_secs(2), started).await);
+            sender.pause().unwrap();
+            assert!(sender.is_paused());
+            // 이미 링크에 실린 청크와 정지 전 NACK이 지나갈 때까지
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let chunks = sender.get_stats().total_chunks;
+            let datagrams = net.stats().sent;
+
+            tokio::time::sleep(Duration::from_millis(1500)).await;
+            // 데이터 청크도 NACK도 없이 주기적인 Pause 통보만 오감
+            assert_eq!(sender.get_stats().total_chunks, chunks);
+            assert!(net.stats().sent - datagrams <= 10, "정지 중 데이터그램이 오감");
+            assert!(sender.segments.len() <= 1);
+            sender.resume();
+        };
+        let (data, ()) = tokio::time::timeout(
+            Duration::from_secs(10),
+            async { tokio::join!(file_receiver.receive_file(2), control) },
+        )
+        .await
+        .expect("재개 후 전송 시간 초과");
+        assert_eq!(data.unwrap(), payload);
+        assert!(!sender.is_paused());
+        assert!(file_receiver.receiver().failed_segments().is_empty());
+
+        send.await.unwrap();
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    /// 청크 2를 계속 버리는 소켓 (전달할 수 없는 청크)
+    struct DropChunkSocket {
+        inner: crate::sim::SimSocket,
+        dropped: AtomicU64,
+    }
+
+    impl DatagramSocket for DropChunkSocket {
+        async fn send_to(&self, buf: &[u8], target: SocketAddr) -> std::io::Result<usize> {
+            if Chunk::from_bytes(buf).is_some_and(|chunk| chunk.header.chunk_id == 2) {
+                self.dropped.fetch_add(1, Ordering::SeqCst);
+                return Ok(buf.len());
             }
+            self.inner.send_to(buf, target).await
+        }
+
+        async fn recv_from(&self, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+            self.inner.recv_from(buf).await
         }
 
-        // 완료된 세그먼트 정리
-        self.segments.retain(|_, state| !state.completed);
+        fn local_addr(&self) -> std::io::Result<SocketAddr> {
+            self.inner.local_addr()
+        }
     }
 
-    /// 중복률 업데이트
-    fn update_redundancy(&self) {
-        let new_redundancy = self.path_manager.calculate_redundancy();
-        *self.current_redundancy.write() = new_redundancy;
+    #[tokio::test]
+    async fn test_undeliverable_chunk_given_up_after_retransmit_limit() {
+        use crate::sim::{SimConfig, SimNetwork};
+
+        let net = SimNetwork::new(SimConfig::default());
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        // 수신자는 스스로 포기하지 않으므로 송신자의 통보로만 실패함
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 5000,
+            base_redundancy_ratio: 0.0,
+            min_redundancy_ratio: 0.0,
+            nack_timeout_ms: 20,
+            max_stalled_nacks: 0,
+            segment_timeout_ms: 30_000,
+            max_chunk_retransmits: 3,
+            ..Config::default()
+        };
+        let server_socket = Arc::new(DropChunkSocket {
+            inner: net.bind(server_addr),
+            dropped: AtomicU64::new(0),
+        });
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, _segment_rx) = crate::Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        assert!(wait_until(Duration::from_secs(2), || sender.client_addr().is_some()).await);
+
+        let segment_id = sender
+            .send_data(Bytes::from(vec![7u8; 5000]), &*server_socket)
+            .await
+            .unwrap();
+        let result =
+            tokio::time::timeout(Duration::from_secs(5), receiver.await_segment(segment_id))
+                .await
+                .expect("포기 통보 시간 초과");
+        assert!(
+            matches!(result, Err(Error::SegmentUndeliverable { segment_id: id })
+                if id == segment_id),
+            "{:?}",
+            result
+        );
+        assert_eq!(receiver.failed_segments(), vec![segment_id]);
+
+        // 원본 한 번 + 재전송 3번 뒤로는 보내지 않음
+        assert_eq!(sender.get_stats().abandoned_segments, 1);
+        assert!(sender.segments.is_empty());
+        assert_eq!(server_socket.dropped.load(Ordering::SeqCst), 4);
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(server_socket.dropped.load(Ordering::SeqCst), 4);
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
     }
 
-    /// 정지
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+    /// 시뮬레이터 위에서 핸드셰이크를 마친 송신자/수신자
+    struct SimPair {
+        net: crate::sim::SimNetwork,
+        sender: Arc<Sender>,
+        socket: Arc<crate::sim::SimSocket>,
+        handle: tokio::task::JoinHandle<Result<()>>,
+        files: crate::receiver::FileReceiver,
     }
 
-    /// 통계 반환
-    pub fn get_stats(&self) -> TransferStats {
-        self.stats.read().clone()
+    impl SimPair {
+        async fn connect(config: Config) -> Self {
+            use crate::sim::{SimConfig, SimNetwork};
+
+            let net = SimNetwork::new(SimConfig {
+                delay: Duration::from_millis(2),
+                ..SimConfig::default()
+            });
+            let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+            let socket = Arc::new(net.bind(server_addr));
+            let sender = Arc::new(Sender::new(
+                config.clone(),
+                Arc::new(PathManager::new(config.clone())),
+            ));
+            let sender_task = sender.clone();
+            let socket_task = socket.clone();
+            let handle =
+                tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+            let (receiver, segment_rx) = crate::Receiver::start_with_socket(
+                config.clone(),
+                Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+                server_addr,
+                Arc::new(PathManager::new(config.clone())),
+            )
+            .await
+            .unwrap();
+            let files = crate::receiver::FileReceiver::from_receiver(receiver, segment_rx);
+            let established = || files.receiver().connection_state() == ConnectionState::Transferring;
+            assert!(wait_until(Duration::from_secs(2), established).await);
+            assert!(sender.client_addr().is_some());
+            Self {
+                net,
+                sender,
+                socket,
+                handle,
+                files,
+            }
+        }
+
+        /// `data`를 64KB 세그먼트로 보내고 받은 데이터 반환
+        async fn transfer(&mut self, data: &Bytes) -> Vec<u8> {
+            let segments = data.len().div_ceil(65536);
+            for index in 0..segments {
+                let part = data.slice(index * 65536..((index + 1) * 65536).min(data.len()));
+                self.sender.send_data(part, &*self.socket).await.unwrap();
+            }
+            tokio::time::timeout(Duration::from_secs(10), self.files.receive_file(segments))
+                .await
+                .expect("전송 시간 초과")
+                .unwrap()
+        }
+
+        async fn stop(self) {
+            self.files.receiver().stop().await;
+            self.sender.stop();
+            self.handle.abort();
+        }
     }
 
-    /// 현재 중복률
-    pub fn current_redundancy_ratio(&self) -> f64 {
-        *self.current_redundancy.read()
+    /// 0으로 채운 구멍과 0xFF 구간이 큰 1MB 파일 (64KB 세그먼트 16개 중 12개가 균일)
+    fn holey_file() -> Bytes {
+        let mut data = vec![0u8; 16 * 65536];
+        for segment in [0usize, 7, 11, 15] {
+            for (i, b) in data[segment * 65536..(segment + 1) * 65536].iter_mut().enumerate() {
+                *b = (i % 251) as u8;
+            }
+        }
+        data[8 * 65536..10 * 65536].fill(0xFF);
+        Bytes::from(data)
     }
-}
 
-/// 간단한 파일 전송용 송신자
-pub struct FileSender {
-    sender: Arc<Sender>,
-    socket: Arc<UdpSocket>,
-}
+    #[tokio::test]
+    async fn test_sparse_segments_skip_zero_regions() {
+        let data = holey_file();
 
-impl FileSender {
-    pub async fn new(config: Config, bind_addr: SocketAddr) -> Result<Self> {
-        let path_manager = Arc::new(PathManager::new(config.clone()));
-        let sender = Arc::new(Sender::new(config, path_manager));
-        let socket = Arc::new(UdpSocket::bind(bind_addr).await?);
+        let mut plain = SimPair::connect(Config::default()).await;
+        assert!(!plain.files.receiver().sparse_segments());
+        assert_eq!(plain.transfer(&data).await, data);
+        let plain_bytes = plain.net.stats().sent_bytes;
+        assert_eq!(plain.sender.get_stats().sparse_segments, 0);
+        plain.stop().await;
+
+        let config = Config {
+            sparse_segments: true,
+            ..Config::default()
+        };
+        let mut sparse = SimPair::connect(config).await;
+        assert!(sparse.sender.sends_sparse());
+        assert!(sparse.files.receiver().sparse_segments());
+        assert_eq!(sparse.transfer(&data).await, data);
+        let sparse_bytes = sparse.net.stats().sent_bytes;
+        assert_eq!(sparse.sender.get_stats().sparse_segments, 12);
+        assert_eq!(sparse.files.receiver().get_stats().await.sparse_segments, 12);
 
-        Ok(Self { sender, socket })
+        // 균일 세그먼트 12개는 표시 하나씩만: 링크 바이트는 청크로 보낸 4개 세그먼트 수준
+        assert!(sparse_bytes * 3 < plain_bytes, "{} vs {}", sparse_bytes, plain_bytes);
+        assert!(wait_until(Duration::from_secs(1), || sparse.sender.bytes_in_flight() == 0).await);
+        sparse.stop().await;
     }
 
-    /// 파일 데이터 전송
-    pub async fn send_file(&self, data: &[u8], client_addr: SocketAddr) -> Result<()> {
-        // 클라이언트 주소 설정
-        *self.sender.client_addr.write() = Some(client_addr);
+    #[tokio::test]
+    async fn test_sparse_markers_authenticated_when_encrypted() {
+        use crate::crypto::{CryptoSession, EphemeralKeyPair};
 
-        let segment_size = self.sender.config.segment_size;
-        let mut offset = 0;
+        let config = Config {
+            encryption_enabled: true,
+            sparse_segments: true,
+            ..Config::default()
+        };
+        let mut pair = SimPair::connect(config).await;
+        // 인증 키가 없으면 암호화 중에는 표시를 보내지 않음
+        assert!(pair.files.receiver().sparse_segments());
+        assert!(!pair.sender.sends_sparse());
 
-        while offset < data.len() {
-            let end = (offset + segment_size).min(data.len());
-            let segment_data = Bytes::copy_from_slice(&data[offset..end]);
+        let (server_keys, client_keys) = (EphemeralKeyPair::generate(), EphemeralKeyPair::generate());
+        let (server_public, client_public) =
+            (server_keys.public_key_bytes(), client_keys.public_key_bytes());
+        let server = CryptoSession::establish(server_keys, client_public);
+        let client = CryptoSession::establish(client_keys, server_public);
+        pair.sender.set_metadata_auth(server.metadata_auth());
+        pair.files.receiver().set_metadata_auth(client.metadata_auth());
+        assert!(pair.sender.sends_sparse());
 
-            self.sender
-                .send_data(segment_data, &self.socket)
-                .await?;
+        let data = holey_file();
+        assert_eq!(pair.transfer(&data).await, data);
+        assert_eq!(pair.sender.get_stats().sparse_segments, 12);
+        assert_eq!(pair.files.receiver().rejected_sparse_markers(), 0);
 
-            offset = end;
+        // 태그 없는 표시와 다른 키로 만든 표시는 세그먼트를 만들지 않고 버림
+        let attacker = pair.net.bind("10.0.0.9:9000".parse().unwrap());
+        let receiver_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let connection_id = pair.sender.client_session.read().unwrap();
+        let forged = SparseSegmentMessage::new(connection_id, 1000, MANIFEST_FILE_ID, 65536, 0);
+        let other = CryptoSession::default().metadata_auth();
+        for marker in [forged, forged.authenticated(&other)] {
+            attacker.send_to(&marker.to_bytes(), receiver_addr).await.unwrap();
         }
-
-        Ok(())
+        let receiver = pair.files.receiver();
+        assert!(wait_until(Duration::from_secs(1), || receiver.rejected_sparse_markers() == 2).await);
+        assert_eq!(receiver.completed_segments(), 16);
+        pair.stop().await;
     }
 
-    /// 통계 반환
-    pub fn stats(&self) -> TransferStats {
-        self.sender.get_stats()
+    #[tokio::test]
+    async fn test_unverifiable_sparse_markers_fall_back_to_chunks() {
+        use crate::crypto::CryptoSession;
+
+        let config = Config {
+            encryption_enabled: true,
+            sparse_segments: true,
+            ..Config::default()
+        };
+        let mut pair = SimPair::connect(config).await;
+        // 송신자만 키가 있어 수신자는 표시를 확인할 수 없음
+        pair.sender.set_metadata_auth(CryptoSession::default().metadata_auth());
+        assert!(pair.sender.sends_sparse());
+
+        let data = holey_file();
+        assert_eq!(pair.transfer(&data).await, data);
+        assert!(!pair.sender.sends_sparse());
+        assert!(pair.files.receiver().rejected_sparse_markers() > 0);
+        assert_eq!(pair.files.receiver().get_stats().await.sparse_segments, 0);
+        pair.stop().await;
     }
 }
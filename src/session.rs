@@ -0,0 +1,192 @@
+//! 클라이언트 세션 테이블
+//!
+//! - 출발지 주소별 세션 (클라이언트 세션 ID + 서버 epoch)
+//! - 같은 세션 ID의 Init은 재전송으로 간주 (InitAck만 재전송)
+//! - 같은 주소에서 다른 세션 ID의 Init은 수신자 재시작으로 간주:
+//!   이전 세션의 전송 태스크를 취소하고 상태를 해제한 뒤 새 epoch로 시작
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use tokio::task::AbortHandle;
+
+/// Init 처리 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitDisposition {
+    /// 새 주소의 첫 세션
+    New { epoch: u64 },
+
+    /// 진행 중인 세션의 Init 재전송
+    Duplicate { epoch: u64 },
+
+    /// 같은 주소에서 새 세션 시작 (이전 세션 정리됨)
+    Restarted { epoch: u64, previous_epoch: u64 },
+}
+
+impl InitDisposition {
+    /// 이 Init으로 유효해진 세션 epoch
+    pub fn epoch(&self) -> u64 {
+        match *self {
+            InitDisposition::New { epoch }
+            | InitDisposition::Duplicate { epoch }
+            | InitDisposition::Restarted { epoch, .. } => epoch,
+        }
+    }
+
+    /// 새 전송을 시작해야 하는지 여부
+    pub fn is_fresh(&self) -> bool {
+        !matches!(self, InitDisposition::Duplicate { .. })
+    }
+}
+
+/// 세션 하나
+struct Session<S> {
+    session_id: u64,
+    epoch: u64,
+    task: Option<AbortHandle>,
+    state: S,
+}
+
+impl<S> Drop for Session<S> {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// 주소별 세션 테이블 (`S`: 세션별 상태, 예: 재전송용 청크 캐시)
+pub struct SessionTable<S> {
+    sessions: HashMap<SocketAddr, Session<S>>,
+    next_epoch: u64,
+}
+
+impl<S: Default> SessionTable<S> {
+    pub fn new() -> Self {
+        Self {
+            sessions: HashMap::new(),
+            next_epoch: 1,
+        }
+    }
+
+    /// Init 수신 처리
+    pub fn on_init(&mut self, addr: SocketAddr, session_id: u64) -> InitDisposition {
+        let previous_epoch = match self.sessions.get(&addr) {
+            Some(session) if session.session_id == session_id => {
+                return InitDisposition::Duplicate {
+                    epoch: session.epoch,
+                };
+            }
+            Some(session) => Some(session.epoch),
+            None => None,
+        };
+
+        // 이전 세션은 Drop에서 태스크 취소 및 상태 해제
+        let epoch = self.next_epoch;
+        self.next_epoch += 1;
+        self.sessions.insert(
+            addr,
+            Session {
+                session_id,
+                epoch,
+                task: None,
+                state: S::default(),
+            },
+        );
+
+        match previous_epoch {
+            Some(previous_epoch) => InitDisposition::Restarted {
+                epoch,
+                previous_epoch,
+            },
+            None => InitDisposition::New { epoch },
+        }
+    }
+
+    /// 세션의 전송 태스크 등록 (epoch가 바뀌었으면 즉시 취소)
+    pub fn attach_task(&mut self, addr: SocketAddr, epoch: u64, task: AbortHandle) {
+        match self.sessions.get_mut(&addr) {
+            Some(session) if session.epoch == epoch => {
+                if let Some(old) = session.task.replace(task) {
+                    old.abort();
+                }
+            }
+            _ => task.abort(),
+        }
+    }
+
+    /// 세션 상태 조회
+    pub fn state(&self, addr: &SocketAddr) -> Option<&S> {
+        self.sessions.get(addr).map(|s| &s.state)
+    }
+
+    /// 현재 세션 epoch
+    pub fn epoch(&self, addr: &SocketAddr) -> Option<u64> {
+        self.sessions.get(addr).map(|s| s.epoch)
+    }
+
+    /// 세션 종료 (태스크 취소 및 상태 해제)
+    pub fn remove(&mut self, addr: &SocketAddr) -> bool {
+        self.sessions.remove(addr).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+impl<S: Default> Default for SessionTable<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_restart_replaces_session_and_cancels_task() {
+        let mut table: SessionTable<Arc<()>> = SessionTable::new();
+        let addr: SocketAddr = "10.0.0.1:5000".parse().unwrap();
+
+        let first = table.on_init(addr, 111);
+        assert_eq!(first, InitDisposition::New { epoch: 1 });
+        let old_state = Arc::downgrade(table.state(&addr).unwrap());
+
+        let old_task = tokio::spawn(std::future::pending::<()>());
+        table.attach_task(addr, first.epoch(), old_task.abort_handle());
+
+        // 같은 세션의 Init 재전송은 그대로 유지
+        assert_eq!(table.on_init(addr, 111), InitDisposition::Duplicate { epoch: 1 });
+        assert!(!old_task.is_finished());
+
+        // 재시작한 수신자의 새 세션 → 이전 태스크 취소, 상태 해제
+        let second = table.on_init(addr, 222);
+        assert_eq!(
+            second,
+            InitDisposition::Restarted {
+                epoch: 2,
+                previous_epoch: 1
+            }
+        );
+        let err = tokio::time::timeout(Duration::from_secs(1), old_task)
+            .await
+            .unwrap()
+            .unwrap_err();
+        assert!(err.is_cancelled());
+        assert!(old_state.upgrade().is_none());
+        assert_eq!(table.len(), 1);
+
+        // 늦게 등록된 이전 epoch 태스크는 즉시 취소
+        let stale = tokio::spawn(std::future::pending::<()>());
+        table.attach_task(addr, first.epoch(), stale.abort_handle());
+        assert!(stale.await.unwrap_err().is_cancelled());
+    }
+}
@@ -0,0 +1,929 @@
+//! 결정적 네트워크 시뮬레이터 (통합 테스트용)
+//!
+//! - `SimNetwork`에 바인딩한 `SimSocket`끼리 메모리 안에서 데이터그램 교환
+//! - 손실 확률, 고정/가변 지연, 재정렬, 송신 링크 대역폭 제한, MTU 블랙홀 주입
+//! - 시드 고정 RNG: 같은 송신 순서면 같은 손실/지연 패턴
+//! - 배달 시각은 `tokio::time` 기준 (일시정지 런타임에서도 동작)
+
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::{Mutex, RwLock};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+use crate::transport::DatagramSocket;
+
+/// 배달 대기 중인 데이터그램 (데이터, 출발지)
+type Datagram = (Vec<u8>, SocketAddr);
+
+/// 링크 특성
+#[derive(Debug, Clone)]
+pub struct SimConfig {
+    /// 패킷 손실 확률 (0.0 ~ 1.0)
+    pub loss: f64,
+
+    /// 고정 편도 지연
+    pub delay: Duration,
+
+    /// 추가 지연 상한 (0 ~ jitter 균등 분포)
+    pub jitter: Duration,
+
+    /// 재정렬 확률 (해당 패킷은 `delay + jitter`만큼 더 늦게 배달)
+    pub reorder: f64,
+
+    /// 복제 확률 (해당 패킷의 사본 하나를 `delay + jitter`만큼 뒤에 한 번 더 배달)
+    pub duplicate: f64,
+
+    /// 송신 링크 대역폭 (bits/s, None이면 무제한)
+    pub bandwidth_bps: Option<u64>,
+
+    /// 이보다 큰 데이터그램은 알림 없이 버림 (PMTU 블랙홀, None이면 제한 없음)
+    pub mtu: Option<usize>,
+
+    /// RNG 시드
+    pub seed: u64,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            loss: 0.0,
+            delay: Duration::ZERO,
+            jitter: Duration::ZERO,
+            reorder: 0.0,
+            duplicate: 0.0,
+            bandwidth_bps: None,
+            mtu: None,
+            seed: 0,
+        }
+    }
+}
+
+/// 시뮬레이터 누적 통계
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SimStats {
+    /// 송신 시도한 데이터그램 수
+    pub sent: u64,
+
+    /// 송신 시도한 바이트 수 (데이터그램 페이로드 합)
+    pub sent_bytes: u64,
+
+    /// 손실 처리된 데이터그램 수
+    pub dropped: u64,
+
+    /// 지연을 더해 재정렬된 데이터그램 수
+    pub reordered: u64,
+
+    /// 사본을 한 번 더 배달한 데이터그램 수
+    pub duplicated: u64,
+}
+
+struct NetInner {
+    config: SimConfig,
+    rng: StdRng,
+    endpoints: HashMap<SocketAddr, mpsc::UnboundedSender<Datagram>>,
+    /// 출발지별 송신 링크가 비는 시각 (대역폭 제한)
+    busy_until: HashMap<SocketAddr, Instant>,
+    stats: SimStats,
+}
+
+/// 인메모리 네트워크
+#[derive(Clone)]
+pub struct SimNetwork {
+    inner: Arc<Mutex<NetInner>>,
+}
+
+impl SimNetwork {
+    pub fn new(config: SimConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(NetInner {
+                rng: StdRng::seed_from_u64(config.seed),
+                config,
+                endpoints: HashMap::new(),
+                busy_until: HashMap::new(),
+                stats: SimStats::default(),
+            })),
+        }
+    }
+
+    /// 주소에 소켓 바인딩 (같은 주소를 다시 바인딩하면 이전 소켓은 더 이상 수신하지 않음)
+    pub fn bind(&self, addr: SocketAddr) -> SimSocket {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.inner.lock().endpoints.insert(addr, tx);
+        SimSocket {
+            addr: RwLock::new(addr),
+            net: self.clone(),
+            rx: tokio::sync::Mutex::new(rx),
+            recv_buffer_size: AtomicUsize::new(0),
+        }
+    }
+
+    /// 링크 특성 변경 (이후 송신부터 적용, RNG 상태는 유지)
+    pub fn set_config(&self, config: SimConfig) {
+        self.inner.lock().config = config;
+    }
+
+    /// 현재 링크 특성
+    pub fn config(&self) -> SimConfig {
+        self.inner.lock().config.clone()
+    }
+
+    /// 누적 통계
+    pub fn stats(&self) -> SimStats {
+        self.inner.lock().stats
+    }
+
+    fn send(&self, from: SocketAddr, buf: &[u8], to: SocketAddr) {
+        let mut inner = self.inner.lock();
+        inner.stats.sent += 1;
+        inner.stats.sent_bytes += buf.len() as u64;
+
+        let config = inner.config.clone();
+        if config.mtu.is_some_and(|mtu| buf.len() > mtu)
+            || (config.loss > 0.0 && inner.rng.gen::<f64>() < config.loss)
+        {
+            inner.stats.dropped += 1;
+            return;
+        }
+
+        // 대역폭 제한: 송신 링크를 직렬화 시간만큼 점유
+        let now = Instant::now();
+        let departure = match config.bandwidth_bps {
+            Some(bps) if bps > 0 => {
+                let tx_time = Duration::from_secs_f64(buf.len() as f64 * 8.0 / bps as f64);
+                let start = inner.busy_until.get(&from).copied().unwrap_or(now).max(now);
+                let done = start + tx_time;
+                inner.busy_until.insert(from, done);
+                done
+            }
+            _ => now,
+        };
+
+        let mut delay = config.delay;
+        if !config.jitter.is_zero() {
+            delay += config.jitter.mul_f64(inner.rng.gen::<f64>());
+        }
+        if config.reorder > 0.0 && inner.rng.gen::<f64>() < config.reorder {
+            delay += config.delay + config.jitter;
+            inner.stats.reordered += 1;
+        }
+        let duplicate = config.duplicate > 0.0 && inner.rng.gen::<f64>() < config.duplicate;
+        if duplicate {
+            inner.stats.duplicated += 1;
+        }
+
+        // 바인딩되지 않은 목적지는 UDP처럼 조용히 버림
+        let Some(tx) = inner.endpoints.get(&to).cloned() else {
+            return;
+        };
+        drop(inner);
+
+        let datagram = (buf.to_vec(), from);
+        let deliver_at = departure + delay;
+        if duplicate {
+            Self::deliver(tx.clone(), datagram.clone(), deliver_at + config.delay + config.jitter);
+        }
+        Self::deliver(tx, datagram, deliver_at);
+    }
+
+    fn deliver(tx: mpsc::UnboundedSender<Datagram>, datagram: Datagram, deliver_at: Instant) {
+        if deliver_at <= Instant::now() {
+            let _ = tx.send(datagram);
+        } else {
+            tokio::spawn(async move {
+                tokio::time::sleep_until(deliver_at).await;
+                let _ = tx.send(datagram);
+            });
+        }
+    }
+}
+
+/// `SimNetwork`에 바인딩된 소켓
+pub struct SimSocket {
+    addr: RwLock<SocketAddr>,
+    net: SimNetwork,
+    rx: tokio::sync::Mutex<mpsc::UnboundedReceiver<Datagram>>,
+    /// 마지막으로 설정된 수신 버퍼 크기 (0이면 설정된 적 없음, 배달에는 영향 없음)
+    recv_buffer_size: AtomicUsize,
+}
+
+impl SimSocket {
+    /// 소속 네트워크
+    pub fn network(&self) -> &SimNetwork {
+        &self.net
+    }
+
+    /// `set_buffer_sizes`로 마지막에 설정된 수신 버퍼 크기
+    pub fn recv_buffer_size(&self) -> usize {
+        self.recv_buffer_size.load(Ordering::Relaxed)
+    }
+
+    /// 소켓을 다른 주소로 옮김 (NAT 재바인딩, 네트워크 전환)
+    ///
+    /// 이후 송신은 새 주소에서 나가고 이전 주소로 오는 데이터그램은 버려짐.
+    /// 이미 배달 중인 데이터그램은 그대로 도착
+    pub fn rebind(&self, addr: SocketAddr) {
+        let mut current = self.addr.write();
+        let mut inner = self.net.inner.lock();
+        if let Some(tx) = inner.endpoints.remove(&current) {
+            inner.endpoints.insert(addr, tx);
+        }
+        *current = addr;
+    }
+}
+
+impl DatagramSocket for SimSocket {
+    async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        let from = *self.addr.read();
+        self.net.send(from, buf, target);
+        Ok(buf.len())
+    }
+
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let (data, from) = self
+            .rx
+            .lock()
+            .await
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "시뮬레이터 소켓 해제됨"))?;
+
+        // UDP와 같이 버퍼보다 긴 데이터그램은 잘림
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok((len, from))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(*self.addr.read())
+    }
+
+    fn set_buffer_sizes(&self, recv: usize, _send: usize) -> io::Result<()> {
+        self.recv_buffer_size.store(recv, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::SegmentId;
+    use crate::multipath::PathManager;
+    use crate::receiver::{FileReceiver, Receiver};
+    use crate::sender::Sender;
+    use crate::Config;
+    use bytes::Bytes;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_delay_and_bandwidth_cap() {
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(50),
+            bandwidth_bps: Some(8_000_000), // 1000 bytes = 1ms
+            ..SimConfig::default()
+        });
+        let a = net.bind("10.0.0.1:1000".parse().unwrap());
+        let b = net.bind("10.0.0.2:2000".parse().unwrap());
+
+        let start = Instant::now();
+        for _ in 0..10 {
+            a.send_to(&[0u8; 1000], b.local_addr().unwrap()).await.unwrap();
+        }
+
+        let mut buf = [0u8; 2000];
+        for i in 1..=10u64 {
+            let (len, from) = b.recv_from(&mut buf).await.unwrap();
+            assert_eq!(len, 1000);
+            assert_eq!(from, a.local_addr().unwrap());
+            assert_eq!(start.elapsed(), Duration::from_millis(50 + i));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bandwidth_probe_sets_initial_rate() {
+        // 40 Mbit/s = 5 MB/s 병목 링크
+        let bandwidth = 5_000_000.0;
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(10),
+            bandwidth_bps: Some(40_000_000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind(client_addr));
+
+        let config = Config {
+            bandwidth_probe: true,
+            initial_window_chunks: 10,
+            ..Config::default()
+        };
+        let chunk_bytes = crate::probe::probe_datagram_len(&config);
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(server_socket).await });
+
+        let (receiver, _segments) = Receiver::start_with_socket(
+            config.clone(),
+            client_socket,
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr() != Some(client_addr) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("핸드셰이크 시간 초과");
+        // 초기 윈도우만으로는 링크 용량보다 한참 느림
+        let guessed = sender.slow_start().rate(chunk_bytes);
+        assert!(guessed < bandwidth / 2.0, "{}", guessed);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.slow_start().window() == config.initial_window_chunks {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("프로브 보고 시간 초과");
+
+        let measured = receiver.probed_rate().expect("프로브 측정") as f64;
+        let initial = sender.slow_start().rate(chunk_bytes);
+        for rate in [measured, initial] {
+            assert!(
+                (bandwidth / 2.0..bandwidth * 2.0).contains(&rate),
+                "측정 {} / 초기 {} bytes/s",
+                measured,
+                initial
+            );
+        }
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_buffer_tuning_grows_toward_bdp() {
+        // 200 Mbit/s × RTT 100ms → BDP 2.5MB
+        let bdp = 25_000_000.0 * 0.1;
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(50),
+            bandwidth_bps: Some(200_000_000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind(client_addr));
+
+        let config = Config {
+            auto_buffer_tuning: true,
+            bandwidth_probe: true,
+            recv_buffer_size: 256 * 1024,
+            send_buffer_size: 256 * 1024,
+            min_socket_buffer_size: 256 * 1024,
+            ..Config::default()
+        };
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, mut segments) = Receiver::start_with_socket(
+            config.clone(),
+            client_socket.clone(),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(client_socket.recv_buffer_size(), 256 * 1024);
+        assert_eq!(receiver.socket_buffer_size(), Some(256 * 1024));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr() != Some(client_addr) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("핸드셰이크 시간 초과");
+
+        const SEGMENTS: usize = 256;
+        let drain = tokio::spawn(async move {
+            for _ in 0..SEGMENTS {
+                segments.recv().await.expect("세그먼트 수신");
+            }
+        });
+        let payload = Bytes::from(vec![0x5Au8; config.segment_size]);
+        for _ in 0..SEGMENTS {
+            sender.send_data(payload.clone(), &*server_socket).await.unwrap();
+        }
+        tokio::time::timeout(Duration::from_secs(10), drain)
+            .await
+            .expect("전송 시간 초과")
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.get_stats().completed_segments < SEGMENTS as u64 {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("완료 보고 시간 초과");
+
+        // 수신 버퍼가 하한에서 BDP 근처로 커짐 (여유 배수 2, 실시간 측정 RTT 변동 허용)
+        let tuned = client_socket.recv_buffer_size() as f64;
+        assert_eq!(receiver.socket_buffer_size(), Some(tuned as usize));
+        assert!(
+            (bdp / 3.0..=bdp * 8.0).contains(&tuned),
+            "수신 버퍼 {} bytes, BDP {} bytes",
+            tuned,
+            bdp
+        );
+        let sender_tuned = sender.socket_buffer_size().unwrap();
+        assert!(sender_tuned > 256 * 1024, "송신 버퍼 {} bytes", sender_tuned);
+        assert_eq!(server_socket.recv_buffer_size(), sender_tuned);
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_time_to_first_segment_includes_startup_delay() {
+        // 편도 100ms: Init 도착 + 첫 청크 도착에 최소 200ms
+        let delay = Duration::from_millis(100);
+        let net = SimNetwork::new(SimConfig {
+            delay,
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind("10.0.0.2:5000".parse().unwrap()));
+
+        let config = Config::default();
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            client_socket,
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr().is_none() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("핸드셰이크 시간 초과");
+
+        const SEGMENTS: usize = 4;
+        let payload = Bytes::from(vec![0x3Cu8; config.segment_size]);
+        for _ in 0..SEGMENTS {
+            sender.send_data(payload.clone(), &*server_socket).await.unwrap();
+        }
+        let report = tokio::time::timeout(
+            Duration::from_secs(10),
+            file_receiver.receive_discard(SEGMENTS),
+        )
+        .await
+        .expect("전송 시간 초과")
+        .unwrap();
+
+        let stats = &report.stats;
+        let ttfs = stats.time_to_first_segment.expect("첫 세그먼트 시각 기록");
+        let ttfb = stats.time_to_first_byte().expect("첫 청크 시각 기록");
+        assert!(ttfb >= delay * 2, "첫 청크 {:?}", ttfb);
+        assert!(ttfs >= ttfb, "첫 세그먼트 {:?} < 첫 청크 {:?}", ttfs, ttfb);
+        assert!(ttfs <= report.stats.elapsed());
+
+        let mut ids: Vec<_> = stats.first_chunk_latency.iter().map(|&(id, _)| id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, (1..=SEGMENTS as u64).collect::<Vec<_>>());
+        assert!(stats
+            .first_chunk_latency
+            .iter()
+            .all(|&(_, latency)| latency >= ttfb));
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_chunk_size_backs_off_on_mtu_blackhole() {
+        // 가득 찬 청크 데이터그램은 모두 버려지고 짧은 청크만 통과하는 경로
+        let net = SimNetwork::new(SimConfig {
+            mtu: Some(1000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind("10.0.0.2:5000".parse().unwrap()));
+
+        let config = Config::default();
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            client_socket,
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut file_receiver = FileReceiver::from_receiver(receiver, segment_rx);
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr().is_none() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("핸드셰이크 시간 초과");
+
+        let full = config.effective_chunk_size();
+        let mut expected = Vec::new();
+        // 첫 세그먼트는 짧은 청크가 없어 NACK도 없음: 응답 없는 꼬리 탐침으로 의심
+        for (i, len) in [full * 10, full * 10 + 100].into_iter().enumerate() {
+            let payload: Vec<u8> = (0..len).map(|b| (b as u8) ^ i as u8).collect();
+            sender.send_data(Bytes::from(payload.clone()), &*server_socket).await.unwrap();
+            expected.extend_from_slice(&payload);
+        }
+
+        let data = tokio::time::timeout(Duration::from_secs(10), file_receiver.receive_file(2))
+            .await
+            .expect("블랙홀 경로 전송 시간 초과")
+            .unwrap();
+        assert_eq!(data, expected);
+        assert!(sender.chunk_size() < full, "청크 크기 {}", sender.chunk_size());
+        assert!(net.stats().dropped > 0);
+        // 처음 크기를 확인한 뒤 줄인 크기로 탐침
+        assert!(sender.get_stats().pmtu_probes >= 3);
+
+        file_receiver.receiver().stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_transfer_completes_over_lossy_link() {
+        let net = SimNetwork::new(SimConfig {
+            loss: 0.05,
+            delay: Duration::from_millis(50),
+            seed: 7,
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        let server_socket = Arc::new(net.bind(server_addr));
+        let client_socket = Arc::new(net.bind(client_addr));
+
+        let config = Config::default();
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, mut segments) = Receiver::start_with_socket(
+            config.clone(),
+            client_socket,
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+
+        // 손실된 Init/쿠키도 재전송으로 핸드셰이크 완료
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr() != Some(client_addr) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("핸드셰이크 시간 초과");
+
+        let payloads: Vec<Bytes> = (0..8u8)
+            .map(|i| Bytes::from((0..60_000).map(|b| (b as u8) ^ i).collect::<Vec<u8>>()))
+            .collect();
+        let mut expected = HashMap::new();
+        for payload in &payloads {
+            let id = sender.send_data(payload.clone(), &*server_socket).await.unwrap();
+            expected.insert(id, payload.clone());
+        }
+
+        tokio::time::timeout(Duration::from_secs(20), async {
+            while !expected.is_empty() {
+                let (id, data) = segments.recv().await.expect("수신자 종료");
+                assert_eq!(expected.remove(&id).as_ref(), Some(&data));
+            }
+        })
+        .await
+        .expect("전송 완료 시간 초과");
+
+        assert!(net.stats().dropped > 0);
+        assert!(sender.get_stats().retransmitted_chunks > 0);
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+    }
+
+    /// 성질 테스트에서 전부 복구되어야 하는 손실률 상한 (넘으면 실패 보고도 허용)
+    const RECOVERABLE_LOSS: f64 = 0.3;
+
+    /// 재전송 조립 성질 테스트의 한 경우. 페이로드는 `seed`로 생성하므로 매개변수만으로 재현됨
+    #[derive(Debug, Clone, PartialEq)]
+    struct AssemblyCase {
+        /// 세그먼트별 길이
+        segments: Vec<usize>,
+        loss: f64,
+        reorder: f64,
+        duplicate: f64,
+        delay_ms: u64,
+        redundancy: f64,
+        /// 손실이 보이면 바로 FEC로 전환
+        fec: bool,
+        seed: u64,
+    }
+
+    impl AssemblyCase {
+        const CHUNK_SIZE: usize = 512;
+        const SEGMENT_SIZE: usize = 16 * 1024;
+
+        fn generate(rng: &mut StdRng) -> Self {
+            let segments = (0..rng.gen_range(1..=4))
+                .map(|_| match rng.gen_range(0..4) {
+                    // 청크 경계 전후 (마지막 청크 길이 계산)
+                    0 => {
+                        let chunks = rng.gen_range(1..=Self::SEGMENT_SIZE / Self::CHUNK_SIZE);
+                        (chunks * Self::CHUNK_SIZE + rng.gen_range(0..=2)).saturating_sub(1)
+                    }
+                    1 => rng.gen_range(0..=Self::CHUNK_SIZE),
+                    _ => rng.gen_range(1..=Self::SEGMENT_SIZE),
+                })
+                .collect();
+            let loss = if rng.gen_bool(0.2) {
+                rng.gen_range(0.5..0.8)
+            } else {
+                rng.gen_range(0.0..RECOVERABLE_LOSS)
+            };
+            Self {
+                segments,
+                loss,
+                reorder: rng.gen_range(0.0..0.3),
+                duplicate: rng.gen_range(0.0..0.3),
+                delay_ms: rng.gen_range(0..=3),
+                redundancy: [0.0, 0.15, 0.5][rng.gen_range(0..3)],
+                fec: rng.gen_bool(0.5),
+                seed: rng.gen(),
+            }
+        }
+
+        fn recoverable(&self) -> bool {
+            self.loss <= RECOVERABLE_LOSS
+        }
+
+        fn payloads(&self) -> Vec<Bytes> {
+            let mut rng = StdRng::seed_from_u64(self.seed);
+            self.segments
+                .iter()
+                .map(|&len| Bytes::from((0..len).map(|_| rng.gen::<u8>()).collect::<Vec<u8>>()))
+                .collect()
+        }
+
+        /// 한 단계 단순한 경우들 (앞쪽일수록 크게 줄임)
+        fn shrink(&self) -> Vec<Self> {
+            let mut candidates = Vec::new();
+            let mut push = |f: &dyn Fn(&mut Self)| {
+                let mut case = self.clone();
+                f(&mut case);
+                if case != *self {
+                    candidates.push(case);
+                }
+            };
+            for i in 0..self.segments.len() {
+                if self.segments.len() > 1 {
+                    push(&|c| {
+                        c.segments.remove(i);
+                    });
+                }
+                push(&|c| c.segments[i] /= 2);
+            }
+            push(&|c| c.loss = 0.0);
+            push(&|c| c.loss /= 2.0);
+            push(&|c| c.reorder = 0.0);
+            push(&|c| c.duplicate = 0.0);
+            push(&|c| c.delay_ms = 0);
+            push(&|c| c.redundancy = 0.0);
+            push(&|c| c.fec = false);
+            candidates
+        }
+
+        /// 전송 후 성질 검사
+        ///
+        /// - 배달된 세그먼트는 보낸 바이트와 정확히 같고 한 번만 배달됨
+        /// - 복구 가능한 손실이면 모든 세그먼트가 배달됨
+        /// - 그보다 심하면 배달되지 않은 세그먼트는 실패로 보고되거나, 청크가 하나도 도착하지
+        ///   않아 수신자가 존재를 모를 수 있음
+        async fn run(&self) -> Result<(), String> {
+            let net = SimNetwork::new(SimConfig {
+                seed: self.seed,
+                ..SimConfig::default()
+            });
+            let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+            let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+            let server_socket = Arc::new(net.bind(server_addr));
+            let client_socket = Arc::new(net.bind(client_addr));
+
+            let config = Config {
+                chunk_size: Self::CHUNK_SIZE,
+                segment_size: Self::SEGMENT_SIZE,
+                base_redundancy_ratio: self.redundancy,
+                min_redundancy_ratio: self.redundancy.min(0.05),
+                fec_loss_threshold: if self.fec { 0.01 } else { 1.0 },
+                fec_switch_hold_ms: 0,
+                max_stalled_nacks: 8,
+                segment_timeout_ms: 3000,
+                ..Config::default()
+            };
+            let sender = Arc::new(Sender::new(
+                config.clone(),
+                Arc::new(PathManager::new(config.clone())),
+            ));
+            let sender_task = sender.clone();
+            let socket_task = server_socket.clone();
+            let handle =
+                tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+            let (receiver, mut segments) = Receiver::start_with_socket(
+                config.clone(),
+                client_socket,
+                server_addr,
+                Arc::new(PathManager::new(config)),
+            )
+            .await
+            .map_err(|e| format!("수신자 시작 실패: {}", e))?;
+
+            let result = async {
+                tokio::time::timeout(Duration::from_secs(5), async {
+                    while sender.client_addr() != Some(client_addr) {
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+                .map_err(|_| "핸드셰이크 시간 초과".to_string())?;
+
+                // 핸드셰이크 뒤부터 손실/재정렬/복제 적용
+                let delay = Duration::from_millis(self.delay_ms);
+                net.set_config(SimConfig {
+                    loss: self.loss,
+                    delay,
+                    jitter: delay,
+                    reorder: self.reorder,
+                    duplicate: self.duplicate,
+                    ..SimConfig::default()
+                });
+
+                let mut sent = HashMap::new();
+                for payload in self.payloads() {
+                    let id = sender
+                        .send_data(payload.clone(), &*server_socket)
+                        .await
+                        .map_err(|e| format!("전송 실패: {}", e))?;
+                    sent.insert(id, payload);
+                }
+
+                let mut pending: Vec<SegmentId> = sent.keys().copied().collect();
+                let mut delivered = Vec::new();
+                let deadline = tokio::time::Instant::now() + Duration::from_secs(15);
+                while !pending.is_empty() && tokio::time::Instant::now() < deadline {
+                    let failed = receiver.failed_segments();
+                    pending.retain(|id| !failed.contains(id));
+                    let Ok(next) =
+                        tokio::time::timeout(Duration::from_millis(50), segments.recv()).await
+                    else {
+                        continue;
+                    };
+                    let (id, data) = next.ok_or("수신자 종료")?;
+                    match sent.get(&id) {
+                        None => return Err(format!("보내지 않은 세그먼트 {} 배달", id)),
+                        Some(payload) if *payload != data => {
+                            let at = payload.iter().zip(&data).position(|(a, b)| a != b);
+                            return Err(format!(
+                                "세그먼트 {} 내용 불일치: {} bytes 보냄, {} bytes 받음, 첫 차이 {:?}",
+                                id,
+                                payload.len(),
+                                data.len(),
+                                at
+                            ));
+                        }
+                        Some(_) if delivered.contains(&id) => {
+                            return Err(format!("세그먼트 {} 중복 배달", id));
+                        }
+                        Some(_) => {
+                            delivered.push(id);
+                            pending.retain(|&p| p != id);
+                        }
+                    }
+                }
+
+                let failed = receiver.failed_segments();
+                if self.recoverable() && (!pending.is_empty() || !failed.is_empty()) {
+                    return Err(format!(
+                        "복구 가능한 손실에서 미완료 {:?}, 실패 보고 {:?}",
+                        pending, failed
+                    ));
+                }
+                Ok(())
+            }
+            .await;
+
+            receiver.stop().await;
+            sender.stop();
+            handle.abort();
+            result
+        }
+    }
+
+    /// 실패한 경우를 더 이상 실패가 재현되지 않을 때까지 줄임
+    async fn shrink_failure(mut case: AssemblyCase, mut error: String) -> (AssemblyCase, String) {
+        let mut budget = 64;
+        'outer: while budget > 0 {
+            for candidate in case.shrink() {
+                budget -= 1;
+                if let Err(e) = candidate.run().await {
+                    case = candidate;
+                    error = e;
+                    continue 'outer;
+                }
+                if budget == 0 {
+                    break;
+                }
+            }
+            break;
+        }
+        (case, error)
+    }
+
+    /// 손실/재정렬/복제/중복률/FEC를 무작위로 골라도 조립 결과는 보낸 바이트와 같음
+    ///
+    /// `SFP_PROPTEST_CASES`(기본 12)와 `SFP_PROPTEST_SEED`로 경우 수와 시드를 바꿀 수 있고,
+    /// 실패하면 가장 작게 줄인 경우를 출력
+    #[tokio::test]
+    async fn test_assembled_output_matches_input_under_arbitrary_loss() {
+        let env = |name: &str, default: u64| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let cases = env("SFP_PROPTEST_CASES", 12);
+        let seed = env("SFP_PROPTEST_SEED", 0x5F9_2181);
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        for index in 0..cases {
+            let case = AssemblyCase::generate(&mut rng);
+            if let Err(error) = case.run().await {
+                let (minimal, error) = shrink_failure(case.clone(), error).await;
+                panic!(
+                    "SFP_PROPTEST_SEED={} 의 {}번째 경우 실패\n원래 경우: {:?}\n\
+                     최소 경우: {:?}\n원인: {}",
+                    seed, index, case, minimal, error
+                );
+            }
+        }
+    }
+}
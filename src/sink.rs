@@ -0,0 +1,127 @@
+//! 수신 데이터 출력 대상 (`FileReceiver::receive_to_sink`)
+//!
+//! 메모리 조립, 파일 기록, 검증 전용 폐기를 하나의 트레이트로 묶어 해시 계산, 압축 해제,
+//! 표준 출력, 별도 저장소 같은 대상을 사용자가 직접 붙일 수 있게 함
+//!
+//! - 세그먼트는 ID 순서대로 전달되며 `offset`은 앞 세그먼트 길이의 누적 합
+//!   (적응형 세그먼트 크기에서도 정확)
+//! - 모든 세그먼트를 전달한 뒤 `finish`를 한 번 호출
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::chunk::SegmentId;
+use crate::Result;
+
+/// 수신한 세그먼트를 받는 출력 대상
+pub trait OutputSink {
+    /// 세그먼트 기록 (`offset`: 전체 데이터에서 이 세그먼트의 시작 위치)
+    fn write_segment(&mut self, segment_id: SegmentId, offset: u64, data: &[u8]) -> Result<()>;
+
+    /// 모든 세그먼트 기록 후 마무리 (버퍼 비우기 등)
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 메모리에 조립
+impl OutputSink for Vec<u8> {
+    fn write_segment(&mut self, _segment_id: SegmentId, offset: u64, data: &[u8]) -> Result<()> {
+        let start = offset as usize;
+        let end = start + data.len();
+        if self.len() < end {
+            self.resize(end, 0);
+        }
+        self[start..end].copy_from_slice(data);
+        Ok(())
+    }
+}
+
+/// 파일에 순서대로 기록
+#[derive(Debug)]
+pub struct FileSink {
+    writer: BufWriter<File>,
+    written: u64,
+}
+
+impl FileSink {
+    /// `path`에 새 파일 생성 (있으면 덮어씀)
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            written: 0,
+        })
+    }
+
+    /// 기록한 바이트 수
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+}
+
+impl OutputSink for FileSink {
+    fn write_segment(&mut self, _segment_id: SegmentId, offset: u64, data: &[u8]) -> Result<()> {
+        debug_assert_eq!(offset, self.written, "세그먼트는 순서대로 전달됨");
+        self.writer.write_all(data)?;
+        self.written += data.len() as u64;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_all()?;
+        Ok(())
+    }
+}
+
+/// 데이터를 버리고 양만 셈 (링크 테스트용)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DiscardSink {
+    /// 받은 세그먼트 수
+    pub segments: u64,
+    /// 받은 바이트 수
+    pub bytes: u64,
+}
+
+impl OutputSink for DiscardSink {
+    fn write_segment(&mut self, _segment_id: SegmentId, _offset: u64, data: &[u8]) -> Result<()> {
+        self.segments += 1;
+        self.bytes += data.len() as u64;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_sinks_assemble_in_order() {
+        let segments: [&[u8]; 3] = [b"abc", b"de", b""];
+        let expected = b"abcde";
+
+        let mut vec = Vec::new();
+        let mut discard = DiscardSink::default();
+        let path = std::env::temp_dir().join(format!("sfp-sink-{}", std::process::id()));
+        let mut file = FileSink::create(&path).unwrap();
+
+        let mut offset = 0;
+        for (i, data) in segments.iter().enumerate() {
+            let id = i as SegmentId + 1;
+            for sink in [&mut vec as &mut dyn OutputSink, &mut discard, &mut file] {
+                sink.write_segment(id, offset, data).unwrap();
+            }
+            offset += data.len() as u64;
+        }
+        for sink in [&mut vec as &mut dyn OutputSink, &mut discard, &mut file] {
+            sink.finish().unwrap();
+        }
+
+        assert_eq!(vec, expected);
+        assert_eq!(discard, DiscardSink { segments: 3, bytes: 5 });
+        assert_eq!(file.written(), 5);
+        assert_eq!(std::fs::read(&path).unwrap(), expected);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
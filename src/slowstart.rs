@@ -0,0 +1,208 @@
+//! 전송 시작 구간의 페이싱 슬로 스타트
+//!
+//! - 첫 라운드(RTT 1회)에는 초기 윈도우만큼만, 라운드 안에서 균등 간격으로 전송
+//! - 라운드가 끝날 때마다 전달 확인된 청크 수만큼 윈도우 증가
+//!   (라운드당 최대 2배, 남은 확인분은 다음 라운드로 이월: 보고가 드문 수신자 대응)
+//! - 손실(NACK) 신호나 윈도우 상한 도달 시 종료하고 이후는 기존 속도 제어에 맡김
+
+use std::time::{Duration, Instant};
+
+use crate::Config;
+
+/// 이보다 가까운 전송 간격은 묶어서 즉시 전송 (타이머 해상도)
+const PACING_GRANULARITY: Duration = Duration::from_millis(1);
+
+/// 페이싱 슬로 스타트 상태
+#[derive(Debug, Clone)]
+pub struct SlowStart {
+    window: u32,
+    max_window: u32,
+    rtt: Duration,
+    round_start: Option<Instant>,
+    round_sent: u32,
+    /// 아직 윈도우에 반영하지 않은 전달 확인 청크 수
+    delivered_credit: u32,
+    exited: bool,
+}
+
+impl SlowStart {
+    /// 설정 기반 생성. 윈도우 상한은 동시 세그먼트 전체 청크 수
+    pub fn new(config: &Config) -> Self {
+        let max_window = (config.max_concurrent_segments * config.chunks_per_segment())
+            .min(u32::MAX as usize) as u32;
+        Self::with_window(
+            config.initial_window_chunks,
+            max_window,
+            Duration::from_millis(config.slow_start_initial_rtt_ms),
+        )
+    }
+
+    /// 초기 윈도우/상한/초기 RTT 지정. 초기 윈도우가 0이면 처음부터 비활성
+    pub fn with_window(initial_window: u32, max_window: u32, initial_rtt: Duration) -> Self {
+        Self {
+            window: initial_window,
+            max_window: max_window.max(initial_window),
+            rtt: initial_rtt.max(Duration::from_micros(100)),
+            round_start: None,
+            round_sent: 0,
+            delivered_credit: 0,
+            exited: initial_window == 0,
+        }
+    }
+
+    /// 슬로 스타트 진행 중 여부
+    pub fn is_active(&self) -> bool {
+        !self.exited
+    }
+
+    /// 현재 라운드 윈도우 (청크 수)
+    pub fn window(&self) -> u32 {
+        self.window
+    }
+
+    /// 현재 라운드 길이로 사용하는 RTT
+    pub fn rtt(&self) -> Duration {
+        self.rtt
+    }
+
+    /// 종료 시점 윈도우로 환산한 전송 속도 (bytes/s). 속도 제어기 초기값으로 사용
+    pub fn rate(&self, chunk_bytes: usize) -> f64 {
+        self.window as f64 * chunk_bytes as f64 / self.rtt.as_secs_f64()
+    }
+
+    /// 청크 하나를 보낼 수 있을 때까지의 대기 시간.
+    /// 0이면 지금 보내도 되며 전송한 것으로 기록
+    pub fn next_send(&mut self, now: Instant) -> Duration {
+        if self.exited {
+            return Duration::ZERO;
+        }
+
+        let start = *self.round_start.get_or_insert(now);
+        if now.saturating_duration_since(start) >= self.rtt {
+            self.finish_round(now);
+            if self.exited {
+                return Duration::ZERO;
+            }
+        }
+        let start = self.round_start.unwrap_or(now);
+
+        // 윈도우 소진 시 다음 라운드까지 대기
+        if self.round_sent >= self.window {
+            return (start + self.rtt).saturating_duration_since(now);
+        }
+
+        // 윈도우를 라운드 전체에 균등 분산
+        let due = start + self.rtt.mul_f64(self.round_sent as f64 / self.window as f64);
+        let wait = due.saturating_duration_since(now);
+        if wait < PACING_GRANULARITY {
+            self.round_sent += 1;
+            return Duration::ZERO;
+        }
+        wait
+    }
+
+    /// 전달 확인 (수신자가 보고한 청크 수)
+    pub fn on_delivered(&mut self, chunks: u32) {
+        self.delivered_credit = self.delivered_credit.saturating_add(chunks);
+    }
+
+    /// RTT 샘플. 전송 시작 전에는 초기값을 대체하고 이후에는 더 짧은 값만 반영
+    pub fn on_rtt_sample(&mut self, rtt: Duration) {
+        if !rtt.is_zero() && (rtt < self.rtt || self.round_start.is_none()) {
+            self.rtt = rtt.max(Duration::from_micros(100));
+        }
+    }
+
+    /// 측정한 전달 속도(bytes/s)로 윈도우 재설정. 윈도우를 한 RTT에 그 속도가 되도록 맞추고
+    /// 초기 윈도우~상한 범위로 제한. 상한에 닿으면 슬로 스타트를 끝냄
+    pub fn on_capacity_estimate(&mut self, rate: f64, chunk_bytes: usize) {
+        if self.exited || !rate.is_finite() || rate <= 0.0 || chunk_bytes == 0 {
+            return;
+        }
+        let window = rate * self.rtt.as_secs_f64() / chunk_bytes as f64;
+        self.window = (window.min(u32::MAX as f64) as u32).clamp(self.window, self.max_window);
+        if self.window >= self.max_window {
+            self.exited = true;
+        }
+    }
+
+    /// 손실 신호 → 슬로 스타트 종료
+    pub fn on_loss(&mut self) {
+        self.exited = true;
+    }
+
+    fn finish_round(&mut self, now: Instant) {
+        let growth = self.delivered_credit.min(self.window);
+        self.delivered_credit -= growth;
+        self.window = self.window.saturating_add(growth).min(self.max_window);
+        if self.window >= self.max_window {
+            self.exited = true;
+        }
+        self.round_start = Some(now);
+        self.round_sent = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `[from, to)` 구간에서 1ms 간격으로 보낼 수 있는 만큼 전송한 청크 수
+    fn send_between(ss: &mut SlowStart, t0: Instant, from_ms: u64, to_ms: u64) -> u32 {
+        let mut sent = 0;
+        for ms in from_ms..to_ms {
+            let now = t0 + Duration::from_millis(ms);
+            while ss.next_send(now).is_zero() {
+                sent += 1;
+                if !ss.is_active() {
+                    return sent;
+                }
+            }
+        }
+        sent
+    }
+
+    #[test]
+    fn test_first_rtt_within_initial_window() {
+        let mut ss = SlowStart::with_window(10, 1000, Duration::from_millis(100));
+        let t0 = Instant::now();
+
+        // 첫 RTT: 초기 윈도우만, 한 번에 몰지 않고 분산
+        assert_eq!(send_between(&mut ss, t0, 0, 1), 1);
+        assert_eq!(send_between(&mut ss, t0, 1, 100), 9);
+
+        // 전달 확인이 없으면 윈도우 유지
+        assert_eq!(send_between(&mut ss, t0, 100, 200), 10);
+
+        // 전달 확인만큼 증가 (라운드당 최대 2배, 나머지는 이월)
+        ss.on_delivered(25);
+        assert_eq!(send_between(&mut ss, t0, 200, 300), 20);
+        ss.on_delivered(5);
+        assert_eq!(send_between(&mut ss, t0, 300, 400), 40);
+        assert_eq!(ss.window(), 40);
+
+        // 손실 → 종료 후 제한 없음
+        ss.on_loss();
+        assert!(!ss.is_active());
+        assert!(ss.next_send(t0 + Duration::from_millis(400)).is_zero());
+    }
+
+    #[test]
+    fn test_exits_at_max_window() {
+        let mut ss = SlowStart::with_window(10, 30, Duration::from_millis(10));
+        let t0 = Instant::now();
+        send_between(&mut ss, t0, 0, 10);
+        ss.on_delivered(10);
+        send_between(&mut ss, t0, 10, 20);
+        ss.on_delivered(20);
+
+        // 20 + 20 → 상한 30에서 종료
+        assert!(ss.next_send(t0 + Duration::from_millis(20)).is_zero());
+        assert!(!ss.is_active());
+        assert_eq!(ss.window(), 30);
+        assert_eq!(ss.rate(1000), 30.0 * 1000.0 / 0.010);
+
+        // 초기 윈도우 0은 비활성
+        assert!(!SlowStart::with_window(0, 30, Duration::from_millis(10)).is_active());
+    }
+}
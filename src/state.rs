@@ -0,0 +1,153 @@
+//! 연결 상태 머신
+//!
+//! Idle → Handshaking → Transferring → Draining → Closed
+//!
+//! - 허용되지 않은 전이는 `Error::InvalidStateTransition`
+//! - 같은 상태로의 전이는 무시 (멱등)
+//! - Closed에서 Handshaking/Transferring은 서버 재사용을 위한 새 연결
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::{Error, Result};
+
+/// 연결 상태
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ConnectionState {
+    /// 시작 전 또는 클라이언트 대기
+    Idle = 0,
+
+    /// Init/쿠키 교환 중
+    Handshaking = 1,
+
+    /// 데이터 전송 중
+    Transferring = 2,
+
+    /// 새 데이터 없이 남은 세그먼트 복구만 진행
+    Draining = 3,
+
+    /// 종료
+    Closed = 4,
+}
+
+impl ConnectionState {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => ConnectionState::Idle,
+            1 => ConnectionState::Handshaking,
+            2 => ConnectionState::Transferring,
+            3 => ConnectionState::Draining,
+            _ => ConnectionState::Closed,
+        }
+    }
+
+    /// `next`로 전이 가능한지 여부
+    pub fn can_transition_to(self, next: ConnectionState) -> bool {
+        use ConnectionState::*;
+
+        if self == next {
+            return true;
+        }
+
+        matches!(
+            (self, next),
+            (Idle, Handshaking)
+                | (Idle, Transferring)
+                | (Handshaking, Transferring)
+                | (Transferring, Handshaking)
+                | (Transferring, Draining)
+                | (Closed, Handshaking)
+                | (Closed, Transferring)
+                | (_, Closed)
+        )
+    }
+
+    /// 데이터/복구 메시지(NACK, SegmentComplete 등)를 처리하는 상태인지 여부
+    pub fn is_established(self) -> bool {
+        matches!(self, ConnectionState::Transferring | ConnectionState::Draining)
+    }
+}
+
+/// 여러 태스크가 공유하는 연결 상태
+#[derive(Debug)]
+pub struct ConnectionStateCell {
+    state: AtomicU8,
+}
+
+impl ConnectionStateCell {
+    pub fn new(state: ConnectionState) -> Self {
+        Self {
+            state: AtomicU8::new(state as u8),
+        }
+    }
+
+    /// 현재 상태
+    pub fn get(&self) -> ConnectionState {
+        ConnectionState::from_u8(self.state.load(Ordering::Acquire))
+    }
+
+    /// 상태 전이. 성공하면 이전 상태 반환
+    pub fn transition(&self, next: ConnectionState) -> Result<ConnectionState> {
+        let mut current = self.state.load(Ordering::Acquire);
+        loop {
+            let from = ConnectionState::from_u8(current);
+            if !from.can_transition_to(next) {
+                return Err(Error::InvalidStateTransition { from, to: next });
+            }
+            match self.state.compare_exchange_weak(
+                current,
+                next as u8,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return Ok(from),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// 검사 없이 상태 지정 (강제 종료 등). 이전 상태 반환
+    pub fn force(&self, next: ConnectionState) -> ConnectionState {
+        ConnectionState::from_u8(self.state.swap(next as u8, Ordering::AcqRel))
+    }
+}
+
+impl Default for ConnectionStateCell {
+    fn default() -> Self {
+        Self::new(ConnectionState::Idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guarded_transitions() {
+        let cell = ConnectionStateCell::default();
+        assert!(matches!(
+            cell.transition(ConnectionState::Draining),
+            Err(Error::InvalidStateTransition {
+                from: ConnectionState::Idle,
+                to: ConnectionState::Draining
+            })
+        ));
+
+        assert_eq!(
+            cell.transition(ConnectionState::Handshaking).unwrap(),
+            ConnectionState::Idle
+        );
+        assert!(cell.transition(ConnectionState::Transferring).is_ok());
+        assert!(cell.transition(ConnectionState::Transferring).is_ok());
+        assert!(cell.transition(ConnectionState::Draining).is_ok());
+
+        // 드레인 중에는 새 전송/핸드셰이크로 되돌아갈 수 없음
+        assert!(cell.transition(ConnectionState::Transferring).is_err());
+        assert!(cell.transition(ConnectionState::Handshaking).is_err());
+        assert_eq!(cell.get(), ConnectionState::Draining);
+
+        assert!(cell.transition(ConnectionState::Closed).is_ok());
+        assert!(cell.transition(ConnectionState::Draining).is_err());
+        assert!(!cell.get().is_established());
+    }
+}
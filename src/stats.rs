@@ -1,8 +1,28 @@
 //! 전송 통계
 
 use std::collections::VecDeque;
+use std::fmt::{self, Write};
 use std::time::{Duration, Instant};
 
+use tokio::sync::mpsc;
+
+use crate::chunk::SegmentId;
+
+/// 순서 역전 지표에 사용하는 최근 기록 수
+const REORDER_HISTORY: usize = 16;
+
+/// 최근 손실률 추정에 사용하는 완료 세그먼트 수
+const RECENT_LOSS_SEGMENTS: usize = 64;
+
+/// 첫 청크 지연을 기록하는 세그먼트 수 (시작 지연 분석용이라 앞쪽만 보관)
+pub const FIRST_CHUNK_HISTORY: usize = 4096;
+
+/// 유효 처리율을 재는 벽시계 창 (지금으로 끝남)
+const GOODPUT_WINDOW: Duration = Duration::from_secs(1);
+
+/// 전달 기록이 창보다 짧게 남았을 때 나누는 최소 구간 (한 번에 몰린 기록으로 튀지 않게)
+const GOODPUT_MIN_SPAN: Duration = Duration::from_millis(50);
+
 /// 청크 도착 기록
 #[derive(Debug, Clone, Copy)]
 struct ChunkArrival {
@@ -30,6 +50,12 @@ pub struct NicStats {
     /// 총 수신 바이트
     pub total_bytes: u64,
 
+    /// 최근 고유 데이터 전달 기록 (재전송/중복 제외, 유효 처리율용)
+    deliveries: VecDeque<ChunkArrival>,
+
+    /// 전달이 확인된 고유 데이터 바이트
+    pub delivered_bytes: u64,
+
     /// 손실된 청크 수 (NACK 기반)
     pub lost_chunks: u64,
 
@@ -39,6 +65,15 @@ pub struct NicStats {
     /// RTT 샘플 (마이크로초)
     rtt_samples: VecDeque<u64>,
 
+    /// 순서가 뒤바뀌어 도착한 청크 수 (손실 아님)
+    pub reordered_chunks: u64,
+
+    /// 최근 순서 역전 기록 (밀려난 청크 수, 늦게 도착한 시간)
+    reorder_samples: VecDeque<(u32, Duration)>,
+
+    /// 추정치 재시작 시점의 (총 청크, 손실 청크). 손실률은 이후 기록만 셈
+    loss_baseline: (u64, u64),
+
     /// 마지막 업데이트 시간
     last_update: Instant,
 }
@@ -51,9 +86,14 @@ impl NicStats {
             window_size,
             total_chunks: 0,
             total_bytes: 0,
+            deliveries: VecDeque::with_capacity(window_size),
+            delivered_bytes: 0,
             lost_chunks: 0,
             duplicate_chunks: 0,
             rtt_samples: VecDeque::with_capacity(10),
+            reordered_chunks: 0,
+            reorder_samples: VecDeque::with_capacity(REORDER_HISTORY),
+            loss_baseline: (0, 0),
             last_update: Instant::now(),
         }
     }
@@ -77,6 +117,19 @@ impl NicStats {
         self.last_update = now;
     }
 
+    /// 고유 데이터 전달 기록 (같은 데이터는 한 번만)
+    pub fn record_delivery(&mut self, size: usize) {
+        if self.deliveries.len() >= self.window_size {
+            self.deliveries.pop_front();
+        }
+        self.deliveries.push_back(ChunkArrival {
+            timestamp: Instant::now(),
+            size,
+            nic_id: self.nic_id,
+        });
+        self.delivered_bytes += size as u64;
+    }
+
     /// 손실 기록
     pub fn record_loss(&mut self, count: u64) {
         self.lost_chunks += count;
@@ -95,6 +148,29 @@ impl NicStats {
         self.rtt_samples.push_back(rtt_us);
     }
 
+    /// 순서 역전 기록. `displacement`는 먼저 도착한 더 뒤 청크와의 ID 차이
+    pub fn record_reorder(&mut self, displacement: u32, delay: Duration) {
+        if self.reorder_samples.len() >= REORDER_HISTORY {
+            self.reorder_samples.pop_front();
+        }
+        self.reorder_samples.push_back((displacement, delay));
+        self.reordered_chunks += 1;
+    }
+
+    /// 최근 순서 역전 정도 (청크 수, 0이면 역전 없음)
+    pub fn reorder_degree(&self) -> u32 {
+        self.reorder_samples.iter().map(|&(d, _)| d).max().unwrap_or(0)
+    }
+
+    /// 최근 순서 역전 청크가 늦게 도착한 최대 시간
+    pub fn reorder_delay(&self) -> Duration {
+        self.reorder_samples
+            .iter()
+            .map(|&(_, delay)| delay)
+            .max()
+            .unwrap_or(Duration::ZERO)
+    }
+
     /// 청크 도착률 계산 (chunks/sec)
     pub fn chunk_arrival_rate(&self) -> f64 {
         if self.arrivals.len() < 2 {
@@ -114,29 +190,42 @@ impl NicStats {
 
     /// 바이트 처리율 계산 (bytes/sec)
     pub fn throughput(&self) -> f64 {
-        if self.arrivals.len() < 2 {
-            return 0.0;
-        }
-
-        let first = self.arrivals.front().unwrap().timestamp;
-        let last = self.arrivals.back().unwrap().timestamp;
-        let duration = last.duration_since(first);
+        window_rate(&self.arrivals)
+    }
 
-        if duration.is_zero() {
+    /// 유효 처리율 (goodput, bytes/sec): 전달이 확인된 고유 데이터만 셈
+    ///
+    /// 전달은 세그먼트 완료 보고 때 한꺼번에 기록되므로 기록 사이 간격이 아니라 지금으로 끝나는
+    /// `GOODPUT_WINDOW`로 나눔. 기록 한도가 차서 창보다 짧게 남았으면 남은 구간으로
+    pub fn goodput(&self) -> f64 {
+        let Some(first) = self.deliveries.front() else {
             return 0.0;
-        }
-
-        let total_size: usize = self.arrivals.iter().map(|a| a.size).sum();
-        total_size as f64 / duration.as_secs_f64()
+        };
+        let now = Instant::now();
+        let span = match self.deliveries.len() >= self.window_size {
+            true => now
+                .duration_since(first.timestamp)
+                .clamp(GOODPUT_MIN_SPAN, GOODPUT_WINDOW),
+            false => GOODPUT_WINDOW,
+        };
+        let bytes: usize = self
+            .deliveries
+            .iter()
+            .filter(|delivery| now.duration_since(delivery.timestamp) <= span)
+            .map(|delivery| delivery.size)
+            .sum();
+        bytes as f64 / span.as_secs_f64()
     }
 
-    /// 손실률 계산
+    /// 손실률 계산 (`restart_estimates` 이후 기록 기준)
     pub fn loss_rate(&self) -> f64 {
-        let total = self.total_chunks + self.lost_chunks;
+        let (base_chunks, base_lost) = self.loss_baseline;
+        let lost = self.lost_chunks - base_lost;
+        let total = self.total_chunks - base_chunks + lost;
         if total == 0 {
             return 0.0;
         }
-        self.lost_chunks as f64 / total as f64
+        lost as f64 / total as f64
     }
 
     /// 평균 RTT 계산 (마이크로초)
@@ -147,18 +236,152 @@ impl NicStats {
         Some(self.rtt_samples.iter().sum::<u64>() / self.rtt_samples.len() as u64)
     }
 
+    /// 같은 NIC의 다른 통계를 합침 (수신 조립 샤드별 통계 합산용).
+    /// 도착/RTT/순서 역전 기록은 시각 순으로 합친 뒤 최근 것만 남김
+    pub fn merge(&mut self, other: &NicStats) {
+        self.arrivals.extend(other.arrivals.iter().copied());
+        self.arrivals.make_contiguous().sort_by_key(|arrival| arrival.timestamp);
+        while self.arrivals.len() > self.window_size {
+            self.arrivals.pop_front();
+        }
+        self.deliveries.extend(other.deliveries.iter().copied());
+        self.deliveries.make_contiguous().sort_by_key(|delivery| delivery.timestamp);
+        while self.deliveries.len() > self.window_size {
+            self.deliveries.pop_front();
+        }
+        self.rtt_samples.extend(other.rtt_samples.iter().copied());
+        while self.rtt_samples.len() > 10 {
+            self.rtt_samples.pop_front();
+        }
+        self.reorder_samples.extend(other.reorder_samples.iter().copied());
+        while self.reorder_samples.len() > REORDER_HISTORY {
+            self.reorder_samples.pop_front();
+        }
+        self.total_chunks += other.total_chunks;
+        self.total_bytes += other.total_bytes;
+        self.delivered_bytes += other.delivered_bytes;
+        self.lost_chunks += other.lost_chunks;
+        self.duplicate_chunks += other.duplicate_chunks;
+        self.reordered_chunks += other.reordered_chunks;
+        self.loss_baseline.0 += other.loss_baseline.0;
+        self.loss_baseline.1 += other.loss_baseline.1;
+        self.last_update = self.last_update.max(other.last_update);
+    }
+
+    /// 경로 전환 후 추정치 재시작: 처리율/RTT/순서 역전 창을 비우고 손실률을 새로 셈.
+    /// 누적 카운터는 그대로 둠
+    pub fn restart_estimates(&mut self) {
+        self.arrivals.clear();
+        self.deliveries.clear();
+        self.rtt_samples.clear();
+        self.reorder_samples.clear();
+        self.loss_baseline = (self.total_chunks, self.lost_chunks);
+    }
+
     /// 통계 리셋
     pub fn reset(&mut self) {
         self.arrivals.clear();
         self.total_chunks = 0;
         self.total_bytes = 0;
+        self.deliveries.clear();
+        self.delivered_bytes = 0;
         self.lost_chunks = 0;
         self.duplicate_chunks = 0;
         self.rtt_samples.clear();
+        self.reordered_chunks = 0;
+        self.reorder_samples.clear();
+        self.loss_baseline = (0, 0);
         self.last_update = Instant::now();
     }
 }
 
+/// 기록 창의 처음과 끝 사이 바이트율 (기록이 둘 미만이거나 같은 시각이면 0)
+fn window_rate(window: &VecDeque<ChunkArrival>) -> f64 {
+    let (Some(first), Some(last)) = (window.front(), window.back()) else {
+        return 0.0;
+    };
+    let duration = last.timestamp.duration_since(first.timestamp);
+    if window.len() < 2 || duration.is_zero() {
+        return 0.0;
+    }
+    let total_size: usize = window.iter().map(|a| a.size).sum();
+    total_size as f64 / duration.as_secs_f64()
+}
+
+/// 내부 채널 하나의 적재량 (조회 시점 값)
+///
+/// 송신 큐가 차 있으면 소켓 송신이, 수신/명령 큐가 차 있으면 처리 태스크가 병목
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueDepth {
+    /// 채널 이름 (`recv`, `data`, `dispatch` 등)
+    pub name: &'static str,
+    /// 같은 이름의 채널이 여럿일 때 순번 (워커 큐), 아니면 0
+    pub index: usize,
+    /// 대기 중인 항목 수
+    pub len: usize,
+    /// 채널 용량
+    pub capacity: usize,
+}
+
+impl QueueDepth {
+    /// 채널 송신측에서 현재 적재량 읽기
+    pub fn of<T>(name: &'static str, index: usize, tx: &mpsc::Sender<T>) -> Self {
+        let capacity = tx.max_capacity();
+        Self {
+            name,
+            index,
+            len: capacity - tx.capacity(),
+            capacity,
+        }
+    }
+
+    /// 채널을 붙잡지 않는 약한 송신측에서 읽기 (채널이 닫혔으면 None)
+    pub fn of_weak<T>(name: &'static str, index: usize, tx: &mpsc::WeakSender<T>) -> Option<Self> {
+        tx.upgrade().map(|tx| Self::of(name, index, &tx))
+    }
+
+    /// 용량 대비 적재율 (0.0 ~ 1.0)
+    pub fn utilization(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.len as f64 / self.capacity as f64
+    }
+}
+
+impl fmt::Display for QueueDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.index > 0 {
+            write!(f, "[{}]", self.index)?;
+        }
+        write!(f, " {}/{}", self.len, self.capacity)
+    }
+}
+
+/// 큐 적재량을 Prometheus 텍스트 형식 게이지로 출력
+/// (`sfp_queue_length`, `sfp_queue_capacity`, 레이블 `queue`/`index`)
+pub fn queue_depth_metrics(depths: &[QueueDepth]) -> String {
+    let mut out = String::new();
+    let mut gauge = |metric: &str, help: &str, value: fn(&QueueDepth) -> usize| {
+        let _ = writeln!(out, "# HELP {} {}", metric, help);
+        let _ = writeln!(out, "# TYPE {} gauge", metric);
+        for depth in depths {
+            let _ = writeln!(
+                out,
+                "{}{{queue=\"{}\",index=\"{}\"}} {}",
+                metric,
+                depth.name,
+                depth.index,
+                value(depth)
+            );
+        }
+    };
+    gauge("sfp_queue_length", "내부 채널에 대기 중인 항목 수", |d| d.len);
+    gauge("sfp_queue_capacity", "내부 채널 용량", |d| d.capacity);
+    out
+}
+
 /// 전체 전송 통계
 #[derive(Debug, Clone)]
 pub struct TransferStats {
@@ -180,9 +403,51 @@ pub struct TransferStats {
     /// 재전송 청크 수
     pub retransmitted_chunks: u64,
 
+    /// 피드백 없는 세그먼트에 보낸 꼬리 손실 탐침 수 (`retransmitted_chunks`에 포함)
+    pub tail_probes: u64,
+
+    /// PMTU 블랙홀을 확인하려고 보낸 탐침 수
+    pub pmtu_probes: u64,
+
+    /// 재전송 한도(`Config::max_chunk_retransmits`)를 넘겨 포기한 세그먼트 수
+    pub abandoned_segments: u64,
+
+    /// 미리 읽은 세그먼트가 준비되지 않아 송신이 소스를 기다린 횟수 (`send_stream_prefetched`)
+    pub readahead_stalls: u64,
+
     /// 중복 전송 청크 수
     pub redundant_chunks: u64,
 
+    /// 청크 여러 개를 묶어 보낸 데이터그램 수 (송신측, `Config::coalesce_chunks`)
+    pub coalesced_datagrams: u64,
+
+    /// 잃어버린 원본 대신 들어간 중복 전송 사본 수 (수신측)
+    pub redundant_useful: u64,
+
+    /// 원본이 이미 있었거나 나중에 도착해 필요 없었던 중복 전송 사본 수 (수신측)
+    pub redundant_wasted: u64,
+
+    /// 조립 소프트 마감(`assembly_soft_deadline_ms`)을 넘겨 NACK을 격상한 세그먼트 수 (수신측)
+    pub escalated_segments: u64,
+
+    /// 완료 세그먼트 보관 창(`delivery_window_segments`)이 차서 받지 않은 새 세그먼트 청크 수 (수신측)
+    pub deferred_admissions: u64,
+
+    /// 소비자가 완료 채널을 닫았거나 정지로 넘기지 못하고 버린 완료 세그먼트 수 (수신측)
+    pub dropped_deliveries: u64,
+
+    /// 완료된 세그먼트에 늦게 도착해 버린 청크 수 (수신측, `redundant_chunks`에 포함)
+    pub late_chunks: u64,
+
+    /// 매니페스트의 세그먼트 다이제스트와 맞지 않아 다시 받은 세그먼트 수 (수신측)
+    pub digest_mismatches: u64,
+
+    /// 세그먼트에 정해진 총 청크 수/크기와 맞지 않아 버린 청크 수 (수신측, 프로토콜 버그나 위조)
+    pub inconsistent_chunks: u64,
+
+    /// 도달성을 확인하고 새 주소로 옮긴 연결 이전 횟수 (송신측)
+    pub migrations: u64,
+
     /// NIC별 통계
     pub nic_stats: Vec<NicStats>,
 
@@ -191,6 +456,39 @@ pub struct TransferStats {
 
     /// 총 NACK 수
     pub total_nacks: u64,
+
+    /// FEC 패리티로 복원한 청크 수
+    pub fec_recovered_chunks: u64,
+
+    /// 완료 보고된 세그먼트에 송신한 청크 수 (송신측)
+    pub reported_sent_chunks: u64,
+
+    /// 수신자가 SegmentComplete로 보고한 수신 청크 수
+    pub reported_received_chunks: u64,
+
+    /// 수신자가 SegmentComplete로 보고한 중복 청크 수
+    pub reported_duplicate_chunks: u64,
+
+    /// 보냈지만 완료 보고/타임아웃으로 정리되지 않은 바이트 (송신측, 조회 시점 값)
+    pub bytes_in_flight: u64,
+
+    /// 내부 채널 적재량 (통계 갱신 시점 값)
+    pub queue_depths: Vec<QueueDepth>,
+
+    /// 세그먼트별 첫 청크 도착 시각 (`start_time` 기준, 수신측, 앞쪽 `FIRST_CHUNK_HISTORY`개)
+    pub first_chunk_latency: Vec<(SegmentId, Duration)>,
+
+    /// 첫 세그먼트 조립 완료 시각 (`start_time` 기준, 수신측)
+    pub time_to_first_segment: Option<Duration>,
+
+    /// 네트워크 전환으로 추정치를 재시작한 횟수
+    pub network_changes: u64,
+
+    /// 청크 대신 균일 세그먼트 표시로 보낸(송신측) 또는 펼친(수신측) 세그먼트 수
+    pub sparse_segments: u64,
+
+    /// 최근 완료 보고된 세그먼트의 (송신 청크, 손실 청크)
+    recent_segments: VecDeque<(u32, u32)>,
 }
 
 impl TransferStats {
@@ -202,13 +500,164 @@ impl TransferStats {
             total_bytes: 0,
             total_chunks: 0,
             retransmitted_chunks: 0,
+            tail_probes: 0,
+            pmtu_probes: 0,
+            abandoned_segments: 0,
+            readahead_stalls: 0,
             redundant_chunks: 0,
+            coalesced_datagrams: 0,
+            redundant_useful: 0,
+            redundant_wasted: 0,
+            escalated_segments: 0,
+            deferred_admissions: 0,
+            dropped_deliveries: 0,
+            late_chunks: 0,
+            digest_mismatches: 0,
+            inconsistent_chunks: 0,
+            migrations: 0,
+            network_changes: 0,
+            sparse_segments: 0,
             nic_stats: (0..nic_count)
                 .map(|i| NicStats::new(i as u8, window_size))
                 .collect(),
             last_nack_time: None,
             total_nacks: 0,
+            fec_recovered_chunks: 0,
+            reported_sent_chunks: 0,
+            reported_received_chunks: 0,
+            reported_duplicate_chunks: 0,
+            bytes_in_flight: 0,
+            queue_depths: Vec::new(),
+            first_chunk_latency: Vec::new(),
+            time_to_first_segment: None,
+            recent_segments: VecDeque::with_capacity(RECENT_LOSS_SEGMENTS),
+        }
+    }
+
+    /// 다른 통계를 합침 (수신 조립 샤드별 통계를 수신자 통계 하나로)
+    ///
+    /// 카운터는 더하고, 시작/첫 세그먼트 시각은 이른 쪽, 마지막 NACK 시각은 늦은 쪽을 씀
+    pub fn merge(&mut self, other: &TransferStats) {
+        self.start_time = self.start_time.min(other.start_time);
+        self.total_segments += other.total_segments;
+        self.completed_segments += other.completed_segments;
+        self.total_bytes += other.total_bytes;
+        self.total_chunks += other.total_chunks;
+        self.retransmitted_chunks += other.retransmitted_chunks;
+        self.tail_probes += other.tail_probes;
+        self.pmtu_probes += other.pmtu_probes;
+        self.abandoned_segments += other.abandoned_segments;
+        self.readahead_stalls += other.readahead_stalls;
+        self.redundant_chunks += other.redundant_chunks;
+        self.coalesced_datagrams += other.coalesced_datagrams;
+        self.redundant_useful += other.redundant_useful;
+        self.redundant_wasted += other.redundant_wasted;
+        self.escalated_segments += other.escalated_segments;
+        self.deferred_admissions += other.deferred_admissions;
+        self.dropped_deliveries += other.dropped_deliveries;
+        self.late_chunks += other.late_chunks;
+        self.digest_mismatches += other.digest_mismatches;
+        self.inconsistent_chunks += other.inconsistent_chunks;
+        self.migrations += other.migrations;
+        self.network_changes += other.network_changes;
+        self.sparse_segments += other.sparse_segments;
+        for (nic, other_nic) in self.nic_stats.iter_mut().zip(&other.nic_stats) {
+            nic.merge(other_nic);
+        }
+        self.last_nack_time = self.last_nack_time.max(other.last_nack_time);
+        self.total_nacks += other.total_nacks;
+        self.fec_recovered_chunks += other.fec_recovered_chunks;
+        self.reported_sent_chunks += other.reported_sent_chunks;
+        self.reported_received_chunks += other.reported_received_chunks;
+        self.reported_duplicate_chunks += other.reported_duplicate_chunks;
+        self.bytes_in_flight += other.bytes_in_flight;
+        self.queue_depths.extend_from_slice(&other.queue_depths);
+        self.first_chunk_latency.extend_from_slice(&other.first_chunk_latency);
+        self.first_chunk_latency.sort_by_key(|&(_, latency)| latency);
+        self.first_chunk_latency.truncate(FIRST_CHUNK_HISTORY);
+        let firsts = (self.time_to_first_segment, other.time_to_first_segment);
+        self.time_to_first_segment = match firsts {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.recent_segments.extend(other.recent_segments.iter().copied());
+        while self.recent_segments.len() > RECENT_LOSS_SEGMENTS {
+            self.recent_segments.pop_front();
+        }
+    }
+
+    /// 세그먼트 완료 보고 반영 (송신측)
+    pub fn record_segment_report(&mut self, sent: u32, received: u32, duplicates: u32) {
+        self.reported_sent_chunks += sent as u64;
+        self.reported_received_chunks += received as u64;
+        self.reported_duplicate_chunks += duplicates as u64;
+
+        if self.recent_segments.len() >= RECENT_LOSS_SEGMENTS {
+            self.recent_segments.pop_front();
         }
+        self.recent_segments
+            .push_back((sent, sent.saturating_sub(received)));
+    }
+
+    /// 최근 손실 기록을 비움 (네트워크 전환 후 이전 경로의 손실로 중복률을 정하지 않도록)
+    pub fn restart_loss_window(&mut self) {
+        self.recent_segments.clear();
+    }
+
+    /// 최근 `RECENT_LOSS_SEGMENTS`개 완료 세그먼트 기준 손실률.
+    /// 전체 평균과 달리 짧은 손실 구간에도 바로 반응 (중복/FEC 판단용)
+    pub fn recent_loss_rate(&self) -> f64 {
+        let (sent, lost) = self
+            .recent_segments
+            .iter()
+            .fold((0u64, 0u64), |(s, l), &(sent, lost)| (s + sent as u64, l + lost as u64));
+        if sent == 0 {
+            return 0.0;
+        }
+        lost as f64 / sent as f64
+    }
+
+    /// 최근 완료 세그먼트별 손실률의 백분위수 (`percentile`은 0.0~1.0)
+    pub fn recent_loss_percentile(&self, percentile: f64) -> f64 {
+        let mut rates: Vec<f64> = self
+            .recent_segments
+            .iter()
+            .filter(|&&(sent, _)| sent > 0)
+            .map(|&(sent, lost)| lost as f64 / sent as f64)
+            .collect();
+        if rates.is_empty() {
+            return 0.0;
+        }
+        rates.sort_by(f64::total_cmp);
+        let rank = (percentile.clamp(0.0, 1.0) * (rates.len() - 1) as f64).round() as usize;
+        rates[rank]
+    }
+
+    /// 수신자 보고 기준 손실률 (완료된 세그먼트 한정)
+    pub fn reported_loss_rate(&self) -> f64 {
+        if self.reported_sent_chunks == 0 {
+            return 0.0;
+        }
+        let received = self.reported_received_chunks.min(self.reported_sent_chunks);
+        1.0 - received as f64 / self.reported_sent_chunks as f64
+    }
+
+    /// 세그먼트의 첫 청크 도착 기록 (수신측)
+    pub fn record_first_chunk(&mut self, segment_id: SegmentId) {
+        if self.first_chunk_latency.len() < FIRST_CHUNK_HISTORY {
+            self.first_chunk_latency.push((segment_id, self.elapsed()));
+        }
+    }
+
+    /// 세그먼트 조립 완료 기록 (수신측, 첫 완료 시각만 보관)
+    pub fn record_segment_completed(&mut self) {
+        self.completed_segments += 1;
+        self.time_to_first_segment.get_or_insert_with(|| self.start_time.elapsed());
+    }
+
+    /// 첫 청크(첫 바이트)가 도착하기까지 걸린 시간
+    pub fn time_to_first_byte(&self) -> Option<Duration> {
+        self.first_chunk_latency.iter().map(|&(_, latency)| latency).min()
     }
 
     /// 경과 시간
@@ -305,3 +754,164 @@ impl Default for TransferStats {
         Self::new(1, 100)
     }
 }
+
+/// 검증 전용 수신 결과 (데이터는 보관하지 않음)
+#[derive(Debug, Clone)]
+pub struct TransferReport {
+    /// 수신한 세그먼트 수
+    pub segments: u64,
+
+    /// 수신한 바이트 수
+    pub bytes: u64,
+
+    /// 첫 세그먼트 대기부터 마지막 세그먼트까지 소요 시간
+    pub elapsed: Duration,
+
+    /// 세그먼트 다이제스트 합 (순서 무관, `expected_digest`와 비교)
+    pub digest: u64,
+
+    /// 영구 실패로 받지 못한 세그먼트 구간 (`[start, end)`, 오름차순)
+    pub failed_segments: Vec<(SegmentId, SegmentId)>,
+
+    /// 받지 못한 바이트 구간 (세그먼트를 ID 순서로 이어 붙인 오프셋, `[start, end)`, 오름차순)
+    pub missing_bytes: Vec<(u64, u64)>,
+
+    /// 모든 세그먼트를 받았는지 (false면 `failed_segments`/`missing_bytes`가 빈 곳)
+    pub complete: bool,
+
+    /// 수신자 전송 통계
+    pub stats: TransferStats,
+}
+
+impl TransferReport {
+    /// 모든 세그먼트를 받았는지
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// 도착한 중복 전송 사본 중 손실을 실제로 메운 비율 (사본이 없었으면 None)
+    ///
+    /// 낮으면 `base_redundancy_ratio`가 대역폭만 쓰고 있다는 뜻. 세그먼트 완료 뒤에 도착한
+    /// 원본은 보지 못하므로 그만큼 유효 쪽으로 약간 치우침
+    pub fn redundancy_effectiveness(&self) -> Option<f64> {
+        let useful = self.stats.redundant_useful;
+        let total = useful + self.stats.redundant_wasted;
+        (total > 0).then(|| useful as f64 / total as f64)
+    }
+
+    /// 세그먼트 ID들을 연속 구간(`[start, end)`)으로 묶음
+    pub fn segment_ranges(ids: impl IntoIterator<Item = SegmentId>) -> Vec<(SegmentId, SegmentId)> {
+        let mut ids: Vec<SegmentId> = ids.into_iter().collect();
+        ids.sort_unstable();
+        ids.dedup();
+        let mut ranges: Vec<(SegmentId, SegmentId)> = Vec::new();
+        for id in ids {
+            match ranges.last_mut() {
+                Some((_, end)) if *end == id => *end += 1,
+                _ => ranges.push((id, id + 1)),
+            }
+        }
+        ranges
+    }
+
+    /// 세그먼트를 ID 순서로 이어 붙였을 때 빠진 세그먼트가 차지하는 바이트 구간
+    /// (`[start, end)`, 맞닿은 구간은 합침)
+    ///
+    /// `segments`: (세그먼트 ID, 길이, 받았는지), 순서 무관
+    pub fn missing_byte_ranges(
+        segments: impl IntoIterator<Item = (SegmentId, u64, bool)>,
+    ) -> Vec<(u64, u64)> {
+        let mut segments: Vec<(SegmentId, u64, bool)> = segments.into_iter().collect();
+        segments.sort_unstable_by_key(|&(id, _, _)| id);
+        let mut offset = 0;
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+        for (_, len, received) in segments {
+            if !received && len > 0 {
+                match ranges.last_mut() {
+                    Some((_, end)) if *end == offset => *end += len,
+                    _ => ranges.push((offset, offset + len)),
+                }
+            }
+            offset += len;
+        }
+        ranges
+    }
+
+    /// 세그먼트 하나의 다이제스트 (세그먼트 ID + 데이터 CRC32)
+    pub fn segment_digest(segment_id: SegmentId, data: &[u8]) -> u32 {
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&segment_id.to_le_bytes());
+        hasher.update(data);
+        hasher.finalize()
+    }
+
+    /// 송신측 원본으로 계산한 기대 다이제스트
+    pub fn expected_digest<'a>(segments: impl IntoIterator<Item = (SegmentId, &'a [u8])>) -> u64 {
+        segments.into_iter().fold(0u64, |acc, (id, data)| {
+            acc.wrapping_add(Self::segment_digest(id, data) as u64)
+        })
+    }
+
+    /// 실효 처리율 (bytes/sec)
+    pub fn throughput(&self) -> f64 {
+        let elapsed = self.elapsed.as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 / elapsed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loss_burst_raises_recent_loss_rate() {
+        let mut stats = TransferStats::default();
+        for _ in 0..1000 {
+            stats.record_segment_report(10, 10, 0);
+        }
+        assert_eq!(stats.recent_loss_rate(), 0.0);
+
+        // 짧은 손실 구간: 세그먼트 20개에서 청크 절반 손실
+        for _ in 0..20 {
+            stats.record_segment_report(10, 5, 0);
+        }
+        let lifetime = stats.reported_loss_rate();
+        let recent = stats.recent_loss_rate();
+        assert!(lifetime < 0.01, "lifetime {}", lifetime);
+        assert!(recent > 0.15, "recent {}", recent);
+        assert_eq!(stats.recent_loss_percentile(0.9), 0.5);
+        assert_eq!(stats.recent_loss_percentile(0.0), 0.0);
+
+        // 손실이 멎으면 윈도우가 지나면서 다시 0
+        for _ in 0..RECENT_LOSS_SEGMENTS {
+            stats.record_segment_report(10, 10, 0);
+        }
+        assert_eq!(stats.recent_loss_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_queue_depths_exported_as_gauges() {
+        let (tx, _rx) = mpsc::channel::<u8>(8);
+        for value in 0..6 {
+            tx.try_send(value).unwrap();
+        }
+        let data = QueueDepth::of("data", 0, &tx);
+        assert_eq!((data.len, data.capacity), (6, 8));
+        assert_eq!(data.utilization(), 0.75);
+        assert_eq!(data.to_string(), "data 6/8");
+
+        // 닫힌 채널은 보고하지 않음
+        let weak = tx.downgrade();
+        assert!(QueueDepth::of_weak("data", 1, &weak).is_some());
+        drop(tx);
+        assert!(QueueDepth::of_weak("data", 1, &weak).is_none());
+
+        let text = queue_depth_metrics(&[data]);
+        assert!(text.contains("# TYPE sfp_queue_length gauge\n"));
+        assert!(text.contains("sfp_queue_length{queue=\"data\",index=\"0\"} 6\n"));
+        assert!(text.contains("sfp_queue_capacity{queue=\"data\",index=\"0\"} 8\n"));
+    }
+}
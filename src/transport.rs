@@ -0,0 +1,469 @@
+//! 데이터그램 전송 추상화
+//!
+//! 송신자/수신자는 `DatagramSocket`만 사용하므로 실제 UDP 외에
+//! 네트워크 시뮬레이터나 다른 전송 계층(DTLS, 인프로세스 채널 등)으로 교체 가능
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+use socket2::{sa_family_t, socklen_t, SockAddr, SockAddrStorage, SockRef};
+use tokio::net::UdpSocket;
+
+/// TOS/Traffic Class 바이트에서 DSCP 위치 (하위 2비트는 ECN)
+const DSCP_SHIFT: u32 = 2;
+
+/// TOS/Traffic Class 바이트의 ECN 비트
+const ECN_MASK: u32 = 0b11;
+
+/// 주소 지정 데이터그램 소켓
+pub trait DatagramSocket: Send + Sync + 'static {
+    /// `target`으로 데이터그램 하나 전송
+    fn send_to(
+        &self,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> impl Future<Output = io::Result<usize>> + Send;
+
+    /// 데이터그램 하나 수신 (길이, 출발지)
+    fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send;
+
+    /// 바인딩된 로컬 주소
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    /// 커널 송수신 버퍼 크기 변경 (버퍼가 없는 전송 계층은 무시)
+    fn set_buffer_sizes(&self, recv: usize, send: usize) -> io::Result<()> {
+        let _ = (recv, send);
+        Ok(())
+    }
+
+    /// 상대 하나로 고정해 이후 그 주소로의 전송을 빠른 경로로 보냄.
+    /// 지원하면 `true` (기본: 고정하지 않고 `false`)
+    fn connect_peer(&self, target: SocketAddr) -> impl Future<Output = io::Result<bool>> + Send {
+        let _ = target;
+        std::future::ready(Ok(false))
+    }
+
+    /// `connect_peer` 해제 (다시 모든 주소에서 수신)
+    fn disconnect_peer(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl DatagramSocket for UdpSocket {
+    fn send_to(
+        &self,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+        UdpSocket::send_to(self, buf, target)
+    }
+
+    fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send {
+        UdpSocket::recv_from(self, buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+
+    /// 커널이 `rmem_max`/`wmem_max` 등으로 더 작게 잡을 수 있음
+    fn set_buffer_sizes(&self, recv: usize, send: usize) -> io::Result<()> {
+        let sock = SockRef::from(self);
+        sock.set_recv_buffer_size(recv)?;
+        sock.set_send_buffer_size(send)
+    }
+}
+
+impl<T: DatagramSocket> DatagramSocket for Arc<T> {
+    fn send_to(
+        &self,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+        (**self).send_to(buf, target)
+    }
+
+    fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send {
+        (**self).recv_from(buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        (**self).local_addr()
+    }
+
+    fn set_buffer_sizes(&self, recv: usize, send: usize) -> io::Result<()> {
+        (**self).set_buffer_sizes(recv, send)
+    }
+
+    fn connect_peer(&self, target: SocketAddr) -> impl Future<Output = io::Result<bool>> + Send {
+        (**self).connect_peer(target)
+    }
+
+    fn disconnect_peer(&self) -> io::Result<()> {
+        (**self).disconnect_peer()
+    }
+}
+
+/// 단일 상대 전송용 UDP 소켓 (`Config::connect_socket`)
+///
+/// 그냥 `UdpSocket`은 `connect_peer`를 지원하지 않아 여러 세션이 공유해도 안전하고,
+/// 이 타입은 상대가 정해지면 `connect()`해 그 주소로의 `send_to`를 `send`로 바꿈.
+/// 고정된 상대가 아닌 주소로는 계속 `send_to`
+#[derive(Debug)]
+pub struct PeerUdpSocket {
+    socket: UdpSocket,
+    peer: RwLock<Option<SocketAddr>>,
+    /// 연결 전 바인딩 주소 (와일드카드 주소 그대로, 연결 해제로 잃은 포트를 되찾을 때 사용)
+    bound: Option<SocketAddr>,
+}
+
+impl PeerUdpSocket {
+    pub fn new(socket: UdpSocket) -> Self {
+        let bound = socket.local_addr().ok();
+        Self {
+            socket,
+            peer: RwLock::new(None),
+            bound,
+        }
+    }
+
+    /// 연결된 상대 주소
+    pub fn peer(&self) -> Option<SocketAddr> {
+        *self.peer.read()
+    }
+
+    /// 안쪽 UDP 소켓
+    pub fn get_ref(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl DatagramSocket for PeerUdpSocket {
+    fn send_to(
+        &self,
+        buf: &[u8],
+        target: SocketAddr,
+    ) -> impl Future<Output = io::Result<usize>> + Send {
+        let connected = *self.peer.read() == Some(target);
+        async move {
+            if connected {
+                self.socket.send(buf).await
+            } else {
+                self.socket.send_to(buf, target).await
+            }
+        }
+    }
+
+    fn recv_from(
+        &self,
+        buf: &mut [u8],
+    ) -> impl Future<Output = io::Result<(usize, SocketAddr)>> + Send {
+        self.socket.recv_from(buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    fn set_buffer_sizes(&self, recv: usize, send: usize) -> io::Result<()> {
+        self.socket.set_buffer_sizes(recv, send)
+    }
+
+    async fn connect_peer(&self, target: SocketAddr) -> io::Result<bool> {
+        self.socket.connect(target).await?;
+        *self.peer.write() = Some(target);
+        Ok(true)
+    }
+
+    /// `AF_UNSPEC` 주소로 `connect()`해 연결 해제. 일부 플랫폼은 해제하면서도 에러를
+    /// 돌려주므로 결과 대신 상대 주소가 사라졌는지로 판단. 임시 포트로 바인딩한 소켓은
+    /// 해제하면서 포트를 잃으므로(Linux) 그때만 처음 바인딩한 주소로 다시 바인딩.
+    /// 와일드카드로 바인딩한 소켓은 연결 중에만 구체 주소가 보이므로 주소는 비교하지 않음
+    fn disconnect_peer(&self) -> io::Result<()> {
+        if self.peer.write().take().is_none() {
+            return Ok(());
+        }
+        // SAFETY: 0으로 채운 저장소는 주소 체계가 AF_UNSPEC(0)이고 길이는 그 필드 크기
+        let unspec = unsafe {
+            SockAddr::new(
+                SockAddrStorage::zeroed(),
+                std::mem::size_of::<sa_family_t>() as socklen_t,
+            )
+        };
+        let sock = SockRef::from(&self.socket);
+        let result = sock.connect(&unspec);
+        match self.socket.peer_addr() {
+            Err(e) if e.kind() == io::ErrorKind::NotConnected => {
+                match self.bound {
+                    Some(bound) if self.socket.local_addr()?.port() != bound.port() => {
+                        sock.bind(&bound.into())
+                    }
+                    _ => Ok(()),
+                }
+            }
+            _ => {
+                result?;
+                Err(io::Error::other("UDP 소켓 연결 해제 실패"))
+            }
+        }
+    }
+}
+
+/// 소켓의 DSCP 설정. IPv4는 `IP_TOS`, IPv6는 `IPV6_TCLASS`의 상위 6비트에 기록하고
+/// 현재 ECN 비트는 그대로 둠 (63보다 큰 값은 `InvalidInput`)
+pub fn set_dscp(socket: &UdpSocket, dscp: u8) -> io::Result<()> {
+    if dscp > 0x3f {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("DSCP는 0~63 (받은 값 {})", dscp),
+        ));
+    }
+    let sock = SockRef::from(socket);
+    let mark = |current: u32| (u32::from(dscp) << DSCP_SHIFT) | (current & ECN_MASK);
+    if socket.local_addr()?.is_ipv4() {
+        sock.set_tos_v4(mark(sock.tos_v4()?))
+    } else {
+        set_tclass_v6(&sock, mark)
+    }
+}
+
+/// 소켓에 설정된 DSCP
+pub fn dscp(socket: &UdpSocket) -> io::Result<u8> {
+    let sock = SockRef::from(socket);
+    let tos = if socket.local_addr()?.is_ipv4() {
+        sock.tos_v4()?
+    } else {
+        tclass_v6(&sock)?
+    };
+    Ok((tos >> DSCP_SHIFT) as u8)
+}
+
+/// `Config::dscp`가 있으면 적용
+pub(crate) fn apply_dscp(socket: &UdpSocket, dscp: Option<u8>) -> io::Result<()> {
+    match dscp {
+        Some(dscp) => set_dscp(socket, dscp),
+        None => Ok(()),
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+fn set_tclass_v6(sock: &SockRef<'_>, mark: impl Fn(u32) -> u32) -> io::Result<()> {
+    sock.set_tclass_v6(mark(sock.tclass_v6()?))
+}
+
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd"))]
+fn tclass_v6(sock: &SockRef<'_>) -> io::Result<u32> {
+    sock.tclass_v6()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd")))]
+fn set_tclass_v6(_sock: &SockRef<'_>, _mark: impl Fn(u32) -> u32) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android", target_os = "macos", target_os = "freebsd")))]
+fn tclass_v6(_sock: &SockRef<'_>) -> io::Result<u32> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use tokio::sync::{mpsc, Mutex};
+
+    use crate::multipath::PathManager;
+    use crate::receiver::Receiver;
+    use crate::sender::Sender;
+    use crate::Config;
+
+    #[tokio::test]
+    async fn test_dscp_marking_keeps_ecn_bits() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        // ECT(1) 표시된 소켓에 EF(46) 적용
+        SockRef::from(&socket).set_tos_v4(0b01).unwrap();
+        apply_dscp(&socket, Some(46)).unwrap();
+        assert_eq!(dscp(&socket).unwrap(), 46);
+        assert_eq!(SockRef::from(&socket).tos_v4().unwrap(), (46 << 2) | 0b01);
+
+        // 설정하지 않으면 그대로, 범위를 벗어나면 거부
+        apply_dscp(&socket, None).unwrap();
+        assert_eq!(dscp(&socket).unwrap(), 46);
+        let err = set_dscp(&socket, 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[tokio::test]
+    async fn test_peer_socket_connects_and_disconnects() {
+        let server = PeerUdpSocket::new(UdpSocket::bind("127.0.0.1:0").await.unwrap());
+        let server_addr = server.local_addr().unwrap();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let stranger = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let client_addr = client.local_addr().unwrap();
+        let mut buf = [0u8; 16];
+
+        // 그냥 UdpSocket은 연결하지 않음
+        let plain = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        assert!(!plain.connect_peer(client_addr).await.unwrap());
+
+        assert!(server.connect_peer(client_addr).await.unwrap());
+        assert_eq!(server.peer(), Some(client_addr));
+        server.send_to(b"hello", client_addr).await.unwrap();
+        assert_eq!(client.recv_from(&mut buf).await.unwrap(), (5, server_addr));
+
+        // 연결된 상대가 아닌 출발지는 걸러짐
+        stranger.send_to(b"x", server_addr).await.unwrap();
+        client.send_to(b"yy", server_addr).await.unwrap();
+        assert_eq!(server.recv_from(&mut buf).await.unwrap(), (2, client_addr));
+
+        server.disconnect_peer().unwrap();
+        assert_eq!(server.local_addr().unwrap(), server_addr);
+        assert_eq!(server.peer(), None);
+        stranger.send_to(b"zzz", server_addr).await.unwrap();
+        assert_eq!(server.recv_from(&mut buf).await.unwrap().0, 3);
+        server.disconnect_peer().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wildcard_bound_peer_socket_disconnects() {
+        let server = PeerUdpSocket::new(UdpSocket::bind("0.0.0.0:0").await.unwrap());
+        let port = server.local_addr().unwrap().port();
+        let server_addr: SocketAddr = ([127, 0, 0, 1], port).into();
+        let client = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let stranger = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut buf = [0u8; 16];
+
+        // 연결 중에는 구체 주소로 보이지만 해제 후에는 처음 바인딩한 와일드카드 주소와 포트
+        assert!(server.connect_peer(client.local_addr().unwrap()).await.unwrap());
+        server.disconnect_peer().unwrap();
+        assert_eq!(server.peer(), None);
+        assert_eq!(server.local_addr().unwrap(), ([0, 0, 0, 0], port).into());
+
+        stranger.send_to(b"abcd", server_addr).await.unwrap();
+        let (len, from) = server.recv_from(&mut buf).await.unwrap();
+        assert_eq!((len, from), (4, stranger.local_addr().unwrap()));
+    }
+
+    /// 손실 없는 인프로세스 채널 소켓
+    struct ChannelSocket {
+        addr: SocketAddr,
+        peer: mpsc::UnboundedSender<(Vec<u8>, SocketAddr)>,
+        rx: Mutex<mpsc::UnboundedReceiver<(Vec<u8>, SocketAddr)>>,
+    }
+
+    fn channel_pair(a: SocketAddr, b: SocketAddr) -> (ChannelSocket, ChannelSocket) {
+        let (a_tx, a_rx) = mpsc::unbounded_channel();
+        let (b_tx, b_rx) = mpsc::unbounded_channel();
+        (
+            ChannelSocket {
+                addr: a,
+                peer: b_tx,
+                rx: Mutex::new(a_rx),
+            },
+            ChannelSocket {
+                addr: b,
+                peer: a_tx,
+                rx: Mutex::new(b_rx),
+            },
+        )
+    }
+
+    impl DatagramSocket for ChannelSocket {
+        async fn send_to(&self, buf: &[u8], _target: SocketAddr) -> io::Result<usize> {
+            let _ = self.peer.send((buf.to_vec(), self.addr));
+            Ok(buf.len())
+        }
+
+        async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+            let (data, from) = self
+                .rx
+                .lock()
+                .await
+                .recv()
+                .await
+                .ok_or_else(|| io::Error::from(io::ErrorKind::NotConnected))?;
+            let len = data.len().min(buf.len());
+            buf[..len].copy_from_slice(&data[..len]);
+            Ok((len, from))
+        }
+
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.addr)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_over_in_memory_transport() {
+        let server_addr: SocketAddr = "10.1.0.1:9000".parse().unwrap();
+        let client_addr: SocketAddr = "10.1.0.2:5000".parse().unwrap();
+        let (server_socket, client_socket) = channel_pair(server_addr, client_addr);
+
+        // PathManager도 UDP 없이 채널 소켓을 NIC로 관리
+        let (nic_a, nic_b) =
+            channel_pair("10.1.1.1:1".parse().unwrap(), "10.1.1.2:2".parse().unwrap());
+        let paths: PathManager<ChannelSocket> = PathManager::for_transport(Config::default());
+        let nic_id = paths.add_nic_socket(Arc::new(nic_a), nic_b.addr).unwrap();
+        paths
+            .get_socket(nic_id)
+            .unwrap()
+            .send_to(b"probe", nic_b.addr)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 16];
+        assert_eq!(nic_b.recv_from(&mut buf).await.unwrap().0, 5);
+
+        let config = Config::default();
+        let server_socket = Arc::new(server_socket);
+        let sender = Arc::new(Sender::new(
+            config.clone(),
+            Arc::new(PathManager::new(config.clone())),
+        ));
+        let sender_task = sender.clone();
+        let socket_task = server_socket.clone();
+        let handle = tokio::spawn(async move { sender_task.start_with_socket(socket_task).await });
+
+        let (receiver, mut segments) = Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(client_socket),
+            server_addr,
+            Arc::new(PathManager::new(config)),
+        )
+        .await
+        .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while sender.client_addr() != Some(client_addr) {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let data = Bytes::from((0..100_000).map(|i| (i % 253) as u8).collect::<Vec<u8>>());
+        let id = sender.send_data(data.clone(), &*server_socket).await.unwrap();
+        let (received_id, received) = tokio::time::timeout(Duration::from_secs(5), segments.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(received_id, id);
+        assert_eq!(received, data);
+
+        receiver.stop().await;
+        sender.stop();
+        handle.abort();
+    }
+}
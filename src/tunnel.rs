@@ -0,0 +1,373 @@
+//! 연결형 데이터그램 채널 위의 소켓 (`TunnelSocket`)
+//!
+//! UDP를 막거나 느리게 만드는 망에서도 QUIC은 통과하는 경우가 많음. QUIC 비신뢰 데이터그램
+//! (RFC 9221)처럼 상대가 하나로 정해진 채널을 `DatagramTunnel`로 구현하면 `TunnelSocket`이
+//! `DatagramSocket`으로 바꿔 송신자/수신자 아래에 그대로 끼움. 핸드셰이크, 암호화, 경로 검증은
+//! 채널이 맡고 NACK/FEC 조립은 SFP가 그 위에서 그대로 함
+//!
+//! - 주소는 채널의 상대 하나뿐: 다른 주소로의 `send_to`는 `AddrNotAvailable`, 수신은 항상 상대 주소
+//! - 채널의 데이터그램 상한(QUIC은 경로 MTU에서 QUIC/UDP/IP 헤더를 뺀 값, 최소 MTU 1200이면
+//!   보통 1100 bytes 남짓)을 넘는 데이터그램은 `InvalidInput`으로 거절. `limit_config`로
+//!   `Config::max_datagram_size`와 청크 크기를 그 안으로 줄여 두고 씀
+//! - 채널이 이미 암호화하므로 `Config::encryption_enabled`는 꺼 둠 (켜면 청크마다 이중 암호화)
+//! - QUIC 데이터그램도 QUIC 혼잡 제어를 받고, 송신 버퍼가 차면 오래된 것부터 버려짐. 버려진 청크는
+//!   다른 손실처럼 NACK으로 복구
+//!
+//! `quic` 기능을 켜면 quinn 연결용 구현 `QuicTunnel`을 제공:
+//!
+//! ```ignore
+//! let connection = endpoint.connect(server_addr, "server.example")?.await?;
+//! let socket = Arc::new(TunnelSocket::new(QuicTunnel::new(&endpoint, connection)?));
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+
+use crate::chunk::{Chunk, ChunkHeader};
+use crate::transport::DatagramSocket;
+use crate::Config;
+
+/// 상대가 하나로 정해진 비신뢰 데이터그램 채널
+pub trait DatagramTunnel: Send + Sync + 'static {
+    /// 데이터그램 하나 전송 (상한 초과는 에러)
+    fn send_datagram(&self, data: Bytes) -> impl Future<Output = io::Result<()>> + Send;
+
+    /// 데이터그램 하나 수신
+    fn recv_datagram(&self) -> impl Future<Output = io::Result<Bytes>> + Send;
+
+    /// 현재 보낼 수 있는 데이터그램 최대 크기 (채널이 데이터그램을 지원하지 않으면 None)
+    fn max_datagram_size(&self) -> Option<usize>;
+
+    /// 로컬 주소
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    /// 상대 주소
+    fn remote_addr(&self) -> SocketAddr;
+}
+
+/// quinn 연결의 QUIC 비신뢰 데이터그램 채널 (`quic` 기능)
+///
+/// 연결은 데이터그램을 받도록 맺어야 함 (quinn 기본 `TransportConfig`는 받음). 송신 버퍼가
+/// 차면 자리가 날 때까지 기다리고, 상대가 데이터그램을 받지 않으면 `Unsupported`
+#[cfg(feature = "quic")]
+#[derive(Debug, Clone)]
+pub struct QuicTunnel {
+    connection: quinn::Connection,
+    local: SocketAddr,
+}
+
+#[cfg(feature = "quic")]
+impl QuicTunnel {
+    /// `endpoint`로 맺은 연결 (로컬 주소는 엔드포인트 주소)
+    pub fn new(endpoint: &quinn::Endpoint, connection: quinn::Connection) -> io::Result<Self> {
+        Ok(Self {
+            connection,
+            local: endpoint.local_addr()?,
+        })
+    }
+
+    /// 안쪽 QUIC 연결
+    pub fn connection(&self) -> &quinn::Connection {
+        &self.connection
+    }
+}
+
+#[cfg(feature = "quic")]
+impl DatagramTunnel for QuicTunnel {
+    async fn send_datagram(&self, data: Bytes) -> io::Result<()> {
+        use quinn::SendDatagramError;
+
+        self.connection
+            .send_datagram_wait(data)
+            .await
+            .map_err(|e| match e {
+                SendDatagramError::TooLarge => io::Error::new(io::ErrorKind::InvalidInput, e),
+                SendDatagramError::UnsupportedByPeer | SendDatagramError::Disabled => {
+                    io::Error::new(io::ErrorKind::Unsupported, e)
+                }
+                SendDatagramError::ConnectionLost(e) => e.into(),
+            })
+    }
+
+    async fn recv_datagram(&self) -> io::Result<Bytes> {
+        Ok(self.connection.read_datagram().await?)
+    }
+
+    fn max_datagram_size(&self) -> Option<usize> {
+        self.connection.max_datagram_size()
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        Ok(self.local)
+    }
+
+    fn remote_addr(&self) -> SocketAddr {
+        self.connection.remote_address()
+    }
+}
+
+/// `DatagramTunnel`을 `DatagramSocket`으로
+#[derive(Debug)]
+pub struct TunnelSocket<T> {
+    tunnel: T,
+}
+
+impl<T: DatagramTunnel> TunnelSocket<T> {
+    pub fn new(tunnel: T) -> Self {
+        Self { tunnel }
+    }
+
+    /// 안쪽 채널
+    pub fn get_ref(&self) -> &T {
+        &self.tunnel
+    }
+
+    /// 채널의 데이터그램 상한에 맞게 `max_datagram_size`와 `chunk_size`를 낮춤 (상한이 없으면
+    /// 그대로). 청크 데이터그램은 프리픽스 + 헤더 + 데이터 (암호화 오버헤드는 세그먼트에 한 번)
+    ///
+    /// QUIC의 상한은 경로 MTU 탐색으로 바뀔 수 있으므로 연결 직후 값(가장 보수적인 값)을 씀
+    pub fn limit_config(&self, config: &mut Config) {
+        if let Some(max) = self.tunnel.max_datagram_size() {
+            config.max_datagram_size = config.max_datagram_size.min(max);
+            let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+            config.chunk_size = config.chunk_size.min(max.saturating_sub(framing));
+        }
+    }
+}
+
+impl<T: DatagramTunnel> DatagramSocket for TunnelSocket<T> {
+    async fn send_to(&self, buf: &[u8], target: SocketAddr) -> io::Result<usize> {
+        if target != self.tunnel.remote_addr() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrNotAvailable,
+                format!("터널 상대({})가 아닌 주소: {}", self.tunnel.remote_addr(), target),
+            ));
+        }
+        match self.tunnel.max_datagram_size() {
+            None => return Err(io::ErrorKind::Unsupported.into()),
+            Some(max) if buf.len() > max => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("데이터그램 {} bytes가 터널 상한 {} bytes 초과", buf.len(), max),
+                ));
+            }
+            Some(_) => {}
+        }
+        self.tunnel.send_datagram(Bytes::copy_from_slice(buf)).await?;
+        Ok(buf.len())
+    }
+
+    /// 버퍼보다 긴 데이터그램은 UDP처럼 잘림
+    async fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        let data = self.tunnel.recv_datagram().await?;
+        let len = data.len().min(buf.len());
+        buf[..len].copy_from_slice(&data[..len]);
+        Ok((len, self.tunnel.remote_addr()))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.tunnel.local_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::{mpsc, Mutex};
+
+    use crate::multipath::PathManager;
+    use crate::receiver::FileReceiver;
+    use crate::{Receiver, TransferStats, TypedSender};
+
+    /// 인프로세스 데이터그램 채널 한쪽 (`drop_every`번째 송신마다 버림, 0이면 손실 없음)
+    struct ChannelTunnel {
+        local: SocketAddr,
+        remote: SocketAddr,
+        tx: mpsc::UnboundedSender<Bytes>,
+        rx: Mutex<mpsc::UnboundedReceiver<Bytes>>,
+        max_datagram_size: usize,
+        drop_every: usize,
+        sent: AtomicUsize,
+    }
+
+    impl DatagramTunnel for ChannelTunnel {
+        async fn send_datagram(&self, data: Bytes) -> io::Result<()> {
+            let sent = self.sent.fetch_add(1, Ordering::Relaxed) + 1;
+            if sent.is_multiple_of(self.drop_every) {
+                return Ok(());
+            }
+            self.tx.send(data).map_err(|_| io::ErrorKind::BrokenPipe.into())
+        }
+
+        async fn recv_datagram(&self) -> io::Result<Bytes> {
+            self.rx.lock().await.recv().await.ok_or(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn max_datagram_size(&self) -> Option<usize> {
+            Some(self.max_datagram_size)
+        }
+
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.local)
+        }
+
+        fn remote_addr(&self) -> SocketAddr {
+            self.remote
+        }
+    }
+
+    /// (서버 쪽, 클라이언트 쪽). 서버→클라이언트 방향만 손실
+    fn tunnel_pair(
+        server: SocketAddr,
+        client: SocketAddr,
+        max_datagram_size: usize,
+        drop_every: usize,
+    ) -> (ChannelTunnel, ChannelTunnel) {
+        let (to_client, from_server) = mpsc::unbounded_channel();
+        let (to_server, from_client) = mpsc::unbounded_channel();
+        let end = |local, remote, tx, rx, drop_every| ChannelTunnel {
+            local,
+            remote,
+            tx,
+            rx: Mutex::new(rx),
+            max_datagram_size,
+            drop_every,
+            sent: AtomicUsize::new(0),
+        };
+        (
+            end(server, client, to_client, from_client, drop_every),
+            end(client, server, to_server, from_server, 0),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_transfer_over_datagram_tunnel() {
+        let server_addr: SocketAddr = "10.0.0.1:4433".parse().unwrap();
+        let client_addr: SocketAddr = "10.0.0.2:5000".parse().unwrap();
+        // QUIC 최소 MTU 1200에서 남는 정도의 상한, 서버 송신 17개 중 하나 손실
+        let (server, client) = tunnel_pair(server_addr, client_addr, 1150, 17);
+        let server = Arc::new(TunnelSocket::new(server));
+        let client = Arc::new(TunnelSocket::new(client));
+
+        let mut config = Config {
+            segment_size: 32 * 1024,
+            ..Config::default()
+        };
+        // 기본 청크(1200)는 상한을 넘으므로 설정을 맞추지 않으면 전송이 거절됨
+        assert_eq!(
+            server.send_to(&[0u8; 1200], client_addr).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        let unrelated: SocketAddr = "10.0.0.9:1".parse().unwrap();
+        assert!(server.send_to(b"x", unrelated).await.is_err());
+        server.limit_config(&mut config);
+        assert_eq!(config.max_datagram_size, 1150);
+        let framing = Chunk::PREFIX_LEN + ChunkHeader::ENCODED_LEN;
+        assert_eq!(config.chunk_size + framing, 1150);
+
+        let stats = send_message_through(server, client, &config).await;
+        // 버려진 청크는 NACK 재전송이나 중복 청크로 복구됨
+        assert!(stats.retransmitted_chunks + stats.redundant_chunks > 0, "{:?}", stats);
+    }
+
+    #[cfg(feature = "quic")]
+    #[tokio::test]
+    async fn test_transfer_over_quic_loopback() {
+        use quinn::rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer};
+        use quinn::rustls::RootCertStore;
+
+        // 루프백 QUIC 연결 (자체 서명 인증서)
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let cert = CertificateDer::from(certified.cert);
+        let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+        let server_config =
+            quinn::ServerConfig::with_single_cert(vec![cert.clone()], key.into()).unwrap();
+        let server_endpoint =
+            quinn::Endpoint::server(server_config, "127.0.0.1:0".parse().unwrap()).unwrap();
+        let mut roots = RootCertStore::empty();
+        roots.add(cert).unwrap();
+        let mut client_endpoint = quinn::Endpoint::client("127.0.0.1:0".parse().unwrap()).unwrap();
+        client_endpoint.set_default_client_config(
+            quinn::ClientConfig::with_root_certificates(Arc::new(roots)).unwrap(),
+        );
+        let server_addr = server_endpoint.local_addr().unwrap();
+        let connecting = client_endpoint.connect(server_addr, "localhost").unwrap();
+        let (client_connection, server_connection) = tokio::join!(connecting, async {
+            server_endpoint.accept().await.unwrap().await
+        });
+
+        let server = Arc::new(TunnelSocket::new(
+            QuicTunnel::new(&server_endpoint, server_connection.unwrap()).unwrap(),
+        ));
+        let client = Arc::new(TunnelSocket::new(
+            QuicTunnel::new(&client_endpoint, client_connection.unwrap()).unwrap(),
+        ));
+        assert_eq!(client.get_ref().remote_addr(), server_addr);
+        assert_eq!(server.get_ref().remote_addr(), client.local_addr().unwrap());
+
+        // QUIC 데이터그램 상한(최소 MTU 1200 기준)에 청크를 맞춤
+        let mut config = Config {
+            segment_size: 32 * 1024,
+            ..Config::default()
+        };
+        let max = server.get_ref().max_datagram_size().unwrap();
+        assert!(max < 1200, "{}", max);
+        let client_addr = client.local_addr().unwrap();
+        assert_eq!(
+            server.send_to(&vec![0u8; max + 1], client_addr).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+        server.limit_config(&mut config);
+        client.limit_config(&mut config);
+
+        send_message_through(server.clone(), client.clone(), &config).await;
+        server.get_ref().connection().close(0u32.into(), b"done");
+        server_endpoint.wait_idle().await;
+    }
+
+    /// 터널 양끝으로 200KB 메시지 하나를 보내 그대로 받는지 확인하고 송신 통계 반환
+    async fn send_message_through<T: DatagramTunnel>(
+        server: Arc<TunnelSocket<T>>,
+        client: Arc<TunnelSocket<T>>,
+        config: &Config,
+    ) -> TransferStats {
+        let server_addr = client.get_ref().remote_addr();
+        let listening = TypedSender::new(config.clone()).bind_socket(server);
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            client,
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut messages = FileReceiver::from_receiver(receiver, segment_rx)
+            .into_messages()
+            .unwrap();
+        let session = tokio::time::timeout(Duration::from_secs(5), listening.accept())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 253) as u8).collect();
+        let mut sink = session.sink();
+        sink.write_all(&data).await.unwrap();
+        sink.shutdown().await.unwrap();
+        let message = tokio::time::timeout(Duration::from_secs(10), messages.next_message())
+            .await
+            .expect("터널 전송 시간 초과")
+            .unwrap()
+            .unwrap();
+        assert_eq!(message, data);
+        messages.receiver().stop().await;
+        session.stats()
+    }
+}
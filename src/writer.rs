@@ -0,0 +1,229 @@
+//! 송신측 `AsyncWrite` (`SenderSink`)
+//!
+//! `FileSender::send_stream`이 소스를 읽어 가는 방향이라면, 이쪽은 생산자가 바이트를 써
+//! 넣는 방향. 쓴 바이트는 현재 세그먼트에 모이고, 세그먼트가 차면 그 세그먼트를 나눠 전송
+//!
+//! - 전송 중인 세그먼트는 하나뿐. 다음 세그먼트까지 차면 앞 세그먼트 전송(혼잡 제어 페이싱,
+//!   송신 상한, 일시 정지)이 끝날 때까지 `poll_write`가 `Pending`을 돌려줘 생산자가 자연히 느려짐
+//! - `flush`는 덜 찬 세그먼트도 바로 보냄 (세그먼트 크기가 들쭉날쭉해질 수 있음)
+//! - `shutdown`은 남은 데이터를 마지막 세그먼트로 보내며 청크에 메시지 경계와 스트림 끝
+//!   (`end_of_stream`)을 표시 (남은 데이터가 없으면 빈 세그먼트). 수신측은
+//!   `MessageReceiver::next_message`가 스트림 전체를 메시지 하나로 돌려주고 다음 호출은 `None`
+//!
+//! ```no_run
+//! # async fn run(session: sfp::ConnectedSession) -> std::io::Result<()> {
+//! use tokio::io::AsyncWriteExt;
+//!
+//! let mut source = tokio::fs::File::open("video.bin").await?;
+//! let mut sink = session.sink();
+//! tokio::io::copy(&mut source, &mut sink).await?;
+//! sink.shutdown().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+
+use bytes::BytesMut;
+use tokio::io::AsyncWrite;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+use crate::sender::Sender;
+use crate::transport::DatagramSocket;
+use crate::{Error, Result};
+
+/// 바이트를 세그먼트로 모아 보내는 `AsyncWrite`
+pub struct SenderSink<S: DatagramSocket = UdpSocket> {
+    sender: Arc<Sender>,
+    socket: Arc<S>,
+    /// 채우는 중인 세그먼트
+    buffer: BytesMut,
+    /// 전송 중인 세그먼트
+    sending: Option<JoinHandle<Result<()>>>,
+    /// 스트림 끝을 보냈는지
+    finished: bool,
+    /// 세그먼트로 넘긴 바이트
+    sent_bytes: u64,
+}
+
+impl<S: DatagramSocket> SenderSink<S> {
+    /// 연결된 송신자와 그 서버 루프의 소켓으로 생성
+    pub fn new(sender: Arc<Sender>, socket: Arc<S>) -> Self {
+        Self {
+            sender,
+            socket,
+            buffer: BytesMut::new(),
+            sending: None,
+            finished: false,
+            sent_bytes: 0,
+        }
+    }
+
+    /// 세그먼트로 넘긴 바이트 (아직 채우는 중인 세그먼트 제외)
+    pub fn sent_bytes(&self) -> u64 {
+        self.sent_bytes
+    }
+
+    /// 채우는 중인 세그먼트 전송 시작 (`end`면 스트림 끝 표시)
+    fn start_send(&mut self, end: bool) {
+        let data = self.buffer.split().freeze();
+        self.sent_bytes += data.len() as u64;
+        let sender = self.sender.clone();
+        let socket = self.socket.clone();
+        self.sending = Some(tokio::spawn(async move {
+            if end {
+                sender.send_end_of_stream(data, &*socket).await?;
+            } else {
+                sender.send_data(data, &*socket).await?;
+            }
+            sender.flush_coalesced(&*socket).await
+        }));
+    }
+
+    /// 전송 중인 세그먼트가 끝날 때까지 대기
+    fn poll_sending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(sending) = self.sending.as_mut() {
+            let result = ready!(Pin::new(sending).poll(cx));
+            self.sending = None;
+            match result {
+                Ok(sent) => sent.map_err(into_io_error)?,
+                Err(e) => return Poll::Ready(Err(io::Error::other(e))),
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S: DatagramSocket> AsyncWrite for SenderSink<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        if this.finished {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        loop {
+            let segment_size = this.sender.current_segment_size().max(1);
+            let room = segment_size.saturating_sub(this.buffer.len());
+            if room > 0 {
+                let n = room.min(buf.len());
+                this.buffer.extend_from_slice(&buf[..n]);
+                return Poll::Ready(Ok(n));
+            }
+            ready!(this.poll_sending(cx))?;
+            this.start_send(false);
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_sending(cx))?;
+        if !this.buffer.is_empty() && !this.finished {
+            this.start_send(false);
+            ready!(this.poll_sending(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.finished {
+            ready!(this.poll_sending(cx))?;
+            this.start_send(true);
+            this.finished = true;
+        }
+        this.poll_sending(cx)
+    }
+}
+
+/// 전송 에러를 `io::Error`로 (소켓 에러는 그대로)
+fn into_io_error(e: Error) -> io::Error {
+    match e {
+        Error::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::multipath::PathManager;
+    use crate::receiver::FileReceiver;
+    use crate::sim::{SimConfig, SimNetwork};
+    use crate::{Config, Receiver, TypedSender};
+
+    #[tokio::test]
+    async fn test_async_read_piped_through_sink() {
+        // 8 Mbit/s 링크: 페이싱이 쓰기를 늦춤
+        let net = SimNetwork::new(SimConfig {
+            delay: Duration::from_millis(2),
+            bandwidth_bps: Some(8_000_000),
+            ..SimConfig::default()
+        });
+        let server_addr: SocketAddr = "10.0.0.1:9000".parse().unwrap();
+        let config = Config {
+            chunk_size: 1000,
+            segment_size: 16_000,
+            ..Config::default()
+        };
+        let listening =
+            TypedSender::new(config.clone()).bind_socket(Arc::new(net.bind(server_addr)));
+        let (receiver, segment_rx) = Receiver::start_with_socket(
+            config.clone(),
+            Arc::new(net.bind("10.0.0.2:5000".parse().unwrap())),
+            server_addr,
+            Arc::new(PathManager::new(config.clone())),
+        )
+        .await
+        .unwrap();
+        let mut messages = FileReceiver::from_receiver(receiver, segment_rx)
+            .into_messages()
+            .unwrap();
+        let session = tokio::time::timeout(Duration::from_secs(5), listening.accept())
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 세그먼트 경계와 맞지 않게 끊겨 읽히는 소스
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        let mut source = (&data[..7_000]).chain(&data[7_000..]);
+        let mut sink = session.sink();
+        let copied = tokio::io::copy(&mut source, &mut sink).await.unwrap();
+        sink.shutdown().await.unwrap();
+        assert_eq!(copied, data.len() as u64);
+        assert_eq!(sink.sent_bytes(), data.len() as u64);
+        assert!(sink.write_all(b"late").await.is_err());
+
+        let message = tokio::time::timeout(Duration::from_secs(10), messages.next_message())
+            .await
+            .expect("스트림 수신 시간 초과")
+            .unwrap()
+            .unwrap();
+        assert_eq!(message, data);
+        // 연결을 닫지 않아도 스트림 끝 표시로 끝남
+        let end = tokio::time::timeout(Duration::from_secs(1), messages.next_message())
+            .await
+            .expect("스트림 끝 표시 없음")
+            .unwrap();
+        assert!(end.is_none());
+        assert_eq!(messages.receiver().end_of_stream(), Some(8));
+        // 16KB 세그먼트 6개 + `copy`가 끝에 flush한 4KB + 빈 끝 세그먼트
+        assert_eq!(session.stats().total_segments, 8);
+        messages.receiver().stop().await;
+    }
+}
@@ -0,0 +1,26 @@
+//! no_std 코어 빌드 테스트
+//!
+//! `--no-default-features`로 라이브러리를 체크하여 코덱 코어가
+//! std/tokio 없이 컴파일되는지 확인
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_core_builds_without_std() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let target_dir = Path::new(manifest_dir).join("target").join("no-std-check");
+
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--lib", "--no-default-features", "--quiet"])
+        .current_dir(manifest_dir)
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .output()
+        .expect("cargo 실행 실패");
+
+    assert!(
+        output.status.success(),
+        "no_std 코어 빌드 실패:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
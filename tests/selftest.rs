@@ -0,0 +1,35 @@
+//! 루프백 자체 점검 통합 테스트
+//!
+//! CI 스모크 테스트: 손실 없이 전체 페이로드가 무결하게 도착하는지 확인
+
+use sfp::selftest::run_selftest;
+use sfp::Config;
+
+#[tokio::test]
+async fn test_loopback_selftest_lossless_and_intact() {
+    let config = Config::default();
+    let payload = 4 * 1024 * 1024 + 12_345;
+
+    let report = run_selftest(&config, payload).await.expect("자체 점검 실패");
+    println!("{}", report.summary());
+
+    assert!(report.verified, "{}", report.summary());
+    assert_eq!(report.transfer.bytes, payload as u64);
+    assert_eq!(
+        report.transfer.segments,
+        payload.div_ceil(config.segment_size) as u64
+    );
+    assert_eq!(report.loss_rate(), 0.0, "{}", report.summary());
+    assert!(report.throughput() > 0.0);
+    // 기본 중복률(15%) 근처, 재전송이 거의 없어야 함
+    assert!(report.overhead() < 0.5, "{}", report.summary());
+}
+
+#[tokio::test]
+async fn test_empty_selftest_completes_immediately() {
+    let report = run_selftest(&Config::default(), 0).await.expect("자체 점검 실패");
+
+    assert!(report.verified, "{}", report.summary());
+    assert_eq!((report.transfer.segments, report.transfer.bytes), (0, 0));
+    assert_eq!(report.overhead(), 0.0);
+}